@@ -27,8 +27,12 @@ async fn main() -> Result<()> {
 
     let encoder = MulticallerSwapEncoder::default();
 
-    let topology =
-        Topology::<LoomDBType>::from_config(topology_config).with_swap_encoder(encoder).build_blockchains().start_clients().await?;
+    let topology = Topology::<LoomDBType>::from_config(topology_config)
+        .with_config_file("config.toml".to_string())
+        .with_swap_encoder(encoder)
+        .build_blockchains()
+        .start_clients()
+        .await?;
 
     let mut worker_task_vec = topology.start_actors().await?;
 
@@ -44,6 +48,8 @@ async fn main() -> Result<()> {
     let tx_signers = topology.get_signers(Some("env_signer".to_string()).as_ref())?;
 
     let backrun_config: BackrunConfigSection = load_from_file("./config.toml".to_string().into()).await?;
+    backrun_config.validate_profit_receivers(&client).await?;
+    let namespaced_backrun_configs = backrun_config.namespaced_strategies();
     let backrun_config: BackrunConfig = backrun_config.backrun_strategy;
 
     let block_nr = client.get_block_number().await?;
@@ -52,7 +58,10 @@ async fn main() -> Result<()> {
     info!("Creating shared state");
 
     info!("Starting state change arb actor");
-    let mut state_change_arb_actor = StateChangeArbActor::new(client.clone(), true, true, backrun_config);
+    let mut state_change_arb_actor = StateChangeArbActor::new(client.clone(), true, true, backrun_config)
+        .with_chain_parameters(blockchain.chain_parameters())
+        .with_market_view(blockchain.market_view())
+        .with_namespaces(namespaced_backrun_configs);
     match state_change_arb_actor
         .access(blockchain.mempool())
         .access(blockchain.latest_block())
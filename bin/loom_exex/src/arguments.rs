@@ -1,3 +1,4 @@
+use alloy::primitives::Address;
 use clap::{Parser, Subcommand};
 
 /// Triggers persistence when the number of canonical blocks in memory exceeds this threshold.
@@ -10,6 +11,10 @@ pub const DEFAULT_MEMORY_BLOCK_BUFFER_TARGET: u64 = 2;
 pub enum Command {
     Node(LoomArgsNode),
     Remote(LoomArgs),
+    /// Deploy the multicaller contract and write its address into the config
+    Deploy(DeployArgs),
+    /// Inspect the market of a running instance
+    Market(MarketArgs),
 }
 
 #[derive(Parser, Debug)]
@@ -36,3 +41,64 @@ pub struct LoomArgs {
     #[arg(long = "engine.memory-block-buffer-target", default_value_t = DEFAULT_MEMORY_BLOCK_BUFFER_TARGET)]
     pub memory_block_buffer_target: u64,
 }
+
+#[derive(Parser, Debug)]
+pub struct DeployArgs {
+    #[arg(long, default_value = "config.toml")]
+    pub loom_config: String,
+
+    /// Websocket client from the config used for deployment
+    #[arg(long, default_value = "remote")]
+    pub client: String,
+
+    /// Encoder in the config to update with the deployed address
+    #[arg(long, default_value = "mainnet")]
+    pub encoder: String,
+
+    /// ETH sent to the multicaller after deployment
+    #[arg(long, default_value = "0")]
+    pub funding: String,
+
+    /// Environment variable with the hex encoded private key of the deployer
+    #[arg(long, default_value = "DEPLOYER_PRIVATE_KEY")]
+    pub private_key_env: String,
+}
+
+#[derive(Parser, Debug)]
+pub struct MarketArgs {
+    #[command(subcommand)]
+    pub command: MarketCommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum MarketCommand {
+    /// Print market stats and pools queried through the web api
+    Inspect(MarketInspectArgs),
+}
+
+#[derive(Parser, Debug)]
+pub struct MarketInspectArgs {
+    /// Web api of the running instance, see `[webserver]` in the config
+    #[arg(long, default_value = "http://127.0.0.1:3333")]
+    pub url: String,
+
+    /// Only pools of the token
+    #[arg(long)]
+    pub token: Option<Address>,
+
+    /// Only pools of the protocol, e.g. UNISWAP_V3
+    #[arg(long)]
+    pub protocol: Option<String>,
+
+    /// Only disabled pools
+    #[arg(long)]
+    pub disabled: bool,
+
+    /// Only the given number of pools with the best path score
+    #[arg(long)]
+    pub top: Option<usize>,
+
+    /// Write the listed pools to a CSV file
+    #[arg(long)]
+    pub csv: Option<String>,
+}
@@ -0,0 +1,36 @@
+use crate::arguments::DeployArgs;
+use alloy::primitives::utils::parse_ether;
+use alloy::providers::{ProviderBuilder, WsConnect};
+use alloy::rpc::client::ClientBuilder;
+use eyre::{eyre, OptionExt, Result};
+use k256::SecretKey;
+use loom::core::topology::TopologyConfig;
+use loom::execution::multicaller::MulticallerDeployer;
+use tracing::info;
+
+/// Deploy the multicaller with the key from `args.private_key_env`, verify and fund it, then store its address in the encoder config
+pub async fn deploy_multicaller(args: DeployArgs) -> Result<()> {
+    info!("Loading config from {}", args.loom_config);
+    let topology_config = TopologyConfig::load_from_file(args.loom_config.clone())?;
+    if !topology_config.encoders.contains_key(&args.encoder) {
+        return Err(eyre!("ENCODER_NOT_FOUND"));
+    }
+
+    let client_config = topology_config.clients.get(&args.client).ok_or_eyre("CLIENT_NOT_FOUND")?;
+    let transport = WsConnect { url: client_config.url.clone(), auth: None, config: None };
+    let client = ClientBuilder::default().ws(transport).await?;
+    let provider = ProviderBuilder::new().disable_recommended_fillers().on_client(client);
+
+    let private_key = std::env::var(&args.private_key_env).map_err(|_| eyre!("PRIVATE_KEY_NOT_SET"))?;
+    let private_key =
+        SecretKey::from_slice(&hex::decode(private_key.trim().trim_start_matches("0x"))?).map_err(|_| eyre!("INVALID_PRIVATE_KEY"))?;
+    let funding = parse_ether(&args.funding)?;
+
+    let multicaller = MulticallerDeployer::new().deploy_verified(provider, private_key, funding).await?;
+    let address = multicaller.address().ok_or_eyre("NOT_DEPLOYED")?;
+
+    TopologyConfig::set_encoder_address(args.loom_config.clone(), &args.encoder, address)?;
+    info!("Multicaller {} written to encoder {} in {}", address, args.encoder, args.loom_config);
+
+    Ok(())
+}
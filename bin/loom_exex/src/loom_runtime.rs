@@ -71,7 +71,7 @@ where
     let private_key_encrypted = hex::decode(env::var("DATA")?)?;
     info!(address=?multicaller_address, "Multicaller");
 
-    let webserver_host = topology_config.webserver.unwrap_or_default().host;
+    let webserver = topology_config.webserver.unwrap_or_default();
     let db_url = topology_config.database.unwrap().url;
     let db_pool = init_db_pool(db_url).await?;
 
@@ -89,8 +89,15 @@ where
     let pools_config = PoolsLoadingConfig::new().disable_all().enable(PoolClass::UniswapV2).enable(PoolClass::UniswapV3);
 
     let backrun_config: BackrunConfigSection = load_from_file::<BackrunConfigSection>(loom_config_filepath.into()).await?;
+    backrun_config.validate_profit_receivers(&provider).await?;
+    let namespaced_backrun_configs = backrun_config.namespaced_strategies();
     let backrun_config: BackrunConfig = backrun_config.backrun_strategy;
 
+    let strategy = match topology_config.blockchains.get("mainnet") {
+        Some(blockchain_config) => strategy.with_namespaces(blockchain_config.strategy_namespaces()?),
+        None => strategy,
+    };
+
     let swap_encoder = MulticallerSwapEncoder::default_with_address(multicaller_address);
 
     let mut bc_actors = BlockchainActors::new(provider.clone(), swap_encoder.clone(), bc.clone(), bc_state, strategy, relays);
@@ -113,12 +120,16 @@ where
         //.with_curve_pool_protocol_loader()? // load curve + steth + wsteth
         .with_new_pool_loader(pools_config.clone())? // load new pools
         .with_pool_loader(pools_config.clone())?
+        .with_proxy_monitor()? // disable pools of upgraded proxies
+        .with_pool_events_publisher()? // publish pool lifecycle events
         .with_swap_path_merger()? // load merger for multiple swap paths
         .with_diff_path_merger()? // load merger for different swap paths
         .with_same_path_merger()? // load merger for same swap paths with different stuffing txes
         .with_backrun_block(backrun_config.clone())? // load backrun searcher for incoming block
         .with_backrun_mempool(backrun_config)? // load backrun searcher for mempool txes
-        .with_web_server(webserver_host, Router::new(), db_pool)? // start web server
+        .with_backrun_namespaces(namespaced_backrun_configs)? // load backrun searchers of strategy namespaces
+        .with_block_stats()? // per block searcher statistics
+        .with_web_server(webserver.host, Router::new(), db_pool, webserver.auth_token, webserver.manual_swaps)? // start web server
     ;
 
     if !is_exex {
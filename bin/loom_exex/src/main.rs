@@ -1,4 +1,4 @@
-use crate::arguments::{AppArgs, Command, LoomArgs};
+use crate::arguments::{AppArgs, Command, LoomArgs, MarketCommand};
 use alloy::eips::BlockId;
 use alloy::providers::{ProviderBuilder, WsConnect};
 use alloy::rpc::client::ClientBuilder;
@@ -24,7 +24,9 @@ use tracing_subscriber::util::SubscriberInitExt;
 use tracing_subscriber::{fmt, EnvFilter, Layer};
 
 mod arguments;
+mod deploy;
 mod loom_runtime;
+mod market_inspect;
 
 fn main() -> eyre::Result<()> {
     let env_filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| "info".into());
@@ -127,5 +129,15 @@ fn main() -> eyre::Result<()> {
             })?;
             Ok(())
         }
+        Command::Deploy(deploy_args) => {
+            let rt = tokio::runtime::Builder::new_multi_thread().enable_all().build()?;
+            rt.block_on(deploy::deploy_multicaller(deploy_args))
+        }
+        Command::Market(market_args) => {
+            let rt = tokio::runtime::Builder::new_multi_thread().enable_all().build()?;
+            match market_args.command {
+                MarketCommand::Inspect(inspect_args) => rt.block_on(market_inspect::inspect_market(inspect_args)),
+            }
+        }
     }
 }
@@ -0,0 +1,115 @@
+use std::fmt::Write as _;
+
+use crate::arguments::MarketInspectArgs;
+use eyre::Result;
+use loom::rpc::handler::dto::pool::{MarketStats, Pool, PoolResponse};
+use tracing::info;
+
+const PAGE_LIMIT: usize = 1000;
+
+async fn fetch_stats(client: &reqwest::Client, url: &str) -> Result<MarketStats> {
+    Ok(client.get(format!("{url}/api/v1/markets")).send().await?.error_for_status()?.json().await?)
+}
+
+/// Fetch the filtered pools page by page, at most `top` pools if set
+async fn fetch_pools(client: &reqwest::Client, url: &str, args: &MarketInspectArgs) -> Result<(Vec<Pool>, usize)> {
+    let mut query: Vec<(&str, String)> = Vec::new();
+    if let Some(protocol) = &args.protocol {
+        query.push(("protocol", protocol.clone()));
+    }
+    if let Some(token) = args.token {
+        query.push(("token", token.to_string()));
+    }
+    if args.disabled {
+        query.push(("disabled", "true".to_string()));
+    }
+    if args.top.is_some() {
+        query.push(("sort", "score".to_string()));
+    }
+
+    let limit = args.top.unwrap_or(usize::MAX);
+    let mut pools = Vec::new();
+    let mut page = 1;
+    loop {
+        let response: PoolResponse = client
+            .get(format!("{url}/api/v1/markets/pools"))
+            .query(&query)
+            .query(&[("page", page), ("limit", PAGE_LIMIT.min(limit))])
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        let received = response.pools.len();
+        pools.extend(response.pools);
+
+        if received == 0 || pools.len() >= response.total.min(limit) {
+            pools.truncate(limit);
+            return Ok((pools, response.total));
+        }
+        page += 1;
+    }
+}
+
+fn pool_tokens(pool: &Pool) -> String {
+    pool.tokens.iter().map(|token| token.to_string()).collect::<Vec<_>>().join(";")
+}
+
+fn pool_score(pool: &Pool) -> String {
+    pool.score.map(|score| format!("{score:.4}")).unwrap_or_default()
+}
+
+fn pools_csv(pools: &[Pool]) -> String {
+    let mut csv = "address,protocol,class,fee,paths,score,disabled,tokens\n".to_string();
+    for pool in pools {
+        let _ = writeln!(
+            csv,
+            "{},{:?},{:?},{},{},{},{},{}",
+            pool.address,
+            pool.protocol,
+            pool.pool_class,
+            pool.fee,
+            pool.paths,
+            pool_score(pool),
+            pool.disabled,
+            pool_tokens(pool)
+        );
+    }
+    csv
+}
+
+/// Print the market of a running loom instance through its web api, optionally dumping the listed pools to CSV
+pub async fn inspect_market(args: MarketInspectArgs) -> Result<()> {
+    let url = args.url.trim_end_matches('/').to_string();
+    let client = reqwest::Client::new();
+
+    let stats = fetch_stats(&client, &url).await?;
+    println!(
+        "pools: {} ({} disabled), paths: {} ({} disabled)",
+        stats.total_pools, stats.disabled_pools, stats.total_paths, stats.disabled_paths
+    );
+
+    let (pools, total) = fetch_pools(&client, &url, &args).await?;
+    println!("matching pools: {total}, listed: {}", pools.len());
+    println!("{:<42} {:<16} {:<12} {:>8} {:>6} {:>10} {:<8} tokens", "address", "protocol", "class", "fee", "paths", "score", "disabled");
+    for pool in pools.iter() {
+        println!(
+            "{:<42} {:<16} {:<12} {:>8} {:>6} {:>10} {:<8} {}",
+            pool.address.to_string(),
+            format!("{:?}", pool.protocol),
+            format!("{:?}", pool.pool_class),
+            pool.fee.to_string(),
+            pool.paths,
+            pool_score(pool),
+            pool.disabled,
+            pool_tokens(pool)
+        );
+    }
+
+    if let Some(csv_path) = &args.csv {
+        std::fs::write(csv_path, pools_csv(&pools))?;
+        info!("{} pools written to {}", pools.len(), csv_path);
+    }
+
+    Ok(())
+}
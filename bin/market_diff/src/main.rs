@@ -0,0 +1,129 @@
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+
+use clap::{arg, Parser};
+use eyre::Result;
+use serde::Deserialize;
+
+use loom_types_entities::{MarketSnapshot, MarketSnapshotDiff};
+
+/// Page of `GET /api/v1/markets/snapshot`
+#[derive(Debug, Deserialize)]
+struct MarketSnapshotPage {
+    snapshot: MarketSnapshot,
+    pools_total: usize,
+    tokens_total: usize,
+    paths_total: usize,
+}
+
+#[derive(Parser, Debug)]
+enum Commands {
+    /// Save the market snapshot of a running bot, e.g. before an upgrade or a config edit
+    Fetch {
+        /// Webserver of the bot, e.g. http://127.0.0.1:3333
+        #[arg(short, long)]
+        url: String,
+        #[arg(short, long)]
+        out: PathBuf,
+        /// Pools, tokens and paths per request
+        #[arg(short, long, default_value_t = 1000)]
+        limit: usize,
+    },
+    /// Show the pools added and removed, the path score changes and the tokens reclassified between two snapshots
+    Diff {
+        before: PathBuf,
+        after: PathBuf,
+        /// Print the full diff as json
+        #[arg(long)]
+        json: bool,
+        /// Number of the largest score changes shown
+        #[arg(long, default_value_t = 20)]
+        top: usize,
+    },
+}
+
+async fn fetch_snapshot(url: &str, limit: usize) -> Result<MarketSnapshot> {
+    let client = reqwest::Client::new();
+    let snapshot_url = format!("{}/api/v1/markets/snapshot", url.trim_end_matches('/'));
+    let limit = limit.max(1);
+
+    let mut snapshot = MarketSnapshot::default();
+    let mut blocks: BTreeSet<u64> = BTreeSet::new();
+    for page in 1usize.. {
+        let response: MarketSnapshotPage =
+            client.get(&snapshot_url).query(&[("page", page), ("limit", limit)]).send().await?.error_for_status()?.json().await?;
+        let done = page * limit >= response.pools_total.max(response.tokens_total).max(response.paths_total);
+        blocks.insert(response.snapshot.block_number);
+        snapshot.merge(response.snapshot);
+        if done {
+            break;
+        }
+    }
+    if blocks.len() > 1 {
+        eprintln!("Pages were taken from the market views of blocks {:?}", blocks);
+    }
+    Ok(snapshot)
+}
+
+fn read_snapshot(path: &PathBuf) -> Result<MarketSnapshot> {
+    Ok(serde_json::from_slice(&std::fs::read(path)?)?)
+}
+
+fn print_diff(diff: &MarketSnapshotDiff, top: usize) {
+    println!("Blocks {} -> {}", diff.from_block, diff.to_block);
+
+    println!("Pools added {} removed {} toggled {}", diff.pools_added.len(), diff.pools_removed.len(), diff.pools_toggled.len());
+    for pool in diff.pools_added.iter() {
+        println!("  + {}", pool);
+    }
+    for pool in diff.pools_removed.iter() {
+        println!("  - {}", pool);
+    }
+    for toggle in diff.pools_toggled.iter() {
+        println!("  {} {}", if toggle.disabled { "disabled" } else { "enabled" }, toggle.pool);
+    }
+
+    println!(
+        "Paths added {} removed {} disabled {} enabled {}",
+        diff.paths_added, diff.paths_removed, diff.paths_disabled, diff.paths_enabled
+    );
+
+    println!("Score changes {}", diff.score_changes.len());
+    for change in diff.score_changes.iter().take(top) {
+        println!("  {:?} -> {:?} : {}", change.before, change.after, change.path);
+    }
+
+    println!("Tokens reclassified {}", diff.tokens_reclassified.len());
+    for token in diff.tokens_reclassified.iter() {
+        println!("  {} : {:?} -> {:?}", token.address, token.before, token.after);
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args = Commands::parse();
+    match args {
+        Commands::Fetch { url, out, limit } => {
+            let snapshot = fetch_snapshot(&url, limit).await?;
+            std::fs::write(&out, serde_json::to_vec(&snapshot)?)?;
+            println!(
+                "Snapshot of block {} saved to {} : {} pools {} tokens {} paths",
+                snapshot.block_number,
+                out.display(),
+                snapshot.pools.len(),
+                snapshot.tokens.len(),
+                snapshot.paths.len()
+            );
+        }
+        Commands::Diff { before, after, json, top } => {
+            let diff = read_snapshot(&before)?.diff(&read_snapshot(&after)?);
+            if json {
+                println!("{}", serde_json::to_string_pretty(&diff)?);
+            } else {
+                print_diff(&diff, top);
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -15,7 +15,7 @@ use std::marker::PhantomData;
 use std::time::Duration;
 use tokio::sync::broadcast::error::RecvError;
 use tokio::time::sleep;
-use tracing::debug;
+use tracing::{debug, error};
 
 pub async fn nonce_and_balance_fetcher_worker<P, N>(
     client: P,
@@ -53,11 +53,16 @@ where
     Ok("Nonce and balance fetcher finished".to_string())
 }
 
-pub async fn nonce_and_balance_monitor_worker(
+pub async fn nonce_and_balance_monitor_worker<P, N>(
+    client: P,
     accounts_state: SharedState<AccountNonceAndBalanceState>,
     latest_block: SharedState<LatestBlock>,
     market_events_rx: Broadcaster<MarketEvents>,
-) -> WorkerResult {
+) -> WorkerResult
+where
+    N: Network,
+    P: Provider<N> + Send + Sync + Clone + 'static,
+{
     let mut market_events = market_events_rx.subscribe();
 
     loop {
@@ -72,6 +77,8 @@ pub async fn nonce_and_balance_monitor_worker(
 
                                     // acquire accounts shared state write lock
                                     let mut accounts_lock = accounts_state.write().await;
+                                    // accounts with a nonce listener a tx was sent to, e.g. sponsored profit accounts
+                                    let mut refetch_accounts: Vec<Address> = Vec::new();
 
                                     for tx in txs {
                                         let tx_from : Address = tx.from;
@@ -89,10 +96,29 @@ pub async fn nonce_and_balance_monitor_worker(
                                                 if let Some(&mut ref mut account) = accounts_lock.get_mut_account(&to) {
                                                     account.add_balance(Address::ZERO, tx.value());
                                                     debug!("Account {} : add ETH balance {} -> {}", to, tx.value(), account.get_eth_balance());
+                                                    if account.has_nonce_listener() && !refetch_accounts.contains(&to) {
+                                                        refetch_accounts.push(to);
+                                                    }
                                                 }
                                             }
                                         }
                                     }
+                                    drop(accounts_lock);
+
+                                    // authorizations of an included tx change the nonce of the account without it sending a tx
+                                    for addr in refetch_accounts {
+                                        match client.get_transaction_count(addr).block_id(BlockId::Number(BlockNumberOrTag::Latest)).await {
+                                            Ok(nonce) => {
+                                                if let Some(account) = accounts_state.write().await.get_mut_account(&addr) {
+                                                    account.set_nonce(nonce);
+                                                }
+                                                debug!("Account {} : nonce refetched {}", addr, nonce);
+                                            }
+                                            Err(e) => {
+                                                error!("Cannot fetch nonce of account {} : {}", addr, e);
+                                            }
+                                        }
+                                    }
                                 }
                             }
                         },
@@ -213,6 +239,7 @@ where
         }
 
         let monitor_task = tokio::task::spawn(nonce_and_balance_monitor_worker(
+            self.client.clone(),
             self.accounts_nonce_and_balance.clone().unwrap(),
             self.latest_block.clone().unwrap(),
             self.market_events.clone().unwrap(),
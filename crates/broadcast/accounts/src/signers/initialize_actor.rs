@@ -1,16 +1,28 @@
+use std::sync::Arc;
+
 use alloy_primitives::{hex, Bytes, B256};
+use alloy_signer_local::PrivateKeySigner;
 use eyre::eyre;
 use tracing::{error, info};
 
 use loom_core_actors::{Accessor, Actor, ActorResult, SharedState, WorkerResult};
 use loom_core_actors_macros::Accessor;
 use loom_core_blockchain::Blockchain;
-use loom_types_entities::{AccountNonceAndBalanceState, KeyStore, LoomTxSigner, TxSigners};
+use loom_types_entities::{AccountNonceAndBalanceState, KeyStore, LoomTxSigner, SponsoredTxSigner, SponsorshipMode, TxSigners};
+
+/// Profit account sponsored by the gas payer key of the actor
+#[derive(Clone)]
+struct Sponsorship {
+    profit_account_key: Vec<u8>,
+    mode: SponsorshipMode,
+    chain_id: u64,
+}
 
 /// The one-shot actor adds a new signer to the signers and monitor list after and stops.
 #[derive(Accessor)]
 pub struct InitializeSignersOneShotBlockingActor {
     key: Option<Vec<u8>>,
+    sponsorship: Option<Sponsorship>,
     #[accessor]
     signers: Option<SharedState<TxSigners>>,
     #[accessor]
@@ -19,40 +31,67 @@ pub struct InitializeSignersOneShotBlockingActor {
 
 async fn initialize_signers_one_shot_worker(
     key: Vec<u8>,
+    sponsorship: Option<Sponsorship>,
     signers: SharedState<TxSigners>,
     monitor: SharedState<AccountNonceAndBalanceState>,
 ) -> WorkerResult {
-    let new_signer = signers.write().await.add_privkey(Bytes::from(key));
-    monitor.write().await.add_account(new_signer.address());
-    info!("New signer added {:?}", new_signer.address());
+    let new_signer_address = match sponsorship {
+        Some(sponsorship) => {
+            let gas_payer = PrivateKeySigner::from_slice(&key)?;
+            let profit_account = PrivateKeySigner::from_slice(&sponsorship.profit_account_key)?;
+            let new_signer = SponsoredTxSigner::new(gas_payer, profit_account, sponsorship.mode, sponsorship.chain_id);
+            info!(profit_account = %new_signer.profit_account(), mode = ?new_signer.mode(), "Sponsored signer created");
+            // the nonce monitor fetches the authority nonce from chain
+            monitor.write().await.add_account(new_signer.profit_account()).set_nonce_listener(new_signer.authority_nonce());
+            let address = new_signer.address();
+            signers.write().await.add_signer(Arc::new(new_signer));
+            address
+        }
+        None => signers.write().await.add_privkey(Bytes::from(key)).address(),
+    };
+    monitor.write().await.add_account(new_signer_address);
+    info!("New signer added {:?}", new_signer_address);
     Ok("Signer added".to_string())
 }
 
+fn decrypt_env_key(var: &str) -> Option<Vec<u8>> {
+    match std::env::var(var) {
+        Ok(priv_key_enc) => {
+            let keystore = KeyStore::new();
+            let key = keystore.encrypt_once(hex::decode(priv_key_enc).unwrap().as_slice()).unwrap();
+            Some(key)
+        }
+        _ => None,
+    }
+}
+
 impl InitializeSignersOneShotBlockingActor {
     pub fn new(key: Option<Vec<u8>>) -> InitializeSignersOneShotBlockingActor {
         let key = key.unwrap_or_else(|| B256::random().to_vec());
 
-        InitializeSignersOneShotBlockingActor { key: Some(key), signers: None, monitor: None }
+        InitializeSignersOneShotBlockingActor { key: Some(key), sponsorship: None, signers: None, monitor: None }
     }
 
     pub fn new_from_encrypted_env() -> InitializeSignersOneShotBlockingActor {
-        let key = match std::env::var("DATA") {
-            Ok(priv_key_enc) => {
-                let keystore = KeyStore::new();
-                let key = keystore.encrypt_once(hex::decode(priv_key_enc).unwrap().as_slice()).unwrap();
-                Some(key)
-            }
-            _ => None,
-        };
+        Self::new_from_encrypted_env_var("DATA")
+    }
 
-        InitializeSignersOneShotBlockingActor { key, signers: None, monitor: None }
+    /// Key encrypted with the keystore password in the environment variable `var`
+    pub fn new_from_encrypted_env_var(var: &str) -> InitializeSignersOneShotBlockingActor {
+        InitializeSignersOneShotBlockingActor { key: decrypt_env_key(var), sponsorship: None, signers: None, monitor: None }
     }
 
     pub fn new_from_encrypted_key(priv_key_enc: Vec<u8>) -> InitializeSignersOneShotBlockingActor {
         let keystore = KeyStore::new();
         let key = keystore.encrypt_once(priv_key_enc.as_slice()).unwrap();
 
-        InitializeSignersOneShotBlockingActor { key: Some(key), signers: None, monitor: None }
+        InitializeSignersOneShotBlockingActor { key: Some(key), sponsorship: None, signers: None, monitor: None }
+    }
+
+    /// Use the key as the gas payer sponsoring the profit account with the encrypted key in the environment variable `var`
+    pub fn with_sponsored_env_var(self, var: &str, mode: SponsorshipMode, chain_id: u64) -> eyre::Result<Self> {
+        let profit_account_key = decrypt_env_key(var).ok_or_else(|| eyre!("NO_SPONSORED_KEY"))?;
+        Ok(Self { sponsorship: Some(Sponsorship { profit_account_key, mode, chain_id }), ..self })
     }
 
     pub fn on_bc(self, bc: &Blockchain) -> Self {
@@ -81,8 +120,9 @@ impl Actor for InitializeSignersOneShotBlockingActor {
             }
         };
 
+        let sponsorship = self.sponsorship.clone();
         let rt = tokio::runtime::Runtime::new()?; // we need a different runtime to wait for the result
-        let handle = rt.spawn(async { initialize_signers_one_shot_worker(key, signers, monitor).await });
+        let handle = rt.spawn(async { initialize_signers_one_shot_worker(key, sponsorship, signers, monitor).await });
 
         self.wait(Ok(vec![handle]))?;
         rt.shutdown_background();
@@ -47,7 +47,19 @@ async fn sign_task<LDT: LoomDataTypes>(
         return Err(eyre!("CANNOT_SIGN_BUNDLE"));
     }
 
-    let broadcast_request = TxComposeData { rlp_bundle: Some(rlp_bundle), ..sign_request };
+    // signed with the nonce of the backrun tx, only one of them can be included
+    let public_fallback = match &sign_request.public_fallback {
+        Some(TxState::SignatureRequired(t)) => match signer.sign_sync(t.clone()) {
+            Ok(tx) => Some(TxState::ReadyForBroadcast(Bytes::from(tx.encode()))),
+            Err(e) => {
+                error!("Public fallback tx signing error : {e}");
+                None
+            }
+        },
+        public_fallback => public_fallback.clone(),
+    };
+
+    let broadcast_request = TxComposeData { rlp_bundle: Some(rlp_bundle), public_fallback, ..sign_request };
 
     match compose_channel_tx.send(MessageTxCompose::broadcast(broadcast_request)) {
         Err(e) => {
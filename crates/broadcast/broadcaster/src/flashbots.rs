@@ -1,17 +1,104 @@
 use std::sync::Arc;
 
 use alloy_network::Ethereum;
-use alloy_primitives::Bytes;
+use alloy_primitives::{keccak256, Address, Bytes, TxHash, U256};
 use alloy_provider::Provider;
 use eyre::{eyre, Result};
 use tokio::sync::broadcast::error::RecvError;
-use tracing::error;
+use tokio::sync::mpsc;
+use tracing::{debug, error, warn};
 
 use loom_broadcast_flashbots::Flashbots;
-use loom_core_actors::{subscribe, Actor, ActorResult, Broadcaster, Consumer, WorkerResult};
+use loom_core_actors::{subscribe, Actor, ActorResult, Broadcaster, Consumer, SharedState, WorkerResult};
 use loom_core_actors_macros::{Accessor, Consumer};
 use loom_core_blockchain::Blockchain;
-use loom_types_events::{MessageTxCompose, RlpState, TxComposeData, TxComposeMessageType};
+use loom_types_entities::{Market, PoolClass};
+use loom_types_events::{MarketEvents, MessageTxCompose, RlpState, TxComposeData, TxComposeMessageType, TxState};
+
+// Maximum number of bundles re-targeted on a block
+const MAX_RETARGET_BUNDLES: usize = 32;
+
+/// Broadcast bundle that is re-targeted to the next block while it is not included and still simulates successfully
+struct RetargetBundle {
+    txs: Vec<Bytes>,
+    backrun_txs: Vec<Bytes>,
+    nonces: Vec<(Address, u64, TxHash)>,
+    profit: U256,
+    target_block: u64,
+    retargets_left: u64,
+    pool_classes: Vec<PoolClass>,
+}
+
+impl RetargetBundle {
+    fn new(broadcast_request: &TxComposeData, retargets: u64) -> Option<Self> {
+        let rlp_bundle = broadcast_request.rlp_bundle.as_ref()?;
+        let txs: Vec<Bytes> = rlp_bundle.iter().map(|item| item.unwrap()).collect();
+        let backrun_txs: Vec<Bytes> =
+            rlp_bundle.iter().filter(|item| matches!(item, RlpState::Backrun(_))).map(|item| item.unwrap()).collect();
+        if txs.iter().any(|tx| tx.is_empty()) || backrun_txs.is_empty() {
+            return None;
+        }
+        let nonces = backrun_nonces(broadcast_request, &backrun_txs);
+        Some(Self {
+            txs,
+            backrun_txs,
+            nonces,
+            profit: bundle_profit(broadcast_request),
+            target_block: broadcast_request.next_block_number,
+            retargets_left: retargets,
+            pool_classes: pool_classes(broadcast_request),
+        })
+    }
+
+    fn uses_pool_class(&self, pool_class: PoolClass) -> bool {
+        self.pool_classes.contains(&pool_class)
+    }
+
+    /// The swap goes through a pool class disabled by the kill switch
+    fn is_disabled(&self, market: &Market) -> bool {
+        self.pool_classes.iter().any(|pool_class| market.is_pool_class_disabled(*pool_class))
+    }
+
+    /// Cancel the submissions of the bundle for its current target block at the relays
+    async fn cancel<P>(self, client: Arc<Flashbots<P>>)
+    where
+        P: Provider<Ethereum> + Send + Sync + Clone + 'static,
+    {
+        let mut submissions = vec![self.txs];
+        if self.backrun_txs != submissions[0] {
+            submissions.push(self.backrun_txs);
+        }
+        for txs in submissions {
+            if let Err(error) = client.cancel_bundle(&txs, self.target_block).await {
+                error!(target_block = self.target_block, %error, "Failed to cancel bundle");
+            }
+        }
+    }
+}
+
+/// Pool classes the swap of the request goes through
+fn pool_classes(broadcast_request: &TxComposeData) -> Vec<PoolClass> {
+    let mut pool_classes: Vec<PoolClass> = Vec::new();
+    for pool in broadcast_request.swap.as_ref().map(|swap| swap.get_pools_vec()).unwrap_or_default() {
+        if !pool_classes.contains(&pool.get_class()) {
+            pool_classes.push(pool.get_class());
+        }
+    }
+    pool_classes
+}
+
+/// Signer, nonce and hash of the backrun txs, signed with consecutive nonces of the request
+fn backrun_nonces(broadcast_request: &TxComposeData, backrun_txs: &[Bytes]) -> Vec<(Address, u64, TxHash)> {
+    match broadcast_request.eoa {
+        Some(eoa) => backrun_txs.iter().enumerate().map(|(idx, tx)| (eoa, broadcast_request.nonce + idx as u64, keccak256(tx))).collect(),
+        None => Vec::new(),
+    }
+}
+
+/// Expected profit of the bundle in ETH, bundles replacing an in flight bundle of the same nonces need a higher one
+fn bundle_profit(broadcast_request: &TxComposeData) -> U256 {
+    broadcast_request.swap.as_ref().map(|swap| swap.abs_profit_eth()).unwrap_or_default()
+}
 
 async fn broadcast_task<P>(broadcast_request: TxComposeData, client: Arc<Flashbots<P>>) -> Result<()>
 where
@@ -27,8 +114,10 @@ where
         if stuffing_rlp_bundle.iter().any(|i| i.is_empty()) || backrun_rlp_bundle.iter().any(|i| i.is_empty()) {
             Err(eyre!("RLP_BUNDLE_IS_INCORRECT"))
         } else {
-            client.broadcast_txes(backrun_rlp_bundle.clone(), block_number).await?;
-            client.broadcast_txes(stuffing_rlp_bundle.clone(), block_number).await?;
+            let nonces = backrun_nonces(&broadcast_request, &backrun_rlp_bundle);
+            let profit = bundle_profit(&broadcast_request);
+            client.broadcast_bundle(backrun_rlp_bundle, block_number, nonces.clone(), profit).await?;
+            client.broadcast_bundle(stuffing_rlp_bundle, block_number, nonces, profit).await?;
 
             Ok(())
         }
@@ -38,6 +127,202 @@ where
     }
 }
 
+/// Simulate the bundle on top of `block_number` and broadcast it for the next block. If the stuffing txs were included
+/// or the full bundle fails otherwise, the backrun txs alone are tried. Bundles with included or reverting backrun txs are dropped.
+async fn retarget_task<P>(
+    bundle: RetargetBundle,
+    block_number: u64,
+    client: Arc<Flashbots<P>>,
+    retargeted_tx: mpsc::UnboundedSender<RetargetBundle>,
+) -> Result<()>
+where
+    P: Provider<Ethereum> + Send + Sync + Clone + 'static,
+{
+    let target_block = block_number + 1;
+    let mut candidates = vec![bundle.txs.clone()];
+    if bundle.backrun_txs != bundle.txs {
+        candidates.push(bundle.backrun_txs.clone());
+    }
+
+    for txs in candidates {
+        match client.simulate_txes(txs.clone(), block_number, None).await {
+            Ok(simulated) if simulated.transactions.iter().all(|tx| tx.error.is_none() && tx.revert.is_none()) => {
+                client.broadcast_bundle(txs.clone(), target_block, bundle.nonces.clone(), bundle.profit).await?;
+                debug!(target_block, txs = txs.len(), retargets_left = bundle.retargets_left - 1, "Bundle re-targeted");
+                if bundle.retargets_left > 1 {
+                    retargeted_tx
+                        .send(RetargetBundle { txs, target_block, retargets_left: bundle.retargets_left - 1, ..bundle })
+                        .map_err(|_| eyre!("RETARGET_CHANNEL_CLOSED"))?;
+                }
+                return Ok(());
+            }
+            Ok(_) => {
+                debug!(target_block, txs = txs.len(), "Bundle reverted in re-target simulation");
+            }
+            Err(e) => {
+                debug!(target_block, txs = txs.len(), "Bundle re-target simulation error : {}", e);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Track broadcast bundles and re-target the ones that are still valid to the next block for up to `retarget_blocks` blocks.
+/// Bundles through a pool class disabled by the kill switch are cancelled and not re-targeted
+async fn bundle_retarget_worker<P>(
+    client: Arc<Flashbots<P>>,
+    market: Option<SharedState<Market>>,
+    bundle_rx: Broadcaster<MessageTxCompose>,
+    market_events_rx: Broadcaster<MarketEvents>,
+    retarget_blocks: u64,
+) -> WorkerResult
+where
+    P: Provider<Ethereum> + Send + Sync + Clone + 'static,
+{
+    subscribe!(bundle_rx);
+    subscribe!(market_events_rx);
+
+    let (retargeted_tx, mut retargeted_rx) = mpsc::unbounded_channel::<RetargetBundle>();
+    let mut bundles: Vec<RetargetBundle> = Vec::new();
+
+    loop {
+        tokio::select! {
+            msg = bundle_rx.recv() => {
+                match msg {
+                    Ok(compose_request) => {
+                        if let TxComposeMessageType::Broadcast(broadcast_request) = compose_request.inner {
+                            if let Some(bundle) = RetargetBundle::new(&broadcast_request, retarget_blocks) {
+                                bundles.push(bundle);
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        error!("bundle_retarget_worker {}", e)
+                    }
+                }
+            }
+            msg = market_events_rx.recv() => {
+                match msg {
+                    Ok(MarketEvents::BlockHeaderUpdate { block_number, .. }) => {
+                        let (expired, pending): (Vec<RetargetBundle>, Vec<RetargetBundle>) =
+                            std::mem::take(&mut bundles).into_iter().partition(|bundle| bundle.target_block <= block_number);
+                        bundles = pending;
+
+                        let expired = match &market {
+                            Some(market) => {
+                                let market_guard = market.read().await;
+                                expired.into_iter().filter(|bundle| !bundle.is_disabled(&market_guard)).collect()
+                            }
+                            None => expired,
+                        };
+                        if expired.len() > MAX_RETARGET_BUNDLES {
+                            debug!(block_number, bundles = expired.len(), "Too many bundles to re-target, latest are kept");
+                        }
+                        for bundle in expired.into_iter().rev().take(MAX_RETARGET_BUNDLES) {
+                            tokio::task::spawn(retarget_task(bundle, block_number, client.clone(), retargeted_tx.clone()));
+                        }
+                    }
+                    Ok(MarketEvents::PoolClassDisabled { pool_class }) => {
+                        let (cancelled, pending): (Vec<RetargetBundle>, Vec<RetargetBundle>) =
+                            std::mem::take(&mut bundles).into_iter().partition(|bundle| bundle.uses_pool_class(pool_class));
+                        bundles = pending;
+                        if !cancelled.is_empty() {
+                            warn!(%pool_class, bundles = cancelled.len(), "Cancelling bundles through the disabled pool class");
+                        }
+                        for bundle in cancelled {
+                            tokio::task::spawn(bundle.cancel(client.clone()));
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            Some(bundle) = retargeted_rx.recv() => {
+                // re-targeted while the pool class was disabled
+                let disabled = match &market {
+                    Some(market) => bundle.is_disabled(&market.read().await),
+                    None => false,
+                };
+                if disabled {
+                    tokio::task::spawn(bundle.cancel(client.clone()));
+                } else {
+                    bundles.push(bundle);
+                }
+            }
+        }
+    }
+}
+
+/// Public mempool fallback txs of the broadcast bundles by target block
+#[derive(Default)]
+struct PublicFallbacks {
+    fallbacks: Vec<(u64, Bytes)>,
+}
+
+impl PublicFallbacks {
+    fn push(&mut self, broadcast_request: &TxComposeData) {
+        if let Some(TxState::ReadyForBroadcast(tx)) = &broadcast_request.public_fallback {
+            self.fallbacks.push((broadcast_request.next_block_number, tx.clone()));
+        }
+    }
+
+    /// Fallback txs of the bundles that targeted the block. Fallbacks of earlier blocks are dropped, their deadline may
+    /// have passed and they would revert on chain
+    fn take_missed(&mut self, block_number: u64) -> Vec<Bytes> {
+        let (missed, pending): (Vec<(u64, Bytes)>, Vec<(u64, Bytes)>) =
+            std::mem::take(&mut self.fallbacks).into_iter().partition(|(target_block, _)| *target_block <= block_number);
+        self.fallbacks = pending;
+        missed.into_iter().filter(|(target_block, _)| *target_block == block_number).map(|(_, tx)| tx).collect()
+    }
+}
+
+/// Send the public mempool fallback txs of the bundles that missed their target block. A fallback of an included bundle
+/// has a used nonce and is rejected by the node
+async fn public_fallback_worker<P>(
+    client: Arc<Flashbots<P>>,
+    bundle_rx: Broadcaster<MessageTxCompose>,
+    market_events_rx: Broadcaster<MarketEvents>,
+) -> WorkerResult
+where
+    P: Provider<Ethereum> + Send + Sync + Clone + 'static,
+{
+    subscribe!(bundle_rx);
+    subscribe!(market_events_rx);
+
+    let mut fallbacks = PublicFallbacks::default();
+
+    loop {
+        tokio::select! {
+            msg = bundle_rx.recv() => {
+                match msg {
+                    Ok(compose_request) => {
+                        if let TxComposeMessageType::Broadcast(broadcast_request) = compose_request.inner {
+                            fallbacks.push(&broadcast_request);
+                        }
+                    }
+                    Err(e) => {
+                        error!("public_fallback_worker {}", e)
+                    }
+                }
+            }
+            msg = market_events_rx.recv() => {
+                if let Ok(MarketEvents::BlockHeaderUpdate { block_number, .. }) = msg {
+                    for tx in fallbacks.take_missed(block_number) {
+                        let client = client.clone();
+                        tokio::task::spawn(async move {
+                            match client.provider().send_raw_transaction(&tx).await {
+                                Ok(pending_tx) => {
+                                    debug!(target_block = block_number, tx_hash = %pending_tx.tx_hash(), "Public fallback sent")
+                                }
+                                Err(error) => debug!(target_block = block_number, %error, "Public fallback rejected"),
+                            }
+                        });
+                    }
+                }
+            }
+        }
+    }
+}
+
 async fn flashbots_broadcaster_worker<P>(
     client: Arc<Flashbots<P>>,
     bundle_rx: Broadcaster<MessageTxCompose>,
@@ -112,9 +397,15 @@ where
 #[derive(Accessor, Consumer)]
 pub struct FlashbotsBroadcastActor<P> {
     client: Arc<Flashbots<P>>,
+    #[accessor]
+    market: Option<SharedState<Market>>,
     #[consumer]
     tx_compose_channel_rx: Option<Broadcaster<MessageTxCompose>>,
+    #[consumer]
+    market_events_rx: Option<Broadcaster<MarketEvents>>,
     allow_broadcast: bool,
+    retarget_blocks: u64,
+    public_fallback: bool,
 }
 
 impl<P> FlashbotsBroadcastActor<P>
@@ -122,11 +413,36 @@ where
     P: Provider<Ethereum> + Send + Sync + Clone + 'static,
 {
     pub fn new(client: Flashbots<P>, allow_broadcast: bool) -> FlashbotsBroadcastActor<P> {
-        FlashbotsBroadcastActor { client: Arc::new(client), tx_compose_channel_rx: None, allow_broadcast }
+        FlashbotsBroadcastActor {
+            client: Arc::new(client),
+            market: None,
+            tx_compose_channel_rx: None,
+            market_events_rx: None,
+            allow_broadcast,
+            retarget_blocks: 0,
+            public_fallback: false,
+        }
+    }
+
+    /// Re-target bundles that are not included to the next block for up to `retarget_blocks` blocks, disabled if zero.
+    /// Requires market events.
+    pub fn with_retarget_blocks(self, retarget_blocks: u64) -> Self {
+        Self { retarget_blocks, ..self }
+    }
+
+    /// Send the public mempool fallback txs of bundles that are not included in their target block. Requires market
+    /// events, not combined with re-targeting.
+    pub fn with_public_fallback(self, public_fallback: bool) -> Self {
+        Self { public_fallback, ..self }
     }
 
     pub fn on_bc(self, bc: &Blockchain) -> Self {
-        Self { tx_compose_channel_rx: Some(bc.tx_compose_channel()), ..self }
+        Self {
+            market: Some(bc.market()),
+            tx_compose_channel_rx: Some(bc.tx_compose_channel()),
+            market_events_rx: Some(bc.market_events_channel()),
+            ..self
+        }
     }
 }
 
@@ -140,10 +456,74 @@ where
             self.tx_compose_channel_rx.clone().unwrap(),
             self.allow_broadcast,
         ));
-        Ok(vec![task])
+        let mut tasks = vec![task];
+
+        if self.retarget_blocks > 0 && self.allow_broadcast {
+            match self.market_events_rx.clone() {
+                Some(market_events_rx) => {
+                    tasks.push(tokio::task::spawn(bundle_retarget_worker(
+                        self.client.clone(),
+                        self.market.clone(),
+                        self.tx_compose_channel_rx.clone().unwrap(),
+                        market_events_rx,
+                        self.retarget_blocks,
+                    )));
+                }
+                None => {
+                    warn!("Market events channel is not set, bundles are not re-targeted");
+                }
+            }
+        }
+
+        if self.public_fallback && self.allow_broadcast {
+            match self.market_events_rx.clone() {
+                Some(_) if self.retarget_blocks > 0 => {
+                    warn!("Bundles are re-targeted, public fallback txs are not sent");
+                }
+                Some(market_events_rx) => {
+                    tasks.push(tokio::task::spawn(public_fallback_worker(
+                        self.client.clone(),
+                        self.tx_compose_channel_rx.clone().unwrap(),
+                        market_events_rx,
+                    )));
+                }
+                None => {
+                    warn!("Market events channel is not set, public fallback txs are not sent");
+                }
+            }
+        }
+        Ok(tasks)
     }
 
     fn name(&self) -> &'static str {
         "FlashbotsBroadcastActor"
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn broadcast_request(target_block: u64, public_fallback: Option<TxState>) -> TxComposeData {
+        TxComposeData { next_block_number: target_block, public_fallback, ..TxComposeData::default() }
+    }
+
+    #[test]
+    fn test_public_fallbacks() {
+        let tx = |byte: u8| Bytes::from(vec![byte]);
+        let mut fallbacks = PublicFallbacks::default();
+        fallbacks.push(&broadcast_request(10, Some(TxState::ReadyForBroadcast(tx(1)))));
+        fallbacks.push(&broadcast_request(11, Some(TxState::ReadyForBroadcast(tx(2)))));
+        fallbacks.push(&broadcast_request(12, Some(TxState::ReadyForBroadcast(tx(3)))));
+        // not signed
+        fallbacks.push(&broadcast_request(10, Some(TxState::SignatureRequired(Default::default()))));
+        fallbacks.push(&broadcast_request(10, None));
+
+        assert!(fallbacks.take_missed(9).is_empty());
+        assert_eq!(fallbacks.take_missed(10), vec![tx(1)]);
+        assert!(fallbacks.take_missed(10).is_empty());
+        // the header of block 11 was skipped, its fallback is stale
+        assert_eq!(fallbacks.take_missed(12), vec![tx(3)]);
+        assert!(fallbacks.fallbacks.is_empty());
+    }
+}
@@ -1,5 +1,11 @@
 pub use anvil::AnvilBroadcastActor;
 pub use flashbots::FlashbotsBroadcastActor;
+pub use sequencer::SequencerBroadcastActor;
+pub use timeboost::{BidStrategy, RoundTiming, TimeboostBroadcastActor, TimeboostConfig};
+pub use user_operation::{OpsCallTarget, UserOperation, UserOperationBroadcastActor, UserOperationConfig};
 
 mod anvil;
 mod flashbots;
+mod sequencer;
+mod timeboost;
+mod user_operation;
@@ -0,0 +1,99 @@
+use alloy_network::Ethereum;
+use alloy_primitives::Bytes;
+use alloy_provider::Provider;
+use eyre::{eyre, Result};
+use tracing::{debug, error};
+
+use loom_core_actors::{subscribe, Actor, ActorResult, Broadcaster, Consumer, WorkerResult};
+use loom_core_actors_macros::{Accessor, Consumer};
+use loom_core_blockchain::Blockchain;
+use loom_types_entities::ExecutionProfile;
+use loom_types_events::{MessageTxCompose, RlpState, TxComposeData, TxComposeMessageType};
+
+/// Send the backrun txs of the request one by one, the stuffing txs are already sent by their owners
+async fn broadcast_task<P>(client: P, execution_profile: ExecutionProfile, broadcast_request: TxComposeData) -> Result<()>
+where
+    P: Provider<Ethereum> + Send + Sync + Clone + 'static,
+{
+    let rlp_bundle = broadcast_request.rlp_bundle.ok_or(eyre!("RLP_BUNDLE_IS_NONE"))?;
+    let backrun_txs: Vec<Bytes> = rlp_bundle.iter().filter(|item| matches!(item, RlpState::Backrun(_))).map(|item| item.unwrap()).collect();
+    if backrun_txs.is_empty() || backrun_txs.iter().any(|tx| tx.is_empty()) {
+        return Err(eyre!("RLP_BUNDLE_IS_INCORRECT"));
+    }
+
+    for tx in backrun_txs {
+        let pending_tx = client.send_raw_transaction(&tx).await?;
+        debug!(?execution_profile, tx_hash = %pending_tx.tx_hash(), block_number = broadcast_request.next_block_number, "Transaction sent");
+    }
+    Ok(())
+}
+
+async fn sequencer_broadcaster_worker<P>(
+    client: P,
+    execution_profile: ExecutionProfile,
+    tx_compose_channel_rx: Broadcaster<MessageTxCompose>,
+) -> WorkerResult
+where
+    P: Provider<Ethereum> + Send + Sync + Clone + 'static,
+{
+    subscribe!(tx_compose_channel_rx);
+
+    loop {
+        match tx_compose_channel_rx.recv().await {
+            Ok(compose_request) => {
+                if let TxComposeMessageType::Broadcast(broadcast_request) = compose_request.inner {
+                    let client = client.clone();
+                    tokio::task::spawn(async move {
+                        if let Err(error) = broadcast_task(client, execution_profile, broadcast_request).await {
+                            error!(%error, "Sequencer broadcast failed");
+                        }
+                    });
+                }
+            }
+            Err(e) => {
+                error!("sequencer_broadcaster_worker {}", e)
+            }
+        }
+    }
+}
+
+/// Broadcaster for chains without bundle auctions, see [`ExecutionProfile`]. Transactions are sent to the sequencer
+/// endpoint of the client and compete by their priority fee or land in the next Flashblock
+#[derive(Accessor, Consumer)]
+pub struct SequencerBroadcastActor<P> {
+    client: P,
+    execution_profile: ExecutionProfile,
+    #[consumer]
+    tx_compose_channel_rx: Option<Broadcaster<MessageTxCompose>>,
+}
+
+impl<P> SequencerBroadcastActor<P>
+where
+    P: Provider<Ethereum> + Send + Sync + Clone + 'static,
+{
+    pub fn new(client: P, execution_profile: ExecutionProfile) -> SequencerBroadcastActor<P> {
+        SequencerBroadcastActor { client, execution_profile, tx_compose_channel_rx: None }
+    }
+
+    pub fn on_bc(self, bc: &Blockchain) -> Self {
+        Self { tx_compose_channel_rx: Some(bc.tx_compose_channel()), ..self }
+    }
+}
+
+impl<P> Actor for SequencerBroadcastActor<P>
+where
+    P: Provider<Ethereum> + Send + Sync + Clone + 'static,
+{
+    fn start(&self) -> ActorResult {
+        let task = tokio::task::spawn(sequencer_broadcaster_worker(
+            self.client.clone(),
+            self.execution_profile,
+            self.tx_compose_channel_rx.clone().unwrap(),
+        ));
+        Ok(vec![task])
+    }
+
+    fn name(&self) -> &'static str {
+        "SequencerBroadcastActor"
+    }
+}
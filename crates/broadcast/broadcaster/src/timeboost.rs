@@ -0,0 +1,402 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use alloy_network::Ethereum;
+use alloy_primitives::{eip191_hash_message, keccak256, Address, Bytes, U256, U64};
+use alloy_provider::Provider;
+use alloy_sol_types::{Eip712Domain, SolStruct};
+use eyre::{eyre, Result};
+use serde::Serialize;
+use tokio::sync::Mutex;
+use tracing::{debug, error, info, warn};
+use url::Url;
+
+use loom_broadcast_flashbots::client::Relay;
+use loom_core_actors::{subscribe, Actor, ActorResult, Broadcaster, Consumer, SharedState, WorkerResult};
+use loom_core_actors_macros::{Accessor, Consumer};
+use loom_core_blockchain::Blockchain;
+use loom_defi_abi::arbitrum::{Bid, IExpressLaneAuction};
+use loom_types_blockchain::LoomDataTypesEthereum;
+use loom_types_entities::{LoomTxSigner, TxSigners};
+use loom_types_events::{MessageTxCompose, RlpState, TxComposeData, TxComposeMessageType};
+
+// round of the express lane owned by the controller, `NO_ROUND` if none
+const NO_ROUND: u64 = u64::MAX;
+
+/// Round schedule of the express lane auction
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RoundTiming {
+    pub offset_timestamp: i64,
+    pub round_duration: u64,
+    pub auction_closing: u64,
+}
+
+impl RoundTiming {
+    /// Round running at `timestamp`
+    pub fn round_at(&self, timestamp: u64) -> u64 {
+        let elapsed = (timestamp as i64).saturating_sub(self.offset_timestamp).max(0) as u64;
+        elapsed / self.round_duration.max(1)
+    }
+
+    /// Seconds from `timestamp` to the start of the next round
+    pub fn seconds_to_next_round(&self, timestamp: u64) -> u64 {
+        let next_round_start = self.offset_timestamp + ((self.round_at(timestamp) + 1) * self.round_duration) as i64;
+        (next_round_start - timestamp as i64).max(0) as u64
+    }
+
+    /// Bids for the next round are accepted until `auction_closing` seconds before it starts
+    pub fn auction_open(&self, timestamp: u64) -> bool {
+        self.seconds_to_next_round(timestamp) > self.auction_closing
+    }
+}
+
+/// Bids between the reserve price and `max_bid`, raised by `increment` after a lost round and lowered after a won one
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BidStrategy {
+    /// Largest bid per round in the bidding token, no bids are placed if zero
+    pub max_bid: U256,
+    pub increment: U256,
+}
+
+impl BidStrategy {
+    /// Bid for the next round, `None` if the reserve price is above the max bid. `last_outcome` is the last bid with a
+    /// resolved round and whether it won the round
+    pub fn next_bid(&self, reserve_price: U256, last_outcome: Option<(U256, bool)>) -> Option<U256> {
+        if self.max_bid.is_zero() || reserve_price > self.max_bid {
+            return None;
+        }
+        let bid = match last_outcome {
+            Some((amount, true)) => amount.saturating_sub(self.increment),
+            Some((amount, false)) => amount.saturating_add(self.increment),
+            None => reserve_price,
+        };
+        Some(bid.clamp(reserve_price, self.max_bid))
+    }
+}
+
+/// Express lane auction of an Arbitrum chain and the bids placed for every round
+#[derive(Clone, Debug)]
+pub struct TimeboostConfig {
+    pub chain_id: u64,
+    pub auction_contract: Address,
+    /// Auctioneer endpoint accepting `auctioneer_submitBid`
+    pub auctioneer_url: Url,
+    /// Sequencer endpoint accepting `timeboost_sendExpressLaneTransaction`
+    pub sequencer_url: Url,
+    pub bid_strategy: BidStrategy,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonBid {
+    chain_id: U256,
+    express_lane_controller: Address,
+    auction_contract_address: Address,
+    round: U64,
+    amount: U256,
+    signature: Bytes,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonExpressLaneSubmission {
+    chain_id: U256,
+    round: U64,
+    auction_contract_address: Address,
+    transaction: Bytes,
+    sequence_number: U64,
+    signature: Bytes,
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|duration| duration.as_secs()).unwrap_or_default()
+}
+
+fn auction_domain(config: &TimeboostConfig) -> Eip712Domain {
+    Eip712Domain::new(
+        Some("ExpressLaneAuction".into()),
+        Some("1".into()),
+        Some(U256::from(config.chain_id)),
+        Some(config.auction_contract),
+        None,
+    )
+}
+
+/// Message signed by the express lane controller for the submission of `transaction`
+fn express_lane_message(config: &TimeboostConfig, round: u64, sequence_number: u64, transaction: &Bytes) -> Vec<u8> {
+    let mut message = Vec::with_capacity(32 + 32 + 20 + 8 + 8 + transaction.len());
+    message.extend_from_slice(keccak256("TIMEBOOST_BID").as_slice());
+    message.extend_from_slice(&U256::from(config.chain_id).to_be_bytes::<32>());
+    message.extend_from_slice(config.auction_contract.as_slice());
+    message.extend_from_slice(&round.to_be_bytes());
+    message.extend_from_slice(&sequence_number.to_be_bytes());
+    message.extend_from_slice(transaction);
+    message
+}
+
+async fn controller_signer(signers: &SharedState<TxSigners>) -> Result<Arc<dyn LoomTxSigner<LoomDataTypesEthereum>>> {
+    signers.read().await.get_signer_by_index(0)
+}
+
+/// Bid for the express lane of every next round while the auction is open and keep the round won by the controller
+async fn timeboost_bidder_worker<P>(
+    client: P,
+    config: TimeboostConfig,
+    signers: SharedState<TxSigners>,
+    controlled_round: Arc<AtomicU64>,
+) -> WorkerResult
+where
+    P: Provider<Ethereum> + Send + Sync + Clone + 'static,
+{
+    let auction = IExpressLaneAuction::IExpressLaneAuctionInstance::new(config.auction_contract, client);
+    let timing_info = auction.roundTimingInfo().call().await?;
+    let timing = RoundTiming {
+        offset_timestamp: timing_info.offsetTimestamp,
+        round_duration: timing_info.roundDurationSeconds,
+        auction_closing: timing_info.auctionClosingSeconds,
+    };
+    let auctioneer = Relay::new(config.auctioneer_url.clone(), None);
+    let domain = auction_domain(&config);
+    // round and amount of the last bid, and the last bid with a resolved round and whether it won
+    let mut last_bid: Option<(u64, U256)> = None;
+    let mut last_outcome: Option<(U256, bool)> = None;
+
+    loop {
+        let timestamp = now();
+        let current_round = timing.round_at(timestamp);
+        let next_round = current_round + 1;
+        let controller = controller_signer(&signers).await?;
+
+        // the winner of the running round is known once its auction is resolved
+        match auction.resolvedRounds().call().await {
+            Ok(resolved) => {
+                let won = [resolved._0, resolved._1]
+                    .into_iter()
+                    .any(|elc_round| elc_round.round == current_round && elc_round.expressLaneController == controller.address());
+                if let Some((_, amount)) = last_bid.filter(|(round, _)| *round == current_round) {
+                    last_outcome = Some((amount, won));
+                }
+                let round = if won { current_round } else { NO_ROUND };
+                if controlled_round.swap(round, Ordering::Relaxed) != round && won {
+                    info!(round, controller = %controller.address(), "Express lane controlled");
+                }
+            }
+            Err(error) => warn!(%error, "resolvedRounds"),
+        }
+
+        if timing.auction_open(timestamp) && last_bid.is_none_or(|(round, _)| round < next_round) {
+            let reserve_price = match auction.reservePrice().call().await {
+                Ok(reserve_price) => Some(reserve_price._0),
+                Err(error) => {
+                    warn!(%error, "reservePrice");
+                    None
+                }
+            };
+            if let Some(amount) = reserve_price.and_then(|reserve_price| config.bid_strategy.next_bid(reserve_price, last_outcome)) {
+                let bid = Bid { round: next_round, expressLaneController: controller.address(), amount };
+                let signature = controller.sign_hash_sync(&bid.eip712_signing_hash(&domain))?;
+                let json_bid = JsonBid {
+                    chain_id: U256::from(config.chain_id),
+                    express_lane_controller: controller.address(),
+                    auction_contract_address: config.auction_contract,
+                    round: U64::from(next_round),
+                    amount,
+                    signature: Bytes::from(signature.as_bytes().to_vec()),
+                };
+                match auctioneer.request::<_, ()>("auctioneer_submitBid", [json_bid]).await {
+                    Ok(_) => {
+                        last_bid = Some((next_round, amount));
+                        info!(round = next_round, %amount, "Express lane bid submitted");
+                    }
+                    Err(error) => error!(%error, round = next_round, "auctioneer_submitBid"),
+                }
+            }
+        }
+
+        tokio::time::sleep(Duration::from_secs(timing.seconds_to_next_round(timestamp).clamp(1, 5))).await;
+    }
+}
+
+/// Send the backrun txs through the express lane when the controller owns the round, to the sequencer otherwise
+async fn broadcast_task<P>(
+    client: P,
+    config: TimeboostConfig,
+    signers: SharedState<TxSigners>,
+    controlled_round: Arc<AtomicU64>,
+    sequence: Arc<Mutex<(u64, u64)>>,
+    broadcast_request: TxComposeData,
+) -> Result<()>
+where
+    P: Provider<Ethereum> + Send + Sync + Clone + 'static,
+{
+    let rlp_bundle = broadcast_request.rlp_bundle.ok_or(eyre!("RLP_BUNDLE_IS_NONE"))?;
+    let backrun_txs: Vec<Bytes> = rlp_bundle.iter().filter(|item| matches!(item, RlpState::Backrun(_))).map(|item| item.unwrap()).collect();
+    if backrun_txs.is_empty() || backrun_txs.iter().any(|tx| tx.is_empty()) {
+        return Err(eyre!("RLP_BUNDLE_IS_INCORRECT"));
+    }
+
+    let round = controlled_round.load(Ordering::Relaxed);
+    if round == NO_ROUND {
+        for tx in backrun_txs {
+            let pending_tx = client.send_raw_transaction(&tx).await?;
+            debug!(tx_hash = %pending_tx.tx_hash(), "Transaction sent to sequencer");
+        }
+        return Ok(());
+    }
+
+    let controller = controller_signer(&signers).await?;
+    let sequencer = Relay::new(config.sequencer_url.clone(), None);
+    // submissions of a round are numbered from zero and executed in order by the sequencer, so they are sent one at a
+    // time and a number is only used up by an accepted submission
+    let mut sequence = sequence.lock().await;
+    if sequence.0 != round {
+        *sequence = (round, 0);
+    }
+    for tx in backrun_txs {
+        let sequence_number = sequence.1;
+        let message = express_lane_message(&config, round, sequence_number, &tx);
+        let signature = controller.sign_hash_sync(&eip191_hash_message(&message))?;
+        let submission = JsonExpressLaneSubmission {
+            chain_id: U256::from(config.chain_id),
+            round: U64::from(round),
+            auction_contract_address: config.auction_contract,
+            transaction: tx,
+            sequence_number: U64::from(sequence_number),
+            signature: Bytes::from(signature.as_bytes().to_vec()),
+        };
+        sequencer.request::<_, ()>("timeboost_sendExpressLaneTransaction", [submission]).await?;
+        sequence.1 += 1;
+        debug!(round, sequence_number, "Transaction sent through the express lane");
+    }
+    Ok(())
+}
+
+async fn timeboost_broadcaster_worker<P>(
+    client: P,
+    config: TimeboostConfig,
+    signers: SharedState<TxSigners>,
+    controlled_round: Arc<AtomicU64>,
+    tx_compose_channel_rx: Broadcaster<MessageTxCompose>,
+) -> WorkerResult
+where
+    P: Provider<Ethereum> + Send + Sync + Clone + 'static,
+{
+    subscribe!(tx_compose_channel_rx);
+    let sequence = Arc::new(Mutex::new((NO_ROUND, 0u64)));
+
+    loop {
+        match tx_compose_channel_rx.recv().await {
+            Ok(compose_request) => {
+                if let TxComposeMessageType::Broadcast(broadcast_request) = compose_request.inner {
+                    let task = broadcast_task(
+                        client.clone(),
+                        config.clone(),
+                        signers.clone(),
+                        controlled_round.clone(),
+                        sequence.clone(),
+                        broadcast_request,
+                    );
+                    tokio::task::spawn(async move {
+                        if let Err(error) = task.await {
+                            error!(%error, "Timeboost broadcast failed");
+                        }
+                    });
+                }
+            }
+            Err(e) => {
+                error!("timeboost_broadcaster_worker {}", e)
+            }
+        }
+    }
+}
+
+/// Arbitrum broadcaster bidding for the Timeboost express lane. Transactions skip the express lane delay of other
+/// transactions in the rounds won by the controller, the first signer, and are sent to the sequencer otherwise.
+/// The controller must hold a deposit of the bidding token in the auction contract.
+#[derive(Accessor, Consumer)]
+pub struct TimeboostBroadcastActor<P> {
+    client: P,
+    config: TimeboostConfig,
+    #[accessor]
+    signers: Option<SharedState<TxSigners>>,
+    #[consumer]
+    tx_compose_channel_rx: Option<Broadcaster<MessageTxCompose>>,
+}
+
+impl<P> TimeboostBroadcastActor<P>
+where
+    P: Provider<Ethereum> + Send + Sync + Clone + 'static,
+{
+    pub fn new(client: P, config: TimeboostConfig) -> TimeboostBroadcastActor<P> {
+        TimeboostBroadcastActor { client, config, signers: None, tx_compose_channel_rx: None }
+    }
+
+    pub fn on_bc(self, bc: &Blockchain) -> Self {
+        Self { tx_compose_channel_rx: Some(bc.tx_compose_channel()), ..self }
+    }
+
+    pub fn with_signers(self, signers: SharedState<TxSigners>) -> Self {
+        Self { signers: Some(signers), ..self }
+    }
+}
+
+impl<P> Actor for TimeboostBroadcastActor<P>
+where
+    P: Provider<Ethereum> + Send + Sync + Clone + 'static,
+{
+    fn start(&self) -> ActorResult {
+        let signers = self.signers.clone().ok_or(eyre!("SIGNERS_NOT_SET"))?;
+        let controlled_round = Arc::new(AtomicU64::new(NO_ROUND));
+
+        let bidder_task = tokio::task::spawn(timeboost_bidder_worker(
+            self.client.clone(),
+            self.config.clone(),
+            signers.clone(),
+            controlled_round.clone(),
+        ));
+        let broadcaster_task = tokio::task::spawn(timeboost_broadcaster_worker(
+            self.client.clone(),
+            self.config.clone(),
+            signers,
+            controlled_round,
+            self.tx_compose_channel_rx.clone().unwrap(),
+        ));
+        Ok(vec![bidder_task, broadcaster_task])
+    }
+
+    fn name(&self) -> &'static str {
+        "TimeboostBroadcastActor"
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_round_timing() {
+        let timing = RoundTiming { offset_timestamp: 1_000, round_duration: 60, auction_closing: 15 };
+        assert_eq!(timing.round_at(999), 0);
+        assert_eq!(timing.round_at(1_059), 0);
+        assert_eq!(timing.round_at(1_060), 1);
+        assert_eq!(timing.seconds_to_next_round(1_030), 30);
+        assert!(timing.auction_open(1_044));
+        assert!(!timing.auction_open(1_045));
+    }
+
+    #[test]
+    fn test_next_bid() {
+        let strategy = BidStrategy { max_bid: U256::from(1_000), increment: U256::from(100) };
+        let reserve_price = U256::from(300);
+
+        assert_eq!(strategy.next_bid(reserve_price, None), Some(reserve_price));
+        assert_eq!(strategy.next_bid(reserve_price, Some((U256::from(500), false))), Some(U256::from(600)));
+        assert_eq!(strategy.next_bid(reserve_price, Some((U256::from(500), true))), Some(U256::from(400)));
+        // bids stay between the reserve price and the max bid
+        assert_eq!(strategy.next_bid(reserve_price, Some((U256::from(950), false))), Some(U256::from(1_000)));
+        assert_eq!(strategy.next_bid(reserve_price, Some((U256::from(350), true))), Some(reserve_price));
+        assert_eq!(strategy.next_bid(U256::from(1_001), None), None);
+        assert_eq!(BidStrategy::default().next_bid(U256::ZERO, None), None);
+    }
+}
@@ -0,0 +1,492 @@
+use std::time::{Duration, Instant};
+
+use alloy_network::Ethereum;
+use alloy_primitives::aliases::U192;
+use alloy_primitives::{eip191_hash_message, hex, keccak256, Address, Bytes, FixedBytes, TxHash, B256, U256, U64};
+use alloy_provider::Provider;
+use alloy_sol_types::{SolCall, SolValue};
+use eyre::{eyre, Result};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{debug, error, info, warn};
+use url::Url;
+
+use loom_broadcast_flashbots::client::Relay;
+use loom_core_actors::{subscribe, Actor, ActorResult, Broadcaster, Consumer, SharedState, WorkerResult};
+use loom_core_actors_macros::{Accessor, Consumer};
+use loom_core_blockchain::Blockchain;
+use loom_defi_abi::erc4337::{IEntryPoint, ISimpleAccount};
+use loom_types_entities::TxSigners;
+use loom_types_events::{MessageOpsCall, OpsCall};
+
+// signature of the gas estimation, recoverable by the account without matching its owner
+const DUMMY_SIGNATURE: [u8; 65] = hex!(
+    "fffffffffffffffffffffffffffffff0000000000000000000000000000000007aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa1c"
+);
+
+const RECEIPT_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// ERC-4337 v0.7 user operation in the format of the bundler rpc, of an account already deployed
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserOperation {
+    pub sender: Address,
+    pub nonce: U256,
+    pub call_data: Bytes,
+    pub call_gas_limit: U256,
+    pub verification_gas_limit: U256,
+    pub pre_verification_gas: U256,
+    pub max_fee_per_gas: U256,
+    pub max_priority_fee_per_gas: U256,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub paymaster: Option<Address>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub paymaster_verification_gas_limit: Option<U256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub paymaster_post_op_gas_limit: Option<U256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub paymaster_data: Option<Bytes>,
+    pub signature: Bytes,
+}
+
+impl UserOperation {
+    /// Two 128 bit values packed into a word, the first one in the high bits
+    fn pack_u128(high: U256, low: U256) -> B256 {
+        B256::from((high << 128) | (low & U256::from(u128::MAX)))
+    }
+
+    /// `paymaster ‖ paymasterVerificationGasLimit ‖ paymasterPostOpGasLimit ‖ paymasterData`, empty without a paymaster
+    pub fn paymaster_and_data(&self) -> Bytes {
+        let Some(paymaster) = self.paymaster else {
+            return Bytes::new();
+        };
+        let mut paymaster_and_data = paymaster.to_vec();
+        paymaster_and_data.extend_from_slice(&self.paymaster_verification_gas_limit.unwrap_or_default().to_be_bytes::<32>()[16..]);
+        paymaster_and_data.extend_from_slice(&self.paymaster_post_op_gas_limit.unwrap_or_default().to_be_bytes::<32>()[16..]);
+        paymaster_and_data.extend_from_slice(self.paymaster_data.as_deref().unwrap_or_default());
+        paymaster_and_data.into()
+    }
+
+    /// Hash signed by the owner of the account, as `EntryPoint.getUserOpHash`
+    pub fn hash(&self, entry_point: Address, chain_id: u64) -> B256 {
+        let packed = (
+            self.sender,
+            self.nonce,
+            keccak256(Bytes::new()),
+            keccak256(&self.call_data),
+            Self::pack_u128(self.verification_gas_limit, self.call_gas_limit),
+            self.pre_verification_gas,
+            Self::pack_u128(self.max_priority_fee_per_gas, self.max_fee_per_gas),
+            keccak256(self.paymaster_and_data()),
+        )
+            .abi_encode();
+        keccak256((keccak256(packed), entry_point, U256::from(chain_id)).abi_encode())
+    }
+}
+
+/// Sponsored fields of the user operation returned by an ERC-7677 paymaster service
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PaymasterData {
+    paymaster: Address,
+    paymaster_data: Bytes,
+    paymaster_verification_gas_limit: Option<U256>,
+    paymaster_post_op_gas_limit: Option<U256>,
+}
+
+/// Context of the paymaster service, e.g. a sponsorship policy, none is sent
+#[derive(Serialize)]
+struct PaymasterContext {}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UserOperationGasEstimate {
+    pre_verification_gas: U256,
+    verification_gas_limit: U256,
+    call_gas_limit: U256,
+    paymaster_verification_gas_limit: Option<U256>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UserOperationReceiptTx {
+    transaction_hash: TxHash,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UserOperationReceipt {
+    success: bool,
+    reason: Option<String>,
+    receipt: UserOperationReceiptTx,
+}
+
+/// Contract an operational call may be sent to and the functions it may call on it, e.g. `approve` of a token
+#[derive(Clone, Debug, Deserialize)]
+pub struct OpsCallTarget {
+    pub to: Address,
+    pub selectors: Vec<FixedBytes<4>>,
+}
+
+/// Smart account sending the operational calls and the services bundling and sponsoring its user operations
+#[derive(Clone, Debug)]
+pub struct UserOperationConfig {
+    pub chain_id: u64,
+    pub entry_point: Address,
+    /// Smart account owned by the first signer, executing the calls with `execute(dest, value, func)`
+    pub account: Address,
+    pub bundler_url: Url,
+    /// ERC-7677 paymaster service sponsoring the gas, paid from the deposit of the account if not set
+    pub paymaster_url: Option<Url>,
+    /// Time to wait for the inclusion of a user operation before sending the next one
+    pub receipt_timeout: Duration,
+    /// Targets and selectors of the calls executed by the account, other calls are dropped
+    pub allowed_calls: Vec<OpsCallTarget>,
+}
+
+impl UserOperationConfig {
+    /// The call executes an allowed function of an allowed target, plain transfers of value are not allowed
+    pub fn is_allowed(&self, ops_call: &OpsCall) -> bool {
+        let Some(selector) = ops_call.data.get(..4) else {
+            return false;
+        };
+        self.allowed_calls
+            .iter()
+            .any(|target| target.to == ops_call.to && target.selectors.iter().any(|allowed| allowed.as_slice() == selector))
+    }
+
+    fn chain_id_hex(&self) -> U64 {
+        U64::from(self.chain_id)
+    }
+
+    /// Fill the paymaster fields of the operation with the data of the paymaster service, `stub` for the gas estimation
+    async fn sponsor(&self, paymaster: &Relay, user_operation: &mut UserOperation, stub: bool) -> Result<()> {
+        let method = if stub { "pm_getPaymasterStubData" } else { "pm_getPaymasterData" };
+        let params = (&*user_operation, self.entry_point, self.chain_id_hex(), PaymasterContext {});
+        let data: PaymasterData = paymaster.request(method, params).await.map_err(|error| eyre!("{method} : {error}"))?;
+        user_operation.paymaster = Some(data.paymaster);
+        user_operation.paymaster_data = Some(data.paymaster_data);
+        if data.paymaster_verification_gas_limit.is_some() {
+            user_operation.paymaster_verification_gas_limit = data.paymaster_verification_gas_limit;
+        }
+        if data.paymaster_post_op_gas_limit.is_some() {
+            user_operation.paymaster_post_op_gas_limit = data.paymaster_post_op_gas_limit;
+        }
+        Ok(())
+    }
+}
+
+/// Build, sponsor, sign and send the user operation of the call, returns its hash
+async fn send_user_operation<P>(
+    client: &P,
+    config: &UserOperationConfig,
+    signers: &SharedState<TxSigners>,
+    bundler: &Relay,
+    paymaster: Option<&Relay>,
+    ops_call: &OpsCall,
+) -> Result<B256>
+where
+    P: Provider<Ethereum> + Send + Sync + Clone + 'static,
+{
+    let owner = signers.read().await.get_signer_by_index(0)?;
+    let entry_point = IEntryPoint::IEntryPointInstance::new(config.entry_point, client.clone());
+    let nonce = entry_point.getNonce(config.account, U192::ZERO).call().await?.nonce;
+    let fees = client.estimate_eip1559_fees(None).await?;
+
+    let mut user_operation = UserOperation {
+        sender: config.account,
+        nonce,
+        call_data: ISimpleAccount::executeCall { dest: ops_call.to, value: ops_call.value, func: ops_call.data.clone() }
+            .abi_encode()
+            .into(),
+        max_fee_per_gas: U256::from(fees.max_fee_per_gas),
+        max_priority_fee_per_gas: U256::from(fees.max_priority_fee_per_gas),
+        signature: Bytes::from(DUMMY_SIGNATURE),
+        ..UserOperation::default()
+    };
+
+    if let Some(paymaster) = paymaster {
+        config.sponsor(paymaster, &mut user_operation, true).await?;
+    }
+    let estimate: UserOperationGasEstimate = bundler
+        .request("eth_estimateUserOperationGas", (&user_operation, config.entry_point))
+        .await
+        .map_err(|error| eyre!("eth_estimateUserOperationGas : {error}"))?;
+    user_operation.pre_verification_gas = estimate.pre_verification_gas;
+    user_operation.verification_gas_limit = estimate.verification_gas_limit;
+    user_operation.call_gas_limit = estimate.call_gas_limit;
+    if estimate.paymaster_verification_gas_limit.is_some() {
+        user_operation.paymaster_verification_gas_limit = estimate.paymaster_verification_gas_limit;
+    }
+    // the paymaster signs the final gas limits
+    if let Some(paymaster) = paymaster {
+        config.sponsor(paymaster, &mut user_operation, false).await?;
+    }
+
+    let user_operation_hash = user_operation.hash(config.entry_point, config.chain_id);
+    let signature = owner.sign_hash_sync(&eip191_hash_message(user_operation_hash))?;
+    user_operation.signature = Bytes::from(signature.as_bytes().to_vec());
+
+    let sent_hash: B256 = bundler
+        .request("eth_sendUserOperation", (&user_operation, config.entry_point))
+        .await
+        .map_err(|error| eyre!("eth_sendUserOperation : {error}"))?;
+    if sent_hash != user_operation_hash {
+        warn!(%sent_hash, %user_operation_hash, "User operation hash mismatch");
+    }
+    debug!(label = ops_call.label, %nonce, sponsored = paymaster.is_some(), "User operation sent");
+    Ok(sent_hash)
+}
+
+/// Wait for the inclusion of the user operation
+async fn wait_receipt(bundler: &Relay, user_operation_hash: B256, timeout: Duration) -> Result<UserOperationReceipt> {
+    let start_time = Instant::now();
+    while start_time.elapsed() < timeout {
+        tokio::time::sleep(RECEIPT_POLL_INTERVAL).await;
+        match bundler.request::<_, Option<UserOperationReceipt>>("eth_getUserOperationReceipt", [user_operation_hash]).await {
+            Ok(Some(receipt)) => return Ok(receipt),
+            Ok(None) => {}
+            Err(error) => debug!(%error, "eth_getUserOperationReceipt"),
+        }
+    }
+    Err(eyre!("USER_OPERATION_RECEIPT_TIMEOUT"))
+}
+
+/// Send the calls one by one, a call is sent after the previous one is included or timed out
+async fn user_operation_worker<P>(
+    client: P,
+    config: UserOperationConfig,
+    signers: SharedState<TxSigners>,
+    ops_call_rx: Broadcaster<MessageOpsCall>,
+) -> WorkerResult
+where
+    P: Provider<Ethereum> + Send + Sync + Clone + 'static,
+{
+    subscribe!(ops_call_rx);
+    let bundler = Relay::new(config.bundler_url.clone(), None);
+    let paymaster = config.paymaster_url.clone().map(|paymaster_url| Relay::new(paymaster_url, None));
+
+    loop {
+        let ops_call = match ops_call_rx.recv().await {
+            Ok(message) => message.inner,
+            Err(RecvError::Lagged(lag)) => {
+                error!("Ops call channel lagged: {}", lag);
+                continue;
+            }
+            Err(RecvError::Closed) => return Err(eyre!("OPS_CALL_CHANNEL_CLOSED")),
+        };
+        if !config.is_allowed(&ops_call) {
+            error!(label = ops_call.label, to = %ops_call.to, data = %ops_call.data, "Ops call not allowed");
+            continue;
+        }
+
+        let user_operation_hash = match send_user_operation(&client, &config, &signers, &bundler, paymaster.as_ref(), &ops_call).await {
+            Ok(user_operation_hash) => user_operation_hash,
+            Err(error) => {
+                error!(%error, label = ops_call.label, to = %ops_call.to, "User operation failed");
+                continue;
+            }
+        };
+        match wait_receipt(&bundler, user_operation_hash, config.receipt_timeout).await {
+            Ok(receipt) if receipt.success => {
+                info!(label = ops_call.label, %user_operation_hash, tx_hash = %receipt.receipt.transaction_hash, "Ops call executed")
+            }
+            Ok(receipt) => {
+                error!(
+                    label = ops_call.label,
+                    %user_operation_hash,
+                    tx_hash = %receipt.receipt.transaction_hash,
+                    reason = receipt.reason.unwrap_or_default(),
+                    "Ops call reverted"
+                )
+            }
+            Err(error) => warn!(%error, label = ops_call.label, %user_operation_hash, "Ops call not included"),
+        }
+    }
+}
+
+/// Venue of low urgency operational calls, e.g. sweeps, approvals and config transactions. The calls are executed by a
+/// smart account through an ERC-4337 bundler with the gas sponsored by a paymaster, so the nonces of the signers sending
+/// the swaps are not used by maintenance traffic. The first signer owns the account. Only calls of the allowed targets
+/// and selectors are sent.
+#[derive(Accessor, Consumer)]
+pub struct UserOperationBroadcastActor<P> {
+    client: P,
+    config: UserOperationConfig,
+    #[accessor]
+    signers: Option<SharedState<TxSigners>>,
+    #[consumer]
+    ops_call_rx: Option<Broadcaster<MessageOpsCall>>,
+}
+
+impl<P> UserOperationBroadcastActor<P>
+where
+    P: Provider<Ethereum> + Send + Sync + Clone + 'static,
+{
+    pub fn new(client: P, config: UserOperationConfig) -> UserOperationBroadcastActor<P> {
+        UserOperationBroadcastActor { client, config, signers: None, ops_call_rx: None }
+    }
+
+    pub fn on_bc(self, bc: &Blockchain) -> Self {
+        Self { ops_call_rx: Some(bc.ops_call_channel()), ..self }
+    }
+
+    pub fn with_signers(self, signers: SharedState<TxSigners>) -> Self {
+        Self { signers: Some(signers), ..self }
+    }
+}
+
+impl<P> Actor for UserOperationBroadcastActor<P>
+where
+    P: Provider<Ethereum> + Send + Sync + Clone + 'static,
+{
+    fn start(&self) -> ActorResult {
+        let task = tokio::task::spawn(user_operation_worker(
+            self.client.clone(),
+            self.config.clone(),
+            self.signers.clone().ok_or(eyre!("SIGNERS_NOT_SET"))?,
+            self.ops_call_rx.clone().unwrap(),
+        ));
+        Ok(vec![task])
+    }
+
+    fn name(&self) -> &'static str {
+        "UserOperationBroadcastActor"
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloy_primitives::PrimitiveSignature;
+    use alloy_provider::ProviderBuilder;
+    use axum::extract::State;
+    use axum::routing::post;
+    use axum::{Json, Router};
+    use loom_defi_abi::erc4337::ENTRY_POINT_V07;
+    use loom_types_entities::LoomTxSigner;
+    use serde_json::{json, Value};
+    use std::sync::{Arc, Mutex};
+
+    const APPROVE_SELECTOR: [u8; 4] = hex!("095ea7b3");
+    const TRANSFER_SELECTOR: [u8; 4] = hex!("a9059cbb");
+
+    /// Node, bundler and paymaster-less rpc answering with fixed values, the requests are recorded
+    async fn mock_rpc(State(requests): State<Arc<Mutex<Vec<Value>>>>, Json(request): Json<Value>) -> Json<Value> {
+        let result = match request["method"].as_str().unwrap_or_default() {
+            // nonce of the account
+            "eth_call" => json!(format!("0x{:064x}", 7)),
+            "eth_feeHistory" => {
+                let fee = "0x3b9aca00";
+                json!({ "oldestBlock": "0x1", "baseFeePerGas": [fee, fee], "gasUsedRatio": [0.5], "reward": [[fee]] })
+            }
+            "eth_estimateUserOperationGas" => {
+                json!({ "preVerificationGas": "0xafc8", "verificationGasLimit": "0x186a0", "callGasLimit": "0xc350" })
+            }
+            "eth_sendUserOperation" => json!(B256::repeat_byte(0x11)),
+            "eth_getUserOperationReceipt" => {
+                json!({ "success": true, "reason": null, "receipt": { "transactionHash": B256::repeat_byte(0x22) } })
+            }
+            _ => Value::Null,
+        };
+        let response = json!({ "jsonrpc": "2.0", "id": request["id"], "result": result });
+        requests.lock().unwrap().push(request);
+        Json(response)
+    }
+
+    fn requests_of(requests: &Arc<Mutex<Vec<Value>>>, method: &str) -> Vec<Value> {
+        requests.lock().unwrap().iter().filter(|request| request["method"] == method).cloned().collect()
+    }
+
+    #[tokio::test]
+    async fn test_user_operation_worker() -> Result<()> {
+        let requests: Arc<Mutex<Vec<Value>>> = Arc::new(Mutex::new(Vec::new()));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let url: Url = format!("http://{}", listener.local_addr()?).parse()?;
+        let router = Router::new().route("/", post(mock_rpc)).with_state(requests.clone());
+        tokio::spawn(async move { axum::serve(listener, router).await });
+
+        let token = Address::repeat_byte(0x33);
+        let config = UserOperationConfig {
+            chain_id: 1,
+            entry_point: ENTRY_POINT_V07,
+            account: Address::repeat_byte(0x44),
+            bundler_url: url.clone(),
+            paymaster_url: None,
+            receipt_timeout: Duration::from_secs(10),
+            allowed_calls: vec![OpsCallTarget { to: token, selectors: vec![APPROVE_SELECTOR.into()] }],
+        };
+        let mut signers = TxSigners::new();
+        let owner = signers.add_testkey();
+        let ops_call_channel: Broadcaster<MessageOpsCall> = Broadcaster::new(10);
+
+        let mut actor = UserOperationBroadcastActor::new(ProviderBuilder::new().on_http(url), config);
+        actor.access(SharedState::new(signers)).consume(ops_call_channel.clone()).start()?;
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        // a call of a selector not allowed, a plain transfer of value and an allowed approval
+        let transfer = [TRANSFER_SELECTOR.as_slice(), &[0u8; 64]].concat();
+        let approve = [APPROVE_SELECTOR.as_slice(), &[0u8; 64]].concat();
+        ops_call_channel.send(MessageOpsCall::new(OpsCall::new(token, U256::ZERO, transfer.into(), "sweep")))?;
+        ops_call_channel.send(MessageOpsCall::new(OpsCall::new(token, U256::from(1), Bytes::new(), "value")))?;
+        ops_call_channel.send(MessageOpsCall::new(OpsCall::new(token, U256::ZERO, approve.clone().into(), "approve")))?;
+
+        // the approval is sent and its receipt polled
+        let start_time = Instant::now();
+        while requests_of(&requests, "eth_getUserOperationReceipt").is_empty() {
+            assert!(start_time.elapsed() < Duration::from_secs(10), "receipt not polled");
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        assert_eq!(requests_of(&requests, "eth_getUserOperationReceipt")[0]["params"][0], json!(B256::repeat_byte(0x11)));
+
+        let sent = requests_of(&requests, "eth_sendUserOperation");
+        assert_eq!(sent.len(), 1);
+        let user_operation: UserOperation = serde_json::from_value(sent[0]["params"][0].clone())?;
+        assert_eq!(user_operation.nonce, U256::from(7));
+        assert_eq!(user_operation.call_gas_limit, U256::from(0xc350));
+        let execute = ISimpleAccount::executeCall::abi_decode(&user_operation.call_data, true)?;
+        assert_eq!((execute.dest, execute.func), (token, Bytes::from(approve)));
+        // signed by the owner
+        let signature = PrimitiveSignature::try_from(user_operation.signature.as_ref())?;
+        let hash = user_operation.hash(ENTRY_POINT_V07, 1);
+        assert_eq!(signature.recover_address_from_msg(hash)?, owner.address());
+        Ok(())
+    }
+
+    #[test]
+    fn test_user_operation_hash() -> Result<()> {
+        let user_operation = UserOperation {
+            sender: Address::repeat_byte(0x11),
+            nonce: U256::from(3),
+            call_data: Bytes::from(vec![0xb6, 0x1d, 0x27, 0xf6]),
+            call_gas_limit: U256::from(50_000),
+            verification_gas_limit: U256::from(100_000),
+            pre_verification_gas: U256::from(45_000),
+            max_fee_per_gas: U256::from(20_000_000_000u64),
+            max_priority_fee_per_gas: U256::from(1_000_000_000u64),
+            paymaster: Some(Address::repeat_byte(0x22)),
+            paymaster_verification_gas_limit: Some(U256::from(60_000)),
+            paymaster_post_op_gas_limit: Some(U256::from(10)),
+            paymaster_data: Some(Bytes::from(vec![0xaa, 0xbb])),
+            signature: Bytes::new(),
+        };
+
+        let paymaster_and_data = user_operation.paymaster_and_data();
+        assert_eq!(paymaster_and_data.len(), 20 + 16 + 16 + 2);
+        assert_eq!(&paymaster_and_data[..20], Address::repeat_byte(0x22).as_slice());
+        assert_eq!(U256::from_be_slice(&paymaster_and_data[20..36]), U256::from(60_000));
+        assert_eq!(U256::from_be_slice(&paymaster_and_data[36..52]), U256::from(10));
+        assert_eq!(UserOperation::default().paymaster_and_data(), Bytes::new());
+
+        let hash = user_operation.hash(ENTRY_POINT_V07, 1);
+        assert_ne!(hash, user_operation.hash(ENTRY_POINT_V07, 10));
+        // the signature is not hashed
+        assert_eq!(hash, UserOperation { signature: Bytes::from(DUMMY_SIGNATURE), ..user_operation.clone() }.hash(ENTRY_POINT_V07, 1));
+
+        let mut signers = TxSigners::new();
+        let owner = signers.add_testkey();
+        let signature = owner.sign_hash_sync(&eip191_hash_message(hash))?;
+        assert_eq!(signature.recover_address_from_msg(hash)?, owner.address());
+        Ok(())
+    }
+}
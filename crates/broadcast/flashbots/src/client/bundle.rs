@@ -87,6 +87,9 @@ pub struct BundleRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(rename = "baseFee")]
     simulation_basefee: Option<u64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    replacement_uuid: Option<String>,
 }
 
 pub fn serialize_txs<S>(txs: &[BundleTransaction], s: S) -> Result<S::Ok, S::Error>
@@ -187,6 +190,17 @@ impl BundleRequest {
 
      */
 
+    /// Get the replacement UUID (if any).
+    pub fn replacement_uuid(&self) -> Option<&String> {
+        self.replacement_uuid.as_ref()
+    }
+
+    /// Set the UUID relays use to deduplicate the bundle and to replace it by a later bundle with the same UUID.
+    pub fn set_replacement_uuid(mut self, uuid: String) -> Self {
+        self.replacement_uuid = Some(uuid);
+        self
+    }
+
     /// Get the target block (if any).
     pub fn target_block(&self) -> Option<U64> {
         self.target_block
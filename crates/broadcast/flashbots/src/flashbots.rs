@@ -2,16 +2,29 @@ use crate::client::{
     make_signed_body, BundleRequest, BundleTransaction, FlashbotsMiddleware, FlashbotsMiddlewareError, RelayConfig, SendBundleResponseType,
     SimulatedBundle,
 };
+use crate::ledger::{replacement_uuid, submission_key, InFlightSubmission, SubmissionLedger};
 use alloy_network::Ethereum;
-use alloy_primitives::{TxHash, U64};
+use alloy_primitives::{Address, Bytes, TxHash, U256, U64};
 use alloy_provider::Provider;
 use alloy_signer_local::PrivateKeySigner;
 use eyre::{eyre, Result};
+use serde::Serialize;
+use std::collections::HashSet;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
 use tracing::{debug, error, info};
 use url::Url;
 
+const RETRY_DELAY: Duration = Duration::from_millis(50);
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CancelBundleRequest {
+    replacement_uuid: String,
+}
+
 #[derive(Clone)]
 pub struct FlashbotsClient<T> {
     pub flashbots_middleware: FlashbotsMiddleware<T>,
@@ -83,6 +96,19 @@ where
         }
     }
 
+    pub async fn send_signed_cancel(&self, body: String, signature: String) -> Result<()> {
+        match self.flashbots_middleware.relay().serialized_request::<serde_json::Value>(body, Some(signature)).await {
+            Ok(_resp) => {
+                debug!("Bundle cancelled at : {}", self.name);
+                Ok(())
+            }
+            Err(error) => {
+                error!("{} {}", self.name, error.to_string());
+                Err(eyre!("FLASHBOTS_RELAY_ERROR"))
+            }
+        }
+    }
+
     pub async fn send_signed_body(&self, body: String, signature: String) -> Result<()> {
         match self.flashbots_middleware.relay().serialized_request::<SendBundleResponseType>(body, Some(signature)).await {
             Ok(_resp) => {
@@ -103,6 +129,8 @@ pub struct Flashbots<P> {
     provider: P,
     simulation_client: FlashbotsClient<P>,
     clients: Vec<Arc<FlashbotsClient<P>>>,
+    retries: u32,
+    ledger: Option<Arc<Mutex<SubmissionLedger>>>,
 }
 
 impl<P> Flashbots<P>
@@ -113,7 +141,22 @@ where
         let signer = signer.unwrap_or(PrivateKeySigner::random());
         let simulation_client = FlashbotsClient::new(provider.clone(), simulation_endpoint);
 
-        Flashbots { req_id: AtomicU64::new(0), signer, provider, clients: vec![], simulation_client }
+        Flashbots { req_id: AtomicU64::new(0), signer, provider, clients: vec![], simulation_client, retries: 0, ledger: None }
+    }
+
+    /// Node provider the relay clients were created with
+    pub fn provider(&self) -> &P {
+        &self.provider
+    }
+
+    /// Number of times a failed send of a bundle to a relay is retried
+    pub fn with_retries(self, retries: u32) -> Self {
+        Self { retries, ..self }
+    }
+
+    /// Track in flight bundles in the ledger, see [`Flashbots::broadcast_bundle`]
+    pub fn with_ledger(self, ledger: SubmissionLedger) -> Self {
+        Self { ledger: Some(Arc::new(Mutex::new(ledger))), ..self }
     }
 
     pub fn with_default_relays(self) -> Self {
@@ -237,6 +280,93 @@ where
 
         Ok(())
     }
+
+    /// Broadcast the bundle with an idempotency key. Failed sends are retried with the same signed body, relays that
+    /// accepted the bundle or are sending it are skipped and bundles with a transaction conflicting with an in flight transaction
+    /// of the same signer nonce are rejected unless their `profit` in ETH is higher. `nonces` are the signer, nonce and hash
+    /// of the own transactions.
+    pub async fn broadcast_bundle(
+        &self,
+        txs: Vec<Bytes>,
+        target_block: u64,
+        nonces: Vec<(Address, u64, TxHash)>,
+        profit: U256,
+    ) -> Result<()> {
+        let submission = InFlightSubmission::new(&txs, target_block, nonces).with_profit(profit);
+        let key = submission.key;
+        let endpoints: Vec<String> = self.clients.iter().map(|client| client.name.clone()).collect();
+        let endpoints: HashSet<String> = match &self.ledger {
+            Some(ledger) => ledger.lock().await.reserve(submission, &endpoints)?,
+            None => endpoints,
+        }
+        .into_iter()
+        .collect();
+
+        let mut bundle = BundleRequest::new().set_target_block(U64::from(target_block)).set_replacement_uuid(replacement_uuid(&key));
+        for tx in txs.into_iter() {
+            bundle = bundle.push_transaction(tx);
+        }
+
+        let req_id = self.req_id.fetch_add(1, Ordering::SeqCst) + 1;
+        let (body, signature) = make_signed_body(req_id, "eth_sendBundle", bundle, &self.signer)?;
+
+        for client in self.clients.iter().filter(|client| endpoints.contains(&client.name)) {
+            let client_clone = client.clone();
+            let body_clone = body.clone();
+            let signature_clone = signature.clone();
+            let ledger = self.ledger.clone();
+            let retries = self.retries;
+
+            tokio::task::spawn(async move {
+                for attempt in 0..=retries {
+                    match client_clone.send_signed_body(body_clone.clone(), signature_clone.clone()).await {
+                        Ok(_) => {
+                            debug!(%key, attempt, "Flashbots bundle broadcast successfully {}", client_clone.name);
+                            if let Some(ledger) = &ledger {
+                                ledger.lock().await.mark_sent(&key, &client_clone.name);
+                            }
+                            return;
+                        }
+                        Err(x) if attempt < retries => {
+                            debug!(%key, attempt, "Retrying broadcast to {} : {}", client_clone.name, x.to_string());
+                            tokio::time::sleep(RETRY_DELAY * (attempt + 1)).await;
+                        }
+                        Err(x) => {
+                            error!("Broadcasting error to {} : {}", client_clone.name, x.to_string());
+                            if let Some(ledger) = &ledger {
+                                ledger.lock().await.mark_failed(&key, &client_clone.name);
+                            }
+                            return;
+                        }
+                    }
+                }
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Cancel a bundle broadcast by `broadcast_bundle` at the relays by its replacement uuid. A builder that already
+    /// received the bundle from a relay may still include it
+    pub async fn cancel_bundle(&self, txs: &[Bytes], target_block: u64) -> Result<()> {
+        let key = submission_key(txs, target_block);
+        let req_id = self.req_id.fetch_add(1, Ordering::SeqCst) + 1;
+        let (body, signature) =
+            make_signed_body(req_id, "eth_cancelBundle", CancelBundleRequest { replacement_uuid: replacement_uuid(&key) }, &self.signer)?;
+
+        for client in self.clients.iter() {
+            let client_clone = client.clone();
+            let body_clone = body.clone();
+            let signature_clone = signature.clone();
+            tokio::task::spawn(async move {
+                if let Err(error) = client_clone.send_signed_cancel(body_clone, signature_clone).await {
+                    debug!(%key, "Cancelling bundle at {} : {}", client_clone.name, error);
+                }
+            });
+        }
+
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,289 @@
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::thread::JoinHandle;
+
+use alloy_primitives::{keccak256, Address, Bytes, TxHash, B256, U256};
+use eyre::{eyre, Result};
+use serde::{Deserialize, Serialize};
+use tracing::error;
+
+/// Idempotency key of a bundle submission, the same transactions for the same target block have the same key
+pub fn submission_key(txs: &[Bytes], target_block: u64) -> B256 {
+    let mut data = target_block.to_be_bytes().to_vec();
+    for tx in txs {
+        data.extend_from_slice(keccak256(tx).as_slice());
+    }
+    keccak256(data)
+}
+
+/// Key formatted as the UUID relays use to deduplicate and replace bundles
+pub fn replacement_uuid(key: &B256) -> String {
+    let hex = alloy_primitives::hex::encode(&key[..16]);
+    format!("{}-{}-{}-{}-{}", &hex[0..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..32])
+}
+
+/// Bundle submitted for a target block that is not produced yet
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InFlightSubmission {
+    pub key: B256,
+    pub target_block: u64,
+    pub tx_hashes: Vec<TxHash>,
+    /// Signer, nonce and hash of the own transactions of the bundle
+    pub nonces: Vec<(Address, u64, TxHash)>,
+    /// Endpoints that accepted the bundle
+    pub endpoints: BTreeSet<String>,
+    /// Expected profit of the bundle in ETH, a bundle claiming the same nonces replaces it only with a higher profit
+    #[serde(default)]
+    pub profit: U256,
+    /// Endpoints the bundle is being sent to, not persisted to resend after a restart
+    #[serde(skip)]
+    pub pending: BTreeSet<String>,
+}
+
+impl InFlightSubmission {
+    pub fn new(txs: &[Bytes], target_block: u64, nonces: Vec<(Address, u64, TxHash)>) -> Self {
+        Self {
+            key: submission_key(txs, target_block),
+            target_block,
+            tx_hashes: txs.iter().map(keccak256).collect(),
+            nonces,
+            endpoints: BTreeSet::new(),
+            profit: U256::ZERO,
+            pending: BTreeSet::new(),
+        }
+    }
+
+    pub fn with_profit(self, profit: U256) -> Self {
+        Self { profit, ..self }
+    }
+
+    // a different transaction of the submission uses one of the signer nonces
+    fn conflicts_with(&self, other: &InFlightSubmission) -> bool {
+        self.nonces.iter().any(|(signer, nonce, tx_hash)| other.nonces.iter().any(|(s, n, h)| s == signer && n == nonce && h != tx_hash))
+    }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct LedgerEntries {
+    submissions: Vec<InFlightSubmission>,
+}
+
+fn write_entries(path: &Path, entries: &LedgerEntries) -> Result<()> {
+    let content = serde_json::to_string(entries)?;
+    // replaced atomically to survive crashes while writing
+    let tmp_path = path.with_extension("tmp");
+    std::fs::write(&tmp_path, content)?;
+    std::fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+/// Writes the ledger file on a thread of its own, entries queued while a write is running are coalesced into the
+/// latest one. Queued entries are written before the writer is dropped.
+#[derive(Debug)]
+struct LedgerWriter {
+    entries_tx: Option<mpsc::Sender<LedgerEntries>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl LedgerWriter {
+    fn spawn(path: PathBuf) -> Self {
+        let (entries_tx, entries_rx) = mpsc::channel::<LedgerEntries>();
+        let handle = std::thread::spawn(move || {
+            while let Ok(mut entries) = entries_rx.recv() {
+                while let Ok(latest) = entries_rx.try_recv() {
+                    entries = latest;
+                }
+                if let Err(error) = write_entries(&path, &entries) {
+                    error!(%error, path = %path.display(), "Failed to save submission ledger");
+                }
+            }
+        });
+        Self { entries_tx: Some(entries_tx), handle: Some(handle) }
+    }
+
+    fn send(&self, entries: LedgerEntries) {
+        if let Some(entries_tx) = &self.entries_tx {
+            if entries_tx.send(entries).is_err() {
+                error!("Submission ledger writer stopped");
+            }
+        }
+    }
+}
+
+impl Drop for LedgerWriter {
+    fn drop(&mut self) {
+        self.entries_tx.take();
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// In flight bundle submissions by idempotency key. Repeated submissions are only sent to the endpoints that did
+/// not accept it and are not sending it yet. A submission using a signer nonce already claimed by a different in flight
+/// transaction replaces the claiming submissions if its profit is higher, e.g. an improved or merged bundle, and is
+/// rejected otherwise. The ledger is written to the file in the background after every change and reloaded on restart.
+#[derive(Debug, Default)]
+pub struct SubmissionLedger {
+    writer: Option<LedgerWriter>,
+    submissions: HashMap<B256, InFlightSubmission>,
+}
+
+impl SubmissionLedger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ledger persisted to the file, submissions in flight before a restart are loaded
+    pub fn load(path: PathBuf) -> Result<Self> {
+        let entries: LedgerEntries = match std::fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content)?,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => LedgerEntries::default(),
+            Err(error) => return Err(error.into()),
+        };
+        let submissions = entries.submissions.into_iter().map(|submission| (submission.key, submission)).collect();
+        Ok(Self { writer: Some(LedgerWriter::spawn(path)), submissions })
+    }
+
+    pub fn len(&self) -> usize {
+        self.submissions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.submissions.is_empty()
+    }
+
+    pub fn get(&self, key: &B256) -> Option<&InFlightSubmission> {
+        self.submissions.get(key)
+    }
+
+    /// Register the submission and return the endpoints it should be sent to, they are pending until marked sent
+    /// or failed. Fails if a different in flight transaction uses one of its signer nonces in a submission with the same
+    /// or a higher profit, submissions with a lower profit are replaced.
+    pub fn reserve(&mut self, submission: InFlightSubmission, endpoints: &[String]) -> Result<Vec<String>> {
+        // target blocks before the target block of the new submission are produced
+        self.prune(submission.target_block.saturating_sub(1));
+
+        if let Some(existing) = self.submissions.get_mut(&submission.key) {
+            let endpoints: Vec<String> = endpoints
+                .iter()
+                .filter(|endpoint| !existing.endpoints.contains(*endpoint) && !existing.pending.contains(*endpoint))
+                .cloned()
+                .collect();
+            existing.pending.extend(endpoints.iter().cloned());
+            return Ok(endpoints);
+        }
+
+        // the same transaction in another bundle does not conflict
+        let conflicts: Vec<B256> =
+            self.submissions.values().filter(|existing| existing.conflicts_with(&submission)).map(|existing| existing.key).collect();
+        for key in conflicts.iter() {
+            let existing = &self.submissions[key];
+            if existing.profit >= submission.profit {
+                return Err(eyre!(
+                    "NONCE_CONFLICT: in flight for block {} with profit {} not below {}",
+                    existing.target_block,
+                    existing.profit,
+                    submission.profit
+                ));
+            }
+        }
+        for key in conflicts {
+            self.submissions.remove(&key);
+        }
+
+        let pending = endpoints.iter().cloned().collect();
+        self.submissions.insert(submission.key, InFlightSubmission { pending, ..submission });
+        self.save();
+        Ok(endpoints.to_vec())
+    }
+
+    pub fn mark_sent(&mut self, key: &B256, endpoint: &str) {
+        if let Some(submission) = self.submissions.get_mut(key) {
+            submission.pending.remove(endpoint);
+            if submission.endpoints.insert(endpoint.to_string()) {
+                self.save();
+            }
+        }
+    }
+
+    /// The endpoint is tried again by the next submission with the same key
+    pub fn mark_failed(&mut self, key: &B256, endpoint: &str) {
+        if let Some(submission) = self.submissions.get_mut(key) {
+            submission.pending.remove(endpoint);
+        }
+    }
+
+    /// Remove submissions targeting blocks up to `block_number`
+    pub fn prune(&mut self, block_number: u64) {
+        let len = self.submissions.len();
+        self.submissions.retain(|_, submission| submission.target_block > block_number);
+        if self.submissions.len() != len {
+            self.save();
+        }
+    }
+
+    fn save(&self) {
+        if let Some(writer) = &self.writer {
+            writer.send(LedgerEntries { submissions: self.submissions.values().cloned().collect() });
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_submission_ledger() -> Result<()> {
+        let signer = Address::repeat_byte(1);
+        let stuffing_tx = Bytes::from(vec![0, 0, 0]);
+        let tx_a = Bytes::from(vec![1, 2, 3]);
+        let tx_b = Bytes::from(vec![4, 5, 6]);
+        let nonce_a = vec![(signer, 5, keccak256(&tx_a))];
+        let nonce_b = vec![(signer, 5, keccak256(&tx_b))];
+        let endpoints = vec!["relay0".to_string(), "relay1".to_string()];
+
+        let path = std::env::temp_dir().join(format!("loom_ledger_{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let mut ledger = SubmissionLedger::load(path.clone())?;
+
+        let submission = InFlightSubmission::new(&[tx_a.clone()], 100, nonce_a.clone());
+        let key = submission.key;
+        assert_eq!(ledger.reserve(submission.clone(), &endpoints)?, endpoints);
+        // still being sent
+        assert!(ledger.reserve(submission.clone(), &endpoints)?.is_empty());
+        ledger.mark_sent(&key, "relay0");
+        ledger.mark_failed(&key, "relay1");
+        assert_eq!(ledger.reserve(submission.clone(), &endpoints)?, vec!["relay1".to_string()]);
+
+        // resend after restart skips the endpoints that accepted the bundle, the file is written when the ledger is dropped
+        drop(ledger);
+        let mut ledger = SubmissionLedger::load(path.clone())?;
+        assert_eq!(ledger.reserve(submission, &endpoints)?, vec!["relay1".to_string()]);
+
+        // the same transaction behind the stuffing transaction does not conflict, a different one with the same nonce does
+        assert!(ledger.reserve(InFlightSubmission::new(&[stuffing_tx.clone(), tx_a], 100, nonce_a), &endpoints).is_ok());
+        assert!(ledger.reserve(InFlightSubmission::new(&[stuffing_tx, tx_b.clone()], 100, nonce_b.clone()), &endpoints).is_err());
+        assert_eq!(ledger.len(), 2);
+
+        // an improved bundle with a higher profit replaces the bundles claiming the nonce
+        let improved = InFlightSubmission::new(&[tx_b.clone()], 100, nonce_b.clone()).with_profit(U256::from(1));
+        assert!(ledger.reserve(improved.clone(), &endpoints).is_ok());
+        assert_eq!(ledger.len(), 1);
+        assert!(ledger.get(&improved.key).is_some());
+        let tx_c = Bytes::from(vec![7, 8, 9]);
+        let nonce_c = vec![(signer, 5, keccak256(&tx_c))];
+        assert!(ledger.reserve(InFlightSubmission::new(&[tx_c], 100, nonce_c).with_profit(U256::from(1)), &endpoints).is_err());
+
+        // nonce is free after the target block is produced
+        assert!(ledger.reserve(InFlightSubmission::new(&[tx_b], 101, nonce_b), &endpoints).is_ok());
+        assert_eq!(ledger.len(), 1);
+
+        assert_eq!(replacement_uuid(&key).len(), 36);
+        drop(ledger);
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+}
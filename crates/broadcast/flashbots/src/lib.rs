@@ -1,4 +1,6 @@
 pub use flashbots::{Flashbots, FlashbotsClient};
+pub use ledger::{replacement_uuid, submission_key, InFlightSubmission, SubmissionLedger};
 
 pub mod client;
 mod flashbots;
+mod ledger;
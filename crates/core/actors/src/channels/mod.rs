@@ -1,5 +1,7 @@
 mod broadcaster;
 mod multiproducer;
+mod snapshot;
 
 pub use broadcaster::*;
 pub use multiproducer::*;
+pub use snapshot::*;
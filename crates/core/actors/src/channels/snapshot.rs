@@ -0,0 +1,51 @@
+use std::sync::Arc;
+
+use tokio::sync::watch;
+
+/// Latest value published by a writer. Readers share the published value and never wait for the writer preparing the
+/// next one, subscribers are notified when a new value is published
+pub struct Snapshot<T> {
+    sender: Arc<watch::Sender<Arc<T>>>,
+}
+
+impl<T> Snapshot<T> {
+    pub fn new(value: T) -> Self {
+        let (sender, _) = watch::channel(Arc::new(value));
+        Self { sender: Arc::new(sender) }
+    }
+
+    /// Replace the shared value, readers holding the previous value keep it
+    pub fn publish(&self, value: T) {
+        self.sender.send_replace(Arc::new(value));
+    }
+
+    pub fn latest(&self) -> Arc<T> {
+        self.sender.borrow().clone()
+    }
+
+    pub fn subscribe(&self) -> watch::Receiver<Arc<T>> {
+        self.sender.subscribe()
+    }
+}
+
+impl<T> Clone for Snapshot<T> {
+    fn clone(&self) -> Self {
+        Self { sender: self.sender.clone() }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_publish() {
+        let snapshot = Snapshot::new(1);
+        let reader = snapshot.clone();
+        let previous = reader.latest();
+
+        snapshot.publish(2);
+        assert_eq!(*previous, 1);
+        assert_eq!(*reader.latest(), 2);
+    }
+}
@@ -1,6 +1,6 @@
 pub use actor::{Accessor, Actor, ActorResult, Consumer, Producer, WorkerResult};
 pub use actor_manager::ActorsManager;
-pub use channels::{Broadcaster, MultiProducer};
+pub use channels::{Broadcaster, MultiProducer, Snapshot};
 pub use shared_state::SharedState;
 
 mod actor;
@@ -8,7 +8,8 @@ use loom_core_actors_macros::{Accessor, Consumer, Producer};
 use loom_core_blockchain::{Blockchain, BlockchainState};
 use loom_evm_db::DatabaseLoomExt;
 use loom_node_debug_provider::DebugProviderExt;
-use loom_types_blockchain::ChainParameters;
+use loom_types_blockchain::{ChainParameters, TouchedAddresses};
+use loom_types_entities::state_proof::ProvenBlock;
 use loom_types_entities::{BlockHistory, BlockHistoryManager, BlockHistoryState, LatestBlock, MarketState};
 use loom_types_events::{MarketEvents, MessageBlock, MessageBlockHeader, MessageBlockLogs, MessageBlockStateUpdate};
 use revm::{Database, DatabaseCommit, DatabaseRef};
@@ -301,6 +302,8 @@ where
                     market_state_guard.state_db = updated_db.clone();
                     market_state_guard.block_hash = msg_block_hash;
                     market_state_guard.block_number = latest_block_number;
+                    market_state_guard.proven_block = latest_block_guard.block_header.as_ref().map(ProvenBlock::from_header);
+                    market_state_guard.set_updated(&TouchedAddresses::from_state_update(&msg.state_update), latest_block_number);
 
 
                     run_sync!(market_events_tx.send(MarketEvents::BlockStateUpdate{ block_hash : msg_block_hash} ));
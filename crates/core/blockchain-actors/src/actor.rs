@@ -13,13 +13,15 @@ use loom_core_blockchain::{Blockchain, BlockchainState, Strategy};
 use loom_core_mempool::MempoolActor;
 use loom_core_router::SwapRouterActor;
 use loom_defi_address_book::TokenAddressEth;
-use loom_defi_health_monitor::{MetricsRecorderActor, PoolHealthMonitorActor, StuffingTxMonitorActor};
+use loom_defi_health_monitor::{BlockStatsActor, MetricsRecorderActor, PoolHealthMonitorActor, StuffingTxMonitorActor};
 use loom_defi_market::{
-    HistoryPoolLoaderOneShotActor, NewPoolLoaderActor, PoolLoaderActor, ProtocolPoolLoaderOneShotActor, RequiredPoolLoaderActor,
+    CuratedPoolLoaderOneShotActor, CurveParamsMonitorActor, DbPoolLoaderActor, HistoryPoolLoaderOneShotActor, NewPoolLoaderActor,
+    PoolCreationListenerActor, PoolEventsPublisherActor, PoolLoaderActor, PoolStateRefresherActor, ProtocolPoolLoaderOneShotActor,
+    ProxyMonitorActor, RequiredPoolLoaderActor, TickWordLoaderActor,
 };
 use loom_defi_pools::{PoolLoadersBuilder, PoolsLoadingConfig};
 use loom_defi_preloader::MarketStatePreloadedOneShotActor;
-use loom_defi_price::PriceActor;
+use loom_defi_price::{PriceActor, PriceGraphActor};
 use loom_evm_db::DatabaseLoomExt;
 use loom_evm_utils::NWETH;
 use loom_execution_estimator::{EvmEstimatorActor, GethEstimatorActor};
@@ -32,6 +34,7 @@ use loom_node_debug_provider::DebugProviderExt;
 use loom_node_grpc::NodeExExGrpcActor;
 use loom_node_json_rpc::{NodeBlockActor, NodeMempoolActor, WaitForNodeSyncOneShotBlockingActor};
 use loom_rpc_handler::WebServerActor;
+use loom_rpc_state::ManualSwapConfig;
 use loom_storage_db::DbPool;
 use loom_strategy_backrun::{
     BackrunConfig, BlockStateChangeProcessorActor, PendingTxStateChangeProcessorActor, StateChangeArbSearcherActor,
@@ -82,6 +85,9 @@ where
         strategy: Strategy<DB>,
         relays: Vec<RelayConfig>,
     ) -> Self {
+        // flash loan lender and periphery contracts of the chain
+        let mut encoder = encoder;
+        encoder.set_chain_preset(bc.chain_preset());
         Self {
             provider,
             bc,
@@ -172,6 +178,8 @@ where
 
     /// Initializes encoder and start encoder actor
     pub fn with_swap_encoder(&mut self, swap_encoder: E) -> Result<&mut Self> {
+        let mut swap_encoder = swap_encoder;
+        swap_encoder.set_chain_preset(self.bc.chain_preset());
         self.mutlicaller_address = Some(swap_encoder.address());
         self.encoder = Some(swap_encoder);
         self.actor_manager.start(SwapRouterActor::<DB>::new().with_signers(self.signers.clone()).on_bc(&self.bc, &self.strategy))?;
@@ -251,6 +259,12 @@ where
         Ok(self)
     }
 
+    /// Starts token valuation through the deepest market pools on every block
+    pub fn with_price_graph(&mut self) -> Result<&mut Self> {
+        self.actor_manager.start(PriceGraphActor::new().on_bc(&self.bc, &self.state))?;
+        Ok(self)
+    }
+
     /// Starts receiving blocks events through RPC
     pub fn with_block_events(&mut self, config: NodeBlockActorConfig) -> Result<&mut Self> {
         self.actor_manager.start(NodeBlockActor::new(self.provider.clone(), config).on_bc(&self.bc))?;
@@ -342,6 +356,38 @@ where
         Ok(self)
     }
 
+    /// Start pool loading as soon as registered factories emit pool creation events, requires a pubsub provider
+    pub fn with_pool_creation_listener(&mut self, pools_config: PoolsLoadingConfig) -> Result<&mut Self> {
+        let pool_loaders = Arc::new(PoolLoadersBuilder::default_pool_loaders(self.provider.clone(), pools_config.clone()));
+        self.actor_manager.start(PoolCreationListenerActor::new(self.provider.clone(), pool_loaders, pools_config).on_bc(&self.bc))?;
+        Ok(self)
+    }
+
+    /// Load curated list of top pools of the chain, fetched from `url` or bundled
+    pub fn with_curated_pool_loader(&mut self, pools_config: PoolsLoadingConfig, url: Option<String>) -> Result<&mut Self> {
+        let pool_loaders = Arc::new(PoolLoadersBuilder::default_pool_loaders(self.provider.clone(), pools_config.clone()));
+        let mut actor = CuratedPoolLoaderOneShotActor::new(self.provider.clone(), pool_loaders, pools_config);
+        if let Some(url) = url {
+            actor = actor.with_url(url);
+        }
+        self.actor_manager.start(actor.on_bc(&self.bc, &self.state))?;
+        Ok(self)
+    }
+
+    /// Publish pool lifecycle events of the market to the persistence and metrics consumers
+    pub fn with_pool_events_publisher(&mut self) -> Result<&mut Self> {
+        self.actor_manager.start(PoolEventsPublisherActor::new().on_bc(&self.bc))?;
+        Ok(self)
+    }
+
+    /// Load pools persisted in the database and persist discovered pools, disable flags and path scores
+    pub fn with_db_pool_loader(&mut self, pools_config: PoolsLoadingConfig, db_pool: DbPool) -> Result<&mut Self> {
+        let pool_loaders = Arc::new(PoolLoadersBuilder::default_pool_loaders(self.provider.clone(), pools_config.clone()));
+        self.actor_manager
+            .start(DbPoolLoaderActor::new(self.provider.clone(), pool_loaders, pools_config, db_pool).on_bc(&self.bc, &self.state))?;
+        Ok(self)
+    }
+
     /// Start pool loader for last 10000 blocks
     pub fn with_pool_history_loader(&mut self, pools_config: PoolsLoadingConfig) -> Result<&mut Self> {
         let pool_loaders = Arc::new(PoolLoadersBuilder::default_pool_loaders(self.provider.clone(), pools_config));
@@ -353,6 +399,27 @@ where
     pub fn with_pool_loader(&mut self, pools_config: PoolsLoadingConfig) -> Result<&mut Self> {
         let pool_loaders = Arc::new(PoolLoadersBuilder::default_pool_loaders(self.provider.clone(), pools_config.clone()));
         self.actor_manager.start(PoolLoaderActor::new(self.provider.clone(), pool_loaders, pools_config).on_bc(&self.bc, &self.state))?;
+        // V3 pools are loaded with the ticks around the current tick only
+        self.actor_manager.start(TickWordLoaderActor::new(self.provider.clone()).on_bc(&self.bc, &self.state))?;
+        Ok(self)
+    }
+
+    /// Start monitor disabling pools of upgraded proxies
+    pub fn with_proxy_monitor(&mut self) -> Result<&mut Self> {
+        self.actor_manager.start(ProxyMonitorActor::new().on_bc(&self.bc))?;
+        Ok(self)
+    }
+
+    /// Start monitor refreshing the state of Curve pools with changed amplification or fees
+    pub fn with_curve_params_monitor(&mut self) -> Result<&mut Self> {
+        self.actor_manager.start(CurveParamsMonitorActor::new().on_bc(&self.bc))?;
+        Ok(self)
+    }
+
+    /// Start refresher of path pools not updated for `stale_blocks` blocks
+    pub fn with_pool_state_refresher(&mut self, stale_blocks: u64) -> Result<&mut Self> {
+        self.actor_manager
+            .start(PoolStateRefresherActor::new(self.provider.clone()).with_stale_blocks(stale_blocks).on_bc(&self.bc, &self.state))?;
         Ok(self)
     }
 
@@ -369,6 +436,7 @@ where
             self.with_new_pool_loader(pools_config.clone())?
                 .with_pool_history_loader(pools_config.clone())?
                 .with_curve_pool_protocol_loader(pools_config.clone())?
+                .with_curve_params_monitor()?
                 .with_pool_loader(pools_config)
         } else {
             self.with_new_pool_loader(pools_config.clone())?.with_pool_history_loader(pools_config.clone())?.with_pool_loader(pools_config)
@@ -395,7 +463,11 @@ where
     pub fn with_geth_estimator(&mut self) -> Result<&mut Self> {
         let flashbots = Flashbots::new(self.provider.clone(), "https://relay.flashbots.net", None).with_default_relays();
 
-        self.actor_manager.start(GethEstimatorActor::new(Arc::new(flashbots), self.encoder.clone().unwrap()).on_bc(&self.strategy))?;
+        self.actor_manager.start(
+            GethEstimatorActor::new(Arc::new(flashbots), self.encoder.clone().unwrap())
+                .with_chain_id(self.bc.chain_id())
+                .on_bc(&self.strategy),
+        )?;
         Ok(self)
     }
 
@@ -453,15 +525,26 @@ where
 
     /// Start backrun for pending txs
     pub fn with_backrun_mempool(&mut self, backrun_config: BackrunConfig) -> Result<&mut Self> {
+        let mempool_sampling = backrun_config.mempool_sampling().copied();
         if !self.has_state_update {
             self.actor_manager.start(StateChangeArbSearcherActor::new(backrun_config).on_bc(&self.bc, &self.strategy))?;
             self.has_state_update = true
         }
-        self.actor_manager.start(PendingTxStateChangeProcessorActor::new(self.provider.clone()).on_bc(
-            &self.bc,
-            &self.state,
-            &self.strategy,
-        ))?;
+        self.actor_manager.start(
+            PendingTxStateChangeProcessorActor::new(self.provider.clone()).with_mempool_sampling(mempool_sampling).on_bc(
+                &self.bc,
+                &self.state,
+                &self.strategy,
+            ),
+        )?;
+        Ok(self)
+    }
+
+    /// Start backrun searchers of strategy namespaces on the state updates of the block and pending txs processors
+    pub fn with_backrun_namespaces(&mut self, backrun_configs: Vec<BackrunConfig>) -> Result<&mut Self> {
+        for backrun_config in backrun_configs {
+            self.actor_manager.start(StateChangeArbSearcherActor::new(backrun_config).on_bc(&self.bc, &self.strategy))?;
+        }
         Ok(self)
     }
 
@@ -482,13 +565,32 @@ where
         Ok(self)
     }
 
-    /// Start web server
-    pub fn with_web_server<S>(&mut self, host: String, router: Router<S>, db_pool: DbPool) -> Result<&mut Self>
+    /// Start per block searcher statistics
+    pub fn with_block_stats(&mut self) -> Result<&mut Self> {
+        self.actor_manager.start(BlockStatsActor::new().on_bc(&self.bc, &self.strategy))?;
+        Ok(self)
+    }
+
+    /// Start web server, manual swaps are only accepted if enabled
+    pub fn with_web_server<S>(
+        &mut self,
+        host: String,
+        router: Router<S>,
+        db_pool: DbPool,
+        auth_token: Option<String>,
+        manual_swaps: Option<ManualSwapConfig>,
+    ) -> Result<&mut Self>
     where
         S: Clone + Send + Sync + 'static,
         Router: From<Router<S>>,
     {
-        self.actor_manager.start(WebServerActor::new(host, router, db_pool, CancellationToken::new()).on_bc(&self.bc, &self.state))?;
+        self.actor_manager.start(
+            WebServerActor::new(host, router, db_pool, CancellationToken::new())
+                .on_bc(&self.bc, &self.state)
+                .with_strategy(&self.strategy)
+                .with_auth_token(auth_token)
+                .with_manual_swaps(manual_swaps),
+        )?;
         Ok(self)
     }
 
@@ -2,14 +2,18 @@ use crate::blockchain_tokens::add_default_tokens_to_market;
 use alloy::primitives::BlockHash;
 use alloy::primitives::ChainId;
 use influxdb::WriteQuery;
-use loom_core_actors::{Broadcaster, SharedState};
+use loom_core_actors::{Broadcaster, SharedState, Snapshot};
 use loom_types_blockchain::{ChainParameters, Mempool};
 use loom_types_blockchain::{LoomDataTypes, LoomDataTypesEthereum};
-use loom_types_entities::{AccountNonceAndBalanceState, LatestBlock, Market};
+use loom_types_entities::{
+    AccountNonceAndBalanceState, ChainPreset, ExecutionProfile, LatestBlock, Market, MarketView, PoolEventRecord, SharedTipRecipients,
+    SimulationTraces, TipRecipients,
+};
 use loom_types_events::{
-    LoomTask, MarketEvents, MempoolEvents, MessageBlock, MessageBlockHeader, MessageBlockLogs, MessageBlockStateUpdate, MessageHealthEvent,
-    MessageMempoolDataUpdate, MessageTxCompose,
+    LoomTask, MarketEvents, MempoolEvents, MessageBlock, MessageBlockHeader, MessageBlockLogs, MessageBlockStateUpdate, MessageBlockStats,
+    MessageFlashblock, MessageHealthEvent, MessageMempoolDataUpdate, MessageOpsCall, MessageTxCompose,
 };
+use std::sync::{Arc, RwLock};
 use tracing::error;
 
 #[derive(Clone)]
@@ -17,22 +21,29 @@ pub struct Blockchain<LDT: LoomDataTypes + 'static = LoomDataTypesEthereum> {
     chain_id: ChainId,
     chain_parameters: ChainParameters,
     market: SharedState<Market<LDT>>,
+    market_view: Snapshot<MarketView<LDT>>,
     latest_block: SharedState<LatestBlock<LDT>>,
     mempool: SharedState<Mempool<LDT>>,
     account_nonce_and_balance: SharedState<AccountNonceAndBalanceState<LDT>>,
+    simulation_traces: SharedState<SimulationTraces>,
+    tip_recipients: SharedTipRecipients,
 
     new_block_headers_channel: Broadcaster<MessageBlockHeader<LDT>>,
     new_block_with_tx_channel: Broadcaster<MessageBlock<LDT>>,
     new_block_state_update_channel: Broadcaster<MessageBlockStateUpdate<LDT>>,
     new_block_logs_channel: Broadcaster<MessageBlockLogs<LDT>>,
     new_mempool_tx_channel: Broadcaster<MessageMempoolDataUpdate<LDT>>,
+    flashblocks_channel: Broadcaster<MessageFlashblock<LDT>>,
     market_events_channel: Broadcaster<MarketEvents<LDT>>,
+    pool_events_channel: Broadcaster<PoolEventRecord<LDT>>,
     mempool_events_channel: Broadcaster<MempoolEvents<LDT>>,
     tx_compose_channel: Broadcaster<MessageTxCompose<LDT>>,
 
     pool_health_monitor_channel: Broadcaster<MessageHealthEvent<LDT>>,
     influxdb_write_channel: Broadcaster<WriteQuery>,
     tasks_channel: Broadcaster<LoomTask>,
+    block_stats_channel: Broadcaster<MessageBlockStats>,
+    ops_call_channel: Broadcaster<MessageOpsCall>,
 }
 
 impl Blockchain<LoomDataTypesEthereum> {
@@ -43,14 +54,18 @@ impl Blockchain<LoomDataTypesEthereum> {
         let new_block_logs_channel: Broadcaster<MessageBlockLogs> = Broadcaster::new(10);
 
         let new_mempool_tx_channel: Broadcaster<MessageMempoolDataUpdate> = Broadcaster::new(5000);
+        let flashblocks_channel: Broadcaster<MessageFlashblock> = Broadcaster::new(100);
 
         let market_events_channel: Broadcaster<MarketEvents> = Broadcaster::new(100);
+        let pool_events_channel: Broadcaster<PoolEventRecord> = Broadcaster::new(1000);
         let mempool_events_channel: Broadcaster<MempoolEvents> = Broadcaster::new(2000);
         let tx_compose_channel: Broadcaster<MessageTxCompose> = Broadcaster::new(2000);
 
         let pool_health_monitor_channel: Broadcaster<MessageHealthEvent> = Broadcaster::new(1000);
         let influx_write_channel: Broadcaster<WriteQuery> = Broadcaster::new(1000);
         let tasks_channel: Broadcaster<LoomTask> = Broadcaster::new(1000);
+        let block_stats_channel: Broadcaster<MessageBlockStats> = Broadcaster::new(10);
+        let ops_call_channel: Broadcaster<MessageOpsCall> = Broadcaster::new(100);
 
         let mut market_instance = Market::default();
 
@@ -60,22 +75,29 @@ impl Blockchain<LoomDataTypesEthereum> {
 
         Blockchain {
             chain_id,
-            chain_parameters: ChainParameters::ethereum(),
+            chain_parameters: ChainParameters::for_chain_id(chain_id),
+            market_view: Snapshot::new(MarketView::new(0, market_instance.clone())),
             market: SharedState::new(market_instance),
             mempool: SharedState::new(Mempool::<LoomDataTypesEthereum>::new()),
             latest_block: SharedState::new(LatestBlock::new(0, BlockHash::ZERO)),
             account_nonce_and_balance: SharedState::new(AccountNonceAndBalanceState::new()),
+            simulation_traces: SharedState::new(SimulationTraces::default()),
+            tip_recipients: Arc::new(RwLock::new(TipRecipients::new(chain_id))),
             new_block_headers_channel,
             new_block_with_tx_channel,
             new_block_state_update_channel,
             new_block_logs_channel,
             new_mempool_tx_channel,
+            flashblocks_channel,
             market_events_channel,
+            pool_events_channel,
             mempool_events_channel,
             pool_health_monitor_channel,
             tx_compose_channel,
             influxdb_write_channel: influx_write_channel,
             tasks_channel,
+            block_stats_channel,
+            ops_call_channel,
         }
     }
 }
@@ -85,6 +107,16 @@ impl<LDT: LoomDataTypes> Blockchain<LDT> {
         self.chain_id
     }
 
+    /// How transactions reach the blocks of the chain
+    pub fn execution_profile(&self) -> ExecutionProfile {
+        ExecutionProfile::for_chain_id(self.chain_id)
+    }
+
+    /// Flash loan lender, periphery contracts and pool class encoders of the chain
+    pub fn chain_preset(&self) -> ChainPreset {
+        ChainPreset::for_chain_id(self.chain_id)
+    }
+
     pub fn chain_parameters(&self) -> ChainParameters {
         self.chain_parameters.clone()
     }
@@ -93,6 +125,11 @@ impl<LDT: LoomDataTypes> Blockchain<LDT> {
         self.market.clone()
     }
 
+    /// Copy of the market published once per block, read without taking the market lock
+    pub fn market_view(&self) -> Snapshot<MarketView<LDT>> {
+        self.market_view.clone()
+    }
+
     pub fn latest_block(&self) -> SharedState<LatestBlock<LDT>> {
         self.latest_block.clone()
     }
@@ -105,6 +142,16 @@ impl<LDT: LoomDataTypes> Blockchain<LDT> {
         self.account_nonce_and_balance.clone()
     }
 
+    /// Trace requests of simulated swaps and the captured traces
+    pub fn simulation_traces(&self) -> SharedState<SimulationTraces> {
+        self.simulation_traces.clone()
+    }
+
+    /// Tip recipients of the builders, read by the swap encoders without an async lock
+    pub fn tip_recipients(&self) -> SharedTipRecipients {
+        self.tip_recipients.clone()
+    }
+
     pub fn new_block_headers_channel(&self) -> Broadcaster<MessageBlockHeader<LDT>> {
         self.new_block_headers_channel.clone()
     }
@@ -125,10 +172,19 @@ impl<LDT: LoomDataTypes> Blockchain<LDT> {
         self.new_mempool_tx_channel.clone()
     }
 
+    pub fn flashblocks_channel(&self) -> Broadcaster<MessageFlashblock<LDT>> {
+        self.flashblocks_channel.clone()
+    }
+
     pub fn market_events_channel(&self) -> Broadcaster<MarketEvents<LDT>> {
         self.market_events_channel.clone()
     }
 
+    /// Lifecycle events of the pools of the market, published from the event log of the market
+    pub fn pool_events_channel(&self) -> Broadcaster<PoolEventRecord<LDT>> {
+        self.pool_events_channel.clone()
+    }
+
     pub fn mempool_events_channel(&self) -> Broadcaster<MempoolEvents<LDT>> {
         self.mempool_events_channel.clone()
     }
@@ -148,4 +204,13 @@ impl<LDT: LoomDataTypes> Blockchain<LDT> {
     pub fn tasks_channel(&self) -> Broadcaster<LoomTask> {
         self.tasks_channel.clone()
     }
+
+    pub fn block_stats_channel(&self) -> Broadcaster<MessageBlockStats> {
+        self.block_stats_channel.clone()
+    }
+
+    /// Operational calls sent through the ERC-4337 bundler
+    pub fn ops_call_channel(&self) -> Broadcaster<MessageOpsCall> {
+        self.ops_call_channel.clone()
+    }
 }
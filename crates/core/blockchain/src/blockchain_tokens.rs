@@ -13,6 +13,10 @@ pub fn add_default_tokens_to_market(market: &mut Market, chain_id: ChainId) -> R
             let dai_token = Token::new_with_data(TokenAddressEth::DAI, Some("DAI".to_string()), None, Some(18), true, false);
             let wbtc_token = Token::new_with_data(TokenAddressEth::WBTC, Some("WBTC".to_string()), None, Some(8), true, false);
             let threecrv_token = Token::new_with_data(TokenAddressEth::THREECRV, Some("3Crv".to_string()), None, Some(18), false, true);
+            let mut steth_token = Token::new_with_data(TokenAddressEth::STETH, Some("stETH".to_string()), None, Some(18), false, false);
+            steth_token.set_rebasing();
+            let mut ampl_token = Token::new_with_data(TokenAddressEth::AMPL, Some("AMPL".to_string()), None, Some(9), false, false);
+            ampl_token.set_rebasing();
 
             market.add_token(weth_token);
             market.add_token(usdc_token);
@@ -20,6 +24,8 @@ pub fn add_default_tokens_to_market(market: &mut Market, chain_id: ChainId) -> R
             market.add_token(dai_token);
             market.add_token(wbtc_token);
             market.add_token(threecrv_token);
+            market.add_token(steth_token);
+            market.add_token(ampl_token);
         }
         NamedChain::Arbitrum => {
             let weth_token = Token::new_with_data(TokenAddressArbitrum::WETH, Some("WETH".to_string()), None, Some(18), true, false);
@@ -1,7 +1,9 @@
-use loom_core_actors::Broadcaster;
+use std::time::Duration;
+
+use loom_core_actors::{Broadcaster, SharedState};
 use loom_evm_db::DatabaseLoomExt;
 use loom_types_blockchain::{LoomDataTypes, LoomDataTypesEthereum};
-use loom_types_entities::BlockHistoryState;
+use loom_types_entities::{BlockHistoryState, StrategyNamespaces};
 use loom_types_events::{MessageSwapCompose, StateUpdateEvent};
 use revm::{Database, DatabaseCommit, DatabaseRef};
 
@@ -9,6 +11,8 @@ use revm::{Database, DatabaseCommit, DatabaseRef};
 pub struct Strategy<DB: Clone + Send + Sync + 'static, LDT: LoomDataTypes + 'static = LoomDataTypesEthereum> {
     swap_compose_channel: Broadcaster<MessageSwapCompose<DB, LDT>>,
     state_update_channel: Broadcaster<StateUpdateEvent<DB, LDT>>,
+    namespaces: SharedState<StrategyNamespaces>,
+    clustering_window: Option<Duration>,
 }
 
 impl<DB: DatabaseRef + Database + DatabaseCommit + BlockHistoryState + DatabaseLoomExt + Send + Sync + Clone + Default + 'static> Default
@@ -25,7 +29,12 @@ impl<DB: DatabaseRef + Database + DatabaseCommit + BlockHistoryState + DatabaseL
     pub fn new() -> Self {
         let compose_channel: Broadcaster<MessageSwapCompose<DB, LoomDataTypesEthereum>> = Broadcaster::new(100);
         let state_update_channel: Broadcaster<StateUpdateEvent<DB, LoomDataTypesEthereum>> = Broadcaster::new(100);
-        Strategy { swap_compose_channel: compose_channel, state_update_channel }
+        Strategy {
+            swap_compose_channel: compose_channel,
+            state_update_channel,
+            namespaces: SharedState::new(StrategyNamespaces::new()),
+            clustering_window: None,
+        }
     }
 }
 
@@ -37,4 +46,23 @@ impl<DB: Send + Sync + Clone + 'static> Strategy<DB, LoomDataTypesEthereum> {
     pub fn state_update_channel(&self) -> Broadcaster<StateUpdateEvent<DB, LoomDataTypesEthereum>> {
         self.state_update_channel.clone()
     }
+
+    pub fn with_namespaces(self, namespaces: StrategyNamespaces) -> Self {
+        Self { namespaces: SharedState::new(namespaces), ..self }
+    }
+
+    /// Signers and limits of the strategy namespaces
+    pub fn namespaces(&self) -> SharedState<StrategyNamespaces> {
+        self.namespaces.clone()
+    }
+
+    pub fn with_clustering_window(self, clustering_window: Option<Duration>) -> Self {
+        Self { clustering_window, ..self }
+    }
+
+    /// Window ready swaps are collected for before the most profitable ones not sharing pools are broadcast, ready swaps are
+    /// broadcast at once if not set
+    pub fn clustering_window(&self) -> Option<Duration> {
+        self.clustering_window
+    }
 }
@@ -8,7 +8,7 @@ use tracing::{debug, error, info, trace};
 use loom_core_actors::{run_sync, subscribe, Accessor, Actor, ActorResult, Broadcaster, Consumer, Producer, SharedState, WorkerResult};
 use loom_core_actors_macros::{Accessor, Consumer, Producer};
 use loom_core_blockchain::Blockchain;
-use loom_types_blockchain::{ChainParameters, Mempool, MempoolTx};
+use loom_types_blockchain::{ChainParameters, Mempool, MempoolTx, MempoolTxStatus};
 use loom_types_blockchain::{LoomBlock, LoomDataTypes, LoomDataTypesEthereum, LoomHeader, LoomTx};
 use loom_types_events::{MempoolEvents, MessageBlock, MessageBlockHeader, MessageMempoolDataUpdate};
 
@@ -65,12 +65,22 @@ pub async fn new_mempool_worker<LDT: LoomDataTypes>(
                 if let Some(tx) = &mempool_update_msg.mempool_tx.tx {
                     if mempool_entry.tx.is_none() {
                         mempool_entry.tx = Some(tx.clone());
-                        if let Some(cur_gas_price) = current_gas_price {
-                            if tx.gas_limit() > 30000 && tx.gas_price() >= cur_gas_price && mempool_guard.is_valid_tx(tx) {
-                                run_sync!(broadcaster.send(MempoolEvents::MempoolActualTxUpdate {tx_hash }));
+                        match mempool_guard.track_tx(tx) {
+                            MempoolTxStatus::Underpriced { latest } => {
+                                trace!("Underpriced replacement tx {} latest {}", tx_hash, latest);
+                            }
+                            status => {
+                                if let MempoolTxStatus::Replacement { replaced } = status {
+                                    run_sync!(broadcaster.send(MempoolEvents::MempoolTxReplaced { tx_hash, replaced_tx_hash: replaced }));
+                                }
+                                if let Some(cur_gas_price) = current_gas_price {
+                                    if tx.gas_limit() > 30000 && tx.gas_price() >= cur_gas_price && mempool_guard.is_valid_tx(tx) {
+                                        run_sync!(broadcaster.send(MempoolEvents::MempoolActualTxUpdate {tx_hash }));
+                                    }
+                                }
+                                run_sync!(broadcaster.send(MempoolEvents::MempoolTxUpdate {tx_hash }));
                             }
                         }
-                        run_sync!(broadcaster.send(MempoolEvents::MempoolTxUpdate {tx_hash }));
                     }
                 }
                 drop(mempool_guard);
@@ -105,6 +115,9 @@ pub async fn new_mempool_worker<LDT: LoomDataTypes>(
                 let ok_txes = mempool_read_guard.filter_ok_by_gas_price(next_base_fee as u128);
                 debug!("Mempool gas update {} {}", next_base_fee, ok_txes.len());
                 for mempool_tx in ok_txes {
+                    if mempool_tx.replaced_by.is_some() {
+                        continue
+                    }
                     let tx = mempool_tx.tx.clone().unwrap();
                     if tx.gas_limit()  < 50000 {
                         continue
@@ -125,6 +138,7 @@ pub async fn new_mempool_worker<LDT: LoomDataTypes>(
                             let mut mempool_write_guard = mempool.write().await;
                             info!("Start mempool cleaning started. len : {}", mempool_write_guard.len());
                             mempool_write_guard.clean_txs( block_number - 50, Utc::now() - Duration::minutes(20) );
+                            mempool_write_guard.reputation_mut().decay();
                             last_cleaning_block = Some(block_number);
                             info!("Start mempool cleaning finished len : {}", mempool_write_guard.len());
                             drop(mempool_write_guard)
@@ -0,0 +1,386 @@
+use std::collections::HashMap;
+
+use alloy_network::TransactionResponse;
+use alloy_primitives::{keccak256, Address, TxHash, U256};
+use alloy_rpc_types::BlockTransactions;
+use eyre::{eyre, ErrReport, OptionExt, Result};
+use loom_core_actors::{subscribe, Accessor, Actor, ActorResult, Broadcaster, Consumer, Producer, SharedState, WorkerResult};
+use loom_core_actors_macros::{Accessor, Consumer, Producer};
+use loom_core_blockchain::{Blockchain, BlockchainState, Strategy};
+use loom_evm_utils::evm_env::env_for_block;
+use loom_evm_utils::NWETH;
+use loom_types_blockchain::ChainParameters;
+use loom_types_entities::{
+    AccountNonceAndBalanceState, BundleGasAccounting, GasHedgeConfig, GasLedger, HedgeOrder, LatestBlock, Market, MarketState, Swap,
+    SwapAmountType, SwapLine, SwapPath, TxSigners,
+};
+use loom_types_events::{
+    MarketEvents, MessageSwapCompose, MessageTxCompose, RlpState, SwapComposeData, TxComposeData, TxComposeMessageType,
+};
+use revm::DatabaseRef;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{debug, error, info};
+
+pub const GAS_HEDGE_ORIGIN: &str = "gas_hedge";
+
+// blocks a broadcast bundle is awaited after its target block, re-targeted bundles land later
+const MAX_PENDING_BLOCKS: u64 = 25;
+
+/// Hash of the first backrun tx of the bundle, the bundle landed when it is included
+fn backrun_tx_hash(tx_compose: &TxComposeData) -> Option<TxHash> {
+    tx_compose.rlp_bundle.as_ref()?.iter().find_map(|rlp| match rlp {
+        RlpState::Backrun(tx) => Some(keccak256(tx)),
+        _ => None,
+    })
+}
+
+/// Record the pending bundles with backrun txs included in the block, bundles not included for too long are dropped
+fn record_included(
+    ledger: &mut GasLedger,
+    pending_bundles: &mut HashMap<TxHash, BundleGasAccounting>,
+    tx_hashes: &[TxHash],
+    block_number: u64,
+) {
+    for tx_hash in tx_hashes {
+        if let Some(bundle) = pending_bundles.remove(tx_hash) {
+            ledger.record(&bundle);
+            debug!(
+                block_number,
+                %tx_hash,
+                profit_eth = NWETH::to_float(bundle.profit_eth),
+                gas_cost_eth = NWETH::to_float(bundle.gas_cost_eth),
+                tips_eth = NWETH::to_float(bundle.tips_eth),
+                "Bundle gas accounting"
+            );
+        }
+    }
+    pending_bundles.retain(|_, bundle| bundle.block_number + MAX_PENDING_BLOCKS > block_number);
+}
+
+/// Profits of a broadcast swap by profit token, with the gas at the fees of the target block and the tips
+fn bundle_accounting(tx_compose: &TxComposeData) -> Option<BundleGasAccounting> {
+    let swap = tx_compose.swap.as_ref()?;
+    let swaps: Vec<&Swap> = match swap {
+        Swap::Multiple(swap_vec) => swap_vec.iter().collect(),
+        _ => vec![swap],
+    };
+
+    let mut profits = Vec::new();
+    let mut profit_eth = U256::ZERO;
+    for swap in swaps {
+        // the surplus of exchange swaps belongs to the order owner
+        if let Swap::ExchangeSwapLine(_) = swap {
+            continue;
+        }
+        if let Some(token) = swap.get_first_token() {
+            profits.push((token.get_address(), swap.abs_profit()));
+            profit_eth += swap.abs_profit_eth();
+        }
+    }
+
+    let gas_cost_eth = U256::from(tx_compose.gas) * U256::from(tx_compose.next_block_base_fee + tx_compose.priority_gas_fee);
+    Some(BundleGasAccounting {
+        block_number: tx_compose.next_block_number,
+        profits,
+        profit_eth,
+        gas_cost_eth,
+        // the tips of estimated swaps include their gas cost
+        tips_eth: tx_compose.tips.unwrap_or_default().saturating_sub(gas_cost_eth),
+    })
+}
+
+/// Swap of the order into WETH through the direct pool with the largest output, with its output
+fn hedge_swap_line<DB: DatabaseRef<Error = ErrReport>>(
+    market: &Market,
+    db: &DB,
+    order: &HedgeOrder,
+    next_block_number: u64,
+    next_block_timestamp: u64,
+) -> Result<(SwapLine, U256)> {
+    let token_from = market.get_token(&order.token).ok_or_eyre("TOKEN_NOT_FOUND")?;
+    let token_to = market.get_token(&NWETH::ADDRESS).ok_or_eyre("WETH_NOT_FOUND")?;
+    let pools = market.get_token_token_pools(&order.token, &NWETH::ADDRESS).ok_or_eyre("NO_WETH_POOLS")?;
+
+    let mut best: Option<(SwapLine, U256)> = None;
+    for pool_id in pools.iter().filter(|pool_id| !market.is_pool_disabled(pool_id)) {
+        let Some(pool) = market.get_pool(pool_id) else { continue };
+        let mut swap_path = SwapPath::default();
+        if swap_path.push_swap_hope(token_from.clone(), token_to.clone(), pool.clone()).is_err() {
+            continue;
+        }
+        let mut swap_line = SwapLine::from(swap_path);
+        let Ok((amount_out, gas_used, calculation_results)) =
+            swap_line.calculate_with_in_amount(db, env_for_block(next_block_number, next_block_timestamp), order.amount_in)
+        else {
+            continue;
+        };
+        if best.as_ref().is_some_and(|(_, best_amount_out)| *best_amount_out >= amount_out) {
+            continue;
+        }
+        swap_line.amount_in = SwapAmountType::Set(order.amount_in);
+        swap_line.amount_out = SwapAmountType::Set(amount_out);
+        swap_line.gas_used = Some(gas_used);
+        swap_line.calculation_results = calculation_results;
+        best = Some((swap_line, amount_out));
+    }
+
+    best.ok_or_eyre("NO_HEDGE_ROUTE")
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn hedge_signers<DB: DatabaseRef<Error = ErrReport> + Clone + Send + Sync + 'static>(
+    hedge_config: &GasHedgeConfig,
+    ledger: &mut GasLedger,
+    market: &SharedState<Market>,
+    market_state: &SharedState<MarketState<DB>>,
+    signers: &SharedState<TxSigners>,
+    account_state: &SharedState<AccountNonceAndBalanceState>,
+    swap_compose_tx: &Broadcaster<MessageSwapCompose<DB>>,
+    next_block: (u64, u64, u64),
+) -> Result<()> {
+    let (next_block_number, next_block_timestamp, next_block_base_fee) = next_block;
+    let eoas: Vec<Address> = signers.read().await.get_address_vec();
+
+    for eoa in eoas {
+        let Some(eth_balance) = account_state.read().await.get_account(&eoa).map(|account| account.get_eth_balance()) else { continue };
+        let Some(order) = ledger.hedge_order(hedge_config, eth_balance, &*market.read().await) else { continue };
+
+        let db = market_state.read().await.state_db.clone();
+        let (mut swap_line, amount_out) = hedge_swap_line(&*market.read().await, &db, &order, next_block_number, next_block_timestamp)?;
+        // only the output of the conversion is paid out to the signer, unwrapped if the encoder unwraps native payouts
+        swap_line.swap_to = Some(eoa);
+        swap_line.native_payout = true;
+        swap_line.min_amount_out = Some(hedge_config.min_amount_out(amount_out));
+        info!(
+            %eoa,
+            eth_balance = NWETH::to_float(eth_balance),
+            token = %order.token,
+            amount_eth = NWETH::to_float(order.amount_eth),
+            swap = %swap_line,
+            "Converting token profit into gas inventory"
+        );
+
+        let swap_compose = SwapComposeData {
+            tx_compose: TxComposeData {
+                eoa: Some(eoa),
+                next_block_number,
+                next_block_timestamp,
+                next_block_base_fee,
+                origin: Some(GAS_HEDGE_ORIGIN.to_string()),
+                ..TxComposeData::default()
+            },
+            swap: Swap::ExchangeSwapLine(swap_line),
+            prestate: Some(db.clone()),
+            poststate: Some(db),
+            origin: Some(GAS_HEDGE_ORIGIN.to_string()),
+            ..SwapComposeData::default()
+        };
+        swap_compose_tx.send(MessageSwapCompose::prepare(swap_compose)).map_err(|_| eyre!("ERROR_SENDING_HEDGE_SWAP"))?;
+        ledger.on_hedged(&order.token, order.amount_in);
+    }
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn gas_hedge_worker<DB: DatabaseRef<Error = ErrReport> + Clone + Send + Sync + 'static>(
+    hedge_config: GasHedgeConfig,
+    chain_parameters: ChainParameters,
+    market: SharedState<Market>,
+    market_state: SharedState<MarketState<DB>>,
+    signers: SharedState<TxSigners>,
+    account_state: SharedState<AccountNonceAndBalanceState>,
+    latest_block: SharedState<LatestBlock>,
+    market_events_rx: Broadcaster<MarketEvents>,
+    tx_compose_channel_rx: Broadcaster<MessageTxCompose>,
+    swap_compose_channel_tx: Broadcaster<MessageSwapCompose<DB>>,
+) -> WorkerResult {
+    subscribe!(market_events_rx);
+    subscribe!(tx_compose_channel_rx);
+
+    let mut ledger = GasLedger::new();
+    // backrun tx hash -> accounting of a broadcast bundle, recorded when the tx is included
+    let mut pending_bundles: HashMap<TxHash, BundleGasAccounting> = HashMap::new();
+    let mut last_check_block = 0;
+
+    loop {
+        tokio::select! {
+            msg = tx_compose_channel_rx.recv() => {
+                match msg {
+                    Ok(compose_message) => {
+                        if let TxComposeMessageType::Broadcast(tx_compose) = compose_message.inner {
+                            if let (Some(tx_hash), Some(bundle)) = (backrun_tx_hash(&tx_compose), bundle_accounting(&tx_compose)) {
+                                pending_bundles.insert(tx_hash, bundle);
+                            }
+                        }
+                    }
+                    Err(RecvError::Lagged(lag)) => error!("Tx compose channel lagged: {}", lag),
+                    Err(RecvError::Closed) => return Err(eyre!("TX_COMPOSE_CHANNEL_CLOSED")),
+                }
+            }
+            msg = market_events_rx.recv() => {
+                match msg {
+                    Ok(MarketEvents::BlockTxUpdate { block_number, .. }) => {
+                        let latest_block_guard = latest_block.read().await;
+                        let tx_hashes: Vec<TxHash> = match latest_block_guard.block_with_txs.as_ref().map(|block| &block.transactions) {
+                            Some(BlockTransactions::Full(txs)) => txs.iter().map(TransactionResponse::tx_hash).collect(),
+                            _ => continue,
+                        };
+                        drop(latest_block_guard);
+                        record_included(&mut ledger, &mut pending_bundles, &tx_hashes, block_number);
+                    }
+                    Ok(MarketEvents::BlockHeaderUpdate { block_number, timestamp, next_base_fee, .. }) => {
+                        if block_number < last_check_block + hedge_config.interval_blocks {
+                            continue;
+                        }
+                        last_check_block = block_number;
+                        info!(
+                            bundles = ledger.bundles(),
+                            profit_eth = NWETH::to_float(ledger.profit_eth()),
+                            gas_cost_eth = NWETH::to_float(ledger.gas_cost_eth()),
+                            tips_eth = NWETH::to_float(ledger.tips_eth()),
+                            "Gas accounting"
+                        );
+                        if let Err(error) = hedge_signers(
+                            &hedge_config,
+                            &mut ledger,
+                            &market,
+                            &market_state,
+                            &signers,
+                            &account_state,
+                            &swap_compose_channel_tx,
+                            (block_number + 1, chain_parameters.next_block_timestamp(timestamp), next_base_fee),
+                        )
+                        .await
+                        {
+                            error!(%error, "Gas inventory conversion failed");
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(RecvError::Lagged(lag)) => error!("Market events channel lagged: {}", lag),
+                    Err(RecvError::Closed) => return Err(eyre!("MARKET_EVENTS_CHANNEL_CLOSED")),
+                }
+            }
+        }
+    }
+}
+
+/// Accounts the token profits of included bundles against the ETH they spend on gas and tips, and converts a portion of
+/// the token profits into ETH through the market pools when the balance of a signer falls below the floor
+#[derive(Accessor, Consumer, Producer)]
+pub struct GasHedgeActor<DB: Clone + Send + Sync + 'static> {
+    hedge_config: GasHedgeConfig,
+    chain_parameters: ChainParameters,
+    #[accessor]
+    market: Option<SharedState<Market>>,
+    #[accessor]
+    market_state: Option<SharedState<MarketState<DB>>>,
+    #[accessor]
+    signers: Option<SharedState<TxSigners>>,
+    #[accessor]
+    account_state: Option<SharedState<AccountNonceAndBalanceState>>,
+    #[accessor]
+    latest_block: Option<SharedState<LatestBlock>>,
+    #[consumer]
+    market_events_rx: Option<Broadcaster<MarketEvents>>,
+    #[consumer]
+    tx_compose_channel_rx: Option<Broadcaster<MessageTxCompose>>,
+    #[producer]
+    swap_compose_channel_tx: Option<Broadcaster<MessageSwapCompose<DB>>>,
+}
+
+impl<DB: DatabaseRef<Error = ErrReport> + Clone + Send + Sync + 'static> GasHedgeActor<DB> {
+    pub fn new(hedge_config: GasHedgeConfig) -> Self {
+        Self {
+            hedge_config,
+            chain_parameters: ChainParameters::ethereum(),
+            market: None,
+            market_state: None,
+            signers: None,
+            account_state: None,
+            latest_block: None,
+            market_events_rx: None,
+            tx_compose_channel_rx: None,
+            swap_compose_channel_tx: None,
+        }
+    }
+
+    pub fn with_signers(self, signers: SharedState<TxSigners>) -> Self {
+        Self { signers: Some(signers), ..self }
+    }
+
+    pub fn on_bc(self, bc: &Blockchain, state: &BlockchainState<DB>, strategy: &Strategy<DB>) -> Self {
+        Self {
+            chain_parameters: bc.chain_parameters(),
+            market: Some(bc.market()),
+            market_state: Some(state.market_state()),
+            account_state: Some(bc.nonce_and_balance()),
+            latest_block: Some(bc.latest_block()),
+            market_events_rx: Some(bc.market_events_channel()),
+            tx_compose_channel_rx: Some(bc.tx_compose_channel()),
+            swap_compose_channel_tx: Some(strategy.swap_compose_channel()),
+            ..self
+        }
+    }
+}
+
+impl<DB: DatabaseRef<Error = ErrReport> + Clone + Send + Sync + 'static> Actor for GasHedgeActor<DB> {
+    fn start(&self) -> ActorResult {
+        let task = tokio::task::spawn(gas_hedge_worker(
+            self.hedge_config.clone(),
+            self.chain_parameters.clone(),
+            self.market.clone().unwrap(),
+            self.market_state.clone().unwrap(),
+            self.signers.clone().unwrap(),
+            self.account_state.clone().unwrap(),
+            self.latest_block.clone().unwrap(),
+            self.market_events_rx.clone().unwrap(),
+            self.tx_compose_channel_rx.clone().unwrap(),
+            self.swap_compose_channel_tx.clone().unwrap(),
+        ));
+        Ok(vec![task])
+    }
+
+    fn name(&self) -> &'static str {
+        "GasHedgeActor"
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloy_primitives::Bytes;
+
+    #[test]
+    fn test_record_included() {
+        let tx = Bytes::from(vec![0x02, 0x01]);
+        let tx_compose = TxComposeData {
+            rlp_bundle: Some(vec![RlpState::Stuffing(Bytes::from(vec![0x01])), RlpState::Backrun(tx.clone())]),
+            ..TxComposeData::default()
+        };
+        let tx_hash = backrun_tx_hash(&tx_compose).unwrap();
+        assert_eq!(tx_hash, keccak256(&tx));
+
+        let bundle = |block_number: u64| BundleGasAccounting {
+            block_number,
+            profits: vec![(Address::repeat_byte(1), U256::from(100))],
+            profit_eth: U256::from(10),
+            gas_cost_eth: U256::from(2),
+            tips_eth: U256::from(3),
+        };
+        let mut ledger = GasLedger::new();
+        let mut pending_bundles = HashMap::from([(tx_hash, bundle(10)), (TxHash::repeat_byte(2), bundle(10))]);
+
+        // broadcast bundles are not accounted before their backrun tx is included
+        record_included(&mut ledger, &mut pending_bundles, &[TxHash::repeat_byte(3)], 10);
+        assert_eq!(ledger.bundles(), 0);
+        assert_eq!(pending_bundles.len(), 2);
+
+        record_included(&mut ledger, &mut pending_bundles, &[tx_hash], 11);
+        assert_eq!(ledger.bundles(), 1);
+        assert_eq!(ledger.token_profit(&Address::repeat_byte(1)), U256::from(100));
+
+        record_included(&mut ledger, &mut pending_bundles, &[], 10 + MAX_PENDING_BLOCKS);
+        assert!(pending_bundles.is_empty());
+        assert_eq!(ledger.bundles(), 1);
+    }
+}
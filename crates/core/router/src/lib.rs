@@ -1,3 +1,7 @@
+mod gas_hedge_actor;
+mod opportunity_clusters;
 mod swap_router_actor;
 
+pub use gas_hedge_actor::{GasHedgeActor, GAS_HEDGE_ORIGIN};
+pub use opportunity_clusters::{select_non_conflicting, OpportunityCandidate, OpportunityClusters};
 pub use swap_router_actor::SwapRouterActor;
@@ -0,0 +1,240 @@
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap};
+
+use alloy_primitives::{TxHash, U256};
+use loom_types_entities::PoolId;
+
+/// Clusters up to this size are searched exhaustively, larger ones greedily by profit
+const MAX_EXACT_CLUSTER_SIZE: usize = 16;
+
+/// Swap competing for the pools of a block
+#[derive(Clone, Debug, Default)]
+pub struct OpportunityCandidate {
+    /// Pools touched by the swap
+    pub pools: Vec<PoolId>,
+    /// Transactions backrun by the swap, sorted. Backruns of other transactions are sent in other bundles
+    pub stuffing_txs: Vec<TxHash>,
+    /// Expected profit in ETH
+    pub profit: U256,
+}
+
+impl OpportunityCandidate {
+    pub fn new(pools: Vec<PoolId>, mut stuffing_txs: Vec<TxHash>, profit: U256) -> Self {
+        stuffing_txs.sort_unstable();
+        Self { pools, stuffing_txs, profit }
+    }
+
+    /// Both swaps go through a pool after the same transactions
+    fn conflicts(&self, other: &Self) -> bool {
+        self.stuffing_txs == other.stuffing_txs && self.pools.iter().any(|pool_id| other.pools.contains(pool_id))
+    }
+
+    /// Another submission of the same swap, e.g. re-priced
+    fn resubmits(&self, other: &Self) -> bool {
+        self.pools == other.pools && self.stuffing_txs == other.stuffing_txs
+    }
+}
+
+fn find_cluster(parents: &mut [usize], idx: usize) -> usize {
+    let mut root = idx;
+    while parents[root] != root {
+        root = parents[root];
+    }
+    parents[idx] = root;
+    root
+}
+
+/// Indices of the candidates of a block that do not share pools after the same transactions with each other and have the
+/// largest total profit. Candidates are clustered by shared pools and every cluster is solved on its own, like weighted
+/// interval scheduling with pools in place of time ranges
+pub fn select_non_conflicting(candidates: &[OpportunityCandidate]) -> Vec<usize> {
+    let mut parents: Vec<usize> = (0..candidates.len()).collect();
+    let mut pool_candidates: HashMap<(PoolId, &[TxHash]), usize> = HashMap::new();
+    for (idx, candidate) in candidates.iter().enumerate() {
+        for pool_id in candidate.pools.iter() {
+            match pool_candidates.entry((*pool_id, candidate.stuffing_txs.as_slice())) {
+                Entry::Occupied(entry) => {
+                    let (root, other_root) = (find_cluster(&mut parents, idx), find_cluster(&mut parents, *entry.get()));
+                    parents[root] = other_root;
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert(idx);
+                }
+            }
+        }
+    }
+
+    let mut clusters: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+    for idx in 0..candidates.len() {
+        clusters.entry(find_cluster(&mut parents, idx)).or_default().push(idx);
+    }
+
+    let mut selected: Vec<usize> = clusters.into_values().flat_map(|cluster| select_cluster(candidates, cluster)).collect();
+    selected.sort_unstable();
+    selected
+}
+
+/// Most profitable subset of the cluster without shared pools
+fn select_cluster(candidates: &[OpportunityCandidate], mut cluster: Vec<usize>) -> Vec<usize> {
+    // the most profitable candidates first, the search finds good subsets early and prunes the rest
+    cluster.sort_by(|a, b| candidates[*b].profit.cmp(&candidates[*a].profit));
+
+    if cluster.len() > MAX_EXACT_CLUSTER_SIZE {
+        let mut selected: Vec<usize> = Vec::new();
+        for idx in cluster {
+            if selected.iter().all(|selected_idx| !candidates[*selected_idx].conflicts(&candidates[idx])) {
+                selected.push(idx);
+            }
+        }
+        return selected;
+    }
+
+    let profits: Vec<U256> = cluster.iter().map(|idx| candidates[*idx].profit).collect();
+    // conflicting candidates of every position of the cluster as a bit mask of positions
+    let conflict_masks: Vec<u32> = cluster
+        .iter()
+        .map(|idx| {
+            cluster
+                .iter()
+                .enumerate()
+                .filter(|(_, other_idx)| *other_idx != idx && candidates[*idx].conflicts(&candidates[**other_idx]))
+                .fold(0u32, |mask, (pos, _)| mask | (1 << pos))
+        })
+        .collect();
+    // profit of the candidates from the position on, an upper bound of what the search can still add
+    let mut remaining_profits = vec![U256::ZERO; cluster.len() + 1];
+    for pos in (0..cluster.len()).rev() {
+        remaining_profits[pos] = remaining_profits[pos + 1] + profits[pos];
+    }
+
+    let mut best: (U256, u32) = (U256::ZERO, 0);
+    search_cluster(0, 0, 0, U256::ZERO, &profits, &conflict_masks, &remaining_profits, &mut best);
+    cluster.into_iter().enumerate().filter(|(pos, _)| best.1 & (1 << pos) != 0).map(|(_, idx)| idx).collect()
+}
+
+#[allow(clippy::too_many_arguments)]
+fn search_cluster(
+    pos: usize,
+    chosen: u32,
+    blocked: u32,
+    profit: U256,
+    profits: &[U256],
+    conflict_masks: &[u32],
+    remaining_profits: &[U256],
+    best: &mut (U256, u32),
+) {
+    // more swaps at the same profit, swaps without a profit of their own are not dropped
+    if profit > best.0 || (profit == best.0 && chosen.count_ones() > best.1.count_ones()) {
+        *best = (profit, chosen);
+    }
+    if pos == profits.len() || profit + remaining_profits[pos] < best.0 {
+        return;
+    }
+    if blocked & (1 << pos) == 0 {
+        search_cluster(
+            pos + 1,
+            chosen | (1 << pos),
+            blocked | conflict_masks[pos],
+            profit + profits[pos],
+            profits,
+            conflict_masks,
+            remaining_profits,
+            best,
+        );
+    }
+    search_cluster(pos + 1, chosen, blocked, profit, profits, conflict_masks, remaining_profits, best);
+}
+
+/// Swaps submitted for the upcoming blocks. A candidate sharing pools after the same transactions with swaps already submitted
+/// for its block would invalidate them or be invalidated, it is only submitted if its profit is above the total profit of those
+/// swaps. Another submission of a submitted swap replaces it
+#[derive(Default)]
+pub struct OpportunityClusters {
+    submitted: BTreeMap<u64, Vec<OpportunityCandidate>>,
+}
+
+impl OpportunityClusters {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Indices of the candidates of the block to submit, they are recorded as submitted for the block
+    pub fn select(&mut self, block_number: u64, candidates: &[OpportunityCandidate]) -> Vec<usize> {
+        // swaps of past blocks do not compete anymore
+        self.submitted.retain(|submitted_block_number, _| *submitted_block_number + 1 >= block_number);
+        let submitted = self.submitted.entry(block_number).or_default();
+
+        let eligible: Vec<usize> = (0..candidates.len())
+            .filter(|idx| {
+                let candidate = &candidates[*idx];
+                let mut conflicting = submitted
+                    .iter()
+                    .filter(|submitted_candidate| !candidate.resubmits(submitted_candidate) && candidate.conflicts(submitted_candidate))
+                    .peekable();
+                conflicting.peek().is_none()
+                    || candidate.profit > conflicting.fold(U256::ZERO, |total, submitted_candidate| total + submitted_candidate.profit)
+            })
+            .collect();
+        let eligible_candidates: Vec<OpportunityCandidate> = eligible.iter().map(|idx| candidates[*idx].clone()).collect();
+
+        let selected: Vec<usize> = select_non_conflicting(&eligible_candidates).into_iter().map(|pos| eligible[pos]).collect();
+        for idx in selected.iter() {
+            let candidate = &candidates[*idx];
+            // the selected swap supersedes the submitted swaps it conflicts with and its own earlier submission
+            submitted.retain(|submitted_candidate| !candidate.conflicts(submitted_candidate));
+            submitted.push(candidate.clone());
+        }
+        selected
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloy_primitives::Address;
+
+    fn candidate(pools: &[u8], profit: u64) -> OpportunityCandidate {
+        backrun(pools, &[], profit)
+    }
+
+    fn backrun(pools: &[u8], stuffing_txs: &[u8], profit: u64) -> OpportunityCandidate {
+        OpportunityCandidate::new(
+            pools.iter().map(|pool| PoolId::Address(Address::repeat_byte(*pool))).collect(),
+            stuffing_txs.iter().map(|tx| TxHash::repeat_byte(*tx)).collect(),
+            U256::from(profit),
+        )
+    }
+
+    #[test]
+    fn test_select_non_conflicting() {
+        // the most profitable swap conflicts with both others that are more profitable together
+        let candidates = vec![candidate(&[1, 2], 10), candidate(&[2, 3], 15), candidate(&[3, 4], 10), candidate(&[5], 1)];
+        assert_eq!(select_non_conflicting(&candidates), vec![0, 2, 3]);
+
+        let candidates = vec![candidate(&[1, 2], 10), candidate(&[2, 3], 25), candidate(&[3, 4], 10)];
+        assert_eq!(select_non_conflicting(&candidates), vec![1]);
+        assert!(select_non_conflicting(&[]).is_empty());
+
+        // backruns of other transactions do not conflict, the order of the transactions does not matter
+        let candidates = vec![backrun(&[1, 2], &[1], 10), backrun(&[2, 3], &[2], 15), backrun(&[2], &[1, 2], 5), backrun(&[2], &[2, 1], 6)];
+        assert_eq!(select_non_conflicting(&candidates), vec![0, 1, 3]);
+    }
+
+    #[test]
+    fn test_opportunity_clusters() {
+        let mut clusters = OpportunityClusters::new();
+        assert_eq!(clusters.select(100, &[candidate(&[1, 2], 10), candidate(&[3], 5)]), vec![0, 1]);
+        // conflicts with the submitted swap and is not more profitable
+        assert!(clusters.select(100, &[candidate(&[2, 4], 10)]).is_empty());
+        assert_eq!(clusters.select(100, &[candidate(&[2, 3], 20)]), vec![0]);
+        assert!(clusters.select(100, &[candidate(&[3], 15)]).is_empty());
+        // another block
+        assert_eq!(clusters.select(101, &[candidate(&[2, 4], 10)]), vec![0]);
+        // a backrun of a transaction
+        assert_eq!(clusters.select(101, &[backrun(&[2, 4], &[1], 5)]), vec![0]);
+        // the same swaps re-priced replace their earlier submissions
+        assert_eq!(clusters.select(101, &[candidate(&[2, 4], 8), backrun(&[2, 4], &[1], 4)]), vec![0, 1]);
+        assert!(clusters.select(101, &[candidate(&[4], 8)]).is_empty());
+        assert_eq!(clusters.select(101, &[candidate(&[4], 9)]), vec![0]);
+    }
+}
@@ -1,13 +1,60 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use alloy_primitives::Address;
 use eyre::{eyre, Result};
 use loom_core_actors::{Accessor, Actor, ActorResult, Broadcaster, Consumer, Producer, SharedState, WorkerResult};
 use loom_core_actors_macros::{Accessor, Consumer, Producer};
 use loom_core_blockchain::{Blockchain, Strategy};
-use loom_types_entities::{AccountNonceAndBalanceState, TxSigners};
+use loom_types_entities::{AccountNonceAndBalanceState, Market, PoolClass, StrategyNamespaces, Swap, TxSigners};
 use loom_types_events::{MessageSwapCompose, MessageTxCompose, SwapComposeData, SwapComposeMessage, TxComposeData};
 use revm::DatabaseRef;
 use tokio::sync::broadcast::error::RecvError;
 use tokio::sync::broadcast::Receiver;
-use tracing::{debug, error, info};
+use tokio::time::Instant;
+use tracing::{debug, error, info, warn};
+
+use crate::opportunity_clusters::{OpportunityCandidate, OpportunityClusters};
+
+/// Pool class of the swap disabled by the kill switch of the market
+async fn disabled_pool_class(market: &SharedState<Market>, swap: &Swap) -> Option<PoolClass> {
+    let market_guard = market.read().await;
+    if market_guard.kill_switch().is_empty() {
+        return None;
+    }
+    swap.get_pools_vec().iter().map(|pool| pool.get_class()).find(|pool_class| market_guard.is_pool_class_disabled(*pool_class))
+}
+
+/// Signer of the swap out of the signers of its namespace, the one with the largest ETH balance unless the EOA is set.
+/// Swaps without a namespace use the signers not owned by any namespace. The swap is counted against the limits of its
+/// namespace for the target block once, re-routed and merged swaps inherit the signer of the reserved one
+async fn namespace_signer<DB: Clone + Send + Sync + 'static>(
+    route_request: &SwapComposeData<DB>,
+    signers: &SharedState<TxSigners>,
+    account_monitor: &SharedState<AccountNonceAndBalanceState>,
+    namespaces: &SharedState<StrategyNamespaces>,
+) -> Result<Address> {
+    let namespace = route_request.tx_compose.namespace.as_deref();
+    let allowed_signers = namespaces.read().await.allowed_signers(namespace, &signers.read().await.get_address_vec())?;
+
+    let signer_address = match route_request.tx_compose.eoa {
+        Some(eoa) if allowed_signers.contains(&eoa) => eoa,
+        Some(eoa) => return Err(eyre!("NAMESPACE_SIGNER_NOT_ALLOWED : {eoa}")),
+        None => {
+            let account_monitor_guard = account_monitor.read().await;
+            allowed_signers
+                .into_iter()
+                .max_by_key(|signer| account_monitor_guard.get_account(signer).map(|account| account.get_eth_balance()).unwrap_or_default())
+                .ok_or(eyre!("NO_SIGNER"))?
+        }
+    };
+
+    if let (Some(namespace), None) = (namespace, route_request.tx_compose.signer.as_ref()) {
+        namespaces.write().await.reserve(namespace, route_request.tx_compose.next_block_number, route_request.swap.capital_eth())?;
+    }
+
+    Ok(signer_address)
+}
 
 /// encoder task performs initial routing for swap request
 async fn router_task_prepare<DB: DatabaseRef + Send + Sync + Clone + 'static>(
@@ -15,12 +62,31 @@ async fn router_task_prepare<DB: DatabaseRef + Send + Sync + Clone + 'static>(
     compose_channel_tx: Broadcaster<MessageSwapCompose<DB>>,
     signers: SharedState<TxSigners>,
     account_monitor: SharedState<AccountNonceAndBalanceState>,
+    market: SharedState<Market>,
+    namespaces: Option<SharedState<StrategyNamespaces>>,
 ) -> Result<()> {
     debug!("router_task_prepare started {}", route_request.swap);
 
-    let signer = match route_request.tx_compose.eoa {
-        Some(eoa) => signers.read().await.get_signer_by_address(&eoa)?,
-        None => signers.read().await.get_random_signer().ok_or(eyre!("NO_SIGNER"))?,
+    if let Some(pool_class) = disabled_pool_class(&market, &route_request.swap).await {
+        debug!(%pool_class, swap = %route_request.swap, "Swap through disabled pool class dropped before encoding");
+        return Err(eyre!("POOL_CLASS_DISABLED"));
+    }
+
+    let namespaces = match namespaces {
+        Some(namespaces) if !namespaces.read().await.is_empty() => Some(namespaces),
+        _ => None,
+    };
+
+    let signer = match (namespaces, route_request.tx_compose.eoa) {
+        (Some(namespaces), _) => match namespace_signer(&route_request, &signers, &account_monitor, &namespaces).await {
+            Ok(signer_address) => signers.read().await.get_signer_by_address(&signer_address)?,
+            Err(error) => {
+                debug!(namespace = ?route_request.tx_compose.namespace, %error, swap = %route_request.swap, "Swap dropped by namespace");
+                return Err(error);
+            }
+        },
+        (None, Some(eoa)) => signers.read().await.get_signer_by_address(&eoa)?,
+        (None, None) => signers.read().await.get_random_signer().ok_or(eyre!("NO_SIGNER"))?,
     };
 
     let nonce = account_monitor.read().await.get_account(&signer.address()).unwrap().get_nonce();
@@ -51,9 +117,16 @@ async fn router_task_prepare<DB: DatabaseRef + Send + Sync + Clone + 'static>(
 async fn router_task_broadcast<DB: DatabaseRef + Send + Sync + Clone + 'static>(
     route_request: SwapComposeData<DB>,
     tx_compose_channel_tx: Broadcaster<MessageTxCompose>,
+    market: SharedState<Market>,
 ) -> Result<()> {
     debug!("router_task_broadcast started {}", route_request.swap);
 
+    // the class may be disabled while the swap was estimated
+    if let Some(pool_class) = disabled_pool_class(&market, &route_request.swap).await {
+        warn!(%pool_class, swap = %route_request.swap, "Pending swap through disabled pool class dropped before signing");
+        return Err(eyre!("POOL_CLASS_DISABLED"));
+    }
+
     let tx_compose = TxComposeData { swap: Some(route_request.swap), tips: route_request.tips, ..route_request.tx_compose };
 
     match tx_compose_channel_tx.send(MessageTxCompose::sign(tx_compose)) {
@@ -65,15 +138,58 @@ async fn router_task_broadcast<DB: DatabaseRef + Send + Sync + Clone + 'static>(
     }
 }
 
+/// Swaps to broadcast out of the collected ones. Swaps of a target block sharing pools after the same transactions would
+/// invalidate each other, only the most profitable non-conflicting subset of them is kept
+fn cluster_swaps<DB: Clone + Send + Sync + 'static>(
+    clusters: &mut OpportunityClusters,
+    pending: Vec<SwapComposeData<DB>>,
+) -> Vec<SwapComposeData<DB>> {
+    let mut blocks: BTreeMap<u64, Vec<SwapComposeData<DB>>> = BTreeMap::new();
+    for swap_compose_request in pending {
+        blocks.entry(swap_compose_request.tx_compose.next_block_number).or_default().push(swap_compose_request);
+    }
+
+    let mut selected = Vec::new();
+    for (block_number, swap_compose_requests) in blocks {
+        let candidates: Vec<OpportunityCandidate> = swap_compose_requests
+            .iter()
+            .map(|request| {
+                OpportunityCandidate::new(
+                    request.swap.get_pool_id_vec(),
+                    request.tx_compose.stuffing_txs_hashes.clone(),
+                    request.swap.abs_profit_eth(),
+                )
+            })
+            .collect();
+        let selected_idx = clusters.select(block_number, &candidates);
+        for (idx, swap_compose_request) in swap_compose_requests.into_iter().enumerate() {
+            if selected_idx.contains(&idx) {
+                selected.push(swap_compose_request);
+            } else {
+                debug!(block_number, swap = %swap_compose_request.swap, "Swap conflicting with more profitable swaps dropped");
+            }
+        }
+    }
+    selected
+}
+
 async fn swap_router_worker<DB: DatabaseRef + Clone + Send + Sync + 'static>(
     signers: SharedState<TxSigners>,
     account_monitor: SharedState<AccountNonceAndBalanceState>,
+    market: SharedState<Market>,
+    namespaces: Option<SharedState<StrategyNamespaces>>,
+    clustering_window: Option<Duration>,
     swap_compose_channel_rx: Broadcaster<MessageSwapCompose<DB>>,
     swap_compose_channel_tx: Broadcaster<MessageSwapCompose<DB>>,
     tx_compose_channel_tx: Broadcaster<MessageTxCompose>,
 ) -> WorkerResult {
     let mut compose_channel_rx: Receiver<MessageSwapCompose<DB>> = swap_compose_channel_rx.subscribe();
 
+    let mut clusters = OpportunityClusters::new();
+    // ready swaps collected until the end of the clustering window
+    let mut pending: Vec<SwapComposeData<DB>> = Vec::new();
+    let mut flush_at: Option<Instant> = None;
+
     info!("swap router worker started");
 
     loop {
@@ -91,17 +207,28 @@ async fn swap_router_worker<DB: DatabaseRef + Clone + Send + Sync + 'static>(
                                         swap_compose_channel_tx.clone(),
                                         signers.clone(),
                                         account_monitor.clone(),
+                                        market.clone(),
+                                        namespaces.clone(),
                                     )
                                 );
                             }
                             SwapComposeMessage::Ready(swap_compose_request)=>{
                                 debug!("MessageSwapComposeRequest::Ready received. stuffing: {:?} swap: {}", swap_compose_request.tx_compose.stuffing_txs_hashes, swap_compose_request.swap);
-                                tokio::task::spawn(
-                                    router_task_broadcast(
-                                        swap_compose_request,
-                                        tx_compose_channel_tx.clone(),
-                                    )
-                                );
+                                match clustering_window {
+                                    Some(clustering_window) => {
+                                        pending.push(swap_compose_request);
+                                        flush_at.get_or_insert_with(|| Instant::now() + clustering_window);
+                                    }
+                                    None => {
+                                        tokio::task::spawn(
+                                            router_task_broadcast(
+                                                swap_compose_request,
+                                                tx_compose_channel_tx.clone(),
+                                                market.clone(),
+                                            )
+                                        );
+                                    }
+                                }
                             }
                             _=>{}
 
@@ -110,6 +237,18 @@ async fn swap_router_worker<DB: DatabaseRef + Clone + Send + Sync + 'static>(
                     Err(e)=>{error!("compose_channel_rx {}",e)}
                 }
             }
+            _ = tokio::time::sleep_until(flush_at.unwrap_or_else(Instant::now)), if flush_at.is_some() => {
+                flush_at = None;
+                for swap_compose_request in cluster_swaps(&mut clusters, std::mem::take(&mut pending)) {
+                    tokio::task::spawn(
+                        router_task_broadcast(
+                            swap_compose_request,
+                            tx_compose_channel_tx.clone(),
+                            market.clone(),
+                        )
+                    );
+                }
+            }
         }
     }
 }
@@ -120,6 +259,11 @@ pub struct SwapRouterActor<DB: Send + Sync + Clone + 'static> {
     signers: Option<SharedState<TxSigners>>,
     #[accessor]
     account_nonce_balance: Option<SharedState<AccountNonceAndBalanceState>>,
+    #[accessor]
+    market: Option<SharedState<Market>>,
+    #[accessor]
+    namespaces: Option<SharedState<StrategyNamespaces>>,
+    clustering_window: Option<Duration>,
     #[consumer]
     swap_compose_channel_rx: Option<Broadcaster<MessageSwapCompose<DB>>>,
     #[producer]
@@ -136,6 +280,9 @@ where
         SwapRouterActor {
             signers: None,
             account_nonce_balance: None,
+            market: None,
+            namespaces: None,
+            clustering_window: None,
             swap_compose_channel_rx: None,
             swap_compose_channel_tx: None,
             tx_compose_channel_tx: None,
@@ -146,12 +293,21 @@ where
         Self { signers: Some(signers), ..self }
     }
 
+    /// Collect ready swaps for the window and broadcast only the most profitable ones not sharing pools, `None` broadcasts
+    /// every ready swap at once. Set from the strategy by `on_bc`
+    pub fn with_clustering_window(self, clustering_window: Option<Duration>) -> Self {
+        Self { clustering_window, ..self }
+    }
+
     pub fn on_bc(self, bc: &Blockchain, strategy: &Strategy<DB>) -> Self {
         Self {
             swap_compose_channel_rx: Some(strategy.swap_compose_channel()),
             swap_compose_channel_tx: Some(strategy.swap_compose_channel()),
             account_nonce_balance: Some(bc.nonce_and_balance()),
+            market: Some(bc.market()),
+            namespaces: Some(strategy.namespaces()),
             tx_compose_channel_tx: Some(bc.tx_compose_channel()),
+            clustering_window: strategy.clustering_window(),
             ..self
         }
     }
@@ -165,6 +321,9 @@ where
         let task = tokio::task::spawn(swap_router_worker(
             self.signers.clone().unwrap(),
             self.account_nonce_balance.clone().unwrap(),
+            self.market.clone().unwrap(),
+            self.namespaces.clone(),
+            self.clustering_window,
             self.swap_compose_channel_rx.clone().unwrap(),
             self.swap_compose_channel_tx.clone().unwrap(),
             self.tx_compose_channel_tx.clone().unwrap(),
@@ -176,3 +335,82 @@ where
         "SwapRouterActor"
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloy_primitives::{TxHash, U256};
+    use loom_types_entities::{MockPool, SwapAmountType, SwapLine, SwapPath, Token};
+    use loom_types_events::TxComposeMessageType;
+    use revm::db::EmptyDB;
+    use std::sync::Arc;
+
+    fn ready_swap(pools: [u8; 2], stuffing_tx: u8, profit: u64) -> MessageSwapCompose<EmptyDB> {
+        let token0 = Arc::new(Token::new(Address::repeat_byte(1)));
+        token0.set_eth_price(Some(U256::from(10).pow(U256::from(18))));
+        let token1 = Arc::new(Token::new(Address::repeat_byte(2)));
+        let pools = pools
+            .into_iter()
+            .map(|pool| MockPool::new(token0.get_address(), token1.get_address(), Address::repeat_byte(pool)))
+            .collect::<Vec<_>>();
+        let swap_line = SwapLine {
+            amount_in: SwapAmountType::Set(U256::from(1_000_000)),
+            amount_out: SwapAmountType::Set(U256::from(1_000_000 + profit)),
+            ..SwapLine::from(SwapPath::new(vec![token0.clone(), token1, token0], pools))
+        };
+        MessageSwapCompose::ready(SwapComposeData {
+            tx_compose: TxComposeData {
+                next_block_number: 100,
+                stuffing_txs_hashes: vec![TxHash::repeat_byte(stuffing_tx)],
+                ..TxComposeData::default()
+            },
+            swap: Swap::BackrunSwapLine(swap_line),
+            ..SwapComposeData::default()
+        })
+    }
+
+    /// Profits of the swaps sent to signing until none arrives for a while
+    async fn broadcast_profits(tx_compose_rx: &mut Receiver<MessageTxCompose>) -> Vec<U256> {
+        let mut profits = Vec::new();
+        while let Ok(Ok(msg)) = tokio::time::timeout(Duration::from_millis(200), tx_compose_rx.recv()).await {
+            if let TxComposeMessageType::Sign(tx_compose) = msg.inner {
+                profits.push(tx_compose.swap.unwrap().abs_profit_eth());
+            }
+        }
+        profits.sort_unstable();
+        profits
+    }
+
+    #[tokio::test]
+    async fn test_clustering_window() {
+        let swap_compose_channel: Broadcaster<MessageSwapCompose<EmptyDB>> = Broadcaster::new(10);
+        let tx_compose_channel: Broadcaster<MessageTxCompose> = Broadcaster::new(10);
+        let mut tx_compose_rx = tx_compose_channel.subscribe();
+
+        let worker = tokio::task::spawn(swap_router_worker(
+            SharedState::new(TxSigners::new()),
+            SharedState::new(AccountNonceAndBalanceState::new()),
+            SharedState::new(Market::default()),
+            None,
+            Some(Duration::from_millis(20)),
+            swap_compose_channel.clone(),
+            swap_compose_channel.clone(),
+            tx_compose_channel.clone(),
+        ));
+        tokio::time::sleep(Duration::from_millis(10)).await;
+
+        // the backrun of another tx through the same pool is kept, the less profitable backrun of the same tx is dropped
+        for swap in [ready_swap([3, 3], 1, 10), ready_swap([3, 3], 2, 5), ready_swap([3, 4], 1, 20)] {
+            swap_compose_channel.send(swap).unwrap();
+        }
+        assert_eq!(broadcast_profits(&mut tx_compose_rx).await, vec![U256::from(5), U256::from(20)]);
+
+        // the re-priced swap replaces its own submission, the swap conflicting with it is dropped
+        for swap in [ready_swap([3, 4], 1, 15), ready_swap([3, 3], 1, 10)] {
+            swap_compose_channel.send(swap).unwrap();
+        }
+        assert_eq!(broadcast_profits(&mut tx_compose_rx).await, vec![U256::from(15)]);
+
+        worker.abort();
+    }
+}
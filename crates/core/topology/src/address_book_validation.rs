@@ -0,0 +1,126 @@
+use std::fmt::{Display, Formatter};
+
+use alloy_primitives::{keccak256, Address, U256};
+use alloy_provider::network::Ethereum;
+use alloy_provider::Provider;
+use loom_defi_abi::uniswap2::IUniswapV2Factory;
+use loom_defi_abi::uniswap3::IUniswapV3Factory;
+use loom_defi_abi::IERC20;
+use loom_defi_address_book::{chain_address_book, AddressBookEntryKind};
+use loom_execution_multicaller::MulticallerDeployer;
+
+/// Address book entry that does not answer as expected on the configured chain
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AddressBookIssue {
+    pub name: String,
+    pub address: Address,
+    pub reason: String,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct AddressBookReport {
+    pub chain_id: u64,
+    pub checked: usize,
+    pub issues: Vec<AddressBookIssue>,
+    /// Multicallers with other code than the deployer of this build when the code hash is not checked
+    pub warnings: Vec<AddressBookIssue>,
+}
+
+impl AddressBookReport {
+    pub fn is_ok(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+impl Display for AddressBookReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "address book of chain {} : {} checked, {} wrong", self.chain_id, self.checked, self.issues.len())?;
+        for issue in self.issues.iter() {
+            write!(f, "\n  {} {} : {}", issue.name, issue.address, issue.reason)?;
+        }
+        for warning in self.warnings.iter() {
+            write!(f, "\n  {} {} : {} (not checked)", warning.name, warning.address, warning.reason)?;
+        }
+        Ok(())
+    }
+}
+
+/// eth_call the address book entries of the chain and the multicallers of the encoders. Tokens have to return their
+/// decimals, factories their fee setter or owner and multicallers have to be deployed. With `check_code_hash` multicallers
+/// also have to match the code hash of the deployer of this build, otherwise a mismatch is only reported as a warning
+pub async fn validate_address_book<P>(
+    client: &P,
+    chain_id: u64,
+    multicallers: &[(String, Address)],
+    check_code_hash: bool,
+) -> AddressBookReport
+where
+    P: Provider<Ethereum>,
+{
+    let mut report = AddressBookReport { chain_id, ..AddressBookReport::default() };
+
+    for entry in chain_address_book(chain_id) {
+        let result = match entry.kind {
+            AddressBookEntryKind::Token { decimals } => match IERC20::new(entry.address, client).decimals().call().await {
+                Ok(response) if response._0 == U256::from(decimals) => Ok(()),
+                Ok(response) => Err(format!("decimals {} instead of {decimals}", response._0)),
+                Err(e) => Err(format!("decimals() failed : {e}")),
+            },
+            AddressBookEntryKind::UniswapV2Factory => match IUniswapV2Factory::new(entry.address, client).feeToSetter().call().await {
+                Ok(_) => Ok(()),
+                Err(e) => Err(format!("feeToSetter() failed : {e}")),
+            },
+            AddressBookEntryKind::UniswapV3Factory => match IUniswapV3Factory::new(entry.address, client).owner().call().await {
+                Ok(_) => Ok(()),
+                Err(e) => Err(format!("owner() failed : {e}")),
+            },
+        };
+        report.checked += 1;
+        if let Err(reason) = result {
+            report.issues.push(AddressBookIssue { name: entry.name.to_string(), address: entry.address, reason });
+        }
+    }
+
+    let code_hash = MulticallerDeployer::new().code_hash();
+    for (name, address) in multicallers.iter() {
+        let name = format!("multicaller of encoder {name}");
+        report.checked += 1;
+        match client.get_code_at(*address).await {
+            Ok(code) if code.is_empty() => {
+                report.issues.push(AddressBookIssue { name, address: *address, reason: "no code".to_string() });
+            }
+            Ok(code) if keccak256(&code) != code_hash => {
+                let issue =
+                    AddressBookIssue { name, address: *address, reason: format!("code hash {} instead of {code_hash}", keccak256(&code)) };
+                if check_code_hash {
+                    report.issues.push(issue);
+                } else {
+                    report.warnings.push(issue);
+                }
+            }
+            Ok(_) => {}
+            Err(e) => report.issues.push(AddressBookIssue { name, address: *address, reason: format!("eth_getCode failed : {e}") }),
+        }
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_report() {
+        let mut report = AddressBookReport { chain_id: 1, checked: 2, ..AddressBookReport::default() };
+        report.warnings.push(AddressBookIssue { name: "multicaller".to_string(), address: Address::ZERO, reason: "code hash".to_string() });
+        assert!(report.is_ok());
+        report.issues.push(AddressBookIssue { name: "USDC".to_string(), address: Address::ZERO, reason: "no code".to_string() });
+        assert!(!report.is_ok());
+        assert_eq!(
+            report.to_string(),
+            "address book of chain 1 : 2 checked, 1 wrong\n  USDC 0x0000000000000000000000000000000000000000 : no code\n  multicaller \
+             0x0000000000000000000000000000000000000000 : code hash (not checked)"
+        );
+    }
+}
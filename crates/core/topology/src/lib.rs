@@ -1,5 +1,7 @@
+pub use address_book_validation::{validate_address_book, AddressBookIssue, AddressBookReport};
 pub use topology::Topology;
 pub use topology_config::*;
 
+mod address_book_validation;
 mod topology;
 mod topology_config;
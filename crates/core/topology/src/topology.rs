@@ -1,8 +1,12 @@
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 
+use crate::address_book_validation::validate_address_book;
 use crate::topology_config::TransportType;
 use crate::topology_config::{BroadcasterConfig, ClientConfig, EncoderConfig, EstimatorConfig, SignersConfig, TopologyConfig};
+use alloy_primitives::utils::parse_units;
 use alloy_primitives::Address;
 use alloy_provider::network::Ethereum;
 use alloy_provider::{Network, Provider, ProviderBuilder, RootProvider};
@@ -11,28 +15,38 @@ use alloy_transport_ipc::IpcConnect;
 use alloy_transport_ws::WsConnect;
 use eyre::{eyre, ErrReport, Result};
 use loom_broadcast_accounts::{InitializeSignersOneShotBlockingActor, NonceAndBalanceMonitorActor, TxSignersActor};
-use loom_broadcast_broadcaster::FlashbotsBroadcastActor;
-use loom_broadcast_flashbots::Flashbots;
+use loom_broadcast_broadcaster::{
+    BidStrategy, FlashbotsBroadcastActor, SequencerBroadcastActor, TimeboostBroadcastActor, TimeboostConfig, UserOperationBroadcastActor,
+    UserOperationConfig,
+};
+use loom_broadcast_flashbots::{Flashbots, SubmissionLedger};
 use loom_core_actors::{Accessor, Actor, Consumer, Producer, SharedState, WorkerResult};
 use loom_core_block_history::BlockHistoryActor;
 use loom_core_blockchain::{Blockchain, BlockchainState, Strategy};
 use loom_core_mempool::MempoolActor;
-use loom_defi_health_monitor::PoolHealthMonitorActor;
-use loom_defi_market::{HistoryPoolLoaderOneShotActor, NewPoolLoaderActor, PoolLoaderActor, ProtocolPoolLoaderOneShotActor};
+use loom_core_router::GasHedgeActor;
+use loom_defi_abi::erc4337::ENTRY_POINT_V07;
+use loom_defi_health_monitor::{BlockStatsActor, PoolHealthMonitorActor};
+use loom_defi_market::{
+    CuratedPoolLoaderOneShotActor, CurveParamsMonitorActor, DbPoolLoaderActor, HistoryPoolLoaderOneShotActor, MarketExportActor,
+    MarketViewPublisherActor, NewPoolLoaderActor, PoolCreationListenerActor, PoolEventsPublisherActor, PoolLoadCoalescer, PoolLoaderActor,
+    PoolStateRefresherActor, ProtocolPoolLoaderOneShotActor, ProxyMonitorActor, TickWordLoaderActor,
+};
 use loom_defi_pools::PoolLoadersBuilder;
 use loom_defi_preloader::MarketStatePreloadedOneShotActor;
-use loom_defi_price::PriceActor;
+use loom_defi_price::{PriceActor, PriceGraphActor};
 use loom_evm_db::DatabaseLoomExt;
-use loom_execution_estimator::{EvmEstimatorActor, GethEstimatorActor};
-use loom_execution_multicaller::MulticallerSwapEncoder;
+use loom_execution_estimator::{EvmEstimatorActor, GethEstimatorActor, NodeBundleValidator};
+use loom_execution_multicaller::{MulticallerSwapEncoder, OpcodeGasTable};
 use loom_node_actor_config::NodeBlockActorConfig;
 #[cfg(feature = "db-access")]
 use loom_node_db_access::RethDbAccessBlockActor;
 use loom_node_grpc::NodeExExGrpcActor;
-use loom_node_json_rpc::{NodeBlockActor, NodeMempoolActor};
+use loom_node_json_rpc::{BlockStateSource, ChainCapabilities, FlashblocksActor, NodeBlockActor, NodeMempoolActor, SubmissionMode};
+use loom_storage_archive::BlockArchiveActor;
+use loom_storage_db::{init_db_pool, run_migrations, DbPool};
 use loom_types_blockchain::{LoomDataTypes, LoomDataTypesEthereum};
-use loom_types_entities::pool_config::PoolsLoadingConfig;
-use loom_types_entities::{BlockHistoryState, MarketState, PoolLoaders, SwapEncoder, TxSigners};
+use loom_types_entities::{BlockHistoryState, MarketState, PoolClass, PoolLoaders, SwapEncoder, TxSigners};
 use revm::{Database, DatabaseCommit, DatabaseRef};
 use tokio::task::JoinHandle;
 use tracing::{error, info, warn};
@@ -45,7 +59,10 @@ pub struct Topology<
     LDT: LoomDataTypes = LoomDataTypesEthereum,
 > {
     config: TopologyConfig,
+    // file the config was loaded from, read again on config reload tasks
+    config_file: Option<String>,
     clients: HashMap<String, RootProvider<N>>,
+    capabilities: HashMap<String, ChainCapabilities>,
     blockchains: HashMap<String, Blockchain>,
     blockchain_states: HashMap<String, BlockchainState<DB>>,
     strategies: HashMap<String, Strategy<DB>>,
@@ -74,12 +91,23 @@ impl<
     > Topology<DB, E, P, Ethereum, LoomDataTypesEthereum>
 {
     pub fn from_config(config: TopologyConfig) -> Topology<DB, MulticallerSwapEncoder> {
-        let encoder = MulticallerSwapEncoder::default();
+        let gas_golf = config.encoders.values().any(|encoder| match encoder {
+            EncoderConfig::SwapStep(c) => c.gas_golf,
+        });
+        let unwrap_native_payout = config.encoders.values().any(|encoder| match encoder {
+            EncoderConfig::SwapStep(c) => c.unwrap_native_payout,
+        });
+        let mut encoder = MulticallerSwapEncoder::default().with_unwrap_native_payout(unwrap_native_payout);
+        if gas_golf {
+            encoder = encoder.with_gas_golf(OpcodeGasTable::default());
+        }
         let pool_loaders = Arc::new(PoolLoadersBuilder::<RootProvider>::new().build());
 
         Topology::<DB, MulticallerSwapEncoder> {
             config,
+            config_file: None,
             clients: HashMap::new(),
+            capabilities: HashMap::new(),
             blockchains: HashMap::new(),
             blockchain_states: HashMap::new(),
             strategies: HashMap::new(),
@@ -93,6 +121,11 @@ impl<
         }
     }
 
+    /// Read the pools loading configs from the file again on config reload tasks
+    pub fn with_config_file(self, config_file: String) -> Self {
+        Self { config_file: Some(config_file), ..self }
+    }
+
     pub fn with_swap_encoder<NE: SwapEncoder + Send + Sync + Clone + 'static>(
         self,
         swap_encoder: NE,
@@ -100,7 +133,9 @@ impl<
         //let swap_encoder = Arc::new(swap_encoder);
         Topology {
             config: self.config,
+            config_file: self.config_file,
             clients: self.clients,
+            capabilities: self.capabilities,
             blockchains: self.blockchains,
             blockchain_states: self.blockchain_states,
             strategies: self.strategies,
@@ -120,7 +155,9 @@ impl<
     ) -> Topology<DB, E, NP, Ethereum, LoomDataTypesEthereum> {
         Topology {
             config: self.config,
+            config_file: self.config_file,
             clients: self.clients,
+            capabilities: self.capabilities,
             blockchains: self.blockchains,
             blockchain_states: self.blockchain_states,
             strategies: self.strategies,
@@ -136,6 +173,7 @@ impl<
 
     pub async fn start_clients(self) -> Result<Self> {
         let mut clients = HashMap::new();
+        let mut capabilities = HashMap::new();
         for (name, v) in self.config.clients.iter() {
             let config_params = v.clone();
 
@@ -165,9 +203,19 @@ impl<
 
             let provider = ProviderBuilder::<_, _, Ethereum>::new().disable_recommended_fillers().on_client(client);
 
+            match ChainCapabilities::discover(&provider).await {
+                Ok(client_capabilities) => {
+                    client_capabilities.report(name);
+                    capabilities.insert(name.clone(), client_capabilities);
+                }
+                Err(e) => {
+                    warn!("Cannot discover chain capabilities of {name}, using defaults : {}", e);
+                }
+            }
+
             clients.insert(name.clone(), provider);
         }
-        Ok(Topology { clients, ..self })
+        Ok(Topology { clients, capabilities, ..self })
     }
 
     pub fn build_blockchains(self) -> Self {
@@ -194,9 +242,26 @@ impl<
 
         for (k, params) in self.config.blockchains.iter() {
             let blockchain = Blockchain::new(params.chain_id.unwrap_or(1) as u64);
+            if let Some(tip_recipients) = &params.tip_recipients {
+                let encoder = tip_recipients.encoder.as_ref().or(default_multicaller_encoder_name.as_ref());
+                let multicaller = encoder.and_then(|encoder| multicaller_encoders.get(encoder)).copied();
+                let tip_recipients = match tip_recipients.tip_recipients(blockchain.chain_id(), multicaller) {
+                    Ok(tip_recipients) => tip_recipients,
+                    Err(e) => panic!("Cannot load tip recipients of {k} : {e}"),
+                };
+                if let Ok(mut guard) = blockchain.tip_recipients().write() {
+                    *guard = tip_recipients;
+                }
+            }
             let market_state = MarketState::new(DB::default());
             let blockchain_state = BlockchainState::<DB>::new_with_market_state(market_state);
-            let strategy = Strategy::<DB>::new();
+            let namespaces = match params.strategy_namespaces() {
+                Ok(namespaces) => namespaces,
+                Err(e) => panic!("Cannot load strategy namespaces of {k} : {e}"),
+            };
+            let strategy = Strategy::<DB>::new()
+                .with_namespaces(namespaces)
+                .with_clustering_window(params.clustering_window_ms.map(Duration::from_millis));
 
             blockchains.insert(k.clone(), blockchain);
 
@@ -208,7 +273,7 @@ impl<
 
         for (name, params) in self.config.signers.iter() {
             match params {
-                SignersConfig::Env(_params) => {
+                SignersConfig::Env(_) | SignersConfig::Sponsored(_) => {
                     let signers_state = SharedState::new(TxSigners::new());
                     signers.insert(name.clone(), signers_state);
                     default_signer_name = Some(name.clone());
@@ -236,6 +301,57 @@ impl<
             return Err(eyre!("NO_CLIENTS_CONNECTED"));
         }
 
+        // wrong addresses for the chain fail on startup instead of as reverting swaps
+        for (k, params) in self.config.blockchains.iter() {
+            if !params.validate_address_book.unwrap_or(true) {
+                continue;
+            }
+            let is_chain = |blockchain: &Option<String>| blockchain.as_ref().or(self.default_blockchain_name.as_ref()) == Some(k);
+
+            // the chain is checked on the client of its node actor with the encoders of its estimators
+            let mut node_clients: Vec<(&String, Option<String>)> = self
+                .config
+                .actors
+                .node
+                .iter()
+                .flatten()
+                .filter(|(_, node_config)| is_chain(&node_config.blockchain))
+                .map(|(name, node_config)| (name, node_config.client.clone()))
+                .collect();
+            node_clients.sort();
+            let Some((_, client_name)) = node_clients.into_iter().next() else {
+                warn!("No node actor of blockchain {k}, address book not validated");
+                continue;
+            };
+            let encoder_names: BTreeSet<String> = self
+                .config
+                .actors
+                .estimator
+                .iter()
+                .flatten()
+                .filter_map(|(_, estimator_config)| match estimator_config {
+                    EstimatorConfig::Evm(c) => is_chain(&c.blockchain).then(|| c.encoder.clone()),
+                    EstimatorConfig::Geth(c) => is_chain(&c.blockchain).then(|| c.encoder.clone()),
+                })
+                .filter_map(|encoder| encoder.or(self.default_multicaller_encoder_name.clone()))
+                .collect();
+            let multicallers: Vec<(String, Address)> =
+                encoder_names.into_iter().filter_map(|name| self.multicaller_encoders.get(&name).map(|address| (name, *address))).collect();
+
+            let chain_id = self.get_blockchain(Some(k))?.chain_id();
+            let check_code_hash = params.validate_multicaller_code_hash.unwrap_or_default();
+            let report = validate_address_book(&self.get_client(client_name.as_ref())?, chain_id, &multicallers, check_code_hash).await;
+            if !report.is_ok() {
+                error!("{report}");
+                return Err(eyre!("ADDRESS_BOOK_VALIDATION_FAILED"));
+            }
+            if report.warnings.is_empty() {
+                info!("{report}");
+            } else {
+                warn!("{report}");
+            }
+        }
+
         for (k, _params) in self.config.blockchains.iter() {
             let blockchain = self.get_blockchain(Some(k))?;
             let blockchain_state = self.get_blockchain_state(Some(k))?;
@@ -299,35 +415,65 @@ impl<
                     panic!("PoolHealthMonitorActor error {}", e)
                 }
             }
+
+            info!("Starting block stats actor {k}");
+            let strategy = self.get_strategy(Some(k))?;
+            let mut block_stats_actor = BlockStatsActor::new();
+            match block_stats_actor
+                .access(blockchain.market())
+                .consume(blockchain.market_events_channel())
+                .consume(blockchain.new_block_state_update_channel())
+                .consume(strategy.swap_compose_channel())
+                .consume(blockchain.health_monitor_channel())
+                .produce(blockchain.block_stats_channel())
+                .produce(blockchain.influxdb_write_channel())
+                .start()
+            {
+                Ok(r) => {
+                    tasks.extend(r);
+                    info!("Block stats actor started")
+                }
+                Err(e) => {
+                    panic!("BlockStatsActor error {}", e)
+                }
+            }
         }
 
         for (name, params) in self.config.signers.iter() {
             let signers = self.get_signers(Some(name))?;
-            match params {
+            let (blockchain, mut initialize_signers_actor) = match params {
                 SignersConfig::Env(params) => {
                     info!("Starting initialize env signers actor {name}");
                     let blockchain = self.get_blockchain(params.blockchain.as_ref())?;
+                    (blockchain, InitializeSignersOneShotBlockingActor::new_from_encrypted_env())
+                }
+                SignersConfig::Sponsored(params) => {
+                    info!("Starting initialize sponsored signers actor {name}");
+                    let blockchain = self.get_blockchain(params.blockchain.as_ref())?;
+                    let initialize_signers_actor = InitializeSignersOneShotBlockingActor::new_from_encrypted_env_var(&params.gas_payer_env)
+                        .with_sponsored_env_var(&params.profit_account_env, params.sponsorship, blockchain.chain_id())
+                        .unwrap_or_else(|e| panic!("Cannot initialize sponsored signer {}", e));
+                    (blockchain, initialize_signers_actor)
+                }
+            };
 
-                    let mut initialize_signers_actor = InitializeSignersOneShotBlockingActor::new_from_encrypted_env();
-                    match initialize_signers_actor.access(signers.clone()).access(blockchain.nonce_and_balance()).start_and_wait() {
-                        Ok(_) => {
-                            info!("Signers have been initialized")
-                        }
-                        Err(e) => {
-                            panic!("Cannot initialize signers {}", e);
-                        }
-                    }
+            match initialize_signers_actor.access(signers.clone()).access(blockchain.nonce_and_balance()).start_and_wait() {
+                Ok(_) => {
+                    info!("Signers have been initialized")
+                }
+                Err(e) => {
+                    panic!("Cannot initialize signers {}", e);
+                }
+            }
 
-                    let mut signers_actor = TxSignersActor::new();
-                    match signers_actor.consume(blockchain.tx_compose_channel()).produce(blockchain.tx_compose_channel()).start() {
-                        Ok(r) => {
-                            tasks.extend(r);
-                            info!("Signers actor has been started")
-                        }
-                        Err(e) => {
-                            panic!("Cannot start signers actor {}", e)
-                        }
-                    }
+            let mut signers_actor = TxSignersActor::new();
+            match signers_actor.consume(blockchain.tx_compose_channel()).produce(blockchain.tx_compose_channel()).start() {
+                Ok(r) => {
+                    tasks.extend(r);
+                    info!("Signers actor has been started")
+                }
+                Err(e) => {
+                    panic!("Cannot start signers actor {}", e)
                 }
             }
         }
@@ -416,6 +562,12 @@ impl<
 
                 if client_config.db_path.is_none() {
                     let mut node_block_actor = NodeBlockActor::new(client, NodeBlockActorConfig::all_enabled());
+                    // nodes without the debug namespace cannot trace block state diffs
+                    if self.get_client_capabilities(params.client.as_ref()).map(|c| c.block_state_source()) == Some(BlockStateSource::Logs)
+                    {
+                        info!("Node actor {name} reads block state updates from logs");
+                        node_block_actor = node_block_actor.with_state_from_logs(blockchain.market());
+                    }
                     match node_block_actor
                         .produce(blockchain.new_block_headers_channel())
                         .produce(blockchain.new_block_with_tx_channel())
@@ -459,6 +611,24 @@ impl<
             }
         }
 
+        if let Some(flashblocks_actors) = &self.config.actors.flashblocks {
+            for (name, params) in flashblocks_actors {
+                let blockchain = self.get_blockchain(params.blockchain.as_ref())?;
+                let client = self.get_client(params.client.as_ref())?;
+                info!("Starting flashblocks actor {name}");
+                let mut flashblocks_actor = FlashblocksActor::new(client).with_name(name.clone()).on_bc(blockchain);
+                match flashblocks_actor.start() {
+                    Ok(r) => {
+                        tasks.extend(r);
+                        info!("Flashblocks actor started successfully {name} @ {}", blockchain.chain_id())
+                    }
+                    Err(e) => {
+                        panic!("{}", e)
+                    }
+                }
+            }
+        }
+
         if let Some(price_actors) = &self.config.actors.price {
             for (name, c) in price_actors {
                 let client = self.get_client(c.client.as_ref())?;
@@ -479,6 +649,96 @@ impl<
             warn!("No price actor in config")
         }
 
+        if let Some(price_graph_actors) = &self.config.actors.price_graph {
+            for (name, c) in price_graph_actors {
+                let blockchain = self.get_blockchain(c.blockchain.as_ref())?;
+                let blockchain_state = self.get_blockchain_state(c.blockchain.as_ref())?;
+                info!("Starting price graph actor");
+                let mut price_graph_actor = PriceGraphActor::new();
+                if let Some(usd_tokens) = &c.usd_tokens {
+                    price_graph_actor = price_graph_actor.with_usd_tokens(usd_tokens.clone());
+                }
+                if let Some(max_depth) = c.max_depth {
+                    price_graph_actor = price_graph_actor.with_max_depth(max_depth);
+                }
+                if let Some(dust_usd) = &c.dust_usd {
+                    price_graph_actor = price_graph_actor.with_dust_usd(parse_units(dust_usd, 18)?.get_absolute());
+                }
+                match price_graph_actor.on_bc(blockchain, blockchain_state).start() {
+                    Ok(r) => {
+                        tasks.extend(r);
+                        info!("Price graph actor has been initialized : {}", name)
+                    }
+                    Err(e) => {
+                        panic!("Cannot initialize price graph actor {} : {}", name, e);
+                    }
+                }
+            }
+        }
+
+        if let Some(archive_actors) = &self.config.actors.archive {
+            for (name, c) in archive_actors {
+                let blockchain = self.get_blockchain(c.blockchain.as_ref())?;
+                let blockchain_state = self.get_blockchain_state(c.blockchain.as_ref())?;
+                info!("Starting block archive actor {name}");
+                let mut block_archive_actor = BlockArchiveActor::new(&c.dir);
+                if let Some(flush_blocks) = c.flush_blocks {
+                    block_archive_actor = block_archive_actor.with_flush_blocks(flush_blocks);
+                }
+                if let Some(confirmations) = c.confirmations {
+                    block_archive_actor = block_archive_actor.with_confirmations(confirmations);
+                }
+                match block_archive_actor.on_bc(blockchain, blockchain_state).start() {
+                    Ok(r) => {
+                        tasks.extend(r);
+                        info!("Block archive actor has been initialized : {}", name)
+                    }
+                    Err(e) => {
+                        panic!("Cannot initialize block archive actor {} : {}", name, e);
+                    }
+                }
+            }
+        }
+
+        if let Some(market_export_actors) = &self.config.actors.market_export {
+            for (name, c) in market_export_actors {
+                let blockchain = self.get_blockchain(c.blockchain.as_ref())?;
+                let blockchain_state = self.get_blockchain_state(c.blockchain.as_ref())?;
+                info!("Starting market export actor {name}");
+                let market_export_actor = MarketExportActor::new(&c.socket).with_reserves(c.reserves).on_bc(blockchain, blockchain_state);
+                match market_export_actor.start() {
+                    Ok(r) => {
+                        tasks.extend(r);
+                        info!("Market export actor has been initialized : {}", name)
+                    }
+                    Err(e) => {
+                        panic!("Cannot initialize market export actor {} : {}", name, e);
+                    }
+                }
+            }
+        }
+
+        if let Some(gas_hedge_actors) = &self.config.actors.gas_hedge {
+            for (name, c) in gas_hedge_actors {
+                let blockchain = self.get_blockchain(c.blockchain.as_ref())?;
+                let blockchain_state = self.get_blockchain_state(c.blockchain.as_ref())?;
+                let strategy = self.get_strategy(c.blockchain.as_ref())?;
+                let signers = self.get_signers(c.signers.as_ref())?;
+                info!("Starting gas hedge actor {name}");
+                let gas_hedge_actor =
+                    GasHedgeActor::new(c.hedge.clone()).with_signers(signers).on_bc(blockchain, blockchain_state, strategy);
+                match gas_hedge_actor.start() {
+                    Ok(r) => {
+                        tasks.extend(r);
+                        info!("Gas hedge actor has been initialized : {}", name)
+                    }
+                    Err(e) => {
+                        panic!("Cannot initialize gas hedge actor {} : {}", name, e);
+                    }
+                }
+            }
+        }
+
         if let Some(node_balance_actors) = &self.config.actors.noncebalance {
             for (name, c) in node_balance_actors {
                 let client = self.get_client(c.client.as_ref())?;
@@ -512,9 +772,40 @@ impl<
                         let client = self.get_client(params.client.as_ref())?;
                         let blockchain = self.get_blockchain(params.blockchain.as_ref())?;
 
-                        let flashbots_client = Flashbots::new(client, "https://relay.flashbots.net", None).with_default_relays();
-                        let mut flashbots_actor = FlashbotsBroadcastActor::new(flashbots_client, true);
-                        match flashbots_actor.consume(blockchain.tx_compose_channel()).start() {
+                        // chains without bundle auctions get plain transactions sent to the sequencer
+                        let execution_profile = blockchain.execution_profile();
+                        let submission_mode = match self.get_client_capabilities(params.client.as_ref()) {
+                            Some(capabilities) => capabilities.submission_mode(execution_profile),
+                            None if execution_profile.uses_bundles() => SubmissionMode::Bundle,
+                            None => SubmissionMode::Public,
+                        };
+                        if submission_mode == SubmissionMode::Public {
+                            match SequencerBroadcastActor::new(client, execution_profile).consume(blockchain.tx_compose_channel()).start() {
+                                Ok(r) => {
+                                    tasks.extend(r);
+                                    info!(
+                                        "Sequencer broadcaster actor {name} started successfully for {} : {:?}",
+                                        blockchain.chain_id(),
+                                        execution_profile
+                                    )
+                                }
+                                Err(e) => {
+                                    panic!("Error starting sequencer broadcaster actor {name} for {} : {}", blockchain.chain_id(), e)
+                                }
+                            }
+                            continue;
+                        }
+
+                        let mut flashbots_client = Flashbots::new(client, "https://relay.flashbots.net", None)
+                            .with_default_relays()
+                            .with_retries(params.retries.unwrap_or_default());
+                        if let Some(ledger_path) = &params.ledger {
+                            flashbots_client = flashbots_client.with_ledger(SubmissionLedger::load(PathBuf::from(ledger_path))?);
+                        }
+                        let mut flashbots_actor = FlashbotsBroadcastActor::new(flashbots_client, true)
+                            .with_retarget_blocks(params.retarget_blocks.unwrap_or_default())
+                            .with_public_fallback(params.public_fallback.unwrap_or_default());
+                        match flashbots_actor.consume(blockchain.tx_compose_channel()).consume(blockchain.market_events_channel()).start() {
                             Ok(r) => {
                                 tasks.extend(r);
                                 info!("Flashbots broadcaster actor {name} started successfully for {}", blockchain.chain_id())
@@ -524,6 +815,57 @@ impl<
                             }
                         }
                     }
+                    BroadcasterConfig::Timeboost(params) => {
+                        let client = self.get_client(params.client.as_ref())?;
+                        let blockchain = self.get_blockchain(params.blockchain.as_ref())?;
+                        let signers = self.get_signers(params.signers.as_ref())?;
+
+                        let timeboost_config = TimeboostConfig {
+                            chain_id: blockchain.chain_id(),
+                            auction_contract: params.auction_contract,
+                            auctioneer_url: params.auctioneer_url.parse()?,
+                            sequencer_url: params.sequencer_url.parse()?,
+                            bid_strategy: BidStrategy {
+                                max_bid: parse_units(params.max_bid.as_deref().unwrap_or("0"), 18)?.get_absolute(),
+                                increment: parse_units(params.bid_increment.as_deref().unwrap_or("0"), 18)?.get_absolute(),
+                            },
+                        };
+                        let mut timeboost_actor = TimeboostBroadcastActor::new(client, timeboost_config);
+                        match timeboost_actor.access(signers).consume(blockchain.tx_compose_channel()).start() {
+                            Ok(r) => {
+                                tasks.extend(r);
+                                info!("Timeboost broadcaster actor {name} started successfully for {}", blockchain.chain_id())
+                            }
+                            Err(e) => {
+                                panic!("Error starting timeboost broadcaster actor {name} for {} : {}", blockchain.chain_id(), e)
+                            }
+                        }
+                    }
+                    BroadcasterConfig::UserOperation(params) => {
+                        let client = self.get_client(params.client.as_ref())?;
+                        let blockchain = self.get_blockchain(params.blockchain.as_ref())?;
+                        let signers = self.get_signers(params.signers.as_ref())?;
+
+                        let user_operation_config = UserOperationConfig {
+                            chain_id: blockchain.chain_id(),
+                            entry_point: params.entry_point.unwrap_or(ENTRY_POINT_V07),
+                            account: params.account,
+                            bundler_url: params.bundler_url.parse()?,
+                            paymaster_url: params.paymaster_url.as_ref().map(|paymaster_url| paymaster_url.parse()).transpose()?,
+                            receipt_timeout: Duration::from_secs(params.receipt_timeout_secs.unwrap_or(120)),
+                            allowed_calls: params.allowed_calls.clone(),
+                        };
+                        let mut user_operation_actor = UserOperationBroadcastActor::new(client, user_operation_config);
+                        match user_operation_actor.access(signers).consume(blockchain.ops_call_channel()).start() {
+                            Ok(r) => {
+                                tasks.extend(r);
+                                info!("User operation broadcaster actor {name} started successfully for {}", blockchain.chain_id())
+                            }
+                            Err(e) => {
+                                panic!("Error starting user operation broadcaster actor {name} for {} : {}", blockchain.chain_id(), e)
+                            }
+                        }
+                    }
                 }
             }
         } else {
@@ -532,6 +874,7 @@ impl<
 
         if let Some(pool_actors) = &self.config.actors.pools {
             let mut blockchains = HashMap::new();
+            let mut db_pool: Option<DbPool> = None;
 
             for (name, params) in pool_actors {
                 let client = self.get_client(params.client.as_ref())?;
@@ -539,8 +882,70 @@ impl<
                 let blockchain_state = self.get_blockchain_state(params.blockchain.as_ref())?;
 
                 let pool_loaders = self.pool_loaders.clone();
+                let pools_config = params.loading_config();
+                // loads of the same pool requested by the db and log based loaders are coalesced
+                let load_coalescer = PoolLoadCoalescer::new();
 
                 blockchains.insert(blockchain.chain_id(), blockchain);
+                if params.db {
+                    let db_pool = match &db_pool {
+                        Some(db_pool) => db_pool.clone(),
+                        None => {
+                            let Some(database) = &self.config.database else {
+                                return Err(eyre!("NO_DATABASE_CONFIG"));
+                            };
+                            run_migrations(database.url.clone()).await?;
+                            db_pool.insert(init_db_pool(database.url.clone()).await?).clone()
+                        }
+                    };
+
+                    info!("Starting db pool loader actor {name}");
+                    let mut db_pool_loader_actor =
+                        DbPoolLoaderActor::new(client.clone(), pool_loaders.clone(), pools_config.clone(), db_pool)
+                            .with_load_coalescer(load_coalescer.clone())
+                            .on_bc(blockchain, blockchain_state);
+                    if let Some(db_sync_blocks) = params.db_sync_blocks {
+                        db_pool_loader_actor = db_pool_loader_actor.with_sync_blocks(db_sync_blocks);
+                    }
+                    match db_pool_loader_actor.start() {
+                        Ok(r) => {
+                            tasks.extend(r);
+                            info!("Db pool loader actor started successfully {name}")
+                        }
+                        Err(e) => {
+                            panic!("DbPoolLoaderActor : {}", e)
+                        }
+                    }
+                }
+                if params.curated {
+                    info!("Starting curated pools loader {name}");
+
+                    let mut curated_pools_loader_actor =
+                        CuratedPoolLoaderOneShotActor::new(client.clone(), pool_loaders.clone(), pools_config.clone());
+                    if let Some(curated_url) = &params.curated_url {
+                        curated_pools_loader_actor = curated_pools_loader_actor.with_url(curated_url.clone());
+                    }
+                    match curated_pools_loader_actor
+                        .access(blockchain.market())
+                        .access(blockchain_state.market_state())
+                        .produce(blockchain.market_events_channel())
+                        .start()
+                    {
+                        Ok(r) => {
+                            // curated pools are loaded before log based discovery starts
+                            for handle in r {
+                                match handle.await {
+                                    Ok(Ok(_)) => info!("Curated pools loaded {name}"),
+                                    Ok(Err(e)) => error!("CuratedPoolLoaderOneShotActor : {}", e),
+                                    Err(e) => error!("CuratedPoolLoaderOneShotActor : {}", e),
+                                }
+                            }
+                        }
+                        Err(e) => {
+                            panic!("CuratedPoolLoaderOneShotActor : {}", e)
+                        }
+                    }
+                }
                 if params.history {
                     info!("Starting history pools loader {name}");
 
@@ -555,7 +960,7 @@ impl<
                         }
                     }
                 }
-                if params.protocol {
+                if params.protocol && pools_config.is_enabled(PoolClass::Curve) {
                     info!("Starting curve pools loader {name}");
 
                     let mut curve_pools_loader_actor = ProtocolPoolLoaderOneShotActor::new(client.clone(), pool_loaders.clone());
@@ -573,7 +978,12 @@ impl<
                 if params.new {
                     info!("Starting new pool loader actor {name}");
                     let mut new_pool_actor = NewPoolLoaderActor::new(pool_loaders.clone());
-                    match new_pool_actor.consume(blockchain.new_block_logs_channel()).produce(blockchain.tasks_channel()).start() {
+                    match new_pool_actor
+                        .access(blockchain.market())
+                        .consume(blockchain.new_block_logs_channel())
+                        .produce(blockchain.tasks_channel())
+                        .start()
+                    {
                         Ok(r) => {
                             tasks.extend(r);
                             info!("New pool actor started")
@@ -584,8 +994,35 @@ impl<
                     }
                 }
 
+                if params.listener {
+                    info!("Starting pool creation listener actor {name}");
+                    let mut pool_creation_listener_actor =
+                        PoolCreationListenerActor::new(client.clone(), pool_loaders.clone(), pools_config.clone());
+                    match pool_creation_listener_actor.access(blockchain.market()).produce(blockchain.tasks_channel()).start() {
+                        Ok(r) => {
+                            tasks.extend(r);
+                            info!("Pool creation listener actor started")
+                        }
+                        Err(e) => {
+                            panic!("PoolCreationListenerActor : {}", e)
+                        }
+                    }
+                }
+
                 info!("Starting pool loader actor {name}");
-                let mut pool_loader_actor = PoolLoaderActor::new(client.clone(), pool_loaders.clone(), PoolsLoadingConfig::new());
+                let mut pool_loader_actor =
+                    PoolLoaderActor::new(client.clone(), pool_loaders.clone(), pools_config).with_load_coalescer(load_coalescer);
+                if let Some(config_file) = self.config_file.clone() {
+                    let name = name.clone();
+                    pool_loader_actor = pool_loader_actor.with_pools_config_reader(Arc::new(move || {
+                        let config = TopologyConfig::load_from_file(config_file.clone())?;
+                        config
+                            .actors
+                            .pools
+                            .and_then(|pool_actors| pool_actors.get(&name).map(|params| params.loading_config()))
+                            .ok_or_else(|| eyre!("POOLS_CONFIG_NOT_FOUND"))
+                    }));
+                }
                 match pool_loader_actor
                     .access(blockchain.market())
                     .access(blockchain_state.market_state())
@@ -601,6 +1038,98 @@ impl<
                         panic!("PoolLoaderActor : {}", e)
                     }
                 }
+
+                info!("Starting tick word loader actor {name}");
+                let mut tick_word_loader_actor = TickWordLoaderActor::new(client.clone());
+                match tick_word_loader_actor
+                    .access(blockchain.market())
+                    .access(blockchain_state.market_state())
+                    .consume(blockchain.health_monitor_channel())
+                    .start()
+                {
+                    Ok(r) => {
+                        tasks.extend(r);
+                        info!("Tick word loader actor started successfully")
+                    }
+                    Err(e) => {
+                        panic!("TickWordLoaderActor : {}", e)
+                    }
+                }
+
+                info!("Starting market view publisher actor {name}");
+                let mut market_view_publisher_actor = MarketViewPublisherActor::new().on_bc(blockchain, blockchain_state);
+                match market_view_publisher_actor.start() {
+                    Ok(r) => {
+                        tasks.extend(r);
+                        info!("Market view publisher actor started successfully")
+                    }
+                    Err(e) => {
+                        panic!("MarketViewPublisherActor : {}", e)
+                    }
+                }
+
+                info!("Starting pool events publisher actor {name}");
+                let mut pool_events_publisher_actor = PoolEventsPublisherActor::new().on_bc(blockchain);
+                match pool_events_publisher_actor.start() {
+                    Ok(r) => {
+                        tasks.extend(r);
+                        info!("Pool events publisher actor started successfully")
+                    }
+                    Err(e) => {
+                        panic!("PoolEventsPublisherActor : {}", e)
+                    }
+                }
+
+                info!("Starting proxy monitor actor {name}");
+                let mut proxy_monitor_actor = ProxyMonitorActor::new()
+                    .with_revalidation(params.trusted_implementations.iter().copied().collect(), params.proxy_revalidate_blocks);
+                match proxy_monitor_actor.access(blockchain.market()).consume(blockchain.new_block_logs_channel()).start() {
+                    Ok(r) => {
+                        tasks.extend(r);
+                        info!("Proxy monitor actor started successfully")
+                    }
+                    Err(e) => {
+                        panic!("ProxyMonitorActor : {}", e)
+                    }
+                }
+
+                if pools_config.is_enabled(PoolClass::Curve) {
+                    info!("Starting curve params monitor actor {name}");
+                    let mut curve_params_monitor_actor = CurveParamsMonitorActor::new();
+                    match curve_params_monitor_actor
+                        .access(blockchain.market())
+                        .consume(blockchain.new_block_logs_channel())
+                        .produce(blockchain.tasks_channel())
+                        .start()
+                    {
+                        Ok(r) => {
+                            tasks.extend(r);
+                            info!("Curve params monitor actor started successfully")
+                        }
+                        Err(e) => {
+                            panic!("CurveParamsMonitorActor : {}", e)
+                        }
+                    }
+                }
+
+                if let Some(stale_blocks) = params.refresh_stale_blocks {
+                    info!("Starting pool state refresher actor {name}");
+                    let mut pool_state_refresher_actor = PoolStateRefresherActor::new(client.clone()).with_stale_blocks(stale_blocks);
+                    match pool_state_refresher_actor
+                        .access(blockchain.market())
+                        .access(blockchain_state.market_state())
+                        .consume(blockchain.market_events_channel())
+                        .start()
+                    {
+                        Ok(r) => {
+                            tasks.extend(r);
+                            info!("Pool state refresher actor started successfully")
+                        }
+                        Err(e) => {
+                            panic!("PoolStateRefresherActor : {}", e)
+                        }
+                    }
+                }
             }
         } else {
             warn!("No pool loader actors in config")
@@ -618,8 +1147,37 @@ impl<
 
                         let mut encoder = self.swap_encoder.clone();
                         encoder.set_address(multicaller_address);
-
-                        let mut evm_estimator_actor = EvmEstimatorActor::new_with_provider(encoder, client);
+                        encoder.set_revoke_approvals(self.revoke_approvals(params.encoder.as_ref()));
+                        encoder.set_execution_profile(blockchain.execution_profile());
+                        encoder.set_chain_preset(blockchain.chain_preset());
+                        encoder.set_tip_recipients(blockchain.tip_recipients(), params.builder.clone());
+
+                        let mut evm_estimator_actor = EvmEstimatorActor::new_with_provider(encoder, client)
+                            .with_chain_id(blockchain.chain_id())
+                            .with_sub_block_interval(blockchain.execution_profile().sub_block_interval());
+                        if let Some(threads) = params.threads {
+                            evm_estimator_actor = evm_estimator_actor.with_threads(threads);
+                        }
+                        if let Some(latency_budget_ms) = params.latency_budget_ms {
+                            evm_estimator_actor = evm_estimator_actor.with_latency_budget(Duration::from_millis(latency_budget_ms));
+                        }
+                        if let Some(validation) = &params.validation {
+                            let mut validator =
+                                NodeBundleValidator::new(self.get_client(validation.client.as_ref())?).with_method(validation.method);
+                            if let Some(timeout_ms) = validation.timeout_ms {
+                                validator = validator.with_timeout(Duration::from_millis(timeout_ms));
+                            }
+                            evm_estimator_actor = evm_estimator_actor.with_validator(validator);
+                        }
+                        if let Some(gas_limit) = &params.gas_limit {
+                            evm_estimator_actor = evm_estimator_actor.with_gas_limit_config(gas_limit.clone());
+                        }
+                        if let Some(public_fallback) = &params.public_fallback {
+                            evm_estimator_actor = evm_estimator_actor.with_public_fallback(public_fallback.clone());
+                        }
+                        if let Some(record_dir) = &params.record_dir {
+                            evm_estimator_actor = evm_estimator_actor.with_opportunity_recorder(PathBuf::from(record_dir));
+                        }
                         match evm_estimator_actor
                             .consume(strategy.swap_compose_channel())
                             .produce(strategy.swap_compose_channel())
@@ -644,10 +1202,18 @@ impl<
 
                         let mut encoder = self.swap_encoder.clone();
                         encoder.set_address(multicaller_address);
+                        encoder.set_revoke_approvals(self.revoke_approvals(params.encoder.as_ref()));
+                        encoder.set_execution_profile(blockchain.execution_profile());
+                        encoder.set_chain_preset(blockchain.chain_preset());
+                        encoder.set_tip_recipients(blockchain.tip_recipients(), params.builder.clone());
 
                         let flashbots_client = Arc::new(Flashbots::new(client, "https://relay.flashbots.net", None).with_default_relays());
 
-                        let mut geth_estimator_actor = GethEstimatorActor::new(flashbots_client, encoder);
+                        let mut geth_estimator_actor =
+                            GethEstimatorActor::new(flashbots_client, encoder).with_chain_id(blockchain.chain_id());
+                        if let Some(gas_limit) = &params.gas_limit {
+                            geth_estimator_actor = geth_estimator_actor.with_gas_limit_config(gas_limit.clone());
+                        }
                         match geth_estimator_actor.consume(strategy.swap_compose_channel()).produce(strategy.swap_compose_channel()).start()
                         {
                             Ok(r) => {
@@ -675,6 +1241,11 @@ impl<
         }
     }
 
+    /// Capabilities discovered on connect, `None` if the discovery failed
+    pub fn get_client_capabilities(&self, name: Option<&String>) -> Option<&ChainCapabilities> {
+        self.capabilities.get(name.unwrap_or(&"local".to_string()))
+    }
+
     pub fn get_client_config(&self, name: Option<&String>) -> Result<ClientConfig> {
         match self.config.clients.get(name.unwrap_or(&"local".to_string())) {
             Some(a) => Ok(a.clone()),
@@ -710,6 +1281,15 @@ impl<
         }
     }
 
+    /// Allowance revoking of the encoder, set per encoder in the config
+    fn revoke_approvals(&self, name: Option<&String>) -> bool {
+        let name = name.or(self.default_multicaller_encoder_name.as_ref());
+        match name.and_then(|name| self.config.encoders.get(name)) {
+            Some(EncoderConfig::SwapStep(c)) => c.revoke_approvals,
+            None => false,
+        }
+    }
+
     pub fn get_signers(&self, name: Option<&String>) -> Result<SharedState<TxSigners>> {
         match self.signers.get(name.unwrap_or(&self.default_multicaller_encoder_name.clone().unwrap())) {
             Some(a) => Ok(a.clone()),
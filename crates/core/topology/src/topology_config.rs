@@ -1,13 +1,77 @@
-use eyre::Result;
+use alloy_primitives::Address;
+use eyre::{eyre, Result};
+use loom_broadcast_broadcaster::OpsCallTarget;
 use loom_broadcast_flashbots::client::RelayConfig;
+use loom_execution_estimator::{GasLimitConfig, NodeValidationMethod, PublicFallbackConfig};
+use loom_rpc_state::ManualSwapConfig;
+use loom_types_entities::pool_config::PoolsLoadingConfig;
+use loom_types_entities::state_proof::StateLoadingMode;
+use loom_types_entities::{
+    GasHedgeConfig, NamespaceConfig, PathBuildConfig, PathGasBudget, PoolClass, SponsorshipMode, StrategyNamespaces, TipRecipient,
+    TipRecipients,
+};
 use serde::Deserialize;
 use std::collections::HashMap;
 use std::fs;
+use std::path::PathBuf;
 use strum_macros::Display;
+use toml_edit::{value, DocumentMut};
 
 #[derive(Clone, Debug, Deserialize)]
 pub struct BlockchainConfig {
     pub chain_id: Option<i64>,
+    /// Strategy namespaces with their own signers and limits by name
+    #[serde(default)]
+    pub namespaces: HashMap<String, NamespaceConfig>,
+    /// eth_call the address book entries of the chain and the multicallers of the encoders on startup, enabled if not set
+    pub validate_address_book: Option<bool>,
+    /// Fail the address book validation on multicallers with other code than the deployer of this build, only warned if not set
+    pub validate_multicaller_code_hash: Option<bool>,
+    /// Tip recipients of the builders, updated at runtime through the control API
+    pub tip_recipients: Option<TipRecipientsConfig>,
+    /// Collect ready swaps for the window in milliseconds and broadcast only the most profitable ones not sharing pools after
+    /// the same transactions, ready swaps are broadcast at once if not set
+    pub clustering_window_ms: Option<u64>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct TipRecipientsConfig {
+    /// Signer of the tip recipient updates, the recipients cannot be updated if not set
+    pub admin: Option<Address>,
+    /// Encoder whose multicaller the updates are signed for, the default encoder if not set
+    pub encoder: Option<String>,
+    /// File keeping the nonce of the last update across restarts
+    pub nonce_file: Option<PathBuf>,
+    /// Recipient of builders without one, `coinbase` or an address
+    #[serde(default)]
+    pub default: TipRecipient,
+    #[serde(default)]
+    pub builders: HashMap<String, TipRecipient>,
+}
+
+impl TipRecipientsConfig {
+    pub fn tip_recipients(&self, chain_id: u64, multicaller: Option<Address>) -> Result<TipRecipients> {
+        let mut tip_recipients =
+            TipRecipients::new(chain_id).with_verifying_contract(multicaller).with_admin(self.admin).with_default(self.default);
+        if let Some(nonce_file) = &self.nonce_file {
+            tip_recipients = tip_recipients.with_nonce_file(nonce_file.clone())?;
+        }
+        for (builder, recipient) in self.builders.iter() {
+            tip_recipients = tip_recipients.with_builder(builder.clone(), *recipient);
+        }
+        Ok(tip_recipients)
+    }
+}
+
+impl BlockchainConfig {
+    /// Namespaces registry of the blockchain strategy, fails on signers shared by namespaces
+    pub fn strategy_namespaces(&self) -> Result<StrategyNamespaces> {
+        let mut namespaces = StrategyNamespaces::new();
+        for (name, namespace_config) in self.namespaces.iter() {
+            namespaces.add(name.clone(), namespace_config.clone())?;
+        }
+        Ok(namespaces)
+    }
 }
 
 #[derive(Clone, Debug, Default, Deserialize, Display)]
@@ -95,11 +159,25 @@ pub struct EnvSingerConfig {
     pub blockchain: Option<String>,
 }
 
+/// Gas payer sponsoring the execution of swaps for a profit account, both keys are encrypted like the env signer key
+#[derive(Clone, Debug, Deserialize)]
+pub struct SponsoredSignerConfig {
+    #[serde(rename = "bc")]
+    pub blockchain: Option<String>,
+    /// Environment variable with the key of the gas payer
+    pub gas_payer_env: String,
+    /// Environment variable with the key of the profit account
+    pub profit_account_env: String,
+    pub sponsorship: SponsorshipMode,
+}
+
 #[derive(Clone, Debug, Deserialize)]
 #[serde(tag = "type")]
 pub enum SignersConfig {
     #[serde(rename = "env")]
     Env(EnvSingerConfig),
+    #[serde(rename = "sponsored")]
+    Sponsored(SponsoredSignerConfig),
 }
 
 #[derive(Clone, Debug, Deserialize)]
@@ -114,6 +192,15 @@ pub struct PreloaderConfig {
 #[derive(Clone, Debug, Deserialize)]
 pub struct SwapStepEncoderConfig {
     pub address: String,
+    /// Reset token allowances of pools to zero after swaps
+    #[serde(default)]
+    pub revoke_approvals: bool,
+    /// Choose hop amount calls by measured gas and order swap lines for warm address access
+    #[serde(default)]
+    pub gas_golf: bool,
+    /// Send the WETH output of a swap paid out to an EOA recipient as native ETH
+    #[serde(default)]
+    pub unwrap_native_payout: bool,
 }
 
 #[derive(Clone, Debug, Deserialize)]
@@ -157,6 +244,14 @@ pub struct FlashbotsBroadcasterConfig {
     pub client: Option<String>,
     pub smart: Option<bool>,
     pub relays: Option<Vec<FlashbotsRelayConfig>>,
+    /// Number of blocks a bundle that is not included is re-targeted to the next block
+    pub retarget_blocks: Option<u64>,
+    /// Send the public mempool fallback tx of a bundle that was not included in its target block
+    pub public_fallback: Option<bool>,
+    /// Number of times a failed send to a relay is retried
+    pub retries: Option<u32>,
+    /// File of the in flight bundle ledger preventing duplicate and conflicting submissions across restarts
+    pub ledger: Option<String>,
 }
 
 impl FlashbotsBroadcasterConfig {
@@ -165,11 +260,54 @@ impl FlashbotsBroadcasterConfig {
     }
 }
 
+#[derive(Clone, Debug, Deserialize)]
+pub struct TimeboostBroadcasterConfig {
+    #[serde(rename = "bc")]
+    pub blockchain: Option<String>,
+    pub client: Option<String>,
+    /// Signers holding the express lane controller as the first signer
+    pub signers: Option<String>,
+    /// Express lane auction contract of the chain
+    pub auction_contract: Address,
+    pub auctioneer_url: String,
+    /// Sequencer endpoint accepting express lane transactions
+    pub sequencer_url: String,
+    /// Largest bid per round in the bidding token, e.g. "0.01", no bids are placed if not set
+    pub max_bid: Option<String>,
+    /// Step a bid is raised by after a lost round and lowered by after a won one, in the bidding token
+    pub bid_increment: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct UserOperationBroadcasterConfig {
+    #[serde(rename = "bc")]
+    pub blockchain: Option<String>,
+    pub client: Option<String>,
+    /// Signers holding the owner of the smart account as the first signer
+    pub signers: Option<String>,
+    /// Smart account executing the operational calls
+    pub account: Address,
+    /// ERC-4337 v0.7 entry point, the canonical one if not set
+    pub entry_point: Option<Address>,
+    pub bundler_url: String,
+    /// ERC-7677 paymaster service sponsoring the gas, paid from the deposit of the account if not set
+    pub paymaster_url: Option<String>,
+    /// Seconds to wait for the inclusion of an operation before sending the next one, 120 if not set
+    pub receipt_timeout_secs: Option<u64>,
+    /// Targets and selectors the account may call, no call is sent if not set
+    #[serde(default)]
+    pub allowed_calls: Vec<OpsCallTarget>,
+}
+
 #[derive(Clone, Debug, Deserialize)]
 #[serde(tag = "type")]
 pub enum BroadcasterConfig {
     #[serde(rename = "flashbots")]
     Flashbots(FlashbotsBroadcasterConfig),
+    #[serde(rename = "timeboost")]
+    Timeboost(TimeboostBroadcasterConfig),
+    #[serde(rename = "user_operation")]
+    UserOperation(UserOperationBroadcasterConfig),
 }
 
 #[derive(Clone, Debug, Deserialize)]
@@ -178,6 +316,31 @@ pub struct EvmEstimatorConfig {
     #[serde(rename = "bc")]
     pub blockchain: Option<String>,
     pub encoder: Option<String>,
+    /// Number of simulation threads
+    pub threads: Option<usize>,
+    /// Time after the first estimation request of a block to publish the finished estimations ordered by profit.
+    /// Estimations are published as they finish if not set
+    pub latency_budget_ms: Option<u64>,
+    /// Verify estimated bundles on a trusted node before publishing
+    pub validation: Option<NodeValidationConfig>,
+    /// Margin over the simulated gas and per pool class floors of the transaction gas limit
+    pub gas_limit: Option<GasLimitConfig>,
+    /// Pre-encode a public mempool transaction reverting on loss next to the bundle
+    pub public_fallback: Option<PublicFallbackConfig>,
+    /// Builder the bundles are sent to, picks the tip recipient of the builder
+    pub builder: Option<String>,
+    /// Directory every ready opportunity is recorded to with a foundry test replaying it on a fork
+    pub record_dir: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct NodeValidationConfig {
+    /// Trusted node supporting the validation method
+    pub client: Option<String>,
+    #[serde(default)]
+    pub method: NodeValidationMethod,
+    /// Bundles not validated within the timeout are dropped
+    pub timeout_ms: Option<u64>,
 }
 
 #[derive(Clone, Debug, Deserialize)]
@@ -186,6 +349,10 @@ pub struct GethEstimatorConfig {
     #[serde(rename = "bc")]
     pub blockchain: Option<String>,
     pub encoder: Option<String>,
+    /// Margin over the simulated gas and per pool class floors of the transaction gas limit
+    pub gas_limit: Option<GasLimitConfig>,
+    /// Builder the bundles are sent to, picks the tip recipient of the builder
+    pub builder: Option<String>,
 }
 
 #[derive(Clone, Debug, Deserialize)]
@@ -205,16 +372,138 @@ pub struct PoolsConfig {
     pub history: bool,
     pub new: bool,
     pub protocol: bool,
+    /// Subscribe to pool creation events of the registered factories, requires a ws or ipc client
+    #[serde(default)]
+    pub listener: bool,
+    /// Pre-load the curated list of the top pools of the chain before log based discovery
+    #[serde(default)]
+    pub curated: bool,
+    /// Fetch the curated list from this url instead of the bundled one
+    pub curated_url: Option<String>,
+    /// Load the pools persisted in the database of the topology and persist discovered pools, disable flags and path scores
+    #[serde(default)]
+    pub db: bool,
+    /// Write disable flags and path scores back to the database every this number of blocks
+    pub db_sync_blocks: Option<u64>,
+    /// Pool classes to load, all if not set
+    pub classes: Option<Vec<PoolClass>>,
+    #[serde(default)]
+    pub disabled_classes: Vec<PoolClass>,
+    /// Load only pools created by these factories, pools without known factory are not affected
+    pub allowed_factories: Option<Vec<Address>>,
+    #[serde(default)]
+    pub denied_factories: Vec<Address>,
+    /// Token pairs never swapped in built paths, e.g. tokens with transfer hooks griefing searchers
+    #[serde(default)]
+    pub denied_pairs: Vec<(Address, Address)>,
+    /// Pools never used in built paths
+    #[serde(default)]
+    pub denied_pools: Vec<Address>,
+    /// Uniswap V3 pools loaded with their oracle observations for TWAP checks
+    #[serde(default)]
+    pub twap_pools: Vec<Address>,
+    /// Base tokens of WooFi pools, the address book tokens of the chain if not set
+    pub woofi_base_tokens: Option<Vec<Address>>,
+    /// Implementations upgraded proxies are trusted with at once, their pools are not disabled
+    #[serde(default)]
+    pub trusted_implementations: Vec<Address>,
+    /// Enable the pools of upgraded proxies again after this number of blocks without another upgrade, disabled until
+    /// restart if not set
+    pub proxy_revalidate_blocks: Option<u64>,
+    /// Re-fetch state of path pools not updated for this number of blocks, disabled if not set
+    pub refresh_stale_blocks: Option<u64>,
+    /// Build cycles up to five hops, longer than three only within the estimated gas budget
+    pub path_gas_budget: Option<PathGasBudget>,
+    /// Token and pool repetition rules of built cycles, by default no token but the anchor and no pool is repeated
+    #[serde(default)]
+    pub path_build: PathBuildConfig,
+    /// Read pool state by tracing calls or verified with storage proofs against the block state root
+    #[serde(default)]
+    pub state_loading: StateLoadingMode,
+}
+
+impl PoolsConfig {
+    pub fn loading_config(&self) -> PoolsLoadingConfig {
+        let mut config = PoolsLoadingConfig::new();
+        if let Some(classes) = &self.classes {
+            config = classes.iter().fold(config.disable_all(), |config, pool_class| config.enable(*pool_class));
+        }
+        config = self.disabled_classes.iter().fold(config, |config, pool_class| config.disable(*pool_class));
+        if let Some(allowed_factories) = &self.allowed_factories {
+            config = config.allow_factories(allowed_factories.iter().copied());
+        }
+        if let Some(path_gas_budget) = self.path_gas_budget {
+            config = config.with_path_gas_budget(path_gas_budget);
+        }
+        if let Some(woofi_base_tokens) = &self.woofi_base_tokens {
+            config = config.with_woofi_base_tokens(woofi_base_tokens.clone());
+        }
+        config
+            .deny_factories(self.denied_factories.iter().copied())
+            .deny_pairs(self.denied_pairs.iter().copied())
+            .deny_pools(self.denied_pools.iter().copied())
+            .with_twap_pools(self.twap_pools.iter().copied())
+            .with_path_build(self.path_build)
+            .with_state_loading(self.state_loading)
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct PriceGraphConfig {
+    #[serde(rename = "bc")]
+    pub blockchain: Option<String>,
+    /// Stablecoins averaged into the USD price, USDC, USDT and DAI if not set
+    pub usd_tokens: Option<Vec<Address>>,
+    /// Hops from WETH tokens are valued through
+    pub max_depth: Option<usize>,
+    /// USD value of the smallest token amount a swap hop may take or return, e.g. "0.01", 0.01 USD if not set
+    pub dust_usd: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ArchiveConfig {
+    #[serde(rename = "bc")]
+    pub blockchain: Option<String>,
+    /// Base directory of the Parquet files, partitioned by table and date
+    pub dir: String,
+    /// Blocks written to one file
+    pub flush_blocks: Option<usize>,
+    /// Blocks behind the head a block is archived at
+    pub confirmations: Option<u64>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct MarketExportConfig {
+    #[serde(rename = "bc")]
+    pub blockchain: Option<String>,
+    /// Path of the unix socket the sidecars connect to
+    pub socket: String,
+    /// Export the token balances of the pools
+    #[serde(default)]
+    pub reserves: bool,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct GasHedgeActorConfig {
+    #[serde(rename = "bc")]
+    pub blockchain: Option<String>,
+    pub signers: Option<String>,
+    #[serde(flatten)]
+    pub hedge: GasHedgeConfig,
 }
 
 #[derive(Clone, Debug, Deserialize)]
 pub struct WebserverConfig {
     pub host: String,
+    /// Bearer token of the routes changing the state of the bot, these routes are rejected if not set
+    pub auth_token: Option<String>,
+    /// Manual swaps through the web api, disabled if not set
+    pub manual_swaps: Option<ManualSwapConfig>,
 }
 
 impl Default for WebserverConfig {
     fn default() -> Self {
-        WebserverConfig { host: "127.0.0.1:3333".to_string() }
+        WebserverConfig { host: "127.0.0.1:3333".to_string(), auth_token: None, manual_swaps: None }
     }
 }
 
@@ -229,10 +518,19 @@ pub struct ActorConfig {
     pub node: Option<HashMap<String, BlockchainClientConfig>>,
     pub node_exex: Option<HashMap<String, ExExClientConfig>>,
     pub mempool: Option<HashMap<String, BlockchainClientConfig>>,
+    /// Subscribers to the preconfirmed Flashblocks transactions of nodes, websocket or ipc clients
+    pub flashblocks: Option<HashMap<String, BlockchainClientConfig>>,
     pub price: Option<HashMap<String, BlockchainClientConfig>>,
+    pub price_graph: Option<HashMap<String, PriceGraphConfig>>,
     pub pools: Option<HashMap<String, PoolsConfig>>,
     pub noncebalance: Option<HashMap<String, BlockchainClientConfig>>,
     pub estimator: Option<HashMap<String, EstimatorConfig>>,
+    /// Writers of block state diffs and updated pools to Parquet files for research
+    pub archive: Option<HashMap<String, ArchiveConfig>>,
+    /// Unix socket exports of the market view of every block for sidecar processes
+    pub market_export: Option<HashMap<String, MarketExportConfig>>,
+    /// Converters of token profits into ETH for the gas of the signers
+    pub gas_hedge: Option<HashMap<String, GasHedgeActorConfig>>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -254,12 +552,72 @@ impl TopologyConfig {
         let config: TopologyConfig = toml::from_str(&contents)?;
         Ok(config)
     }
+
+    /// Write the multicaller address of the encoder into the config file, keeping formatting and comments
+    pub fn set_encoder_address(file_name: String, encoder: &str, address: Address) -> Result<()> {
+        let contents = fs::read_to_string(&file_name)?;
+        let mut document: DocumentMut = contents.parse()?;
+
+        if document.get("encoders").and_then(|encoders| encoders.get(encoder)).is_none() {
+            return Err(eyre!("ENCODER_NOT_FOUND"));
+        }
+        document["encoders"][encoder]["address"] = value(address.to_string());
+
+        fs::write(file_name, document.to_string())?;
+        Ok(())
+    }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
 
+    #[test]
+    fn test_set_encoder_address() -> Result<()> {
+        let file_name = std::env::temp_dir().join("loom_test_set_encoder_address.toml").to_string_lossy().to_string();
+        fs::write(
+            &file_name,
+            "# encoders\n[encoders]\nmainnet = { type = \"swapstep\", address = \"0x0000000000000000000000000000000000000000\" }\n",
+        )?;
+
+        let address = Address::repeat_byte(0x11);
+        TopologyConfig::set_encoder_address(file_name.clone(), "mainnet", address)?;
+        assert!(TopologyConfig::set_encoder_address(file_name.clone(), "unknown", address).is_err());
+
+        let contents = fs::read_to_string(&file_name)?;
+        assert!(contents.starts_with("# encoders"));
+        let document: toml::Table = toml::from_str(&contents)?;
+        assert_eq!(document["encoders"]["mainnet"]["address"].as_str(), Some(address.to_string().as_str()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_estimator_validation() -> Result<()> {
+        let config: EstimatorConfig = toml::from_str(
+            "type = \"evm\"\nencoder = \"mainnet\"\nvalidation = { client = \"local\", method = \"trace_call_many\", timeout_ms = 50 }",
+        )?;
+        let EstimatorConfig::Evm(params) = config else { panic!("EVM_ESTIMATOR_EXPECTED") };
+        let validation = params.validation.unwrap();
+        assert_eq!(validation.method, NodeValidationMethod::TraceCallMany);
+        assert_eq!(validation.timeout_ms, Some(50));
+        Ok(())
+    }
+
+    #[test]
+    fn test_estimator_gas_limit() -> Result<()> {
+        let config: EstimatorConfig =
+            toml::from_str("type = \"evm\"\nencoder = \"mainnet\"\ngas_limit = { margin_bps = 500, class_floors = { curve = 300000 } }")?;
+        let EstimatorConfig::Evm(params) = config else { panic!("EVM_ESTIMATOR_EXPECTED") };
+        let gas_limit = params.gas_limit.unwrap();
+        assert_eq!(gas_limit.margin_bps, 500);
+        assert_eq!(gas_limit.class_floors.get(&PoolClass::Curve), Some(&300_000));
+
+        let config: EstimatorConfig = toml::from_str("type = \"geth\"\ngas_limit = {}")?;
+        let EstimatorConfig::Geth(params) = config else { panic!("GETH_ESTIMATOR_EXPECTED") };
+        assert_eq!(params.gas_limit.unwrap().margin_bps, 1_000);
+        Ok(())
+    }
+
     #[test]
     fn test_load() {
         match TopologyConfig::load_from_file("../../config.toml".to_string()) {
@@ -3,6 +3,9 @@ use alloy::sol_types::{SolCall, SolInterface};
 
 use crate::balancer::IVault;
 use crate::lido::{IStEth, IWStEth};
+use crate::solidly::ISolidlyPair;
+use crate::uniswap2::IUniswapV2Pair;
+use crate::uniswap_periphery::ISwapRouter02;
 use crate::{IMultiCaller, IERC20, IWETH};
 
 pub struct AbiEncoderHelper;
@@ -24,10 +27,37 @@ impl AbiEncoderHelper {
         IERC20::IERC20Calls::balanceOf(IERC20::balanceOfCall { account }).abi_encode().into()
     }
 
+    pub fn encode_erc20_allowance(owner: Address, spender: Address) -> Bytes {
+        IERC20::IERC20Calls::allowance(IERC20::allowanceCall { owner, spender }).abi_encode().into()
+    }
+
     pub fn encode_erc20_approve(spender: Address, amount: U256) -> Bytes {
         IERC20::IERC20Calls::approve(IERC20::approveCall { spender, amount }).abi_encode().into()
     }
 
+    /// Empty multicall of the Uniswap SwapRouter02, reverting once the block timestamp is after the deadline
+    pub fn encode_deadline_check(deadline: U256) -> Bytes {
+        ISwapRouter02::ISwapRouter02Calls::multicall(ISwapRouter02::multicallCall { deadline, data: vec![] }).abi_encode().into()
+    }
+
+    /// Sends the whole router balance of `token` to `recipient`, reverting when it is below `amount_minimum`
+    pub fn encode_sweep_token(token: Address, amount_minimum: U256, recipient: Address) -> Bytes {
+        ISwapRouter02::ISwapRouter02Calls::sweepToken(ISwapRouter02::sweepTokenCall { token, amountMinimum: amount_minimum, recipient })
+            .abi_encode()
+            .into()
+    }
+
+    /// Unwraps the whole router WETH balance to `recipient`, reverting when it is below `amount_minimum`
+    pub fn encode_unwrap_weth9(amount_minimum: U256, recipient: Address) -> Bytes {
+        ISwapRouter02::ISwapRouter02Calls::unwrapWETH9(ISwapRouter02::unwrapWETH9Call { amountMinimum: amount_minimum, recipient })
+            .abi_encode()
+            .into()
+    }
+
+    pub fn encode_uniswap2_sync() -> Bytes {
+        IUniswapV2Pair::IUniswapV2PairCalls::sync(IUniswapV2Pair::syncCall {}).abi_encode().into()
+    }
+
     pub fn encode_multicaller_transfer_tips_weth(min_balance: U256, tips: U256, owner: Address) -> Bytes {
         IMultiCaller::IMultiCallerCalls::transferTipsMinBalanceWETH(IMultiCaller::transferTipsMinBalanceWETHCall {
             min_balance,
@@ -48,6 +78,16 @@ impl AbiEncoderHelper {
         .into()
     }
 
+    pub fn encode_multicaller_transfer_tips_no_payout(token: Address, min_balance: U256, tips: U256) -> Bytes {
+        IMultiCaller::IMultiCallerCalls::transferTipsMinBalanceNoPayout(IMultiCaller::transferTipsMinBalanceNoPayoutCall {
+            token,
+            min_balance,
+            tips,
+        })
+        .abi_encode()
+        .into()
+    }
+
     pub fn encode_multicaller_uni2_get_in_amount(token_from: Address, token_to: Address, pool: Address, amount: U256, fee: U256) -> Bytes {
         let call = if fee.is_zero() || fee.to::<u32>() == 9970 {
             if token_from > token_to {
@@ -80,6 +120,10 @@ impl AbiEncoderHelper {
         call.abi_encode().into()
     }
 
+    pub fn encode_solidly_get_amount_out(amount_in: U256, token_in: Address) -> Bytes {
+        ISolidlyPair::getAmountOutCall { amountIn: amount_in, tokenIn: token_in }.abi_encode().into()
+    }
+
     pub fn encode_multicaller_log_arg(value: U256) -> Bytes {
         IMultiCaller::logArgCall { value }.abi_encode().into()
     }
@@ -107,6 +151,13 @@ impl AbiEncoderHelper {
         Bytes::from(call.abi_encode())
     }
 
+    /// Flash loan of several tokens in one call, the vault requires `tokens` to be sorted and unique
+    pub fn encode_balancer_flashloan_tokens(tokens: Vec<Address>, amounts: Vec<U256>, user_data: Bytes, recipient: Address) -> Bytes {
+        let call = IVault::IVaultCalls::flashLoan(IVault::flashLoanCall { recipient, tokens, amounts, userData: user_data });
+
+        Bytes::from(call.abi_encode())
+    }
+
     pub fn encode_wsteth_wrap(st_eth_amount: U256) -> Bytes {
         let call = IWStEth::IWStEthCalls::wrap(IWStEth::wrapCall { stETHAmount: st_eth_amount });
 
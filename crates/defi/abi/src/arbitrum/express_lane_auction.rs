@@ -0,0 +1,46 @@
+use alloy::sol;
+
+sol! {
+    /// Sealed bid for the express lane of a round, signed with EIP-712 in the domain of the auction contract
+    #[derive(Debug, PartialEq, Eq)]
+    struct Bid {
+        uint64 round;
+        address expressLaneController;
+        uint256 amount;
+    }
+}
+
+sol! {
+    #[sol(abi = true, rpc)]
+    #[derive(Debug, PartialEq, Eq)]
+    interface IExpressLaneAuction {
+        struct ELCRound {
+            address expressLaneController;
+            uint64 round;
+        }
+
+        event AuctionResolved(
+            bool indexed isMultiBidAuction,
+            uint64 round,
+            address indexed firstPriceBidder,
+            address indexed firstPriceExpressLaneController,
+            uint256 firstPriceAmount,
+            uint256 price,
+            uint64 roundStartTimestamp,
+            uint64 roundEndTimestamp
+        );
+
+        function roundTimingInfo() external view returns (
+            int64 offsetTimestamp,
+            uint64 roundDurationSeconds,
+            uint64 auctionClosingSeconds,
+            uint64 reserveSubmissionSeconds
+        );
+        function currentRound() external view returns (uint64);
+        function reservePrice() external view returns (uint256);
+        function biddingToken() external view returns (address);
+        function balanceOf(address account) external view returns (uint256);
+        function resolvedRounds() external view returns (ELCRound memory, ELCRound memory);
+        function deposit(uint256 amount) external;
+    }
+}
@@ -0,0 +1,3 @@
+pub use express_lane_auction::*;
+
+mod express_lane_auction;
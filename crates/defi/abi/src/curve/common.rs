@@ -35,3 +35,35 @@ sol! {
         function balances(int128) external view returns (uint256);
     }
 }
+
+sol! {
+    #[sol(abi = true, rpc)]
+    #[derive(Debug, PartialEq, Eq)]
+    interface ICurveStableParams {
+        event RampA(uint256 old_A, uint256 new_A, uint256 initial_time, uint256 future_time);
+        event StopRampA(uint256 A, uint256 t);
+        event CommitNewFee(uint256 indexed deadline, uint256 fee, uint256 admin_fee);
+        event NewFee(uint256 indexed deadline, uint256 fee, uint256 admin_fee);
+        event ApplyNewFee(uint256 fee, uint256 offpeg_fee_multiplier);
+    }
+}
+
+sol! {
+    #[sol(abi = true, rpc)]
+    #[derive(Debug, PartialEq, Eq)]
+    interface ICurveCryptoParams {
+        event RampAgamma(uint256 initial_A, uint256 future_A, uint256 initial_gamma, uint256 future_gamma, uint256 initial_time, uint256 future_time);
+        event StopRampA(uint256 current_A, uint256 current_gamma, uint256 time);
+        event NewParameters(uint256 admin_fee, uint256 mid_fee, uint256 out_fee, uint256 fee_gamma, uint256 allowed_extra_profit, uint256 adjustment_step, uint256 ma_half_time);
+    }
+}
+
+sol! {
+    #[sol(abi = true, rpc)]
+    #[derive(Debug, PartialEq, Eq)]
+    interface ICurveLiquidity {
+        function lp_token() external view returns (address);
+        function token() external view returns (address);
+        function fee() external view returns (uint256);
+    }
+}
@@ -0,0 +1,24 @@
+use alloy::primitives::{address, Address};
+use alloy::sol;
+
+/// ERC-4337 v0.7 entry point, deployed at the same address on every chain
+pub const ENTRY_POINT_V07: Address = address!("0000000071727De22E5E9d8BAf0edAc6f37da032");
+
+sol! {
+    #[sol(abi = true, rpc)]
+    #[derive(Debug, PartialEq, Eq)]
+    interface IEntryPoint {
+        function getNonce(address sender, uint192 key) external view returns (uint256 nonce);
+        function balanceOf(address account) external view returns (uint256);
+    }
+}
+
+sol! {
+    /// Smart account executing the calls of its user operations, as the eth-infinitism SimpleAccount
+    #[sol(abi = true, rpc)]
+    #[derive(Debug, PartialEq, Eq)]
+    interface ISimpleAccount {
+        function execute(address dest, uint256 value, bytes calldata func) external;
+        function owner() external view returns (address);
+    }
+}
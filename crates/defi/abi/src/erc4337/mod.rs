@@ -0,0 +1,3 @@
+pub use entry_point::*;
+
+mod entry_point;
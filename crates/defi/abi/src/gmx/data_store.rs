@@ -0,0 +1,11 @@
+use alloy::sol;
+
+sol! {
+    #[sol(abi=true,rpc)]
+    #[derive(Debug, PartialEq, Eq)]
+    interface IGmxDataStore {
+        function getUint(bytes32 key) external view returns (uint256);
+        function getAddress(bytes32 key) external view returns (address);
+        function getBool(bytes32 key) external view returns (bool);
+    }
+}
@@ -0,0 +1,7 @@
+pub use data_store::IGmxDataStore;
+pub use price_feed::IChainlinkPriceFeed;
+pub use reader::{IGmxReader, MarketPrices, MarketProps, PriceProps, SwapFees};
+
+mod data_store;
+mod price_feed;
+mod reader;
@@ -0,0 +1,10 @@
+use alloy::sol;
+
+sol! {
+    #[sol(abi=true,rpc)]
+    #[derive(Debug, PartialEq, Eq)]
+    interface IChainlinkPriceFeed {
+        function decimals() external view returns (uint8);
+        function latestRoundData() external view returns (uint80 roundId, int256 answer, uint256 startedAt, uint256 updatedAt, uint80 answeredInRound);
+    }
+}
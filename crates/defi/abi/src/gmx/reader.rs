@@ -0,0 +1,49 @@
+use alloy::sol;
+
+sol! {
+    #[derive(Debug, PartialEq, Eq)]
+    struct MarketProps {
+        address marketToken;
+        address indexToken;
+        address longToken;
+        address shortToken;
+    }
+
+    #[derive(Debug, PartialEq, Eq)]
+    struct PriceProps {
+        uint256 min;
+        uint256 max;
+    }
+
+    #[derive(Debug, PartialEq, Eq)]
+    struct MarketPrices {
+        PriceProps indexTokenPrice;
+        PriceProps longTokenPrice;
+        PriceProps shortTokenPrice;
+    }
+
+    #[derive(Debug, PartialEq, Eq)]
+    struct SwapFees {
+        uint256 feeReceiverAmount;
+        uint256 feeAmountForPool;
+        uint256 amountAfterFees;
+        address uiFeeReceiver;
+        uint256 uiFeeReceiverFactor;
+        uint256 uiFeeAmount;
+    }
+
+    #[sol(abi=true,rpc)]
+    #[derive(Debug, PartialEq, Eq)]
+    interface IGmxReader {
+        function getMarket(address dataStore, address key) external view returns (MarketProps memory);
+        function getMarkets(address dataStore, uint256 start, uint256 end) external view returns (MarketProps[] memory);
+        function getSwapAmountOut(
+            address dataStore,
+            MarketProps memory market,
+            MarketPrices memory prices,
+            address tokenIn,
+            uint256 amountIn,
+            address uiFeeReceiver
+        ) external view returns (uint256 amountOut, int256 impactAmount, SwapFees memory fees);
+    }
+}
@@ -1,21 +1,31 @@
 pub use abi_helpers::AbiEncoderHelper;
 pub use erc20::IERC20;
 pub use multicaller::IMultiCaller;
+pub use proxy::IERC1967;
+pub use token_hooks::{IERC1820Registry, IERC165};
 pub use weth::IWETH;
 
 mod abi_helpers;
 
+pub mod arbitrum;
 pub mod balancer;
 pub mod curve;
 mod erc20;
+pub mod erc4337;
+pub mod gmx;
 pub mod lido;
 pub mod maverick;
 pub mod multicaller;
+pub mod pendle;
+mod proxy;
+pub mod solidly;
+mod token_hooks;
 pub mod uniswap2;
 pub mod uniswap3;
 pub mod uniswap4;
 pub mod uniswap_periphery;
 mod weth;
+pub mod woofi;
 
 pub mod maverick2;
 
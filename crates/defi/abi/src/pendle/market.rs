@@ -0,0 +1,27 @@
+use alloy::sol;
+
+sol! {
+    #[derive(Debug, PartialEq, Eq)]
+    struct MarketState {
+        int256 totalPt;
+        int256 totalSy;
+        int256 totalLp;
+        address treasury;
+        int256 scalarRoot;
+        uint256 expiry;
+        uint256 lnFeeRateRoot;
+        uint256 reserveFeePercent;
+        uint256 lastLnImpliedRate;
+    }
+
+    #[sol(abi=true,rpc)]
+    #[derive(Debug, PartialEq, Eq)]
+    interface IPendleMarket {
+        event Swap(address indexed caller, address indexed receiver, int256 netPtOut, int256 netSyOut, uint256 netSyFee, uint256 netSyToReserve);
+
+        function readTokens() external view returns (address _SY, address _PT, address _YT);
+        function expiry() external view returns (uint256);
+        function readState(address router) external view returns (MarketState memory market);
+        function isExpired() external view returns (bool);
+    }
+}
@@ -0,0 +1,7 @@
+pub use market::{IPendleMarket, MarketState};
+pub use router::{ApproxParams, FillOrderParams, IPendleRouter, LimitOrderData, Order, SwapData, TokenInput, TokenOutput};
+pub use sy::{IPendleYieldToken, IStandardizedYield};
+
+mod market;
+mod router;
+mod sy;
@@ -0,0 +1,91 @@
+use alloy::sol;
+
+sol! {
+    #[derive(Debug, PartialEq, Eq)]
+    struct ApproxParams {
+        uint256 guessMin;
+        uint256 guessMax;
+        uint256 guessOffchain;
+        uint256 maxIteration;
+        uint256 eps;
+    }
+
+    #[derive(Debug, PartialEq, Eq)]
+    struct SwapData {
+        uint8 swapType;
+        address extRouter;
+        bytes extCalldata;
+        bool needScale;
+    }
+
+    #[derive(Debug, PartialEq, Eq)]
+    struct TokenInput {
+        address tokenIn;
+        uint256 netTokenIn;
+        address tokenMintSy;
+        address pendleSwap;
+        SwapData swapData;
+    }
+
+    #[derive(Debug, PartialEq, Eq)]
+    struct TokenOutput {
+        address tokenOut;
+        uint256 minTokenOut;
+        address tokenRedeemSy;
+        address pendleSwap;
+        SwapData swapData;
+    }
+
+    #[derive(Debug, PartialEq, Eq)]
+    struct Order {
+        uint256 salt;
+        uint256 expiry;
+        uint256 nonce;
+        uint8 orderType;
+        address token;
+        address YT;
+        address maker;
+        address receiver;
+        uint256 makingAmount;
+        uint256 lnImpliedRate;
+        uint256 failSafeRate;
+        bytes permit;
+    }
+
+    #[derive(Debug, PartialEq, Eq)]
+    struct FillOrderParams {
+        Order order;
+        bytes signature;
+        uint256 makingAmount;
+    }
+
+    #[derive(Debug, PartialEq, Eq)]
+    struct LimitOrderData {
+        address limitRouter;
+        uint256 epsSkipMarket;
+        FillOrderParams[] normalFills;
+        FillOrderParams[] flashFills;
+        bytes optData;
+    }
+
+    #[sol(abi=true,rpc)]
+    #[derive(Debug, PartialEq, Eq)]
+    interface IPendleRouter {
+        function swapExactTokenForPt(
+            address receiver,
+            address market,
+            uint256 minPtOut,
+            ApproxParams calldata guessPtOut,
+            TokenInput calldata input,
+            LimitOrderData calldata limit
+        ) external payable returns (uint256 netPtOut, uint256 netSyFee, uint256 netSyInterm);
+
+        function swapExactPtForToken(
+            address receiver,
+            address market,
+            uint256 exactPtIn,
+            TokenOutput calldata output,
+            LimitOrderData calldata limit
+        ) external returns (uint256 netTokenOut, uint256 netSyFee, uint256 netSyInterm);
+    }
+}
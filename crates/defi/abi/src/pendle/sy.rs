@@ -0,0 +1,18 @@
+use alloy::sol;
+
+sol! {
+    #[sol(abi=true,rpc)]
+    #[derive(Debug, PartialEq, Eq)]
+    interface IStandardizedYield {
+        function exchangeRate() external view returns (uint256 res);
+        function yieldToken() external view returns (address);
+        function getTokensIn() external view returns (address[] memory res);
+        function getTokensOut() external view returns (address[] memory res);
+    }
+
+    #[sol(abi=true,rpc)]
+    #[derive(Debug, PartialEq, Eq)]
+    interface IPendleYieldToken {
+        function pyIndexStored() external view returns (uint256);
+    }
+}
@@ -0,0 +1,11 @@
+use alloy::sol;
+
+sol! {
+    #[sol(abi = true, rpc)]
+    #[derive(Debug, PartialEq, Eq)]
+    interface IERC1967 {
+        event Upgraded(address indexed implementation);
+        event AdminChanged(address previousAdmin, address newAdmin);
+        event BeaconUpgraded(address indexed beacon);
+    }
+}
@@ -0,0 +1,21 @@
+use alloy::sol;
+
+sol! {
+    #[sol(abi = true, rpc)]
+    #[derive(Debug, PartialEq, Eq)]
+    interface IThenaPairFactory {
+        function getFee(bool _stable) external view returns (uint256);
+    }
+
+    #[sol(abi = true, rpc)]
+    #[derive(Debug, PartialEq, Eq)]
+    interface IRamsesPairFactory {
+        function pairFee(address _pair) external view returns (uint256 fee);
+    }
+
+    #[sol(abi = true, rpc)]
+    #[derive(Debug, PartialEq, Eq)]
+    interface IEqualizerPairFactory {
+        function getRealFee(address _pair) external view returns (uint256);
+    }
+}
@@ -0,0 +1,5 @@
+pub use factory::{IEqualizerPairFactory, IRamsesPairFactory, IThenaPairFactory};
+pub use pair::ISolidlyPair;
+
+mod factory;
+mod pair;
@@ -0,0 +1,15 @@
+use alloy::sol;
+
+sol! {
+    #[sol(abi = true, rpc)]
+    #[derive(Debug, PartialEq, Eq)]
+    interface ISolidlyPair {
+        function factory() external view returns (address);
+        function token0() external view returns (address);
+        function token1() external view returns (address);
+        function stable() external view returns (bool);
+        function getReserves() external view returns (uint256 _reserve0, uint256 _reserve1, uint256 _blockTimestampLast);
+        function getAmountOut(uint256 amountIn, address tokenIn) external view returns (uint256);
+        function swap(uint256 amount0Out, uint256 amount1Out, address to, bytes calldata data) external;
+    }
+}
@@ -0,0 +1,11 @@
+use alloy::sol;
+
+sol! {
+    #[sol(abi=true,rpc)]
+    #[derive(Debug, PartialEq, Eq)]
+    interface IUniswapV2Factory {
+        function feeTo() external view returns (address);
+        function feeToSetter() external view returns (address);
+        function getPair(address tokenA, address tokenB) external view returns (address pair);
+    }
+}
@@ -0,0 +1,10 @@
+use alloy::sol;
+
+sol! {
+    #[sol(abi=true,rpc)]
+    #[derive(Debug, PartialEq, Eq)]
+    interface IUniswapV3Factory {
+        function owner() external view returns (address);
+        function getPool(address tokenA, address tokenB, uint24 fee) external view returns (address pool);
+    }
+}
@@ -1,7 +1,9 @@
 pub use custorm_quoter::ICustomQuoter;
 pub use quoter::IQuoterV2;
+pub use swap_router::ISwapRouter02;
 pub use ticklens::ITickLens;
 
 mod custorm_quoter;
 mod quoter;
+mod swap_router;
 mod ticklens;
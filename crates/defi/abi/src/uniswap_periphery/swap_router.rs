@@ -0,0 +1,10 @@
+use alloy::sol;
+
+sol! {
+    #[derive(Debug, PartialEq, Eq)]
+    interface ISwapRouter02 {
+        function multicall(uint256 deadline, bytes[] calldata data) external payable returns (bytes[] memory results);
+        function sweepToken(address token, uint256 amountMinimum, address recipient) external payable;
+        function unwrapWETH9(uint256 amountMinimum, address recipient) external payable;
+    }
+}
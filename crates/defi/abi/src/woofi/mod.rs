@@ -0,0 +1,5 @@
+pub use pool::IWooPPV2;
+pub use wooracle::{IWooracleV2, WooracleState};
+
+mod pool;
+mod wooracle;
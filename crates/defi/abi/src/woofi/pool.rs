@@ -0,0 +1,36 @@
+use alloy::sol;
+
+sol! {
+    #[sol(abi = true, rpc)]
+    #[derive(Debug, PartialEq, Eq)]
+    interface IWooPPV2 {
+        event WooSwap(
+            address indexed fromToken,
+            address indexed toToken,
+            uint256 fromAmount,
+            uint256 toAmount,
+            address from,
+            address indexed to,
+            address rebateTo,
+            uint256 swapVol,
+            uint256 swapFee
+        );
+
+        function quoteToken() external view returns (address);
+
+        function wooracle() external view returns (address);
+
+        function tokenInfos(address token) external view returns (uint192 reserve, uint16 feeRate);
+
+        function query(address fromToken, address toToken, uint256 fromAmount) external view returns (uint256 toAmount);
+
+        function swap(
+            address fromToken,
+            address toToken,
+            uint256 fromAmount,
+            uint256 minToAmount,
+            address to,
+            address rebateTo
+        ) external returns (uint256 realToAmount);
+    }
+}
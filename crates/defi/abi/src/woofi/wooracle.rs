@@ -0,0 +1,21 @@
+use alloy::sol;
+
+sol! {
+    #[derive(Debug, PartialEq, Eq)]
+    struct WooracleState {
+        uint128 price;
+        uint64 spread;
+        uint64 coeff;
+        bool woFeasible;
+    }
+
+    #[sol(abi = true, rpc)]
+    #[derive(Debug, PartialEq, Eq)]
+    interface IWooracleV2 {
+        function state(address base) external view returns (WooracleState memory);
+
+        function decimals(address base) external view returns (uint8);
+
+        function quoteToken() external view returns (address);
+    }
+}
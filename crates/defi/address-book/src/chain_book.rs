@@ -0,0 +1,85 @@
+use alloy_primitives::Address;
+
+use crate::{FactoryAddress, TokenAddressArbitrum, TokenAddressAvalanche, TokenAddressBase, TokenAddressBsc, TokenAddressEth};
+
+/// What an address book entry is expected to answer to an eth_call
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AddressBookEntryKind {
+    Token {
+        decimals: u8,
+    },
+    /// Factory answering `feeToSetter()`
+    UniswapV2Factory,
+    /// Factory answering `owner()`
+    UniswapV3Factory,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AddressBookEntry {
+    pub name: &'static str,
+    pub address: Address,
+    pub kind: AddressBookEntryKind,
+}
+
+impl AddressBookEntry {
+    const fn token(name: &'static str, address: Address, decimals: u8) -> Self {
+        Self { name, address, kind: AddressBookEntryKind::Token { decimals } }
+    }
+
+    const fn factory(name: &'static str, address: Address, kind: AddressBookEntryKind) -> Self {
+        Self { name, address, kind }
+    }
+}
+
+/// Address book entries used on the chain, empty for chains without an address book
+pub fn chain_address_book(chain_id: u64) -> Vec<AddressBookEntry> {
+    use AddressBookEntryKind::{UniswapV2Factory, UniswapV3Factory};
+
+    match chain_id {
+        1 => vec![
+            AddressBookEntry::token("WETH", TokenAddressEth::WETH, 18),
+            AddressBookEntry::token("USDC", TokenAddressEth::USDC, 6),
+            AddressBookEntry::token("USDT", TokenAddressEth::USDT, 6),
+            AddressBookEntry::token("DAI", TokenAddressEth::DAI, 18),
+            AddressBookEntry::token("WBTC", TokenAddressEth::WBTC, 8),
+            AddressBookEntry::token("3Crv", TokenAddressEth::THREECRV, 18),
+            AddressBookEntry::token("stETH", TokenAddressEth::STETH, 18),
+            AddressBookEntry::token("AMPL", TokenAddressEth::AMPL, 9),
+            AddressBookEntry::factory("UniswapV2Factory", FactoryAddress::UNISWAP_V2, UniswapV2Factory),
+            AddressBookEntry::factory("SushiswapV2Factory", FactoryAddress::SUSHISWAP_V2, UniswapV2Factory),
+            AddressBookEntry::factory("UniswapV3Factory", FactoryAddress::UNISWAP_V3, UniswapV3Factory),
+            AddressBookEntry::factory("SushiswapV3Factory", FactoryAddress::SUSHISWAP_V3, UniswapV3Factory),
+            AddressBookEntry::factory("PancakeV3Factory", FactoryAddress::PANCAKE_V3, UniswapV3Factory),
+        ],
+        42161 => vec![
+            AddressBookEntry::token("WETH", TokenAddressArbitrum::WETH, 18),
+            AddressBookEntry::token("WBTC", TokenAddressArbitrum::WBTC, 8),
+            AddressBookEntry::token("USDC", TokenAddressArbitrum::USDC, 6),
+            AddressBookEntry::token("USDT", TokenAddressArbitrum::USDT, 6),
+            AddressBookEntry::token("DAI", TokenAddressArbitrum::DAI, 18),
+            AddressBookEntry::factory("UniswapV3Factory", FactoryAddress::UNISWAP_V3, UniswapV3Factory),
+        ],
+        8453 => {
+            vec![AddressBookEntry::token("WETH", TokenAddressBase::WETH, 18), AddressBookEntry::token("USDC", TokenAddressBase::USDC, 6)]
+        }
+        43114 => vec![
+            AddressBookEntry::token("WAVAX", TokenAddressAvalanche::WAVAX, 18),
+            AddressBookEntry::token("USDC", TokenAddressAvalanche::USDC, 6),
+            AddressBookEntry::token("USDT", TokenAddressAvalanche::USDT, 6),
+            AddressBookEntry::token("DAI", TokenAddressAvalanche::DAI, 18),
+            AddressBookEntry::token("FRAX", TokenAddressAvalanche::FRAX, 18),
+            AddressBookEntry::token("WBTC", TokenAddressAvalanche::WBTC, 8),
+            AddressBookEntry::token("WETH", TokenAddressAvalanche::WETH, 18),
+        ],
+        56 => vec![
+            AddressBookEntry::token("WBNB", TokenAddressBsc::WBNB, 18),
+            AddressBookEntry::token("USDC", TokenAddressBsc::USDC, 18),
+            AddressBookEntry::token("USDT", TokenAddressBsc::USDT, 18),
+            AddressBookEntry::token("DAI", TokenAddressBsc::DAI, 18),
+            AddressBookEntry::token("WETH", TokenAddressBsc::WETH, 18),
+            AddressBookEntry::token("WBTC", TokenAddressBsc::WBTC, 8),
+            AddressBookEntry::factory("PancakeV3Factory", FactoryAddress::PANCAKE_V3, UniswapV3Factory),
+        ],
+        _ => vec![],
+    }
+}
@@ -1,5 +1,11 @@
 use alloy_primitives::{address, Address};
 
+pub use chain_book::{chain_address_book, AddressBookEntry, AddressBookEntryKind};
+pub use nweth::NWETH;
+
+mod chain_book;
+mod nweth;
+
 #[non_exhaustive]
 pub struct TokenAddressEth;
 
@@ -15,6 +21,8 @@ impl TokenAddressEth {
     pub const STETH: Address = address!("ae7ab96520de3a18e5e111b5eaab095312d7fe84");
     pub const WSTETH: Address = address!("7f39c581f595b53c5cb19bd0b3f8da6c935e2ca0");
     pub const LUSD: Address = address!("5f98805a4e8be255a32880fdec7f6728c6568ba0");
+    pub const FRAX: Address = address!("853d955acef822db058eb8505911ed77f175b99e");
+    pub const AMPL: Address = address!("d46ba6d942050d489dbd938a2c909a5d5039a161");
 
     pub fn is_weth(&address: &Address) -> bool {
         address.eq(&Self::WETH)
@@ -109,6 +117,11 @@ impl FactoryAddress {
     pub const ANTFARM: Address = address!("E48AEE124F9933661d4DD3Eb265fA9e153e32CBe");
     pub const INTEGRAL: Address = address!("C480b33eE5229DE3FbDFAD1D2DCD3F3BAD0C56c6");
 
+    // ve(3,3) volatile pairs, fees are set by the factory per pool
+    pub const RAMSES: Address = address!("AAA20D08e59F6561f242b08513D36266C5A29415");
+    pub const THENA: Address = address!("AFD89d21BdB66d00817d4153E055830B1c2B3970");
+    pub const EQUALIZER: Address = address!("c6366EFD0AF1d09171fe0EBF32c7943BB310832a");
+
     // Uniswap V3 compatible
     pub const UNISWAP_V3: Address = address!("1f98431c8ad98523631ae4a59f267346ea31f984");
     pub const SUSHISWAP_V3: Address = address!("baceb8ec6b9355dfc0269c18bac9d6e2bdc29c4f");
@@ -128,6 +141,7 @@ pub struct PeripheryAddress;
 impl PeripheryAddress {
     pub const UNISWAP_PERMIT_2_ADDRESS: Address = address!("000000000022D473030F116dDEE9F6B43aC78BA3");
     pub const UNISWAP_V2_ROUTER: Address = address!("7a250d5630b4cf539739df2c5dacb4c659f2488d");
+    pub const UNISWAP_V3_SWAP_ROUTER_02: Address = address!("68b3465833fb72A70ecDF485E0e4C7bD8665Fc45");
     pub const UNISWAP_V3_QUOTER_V2: Address = address!("61ffe014ba17989e743c5f6cb21bf9697530b21e");
     pub const UNISWAP_V3_TICK_LENS: Address = address!("bfd8137f7d1516d3ea5ca83523914859ec47f573");
     pub const PANCAKE_V3_QUOTER: Address = address!("b048bbc1ee6b733fffcfb9e9cef7375518e25997");
@@ -137,6 +151,22 @@ impl PeripheryAddress {
     pub const UNISWAPV4_STATE_VIEW_ADDRESS: Address = address!("7fFE42C4a5DEeA5b0feC41C94C136Cf115597227");
     pub const MAVERICK_V2_QUOTER: Address = address!("b40AfdB85a07f37aE217E7D6462e609900dD8D7A");
     pub const MAVERICK_V2_TICK_LENS: Address = address!("6A9EB38DE5D349Fe751E0aDb4c0D9D391f94cc8D");
+    pub const PENDLE_ROUTER_V4: Address = address!("888888888889758F76e7103c6CbF23ABbF58F946");
+    pub const BALANCER_VAULT: Address = address!("BA12222222228d8Ba445958a75a0704d566BF2C8");
+}
+
+#[non_exhaustive]
+pub struct PeripheryAddressBsc;
+
+impl PeripheryAddressBsc {
+    pub const UNISWAP_V3_SWAP_ROUTER_02: Address = address!("B971eF87ede563556b2ED4b1C0b0019111Dd85d2");
+}
+
+#[non_exhaustive]
+pub struct PeripheryAddressAvalanche;
+
+impl PeripheryAddressAvalanche {
+    pub const UNISWAP_V3_SWAP_ROUTER_02: Address = address!("bb00FF08d01D300023C629E8fFfFcb65A5a578cE");
 }
 
 #[non_exhaustive]
@@ -200,6 +230,46 @@ impl MaverickV2PoolAddress {
     pub const USDC_USDT: Address = address!("31373595F40Ea48a7aAb6CBCB0d377C6066E2dCA");
 }
 
+#[non_exhaustive]
+pub struct GmxV2AddressArbitrum;
+
+impl GmxV2AddressArbitrum {
+    pub const DATA_STORE: Address = address!("FD70de6b91282D8017aA4E741e9Ae325CAb992d8");
+    pub const READER: Address = address!("f60becbba223EEA9495Da3f606753867eC10d139");
+}
+
+#[non_exhaustive]
+pub struct GmxV2MarketAddressArbitrum;
+
+impl GmxV2MarketAddressArbitrum {
+    pub const ETH_USD: Address = address!("70d95587d40A2caf56bd97485aB3Eec10Bee6336");
+    pub const BTC_USD: Address = address!("47c031236e19d024b42f8AE6780E44A573170703");
+}
+
+#[non_exhaustive]
+pub struct WooFiV2Address;
+
+impl WooFiV2Address {
+    // same address on Arbitrum, BSC and Base
+    pub const WOOPP_V2: Address = address!("Ed9e3f98bBed560e66B89AaC922E29D4596A9642");
+
+    // base tokens quoted by the pool, the pool has no token enumeration
+    pub const BASE_TOKENS_ARBITRUM: [Address; 3] = [
+        address!("82aF49447D8a07e3bd95BD0d56f35241523fBab1"), // WETH
+        address!("2f2a2543B76A4166549F7aaB2e75Bef0aefC5B0f"), // WBTC
+        address!("912CE59144191C1204E64559FE8253a0e49E6548"), // ARB
+    ];
+    pub const BASE_TOKENS_BSC: [Address; 3] = [
+        address!("bb4CdB9CBd36B01bD1cBaEBF2De08d9173bc095c"), // WBNB
+        address!("7130d2A12B9BCbFAe4f2634d864A1Ee1Ce3Ead9c"), // BTCB
+        address!("2170Ed0880ac9A755fd29B2688956BD959F933F8"), // ETH
+    ];
+    pub const BASE_TOKENS_BASE: [Address; 2] = [
+        address!("4200000000000000000000000000000000000006"), // WETH
+        address!("cbB7C0000aB88B473b1f5aFd9ef808440eed33Bf"), // cbBTC
+    ];
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -208,4 +278,19 @@ mod test {
     fn test_token() {
         assert_eq!(TokenAddressEth::WETH, address!("c02aaa39b223fe8d0a0e5c4f27ead9083c756cc2"));
     }
+
+    #[test]
+    fn test_chain_address_book() {
+        let book = chain_address_book(1);
+        assert!(book.contains(&AddressBookEntry {
+            name: "WETH",
+            address: TokenAddressEth::WETH,
+            kind: AddressBookEntryKind::Token { decimals: 18 }
+        }));
+        for chain_id in [1, 42161, 8453, 43114, 56] {
+            let book = chain_address_book(chain_id);
+            assert!(book.iter().enumerate().all(|(idx, entry)| book[idx + 1..].iter().all(|other| other.address != entry.address)));
+        }
+        assert!(chain_address_book(31337).is_empty());
+    }
 }
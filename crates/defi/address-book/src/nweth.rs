@@ -1,5 +1,5 @@
-use alloy::primitives::{Address, U256};
-use loom_defi_address_book::TokenAddressEth;
+use crate::TokenAddressEth;
+use alloy_primitives::{Address, U256};
 use std::ops::{Add, Mul};
 
 pub struct NWETH {}
@@ -51,7 +51,7 @@ impl NWETH {
     #[inline]
     pub fn from_float(value: f64) -> U256 {
         let multiplier = U256::from(value as i64);
-        let modulus = U256::from(((value - value.round()) * 10_i64.pow(18) as f64) as u64);
+        let modulus = U256::from((value.fract() * 10_i64.pow(18) as f64) as u64);
         multiplier.mul(U256::from(10).pow(U256::from(18))).add(modulus)
     }
 
@@ -65,3 +65,19 @@ impl NWETH {
         Self::ADDRESS
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_conversions() {
+        assert_eq!(NWETH::to_float(U256::from(1_500_000_000_000_000_000u128)), 1.5);
+        assert_eq!(NWETH::to_float(U256::MAX), 0.0);
+        assert_eq!(NWETH::from_float(1.5), U256::from(1_500_000_000_000_000_000u128));
+        assert_eq!(NWETH::from_float(2.25), U256::from(2_250_000_000_000_000_000u128));
+        assert_eq!(NWETH::to_float_gwei(2_500_000_000), 2.5);
+        assert_eq!(NWETH::to_float_wei(500_000_000_000_000_000), 0.5);
+        assert_eq!(NWETH::get_exp(), U256::from(10).pow(U256::from(18)));
+    }
+}
@@ -0,0 +1,290 @@
+use std::collections::{BTreeMap, HashSet};
+
+use alloy_primitives::{Address, BlockNumber, U256};
+use eyre::eyre;
+use influxdb::{Timestamp, WriteQuery};
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{error, info};
+
+use loom_core_actors::{subscribe, Accessor, Actor, ActorResult, Broadcaster, Consumer, Producer, SharedState, WorkerResult};
+use loom_core_actors_macros::{Accessor, Consumer, Producer};
+use loom_core_blockchain::{Blockchain, Strategy};
+use loom_evm_utils::NWETH;
+use loom_types_entities::{Market, PoolId};
+use loom_types_events::{
+    BlockStats, HealthEvent, MarketEvents, Message, MessageBlockStateUpdate, MessageBlockStats, MessageHealthEvent, MessageSwapCompose,
+    MissedOpportunity, SwapComposeMessage,
+};
+
+/// Accumulates the statistics of the blocks still being worked on. Results for the state of block N target N+1 and keep
+/// arriving after the header of N+1, the stats of block N are finished when the header of N+2 arrives.
+#[derive(Default)]
+struct BlockStatsCollector {
+    latest_block: BlockNumber,
+    blocks: BTreeMap<BlockNumber, BlockStats>,
+}
+
+impl BlockStatsCollector {
+    fn entry(&mut self, block_number: BlockNumber) -> Option<&mut BlockStats> {
+        // already finished
+        if block_number + 1 < self.latest_block {
+            return None;
+        }
+        Some(self.blocks.entry(block_number).or_insert_with(|| BlockStats::new(block_number)))
+    }
+
+    /// Returns the finished block stats
+    fn on_block_header(&mut self, block_number: BlockNumber) -> Vec<BlockStats> {
+        self.latest_block = self.latest_block.max(block_number);
+        let unfinished = self.blocks.split_off(&self.latest_block.saturating_sub(1));
+        std::mem::replace(&mut self.blocks, unfinished).into_values().collect()
+    }
+
+    fn on_pools_updated(&mut self, block_number: BlockNumber, pools_updated: usize) {
+        if let Some(stats) = self.entry(block_number) {
+            stats.pools_updated += pools_updated;
+        }
+    }
+
+    fn on_paths_evaluated(&mut self, next_block_number: BlockNumber, paths: usize) {
+        if let Some(stats) = self.entry(next_block_number.saturating_sub(1)) {
+            stats.paths_evaluated += paths;
+        }
+    }
+
+    /// Opportunities found after the header of their target block are missed by latency
+    fn on_opportunity(&mut self, next_block_number: BlockNumber, swap: String, origin: Option<String>, profit_eth: U256) {
+        let latest_block = self.latest_block;
+        if let Some(stats) = self.entry(next_block_number.saturating_sub(1)) {
+            stats.add_opportunity(profit_eth);
+            if next_block_number <= latest_block {
+                stats.add_missed(MissedOpportunity { swap, origin, profit_eth, found_at_block: latest_block });
+            }
+        }
+    }
+
+    /// Opportunities are simulated on the latest state
+    fn on_simulation_failure(&mut self) {
+        if let Some(stats) = self.entry(self.latest_block) {
+            stats.simulation_failures += 1;
+        }
+    }
+}
+
+fn block_stats_query(stats: &BlockStats) -> WriteQuery {
+    let mut write_query = WriteQuery::new(Timestamp::from(chrono::Utc::now()), "block_stats")
+        .add_field("block_number", stats.block_number)
+        .add_field("pools_updated", stats.pools_updated as u64)
+        .add_field("paths_evaluated", stats.paths_evaluated as u64)
+        .add_field("opportunities", stats.opportunities as u64)
+        .add_field("best_profit", NWETH::to_float(stats.best_profit_eth))
+        .add_field("simulation_failures", stats.simulation_failures as u64);
+    if let Some(missed) = &stats.best_missed {
+        write_query = write_query.add_field("best_missed_profit", NWETH::to_float(missed.profit_eth));
+    }
+    write_query
+}
+
+async fn block_stats_worker<DB: Clone + Send + Sync + 'static>(
+    market: SharedState<Market>,
+    market_events_rx: Broadcaster<MarketEvents>,
+    block_state_update_rx: Broadcaster<MessageBlockStateUpdate>,
+    compose_channel_rx: Broadcaster<MessageSwapCompose<DB>>,
+    health_monitor_rx: Broadcaster<MessageHealthEvent>,
+    block_stats_tx: Broadcaster<MessageBlockStats>,
+    influxdb_write_channel_tx: Broadcaster<WriteQuery>,
+) -> WorkerResult {
+    subscribe!(market_events_rx);
+    subscribe!(block_state_update_rx);
+    subscribe!(compose_channel_rx);
+    subscribe!(health_monitor_rx);
+
+    let mut collector = BlockStatsCollector::default();
+
+    loop {
+        tokio::select! {
+            msg = market_events_rx.recv() => {
+                match msg {
+                    Ok(MarketEvents::BlockHeaderUpdate { block_number, .. }) => {
+                        for stats in collector.on_block_header(block_number) {
+                            info!(
+                                block_number = stats.block_number,
+                                pools_updated = stats.pools_updated,
+                                paths_evaluated = stats.paths_evaluated,
+                                opportunities = stats.opportunities,
+                                best_profit = NWETH::to_float(stats.best_profit_eth),
+                                simulation_failures = stats.simulation_failures,
+                                best_missed = stats.best_missed.as_ref().map(|missed| NWETH::to_float(missed.profit_eth)),
+                                "Block stats"
+                            );
+                            if let Err(e) = influxdb_write_channel_tx.send(block_stats_query(&stats)) {
+                                error!("Failed to send block stats to influxdb: {:?}", e);
+                            }
+                            if let Err(e) = block_stats_tx.send(Message::new(stats)) {
+                                error!("block_stats_tx.send error : {:?}", e);
+                            }
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(RecvError::Lagged(lag)) => info!("Market events channel lagged: {}", lag),
+                    Err(RecvError::Closed) => return Err(eyre!("MARKET_EVENTS_CHANNEL_CLOSED")),
+                }
+            }
+            msg = block_state_update_rx.recv() => {
+                match msg {
+                    Ok(block_state_update) => {
+                        let addresses: HashSet<Address> =
+                            block_state_update.state_update.iter().flat_map(|state_update| state_update.keys().cloned()).collect();
+                        let market_guard = market.read().await;
+                        let pools_updated = addresses.into_iter().filter(|address| market_guard.is_pool(&PoolId::Address(*address))).count();
+                        drop(market_guard);
+                        collector.on_pools_updated(block_state_update.block_header.number, pools_updated);
+                    }
+                    Err(RecvError::Lagged(lag)) => info!("Block state update channel lagged: {}", lag),
+                    Err(RecvError::Closed) => return Err(eyre!("BLOCK_STATE_UPDATE_CHANNEL_CLOSED")),
+                }
+            }
+            msg = compose_channel_rx.recv() => {
+                match msg {
+                    Ok(compose_message) => {
+                        if let SwapComposeMessage::Prepare(data) = compose_message.inner {
+                            collector.on_opportunity(
+                                data.tx_compose.next_block_number,
+                                data.swap.to_string(),
+                                data.origin,
+                                data.swap.abs_profit_eth(),
+                            );
+                        }
+                    }
+                    Err(RecvError::Lagged(lag)) => info!("Swap compose channel lagged: {}", lag),
+                    Err(RecvError::Closed) => return Err(eyre!("SWAP_COMPOSE_CHANNEL_CLOSED")),
+                }
+            }
+            msg = health_monitor_rx.recv() => {
+                match msg {
+                    Ok(health_event) => match health_event.inner {
+                        HealthEvent::PathsEvaluated { next_block_number, paths } => collector.on_paths_evaluated(next_block_number, paths),
+                        HealthEvent::SwapLineEstimationError(_) => collector.on_simulation_failure(),
+                        _ => {}
+                    },
+                    Err(RecvError::Lagged(lag)) => info!("Health monitor channel lagged: {}", lag),
+                    Err(RecvError::Closed) => return Err(eyre!("HEALTH_MONITOR_CHANNEL_CLOSED")),
+                }
+            }
+        }
+    }
+}
+
+/// Emits the searcher coverage of every block: pools updated, paths evaluated, opportunities found, simulation failures
+/// and the best opportunity that was found too late for its target block
+#[derive(Accessor, Consumer, Producer)]
+pub struct BlockStatsActor<DB: Clone + Send + Sync + 'static> {
+    #[accessor]
+    market: Option<SharedState<Market>>,
+    #[consumer]
+    market_events_rx: Option<Broadcaster<MarketEvents>>,
+    #[consumer]
+    block_state_update_rx: Option<Broadcaster<MessageBlockStateUpdate>>,
+    #[consumer]
+    compose_channel_rx: Option<Broadcaster<MessageSwapCompose<DB>>>,
+    #[consumer]
+    health_monitor_rx: Option<Broadcaster<MessageHealthEvent>>,
+    #[producer]
+    block_stats_tx: Option<Broadcaster<MessageBlockStats>>,
+    #[producer]
+    influxdb_write_channel_tx: Option<Broadcaster<WriteQuery>>,
+}
+
+impl<DB: Clone + Send + Sync + 'static> Default for BlockStatsActor<DB> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<DB: Clone + Send + Sync + 'static> BlockStatsActor<DB> {
+    pub fn new() -> Self {
+        Self {
+            market: None,
+            market_events_rx: None,
+            block_state_update_rx: None,
+            compose_channel_rx: None,
+            health_monitor_rx: None,
+            block_stats_tx: None,
+            influxdb_write_channel_tx: None,
+        }
+    }
+
+    pub fn on_bc(self, bc: &Blockchain, strategy: &Strategy<DB>) -> Self {
+        Self {
+            market: Some(bc.market()),
+            market_events_rx: Some(bc.market_events_channel()),
+            block_state_update_rx: Some(bc.new_block_state_update_channel()),
+            compose_channel_rx: Some(strategy.swap_compose_channel()),
+            health_monitor_rx: Some(bc.health_monitor_channel()),
+            block_stats_tx: Some(bc.block_stats_channel()),
+            influxdb_write_channel_tx: Some(bc.influxdb_write_channel()),
+        }
+    }
+}
+
+impl<DB: Clone + Send + Sync + 'static> Actor for BlockStatsActor<DB> {
+    fn start(&self) -> ActorResult {
+        let task = tokio::task::spawn(block_stats_worker(
+            self.market.clone().unwrap(),
+            self.market_events_rx.clone().unwrap(),
+            self.block_state_update_rx.clone().unwrap(),
+            self.compose_channel_rx.clone().unwrap(),
+            self.health_monitor_rx.clone().unwrap(),
+            self.block_stats_tx.clone().unwrap(),
+            self.influxdb_write_channel_tx.clone().unwrap(),
+        ));
+        Ok(vec![task])
+    }
+
+    fn name(&self) -> &'static str {
+        "BlockStatsActor"
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_block_stats_collector() {
+        let mut collector = BlockStatsCollector::default();
+        assert!(collector.on_block_header(100).is_empty());
+
+        collector.on_pools_updated(100, 3);
+        collector.on_paths_evaluated(101, 50);
+        collector.on_opportunity(101, "a".to_string(), None, U256::from(10));
+        collector.on_simulation_failure();
+
+        // block 101 arrived, late results for block 100 are still counted
+        assert!(collector.on_block_header(101).is_empty());
+        collector.on_paths_evaluated(101, 20);
+        collector.on_opportunity(101, "b".to_string(), None, U256::from(5));
+        collector.on_opportunity(101, "c".to_string(), Some("mempool".to_string()), U256::from(7));
+        collector.on_paths_evaluated(102, 30);
+
+        let finished = collector.on_block_header(102);
+        assert_eq!(finished.len(), 1);
+        let stats = &finished[0];
+        assert_eq!(stats.block_number, 100);
+        assert_eq!(stats.pools_updated, 3);
+        assert_eq!(stats.paths_evaluated, 70);
+        assert_eq!(stats.opportunities, 3);
+        assert_eq!(stats.best_profit_eth, U256::from(10));
+        assert_eq!(stats.simulation_failures, 1);
+        let missed = stats.best_missed.as_ref().unwrap();
+        assert_eq!(missed.swap, "c");
+        assert_eq!(missed.found_at_block, 101);
+
+        // block 100 is finished, results for it are dropped
+        collector.on_paths_evaluated(101, 10);
+        let finished = collector.on_block_header(103);
+        assert_eq!(finished.len(), 1);
+        assert_eq!(finished[0].block_number, 101);
+        assert_eq!(finished[0].paths_evaluated, 30);
+    }
+}
@@ -1,9 +1,11 @@
+mod block_stats_actor;
 mod pool_health_monitor;
 mod state_health_monitor;
 mod stuffing_tx_monitor;
 
 mod metrics_recorder_actor;
 
+pub use block_stats_actor::BlockStatsActor;
 pub use metrics_recorder_actor::MetricsRecorderActor;
 pub use pool_health_monitor::PoolHealthMonitorActor;
 pub use state_health_monitor::StateHealthMonitorActor;
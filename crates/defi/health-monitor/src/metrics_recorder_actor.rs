@@ -6,21 +6,24 @@ use loom_core_actors::{Accessor, Consumer, SharedState};
 use loom_core_actors_macros::{Accessor, Consumer, Producer};
 use loom_core_blockchain::{Blockchain, BlockchainState};
 use loom_evm_db::DatabaseLoomExt;
-use loom_types_entities::{Market, MarketState};
+use loom_types_entities::{Market, MarketState, PoolEventRecord};
 use loom_types_events::MessageBlockHeader;
 use revm::DatabaseRef;
+use std::collections::BTreeMap;
 use std::time::Duration;
 use tikv_jemalloc_ctl::stats;
-use tokio::sync::broadcast::error::RecvError;
-use tracing::{error, info};
+use tokio::sync::broadcast::error::{RecvError, TryRecvError};
+use tracing::{error, info, warn};
 
 async fn metrics_recorder_worker<DB: DatabaseLoomExt + DatabaseRef + Send + Sync + 'static>(
     market: SharedState<Market>,
     market_state: SharedState<MarketState<DB>>,
     block_header_update_rx: Broadcaster<MessageBlockHeader>,
+    pool_events_rx: Broadcaster<PoolEventRecord>,
     influx_channel_tx: Broadcaster<WriteQuery>,
 ) -> WorkerResult {
     subscribe!(block_header_update_rx);
+    subscribe!(pool_events_rx);
     loop {
         let block_header = match block_header_update_rx.recv().await {
             Ok(block) => block,
@@ -55,6 +58,16 @@ async fn metrics_recorder_worker<DB: DatabaseLoomExt + DatabaseRef + Send + Sync
         let paths_disabled = market_guard.swap_paths().disabled_len();
         drop(market_guard);
 
+        // pool events published since the previous block by kind
+        let mut pool_events: BTreeMap<&'static str, usize> = BTreeMap::new();
+        loop {
+            match pool_events_rx.try_recv() {
+                Ok(record) => *pool_events.entry(record.event.kind()).or_default() += 1,
+                Err(TryRecvError::Lagged(lag)) => warn!("Pool events channel lagged: {}", lag),
+                Err(_) => break,
+            }
+        }
+
         let influx_channel_clone = influx_channel_tx.clone();
 
         if let Err(e) = tokio::time::timeout(Duration::from_secs(2), async move {
@@ -100,6 +113,16 @@ async fn metrics_recorder_worker<DB: DatabaseLoomExt + DatabaseRef + Send + Sync
                 error!("Failed to send pools_disabled to influxdb: {:?}", e);
             }
 
+            for (kind, count) in pool_events {
+                let write_query = WriteQuery::new(Timestamp::from(current_timestamp), "pool_events")
+                    .add_tag("kind", kind)
+                    .add_field("value", count as f32)
+                    .add_field("block_number", block_header.inner.header.number);
+                if let Err(e) = influx_channel_clone.send(write_query) {
+                    error!("Failed to send pool_events to influxdb: {:?}", e);
+                }
+            }
+
             let write_query = WriteQuery::new(Timestamp::from(current_timestamp), "jemalloc_allocated")
                 .add_field("value", (allocated >> 20) as f32)
                 .add_field("block_number", block_header.inner.header.number);
@@ -129,6 +152,8 @@ pub struct MetricsRecorderActor<DB: Clone + Send + Sync + 'static> {
     market_state: Option<SharedState<MarketState<DB>>>,
     #[consumer]
     block_header_rx: Option<Broadcaster<MessageBlockHeader>>,
+    #[consumer]
+    pool_events_rx: Option<Broadcaster<PoolEventRecord>>,
     #[producer]
     influxdb_write_channel_tx: Option<Broadcaster<WriteQuery>>,
 }
@@ -138,7 +163,7 @@ where
     DB: DatabaseRef + DatabaseLoomExt + Clone + Send + Sync + 'static,
 {
     pub fn new() -> Self {
-        Self { market: None, market_state: None, block_header_rx: None, influxdb_write_channel_tx: None }
+        Self { market: None, market_state: None, block_header_rx: None, pool_events_rx: None, influxdb_write_channel_tx: None }
     }
 
     pub fn on_bc(self, bc: &Blockchain, bc_state: &BlockchainState<DB>) -> Self {
@@ -146,6 +171,7 @@ where
             market: Some(bc.market()),
             market_state: Some(bc_state.market_state()),
             block_header_rx: Some(bc.new_block_headers_channel()),
+            pool_events_rx: Some(bc.pool_events_channel()),
             influxdb_write_channel_tx: Some(bc.influxdb_write_channel()),
         }
     }
@@ -160,6 +186,7 @@ where
             self.market.clone().unwrap(),
             self.market_state.clone().unwrap(),
             self.block_header_rx.clone().unwrap(),
+            self.pool_events_rx.clone().unwrap(),
             self.influxdb_write_channel_tx.clone().unwrap(),
         ));
         Ok(vec![task])
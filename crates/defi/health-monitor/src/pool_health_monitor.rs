@@ -121,6 +121,10 @@ pub async fn pool_health_monitor_worker(
                                     }
                                     HealthEvent::PoolSwapError(swap_error)=>{
                                         debug!("Pool health_monitor message update: {:?} {} {} ", swap_error.pool, swap_error.msg, swap_error.amount);
+                                        if swap_error.missing_tick_word().is_some() {
+                                            // loaded on demand by the tick word loader, the pool is fine
+                                            continue;
+                                        }
                                         let entry = pool_errors_map.entry(swap_error.pool).or_insert(0);
                                         *entry += 1;
                                         if *entry >= 10 {
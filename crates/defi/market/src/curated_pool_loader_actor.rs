@@ -0,0 +1,248 @@
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use alloy_network::Network;
+use alloy_primitives::Address;
+use alloy_provider::Provider;
+use eyre::{eyre, Result};
+use revm::{Database, DatabaseCommit, DatabaseRef};
+use serde::Deserialize;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use tracing::{debug, error, info};
+
+use loom_core_actors::{run_sync, Accessor, Actor, ActorResult, Broadcaster, Producer, SharedState, WorkerResult};
+use loom_core_actors_macros::{Accessor, Producer};
+use loom_core_blockchain::{Blockchain, BlockchainState};
+use loom_node_debug_provider::DebugProviderExt;
+use loom_types_entities::pool_config::PoolsLoadingConfig;
+use loom_types_entities::{Market, MarketState, PoolClass, PoolId, PoolLoaders};
+use loom_types_events::MarketEvents;
+
+use crate::pool_loader_actor::fetch_and_add_allowed_pool;
+
+const MAX_CONCURRENT_TASKS: usize = 20;
+
+const ETHEREUM_CURATED_POOLS: &str = include_str!("../curated/ethereum.toml");
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct CuratedPool {
+    pub address: Address,
+    pub class: PoolClass,
+}
+
+/// List of the top pools of a chain by TVL in the format of the bundled lists
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct CuratedPools {
+    pub pools: Vec<CuratedPool>,
+}
+
+impl CuratedPools {
+    pub fn parse(list: &str) -> Result<Self> {
+        toml::from_str(list).map_err(|error| eyre!("CURATED_POOLS_PARSE_ERROR: {error}"))
+    }
+
+    /// Bundled list of the chain, `None` for chains without one
+    pub fn bundled(chain_id: u64) -> Option<Self> {
+        let list = match chain_id {
+            1 => ETHEREUM_CURATED_POOLS,
+            _ => return None,
+        };
+        match Self::parse(list) {
+            Ok(curated_pools) => Some(curated_pools),
+            Err(error) => {
+                error!(%error, chain_id, "Bundled curated pools list is invalid");
+                None
+            }
+        }
+    }
+
+    pub async fn fetch(url: &str) -> Result<Self> {
+        let list = reqwest::get(url).await?.error_for_status()?.text().await?;
+        Self::parse(&list)
+    }
+
+    pub fn pool_ids(&self) -> Vec<(PoolId, PoolClass)> {
+        self.pools.iter().map(|pool| (PoolId::Address(pool.address), pool.class)).collect()
+    }
+}
+
+async fn curated_pool_loader_one_shot_worker<P, PL, N, DB>(
+    client: P,
+    pool_loaders: Arc<PoolLoaders<PL, N>>,
+    pools_config: PoolsLoadingConfig,
+    url: Option<String>,
+    market: SharedState<Market>,
+    market_state: SharedState<MarketState<DB>>,
+    market_events_tx: Broadcaster<MarketEvents>,
+) -> WorkerResult
+where
+    N: Network,
+    P: Provider<N> + DebugProviderExt<N> + Send + Sync + Clone + 'static,
+    PL: Provider<N> + Send + Sync + Clone + 'static,
+    DB: Database + DatabaseRef + DatabaseCommit + Send + Sync + Clone + 'static,
+{
+    // the bundled list is used when the url is not set or not reachable
+    let curated_pools = match &url {
+        Some(url) => match CuratedPools::fetch(url).await {
+            Ok(curated_pools) => Some(curated_pools),
+            Err(error) => {
+                error!(%error, url, "Failed to fetch curated pools list");
+                None
+            }
+        },
+        None => None,
+    };
+    let curated_pools = match curated_pools {
+        Some(curated_pools) => curated_pools,
+        None => {
+            let chain_id = client.get_chain_id().await?;
+            match CuratedPools::bundled(chain_id) {
+                Some(curated_pools) => curated_pools,
+                None => {
+                    info!(chain_id, "No curated pools list for the chain");
+                    return Ok("curated_pool_loader_worker".to_string());
+                }
+            }
+        }
+    };
+
+    let semaphore = Arc::new(Semaphore::new(pools_config.threads().unwrap_or(MAX_CONCURRENT_TASKS)));
+    let mut loading_tasks = JoinSet::new();
+
+    for (pool_id, pool_class) in curated_pools.pool_ids() {
+        if !pools_config.is_enabled(pool_class) || market.read().await.is_pool(&pool_id) {
+            continue;
+        }
+
+        let semaphore = semaphore.clone();
+        let client = client.clone();
+        let market = market.clone();
+        let market_state = market_state.clone();
+        let pool_loaders = pool_loaders.clone();
+        let pools_config = pools_config.clone();
+        let market_events_tx = market_events_tx.clone();
+
+        loading_tasks.spawn(async move {
+            let Ok(_permit) = semaphore.acquire().await else {
+                return false;
+            };
+            match fetch_and_add_allowed_pool(client, market, market_state, pool_loaders, &pools_config, pool_id, pool_class).await {
+                Ok(Some((pool_id, swap_path_idx_vec))) => {
+                    debug!(%pool_id, %pool_class, "Curated pool loaded");
+                    run_sync!(market_events_tx.send(MarketEvents::NewPoolLoaded { pool_id, swap_path_idx_vec }));
+                    true
+                }
+                Ok(None) => {
+                    debug!(%pool_id, %pool_class, "Curated pool factory is not allowed");
+                    false
+                }
+                Err(error) => {
+                    error!(%error, %pool_id, %pool_class, "Failed to load curated pool");
+                    false
+                }
+            }
+        });
+    }
+
+    let mut loaded = 0usize;
+    while let Some(result) = loading_tasks.join_next().await {
+        if matches!(result, Ok(true)) {
+            loaded += 1;
+        }
+    }
+    info!(loaded, total = curated_pools.pools.len(), "Curated pools loaded");
+
+    Ok("curated_pool_loader_worker".to_string())
+}
+
+/// Pre-loads a curated list of the top pools of the chain, fetched from `url` or bundled with the crate,
+/// so that the market has paths to search before log based discovery finds their pools
+#[derive(Accessor, Producer)]
+pub struct CuratedPoolLoaderOneShotActor<P, PL, N, DB>
+where
+    N: Network,
+    P: Provider<N> + Send + Sync + Clone + 'static,
+    PL: Provider<N> + Send + Sync + Clone + 'static,
+    DB: Database + DatabaseRef + DatabaseCommit + Send + Sync + Clone + 'static,
+{
+    client: P,
+    pool_loaders: Arc<PoolLoaders<PL, N>>,
+    pools_config: PoolsLoadingConfig,
+    url: Option<String>,
+    #[accessor]
+    market: Option<SharedState<Market>>,
+    #[accessor]
+    market_state: Option<SharedState<MarketState<DB>>>,
+    #[producer]
+    market_events_tx: Option<Broadcaster<MarketEvents>>,
+    _n: PhantomData<N>,
+}
+
+impl<P, PL, N, DB> CuratedPoolLoaderOneShotActor<P, PL, N, DB>
+where
+    N: Network,
+    P: Provider<N> + Send + Sync + Clone + 'static,
+    PL: Provider<N> + Send + Sync + Clone + 'static,
+    DB: Database + DatabaseRef + DatabaseCommit + Send + Sync + Clone + 'static,
+{
+    pub fn new(client: P, pool_loaders: Arc<PoolLoaders<PL, N>>, pools_config: PoolsLoadingConfig) -> Self {
+        Self { client, pool_loaders, pools_config, url: None, market: None, market_state: None, market_events_tx: None, _n: PhantomData }
+    }
+
+    pub fn with_url(self, url: String) -> Self {
+        Self { url: Some(url), ..self }
+    }
+
+    pub fn on_bc(self, bc: &Blockchain, state: &BlockchainState<DB>) -> Self {
+        Self {
+            market: Some(bc.market()),
+            market_state: Some(state.market_state_commit()),
+            market_events_tx: Some(bc.market_events_channel()),
+            ..self
+        }
+    }
+}
+
+impl<P, PL, N, DB> Actor for CuratedPoolLoaderOneShotActor<P, PL, N, DB>
+where
+    N: Network,
+    P: Provider<N> + DebugProviderExt<N> + Send + Sync + Clone + 'static,
+    PL: Provider<N> + Send + Sync + Clone + 'static,
+    DB: Database + DatabaseRef + DatabaseCommit + Send + Sync + Clone + 'static,
+{
+    fn start(&self) -> ActorResult {
+        let task = tokio::task::spawn(curated_pool_loader_one_shot_worker(
+            self.client.clone(),
+            self.pool_loaders.clone(),
+            self.pools_config.clone(),
+            self.url.clone(),
+            self.market.clone().unwrap(),
+            self.market_state.clone().unwrap(),
+            self.market_events_tx.clone().unwrap(),
+        ));
+        Ok(vec![task])
+    }
+
+    fn name(&self) -> &'static str {
+        "CuratedPoolLoaderOneShotActor"
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_bundled_curated_pools() {
+        let curated_pools = CuratedPools::bundled(1).unwrap();
+        assert!(!curated_pools.pools.is_empty());
+
+        let addresses: HashSet<Address> = curated_pools.pools.iter().map(|pool| pool.address).collect();
+        assert_eq!(addresses.len(), curated_pools.pools.len());
+        assert!(curated_pools.pools.iter().all(|pool| pool.class != PoolClass::Unknown));
+
+        assert!(CuratedPools::bundled(0).is_none());
+    }
+}
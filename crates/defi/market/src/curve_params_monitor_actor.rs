@@ -0,0 +1,144 @@
+use alloy_primitives::B256;
+use alloy_rpc_types::Log;
+use alloy_sol_types::SolEvent;
+use std::collections::BTreeSet;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{error, info};
+
+use loom_core_actors::{subscribe, Accessor, Actor, ActorResult, Broadcaster, Consumer, Producer, SharedState, WorkerResult};
+use loom_core_actors_macros::{Accessor, Consumer, Producer};
+use loom_core_blockchain::Blockchain;
+use loom_defi_abi::curve::{ICurveCryptoParams, ICurveStableParams};
+use loom_types_entities::{Market, PoolClass, PoolId};
+use loom_types_events::{LoomTask, MessageBlockLogs};
+
+/// Events of Curve pools changing the amplification, e.g. starting or stopping an A ramp, or the fees
+const CURVE_PARAMS_EVENTS: [B256; 8] = [
+    ICurveStableParams::RampA::SIGNATURE_HASH,
+    ICurveStableParams::StopRampA::SIGNATURE_HASH,
+    ICurveStableParams::CommitNewFee::SIGNATURE_HASH,
+    ICurveStableParams::NewFee::SIGNATURE_HASH,
+    ICurveStableParams::ApplyNewFee::SIGNATURE_HASH,
+    ICurveCryptoParams::RampAgamma::SIGNATURE_HASH,
+    ICurveCryptoParams::StopRampA::SIGNATURE_HASH,
+    ICurveCryptoParams::NewParameters::SIGNATURE_HASH,
+];
+
+fn is_params_change(log: &Log) -> bool {
+    log.topic0().is_some_and(|topic0| CURVE_PARAMS_EVENTS.contains(topic0))
+}
+
+/// Pools emitting parameter change events in the logs
+fn params_changes(logs: &[Log]) -> BTreeSet<PoolId> {
+    logs.iter().filter(|log| is_params_change(log)).map(|log| PoolId::Address(log.inner.address)).collect()
+}
+
+pub async fn curve_params_monitor_worker(
+    log_update_rx: Broadcaster<MessageBlockLogs>,
+    market: SharedState<Market>,
+    tasks_tx: Broadcaster<LoomTask>,
+) -> WorkerResult {
+    subscribe!(log_update_rx);
+
+    loop {
+        let log_update: Result<MessageBlockLogs, RecvError> = log_update_rx.recv().await;
+        match log_update {
+            Ok(log_update_msg) => {
+                let mut pool_ids = params_changes(&log_update_msg.inner.logs);
+                if pool_ids.is_empty() {
+                    continue;
+                }
+                {
+                    let market_guard = market.read().await;
+                    pool_ids.retain(|pool_id| market_guard.get_pool(pool_id).is_some_and(|pool| pool.get_class() == PoolClass::Curve));
+                }
+
+                for pool_id in pool_ids {
+                    // the state of the pool is fetched again with the new parameters, A ramps are followed by the pool math
+                    info!(%pool_id, block_number = log_update_msg.inner.block_header.number, "Curve pool parameters changed");
+                    if let Err(error) = tasks_tx.send(LoomTask::RefreshPoolState(pool_id)) {
+                        error!(%error, %pool_id, "tasks_tx.send");
+                    }
+                }
+            }
+            Err(e) => {
+                error!("block_update error {}", e)
+            }
+        }
+    }
+}
+
+/// Refreshes the state of Curve pools when their amplification or fees are changed, without reloading the pools
+#[derive(Accessor, Consumer, Producer, Default)]
+pub struct CurveParamsMonitorActor {
+    #[accessor]
+    market: Option<SharedState<Market>>,
+    #[consumer]
+    log_update_rx: Option<Broadcaster<MessageBlockLogs>>,
+    #[producer]
+    tasks_tx: Option<Broadcaster<LoomTask>>,
+}
+
+impl CurveParamsMonitorActor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn on_bc(self, bc: &Blockchain) -> Self {
+        Self { market: Some(bc.market()), log_update_rx: Some(bc.new_block_logs_channel()), tasks_tx: Some(bc.tasks_channel()) }
+    }
+}
+
+impl Actor for CurveParamsMonitorActor {
+    fn start(&self) -> ActorResult {
+        let task = tokio::task::spawn(curve_params_monitor_worker(
+            self.log_update_rx.clone().unwrap(),
+            self.market.clone().unwrap(),
+            self.tasks_tx.clone().unwrap(),
+        ));
+        Ok(vec![task])
+    }
+
+    fn name(&self) -> &'static str {
+        "CurveParamsMonitorActor"
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloy_primitives::{Address, LogData, U256};
+
+    fn log(address: Address, data: LogData) -> Log {
+        Log { inner: alloy_primitives::Log { address, data }, ..Default::default() }
+    }
+
+    #[test]
+    fn test_params_changes() {
+        let (pool0, pool1) = (Address::repeat_byte(0x10), Address::repeat_byte(0x11));
+        let ramp = ICurveStableParams::RampA {
+            old_A: U256::from(100),
+            new_A: U256::from(200),
+            initial_time: U256::from(1000),
+            future_time: U256::from(87400),
+        };
+        let new_parameters = ICurveCryptoParams::NewParameters {
+            admin_fee: U256::from(5000000000u64),
+            mid_fee: U256::from(3000000),
+            out_fee: U256::from(30000000),
+            fee_gamma: U256::from(500000000000000u64),
+            allowed_extra_profit: U256::from(2000000000000u64),
+            adjustment_step: U256::from(490000000000000u64),
+            ma_half_time: U256::from(600),
+        };
+
+        let logs = vec![
+            log(pool0, ramp.encode_log_data()),
+            log(pool0, ramp.encode_log_data()),
+            log(pool1, new_parameters.encode_log_data()),
+            log(Address::repeat_byte(0x12), LogData::default()),
+        ];
+        assert_eq!(params_changes(&logs).into_iter().collect::<Vec<_>>(), vec![PoolId::Address(pool0), PoolId::Address(pool1)]);
+        assert!(params_changes(&logs[3..]).is_empty());
+    }
+}
@@ -0,0 +1,489 @@
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use alloy_network::Network;
+use alloy_primitives::{Address, B256};
+use alloy_provider::Provider;
+use eyre::Result;
+use revm::{Database, DatabaseCommit, DatabaseRef};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use tracing::{debug, error, info};
+
+use loom_core_actors::{run_sync, subscribe, Accessor, Actor, ActorResult, Broadcaster, Consumer, Producer, SharedState, WorkerResult};
+use loom_core_actors_macros::{Accessor, Consumer, Producer};
+use loom_core_blockchain::{Blockchain, BlockchainState};
+use loom_node_debug_provider::DebugProviderExt;
+use loom_storage_db::{
+    insert_pools, insert_tokens, load_path_scores, load_pools, load_tokens, set_pools_disabled, upsert_path_scores, DbPool,
+    PathScoreRecord, PoolRecord, TokenRecord,
+};
+use loom_types_entities::pool_config::PoolsLoadingConfig;
+use loom_types_entities::{path_key, Market, MarketState, PoolClass, PoolEvent, PoolEventRecord, PoolId, PoolLoaders, Token};
+use loom_types_events::MarketEvents;
+
+use crate::pool_load_coalescer::{PoolLoadCoalescer, PoolLoadOutcome};
+use crate::pool_loader_actor::fetch_and_add_allowed_pool_outcome;
+
+const MAX_CONCURRENT_TASKS: usize = 20;
+const DEFAULT_SYNC_BLOCKS: u64 = 100;
+
+fn pool_id_to_bytes(pool_id: &PoolId) -> Vec<u8> {
+    match pool_id {
+        PoolId::Address(address) => address.to_vec(),
+        PoolId::Bytes32(bytes32) => bytes32.to_vec(),
+    }
+}
+
+fn pool_id_from_bytes(bytes: &[u8]) -> Option<PoolId> {
+    match bytes.len() {
+        20 => Some(PoolId::Address(Address::from_slice(bytes))),
+        32 => Some(PoolId::Bytes32(B256::from_slice(bytes))),
+        _ => None,
+    }
+}
+
+/// Add persisted tokens and pools to the market and apply the persisted disable flags and path scores.
+/// Returns the persisted pools with their disable flags
+#[allow(clippy::too_many_arguments)]
+async fn hydrate_market<P, PL, N, DB>(
+    client: P,
+    pool_loaders: Arc<PoolLoaders<PL, N>>,
+    pools_config: &PoolsLoadingConfig,
+    load_coalescer: &PoolLoadCoalescer,
+    db_pool: &DbPool,
+    chain_id: i64,
+    market: SharedState<Market>,
+    market_state: SharedState<MarketState<DB>>,
+    market_events_tx: Broadcaster<MarketEvents>,
+) -> Result<HashMap<PoolId, bool>>
+where
+    N: Network,
+    P: Provider<N> + DebugProviderExt<N> + Send + Sync + Clone + 'static,
+    PL: Provider<N> + Send + Sync + Clone + 'static,
+    DB: Database + DatabaseRef + DatabaseCommit + Send + Sync + Clone + 'static,
+{
+    let token_records = load_tokens(db_pool, chain_id).await?;
+    {
+        let mut market_guard = market.write().await;
+        for record in token_records {
+            let Ok(address) = Address::try_from(record.address.as_slice()) else { continue };
+            if market_guard.get_token(&address).is_none() {
+                let decimals = u8::try_from(record.decimals).ok();
+                market_guard.add_token(Token::new_with_data(address, record.symbol, record.name, decimals, record.basic, record.middle));
+            }
+        }
+    }
+
+    let pool_records = load_pools(db_pool, chain_id).await?;
+    let mut persisted_pools: HashMap<PoolId, bool> = HashMap::new();
+
+    let semaphore = Arc::new(Semaphore::new(pools_config.threads().unwrap_or(MAX_CONCURRENT_TASKS)));
+    let mut loading_tasks = JoinSet::new();
+
+    for record in pool_records.iter() {
+        let Some(pool_id) = pool_id_from_bytes(&record.pool_id) else {
+            error!(pool_id = ?record.pool_id, "Persisted pool id is invalid");
+            continue;
+        };
+        persisted_pools.insert(pool_id, record.disabled);
+
+        let Ok(pool_class) = PoolClass::from_str(&record.pool_class) else {
+            error!(%pool_id, pool_class = record.pool_class, "Persisted pool class is unknown");
+            continue;
+        };
+        if !pools_config.is_enabled(pool_class) || market.read().await.is_pool(&pool_id) {
+            continue;
+        }
+
+        let semaphore = semaphore.clone();
+        let client = client.clone();
+        let market = market.clone();
+        let market_state = market_state.clone();
+        let pool_loaders = pool_loaders.clone();
+        let pools_config = pools_config.clone();
+        let market_events_tx = market_events_tx.clone();
+        let load_coalescer = load_coalescer.clone();
+
+        loading_tasks.spawn(async move {
+            // pools discovered from logs meanwhile are loaded once, the loader reporting them is the one that ran the load
+            let (outcome, leader) = load_coalescer
+                .load(pool_id, || async move {
+                    let Ok(_permit) = semaphore.acquire().await else {
+                        return PoolLoadOutcome::Failed("failed acquire semaphore".to_string());
+                    };
+                    fetch_and_add_allowed_pool_outcome(client, market, market_state, pool_loaders, &pools_config, pool_id, pool_class).await
+                })
+                .await;
+            match outcome {
+                PoolLoadOutcome::Loaded(swap_path_idx_vec) => {
+                    debug!(%pool_id, %pool_class, leader, "Persisted pool loaded");
+                    if leader {
+                        run_sync!(market_events_tx.send(MarketEvents::NewPoolLoaded { pool_id, swap_path_idx_vec }));
+                    }
+                    true
+                }
+                PoolLoadOutcome::NotAllowed => {
+                    debug!(%pool_id, %pool_class, "Persisted pool is not allowed");
+                    false
+                }
+                PoolLoadOutcome::Failed(error) => {
+                    error!(%error, %pool_id, %pool_class, "Failed to load persisted pool");
+                    false
+                }
+            }
+        });
+    }
+
+    let mut loaded = 0usize;
+    while let Some(result) = loading_tasks.join_next().await {
+        if matches!(result, Ok(true)) {
+            loaded += 1;
+        }
+    }
+
+    let path_scores: HashMap<String, f64> =
+        load_path_scores(db_pool, chain_id).await?.into_iter().map(|record| (record.path, record.score)).collect();
+    {
+        let mut market_guard = market.write().await;
+        for (pool_id, disabled) in persisted_pools.iter() {
+            if *disabled {
+                market_guard.set_pool_all_disabled(*pool_id, true);
+            }
+        }
+        if !path_scores.is_empty() {
+            for swap_path in market_guard.swap_paths_mut().paths.iter_mut() {
+                if let Some(score) = path_scores.get(&path_key(swap_path)) {
+                    swap_path.score = Some(*score);
+                }
+            }
+        }
+    }
+    info!(loaded, total = pool_records.len(), path_scores = path_scores.len(), "Persisted pools loaded");
+
+    Ok(persisted_pools)
+}
+
+/// Persist a pool loaded by any pool loader together with its tokens
+async fn persist_pool(db_pool: &DbPool, chain_id: i64, market: &SharedState<Market>, pool_id: PoolId) -> Result<()> {
+    let (pool_record, token_records) = {
+        let market_guard = market.read().await;
+        let Some(pool) = market_guard.get_pool(&pool_id) else { return Ok(()) };
+        let pool_record = PoolRecord {
+            chain_id,
+            pool_id: pool_id_to_bytes(&pool_id),
+            pool_class: pool.get_class().to_string(),
+            disabled: market_guard.is_pool_flag_disabled(&pool_id),
+            first_seen_block: market_guard.pool_first_seen(&pool_id).map(|block_number| block_number as i64),
+        };
+        let token_records: Vec<TokenRecord> = pool
+            .get_tokens()
+            .iter()
+            .map(|address| {
+                let token = market_guard.get_token_or_default(address);
+                TokenRecord {
+                    chain_id,
+                    address: address.to_vec(),
+                    symbol: Some(token.get_symbol()),
+                    name: Some(token.get_name()),
+                    decimals: token.get_decimals() as i32,
+                    basic: token.is_basic(),
+                    middle: token.is_middle(),
+                }
+            })
+            .collect();
+        (pool_record, token_records)
+    };
+
+    insert_tokens(db_pool, &token_records).await?;
+    insert_pools(db_pool, &[pool_record]).await?;
+    Ok(())
+}
+
+/// Persist the disable flag of a persisted pool if it changed
+async fn persist_pool_disabled(
+    db_pool: &DbPool,
+    chain_id: i64,
+    persisted_pools: &mut HashMap<PoolId, bool>,
+    pool_id: PoolId,
+    disabled: bool,
+) -> Result<()> {
+    match persisted_pools.get_mut(&pool_id) {
+        Some(persisted_disabled) if *persisted_disabled != disabled => {
+            set_pools_disabled(db_pool, chain_id, vec![pool_id_to_bytes(&pool_id)], disabled).await?;
+            *persisted_disabled = disabled;
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Persist the pools of the market missing in the database and changed disable flags of the persisted pools, used
+/// when pool events were missed
+async fn resync_pools(
+    db_pool: &DbPool,
+    chain_id: i64,
+    market: &SharedState<Market>,
+    persisted_pools: &mut HashMap<PoolId, bool>,
+) -> Result<()> {
+    let (new_pools, changed_pools) = {
+        let market_guard = market.read().await;
+        let new_pools: Vec<PoolId> =
+            market_guard.pools().keys().filter(|pool_id| !persisted_pools.contains_key(*pool_id)).copied().collect();
+        let changed_pools: Vec<(PoolId, bool)> = persisted_pools
+            .iter()
+            .filter_map(|(pool_id, disabled)| {
+                let is_disabled = market_guard.is_pool_flag_disabled(pool_id);
+                (is_disabled != *disabled).then_some((*pool_id, is_disabled))
+            })
+            .collect();
+        (new_pools, changed_pools)
+    };
+
+    for pool_id in new_pools.iter() {
+        persist_pool(db_pool, chain_id, market, *pool_id).await?;
+        persisted_pools.insert(*pool_id, market.read().await.is_pool_flag_disabled(pool_id));
+    }
+    for (pool_id, disabled) in changed_pools.iter() {
+        persist_pool_disabled(db_pool, chain_id, persisted_pools, *pool_id, *disabled).await?;
+    }
+    info!(new_pools = new_pools.len(), changed_pools = changed_pools.len(), "Pools resynced to database");
+    Ok(())
+}
+
+/// Persist the scores of scored paths
+async fn sync_path_scores(db_pool: &DbPool, chain_id: i64, market: &SharedState<Market>) -> Result<()> {
+    let path_score_records: Vec<PathScoreRecord> = market
+        .read()
+        .await
+        .swap_paths()
+        .paths
+        .iter()
+        .filter_map(|swap_path| Some(PathScoreRecord { chain_id, path: path_key(swap_path), score: swap_path.score? }))
+        .collect();
+
+    if !path_score_records.is_empty() {
+        upsert_path_scores(db_pool, &path_score_records).await?;
+    }
+    debug!(path_scores = path_score_records.len(), "Path scores synced to database");
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn db_pool_loader_worker<P, PL, N, DB>(
+    client: P,
+    pool_loaders: Arc<PoolLoaders<PL, N>>,
+    pools_config: PoolsLoadingConfig,
+    load_coalescer: PoolLoadCoalescer,
+    db_pool: DbPool,
+    sync_blocks: u64,
+    market: SharedState<Market>,
+    market_state: SharedState<MarketState<DB>>,
+    market_events_rx: Broadcaster<MarketEvents>,
+    market_events_tx: Broadcaster<MarketEvents>,
+    pool_events_rx: Broadcaster<PoolEventRecord>,
+) -> WorkerResult
+where
+    N: Network,
+    P: Provider<N> + DebugProviderExt<N> + Send + Sync + Clone + 'static,
+    PL: Provider<N> + Send + Sync + Clone + 'static,
+    DB: Database + DatabaseRef + DatabaseCommit + Send + Sync + Clone + 'static,
+{
+    let chain_id = client.get_chain_id().await? as i64;
+    let mut persisted_pools = hydrate_market(
+        client,
+        pool_loaders,
+        &pools_config,
+        &load_coalescer,
+        &db_pool,
+        chain_id,
+        market.clone(),
+        market_state,
+        market_events_tx,
+    )
+    .await?;
+
+    // subscribed after hydration so its own pool loads don't lag the receivers, pools found by other loaders
+    // meanwhile are persisted by the resync
+    subscribe!(market_events_rx);
+    subscribe!(pool_events_rx);
+    resync_pools(&db_pool, chain_id, &market, &mut persisted_pools).await?;
+
+    let mut last_seq: Option<u64> = None;
+
+    loop {
+        tokio::select! {
+            msg = pool_events_rx.recv() => {
+                let pool_event: Result<PoolEventRecord, RecvError> = msg;
+                match pool_event {
+                    Ok(record) => {
+                        // a gap in the sequence numbers means events were dropped before they were published
+                        let missed = last_seq.is_some_and(|last_seq| record.seq > last_seq + 1);
+                        last_seq = Some(record.seq);
+                        let result = match record.event {
+                            _ if missed => resync_pools(&db_pool, chain_id, &market, &mut persisted_pools).await,
+                            PoolEvent::Added { pool_id, .. } if !persisted_pools.contains_key(&pool_id) => {
+                                persist_pool(&db_pool, chain_id, &market, pool_id).await.map(|_| {
+                                    persisted_pools.insert(pool_id, false);
+                                })
+                            }
+                            PoolEvent::Disabled { pool_id, .. } => {
+                                // denied pools and disabled classes follow the running config, only the pool flag is persisted
+                                let disabled = market.read().await.is_pool_flag_disabled(&pool_id);
+                                persist_pool_disabled(&db_pool, chain_id, &mut persisted_pools, pool_id, disabled).await
+                            }
+                            _ => Ok(()),
+                        };
+                        if let Err(error) = result {
+                            error!(%error, seq = record.seq, "Failed to persist pool event")
+                        }
+                    }
+                    Err(RecvError::Lagged(lagged)) => {
+                        error!(lagged, "pool_events_rx lagged");
+                        if let Err(error) = resync_pools(&db_pool, chain_id, &market, &mut persisted_pools).await {
+                            error!(%error, "Failed to resync pools to database")
+                        }
+                    }
+                    Err(e) => {
+                        error!("pool_events_rx error {}", e)
+                    }
+                }
+            }
+            msg = market_events_rx.recv() => {
+                let market_event: Result<MarketEvents, RecvError> = msg;
+                match market_event {
+                    Ok(MarketEvents::BlockHeaderUpdate { block_number, .. }) if block_number % sync_blocks == 0 => {
+                        if let Err(error) = sync_path_scores(&db_pool, chain_id, &market).await {
+                            error!(%error, "Failed to sync path scores to database")
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(e) => {
+                        error!("market_events_rx error {}", e)
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Hydrates the market with the pools, tokens, disable flags and path scores persisted in the database and persists
+/// pools added to the market by the other loaders and disable flags from the pool events. Path scores are written back
+/// every `sync_blocks` blocks
+#[derive(Accessor, Consumer, Producer)]
+pub struct DbPoolLoaderActor<P, PL, N, DB>
+where
+    N: Network,
+    P: Provider<N> + Send + Sync + Clone + 'static,
+    PL: Provider<N> + Send + Sync + Clone + 'static,
+    DB: Database + DatabaseRef + DatabaseCommit + Send + Sync + Clone + 'static,
+{
+    client: P,
+    pool_loaders: Arc<PoolLoaders<PL, N>>,
+    pools_config: PoolsLoadingConfig,
+    db_pool: DbPool,
+    sync_blocks: u64,
+    load_coalescer: PoolLoadCoalescer,
+    #[accessor]
+    market: Option<SharedState<Market>>,
+    #[accessor]
+    market_state: Option<SharedState<MarketState<DB>>>,
+    #[consumer]
+    market_events_rx: Option<Broadcaster<MarketEvents>>,
+    #[producer]
+    market_events_tx: Option<Broadcaster<MarketEvents>>,
+    #[consumer]
+    pool_events_rx: Option<Broadcaster<PoolEventRecord>>,
+    _n: PhantomData<N>,
+}
+
+impl<P, PL, N, DB> DbPoolLoaderActor<P, PL, N, DB>
+where
+    N: Network,
+    P: Provider<N> + Send + Sync + Clone + 'static,
+    PL: Provider<N> + Send + Sync + Clone + 'static,
+    DB: Database + DatabaseRef + DatabaseCommit + Send + Sync + Clone + 'static,
+{
+    pub fn new(client: P, pool_loaders: Arc<PoolLoaders<PL, N>>, pools_config: PoolsLoadingConfig, db_pool: DbPool) -> Self {
+        Self {
+            client,
+            pool_loaders,
+            pools_config,
+            db_pool,
+            sync_blocks: DEFAULT_SYNC_BLOCKS,
+            load_coalescer: PoolLoadCoalescer::default(),
+            market: None,
+            market_state: None,
+            market_events_rx: None,
+            market_events_tx: None,
+            pool_events_rx: None,
+            _n: PhantomData,
+        }
+    }
+
+    pub fn with_sync_blocks(self, sync_blocks: u64) -> Self {
+        Self { sync_blocks: sync_blocks.max(1), ..self }
+    }
+
+    /// Share in-flight pool loads with other pool loaders of the market
+    pub fn with_load_coalescer(self, load_coalescer: PoolLoadCoalescer) -> Self {
+        Self { load_coalescer, ..self }
+    }
+
+    pub fn on_bc(self, bc: &Blockchain, state: &BlockchainState<DB>) -> Self {
+        Self {
+            market: Some(bc.market()),
+            market_state: Some(state.market_state_commit()),
+            market_events_rx: Some(bc.market_events_channel()),
+            market_events_tx: Some(bc.market_events_channel()),
+            pool_events_rx: Some(bc.pool_events_channel()),
+            ..self
+        }
+    }
+}
+
+impl<P, PL, N, DB> Actor for DbPoolLoaderActor<P, PL, N, DB>
+where
+    N: Network,
+    P: Provider<N> + DebugProviderExt<N> + Send + Sync + Clone + 'static,
+    PL: Provider<N> + Send + Sync + Clone + 'static,
+    DB: Database + DatabaseRef + DatabaseCommit + Send + Sync + Clone + 'static,
+{
+    fn start(&self) -> ActorResult {
+        let task = tokio::task::spawn(db_pool_loader_worker(
+            self.client.clone(),
+            self.pool_loaders.clone(),
+            self.pools_config.clone(),
+            self.load_coalescer.clone(),
+            self.db_pool.clone(),
+            self.sync_blocks,
+            self.market.clone().unwrap(),
+            self.market_state.clone().unwrap(),
+            self.market_events_rx.clone().unwrap(),
+            self.market_events_tx.clone().unwrap(),
+            self.pool_events_rx.clone().unwrap(),
+        ));
+        Ok(vec![task])
+    }
+
+    fn name(&self) -> &'static str {
+        "DbPoolLoaderActor"
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_pool_id_bytes() {
+        let pool_id = PoolId::Address(Address::repeat_byte(1));
+        assert_eq!(pool_id_from_bytes(&pool_id_to_bytes(&pool_id)), Some(pool_id));
+        let pool_id = PoolId::Bytes32(B256::repeat_byte(2));
+        assert_eq!(pool_id_from_bytes(&pool_id_to_bytes(&pool_id)), Some(pool_id));
+        assert_eq!(pool_id_from_bytes(&[0u8; 4]), None);
+    }
+}
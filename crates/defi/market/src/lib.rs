@@ -1,12 +1,38 @@
+pub use curated_pool_loader_actor::{CuratedPool, CuratedPoolLoaderOneShotActor, CuratedPools};
+pub use curve_params_monitor_actor::CurveParamsMonitorActor;
+pub use db_pool_loader_actor::DbPoolLoaderActor;
 pub use history_pool_loader_actor::HistoryPoolLoaderOneShotActor;
+pub use market_export_actor::{MarketExport, MarketExportActor, MarketExportDiff, MarketExportFull};
+pub use market_view_publisher_actor::MarketViewPublisherActor;
 pub use new_pool_actor::NewPoolLoaderActor;
-pub use pool_loader_actor::{fetch_and_add_pool_by_pool_id, fetch_state_and_add_pool, PoolLoaderActor};
+pub use pool_creation_listener_actor::PoolCreationListenerActor;
+pub use pool_events_publisher_actor::PoolEventsPublisherActor;
+pub use pool_load_coalescer::{PoolLoadCoalescer, PoolLoadOutcome};
+pub use pool_loader_actor::{fetch_and_add_pool_by_pool_id, fetch_state_and_add_pool, PoolLoaderActor, PoolsConfigReader};
+pub use pool_state_refresher_actor::PoolStateRefresherActor;
+pub use processed_pools::{PoolLoadStatus, ProcessedPools};
 pub use protocol_pool_loader_actor::ProtocolPoolLoaderOneShotActor;
+pub use proxy_monitor_actor::{fetch_proxy_implementation, ProxyMonitorActor, EIP1967_IMPLEMENTATION_SLOT};
 pub use required_pools_actor::RequiredPoolLoaderActor;
+pub use tick_word_loader_actor::TickWordLoaderActor;
+pub use token_hooks::{fetch_transfer_hook, ERC1363_INTERFACE_ID, ERC1820_REGISTRY};
 
+mod curated_pool_loader_actor;
+mod curve_params_monitor_actor;
+mod db_pool_loader_actor;
 mod history_pool_loader_actor;
 mod logs_parser;
+mod market_export_actor;
+mod market_view_publisher_actor;
 mod new_pool_actor;
+mod pool_creation_listener_actor;
+mod pool_events_publisher_actor;
+mod pool_load_coalescer;
 mod pool_loader_actor;
+mod pool_state_refresher_actor;
+mod processed_pools;
 mod protocol_pool_loader_actor;
+mod proxy_monitor_actor;
 mod required_pools_actor;
+mod tick_word_loader_actor;
+mod token_hooks;
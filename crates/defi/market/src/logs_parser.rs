@@ -5,14 +5,14 @@ use eyre::Result;
 use std::collections::HashMap;
 
 use loom_core_actors::{run_sync, Broadcaster};
-use loom_types_entities::PoolLoaders;
+use loom_types_entities::{PoolId, PoolLoaders};
 use loom_types_events::LoomTask;
 
 pub async fn process_log_entries<P, N>(
     log_entries: Vec<Log>,
     pool_loaders: &PoolLoaders<P, N>,
     tasks_tx: Broadcaster<LoomTask>,
-) -> Result<()>
+) -> Result<Vec<PoolId>>
 where
     N: Network,
     P: Provider<N> + Send + Sync + Clone + 'static,
@@ -31,6 +31,7 @@ where
         }
     }
 
+    let pool_ids = pool_to_fetch.iter().map(|(pool_id, _)| *pool_id).collect();
     run_sync!(tasks_tx.send(LoomTask::FetchAndAddPools(pool_to_fetch)));
-    Ok(())
+    Ok(pool_ids)
 }
@@ -0,0 +1,367 @@
+use std::collections::{BTreeMap, HashSet};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Instant;
+
+use alloy_primitives::{Address, U256};
+use eyre::{eyre, ErrReport, Result};
+use revm::primitives::Env;
+use revm::DatabaseRef;
+use serde::{Deserialize, Serialize};
+use tokio::io::AsyncWriteExt;
+use tokio::net::{UnixListener, UnixStream};
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{debug, error, info, warn};
+
+use loom_core_actors::{Accessor, Actor, ActorResult, SharedState, Snapshot, WorkerResult};
+use loom_core_actors_macros::Accessor;
+use loom_core_blockchain::{Blockchain, BlockchainState};
+use loom_defi_pools::state_readers::ERC20StateReader;
+use loom_types_entities::{
+    path_key, MarketSnapshot, MarketState, MarketView, PathSnapshot, PoolEvent, PoolId, PoolSnapshot, TokenClassification,
+};
+
+// diffs buffered for a client, a client falling further behind is disconnected and gets the full market on reconnect
+const EXPORT_CHANNEL_CAPACITY: usize = 64;
+
+/// Balances of the pool tokens held by the pool in the order of its tokens read from the state of the view block, pools
+/// of a vault do not hold their reserves
+fn pool_reserves<DB: DatabaseRef<Error = ErrReport>>(state_db: &DB, pool_id: &PoolId, tokens: Vec<Address>) -> Option<Vec<U256>> {
+    let PoolId::Address(address) = pool_id else { return None };
+    tokens.into_iter().map(|token| ERC20StateReader::balance_of(state_db, Env::default(), token, *address).ok()).collect()
+}
+
+/// Pools of the view with their state updated after the block
+fn updated_pools<DB>(market_view: &MarketView, market_state: &MarketState<DB>, block_number: u64) -> Vec<PoolId> {
+    market_view
+        .pools()
+        .keys()
+        .filter(|pool_id| match pool_id {
+            PoolId::Address(address) => market_state.last_updated.get(address).is_some_and(|updated| *updated > block_number),
+            PoolId::Bytes32(_) => false,
+        })
+        .copied()
+        .collect()
+}
+
+/// Market view sent to a client when it connects
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct MarketExportFull {
+    pub epoch: u64,
+    pub snapshot: MarketSnapshot,
+    /// Reserves of the pools by pool id
+    pub reserves: BTreeMap<String, Vec<U256>>,
+}
+
+/// Changes of the market view of a block over the previously exported one
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct MarketExportDiff {
+    pub epoch: u64,
+    pub block_number: u64,
+    /// Pools added, enabled or disabled by pool id
+    pub pools: BTreeMap<String, PoolSnapshot>,
+    pub pools_removed: Vec<String>,
+    /// Tokens of the added pools
+    pub tokens: BTreeMap<Address, TokenClassification>,
+    /// Paths of the pools with paths added by path key
+    pub paths: BTreeMap<String, PathSnapshot>,
+    /// Reserves of the changed pools and of the pools with their state updated since the previous export
+    pub reserves: BTreeMap<String, Vec<U256>>,
+}
+
+/// Market export line, one JSON document per line. A client gets the full market first and the diffs of every following
+/// block
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MarketExport {
+    Full(MarketExportFull),
+    Diff(MarketExportDiff),
+}
+
+impl MarketExport {
+    /// Full export of the market view with the reserves of its pools read from `state_db` if set
+    pub fn full<DB: DatabaseRef<Error = ErrReport>>(market_view: &MarketView, state_db: Option<&DB>) -> Self {
+        let snapshot = MarketSnapshot::new(market_view.market(), market_view.block_number());
+        let reserves = match state_db {
+            Some(state_db) => market_view
+                .pools()
+                .iter()
+                .filter_map(|(pool_id, pool)| Some((pool_id.to_string(), pool_reserves(state_db, pool_id, pool.get_tokens())?)))
+                .collect(),
+            None => BTreeMap::new(),
+        };
+        Self::Full(MarketExportFull { epoch: market_view.epoch(), snapshot, reserves })
+    }
+
+    /// Changes of the market view from the pool events after `since_seq` with the reserves of the changed pools and of the
+    /// pools in `updated_pools` read from `state_db` if set
+    pub fn diff<DB: DatabaseRef<Error = ErrReport>>(
+        market_view: &MarketView,
+        since_seq: u64,
+        updated_pools: &[PoolId],
+        state_db: Option<&DB>,
+    ) -> Self {
+        let market = market_view.market();
+        let mut diff = MarketExportDiff { epoch: market_view.epoch(), block_number: market_view.block_number(), ..Default::default() };
+
+        let mut changed_pools: HashSet<PoolId> = updated_pools.iter().copied().collect();
+        for record in market.pool_events().since(since_seq) {
+            let pool_id = record.event.pool_id();
+            let Some(pool) = market.get_pool(&pool_id) else {
+                if matches!(record.event, PoolEvent::Removed { .. }) {
+                    diff.pools_removed.push(pool_id.to_string());
+                }
+                continue;
+            };
+            changed_pools.insert(pool_id);
+            match record.event {
+                PoolEvent::Added { .. } | PoolEvent::Disabled { .. } => {
+                    diff.pools.insert(pool_id.to_string(), PoolSnapshot::new(market, &pool_id, pool));
+                    for token in pool.get_tokens().iter().filter_map(|address| market.get_token(address)) {
+                        diff.tokens.insert(token.get_address(), TokenClassification::new(market, &token));
+                    }
+                }
+                PoolEvent::Updated { .. } => {
+                    let swap_paths = market.swap_paths();
+                    for swap_path in
+                        swap_paths.pool_paths.get(&pool_id).into_iter().flatten().filter_map(|idx| swap_paths.get_path_by_idx(*idx))
+                    {
+                        diff.paths.insert(path_key(swap_path), PathSnapshot::new(market, swap_path));
+                    }
+                }
+                PoolEvent::Removed { .. } => {}
+            }
+        }
+        // pools removed and added again within the block are kept
+        diff.pools_removed.retain(|pool_id| !diff.pools.contains_key(pool_id));
+
+        if let Some(state_db) = state_db {
+            for pool_id in changed_pools {
+                if let Some(reserves) = market.get_pool(&pool_id).and_then(|pool| pool_reserves(state_db, &pool_id, pool.get_tokens())) {
+                    diff.reserves.insert(pool_id.to_string(), reserves);
+                }
+            }
+        }
+        Self::Diff(diff)
+    }
+
+    /// Newline terminated JSON document
+    pub fn to_line(&self) -> Result<String> {
+        let mut line = serde_json::to_string(self)?;
+        line.push('\n');
+        Ok(line)
+    }
+}
+
+/// Writes the full market to the client and the diffs of every following block. A client too slow for the diffs is
+/// disconnected, it gets the full market again on reconnect
+async fn market_export_client_worker(mut stream: UnixStream, full_line: String, mut export_rx: broadcast::Receiver<Arc<String>>) {
+    if let Err(error) = stream.write_all(full_line.as_bytes()).await {
+        debug!(%error, "Market export client disconnected");
+        return;
+    }
+    loop {
+        let line = match export_rx.recv().await {
+            Ok(line) => line,
+            Err(RecvError::Lagged(skipped)) => {
+                warn!(skipped, "Market export client too slow, disconnected");
+                return;
+            }
+            Err(RecvError::Closed) => return,
+        };
+        if let Err(error) = stream.write_all(line.as_bytes()).await {
+            debug!(%error, "Market export client disconnected");
+            return;
+        }
+    }
+}
+
+pub async fn market_export_worker<DB>(
+    socket_path: PathBuf,
+    reserves: bool,
+    market_view: Snapshot<MarketView>,
+    market_state: SharedState<MarketState<DB>>,
+) -> WorkerResult
+where
+    DB: DatabaseRef<Error = ErrReport> + Send + Sync + Clone + 'static,
+{
+    // socket left by a previous run
+    if socket_path.exists() {
+        std::fs::remove_file(&socket_path)?;
+    }
+    let listener = UnixListener::bind(&socket_path)?;
+    info!(path = %socket_path.display(), reserves, "Market export listening");
+
+    let (export_tx, _) = broadcast::channel::<Arc<String>>(EXPORT_CHANNEL_CAPACITY);
+    let mut market_view_rx = market_view.subscribe();
+    // last exported view, the diffs follow its pool events and its block
+    let mut exported = market_view.latest();
+    let mut exported_seq = exported.market().pool_events().last_seq();
+
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => {
+                match accepted {
+                    Ok((stream, _)) => {
+                        // subscribed before the full export, diffs are only produced by this loop
+                        let export_rx = export_tx.subscribe();
+                        // reserves are read from the market state of the view block, without them if the state moved on
+                        let market_state_guard = market_state.read().await;
+                        let state_db = (reserves && market_state_guard.block_number == exported.block_number())
+                            .then_some(&market_state_guard.state_db);
+                        let full_line = MarketExport::full(&exported, state_db).to_line();
+                        drop(market_state_guard);
+                        match full_line {
+                            Ok(full_line) => {
+                                debug!(bytes = full_line.len(), "Market export client connected");
+                                tokio::task::spawn(market_export_client_worker(stream, full_line, export_rx));
+                            }
+                            Err(error) => error!(%error, "Market export encoding failed"),
+                        }
+                    }
+                    Err(error) => error!(%error, "listener.accept"),
+                }
+            }
+            changed = market_view_rx.changed() => {
+                if changed.is_err() {
+                    return Err(eyre!("MARKET_VIEW_CLOSED"));
+                }
+                let view = market_view_rx.borrow_and_update().clone();
+                let start_time = Instant::now();
+
+                // reserves are pinned to the market state of the view block, a view behind the state is followed by a
+                // newer one and its changes are exported with it
+                let market_state_guard = market_state.read().await;
+                if reserves && market_state_guard.block_number != view.block_number() {
+                    debug!(
+                        block_number = view.block_number(),
+                        state_block_number = market_state_guard.block_number,
+                        "Market view behind the market state"
+                    );
+                    continue;
+                }
+                let updated_pools = match reserves {
+                    true => updated_pools(&view, &market_state_guard, exported.block_number()),
+                    false => Vec::new(),
+                };
+                let state_db = reserves.then_some(&market_state_guard.state_db);
+
+                // pool events dropped from the log are not in the diff, the clients are resynced with the full market
+                let events_lost = view.market().pool_events().first_seq().is_some_and(|first_seq| first_seq > exported_seq + 1);
+                let line = match events_lost {
+                    true => MarketExport::full(&view, state_db).to_line(),
+                    false => MarketExport::diff(&view, exported_seq, &updated_pools, state_db).to_line(),
+                };
+                drop(market_state_guard);
+
+                exported_seq = view.market().pool_events().last_seq();
+                exported = view;
+                match line {
+                    Ok(line) => {
+                        debug!(bytes = line.len(), full = events_lost, elapsed = start_time.elapsed().as_micros(), "Market exported");
+                        // no clients connected
+                        let _ = export_tx.send(Arc::new(line));
+                    }
+                    Err(error) => error!(%error, "Market export encoding failed"),
+                }
+            }
+        }
+    }
+}
+
+/// Exports the market view of every block with the pool reserves over a unix socket, for sidecar processes reading the
+/// market without polling the rpc
+#[derive(Accessor)]
+pub struct MarketExportActor<DB: Clone + Send + Sync + 'static> {
+    socket_path: PathBuf,
+    reserves: bool,
+    market_view: Option<Snapshot<MarketView>>,
+    #[accessor]
+    market_state: Option<SharedState<MarketState<DB>>>,
+}
+
+impl<DB> MarketExportActor<DB>
+where
+    DB: DatabaseRef<Error = ErrReport> + Send + Sync + Clone + 'static,
+{
+    pub fn new(socket_path: impl Into<PathBuf>) -> Self {
+        Self { socket_path: socket_path.into(), reserves: false, market_view: None, market_state: None }
+    }
+
+    /// Read the token balances of the changed pools for every export, an evm call per pool token
+    pub fn with_reserves(self, reserves: bool) -> Self {
+        Self { reserves, ..self }
+    }
+
+    pub fn on_bc(self, bc: &Blockchain, state: &BlockchainState<DB>) -> Self {
+        Self { market_view: Some(bc.market_view()), market_state: Some(state.market_state()), ..self }
+    }
+}
+
+impl<DB> Actor for MarketExportActor<DB>
+where
+    DB: DatabaseRef<Error = ErrReport> + Send + Sync + Clone + 'static,
+{
+    fn start(&self) -> ActorResult {
+        let task = tokio::task::spawn(market_export_worker(
+            self.socket_path.clone(),
+            self.reserves,
+            self.market_view.clone().unwrap(),
+            self.market_state.clone().unwrap(),
+        ));
+        Ok(vec![task])
+    }
+
+    fn name(&self) -> &'static str {
+        "MarketExportActor"
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use loom_evm_db::LoomDBType;
+    use loom_types_entities::{Market, MockPool, SwapPath, Token};
+
+    #[test]
+    fn test_market_export() {
+        let (token0, token1, pool) = (Address::repeat_byte(1), Address::repeat_byte(2), Address::repeat_byte(0x10));
+        let mut market = Market::default();
+        market.add_pool(MockPool::new(token0, token1, pool)).unwrap();
+        market.add_paths(vec![SwapPath::new(vec![Token::new(token0), Token::new(token1)], vec![MockPool::new(token0, token1, pool)])]);
+        let market_view = MarketView::new(100, market.clone()).with_epoch(7);
+
+        let MarketExport::Full(full) = MarketExport::full::<LoomDBType>(&market_view, None) else { panic!("full export expected") };
+        assert_eq!(full.epoch, 7);
+        assert_eq!(full.snapshot.block_number, 100);
+        assert_eq!(full.snapshot.pools.len(), 1);
+        assert_eq!(full.snapshot.paths.len(), 1);
+        assert!(full.reserves.is_empty());
+
+        let line = MarketExport::Full(full.clone()).to_line().unwrap();
+        assert_eq!(line.matches('\n').count(), 1);
+        assert_eq!(serde_json::from_str::<MarketExport>(&line).unwrap(), MarketExport::Full(full));
+
+        // the diff only has the pool added after the exported events
+        let since_seq = market.pool_events().last_seq();
+        let (token2, pool1) = (Address::repeat_byte(3), Address::repeat_byte(0x11));
+        market.add_pool(MockPool::new(token1, token2, pool1)).unwrap();
+        market.add_paths(vec![SwapPath::new(vec![Token::new(token1), Token::new(token2)], vec![MockPool::new(token1, token2, pool1)])]);
+        market.remove_pool(&PoolId::Address(pool)).unwrap();
+        let market_view = MarketView::new(101, market).with_epoch(8);
+
+        let MarketExport::Diff(diff) = MarketExport::diff::<LoomDBType>(&market_view, since_seq, &[], None) else {
+            panic!("diff expected")
+        };
+        assert_eq!((diff.epoch, diff.block_number), (8, 101));
+        assert_eq!(diff.pools.keys().cloned().collect::<Vec<_>>(), vec![PoolId::Address(pool1).to_string()]);
+        assert_eq!(diff.pools_removed, vec![PoolId::Address(pool).to_string()]);
+        assert_eq!(diff.paths.len(), 1);
+        assert!(diff.reserves.is_empty());
+        assert_eq!(
+            serde_json::from_str::<MarketExport>(&MarketExport::Diff(diff.clone()).to_line().unwrap()).unwrap(),
+            MarketExport::Diff(diff)
+        );
+    }
+}
@@ -0,0 +1,175 @@
+use std::time::Duration;
+
+use tokio::sync::broadcast::error::RecvError;
+use tokio::time::Instant;
+use tracing::{debug, error, warn};
+
+use loom_core_actors::{subscribe, Accessor, Actor, ActorResult, Broadcaster, Consumer, SharedState, Snapshot, WorkerResult};
+use loom_core_actors_macros::{Accessor, Consumer};
+use loom_core_blockchain::{Blockchain, BlockchainState};
+use loom_types_entities::{Market, MarketState, MarketView};
+use loom_types_events::MarketEvents;
+use revm::DatabaseRef;
+
+// time a view is deferred for pools staged by loaders before it is published anyway
+const MAX_VIEW_DEFERRAL: Duration = Duration::from_millis(500);
+
+pub async fn market_view_publisher_worker<DB: Clone + Send + Sync + 'static>(
+    market: SharedState<Market>,
+    market_state: SharedState<MarketState<DB>>,
+    market_view: Snapshot<MarketView>,
+    market_events_rx: Broadcaster<MarketEvents>,
+    max_deferral: Duration,
+) -> WorkerResult {
+    subscribe!(market_events_rx);
+
+    let mut epoch: u64 = market_view.latest().epoch();
+    // block state applied and the view of the block not published yet, the view is published with staged pools after
+    // the deadline
+    let mut pending_deadline: Option<Instant> = None;
+
+    loop {
+        tokio::select! {
+            market_event = market_events_rx.recv() => {
+                let market_event: Result<MarketEvents, RecvError> = market_event;
+                match market_event {
+                    // pools and paths added while the block state was applied are part of the view of the block
+                    Ok(MarketEvents::BlockStateUpdate { .. }) => {
+                        pending_deadline.get_or_insert(Instant::now() + max_deferral);
+                    }
+                    // a committed pool may be the last staged one of a deferred view
+                    Ok(MarketEvents::NewPoolLoaded { .. }) if pending_deadline.is_some() => {}
+                    Ok(_) => continue,
+                    Err(e) => {
+                        error!("market_events_rx error {}", e);
+                        continue;
+                    }
+                }
+            }
+            _ = tokio::time::sleep_until(pending_deadline.unwrap_or_else(Instant::now)), if pending_deadline.is_some() => {}
+        }
+
+        let Some(deadline) = pending_deadline else {
+            continue;
+        };
+        let start_time = std::time::Instant::now();
+        let market_guard = market.read().await;
+        // second phase of pool loads, the view is only cut when no pool is partially added
+        if market_guard.has_staged_pools() {
+            if Instant::now() < deadline {
+                debug!("Market view deferred for staged pools");
+                continue;
+            }
+            warn!(deferred_ms = max_deferral.as_millis(), "Market view published with staged pools");
+        }
+        // pools, tokens and paths are shared with the market, they are copied by the first change after the publication
+        let market_copy = market_guard.clone();
+        drop(market_guard);
+        // committed pools have their state applied, the view is versioned with the block of the market state
+        let block_number = market_state.read().await.block_number;
+
+        epoch += 1;
+        market_view.publish(MarketView::new(block_number, market_copy).with_epoch(epoch));
+        pending_deadline = None;
+        debug!(block_number, epoch, elapsed = start_time.elapsed().as_micros(), "Market view published");
+    }
+}
+
+/// Publishes a shared copy of the market once per block after the block state is applied and the pools staged by loaders are
+/// committed, the epoch of the view is advanced with every publication. The view is published with staged pools when they
+/// are not committed within the max deferral
+#[derive(Accessor, Consumer)]
+pub struct MarketViewPublisherActor<DB: Clone + Send + Sync + 'static> {
+    #[accessor]
+    market: Option<SharedState<Market>>,
+    #[accessor]
+    market_state: Option<SharedState<MarketState<DB>>>,
+    market_view: Option<Snapshot<MarketView>>,
+    #[consumer]
+    market_events_rx: Option<Broadcaster<MarketEvents>>,
+    max_deferral: Duration,
+}
+
+impl<DB: DatabaseRef + Send + Sync + Clone + 'static> MarketViewPublisherActor<DB> {
+    pub fn new() -> Self {
+        Self { market: None, market_state: None, market_view: None, market_events_rx: None, max_deferral: MAX_VIEW_DEFERRAL }
+    }
+
+    pub fn with_market_view(self, market_view: Snapshot<MarketView>) -> Self {
+        Self { market_view: Some(market_view), ..self }
+    }
+
+    pub fn with_max_deferral(self, max_deferral: Duration) -> Self {
+        Self { max_deferral, ..self }
+    }
+
+    pub fn on_bc(self, bc: &Blockchain, state: &BlockchainState<DB>) -> Self {
+        Self {
+            market: Some(bc.market()),
+            market_state: Some(state.market_state()),
+            market_view: Some(bc.market_view()),
+            market_events_rx: Some(bc.market_events_channel()),
+            ..self
+        }
+    }
+}
+
+impl<DB: DatabaseRef + Send + Sync + Clone + 'static> Default for MarketViewPublisherActor<DB> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<DB: DatabaseRef + Send + Sync + Clone + 'static> Actor for MarketViewPublisherActor<DB> {
+    fn start(&self) -> ActorResult {
+        let task = tokio::task::spawn(market_view_publisher_worker(
+            self.market.clone().unwrap(),
+            self.market_state.clone().unwrap(),
+            self.market_view.clone().unwrap(),
+            self.market_events_rx.clone().unwrap(),
+            self.max_deferral,
+        ));
+        Ok(vec![task])
+    }
+
+    fn name(&self) -> &'static str {
+        "MarketViewPublisherActor"
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloy_primitives::Address;
+    use loom_evm_db::LoomDBType;
+    use loom_types_entities::PoolId;
+
+    #[tokio::test]
+    async fn test_deferral_bounded() {
+        let market = SharedState::new(Market::default());
+        let mut state = MarketState::new(LoomDBType::default());
+        state.block_number = 10;
+        let market_view = Snapshot::new(MarketView::default());
+        let market_events: Broadcaster<MarketEvents> = Broadcaster::new(10);
+        market.write().await.stage_pool(PoolId::Address(Address::repeat_byte(1)));
+
+        tokio::task::spawn(market_view_publisher_worker(
+            market,
+            SharedState::new(state),
+            market_view.clone(),
+            market_events.clone(),
+            Duration::from_millis(50),
+        ));
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        market_events.send(MarketEvents::BlockStateUpdate { block_hash: Default::default() }).unwrap();
+
+        // deferred for the staged pool
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(market_view.latest().epoch(), 0);
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let latest = market_view.latest();
+        assert_eq!(latest.epoch(), 1);
+        assert_eq!(latest.block_number(), 10);
+    }
+}
@@ -5,10 +5,10 @@ use std::sync::Arc;
 use tokio::sync::broadcast::error::RecvError;
 use tracing::{debug, error};
 
-use loom_core_actors::{subscribe, Actor, ActorResult, Broadcaster, Consumer, Producer, WorkerResult};
-use loom_core_actors_macros::{Consumer, Producer};
+use loom_core_actors::{subscribe, Accessor, Actor, ActorResult, Broadcaster, Consumer, Producer, SharedState, WorkerResult};
+use loom_core_actors_macros::{Accessor, Consumer, Producer};
 use loom_core_blockchain::Blockchain;
-use loom_types_entities::PoolLoaders;
+use loom_types_entities::{Market, PoolLoaders};
 use loom_types_events::{LoomTask, MessageBlockLogs};
 
 use crate::logs_parser::process_log_entries;
@@ -17,6 +17,7 @@ pub async fn new_pool_worker<P, N>(
     log_update_rx: Broadcaster<MessageBlockLogs>,
     pools_loaders: Arc<PoolLoaders<P, N>>,
     tasks_tx: Broadcaster<LoomTask>,
+    market: SharedState<Market>,
 ) -> WorkerResult
 where
     N: Network,
@@ -32,11 +33,22 @@ where
                 let log_update : Result<MessageBlockLogs, RecvError>  = msg;
                 match log_update {
                     Ok(log_update_msg)=>{
-                        process_log_entries(
+                        let block_number = log_update_msg.inner.block_header.number;
+                        let pool_ids = process_log_entries(
                                 log_update_msg.inner.logs,
                                 &pools_loaders,
                                 tasks_tx.clone(),
-                        ).await?
+                        ).await?;
+
+                        // pool age for risk scoring, pools already in the market were loaded before
+                        if !pool_ids.is_empty() {
+                            let mut market_guard = market.write().await;
+                            for pool_id in pool_ids {
+                                if !market_guard.is_pool(&pool_id) {
+                                    market_guard.set_pool_first_seen(pool_id, block_number);
+                                }
+                            }
+                        }
                     }
                     Err(e)=>{
                         error!("block_update error {}", e)
@@ -48,13 +60,15 @@ where
     }
 }
 
-#[derive(Consumer, Producer)]
+#[derive(Accessor, Consumer, Producer)]
 pub struct NewPoolLoaderActor<P, N>
 where
     N: Network,
     P: Provider<N> + Send + Sync + Clone + 'static,
 {
     pool_loaders: Arc<PoolLoaders<P, N>>,
+    #[accessor]
+    market: Option<SharedState<Market>>,
     #[consumer]
     log_update_rx: Option<Broadcaster<MessageBlockLogs>>,
     #[producer]
@@ -67,11 +81,11 @@ where
     P: Provider<N> + Send + Sync + Clone + 'static,
 {
     pub fn new(pool_loaders: Arc<PoolLoaders<P, N>>) -> Self {
-        NewPoolLoaderActor { log_update_rx: None, pool_loaders, tasks_tx: None }
+        NewPoolLoaderActor { log_update_rx: None, pool_loaders, market: None, tasks_tx: None }
     }
 
     pub fn on_bc(self, bc: &Blockchain) -> Self {
-        Self { log_update_rx: Some(bc.new_block_logs_channel()), tasks_tx: Some(bc.tasks_channel()), ..self }
+        Self { log_update_rx: Some(bc.new_block_logs_channel()), market: Some(bc.market()), tasks_tx: Some(bc.tasks_channel()), ..self }
     }
 }
 
@@ -85,6 +99,7 @@ where
             self.log_update_rx.clone().unwrap(),
             self.pool_loaders.clone(),
             self.tasks_tx.clone().unwrap(),
+            self.market.clone().unwrap(),
         ));
         Ok(vec![task])
     }
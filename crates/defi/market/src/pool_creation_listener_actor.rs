@@ -0,0 +1,227 @@
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::time::Duration;
+
+use alloy_network::Network;
+use alloy_primitives::{Address, B256};
+use alloy_provider::Provider;
+use alloy_rpc_types::{Filter, Log};
+use alloy_sol_types::{sol, SolEvent};
+use eyre::eyre;
+use tokio_stream::StreamExt;
+use tracing::{debug, error, info, warn};
+
+use loom_core_actors::{Accessor, Actor, ActorResult, Broadcaster, Producer, SharedState, WorkerResult};
+use loom_core_actors_macros::{Accessor, Producer};
+use loom_core_blockchain::Blockchain;
+use loom_types_entities::pool_config::PoolsLoadingConfig;
+use loom_types_entities::{Market, PoolId, PoolLoaders};
+use loom_types_events::LoomTask;
+
+const RESUBSCRIBE_DELAY: Duration = Duration::from_secs(1);
+
+sol! {
+    interface IUniswapV2Factory {
+        event PairCreated(address indexed token0, address indexed token1, address pair, uint256 index);
+    }
+
+    interface IUniswapV3Factory {
+        event PoolCreated(address indexed token0, address indexed token1, uint24 indexed fee, int24 tickSpacing, address pool);
+    }
+
+    interface ISolidlyFactory {
+        event PairCreated(address indexed token0, address indexed token1, bool stable, address pair, uint256 index);
+    }
+
+    interface IMaverickFactory {
+        event PoolCreated(
+            address poolAddress,
+            uint256 fee,
+            uint256 tickSpacing,
+            int32 activeTick,
+            int256 lookback,
+            uint64 protocolFeeRatio,
+            address tokenA,
+            address tokenB
+        );
+    }
+}
+
+/// Pool creation events of the supported factories
+fn pool_created_signatures() -> Vec<B256> {
+    vec![
+        IUniswapV2Factory::PairCreated::SIGNATURE_HASH,
+        IUniswapV3Factory::PoolCreated::SIGNATURE_HASH,
+        ISolidlyFactory::PairCreated::SIGNATURE_HASH,
+        IMaverickFactory::PoolCreated::SIGNATURE_HASH,
+    ]
+}
+
+/// Address of the pool created by the factory log
+fn created_pool_address(log: &Log) -> Option<Address> {
+    let topic0 = *log.topics().first()?;
+    if topic0 == IUniswapV2Factory::PairCreated::SIGNATURE_HASH {
+        IUniswapV2Factory::PairCreated::decode_log(&log.inner, true).ok().map(|event| event.pair)
+    } else if topic0 == IUniswapV3Factory::PoolCreated::SIGNATURE_HASH {
+        IUniswapV3Factory::PoolCreated::decode_log(&log.inner, true).ok().map(|event| event.pool)
+    } else if topic0 == ISolidlyFactory::PairCreated::SIGNATURE_HASH {
+        ISolidlyFactory::PairCreated::decode_log(&log.inner, true).ok().map(|event| event.pair)
+    } else if topic0 == IMaverickFactory::PoolCreated::SIGNATURE_HASH {
+        IMaverickFactory::PoolCreated::decode_log(&log.inner, true).ok().map(|event| event.poolAddress)
+    } else {
+        None
+    }
+}
+
+async fn pool_creation_listener_worker<P, PL, N>(
+    client: P,
+    pool_loaders: Arc<PoolLoaders<PL, N>>,
+    pools_config: PoolsLoadingConfig,
+    market: SharedState<Market>,
+    tasks_tx: Broadcaster<LoomTask>,
+) -> WorkerResult
+where
+    N: Network,
+    P: Provider<N> + Send + Sync + Clone + 'static,
+    PL: Provider<N> + Send + Sync + Clone + 'static,
+{
+    let factories = pool_loaders.factory_addresses();
+    if factories.is_empty() {
+        return Err(eyre!("NO_FACTORIES_REGISTERED"));
+    }
+    let filter = Filter::new().address(factories.clone()).event_signature(pool_created_signatures());
+
+    loop {
+        let subscription = match client.subscribe_logs(&filter).await {
+            Ok(subscription) => subscription,
+            Err(error) => {
+                error!(%error, "Failed to subscribe to pool creation logs");
+                tokio::time::sleep(RESUBSCRIBE_DELAY).await;
+                continue;
+            }
+        };
+        info!(factories = factories.len(), "Subscribed to pool creation logs");
+
+        let mut stream = subscription.into_stream();
+        while let Some(log) = stream.next().await {
+            if log.removed {
+                continue;
+            }
+            let Some(pool_class) = pool_loaders.pool_class_by_factory(&log.address()) else {
+                continue;
+            };
+            if !pools_config.is_enabled(pool_class) {
+                continue;
+            }
+            let Some(pool_address) = created_pool_address(&log) else {
+                debug!(factory = %log.address(), "Unknown pool creation log");
+                continue;
+            };
+
+            let pool_id = PoolId::Address(pool_address);
+            {
+                let mut market_guard = market.write().await;
+                if market_guard.is_pool(&pool_id) {
+                    continue;
+                }
+                if let Some(block_number) = log.block_number {
+                    market_guard.set_pool_first_seen(pool_id, block_number);
+                }
+            }
+
+            info!(%pool_id, %pool_class, factory = %log.address(), "New pool created");
+            if let Err(error) = tasks_tx.send(LoomTask::FetchAndAddPools(vec![(pool_id, pool_class)])) {
+                error!(%error, "tasks_tx.send");
+            }
+        }
+
+        warn!("Pool creation logs subscription ended, resubscribing");
+        tokio::time::sleep(RESUBSCRIBE_DELAY).await;
+    }
+}
+
+/// Subscribes to the pool creation events of all registered factories and requests loading of new pools
+/// as soon as they are created, without waiting for the block logs scan
+#[derive(Accessor, Producer)]
+pub struct PoolCreationListenerActor<P, PL, N>
+where
+    N: Network,
+    P: Provider<N> + Send + Sync + Clone + 'static,
+    PL: Provider<N> + Send + Sync + Clone + 'static,
+{
+    client: P,
+    pool_loaders: Arc<PoolLoaders<PL, N>>,
+    pools_config: PoolsLoadingConfig,
+    #[accessor]
+    market: Option<SharedState<Market>>,
+    #[producer]
+    tasks_tx: Option<Broadcaster<LoomTask>>,
+    _n: PhantomData<N>,
+}
+
+impl<P, PL, N> PoolCreationListenerActor<P, PL, N>
+where
+    N: Network,
+    P: Provider<N> + Send + Sync + Clone + 'static,
+    PL: Provider<N> + Send + Sync + Clone + 'static,
+{
+    pub fn new(client: P, pool_loaders: Arc<PoolLoaders<PL, N>>, pools_config: PoolsLoadingConfig) -> Self {
+        Self { client, pool_loaders, pools_config, market: None, tasks_tx: None, _n: PhantomData }
+    }
+
+    pub fn on_bc(self, bc: &Blockchain) -> Self {
+        Self { market: Some(bc.market()), tasks_tx: Some(bc.tasks_channel()), ..self }
+    }
+}
+
+impl<P, PL, N> Actor for PoolCreationListenerActor<P, PL, N>
+where
+    N: Network,
+    P: Provider<N> + Send + Sync + Clone + 'static,
+    PL: Provider<N> + Send + Sync + Clone + 'static,
+{
+    fn start(&self) -> ActorResult {
+        let task = tokio::task::spawn(pool_creation_listener_worker(
+            self.client.clone(),
+            self.pool_loaders.clone(),
+            self.pools_config.clone(),
+            self.market.clone().unwrap(),
+            self.tasks_tx.clone().unwrap(),
+        ));
+        Ok(vec![task])
+    }
+
+    fn name(&self) -> &'static str {
+        "PoolCreationListenerActor"
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloy_primitives::aliases::{I24, U24};
+    use alloy_primitives::{Bytes, LogData, U256};
+
+    #[test]
+    fn test_created_pool_address() {
+        let token0 = Address::repeat_byte(1);
+        let token1 = Address::repeat_byte(2);
+        let pool = Address::repeat_byte(3);
+
+        let event = IUniswapV3Factory::PoolCreated { token0, token1, fee: U24::from(3000), tickSpacing: I24::try_from(60).unwrap(), pool };
+        let log =
+            Log { inner: alloy_primitives::Log { address: Address::repeat_byte(4), data: event.encode_log_data() }, ..Log::default() };
+        assert_eq!(created_pool_address(&log), Some(pool));
+
+        let event = ISolidlyFactory::PairCreated { token0, token1, stable: true, pair: pool, index: U256::from(1) };
+        let log =
+            Log { inner: alloy_primitives::Log { address: Address::repeat_byte(4), data: event.encode_log_data() }, ..Log::default() };
+        assert_eq!(created_pool_address(&log), Some(pool));
+
+        let log = Log {
+            inner: alloy_primitives::Log { address: Address::repeat_byte(4), data: LogData::new_unchecked(vec![B256::ZERO], Bytes::new()) },
+            ..Log::default()
+        };
+        assert_eq!(created_pool_address(&log), None);
+    }
+}
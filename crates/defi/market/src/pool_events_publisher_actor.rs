@@ -0,0 +1,93 @@
+use std::time::Duration;
+
+use tokio::time::MissedTickBehavior;
+use tracing::{debug, error, warn};
+
+use loom_core_actors::{Accessor, Actor, ActorResult, Broadcaster, Producer, SharedState, WorkerResult};
+use loom_core_actors_macros::{Accessor, Producer};
+use loom_core_blockchain::Blockchain;
+use loom_types_entities::{Market, PoolEventRecord};
+
+const POOL_EVENTS_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+pub async fn pool_events_publisher_worker(
+    market: SharedState<Market>,
+    interval: Duration,
+    pool_events_tx: Broadcaster<PoolEventRecord>,
+) -> WorkerResult {
+    let mut ticker = tokio::time::interval(interval);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+    // events recorded before the start are part of the market the consumers start from
+    let mut last_seq = market.read().await.pool_events().last_seq();
+
+    loop {
+        ticker.tick().await;
+
+        let market_guard = market.read().await;
+        let pool_events = market_guard.pool_events();
+        if pool_events.last_seq() == last_seq {
+            continue;
+        }
+        if let Some(first_seq) = pool_events.first_seq().filter(|first_seq| *first_seq > last_seq + 1) {
+            warn!(last_seq, first_seq, "Pool events dropped from the market log before they were published");
+        }
+        let records: Vec<PoolEventRecord> = pool_events.since(last_seq).cloned().collect();
+        drop(market_guard);
+
+        let count = records.len();
+        for record in records {
+            last_seq = record.seq;
+            if let Err(e) = pool_events_tx.send(record) {
+                error!("pool_events_tx.send error : {}", e);
+            }
+        }
+        debug!(last_seq, count, "Pool events published");
+    }
+}
+
+/// Publishes the pool lifecycle events recorded by the market in the order of their sequence numbers
+#[derive(Accessor, Producer)]
+pub struct PoolEventsPublisherActor {
+    interval: Duration,
+    #[accessor]
+    market: Option<SharedState<Market>>,
+    #[producer]
+    pool_events_tx: Option<Broadcaster<PoolEventRecord>>,
+}
+
+impl PoolEventsPublisherActor {
+    pub fn new() -> Self {
+        Self { interval: POOL_EVENTS_POLL_INTERVAL, market: None, pool_events_tx: None }
+    }
+
+    /// Polling interval of the event log of the market
+    pub fn with_interval(self, interval: Duration) -> Self {
+        Self { interval, ..self }
+    }
+
+    pub fn on_bc(self, bc: &Blockchain) -> Self {
+        Self { market: Some(bc.market()), pool_events_tx: Some(bc.pool_events_channel()), ..self }
+    }
+}
+
+impl Default for PoolEventsPublisherActor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Actor for PoolEventsPublisherActor {
+    fn start(&self) -> ActorResult {
+        let task = tokio::task::spawn(pool_events_publisher_worker(
+            self.market.clone().unwrap(),
+            self.interval,
+            self.pool_events_tx.clone().unwrap(),
+        ));
+        Ok(vec![task])
+    }
+
+    fn name(&self) -> &'static str {
+        "PoolEventsPublisherActor"
+    }
+}
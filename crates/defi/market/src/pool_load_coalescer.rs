@@ -0,0 +1,113 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+use loom_types_entities::PoolId;
+use tokio::sync::OnceCell;
+
+/// Outcome of a pool load, shared by all requesters of the pool
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PoolLoadOutcome {
+    /// Pool added to the market with the indices of its new swap paths
+    Loaded(Vec<usize>),
+    /// Pool factory is not allowed by the config
+    NotAllowed,
+    Failed(String),
+}
+
+/// In-flight pool loads by pool id. Requesters of a pool with a load in flight await that load instead of starting
+/// another one, e.g. when several tasks reference a popular new pool within a few blocks or a persisted pool is
+/// discovered from logs while the db loader hydrates the market
+#[derive(Clone, Default)]
+pub struct PoolLoadCoalescer {
+    in_flight: Arc<Mutex<HashMap<PoolId, Arc<OnceCell<PoolLoadOutcome>>>>>,
+}
+
+impl PoolLoadCoalescer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run `load` unless a load of the pool is in flight, otherwise await its outcome. Returns the outcome and true for the
+    /// requester that ran the load. A load cancelled before completion is taken over by one of the waiting requesters
+    pub async fn load<F, Fut>(&self, pool_id: PoolId, load: F) -> (PoolLoadOutcome, bool)
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = PoolLoadOutcome>,
+    {
+        let cell = self.in_flight.lock().unwrap().entry(pool_id).or_default().clone();
+
+        let mut leader = false;
+        let outcome = cell
+            .get_or_init(|| {
+                leader = true;
+                load()
+            })
+            .await
+            .clone();
+
+        if leader {
+            // later requesters start a new load, e.g. retries of failed pools
+            let mut in_flight = self.in_flight.lock().unwrap();
+            if in_flight.get(&pool_id).is_some_and(|in_flight_cell| Arc::ptr_eq(in_flight_cell, &cell)) {
+                in_flight.remove(&pool_id);
+            }
+        }
+
+        (outcome, leader)
+    }
+
+    pub fn is_in_flight(&self, pool_id: &PoolId) -> bool {
+        self.in_flight.lock().unwrap().contains_key(pool_id)
+    }
+
+    /// Number of pools with a load in flight
+    pub fn len(&self) -> usize {
+        self.in_flight.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloy_primitives::Address;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_concurrent_loads_coalesced() {
+        let coalescer = PoolLoadCoalescer::new();
+        let pool_id = PoolId::Address(Address::repeat_byte(1));
+        let loads = Arc::new(AtomicUsize::new(0));
+
+        let mut requesters = tokio::task::JoinSet::new();
+        for _ in 0..4 {
+            let coalescer = coalescer.clone();
+            let loads = loads.clone();
+            requesters.spawn(async move {
+                coalescer
+                    .load(pool_id, || async move {
+                        loads.fetch_add(1, Ordering::Relaxed);
+                        tokio::time::sleep(Duration::from_millis(50)).await;
+                        PoolLoadOutcome::Loaded(vec![1, 2])
+                    })
+                    .await
+            });
+        }
+        let results: Vec<(PoolLoadOutcome, bool)> = requesters.join_all().await;
+
+        assert_eq!(loads.load(Ordering::Relaxed), 1);
+        assert_eq!(results.iter().filter(|(_, leader)| *leader).count(), 1);
+        assert!(results.iter().all(|(outcome, _)| *outcome == PoolLoadOutcome::Loaded(vec![1, 2])));
+        assert!(coalescer.is_empty());
+
+        // completed loads are not cached
+        let (outcome, leader) = coalescer.load(pool_id, || async { PoolLoadOutcome::NotAllowed }).await;
+        assert!(leader);
+        assert_eq!(outcome, PoolLoadOutcome::NotAllowed);
+    }
+}
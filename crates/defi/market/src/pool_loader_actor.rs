@@ -1,11 +1,13 @@
-use std::collections::{BTreeMap, HashMap};
 use std::marker::PhantomData;
+use std::num::NonZeroUsize;
 use std::sync::Arc;
+use std::time::Duration;
 
 use alloy_network::Network;
+use alloy_primitives::Address;
 use alloy_provider::Provider;
 use eyre::Result;
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 
 use loom_core_actors::{run_sync, subscribe, Actor, ActorResult, Broadcaster, Producer, SharedState, WorkerResult};
 use loom_core_actors::{Accessor, Consumer};
@@ -13,20 +15,60 @@ use loom_core_actors_macros::{Accessor, Consumer, Producer};
 use loom_core_blockchain::{Blockchain, BlockchainState};
 use loom_node_debug_provider::DebugProviderExt;
 use loom_types_entities::required_state::RequiredStateReader;
-use loom_types_entities::{Market, MarketState, PoolClass, PoolId, PoolLoaders, PoolWrapper, SwapDirection};
+use loom_types_entities::{Market, MarketState, PoolClass, PoolId, PoolLoaders, PoolWrapper, SwapPath};
 use loom_types_events::{LoomTask, MarketEvents};
 
+use loom_defi_pools::db_reader::{observations_required_state, UniswapV3DBReader};
 use loom_types_blockchain::get_touched_addresses;
 use loom_types_entities::pool_config::PoolsLoadingConfig;
+use loom_types_entities::state_proof::StateLoadingMode;
 use revm::{Database, DatabaseCommit, DatabaseRef};
 use tokio::sync::Semaphore;
 
+use crate::pool_load_coalescer::{PoolLoadCoalescer, PoolLoadOutcome};
+use crate::pool_state_refresher_actor::refresh_pool_state;
+use crate::processed_pools::ProcessedPools;
+use crate::proxy_monitor_actor::fetch_proxy_implementation;
+use crate::token_hooks::fetch_transfer_hook;
+
 const MAX_CONCURRENT_TASKS: usize = 20;
+const PROCESSED_POOLS_CAPACITY: usize = 100_000;
+const FAILED_POOL_RETRY_AFTER_SECS: u64 = 600;
+
+/// Reads the pools loading config from its source again on [`LoomTask::ReloadConfig`]
+pub type PoolsConfigReader = Arc<dyn Fn() -> Result<PoolsLoadingConfig> + Send + Sync>;
+
+/// Keep pools of disabled classes and factories and denied pairs and pools out of path building. Pairs and pools no
+/// longer denied by the config are enabled again.
+fn apply_pools_config(market: &mut Market, pools_config: &PoolsLoadingConfig) {
+    market.set_pools_config(pools_config.clone());
+    market.set_denied(
+        pools_config.denied_pairs().iter().copied(),
+        pools_config.denied_pools().iter().map(|pool_address| PoolId::Address(*pool_address)),
+    );
+}
+
+/// Build the swap paths of the enabled pools of the token, paths already in the market are skipped when added
+fn build_token_paths(market: &Market, token: Address) -> Result<Vec<SwapPath>> {
+    let mut swap_paths = Vec::new();
+    for pool_id in market.get_token_pools(&token).into_iter().flatten() {
+        if market.is_pool_disabled(pool_id) {
+            continue;
+        }
+        if let Some(pool) = market.get_pool(pool_id) {
+            swap_paths.extend(market.build_pool_swap_path_vec(pool)?);
+        }
+    }
+    Ok(swap_paths)
+}
 
+#[allow(clippy::too_many_arguments)]
 pub async fn pool_loader_worker<P, PL, N, DB>(
     client: P,
     pool_loaders: Arc<PoolLoaders<PL, N>>,
-    pools_config: PoolsLoadingConfig,
+    mut pools_config: PoolsLoadingConfig,
+    pools_config_reader: Option<PoolsConfigReader>,
+    load_coalescer: PoolLoadCoalescer,
     market: SharedState<Market>,
     market_state: SharedState<MarketState<DB>>,
     tasks_rx: Broadcaster<LoomTask>,
@@ -38,58 +80,152 @@ where
     PL: Provider<N> + Send + Sync + Clone + 'static,
     DB: Database + DatabaseRef + DatabaseCommit + Send + Sync + Clone + 'static,
 {
-    let mut processed_pools = HashMap::new();
+    let mut processed_pools =
+        ProcessedPools::new(NonZeroUsize::new(PROCESSED_POOLS_CAPACITY).unwrap(), Duration::from_secs(FAILED_POOL_RETRY_AFTER_SECS));
+    let (load_result_tx, mut load_result_rx) = tokio::sync::mpsc::unbounded_channel::<(PoolId, bool)>();
     let semaphore = std::sync::Arc::new(Semaphore::new(pools_config.threads().unwrap_or(MAX_CONCURRENT_TASKS)));
 
+    apply_pools_config(&mut market.write().await, &pools_config);
+
     subscribe!(tasks_rx);
     loop {
-        if let Ok(task) = tasks_rx.recv().await {
-            let pools = match task {
-                LoomTask::FetchAndAddPools(pools) => pools,
-            };
-
-            for (pool_id, pool_class) in pools {
-                // Check if pool already exists
-                if processed_pools.insert(pool_id, true).is_some() {
-                    continue;
+        tokio::select! {
+            Some((pool_id, loaded)) = load_result_rx.recv() => {
+                if loaded {
+                    processed_pools.set_loaded(pool_id);
+                } else {
+                    processed_pools.set_failed(pool_id);
                 }
-
-                let sema_clone = semaphore.clone();
-                let client_clone = client.clone();
-                let market_clone = market.clone();
-                let market_state = market_state.clone();
-                let pool_loaders_clone = pool_loaders.clone();
-                let market_events_tx_clone = market_events_tx.clone();
-
-                tokio::task::spawn(async move {
-                    match sema_clone.acquire().await {
-                        Ok(permit) => {
-                            match fetch_and_add_pool_by_pool_id(
-                                client_clone,
-                                market_clone,
-                                market_state,
-                                pool_loaders_clone,
-                                pool_id,
-                                pool_class,
-                            )
-                            .await
-                            {
-                                Ok((pool_id, swap_path_idx_vec)) => {
-                                    info!(%pool_id, %pool_class, "Pool loaded successfully");
-                                    run_sync!(market_events_tx_clone.send(MarketEvents::NewPoolLoaded { pool_id, swap_path_idx_vec }))
-                                }
-                                Err(error) => {
-                                    error!(%error, %pool_id, %pool_class, "failed fetch_and_add_pool_by_address");
-                                }
+            }
+            task = tasks_rx.recv() => {
+                let Ok(task) = task else {
+                    continue;
+                };
+                let pools = match task {
+                    LoomTask::FetchAndAddPools(pools) => pools,
+                    LoomTask::RefreshPoolState(pool_id) => {
+                        let (pool, state_loading) = {
+                            let market_guard = market.read().await;
+                            (market_guard.get_pool(&pool_id).cloned(), market_guard.pools_config().state_loading())
+                        };
+                        let Some(pool) = pool else {
+                            warn!(%pool_id, "Pool to refresh is not loaded");
+                            continue;
+                        };
+                        let client_clone = client.clone();
+                        let market_state = market_state.clone();
+                        tokio::task::spawn(async move {
+                            match refresh_pool_state(client_clone, market_state, pool, state_loading).await {
+                                Ok(_) => info!(%pool_id, "Pool state refreshed"),
+                                Err(error) => error!(%error, %pool_id, "refresh_pool_state"),
+                            }
+                        });
+                        continue;
+                    }
+                    LoomTask::RebuildPathsForToken(token) => {
+                        // paths are built under the read lock, the write lock is held only to add them
+                        let swap_paths = match build_token_paths(&market.read().await, token) {
+                            Ok(swap_paths) => swap_paths,
+                            Err(error) => {
+                                error!(%error, %token, "build_token_paths");
+                                continue;
                             }
+                        };
+                        let paths_added = market.write().await.add_paths(swap_paths).len();
+                        info!(%token, paths_added, "Token swap paths rebuilt");
+                        continue;
+                    }
+                    LoomTask::ReloadConfig => {
+                        let Some(pools_config_reader) = pools_config_reader.as_ref() else {
+                            warn!("Pools loading config has no source to reload from");
+                            continue;
+                        };
+                        match pools_config_reader() {
+                            Ok(reloaded) => pools_config = reloaded,
+                            Err(error) => {
+                                error!(%error, "failed to reload pools loading config");
+                                continue;
+                            }
+                        }
+                        apply_pools_config(&mut market.write().await, &pools_config);
+                        // pools still in the market are marked loaded again on the next request
+                        let retried = processed_pools.clear();
+                        info!(retried, "Pools loading config reloaded");
+                        continue;
+                    }
+                };
+
+                for (pool_id, pool_class) in pools {
+                    if !pools_config.is_enabled(pool_class) {
+                        continue;
+                    }
+                    // Check if pool is already loaded or failed recently
+                    if processed_pools.is_settled(&pool_id) {
+                        continue;
+                    }
+                    // Pool could be evicted from the cache while still present in the market
+                    if market.read().await.is_pool(&pool_id) {
+                        processed_pools.set_loaded(pool_id);
+                        continue;
+                    }
+
+                    let sema_clone = semaphore.clone();
+                    let client_clone = client.clone();
+                    let market_clone = market.clone();
+                    let market_state = market_state.clone();
+                    let pool_loaders_clone = pool_loaders.clone();
+                    let market_events_tx_clone = market_events_tx.clone();
+                    let load_result_tx_clone = load_result_tx.clone();
+                    let pools_config_clone = pools_config.clone();
+                    let load_coalescer_clone = load_coalescer.clone();
+
+                    tokio::task::spawn(async move {
+                        // requesters of a pool with a load in flight await it without a permit
+                        let (outcome, leader) = load_coalescer_clone
+                            .load(pool_id, || async move {
+                                let Ok(_permit) = sema_clone.acquire().await else {
+                                    return PoolLoadOutcome::Failed("failed acquire semaphore".to_string());
+                                };
+                                fetch_and_add_allowed_pool_outcome(
+                                    client_clone,
+                                    market_clone,
+                                    market_state,
+                                    pool_loaders_clone,
+                                    &pools_config_clone,
+                                    pool_id,
+                                    pool_class,
+                                )
+                                .await
+                            })
+                            .await;
 
-                            drop(permit);
+                        if !leader {
+                            debug!(%pool_id, %pool_class, ?outcome, "Joined in-flight pool load");
+                            return;
                         }
-                        Err(error) => {
-                            error!(%error, "failed acquire semaphore");
+
+                        let loaded = match outcome {
+                            PoolLoadOutcome::Loaded(swap_path_idx_vec) => {
+                                info!(%pool_id, %pool_class, "Pool loaded successfully");
+                                run_sync!(market_events_tx_clone.send(MarketEvents::NewPoolLoaded { pool_id, swap_path_idx_vec }));
+                                true
+                            }
+                            PoolLoadOutcome::NotAllowed => {
+                                // Not retried until the config is reloaded
+                                debug!(%pool_id, %pool_class, "Pool factory is not allowed");
+                                true
+                            }
+                            PoolLoadOutcome::Failed(error) => {
+                                error!(%error, %pool_id, %pool_class, "failed fetch_and_add_pool_by_address");
+                                false
+                            }
+                        };
+
+                        if let Err(error) = load_result_tx_clone.send((pool_id, loaded)) {
+                            error!(%error, "failed to send pool load result");
                         }
-                    }
-                });
+                    });
+                }
             }
         }
     }
@@ -116,6 +252,77 @@ where
     fetch_state_and_add_pool(client, market.clone(), market_state.clone(), pool).await
 }
 
+/// [`fetch_and_add_allowed_pool`] with an outcome shareable by the requesters of a coalesced load
+pub(crate) async fn fetch_and_add_allowed_pool_outcome<P, PL, N, DB>(
+    client: P,
+    market: SharedState<Market>,
+    market_state: SharedState<MarketState<DB>>,
+    pool_loaders: Arc<PoolLoaders<PL, N>>,
+    pools_config: &PoolsLoadingConfig,
+    pool_id: PoolId,
+    pool_class: PoolClass,
+) -> PoolLoadOutcome
+where
+    N: Network,
+    P: Provider<N> + DebugProviderExt<N> + Send + Sync + Clone + 'static,
+    PL: Provider<N> + Send + Sync + Clone + 'static,
+    DB: DatabaseRef + Database + DatabaseCommit + Send + Sync + Clone + 'static,
+{
+    match fetch_and_add_allowed_pool(client, market, market_state, pool_loaders, pools_config, pool_id, pool_class).await {
+        Ok(Some((_, swap_path_idx_vec))) => PoolLoadOutcome::Loaded(swap_path_idx_vec),
+        Ok(None) => PoolLoadOutcome::NotAllowed,
+        Err(error) => PoolLoadOutcome::Failed(error.to_string()),
+    }
+}
+
+/// Fetch pool data and add it to the market if the pool factory is allowed by the config.
+/// Returns `None` for filtered out pools.
+pub(crate) async fn fetch_and_add_allowed_pool<P, PL, N, DB>(
+    client: P,
+    market: SharedState<Market>,
+    market_state: SharedState<MarketState<DB>>,
+    pool_loaders: Arc<PoolLoaders<PL, N>>,
+    pools_config: &PoolsLoadingConfig,
+    pool_id: PoolId,
+    pool_class: PoolClass,
+) -> Result<Option<(PoolId, Vec<usize>)>>
+where
+    N: Network,
+    P: Provider<N> + DebugProviderExt<N> + Send + Sync + Clone + 'static,
+    PL: Provider<N> + Send + Sync + Clone + 'static,
+    DB: DatabaseRef + Database + DatabaseCommit + Send + Sync + Clone + 'static,
+{
+    debug!(%pool_id, %pool_class, "Fetching pool");
+
+    let pool = pool_loaders.load_pool_without_provider(pool_id, &pool_class).await?;
+    if !pools_config.is_pool_allowed(pool.as_ref()) {
+        return Ok(None);
+    }
+    fetch_state_and_add_pool(client, market, market_state, pool).await.map(Some)
+}
+
+/// Load the oracle observations of a V3 pool for TWAP checks, block state updates keep them updated afterwards
+async fn fetch_observations<P, N, DB>(
+    client: P,
+    market_state: SharedState<MarketState<DB>>,
+    pool_address: Address,
+    state_loading: StateLoadingMode,
+) -> Result<u16>
+where
+    N: Network,
+    P: Provider<N> + DebugProviderExt<N> + Send + Sync + Clone + 'static,
+    DB: Database + DatabaseRef + DatabaseCommit + Send + Sync + Clone + 'static,
+{
+    let (cardinality, proven_block) = {
+        let market_state_guard = market_state.read().await;
+        (UniswapV3DBReader::slot0(&market_state_guard.state_db, pool_address)?.observationCardinality, market_state_guard.proven_block)
+    };
+    let required_state = observations_required_state(pool_address, cardinality);
+    let state = RequiredStateReader::fetch_with_mode(client, required_state, None, state_loading, proven_block).await?;
+    market_state.write().await.apply_geth_update(state);
+    Ok(cardinality)
+}
+
 pub async fn fetch_state_and_add_pool<P, N, DB>(
     client: P,
     market: SharedState<Market>,
@@ -127,16 +334,46 @@ where
     P: Provider<N> + DebugProviderExt<N> + Send + Sync + Clone + 'static,
     DB: Database + DatabaseRef + DatabaseCommit + Send + Sync + Clone + 'static,
 {
+    let pool_address = pool_wrapped.get_address();
+    // pools behind upgradable proxies are disabled by the proxy monitor when the implementation changes
+    let proxy_implementation = fetch_proxy_implementation(client.clone(), pool_address).await.unwrap_or_else(|error| {
+        debug!(%error, %pool_address, "failed to fetch proxy implementation");
+        None
+    });
+
+    let (state_loading, unchecked_tokens, twap_pool) = {
+        let market_guard = market.read().await;
+        let unchecked_tokens: Vec<Address> =
+            pool_wrapped.get_tokens().into_iter().filter(|token| market_guard.token_transfer_hook(token).is_none()).collect();
+        let twap_pool = pool_wrapped.get_class() == PoolClass::UniswapV3 && market_guard.pools_config().is_twap_pool(&pool_address);
+        (market_guard.pools_config().state_loading(), unchecked_tokens, twap_pool)
+    };
+    // tokens calling transfer hooks are never flash swapped and their received amounts are read from the balance, the
+    // pool fails to load if a token can't be checked
+    let mut transfer_hooks: Vec<(Address, bool)> = Vec::with_capacity(unchecked_tokens.len());
+    for token in unchecked_tokens {
+        let transfer_hook = fetch_transfer_hook(client.clone(), token).await?;
+        if transfer_hook {
+            info!(%token, %pool_address, "Token calls transfer hooks");
+        }
+        transfer_hooks.push((token, transfer_hook));
+    }
+
+    let proven_block = market_state.read().await.proven_block;
     match pool_wrapped.get_state_required() {
-        Ok(required_state) => match RequiredStateReader::fetch_calls_and_slots(client, required_state, None).await {
+        Ok(required) => match RequiredStateReader::fetch_with_mode(client.clone(), required, None, state_loading, proven_block).await {
             Ok(state) => {
-                let pool_address = pool_wrapped.get_address();
+                let pool_id = pool_wrapped.get_pool_id();
+                // first phase, the pool state is applied while the pool is staged and not visible in market views
+                market.write().await.stage_pool(pool_id);
                 {
                     let updated_addresses = get_touched_addresses(&state);
 
                     let mut market_state_write_guard = market_state.write().await;
                     market_state_write_guard.apply_geth_update(state);
                     market_state_write_guard.config.disable_cell_vec(pool_address, pool_wrapped.get_read_only_cell_vec());
+                    let block_number = market_state_write_guard.block_number;
+                    market_state_write_guard.track_updates(pool_address, block_number);
 
                     let pool_tokens = pool_wrapped.get_tokens();
 
@@ -148,21 +385,36 @@ where
 
                     drop(market_state_write_guard);
                 }
+                if twap_pool {
+                    match fetch_observations(client, market_state.clone(), pool_address, state_loading).await {
+                        Ok(cardinality) => info!(%pool_address, cardinality, "Pool oracle observations loaded"),
+                        Err(error) => warn!(%error, %pool_address, "failed to load pool oracle observations"),
+                    }
+                }
 
-                let directions_vec = pool_wrapped.get_swap_directions();
                 let pool_manager_cells = pool_wrapped.get_pool_manager_cells();
-                let pool_id = pool_wrapped.get_pool_id();
-
-                let mut directions_tree: BTreeMap<PoolWrapper, Vec<SwapDirection>> = BTreeMap::new();
-                directions_tree.insert(pool_wrapped.clone(), directions_vec);
 
                 let start_time = std::time::Instant::now();
                 let mut market_write_guard = market.write().await;
                 debug!(elapsed = start_time.elapsed().as_micros(), "market_guard market.write acquired");
                 // Ignore error if pool already exists because it was maybe already added by e.g. db pool loader
-                let _ = market_write_guard.add_pool(pool_wrapped);
+                let _ = market_write_guard.add_pool(pool_wrapped.clone());
+                for (token, transfer_hook) in transfer_hooks {
+                    market_write_guard.set_token_transfer_hook(token, transfer_hook);
+                }
+                if let Some(implementation) = proxy_implementation {
+                    info!(%pool_address, %implementation, "Pool is an upgradable proxy");
+                    market_write_guard.set_proxy_implementation(pool_address, implementation);
+                }
 
-                let swap_paths = market_write_guard.build_swap_path_vec(&directions_tree)?;
+                // only cycles through the new pool are explored, existing paths of its tokens are kept
+                let swap_paths = match market_write_guard.build_pool_swap_path_vec(&pool_wrapped) {
+                    Ok(swap_paths) => swap_paths,
+                    Err(e) => {
+                        market_write_guard.commit_pool(&pool_id);
+                        return Err(e);
+                    }
+                };
                 let swap_paths_added = market_write_guard.add_paths(swap_paths);
 
                 for (pool_manager_address, cells_vec) in pool_manager_cells {
@@ -171,6 +423,8 @@ where
                     }
                 }
 
+                // second phase, the pool and its paths are visible in the next market view
+                market_write_guard.commit_pool(&pool_id);
                 debug!(elapsed = start_time.elapsed().as_micros(),  market = %market_write_guard, "market_guard path added");
 
                 drop(market_write_guard);
@@ -201,6 +455,8 @@ where
     client: P,
     pool_loaders: Arc<PoolLoaders<PL, N>>,
     pools_config: PoolsLoadingConfig,
+    pools_config_reader: Option<PoolsConfigReader>,
+    load_coalescer: PoolLoadCoalescer,
     #[accessor]
     market: Option<SharedState<Market>>,
     #[accessor]
@@ -224,6 +480,8 @@ where
             client,
             pool_loaders,
             pools_config,
+            pools_config_reader: None,
+            load_coalescer: PoolLoadCoalescer::default(),
             market: None,
             market_state: None,
             tasks_rx: None,
@@ -232,6 +490,16 @@ where
         }
     }
 
+    /// Share in-flight pool loads with other pool loaders of the market
+    pub fn with_load_coalescer(self, load_coalescer: PoolLoadCoalescer) -> Self {
+        Self { load_coalescer, ..self }
+    }
+
+    /// Read the config again on [`LoomTask::ReloadConfig`], e.g. from the config file
+    pub fn with_pools_config_reader(self, pools_config_reader: PoolsConfigReader) -> Self {
+        Self { pools_config_reader: Some(pools_config_reader), ..self }
+    }
+
     pub fn on_bc(self, bc: &Blockchain, state: &BlockchainState<DB>) -> Self {
         Self {
             market: Some(bc.market()),
@@ -255,6 +523,8 @@ where
             self.client.clone(),
             self.pool_loaders.clone(),
             self.pools_config.clone(),
+            self.pools_config_reader.clone(),
+            self.load_coalescer.clone(),
             self.market.clone().unwrap(),
             self.market_state.clone().unwrap(),
             self.tasks_rx.clone().unwrap(),
@@ -267,3 +537,53 @@ where
         "PoolLoaderActor"
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use loom_types_blockchain::{LoomDataTypes, LoomDataTypesEthereum};
+    use loom_types_entities::{MockPool, Token};
+
+    fn market_with_pools(token: Address) -> (Market, Vec<PoolWrapper>) {
+        let weth = LoomDataTypesEthereum::WETH;
+        let mut market = Market::default();
+        market.add_token(Token::new_with_data(weth, Some("WETH".to_string()), None, Some(18), true, false));
+        let pools: Vec<PoolWrapper> =
+            (10..12u8).map(|byte| PoolWrapper::from(MockPool::new(weth, token, Address::repeat_byte(byte)))).collect();
+        for pool in pools.iter() {
+            market.add_pool(pool.clone()).unwrap();
+        }
+        (market, pools)
+    }
+
+    #[test]
+    fn test_reloaded_config_replaces_denials() {
+        let token = Address::repeat_byte(1);
+        let (mut market, pools) = market_with_pools(token);
+        let pool_id = pools[1].get_pool_id();
+
+        let denying_config =
+            PoolsLoadingConfig::new().deny_pools([pools[1].get_address()]).deny_pairs([(token, LoomDataTypesEthereum::WETH)]);
+        apply_pools_config(&mut market, &denying_config);
+        assert!(market.is_pool_disabled(&pool_id));
+        assert!(market.is_pair_denied(&LoomDataTypesEthereum::WETH, &token));
+
+        apply_pools_config(&mut market, &PoolsLoadingConfig::new());
+        assert!(!market.is_pool_disabled(&pool_id));
+        assert!(!market.is_pair_denied(&LoomDataTypesEthereum::WETH, &token));
+    }
+
+    #[test]
+    fn test_build_token_paths() -> Result<()> {
+        let token = Address::repeat_byte(1);
+        let (mut market, _) = market_with_pools(token);
+
+        let swap_paths = build_token_paths(&market, token)?;
+        assert!(!swap_paths.is_empty());
+        assert!(!market.add_paths(swap_paths).is_empty());
+
+        // paths already in the market are not added again
+        assert!(market.add_paths(build_token_paths(&market, token)?).is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,273 @@
+use std::collections::HashSet;
+use std::marker::PhantomData;
+
+use alloy_network::Network;
+use alloy_provider::Provider;
+use eyre::Result;
+use revm::{Database, DatabaseCommit, DatabaseRef};
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{debug, error, info};
+
+use loom_core_actors::{subscribe, Accessor, Actor, ActorResult, Broadcaster, Consumer, SharedState, WorkerResult};
+use loom_core_actors_macros::{Accessor, Consumer};
+use loom_core_blockchain::{Blockchain, BlockchainState};
+use loom_node_debug_provider::DebugProviderExt;
+use loom_types_entities::required_state::RequiredStateReader;
+use loom_types_entities::state_proof::StateLoadingMode;
+use loom_types_entities::{Market, MarketState, PoolWrapper};
+use loom_types_events::MarketEvents;
+
+const DEFAULT_STALE_BLOCKS: u64 = 50;
+const DEFAULT_MAX_POOLS_PER_BLOCK: usize = 10;
+
+/// Pools of enabled swap paths scored at least `min_path_score` that were not updated for `stale_blocks` blocks.
+/// Paths without a score are included. The oldest pools go first.
+fn stale_pools<DB>(market: &Market, market_state: &MarketState<DB>, stale_blocks: u64, min_path_score: f64) -> Vec<PoolWrapper> {
+    let mut seen = HashSet::new();
+    let mut pools: Vec<(u64, PoolWrapper)> = Vec::new();
+
+    for path in market.swap_paths().paths.iter().filter(|path| !path.disabled && path.score.unwrap_or(min_path_score) >= min_path_score) {
+        for pool in path.pools.iter() {
+            let pool_id = pool.get_pool_id();
+            if !seen.insert(pool_id) || market.is_pool_disabled(&pool_id) {
+                continue;
+            }
+            let pool_address = pool.get_address();
+            let last_updated = market_state.last_updated.get(&pool_address).copied().unwrap_or_default();
+            if market_state.block_number.saturating_sub(last_updated) >= stale_blocks {
+                pools.push((last_updated, pool.clone()));
+            }
+        }
+    }
+
+    pools.sort_by_key(|(last_updated, _)| *last_updated);
+    pools.into_iter().map(|(_, pool)| pool).collect()
+}
+
+pub(crate) async fn refresh_pool_state<P, N, DB>(
+    client: P,
+    market_state: SharedState<MarketState<DB>>,
+    pool: PoolWrapper,
+    state_loading: StateLoadingMode,
+) -> Result<()>
+where
+    N: Network,
+    P: Provider<N> + DebugProviderExt<N> + Send + Sync + Clone + 'static,
+    DB: Database + DatabaseRef + DatabaseCommit + Send + Sync + Clone + 'static,
+{
+    let required_state = pool.get_state_required()?;
+    let proven_block = market_state.read().await.proven_block;
+    let state = RequiredStateReader::fetch_with_mode(client, required_state, None, state_loading, proven_block).await?;
+
+    let mut market_state_guard = market_state.write().await;
+    market_state_guard.apply_geth_update(state);
+    let block_number = market_state_guard.block_number;
+    market_state_guard.track_updates(pool.get_address(), block_number);
+    Ok(())
+}
+
+async fn pool_state_refresher_worker<P, N, DB>(
+    client: P,
+    stale_blocks: u64,
+    max_pools_per_block: usize,
+    min_path_score: f64,
+    market: SharedState<Market>,
+    market_state: SharedState<MarketState<DB>>,
+    market_events_rx: Broadcaster<MarketEvents>,
+) -> WorkerResult
+where
+    N: Network,
+    P: Provider<N> + DebugProviderExt<N> + Send + Sync + Clone + 'static,
+    DB: Database + DatabaseRef + DatabaseCommit + Send + Sync + Clone + 'static,
+{
+    subscribe!(market_events_rx);
+
+    loop {
+        let event = match market_events_rx.recv().await {
+            Ok(event) => event,
+            Err(RecvError::Lagged(lag)) => {
+                debug!(lag, "Market events lagged");
+                continue;
+            }
+            Err(e) => {
+                error!("market_events_rx error : {}", e);
+                break;
+            }
+        };
+
+        let MarketEvents::BlockStateUpdate { .. } = event else {
+            continue;
+        };
+
+        let (block_number, pools, state_loading) = {
+            let market_guard = market.read().await;
+            let mut market_state_guard = market_state.write().await;
+            let pools: Vec<PoolWrapper> = stale_pools(&market_guard, &market_state_guard, stale_blocks, min_path_score)
+                .into_iter()
+                .take(max_pools_per_block)
+                .collect();
+            // Postpone the next refresh of the selected pools even if fetching fails
+            let block_number = market_state_guard.block_number;
+            for pool in pools.iter() {
+                market_state_guard.track_updates(pool.get_address(), block_number);
+            }
+            (block_number, pools, market_guard.pools_config().state_loading())
+        };
+
+        if pools.is_empty() {
+            continue;
+        }
+        info!(block_number, pools = pools.len(), "Refreshing stale pool states");
+
+        for pool in pools {
+            let client = client.clone();
+            let market_state = market_state.clone();
+            tokio::task::spawn(async move {
+                let pool_id = pool.get_pool_id();
+                match refresh_pool_state(client, market_state, pool, state_loading).await {
+                    Ok(_) => debug!(%pool_id, "Pool state refreshed"),
+                    Err(error) => error!(%error, %pool_id, "refresh_pool_state"),
+                }
+            });
+        }
+    }
+
+    Ok("PoolStateRefresherActor".to_string())
+}
+
+/// Re-fetches the required state of pools present in swap paths that were not touched by block state updates for
+/// `stale_blocks` blocks. Protects against updates missed because of filtered state diffs.
+#[derive(Accessor, Consumer)]
+pub struct PoolStateRefresherActor<P, N, DB>
+where
+    N: Network,
+    P: Provider<N> + Send + Sync + Clone + 'static,
+    DB: Database + DatabaseRef + DatabaseCommit + Send + Sync + Clone + 'static,
+{
+    client: P,
+    stale_blocks: u64,
+    max_pools_per_block: usize,
+    min_path_score: f64,
+    #[accessor]
+    market: Option<SharedState<Market>>,
+    #[accessor]
+    market_state: Option<SharedState<MarketState<DB>>>,
+    #[consumer]
+    market_events_rx: Option<Broadcaster<MarketEvents>>,
+    _n: PhantomData<N>,
+}
+
+impl<P, N, DB> PoolStateRefresherActor<P, N, DB>
+where
+    N: Network,
+    P: Provider<N> + Send + Sync + Clone + 'static,
+    DB: Database + DatabaseRef + DatabaseCommit + Send + Sync + Clone + 'static,
+{
+    pub fn new(client: P) -> Self {
+        Self {
+            client,
+            stale_blocks: DEFAULT_STALE_BLOCKS,
+            max_pools_per_block: DEFAULT_MAX_POOLS_PER_BLOCK,
+            min_path_score: f64::MIN,
+            market: None,
+            market_state: None,
+            market_events_rx: None,
+            _n: PhantomData,
+        }
+    }
+
+    pub fn with_stale_blocks(self, stale_blocks: u64) -> Self {
+        Self { stale_blocks, ..self }
+    }
+
+    pub fn with_max_pools_per_block(self, max_pools_per_block: usize) -> Self {
+        Self { max_pools_per_block, ..self }
+    }
+
+    pub fn with_min_path_score(self, min_path_score: f64) -> Self {
+        Self { min_path_score, ..self }
+    }
+
+    pub fn on_bc(self, bc: &Blockchain, state: &BlockchainState<DB>) -> Self {
+        Self {
+            market: Some(bc.market()),
+            market_state: Some(state.market_state_commit()),
+            market_events_rx: Some(bc.market_events_channel()),
+            ..self
+        }
+    }
+}
+
+impl<P, N, DB> Actor for PoolStateRefresherActor<P, N, DB>
+where
+    N: Network,
+    P: Provider<N> + DebugProviderExt<N> + Send + Sync + Clone + 'static,
+    DB: Database + DatabaseRef + DatabaseCommit + Send + Sync + Clone + 'static,
+{
+    fn start(&self) -> ActorResult {
+        let task = tokio::task::spawn(pool_state_refresher_worker(
+            self.client.clone(),
+            self.stale_blocks,
+            self.max_pools_per_block,
+            self.min_path_score,
+            self.market.clone().unwrap(),
+            self.market_state.clone().unwrap(),
+            self.market_events_rx.clone().unwrap(),
+        ));
+        Ok(vec![task])
+    }
+
+    fn name(&self) -> &'static str {
+        "PoolStateRefresherActor"
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloy_primitives::Address;
+    use loom_evm_db::LoomDBType;
+    use loom_types_blockchain::{LoomDataTypes, LoomDataTypesEthereum};
+    use loom_types_entities::{MockPool, SwapPath, Token};
+
+    #[test]
+    fn test_stale_pools() {
+        let (weth, token) = (LoomDataTypesEthereum::WETH, Address::repeat_byte(1));
+        let pools: Vec<PoolWrapper> =
+            (10..14u8).map(|byte| PoolWrapper::from(MockPool::new(weth, token, Address::repeat_byte(byte)))).collect();
+        let tokens = || vec![Token::new(weth), Token::new(token), Token::new(weth)];
+        let mut market = Market::default();
+        for pool in pools.iter() {
+            market.add_pool(pool.clone()).unwrap();
+        }
+        let path_idx = market.add_paths(vec![
+            SwapPath::new(tokens(), vec![pools[0].clone(), pools[1].clone()]),
+            SwapPath::new(tokens(), vec![pools[0].clone(), pools[2].clone()]),
+            SwapPath::new(tokens(), vec![pools[3].clone(), pools[1].clone()]),
+        ]);
+        market.swap_paths_mut().get_path_by_idx_mut(path_idx[1]).unwrap().score = Some(0.5);
+        market.swap_paths_mut().get_path_by_idx_mut(path_idx[2]).unwrap().score = Some(2.0);
+
+        let mut market_state = MarketState::new(LoomDBType::default());
+        market_state.block_number = 100;
+        market_state.track_updates(pools[0].get_address(), 40);
+        market_state.track_updates(pools[1].get_address(), 90);
+        market_state.track_updates(pools[2].get_address(), 10);
+        market_state.track_updates(pools[3].get_address(), 50);
+
+        // pools of low scored paths are skipped, the oldest pool goes first
+        let stale = stale_pools(&market, &market_state, 50, 1.0);
+        assert_eq!(stale.iter().map(|pool| pool.get_address()).collect::<Vec<_>>(), vec![pools[0].get_address(), pools[3].get_address()]);
+
+        // one block before the window is crossed
+        market_state.block_number = 99;
+        assert_eq!(stale_pools(&market, &market_state, 50, 1.0).len(), 1);
+
+        // refreshed or updated pools recover
+        market_state.block_number = 100;
+        market_state.track_updates(pools[3].get_address(), 100);
+        market_state.set_updated(vec![pools[0].get_address()], 100);
+        assert!(stale_pools(&market, &market_state, 50, 1.0).is_empty());
+        assert_eq!(stale_pools(&market, &market_state, 50, f64::MIN).len(), 1);
+    }
+}
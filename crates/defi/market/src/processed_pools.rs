@@ -0,0 +1,110 @@
+use std::num::NonZeroUsize;
+use std::time::{Duration, Instant};
+
+use loom_types_entities::PoolId;
+use lru::LruCache;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PoolLoadStatus {
+    Loading,
+    Loaded,
+    Failed(Instant),
+}
+
+/// Bounded cache of pools requested for loading. Failed pools are retried after `retry_after`.
+pub struct ProcessedPools {
+    cache: LruCache<PoolId, PoolLoadStatus>,
+    retry_after: Duration,
+}
+
+impl ProcessedPools {
+    pub fn new(capacity: NonZeroUsize, retry_after: Duration) -> Self {
+        Self { cache: LruCache::new(capacity), retry_after }
+    }
+
+    /// Returns true and marks the pool as loading if it was not processed or its last load failed long enough ago
+    pub fn start_loading(&mut self, pool_id: PoolId) -> bool {
+        match self.cache.get(&pool_id) {
+            Some(PoolLoadStatus::Loading) | Some(PoolLoadStatus::Loaded) => false,
+            Some(PoolLoadStatus::Failed(failed_at)) if failed_at.elapsed() < self.retry_after => false,
+            _ => {
+                self.cache.put(pool_id, PoolLoadStatus::Loading);
+                true
+            }
+        }
+    }
+
+    /// Returns true if the pool was loaded or its last load failed recently. Loads in flight are coalesced by
+    /// [`crate::PoolLoadCoalescer`]
+    pub fn is_settled(&mut self, pool_id: &PoolId) -> bool {
+        match self.cache.get(pool_id) {
+            Some(PoolLoadStatus::Loaded) => true,
+            Some(PoolLoadStatus::Failed(failed_at)) => failed_at.elapsed() < self.retry_after,
+            _ => false,
+        }
+    }
+
+    pub fn set_loaded(&mut self, pool_id: PoolId) {
+        self.cache.put(pool_id, PoolLoadStatus::Loaded);
+    }
+
+    pub fn set_failed(&mut self, pool_id: PoolId) {
+        self.cache.put(pool_id, PoolLoadStatus::Failed(Instant::now()));
+    }
+
+    /// Forget the settled pools so failed pools and pools filtered out by the config are requested again, returns the
+    /// number of pools forgotten
+    pub fn clear(&mut self) -> usize {
+        let len = self.cache.len();
+        self.cache.clear();
+        len
+    }
+
+    pub fn status(&self, pool_id: &PoolId) -> Option<PoolLoadStatus> {
+        self.cache.peek(pool_id).copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.cache.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cache.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloy_primitives::Address;
+
+    #[test]
+    fn test_failed_pool_retried_after_ttl() {
+        let mut processed_pools = ProcessedPools::new(NonZeroUsize::new(10).unwrap(), Duration::ZERO);
+        let pool_id = PoolId::Address(Address::repeat_byte(1));
+
+        assert!(processed_pools.start_loading(pool_id));
+        assert!(!processed_pools.start_loading(pool_id));
+
+        processed_pools.set_failed(pool_id);
+        assert!(processed_pools.start_loading(pool_id));
+
+        processed_pools.set_failed(pool_id);
+        assert_eq!(processed_pools.clear(), 1);
+        assert_eq!(processed_pools.status(&pool_id), None);
+        assert!(processed_pools.start_loading(pool_id));
+
+        processed_pools.set_loaded(pool_id);
+        assert!(!processed_pools.start_loading(pool_id));
+        assert!(processed_pools.is_settled(&pool_id));
+    }
+
+    #[test]
+    fn test_capacity_bounded() {
+        let mut processed_pools = ProcessedPools::new(NonZeroUsize::new(2).unwrap(), Duration::from_secs(60));
+        for i in 0..5u8 {
+            processed_pools.start_loading(PoolId::Address(Address::repeat_byte(i)));
+        }
+        assert_eq!(processed_pools.len(), 2);
+    }
+}
@@ -0,0 +1,200 @@
+use alloy_network::Network;
+use alloy_primitives::{b256, Address, B256, U256};
+use alloy_provider::Provider;
+use alloy_rpc_types::Log;
+use alloy_sol_types::SolEvent;
+use eyre::Result;
+use std::collections::{HashMap, HashSet};
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{error, info, warn};
+
+use loom_core_actors::{subscribe, Accessor, Actor, ActorResult, Broadcaster, Consumer, SharedState, WorkerResult};
+use loom_core_actors_macros::{Accessor, Consumer};
+use loom_core_blockchain::Blockchain;
+use loom_defi_abi::IERC1967;
+use loom_types_entities::Market;
+use loom_types_events::MessageBlockLogs;
+
+/// `bytes32(uint256(keccak256("eip1967.proxy.implementation")) - 1)`
+pub const EIP1967_IMPLEMENTATION_SLOT: B256 = b256!("360894a13ba1a3210667c828492db98dca3e2076cc3735a920a3ca505d382bbc");
+
+/// Implementation address stored in the EIP-1967 slot, `None` if the contract is not a proxy
+pub async fn fetch_proxy_implementation<P, N>(client: P, address: Address) -> Result<Option<Address>>
+where
+    N: Network,
+    P: Provider<N> + Send + Sync + Clone + 'static,
+{
+    let value = client.get_storage_at(address, U256::from_be_bytes(EIP1967_IMPLEMENTATION_SLOT.0)).await?;
+    let implementation = Address::from_word(B256::from(value.to_be_bytes()));
+    Ok((!implementation.is_zero()).then_some(implementation))
+}
+
+fn parse_upgrade(log: &Log) -> Option<(Address, Address)> {
+    if log.topic0() != Some(&IERC1967::Upgraded::SIGNATURE_HASH) {
+        return None;
+    }
+    IERC1967::Upgraded::decode_log(&log.inner, false).ok().map(|event| (event.address, event.data.implementation))
+}
+
+/// Revalidates upgraded proxies whose new implementation is trusted or that were not upgraded again for `revalidate_blocks`
+#[derive(Clone, Debug, Default)]
+pub struct ProxyRevalidation {
+    trusted_implementations: HashSet<Address>,
+    revalidate_blocks: Option<u64>,
+    // block of the last upgrade of proxies waiting for revalidation
+    upgraded_at: HashMap<Address, u64>,
+}
+
+impl ProxyRevalidation {
+    pub fn new(trusted_implementations: HashSet<Address>, revalidate_blocks: Option<u64>) -> Self {
+        Self { trusted_implementations, revalidate_blocks, upgraded_at: HashMap::new() }
+    }
+
+    fn due(&self, block_number: u64) -> Vec<Address> {
+        let Some(revalidate_blocks) = self.revalidate_blocks else {
+            return Vec::new();
+        };
+        self.upgraded_at
+            .iter()
+            .filter(|(_, upgraded_at)| block_number >= upgraded_at.saturating_add(revalidate_blocks))
+            .map(|(proxy, _)| *proxy)
+            .collect()
+    }
+
+    /// Disable the pools of upgraded proxies and enable the pools of revalidated ones
+    pub fn on_block(&mut self, market: &mut Market, block_number: u64, upgrades: Vec<(Address, Address)>) {
+        for (proxy, implementation) in upgrades {
+            if !market.set_proxy_upgraded(proxy, implementation) {
+                continue;
+            }
+            if self.trusted_implementations.contains(&implementation) {
+                market.revalidate_proxy(proxy);
+                self.upgraded_at.remove(&proxy);
+                info!(%proxy, %implementation, "Proxy upgraded to a trusted implementation");
+            } else {
+                self.upgraded_at.insert(proxy, block_number);
+                warn!(%proxy, %implementation, "Proxy implementation changed, pools disabled until revalidated");
+            }
+        }
+
+        for proxy in self.due(block_number) {
+            self.upgraded_at.remove(&proxy);
+            if market.revalidate_proxy(proxy) {
+                info!(%proxy, "Proxy revalidated, pools enabled");
+            }
+        }
+    }
+}
+
+pub async fn proxy_monitor_worker(
+    log_update_rx: Broadcaster<MessageBlockLogs>,
+    market: SharedState<Market>,
+    mut revalidation: ProxyRevalidation,
+) -> WorkerResult {
+    subscribe!(log_update_rx);
+
+    loop {
+        let log_update: Result<MessageBlockLogs, RecvError> = log_update_rx.recv().await;
+        match log_update {
+            Ok(log_update_msg) => {
+                let block_number = log_update_msg.inner.block_header.number;
+                let upgrades: Vec<(Address, Address)> = log_update_msg.inner.logs.iter().filter_map(parse_upgrade).collect();
+                if upgrades.is_empty() && revalidation.due(block_number).is_empty() {
+                    continue;
+                }
+
+                revalidation.on_block(&mut *market.write().await, block_number, upgrades);
+            }
+            Err(e) => {
+                error!("block_update error {}", e)
+            }
+        }
+    }
+}
+
+/// Disables pools of known proxies when an `Upgraded` event changes their implementation and enables them again when the
+/// new implementation is revalidated
+#[derive(Accessor, Consumer, Default)]
+pub struct ProxyMonitorActor {
+    revalidation: ProxyRevalidation,
+    #[accessor]
+    market: Option<SharedState<Market>>,
+    #[consumer]
+    log_update_rx: Option<Broadcaster<MessageBlockLogs>>,
+}
+
+impl ProxyMonitorActor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Revalidate proxies upgraded to a trusted implementation at once and other proxies after `revalidate_blocks` blocks
+    /// without another upgrade, upgraded proxies stay disabled if not set
+    pub fn with_revalidation(self, trusted_implementations: HashSet<Address>, revalidate_blocks: Option<u64>) -> Self {
+        Self { revalidation: ProxyRevalidation::new(trusted_implementations, revalidate_blocks), ..self }
+    }
+
+    pub fn on_bc(self, bc: &Blockchain) -> Self {
+        Self { market: Some(bc.market()), log_update_rx: Some(bc.new_block_logs_channel()), ..self }
+    }
+}
+
+impl Actor for ProxyMonitorActor {
+    fn start(&self) -> ActorResult {
+        let task = tokio::task::spawn(proxy_monitor_worker(
+            self.log_update_rx.clone().unwrap(),
+            self.market.clone().unwrap(),
+            self.revalidation.clone(),
+        ));
+        Ok(vec![task])
+    }
+
+    fn name(&self) -> &'static str {
+        "ProxyMonitorActor"
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloy_primitives::LogData;
+    use loom_types_entities::{MockPool, PoolId};
+
+    #[test]
+    fn test_parse_upgrade() {
+        let proxy = Address::repeat_byte(1);
+        let implementation = Address::repeat_byte(2);
+        let event = IERC1967::Upgraded { implementation };
+        let log = Log { inner: alloy_primitives::Log { address: proxy, data: event.encode_log_data() }, ..Default::default() };
+        assert_eq!(parse_upgrade(&log), Some((proxy, implementation)));
+
+        let other = Log { inner: alloy_primitives::Log { address: proxy, data: LogData::default() }, ..Default::default() };
+        assert_eq!(parse_upgrade(&other), None);
+    }
+
+    #[test]
+    fn test_revalidation() {
+        let (proxy, implementation, upgraded, trusted) =
+            (Address::repeat_byte(1), Address::repeat_byte(2), Address::repeat_byte(3), Address::repeat_byte(4));
+        let pool = MockPool::new(Address::repeat_byte(5), Address::repeat_byte(6), proxy);
+        let mut market = Market::default();
+        market.add_pool(pool).unwrap();
+        market.set_proxy_implementation(proxy, implementation);
+        let pool_id = PoolId::Address(proxy);
+
+        let mut revalidation = ProxyRevalidation::new(HashSet::from([trusted]), Some(10));
+        revalidation.on_block(&mut market, 100, vec![(proxy, upgraded)]);
+        assert!(market.is_pool_disabled(&pool_id));
+        assert_eq!(revalidation.due(109), Vec::<Address>::new());
+
+        revalidation.on_block(&mut market, 109, vec![]);
+        assert!(market.is_pool_disabled(&pool_id));
+        revalidation.on_block(&mut market, 110, vec![]);
+        assert!(!market.is_pool_disabled(&pool_id));
+        assert_eq!(market.proxy_implementation(&proxy), Some(upgraded));
+
+        revalidation.on_block(&mut market, 120, vec![(proxy, trusted)]);
+        assert!(!market.is_pool_disabled(&pool_id));
+        assert_eq!(market.proxy_implementation(&proxy), Some(trusted));
+    }
+}
@@ -0,0 +1,157 @@
+use std::collections::HashSet;
+use std::marker::PhantomData;
+
+use alloy_network::Network;
+use alloy_provider::Provider;
+use eyre::{eyre, Result};
+use revm::{Database, DatabaseCommit, DatabaseRef};
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{debug, error};
+
+use loom_core_actors::{subscribe, Accessor, Actor, ActorResult, Broadcaster, Consumer, SharedState, WorkerResult};
+use loom_core_actors_macros::{Accessor, Consumer};
+use loom_core_blockchain::{Blockchain, BlockchainState};
+use loom_defi_pools::UniswapV3Pool;
+use loom_node_debug_provider::DebugProviderExt;
+use loom_types_entities::required_state::RequiredStateReader;
+use loom_types_entities::state_proof::StateLoadingMode;
+use loom_types_entities::{Market, MarketState, PoolId, PoolWrapper};
+use loom_types_events::{HealthEvent, MessageHealthEvent};
+
+async fn load_tick_word<P, N, DB>(
+    client: P,
+    market_state: SharedState<MarketState<DB>>,
+    pool: PoolWrapper,
+    word: i16,
+    state_loading: StateLoadingMode,
+) -> Result<()>
+where
+    N: Network,
+    P: Provider<N> + DebugProviderExt<N> + Send + Sync + Clone + 'static,
+    DB: Database + DatabaseRef + DatabaseCommit + Send + Sync + Clone + 'static,
+{
+    let required_state =
+        pool.as_any().downcast_ref::<UniswapV3Pool>().ok_or(eyre!("NOT_UNISWAP_V3_POOL"))?.get_tick_words_state_required(word);
+    let proven_block = market_state.read().await.proven_block;
+    let state = RequiredStateReader::fetch_with_mode(client, required_state, None, state_loading, proven_block).await?;
+    market_state.write().await.apply_geth_update(state);
+    Ok(())
+}
+
+async fn tick_word_loader_worker<P, N, DB>(
+    client: P,
+    market: SharedState<Market>,
+    market_state: SharedState<MarketState<DB>>,
+    pool_health_monitor_rx: Broadcaster<MessageHealthEvent>,
+) -> WorkerResult
+where
+    N: Network,
+    P: Provider<N> + DebugProviderExt<N> + Send + Sync + Clone + 'static,
+    DB: Database + DatabaseRef + DatabaseCommit + Send + Sync + Clone + 'static,
+{
+    subscribe!(pool_health_monitor_rx);
+
+    // loaded words are kept up to date by block state updates and are not requested again
+    let mut requested: HashSet<(PoolId, i16)> = HashSet::new();
+
+    loop {
+        let health_event = match pool_health_monitor_rx.recv().await {
+            Ok(health_event) => health_event,
+            Err(RecvError::Lagged(lag)) => {
+                debug!(lag, "Health events lagged");
+                continue;
+            }
+            Err(e) => {
+                error!("pool_health_monitor_rx error : {}", e);
+                break;
+            }
+        };
+
+        let HealthEvent::PoolSwapError(swap_error) = health_event.inner else {
+            continue;
+        };
+        let Some(word) = swap_error.missing_tick_word() else {
+            continue;
+        };
+        if !requested.insert((swap_error.pool, word)) {
+            continue;
+        }
+        let (pool, state_loading) = {
+            let market_guard = market.read().await;
+            (market_guard.get_pool(&swap_error.pool).cloned(), market_guard.pools_config().state_loading())
+        };
+        let Some(pool) = pool else {
+            continue;
+        };
+
+        match load_tick_word(client.clone(), market_state.clone(), pool, word, state_loading).await {
+            Ok(_) => debug!(pool_id = %swap_error.pool, word, "Tick word loaded"),
+            Err(error) => {
+                error!(%error, pool_id = %swap_error.pool, word, "load_tick_word");
+                // retried with the next failed calculation
+                requested.remove(&(swap_error.pool, word));
+            }
+        }
+    }
+
+    Ok("TickWordLoaderActor".to_string())
+}
+
+/// Extends the tick range of V3 pools loaded around the current tick when a calculation reaches a missing tick word
+#[derive(Accessor, Consumer)]
+pub struct TickWordLoaderActor<P, N, DB>
+where
+    N: Network,
+    P: Provider<N> + Send + Sync + Clone + 'static,
+    DB: Database + DatabaseRef + DatabaseCommit + Send + Sync + Clone + 'static,
+{
+    client: P,
+    #[accessor]
+    market: Option<SharedState<Market>>,
+    #[accessor]
+    market_state: Option<SharedState<MarketState<DB>>>,
+    #[consumer]
+    pool_health_monitor_rx: Option<Broadcaster<MessageHealthEvent>>,
+    _n: PhantomData<N>,
+}
+
+impl<P, N, DB> TickWordLoaderActor<P, N, DB>
+where
+    N: Network,
+    P: Provider<N> + Send + Sync + Clone + 'static,
+    DB: Database + DatabaseRef + DatabaseCommit + Send + Sync + Clone + 'static,
+{
+    pub fn new(client: P) -> Self {
+        Self { client, market: None, market_state: None, pool_health_monitor_rx: None, _n: PhantomData }
+    }
+
+    pub fn on_bc(self, bc: &Blockchain, state: &BlockchainState<DB>) -> Self {
+        Self {
+            market: Some(bc.market()),
+            market_state: Some(state.market_state_commit()),
+            pool_health_monitor_rx: Some(bc.health_monitor_channel()),
+            ..self
+        }
+    }
+}
+
+impl<P, N, DB> Actor for TickWordLoaderActor<P, N, DB>
+where
+    N: Network,
+    P: Provider<N> + DebugProviderExt<N> + Send + Sync + Clone + 'static,
+    DB: Database + DatabaseRef + DatabaseCommit + Send + Sync + Clone + 'static,
+{
+    fn start(&self) -> ActorResult {
+        let task = tokio::task::spawn(tick_word_loader_worker(
+            self.client.clone(),
+            self.market.clone().unwrap(),
+            self.market_state.clone().unwrap(),
+            self.pool_health_monitor_rx.clone().unwrap(),
+        ));
+        Ok(vec![task])
+    }
+
+    fn name(&self) -> &'static str {
+        "TickWordLoaderActor"
+    }
+}
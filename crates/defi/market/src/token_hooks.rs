@@ -0,0 +1,49 @@
+use alloy_network::{Network, TransactionBuilder};
+use alloy_primitives::{address, fixed_bytes, keccak256, Address, FixedBytes};
+use alloy_provider::Provider;
+use alloy_sol_types::SolCall;
+use eyre::Result;
+
+use loom_defi_abi::{IERC1820Registry, IERC165};
+
+/// ERC-1820 registry, ERC-777 tokens register themselves as their `ERC777Token` implementer
+pub const ERC1820_REGISTRY: Address = address!("1820a4B7618BdE71Dce8cdc73aAB6C95905faD24");
+/// ERC-165 interface id of ERC-1363 payable tokens calling `onTransferReceived` of the receiver
+pub const ERC1363_INTERFACE_ID: FixedBytes<4> = fixed_bytes!("b0202a11");
+
+/// Output of the call, `None` if the call reverted. Transport errors are returned
+async fn eth_call<P, N>(client: &P, to: Address, input: Vec<u8>) -> Result<Option<Vec<u8>>>
+where
+    N: Network,
+    P: Provider<N> + Send + Sync + Clone + 'static,
+{
+    let request = N::TransactionRequest::default().with_to(to).with_input(input);
+    match client.call(&request).await {
+        Ok(output) => Ok(Some(output.to_vec())),
+        Err(error) if error.is_error_resp() => Ok(None),
+        Err(error) => Err(error.into()),
+    }
+}
+
+/// Check if transfers of the token call hooks of the sender or the receiver (ERC-777, ERC-1363). Reverting calls are
+/// treated as no hook, tokens without ERC-165 revert on `supportsInterface`. RPC errors are returned, so an unknown token
+/// is checked again instead of being taken for a token without hooks
+pub async fn fetch_transfer_hook<P, N>(client: P, token: Address) -> Result<bool>
+where
+    N: Network,
+    P: Provider<N> + Send + Sync + Clone + 'static,
+{
+    let erc777_call = IERC1820Registry::getInterfaceImplementerCall { account: token, interfaceHash: keccak256("ERC777Token") };
+    let erc1363_call = IERC165::supportsInterfaceCall { interfaceId: ERC1363_INTERFACE_ID };
+    let (erc777_output, erc1363_output) = tokio::try_join!(
+        eth_call(&client, ERC1820_REGISTRY, erc777_call.abi_encode()),
+        eth_call(&client, token, erc1363_call.abi_encode())
+    )?;
+
+    let erc777 = erc777_output.is_some_and(|output| {
+        IERC1820Registry::getInterfaceImplementerCall::abi_decode_returns(&output, false).is_ok_and(|ret| !ret._0.is_zero())
+    });
+    let erc1363 =
+        erc1363_output.is_some_and(|output| IERC165::supportsInterfaceCall::abi_decode_returns(&output, false).is_ok_and(|ret| ret._0));
+    Ok(erc777 || erc1363)
+}
@@ -13,12 +13,23 @@ use loom_types_entities::required_state::RequiredState;
 use loom_types_entities::{Pool, PoolAbiEncoder, PoolClass, PoolId, PoolProtocol, PreswapRequirement, SwapDirection};
 use revm::primitives::Env;
 use revm::DatabaseRef;
-use tracing::error;
+use tracing::{error, trace};
 
 use crate::protocols::{CurveCommonContract, CurveContract, CurveProtocol};
 
 lazy_static! {
     static ref U256_ONE: U256 = U256::from(1);
+    static ref FEE_DENOMINATOR: U256 = U256::from(10_000_000_000u64);
+}
+
+/// LP amount minted for a deposit of one coin less the imbalance fee `fee * n / (4 * (n - 1))` of the pool with `fee` and `n` coins
+fn imbalance_fee_deducted(fee: U256, coins_count: usize, lp_amount: U256) -> Result<U256> {
+    let coins_count = U256::from(coins_count);
+    let imbalance_fee = coins_count
+        .checked_sub(*U256_ONE)
+        .and_then(|divisor| (fee * coins_count).checked_div(U256::from(4) * divisor))
+        .ok_or_eyre("NOT_ENOUGH_COINS")?;
+    Ok(lp_amount - lp_amount * imbalance_fee / *FEE_DENOMINATOR)
 }
 
 pub struct CurvePool<P, N, E = CurvePoolAbiEncoder<P, N>>
@@ -80,6 +91,19 @@ where
         Self { abi_encoder: Some(Arc::new(e)), ..self }
     }
 
+    /// LP token of the pool if minting and burning it are swap directions of the pool. Deposits of raw ETH are not encoded
+    pub fn lp_token_swappable(&self) -> Option<Address> {
+        self.lp_token.filter(|_| !self.is_native)
+    }
+
+    /// Deposit of one coin is charged the imbalance fee on the part above its share of the balances, charging it on the
+    /// whole amount keeps the minted amount below the actual one
+    fn deduct_imbalance_fee(&self, state_db: &dyn DatabaseRef<Error = ErrReport>, env: Env, lp_amount: U256) -> Result<U256> {
+        let (value, _) = evm_call(state_db, env, self.get_address(), self.pool_contract.get_fee_call_data().to_vec())?;
+        let fee = U256::from_be_slice(value.get(0..32).ok_or_eyre("FEE_NOT_FETCHED")?);
+        imbalance_fee_deducted(fee, self.tokens.len(), lp_amount)
+    }
+
     pub fn get_meta_coin_idx(&self, address: Address) -> Result<u32> {
         match self.get_coin_idx(address) {
             Ok(i) => Ok(i),
@@ -127,7 +151,7 @@ where
             }
         }
 
-        let lp_token = match CurveCommonContract::<P, N>::lp_token(pool_contract.get_address()).await {
+        let lp_token = match CurveCommonContract::<P, N>::lp_token(client.clone(), pool_contract.get_address()).await {
             Ok(lp_token_address) => Some(lp_token_address),
             Err(_) => None,
         };
@@ -182,7 +206,7 @@ where
             }
         }
 
-        let lp_token = match CurveCommonContract::<P, N>::lp_token(pool_contract.get_address()).await {
+        let lp_token = match CurveCommonContract::<P, N>::lp_token(client.clone(), pool_contract.get_address()).await {
             Ok(lp_token_address) => Some(lp_token_address),
             Err(_) => None,
         };
@@ -264,9 +288,14 @@ where
                     }
                     ret.push((self.tokens[i], self.tokens[j]).into());
                 }
-                if let Some(lp_token_address) = self.lp_token {
-                    ret.push((self.tokens[i], lp_token_address).into());
-                    ret.push((lp_token_address, self.tokens[i]).into());
+            }
+        }
+        // add_liquidity mints the LP token for one coin, remove_liquidity_one_coin burns it for one coin
+        if let Some(lp_token_address) = self.lp_token_swappable() {
+            for token_address in self.tokens.iter() {
+                ret.push((*token_address, lp_token_address).into());
+                if self.pool_contract.can_remove_liquidity_one_coin() {
+                    ret.push((lp_token_address, *token_address).into());
                 }
             }
         }
@@ -284,7 +313,17 @@ where
         let mut env = env;
         env.tx.gas_limit = 500_000;
 
-        let call_data = if self.is_meta {
+        let lp_token = self.lp_token.filter(|lp_token| lp_token == token_address_from || lp_token == token_address_to);
+
+        let call_data = if let Some(lp_token) = lp_token {
+            if *token_address_from == lp_token {
+                let i: u32 = self.get_coin_idx(*token_address_to)?;
+                self.pool_contract.calc_withdraw_one_coin_call_data(i, in_amount)?
+            } else {
+                let i: u32 = self.get_coin_idx(*token_address_from)?;
+                self.pool_contract.calc_token_amount_call_data(i, in_amount)?
+            }
+        } else if self.is_meta {
             let i: Result<u32> = self.get_coin_idx(*token_address_from);
             let j: Result<u32> = self.get_coin_idx(*token_address_to);
             if i.is_ok() && j.is_ok() {
@@ -294,28 +333,23 @@ where
                 let j: u32 = self.get_meta_coin_idx(*token_address_to)?;
                 self.pool_contract.get_dy_underlying_call_data(i, j, in_amount)?
             }
-        } else if let Some(lp_token) = self.lp_token {
-            if *token_address_from == lp_token {
-                let i: u32 = self.get_coin_idx(*token_address_to)?;
-                self.pool_contract.calc_withdraw_one_coin_call_data(i, in_amount)?
-            } else if *token_address_to == lp_token {
-                let i: u32 = self.get_coin_idx(*token_address_from)?;
-                self.pool_contract.calc_token_amount_call_data(i, in_amount)?
-            } else {
-                let i: u32 = self.get_coin_idx(*token_address_from)?;
-                let j: u32 = self.get_coin_idx(*token_address_to)?;
-                self.pool_contract.get_dy_call_data(i, j, in_amount)?
-            }
         } else {
             let i: u32 = self.get_coin_idx(*token_address_from)?;
             let j: u32 = self.get_coin_idx(*token_address_to)?;
             self.pool_contract.get_dy_call_data(i, j, in_amount)?
         };
 
-        let (value, gas_used) = evm_call(state_db, env, self.get_address(), call_data.to_vec())?;
+        let (value, gas_used) = evm_call(state_db, env.clone(), self.get_address(), call_data.to_vec())?;
 
         let ret = if value.len() > 32 { U256::from_be_slice(&value[0..32]) } else { U256::from_be_slice(&value[0..]) };
 
+        let ret = match lp_token {
+            Some(lp_token) if *token_address_to == lp_token && self.pool_contract.calc_token_amount_without_fee() => {
+                self.deduct_imbalance_fee(state_db, env, ret)?
+            }
+            _ => ret,
+        };
+
         if ret.is_zero() {
             Err(eyre!("ZERO_OUT_AMOUNT"))
         } else {
@@ -393,20 +427,6 @@ where
                 }
             }
         } else {
-            if let Some(_lp_token) = self.lp_token {
-                for i in 0..self.tokens.len() {
-                    let value = self.balances[i] / U256::from(10);
-                    match self.pool_contract.get_add_liquidity_call_data(i as u32, value, Address::ZERO) {
-                        Ok(data) => {
-                            state_reader.add_call(self.get_address(), data);
-                        }
-                        Err(e) => {
-                            error!("{}", e);
-                        }
-                    }
-                }
-            }
-
             for i in 0..self.tokens.len() {
                 for j in 0..self.tokens.len() {
                     if i == j {
@@ -429,6 +449,29 @@ where
                 }
             }
         }
+
+        if self.lp_token.is_some() {
+            for i in 0..self.tokens.len() {
+                let value = self.balances.get(i).copied().unwrap_or_default() / U256::from(10);
+                let calls = [
+                    self.pool_contract.get_add_liquidity_call_data(i as u32, value, Address::ZERO),
+                    self.pool_contract.calc_token_amount_call_data(i as u32, value),
+                    self.pool_contract.calc_withdraw_one_coin_call_data(i as u32, value),
+                ];
+                for call in calls {
+                    match call {
+                        Ok(data) => {
+                            state_reader.add_call(self.get_address(), data);
+                        }
+                        Err(e) => {
+                            trace!("{}", e);
+                        }
+                    }
+                }
+            }
+            state_reader.add_call(self.get_address(), self.pool_contract.get_fee_call_data());
+        }
+
         state_reader.add_slot_range(self.get_address(), U256::from(0), 0x20);
 
         for token_address in self.get_tokens() {
@@ -444,6 +487,10 @@ where
     fn preswap_requirement(&self) -> PreswapRequirement {
         PreswapRequirement::Allowance
     }
+
+    fn get_lp_token(&self) -> Option<Address> {
+        self.lp_token_swappable()
+    }
 }
 
 #[allow(dead_code)]
@@ -526,7 +573,13 @@ where
         recipient: Address,
         _payload: Bytes,
     ) -> Result<Bytes> {
-        if self.is_meta {
+        if self.lp_token == Some(token_from_address) {
+            let i: u32 = self.get_coin_idx(token_to_address)?;
+            self.curve_contract.get_remove_liquidity_one_coin_call_data(i, amount, recipient)
+        } else if self.lp_token == Some(token_to_address) {
+            let i: u32 = self.get_coin_idx(token_from_address)?;
+            self.curve_contract.get_add_liquidity_call_data(i, amount, recipient)
+        } else if self.is_meta {
             let i: Result<u32> = self.get_coin_idx(token_from_address);
             let j: Result<u32> = self.get_coin_idx(token_to_address);
 
@@ -538,18 +591,6 @@ where
                     self.curve_contract.get_exchange_underlying_call_data(meta_i, meta_j, amount, U256::ZERO, recipient)
                 }
             }
-        } else if let Some(lp_token) = self.lp_token {
-            if token_from_address == lp_token {
-                let i: u32 = self.get_coin_idx(token_to_address)?;
-                self.curve_contract.get_remove_liquidity_one_coin_call_data(i, amount, recipient)
-            } else if token_to_address == lp_token {
-                let i: u32 = self.get_coin_idx(token_from_address)?;
-                self.curve_contract.get_add_liquidity_call_data(i, amount, recipient)
-            } else {
-                let i: u32 = self.get_coin_idx(token_from_address)?;
-                let j: u32 = self.get_coin_idx(token_to_address)?;
-                self.curve_contract.get_exchange_call_data(i, j, amount, U256::ZERO, recipient)
-            }
         } else {
             let i: u32 = self.get_coin_idx(token_from_address)?;
             let j: u32 = self.get_coin_idx(token_to_address)?;
@@ -568,8 +609,16 @@ where
         Err(eyre!("NOT_IMPLEMENTED"))
     }
 
-    fn swap_in_amount_offset(&self, _token_from_address: Address, _token_to_address: Address) -> Option<u32> {
-        Some(0x44)
+    fn swap_in_amount_offset(&self, token_from_address: Address, token_to_address: Address) -> Option<u32> {
+        if self.lp_token == Some(token_from_address) {
+            // remove_liquidity_one_coin(amount, i, min_amount)
+            Some(0x04)
+        } else if self.lp_token == Some(token_to_address) {
+            // add_liquidity(amounts, min_mint_amount), the amount of the coin in the amounts array
+            self.get_coin_idx(token_from_address).ok().map(|i| 0x04 + 0x20 * i)
+        } else {
+            Some(0x44)
+        }
     }
 
     fn swap_out_amount_offset(&self, _token_from_address: Address, _token_to_address: Address) -> Option<u32> {
@@ -605,8 +654,72 @@ mod tests {
     use loom_types_entities::{MarketState, Pool};
     use tracing::debug;
 
+    use crate::curvepool::imbalance_fee_deducted;
     use crate::protocols::CurveProtocol;
-    use crate::CurvePool;
+    use crate::{CurvePool, CurvePoolAbiEncoder};
+    use alloy::network::Ethereum;
+    use alloy::primitives::{address, Address};
+    use alloy::providers::RootProvider;
+    use loom_types_entities::{PoolAbiEncoder, SwapDirection};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_lp_swap_directions() -> Result<()> {
+        let client = RootProvider::<Ethereum>::new_http("http://localhost:8545".parse()?);
+        let (dai, usdc, usdt) = (Address::repeat_byte(1), Address::repeat_byte(2), Address::repeat_byte(3));
+        let lp_token = address!("6c3F90f043a72FA612cbac8115EE7e52BDe6E490");
+
+        let mut pool: CurvePool<RootProvider, Ethereum> = CurvePool {
+            address: address!("bEbc44782C7dB0a1A60Cb6fe97d0b483032FF1C7"),
+            pool_contract: Arc::new(CurveProtocol::new_i128_3(client, address!("bEbc44782C7dB0a1A60Cb6fe97d0b483032FF1C7"))),
+            balances: vec![U256::from(100); 3],
+            tokens: vec![dai, usdc, usdt],
+            underlying_tokens: vec![],
+            lp_token: Some(lp_token),
+            abi_encoder: None,
+            is_meta: false,
+            is_native: false,
+        };
+        pool.abi_encoder = Some(Arc::new(CurvePoolAbiEncoder::new(&pool)));
+
+        // coin pairs both ways and a mint and a burn for every coin
+        let directions = pool.get_swap_directions();
+        assert_eq!(directions.len(), 12);
+        assert!(directions.contains(&SwapDirection::new(usdc, lp_token)));
+        assert!(directions.contains(&SwapDirection::new(lp_token, usdt)));
+
+        // the amount of the coin is written at its place in the amounts of add_liquidity
+        let encoder = pool.get_abi_encoder().unwrap();
+        let amount = U256::from(0x1234);
+        let call_data = encoder.encode_swap_in_amount_provided(usdc, lp_token, amount, Address::ZERO, Default::default())?;
+        let offset = encoder.swap_in_amount_offset(usdc, lp_token).unwrap() as usize;
+        assert_eq!(offset, 0x24);
+        assert_eq!(U256::from_be_slice(&call_data[offset..offset + 0x20]), amount);
+
+        let call_data = encoder.encode_swap_in_amount_provided(lp_token, usdt, amount, Address::ZERO, Default::default())?;
+        let offset = encoder.swap_in_amount_offset(lp_token, usdt).unwrap() as usize;
+        assert_eq!(U256::from_be_slice(&call_data[offset..offset + 0x20]), amount);
+        assert_eq!(encoder.swap_in_amount_offset(dai, usdt), Some(0x44));
+
+        // deposits of raw ETH are not encoded
+        pool.is_native = true;
+        assert_eq!(pool.get_swap_directions().len(), 6);
+        assert_eq!(pool.get_lp_token(), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_imbalance_fee_deducted() -> Result<()> {
+        // 3pool fee of 0.01%, the imbalance fee of its 3 coins is 0.00375%
+        let fee = U256::from(1_000_000);
+        let lp_amount = U256::from(1_000_000) * U256::from(10).pow(U256::from(18));
+        assert_eq!(imbalance_fee_deducted(fee, 3, lp_amount)?, U256::from(999_962_500_000_000_000_000_000u128));
+        // 0.005% for 2 coins
+        assert_eq!(imbalance_fee_deducted(fee, 2, U256::from(1_000_000))?, U256::from(999_950));
+        assert!(imbalance_fee_deducted(fee, 1, lp_amount).is_err());
+        assert!(imbalance_fee_deducted(fee, 0, lp_amount).is_err());
+        Ok(())
+    }
 
     #[tokio::test]
     async fn test_pool() -> Result<()> {
@@ -1,3 +1,5 @@
-pub use uniswapv3::UniswapV3DBReader;
+pub use uniswapv3::{Observation, UniswapV3DBReader, OBSERVATIONS_SLOT};
+pub use uniswapv3_oracle::{observations_required_state, UniswapV3Twap};
 
 mod uniswapv3;
+mod uniswapv3_oracle;
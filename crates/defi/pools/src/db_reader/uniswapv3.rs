@@ -15,6 +15,8 @@ pub struct UniswapV3DBReader {}
 
 lazy_static! {
     static ref BITS160MASK: U256 = U256::from(1).shl(160) - U256::from(1);
+    static ref BITS56MASK: U256 = U256::from(1).shl(56) - U256::from(1);
+    static ref BITS32MASK: U256 = U256::from(1).shl(32) - U256::from(1);
     static ref BITS128MASK: U256 = U256::from(1).shl(128) - U256::from(1);
     static ref BITS24MASK: U256 = U256::from(1).shl(24) - U256::from(1);
     static ref BITS16MASK: U256 = U256::from(1).shl(16) - U256::from(1);
@@ -68,10 +70,10 @@ impl UniswapV3DBReader {
         Ok(cell)
     }
 
-    pub fn observations<DB: DatabaseRef>(db: &DB, address: Address, idx: u32) -> Result<U256> {
-        //i16
-        let cell = try_read_hashmap_cell(&db, &address, &U256::from(7), &U256::from(idx))?;
-        Ok(cell)
+    /// Observation `idx` of the oracle ring buffer, a fixed size array starting at slot 8
+    pub fn observation<DB: DatabaseRef>(db: &DB, address: Address, idx: u16) -> Result<Observation> {
+        let cell = try_read_cell(&db, &address, &(U256::from(OBSERVATIONS_SLOT) + U256::from(idx)))?;
+        Ok(Observation::from_cell(cell))
     }
 
     pub fn slot0<DB: DatabaseRef>(db: &DB, address: Address) -> Result<slot0Return> {
@@ -94,6 +96,40 @@ impl UniswapV3DBReader {
     }
 }
 
+/// First slot of the `observations` array of the pool
+pub const OBSERVATIONS_SLOT: u64 = 8;
+
+/// Oracle observation of a V3 pool
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Observation {
+    pub block_timestamp: u32,
+    pub tick_cumulative: i64,
+    pub seconds_per_liquidity_cumulative_x128: U160,
+    pub initialized: bool,
+}
+
+impl Observation {
+    pub fn from_cell(cell: U256) -> Self {
+        let block_timestamp: u32 = (cell & *BITS32MASK).to();
+        let tick_cumulative: Uint<56, 1> = ((Shr::<U256>::shr(cell, U256::from(32))) & *BITS56MASK).to();
+        let tick_cumulative: i64 = Signed::<56, 1>::from_raw(tick_cumulative).as_i64();
+        Self {
+            block_timestamp,
+            tick_cumulative,
+            seconds_per_liquidity_cumulative_x128: ((Shr::<U256>::shr(cell, U256::from(88))) & *BITS160MASK).to(),
+            initialized: !((Shr::<U256>::shr(cell, U256::from(248))) & *BITS1MASK).is_zero(),
+        }
+    }
+
+    pub fn to_cell(&self) -> U256 {
+        let tick_cumulative = U256::from(self.tick_cumulative as u64 & ((1u64 << 56) - 1));
+        U256::from(self.block_timestamp)
+            | tick_cumulative.shl(32)
+            | U256::from(self.seconds_per_liquidity_cumulative_x128).shl(88)
+            | U256::from(self.initialized as u8).shl(248)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use alloy::primitives::Address;
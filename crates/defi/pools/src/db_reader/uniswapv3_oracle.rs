@@ -0,0 +1,163 @@
+use alloy::primitives::{Address, U256};
+use eyre::{eyre, Result};
+use revm::{Database, DatabaseCommit, DatabaseRef};
+
+use loom_types_entities::required_state::RequiredState;
+use loom_types_entities::MarketState;
+
+use crate::db_reader::uniswapv3::{Observation, OBSERVATIONS_SLOT};
+use crate::db_reader::UniswapV3DBReader;
+
+/// Slots of the initialized observations of the pool, loaded with the pool state of pools used for TWAP checks
+pub fn observations_required_state(address: Address, cardinality: u16) -> RequiredState {
+    let mut required_state = RequiredState::new();
+    required_state.add_slot_range(address, U256::from(OBSERVATIONS_SLOT), cardinality as usize);
+    required_state
+}
+
+/// Observation extended to `time` at the current tick, as `Oracle.transform`
+fn transform(last: &Observation, time: u32, tick: i32) -> Observation {
+    let delta = time.saturating_sub(last.block_timestamp) as i64;
+    Observation { block_timestamp: time, tick_cumulative: last.tick_cumulative + tick as i64 * delta, ..*last }
+}
+
+/// Tick cumulative of the pool `seconds_ago` before `time`, as `Oracle.observeSingle`. Observations older than the
+/// oldest loaded one are not available.
+fn tick_cumulative<DB: DatabaseRef>(db: &DB, pool: Address, time: u32, seconds_ago: u32) -> Result<i64> {
+    let slot0 = UniswapV3DBReader::slot0(db, pool)?;
+    let (tick, index, cardinality) = (slot0.tick.as_i32(), slot0.observationIndex, slot0.observationCardinality);
+    if cardinality == 0 {
+        return Err(eyre!("ORACLE_NOT_INITIALIZED"));
+    }
+
+    let newest = UniswapV3DBReader::observation(db, pool, index)?;
+    if !newest.initialized {
+        return Err(eyre!("OBSERVATION_NOT_LOADED"));
+    }
+    let target = time.checked_sub(seconds_ago).ok_or_else(|| eyre!("TARGET_BEFORE_EPOCH"))?;
+    if newest.block_timestamp <= target {
+        return Ok(transform(&newest, target, tick).tick_cumulative);
+    }
+
+    let mut oldest = UniswapV3DBReader::observation(db, pool, ((index as u32 + 1) % cardinality as u32) as u16)?;
+    if !oldest.initialized {
+        oldest = UniswapV3DBReader::observation(db, pool, 0)?;
+    }
+    if !oldest.initialized {
+        return Err(eyre!("OBSERVATION_NOT_LOADED"));
+    }
+    if oldest.block_timestamp > target {
+        return Err(eyre!("OLD"));
+    }
+
+    // binary search of the observations surrounding the target, the oldest is at index + 1
+    let (mut left, mut right) = (index as u32 + 1, index as u32 + cardinality as u32);
+    while left <= right {
+        let middle = (left + right) / 2;
+        let before_or_at = UniswapV3DBReader::observation(db, pool, (middle % cardinality as u32) as u16)?;
+        if !before_or_at.initialized {
+            left = middle + 1;
+            continue;
+        }
+        let at_or_after = UniswapV3DBReader::observation(db, pool, ((middle + 1) % cardinality as u32) as u16)?;
+
+        if before_or_at.block_timestamp <= target && target <= at_or_after.block_timestamp {
+            if target == before_or_at.block_timestamp {
+                return Ok(before_or_at.tick_cumulative);
+            }
+            if target == at_or_after.block_timestamp {
+                return Ok(at_or_after.tick_cumulative);
+            }
+            let observation_delta = (at_or_after.block_timestamp - before_or_at.block_timestamp) as i64;
+            let target_delta = (target - before_or_at.block_timestamp) as i64;
+            return Ok(before_or_at.tick_cumulative
+                + (at_or_after.tick_cumulative - before_or_at.tick_cumulative) / observation_delta * target_delta);
+        }
+
+        if before_or_at.block_timestamp < target {
+            left = middle + 1;
+        } else {
+            right = middle - 1;
+        }
+    }
+    Err(eyre!("OBSERVATION_NOT_LOADED"))
+}
+
+/// TWAP queries of Uniswap V3 pools with their observations loaded, e.g. to avoid backrunning into a price
+/// manipulated within the block
+pub trait UniswapV3Twap {
+    /// Time weighted average tick over the `seconds_ago` seconds before `time`, rounded to negative infinity as
+    /// `OracleLibrary.consult`
+    fn uniswap_v3_twap_tick(&self, pool: Address, time: u32, seconds_ago: u32) -> Result<i32>;
+
+    /// Ticks between the spot tick and the time weighted average tick, a tick is about one basis point of the price
+    fn uniswap_v3_twap_deviation(&self, pool: Address, time: u32, seconds_ago: u32) -> Result<u32>;
+}
+
+impl<DB: Database + DatabaseRef + DatabaseCommit> UniswapV3Twap for MarketState<DB> {
+    fn uniswap_v3_twap_tick(&self, pool: Address, time: u32, seconds_ago: u32) -> Result<i32> {
+        if seconds_ago == 0 {
+            return Err(eyre!("ZERO_TWAP_PERIOD"));
+        }
+        let tick_cumulative_now = tick_cumulative(&self.state_db, pool, time, 0)?;
+        let tick_cumulative_ago = tick_cumulative(&self.state_db, pool, time, seconds_ago)?;
+        let delta = tick_cumulative_now - tick_cumulative_ago;
+        let mut twap_tick = delta / seconds_ago as i64;
+        if delta < 0 && delta % seconds_ago as i64 != 0 {
+            twap_tick -= 1;
+        }
+        Ok(twap_tick as i32)
+    }
+
+    fn uniswap_v3_twap_deviation(&self, pool: Address, time: u32, seconds_ago: u32) -> Result<u32> {
+        let twap_tick = self.uniswap_v3_twap_tick(pool, time, seconds_ago)?;
+        let spot_tick = UniswapV3DBReader::slot0(&self.state_db, pool)?.tick.as_i32();
+        Ok(spot_tick.abs_diff(twap_tick))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use loom_evm_db::LoomDBType;
+
+    const POOL: Address = Address::repeat_byte(0x10);
+
+    fn slot0_cell(tick: i32, index: u16, cardinality: u16) -> U256 {
+        let tick = U256::from(tick as u32 & 0xFFFFFF);
+        (tick << 160) | (U256::from(index) << 184) | (U256::from(cardinality) << 200) | (U256::from(cardinality) << 216)
+    }
+
+    fn observation(block_timestamp: u32, tick_cumulative: i64) -> Observation {
+        Observation { block_timestamp, tick_cumulative, initialized: true, ..Default::default() }
+    }
+
+    #[test]
+    fn test_twap() {
+        let mut db = LoomDBType::default();
+        // ring buffer of 4 with the newest observation at index 1 and the oldest at index 2, the tick was 100 until 1100,
+        // 200 until 1300 and -50 since
+        let observations = [observation(1300, 50_000), observation(1400, 45_000), observation(1000, 0), observation(1100, 10_000)];
+        for (idx, observation) in observations.iter().enumerate() {
+            db.insert_account_storage(POOL, U256::from(OBSERVATIONS_SLOT + idx as u64), observation.to_cell()).unwrap();
+        }
+        db.insert_account_storage(POOL, U256::ZERO, slot0_cell(-50, 1, 4)).unwrap();
+        let market_state = MarketState::new(db);
+
+        for observation in observations {
+            let cell = observation.to_cell();
+            assert_eq!(Observation::from_cell(cell), observation);
+        }
+
+        // -50 since the newest observation
+        assert_eq!(market_state.uniswap_v3_twap_tick(POOL, 1500, 100).unwrap(), -50);
+        // 100 seconds at 200 and 100 seconds at -50
+        assert_eq!(market_state.uniswap_v3_twap_tick(POOL, 1400, 200).unwrap(), 75);
+        // interpolated between the observations at 1000 and 1100
+        assert_eq!(market_state.uniswap_v3_twap_tick(POOL, 1400, 350).unwrap(), 114);
+        // rounded to negative infinity
+        assert_eq!(market_state.uniswap_v3_twap_tick(POOL, 1470, 180).unwrap(), -37);
+        assert_eq!(market_state.uniswap_v3_twap_deviation(POOL, 1400, 200).unwrap(), 125);
+        assert!(market_state.uniswap_v3_twap_tick(POOL, 1400, 500).is_err());
+    }
+}
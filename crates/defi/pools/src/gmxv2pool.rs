@@ -0,0 +1,370 @@
+use alloy::network::primitives::{BlockTransactionsKind, HeaderResponse};
+use alloy::primitives::{keccak256, Address, B256, U256};
+use alloy::providers::{network::BlockResponse, Network, Provider};
+use alloy::rpc::types::BlockNumberOrTag;
+use alloy::sol_types::{SolCall, SolValue};
+use eyre::{eyre, ErrReport, Result};
+use lazy_static::lazy_static;
+use loom_defi_abi::gmx::IChainlinkPriceFeed::latestRoundDataCall;
+use loom_defi_abi::gmx::IGmxReader::{getMarketCall, getSwapAmountOutCall};
+use loom_defi_abi::gmx::{IChainlinkPriceFeed, IGmxDataStore, IGmxReader, MarketPrices, MarketProps, PriceProps};
+use loom_defi_abi::IERC20;
+use loom_defi_address_book::GmxV2AddressArbitrum;
+use loom_evm_utils::evm::evm_call;
+use loom_types_entities::required_state::RequiredState;
+use loom_types_entities::{Pool, PoolAbiEncoder, PoolClass, PoolId, PoolProtocol, PreswapRequirement, SwapDirection};
+use revm::primitives::Env;
+use revm::DatabaseRef;
+use std::any::Any;
+use tracing::debug;
+
+// the DataStore and Reader of the address book are deployed on Arbitrum only
+const ARBITRUM_CHAIN_ID: u64 = 42161;
+
+lazy_static! {
+    // GMX prices are USD per token unit with 30 decimals
+    static ref GMX_FLOAT_PRECISION: U256 = U256::from(10).pow(U256::from(30));
+}
+
+/// DataStore key `keccak256(abi.encode(keccak256(abi.encode(name)), token))`
+fn token_key(name: &str, token: Address) -> B256 {
+    keccak256((keccak256(name.to_string().abi_encode()), token).abi_encode())
+}
+
+/// Chainlink price feed of a market token as configured in the GMX DataStore
+#[derive(Clone, Debug)]
+pub struct GmxPriceFeed {
+    pub token: Address,
+    pub feed: Address,
+    /// Converts the feed answer to the GMX price, `price = answer * multiplier / 10^30`
+    pub multiplier: U256,
+    /// Maximal age of the feed answer in seconds
+    pub heartbeat: u64,
+}
+
+impl GmxPriceFeed {
+    /// GMX price of the feed answer updated at `updated_at`, fails if the answer is older than the heartbeat at `timestamp`
+    pub fn price(&self, answer: U256, updated_at: U256, timestamp: U256) -> Result<U256> {
+        if updated_at.saturating_add(U256::from(self.heartbeat)) < timestamp {
+            return Err(eyre!("GMX_PRICE_STALE"));
+        }
+        if answer.is_zero() {
+            return Err(eyre!("GMX_PRICE_ZERO"));
+        }
+        Ok(answer * self.multiplier / *GMX_FLOAT_PRECISION)
+    }
+}
+
+/// GMX v2 market (GM pool) swapping between the long and the short token at oracle prices.
+///
+/// Swaps are priced with the Chainlink feeds configured in the DataStore, the same feeds GMX uses for atomic actions.
+/// GM swaps are executed by GMX keepers and can not be encoded into a multicaller call, the pool is used for pricing.
+#[derive(Clone)]
+pub struct GmxV2Pool {
+    address: Address,
+    index_token: Address,
+    long_token: Address,
+    short_token: Address,
+    data_store: Address,
+    reader: Address,
+    price_feeds: Vec<GmxPriceFeed>,
+    // oracle prices at loading, used for the swap calls of the required state
+    prices: Option<MarketPrices>,
+    liquidity_long: U256,
+    liquidity_short: U256,
+}
+
+impl GmxV2Pool {
+    pub fn new(address: Address, long_token: Address, short_token: Address) -> Self {
+        GmxV2Pool {
+            address,
+            index_token: Address::ZERO,
+            long_token,
+            short_token,
+            data_store: GmxV2AddressArbitrum::DATA_STORE,
+            reader: GmxV2AddressArbitrum::READER,
+            price_feeds: Vec::new(),
+            prices: None,
+            liquidity_long: U256::ZERO,
+            liquidity_short: U256::ZERO,
+        }
+    }
+
+    pub fn with_price_feeds(self, price_feeds: Vec<GmxPriceFeed>) -> Self {
+        Self { price_feeds, ..self }
+    }
+
+    fn market_props(&self) -> MarketProps {
+        MarketProps { marketToken: self.address, indexToken: self.index_token, longToken: self.long_token, shortToken: self.short_token }
+    }
+
+    fn price_tokens(&self) -> Vec<Address> {
+        let mut tokens = vec![self.long_token];
+        for token in [self.short_token, self.index_token] {
+            if !token.is_zero() && !tokens.contains(&token) {
+                tokens.push(token);
+            }
+        }
+        tokens
+    }
+
+    fn market_prices(&self, prices: &[(Address, U256)]) -> Result<MarketPrices> {
+        let price_props = |token: Address| -> Result<PriceProps> {
+            let price = prices.iter().find(|(price_token, _)| *price_token == token).map(|(_, price)| *price);
+            let price = price.ok_or_else(|| eyre!("GMX_PRICE_NOT_FOUND"))?;
+            Ok(PriceProps { min: price, max: price })
+        };
+        // swap only markets have no index token
+        let index_token = if self.index_token.is_zero() { self.long_token } else { self.index_token };
+        Ok(MarketPrices {
+            indexTokenPrice: price_props(index_token)?,
+            longTokenPrice: price_props(self.long_token)?,
+            shortTokenPrice: price_props(self.short_token)?,
+        })
+    }
+
+    /// Read the oracle prices from the price feeds in the state and check they are not stale at the block of `env`
+    fn oracle_prices(&self, state_db: &dyn DatabaseRef<Error = ErrReport>, env: &Env) -> Result<MarketPrices> {
+        let mut prices = Vec::new();
+        for price_feed in self.price_feeds.iter() {
+            let (value, _) = evm_call(state_db, env.clone(), price_feed.feed, latestRoundDataCall {}.abi_encode())?;
+            let round = latestRoundDataCall::abi_decode_returns(&value, false)?;
+            let price = price_feed.price(round.answer.try_into().unwrap_or_default(), round.updatedAt, env.block.timestamp)?;
+            prices.push((price_feed.token, price));
+        }
+        self.market_prices(&prices)
+    }
+
+    pub async fn fetch_pool_data<N: Network, P: Provider<N> + Send + Sync + Clone + 'static>(client: P, address: Address) -> Result<Self> {
+        let chain_id = client.get_chain_id().await?;
+        if chain_id != ARBITRUM_CHAIN_ID {
+            return Err(eyre!("GMX_UNSUPPORTED_CHAIN : {chain_id}"));
+        }
+
+        let data_store = IGmxDataStore::IGmxDataStoreInstance::new(GmxV2AddressArbitrum::DATA_STORE, client.clone());
+        let reader = IGmxReader::IGmxReaderInstance::new(GmxV2AddressArbitrum::READER, client.clone());
+
+        let market = reader.getMarket(GmxV2AddressArbitrum::DATA_STORE, address).call().await?._0;
+        if market.marketToken != address {
+            return Err(eyre!("GMX_MARKET_NOT_FOUND"));
+        }
+
+        let mut pool = GmxV2Pool { index_token: market.indexToken, ..GmxV2Pool::new(address, market.longToken, market.shortToken) };
+
+        let block = client
+            .get_block_by_number(BlockNumberOrTag::Latest, BlockTransactionsKind::Hashes)
+            .await?
+            .ok_or_else(|| eyre!("BLOCK_NOT_FOUND"))?;
+        let timestamp = U256::from(block.header().timestamp());
+
+        let mut prices = Vec::new();
+        for token in pool.price_tokens() {
+            let feed = data_store.getAddress(token_key("PRICE_FEED", token)).call().await?._0;
+            if feed.is_zero() {
+                return Err(eyre!("GMX_PRICE_FEED_NOT_SET"));
+            }
+            let multiplier = data_store.getUint(token_key("PRICE_FEED_MULTIPLIER", token)).call().await?._0;
+            let heartbeat = data_store.getUint(token_key("PRICE_FEED_HEARTBEAT_DURATION", token)).call().await?._0;
+            let price_feed = GmxPriceFeed { token, feed, multiplier, heartbeat: heartbeat.saturating_to() };
+
+            let round = IChainlinkPriceFeed::IChainlinkPriceFeedInstance::new(feed, client.clone()).latestRoundData().call().await?;
+            match price_feed.price(round.answer.try_into().unwrap_or_default(), round.updatedAt, timestamp) {
+                Ok(price) => prices.push((token, price)),
+                Err(e) => debug!("GMX price of {} is not available : {}", token, e),
+            }
+            pool.price_feeds.push(price_feed);
+        }
+        pool.prices = pool.market_prices(&prices).ok();
+
+        pool.liquidity_long = IERC20::IERC20Instance::new(pool.long_token, client.clone()).balanceOf(address).call().await?._0;
+        pool.liquidity_short = IERC20::IERC20Instance::new(pool.short_token, client.clone()).balanceOf(address).call().await?._0;
+
+        Ok(pool)
+    }
+}
+
+impl Pool for GmxV2Pool {
+    fn as_any<'a>(&self) -> &dyn Any {
+        self
+    }
+
+    fn get_class(&self) -> PoolClass {
+        PoolClass::GmxV2
+    }
+
+    fn get_protocol(&self) -> PoolProtocol {
+        PoolProtocol::GmxV2
+    }
+
+    fn get_address(&self) -> Address {
+        self.address
+    }
+
+    fn get_pool_id(&self) -> PoolId {
+        PoolId::Address(self.address)
+    }
+
+    fn get_fee(&self) -> U256 {
+        U256::ZERO
+    }
+
+    fn get_tokens(&self) -> Vec<Address> {
+        vec![self.long_token, self.short_token]
+    }
+
+    /// GMX swaps are orders executed by a keeper in a later transaction, they cannot be encoded as a hop of an atomic
+    /// swap. Markets are loaded for pricing only and are not part of swap paths
+    fn get_swap_directions(&self) -> Vec<SwapDirection> {
+        Vec::new()
+    }
+
+    fn calculate_out_amount(
+        &self,
+        state_db: &dyn DatabaseRef<Error = ErrReport>,
+        env: Env,
+        token_address_from: &Address,
+        token_address_to: &Address,
+        in_amount: U256,
+    ) -> Result<(U256, u64), ErrReport> {
+        if !self.get_tokens().contains(token_address_from) || !self.get_tokens().contains(token_address_to) {
+            return Err(eyre!("GMX_TOKEN_NOT_FOUND"));
+        }
+
+        let prices = self.oracle_prices(state_db, &env)?;
+
+        let mut env = env;
+        env.tx.gas_limit = 3_000_000;
+
+        let call_data_vec = getSwapAmountOutCall {
+            dataStore: self.data_store,
+            market: self.market_props(),
+            prices,
+            tokenIn: *token_address_from,
+            amountIn: in_amount,
+            uiFeeReceiver: Address::ZERO,
+        }
+        .abi_encode();
+
+        let (value, gas_used) = evm_call(state_db, env, self.reader, call_data_vec)?;
+        let ret = getSwapAmountOutCall::abi_decode_returns(&value, false)?.amountOut;
+
+        if ret.is_zero() {
+            Err(eyre!("ZERO_OUT_AMOUNT"))
+        } else {
+            Ok((ret, gas_used))
+        }
+    }
+
+    fn calculate_in_amount(
+        &self,
+        _state_db: &dyn DatabaseRef<Error = ErrReport>,
+        _env: Env,
+        _token_address_from: &Address,
+        _token_address_to: &Address,
+        _out_amount: U256,
+    ) -> Result<(U256, u64), ErrReport> {
+        Err(eyre!("NOT_IMPLEMENTED"))
+    }
+
+    fn can_flash_swap(&self) -> bool {
+        false
+    }
+
+    fn can_calculate_in_amount(&self) -> bool {
+        false
+    }
+
+    fn get_abi_encoder(&self) -> Option<&dyn PoolAbiEncoder> {
+        None
+    }
+
+    fn get_read_only_cell_vec(&self) -> Vec<U256> {
+        Vec::new()
+    }
+
+    fn get_state_required(&self) -> Result<RequiredState> {
+        let mut state_required = RequiredState::new();
+
+        // price feeds are required to check the keeper price freshness
+        for price_feed in self.price_feeds.iter() {
+            state_required.add_call(price_feed.feed, latestRoundDataCall {}.abi_encode());
+        }
+
+        state_required.add_call(self.reader, getMarketCall { dataStore: self.data_store, key: self.address }.abi_encode());
+
+        if let Some(prices) = &self.prices {
+            for (token_in, amount_in) in [(self.long_token, self.liquidity_long), (self.short_token, self.liquidity_short)] {
+                state_required.add_call(
+                    self.reader,
+                    getSwapAmountOutCall {
+                        dataStore: self.data_store,
+                        market: self.market_props(),
+                        prices: prices.clone(),
+                        tokenIn: token_in,
+                        amountIn: amount_in / U256::from(100),
+                        uiFeeReceiver: Address::ZERO,
+                    }
+                    .abi_encode(),
+                );
+            }
+        }
+
+        for token_address in self.get_tokens() {
+            state_required.add_call(token_address, IERC20::balanceOfCall { account: self.address }.abi_encode());
+        }
+
+        Ok(state_required)
+    }
+
+    fn is_native(&self) -> bool {
+        false
+    }
+
+    fn preswap_requirement(&self) -> PreswapRequirement {
+        PreswapRequirement::Base
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_price_feed_price() {
+        // ETH/USD feed with 8 decimals for WETH with 18 decimals, multiplier 10^(60 - 18 - 8)
+        let price_feed = GmxPriceFeed {
+            token: Address::repeat_byte(1),
+            feed: Address::repeat_byte(2),
+            multiplier: U256::from(10).pow(U256::from(34)),
+            heartbeat: 60,
+        };
+        let answer = U256::from(3000_0000_0000u64);
+
+        // 3000 USD per 10^18 units with 30 decimals
+        let price = price_feed.price(answer, U256::from(1000), U256::from(1060)).unwrap();
+        assert_eq!(price, U256::from(3000) * U256::from(10).pow(U256::from(12)));
+
+        assert!(price_feed.price(answer, U256::from(1000), U256::from(1061)).is_err());
+        assert!(price_feed.price(U256::ZERO, U256::from(1000), U256::from(1000)).is_err());
+    }
+
+    #[test]
+    fn test_market_prices() {
+        let (long_token, short_token) = (Address::repeat_byte(1), Address::repeat_byte(2));
+        let pool = GmxV2Pool::new(Address::repeat_byte(3), long_token, short_token);
+        assert_eq!(pool.price_tokens(), vec![long_token, short_token]);
+
+        let prices = pool.market_prices(&[(long_token, U256::from(10)), (short_token, U256::from(1))]).unwrap();
+        assert_eq!(prices.indexTokenPrice, prices.longTokenPrice);
+        assert_eq!(prices.shortTokenPrice.max, U256::from(1));
+
+        assert!(pool.market_prices(&[(long_token, U256::from(10))]).is_err());
+    }
+
+    #[test]
+    fn test_no_swap_directions() {
+        let pool = GmxV2Pool::new(Address::repeat_byte(3), Address::repeat_byte(1), Address::repeat_byte(2));
+        assert!(pool.get_abi_encoder().is_none());
+        assert!(pool.get_swap_directions().is_empty());
+    }
+}
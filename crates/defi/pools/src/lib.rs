@@ -1,22 +1,28 @@
 extern crate core;
 
 pub use curvepool::{CurvePool, CurvePoolAbiEncoder};
+pub use gmxv2pool::{GmxPriceFeed, GmxV2Pool};
 pub use loaders::*;
 pub use loom_types_entities::pool_config::PoolsLoadingConfig;
 pub use maverickpool::MaverickPool;
 pub use pancakev3pool::PancakeV3Pool;
-pub use uniswapv2pool::UniswapV2Pool;
+pub use pendlepool::{PendleMarketMath, PendleV2Pool};
+pub use uniswapv2pool::{SolidlyFeeModule, UniswapV2Pool};
 pub use uniswapv3pool::{Slot0, UniswapV3Pool};
+pub use woofiv2pool::{woofi_out_amount, WooFiBaseState, WooFiV2Pool};
 
 pub mod db_reader;
+mod gmxv2pool;
 mod maverickpool;
 pub mod state_readers;
 mod uniswapv2pool;
 mod uniswapv3pool;
+mod woofiv2pool;
 
 mod curvepool;
 pub mod protocols;
 
 mod loaders;
 mod pancakev3pool;
+mod pendlepool;
 mod virtual_impl;
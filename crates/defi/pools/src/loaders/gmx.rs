@@ -0,0 +1,65 @@
+use crate::{pool_loader, GmxV2Pool};
+use alloy::primitives::Bytes;
+use alloy::providers::network::Ethereum;
+use eyre::{eyre, ErrReport, Result};
+use loom_types_blockchain::{LoomDataTypes, LoomDataTypesEthereum};
+use loom_types_entities::{PoolClass, PoolId, PoolLoader, PoolWrapper};
+use revm::primitives::Env;
+use revm::DatabaseRef;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use tokio_stream::Stream;
+
+pool_loader!(GmxV2PoolLoader);
+
+impl<P> PoolLoader<P, Ethereum, LoomDataTypesEthereum> for GmxV2PoolLoader<P, Ethereum, LoomDataTypesEthereum>
+where
+    P: Provider<Ethereum> + Clone + 'static,
+{
+    fn get_pool_class_by_log(
+        &self,
+        _log_entry: &<LoomDataTypesEthereum as LoomDataTypes>::Log,
+    ) -> Option<(PoolId<LoomDataTypesEthereum>, PoolClass)> {
+        // GMX events are emitted by the EventEmitter, not by the market
+        None
+    }
+
+    fn fetch_pool_by_id<'a>(
+        &'a self,
+        pool_id: PoolId<LoomDataTypesEthereum>,
+    ) -> Pin<Box<dyn Future<Output = Result<PoolWrapper<LoomDataTypesEthereum>>> + Send + 'a>> {
+        Box::pin(async move {
+            if let Some(provider) = self.provider.clone() {
+                self.fetch_pool_by_id_from_provider(pool_id, provider).await
+            } else {
+                Err(eyre!("NO_PROVIDER"))
+            }
+        })
+    }
+
+    fn fetch_pool_by_id_from_provider(
+        &self,
+        pool_id: PoolId<LoomDataTypesEthereum>,
+        provider: P,
+    ) -> Pin<Box<dyn Future<Output = Result<PoolWrapper<LoomDataTypesEthereum>>> + Send>> {
+        Box::pin(async move { Ok(PoolWrapper::new(Arc::new(GmxV2Pool::fetch_pool_data(provider.clone(), pool_id.address()?).await?))) })
+    }
+
+    fn fetch_pool_by_id_from_evm(
+        &self,
+        _pool_id: PoolId<LoomDataTypesEthereum>,
+        _db: &dyn DatabaseRef<Error = ErrReport>,
+        _env: Env,
+    ) -> Result<PoolWrapper<LoomDataTypesEthereum>> {
+        Err(eyre!("NOT_IMPLEMENTED"))
+    }
+
+    fn is_code(&self, _code: &Bytes) -> bool {
+        false
+    }
+
+    fn protocol_loader(&self) -> Result<Pin<Box<dyn Stream<Item = (PoolId, PoolClass)> + Send>>> {
+        Err(eyre!("NOT_IMPLEMENTED"))
+    }
+}
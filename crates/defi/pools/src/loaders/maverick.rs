@@ -1,10 +1,11 @@
 use crate::{pool_loader, MaverickPool};
-use alloy::primitives::Bytes;
 use alloy::primitives::Log as EVMLog;
+use alloy::primitives::{Address, Bytes};
 use alloy::providers::network::Ethereum;
 use alloy::sol_types::SolEventInterface;
 use eyre::{eyre, ErrReport, Result};
 use loom_defi_abi::maverick::IMaverickPool::IMaverickPoolEvents;
+use loom_defi_address_book::FactoryAddress;
 use loom_types_blockchain::{LoomDataTypes, LoomDataTypesEthereum};
 use loom_types_entities::{PoolClass, PoolId, PoolLoader, PoolWrapper};
 use revm::primitives::Env;
@@ -76,4 +77,8 @@ where
     fn protocol_loader(&self) -> Result<Pin<Box<dyn Stream<Item = (PoolId, PoolClass)> + Send>>> {
         Err(eyre!("NOT_IMPLEMENTED"))
     }
+
+    fn factories(&self) -> Vec<Address> {
+        vec![FactoryAddress::MAVERICK]
+    }
 }
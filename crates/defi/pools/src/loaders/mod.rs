@@ -1,17 +1,23 @@
 mod curve;
+mod gmx;
 mod maverick;
+mod pendle;
 mod uniswap2;
 mod uniswap3;
+mod woofi;
 
 use crate::loaders::curve::CurvePoolLoader;
 use alloy::providers::network::Ethereum;
 use alloy::providers::{Network, Provider, RootProvider};
+pub use gmx::GmxV2PoolLoader;
 use loom_types_blockchain::{LoomDataTypes, LoomDataTypesEthereum};
 use loom_types_entities::pool_config::PoolsLoadingConfig;
 use loom_types_entities::{PoolClass, PoolLoader, PoolLoaders};
 pub use maverick::MaverickPoolLoader;
+pub use pendle::PendleV2PoolLoader;
 pub use uniswap2::UniswapV2PoolLoader;
 pub use uniswap3::UniswapV3PoolLoader;
+pub use woofi::WooFiV2PoolLoader;
 
 /// creates  pool loader and imports necessary crates
 #[macro_export]
@@ -93,6 +99,10 @@ where
         Self { inner: self.inner.add_loader(pool_class, pool_loader) }
     }
 
+    pub fn add_factory(self, factory_address: LDT::Address, pool_class: PoolClass) -> Self {
+        Self { inner: self.inner.with_factory(factory_address, pool_class) }
+    }
+
     pub fn build(self) -> PoolLoaders<P, N, LDT> {
         self.inner
     }
@@ -117,6 +127,7 @@ where
     where
         P: Provider<Ethereum> + Clone,
     {
+        let woofi_loader = WooFiV2PoolLoader::with_provider(provider.clone()).with_base_tokens(config.woofi_base_tokens().cloned());
         let pool_loader = PoolLoadersBuilder::<P>::new()
             .with_provider(provider.clone())
             .with_config(config)
@@ -124,8 +135,98 @@ where
             .add_loader(PoolClass::UniswapV2, UniswapV2PoolLoader::with_provider(provider.clone()))
             .add_loader(PoolClass::UniswapV3, UniswapV3PoolLoader::with_provider(provider.clone()))
             .add_loader(PoolClass::Curve, CurvePoolLoader::with_provider(provider.clone()))
+            .add_loader(PoolClass::GmxV2, GmxV2PoolLoader::with_provider(provider.clone()))
+            .add_loader(PoolClass::PendleV2, PendleV2PoolLoader::with_provider(provider.clone()))
+            .add_loader(PoolClass::WooFiV2, woofi_loader)
             .build();
 
         pool_loader
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloy::primitives::Address;
+    use alloy::providers::ProviderBuilder;
+    use loom_defi_address_book::FactoryAddress;
+    use loom_types_entities::PoolId;
+    use std::collections::HashSet;
+    use std::sync::Arc;
+    use url::Url;
+
+    fn provider() -> RootProvider<Ethereum> {
+        // never called, loaders only keep the provider
+        ProviderBuilder::new().disable_recommended_fillers().on_http(Url::parse("http://localhost:8545").unwrap())
+    }
+
+    #[test]
+    fn test_default_pool_loaders() {
+        let pool_loaders = PoolLoadersBuilder::<RootProvider<Ethereum>>::default_pool_loaders(provider(), PoolsLoadingConfig::default());
+
+        let pool_classes: HashSet<PoolClass> = pool_loaders.pool_classes().into_iter().collect();
+        assert_eq!(
+            pool_classes,
+            HashSet::from([
+                PoolClass::Curve,
+                PoolClass::GmxV2,
+                PoolClass::Maverick,
+                PoolClass::PendleV2,
+                PoolClass::UniswapV2,
+                PoolClass::UniswapV3,
+                PoolClass::WooFiV2,
+            ])
+        );
+
+        // factories declared by a loader resolve to the class it is registered for
+        for (factory_address, pool_class) in [
+            (FactoryAddress::UNISWAP_V2, PoolClass::UniswapV2),
+            (FactoryAddress::SUSHISWAP_V2, PoolClass::UniswapV2),
+            (FactoryAddress::UNISWAP_V3, PoolClass::UniswapV3),
+            (FactoryAddress::PANCAKE_V3, PoolClass::UniswapV3),
+            (FactoryAddress::MAVERICK, PoolClass::Maverick),
+        ] {
+            assert_eq!(pool_loaders.pool_class_by_factory(&factory_address), Some(pool_class));
+            assert!(pool_loaders.get_loader(&pool_class).unwrap().factories().contains(&factory_address));
+        }
+        for pool_class in pool_loaders.pool_classes() {
+            for factory_address in pool_loaders.get_loader(&pool_class).unwrap().factories() {
+                assert_eq!(pool_loaders.pool_class_by_factory(&factory_address), Some(pool_class));
+            }
+        }
+        assert_eq!(pool_loaders.pool_class_by_factory(&Address::repeat_byte(1)), None);
+    }
+
+    #[test]
+    fn test_register_loader() {
+        let uniswap2: Arc<dyn PoolLoader<RootProvider<Ethereum>, Ethereum>> =
+            Arc::new(UniswapV2PoolLoader::<_, Ethereum, LoomDataTypesEthereum>::with_provider(provider()));
+        let uniswap3: Arc<dyn PoolLoader<RootProvider<Ethereum>, Ethereum>> =
+            Arc::new(UniswapV3PoolLoader::<_, Ethereum, LoomDataTypesEthereum>::with_provider(provider()));
+        let mut pool_loaders = PoolLoadersBuilder::<RootProvider<Ethereum>>::new()
+            .with_provider(provider())
+            .add_factory(Address::repeat_byte(1), PoolClass::UniswapV2)
+            .build();
+        pool_loaders.register_loader(PoolClass::UniswapV2, uniswap2.clone());
+        pool_loaders.register_loader(PoolClass::UniswapV3, uniswap3.clone());
+
+        assert!(Arc::ptr_eq(&pool_loaders.get_loader(&PoolClass::UniswapV2).unwrap(), &uniswap2));
+        assert!(Arc::ptr_eq(&pool_loaders.get_loader(&PoolClass::UniswapV3).unwrap(), &uniswap3));
+        assert_eq!(pool_loaders.pool_class_by_factory(&Address::repeat_byte(1)), Some(PoolClass::UniswapV2));
+        assert_eq!(pool_loaders.pool_class_by_factory(&FactoryAddress::UNISWAP_V3), Some(PoolClass::UniswapV3));
+
+        // registering again replaces the loader of the class
+        pool_loaders.register_loader(PoolClass::UniswapV2, uniswap3.clone());
+        assert!(Arc::ptr_eq(&pool_loaders.get_loader(&PoolClass::UniswapV2).unwrap(), &uniswap3));
+    }
+
+    #[tokio::test]
+    async fn test_unknown_pool_class() {
+        let pool_loaders = PoolLoadersBuilder::<RootProvider<Ethereum>>::default_pool_loaders(provider(), PoolsLoadingConfig::default());
+
+        assert!(pool_loaders.get_loader(&PoolClass::UniswapV4).is_none());
+        let err =
+            pool_loaders.load_pool_without_provider(PoolId::Address(Address::repeat_byte(1)), &PoolClass::UniswapV4).await.unwrap_err();
+        assert_eq!(err.to_string(), "POOL_CLASS_NOT_FOUND");
+    }
+}
@@ -1,12 +1,13 @@
 use crate::protocols::{fetch_uni2_factory, UniswapV2Protocol};
 use crate::{pool_loader, UniswapV2Pool};
-use alloy::primitives::Bytes;
 use alloy::primitives::Log as EVMLog;
+use alloy::primitives::{Address, Bytes};
 use alloy::providers::network::Ethereum;
 use alloy::sol_types::SolEventInterface;
 use eyre::{eyre, ErrReport};
 use futures::Stream;
 use loom_defi_abi::uniswap2::IUniswapV2Pair::IUniswapV2PairEvents;
+use loom_defi_address_book::FactoryAddress;
 use loom_types_blockchain::{LoomDataTypes, LoomDataTypesEthereum};
 use loom_types_entities::{get_protocol_by_factory, PoolClass, PoolId, PoolLoader, PoolProtocol, PoolWrapper};
 use revm::primitives::Env;
@@ -89,4 +90,55 @@ where
     fn protocol_loader(&self) -> eyre::Result<Pin<Box<dyn Stream<Item = (PoolId, PoolClass)> + Send>>> {
         Err(eyre!("NOT_IMPLEMENTED"))
     }
+
+    fn factories(&self) -> Vec<Address> {
+        vec![
+            FactoryAddress::UNISWAP_V2,
+            FactoryAddress::SUSHISWAP_V2,
+            FactoryAddress::NOMISWAP,
+            FactoryAddress::DOOARSWAP,
+            FactoryAddress::SAFESWAP,
+            FactoryAddress::MINISWAP,
+            FactoryAddress::SHIBASWAP,
+            FactoryAddress::OG_PEPE,
+            FactoryAddress::ANTFARM,
+            FactoryAddress::INTEGRAL,
+            FactoryAddress::RAMSES,
+            FactoryAddress::THENA,
+            FactoryAddress::EQUALIZER,
+        ]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloy::providers::{ProviderBuilder, RootProvider};
+    use alloy_rpc_client::RpcClient;
+    use loom_defi_address_book::{TokenAddressEth, UniswapV2PoolAddress};
+    use loom_node_debug_provider::ReplayTransport;
+    use loom_types_entities::Pool;
+    use std::path::PathBuf;
+
+    fn replay_provider(fixture: &str) -> eyre::Result<RootProvider<Ethereum>> {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("fixtures").join(fixture);
+        let client = RpcClient::new(ReplayTransport::from_file(path)?, true);
+        Ok(ProviderBuilder::new().disable_recommended_fillers().on_client(client))
+    }
+
+    #[tokio::test]
+    async fn test_fetch_pool_replayed() -> eyre::Result<()> {
+        let provider = replay_provider("uniswap2_usdc_weth.jsonl")?;
+        let loader = UniswapV2PoolLoader::<_, Ethereum, LoomDataTypesEthereum>::with_provider(provider);
+
+        let pool = loader.fetch_pool_by_id(PoolId::Address(UniswapV2PoolAddress::USDC_WETH)).await?;
+        assert_eq!(pool.get_address(), UniswapV2PoolAddress::USDC_WETH);
+        assert_eq!(pool.get_protocol(), PoolProtocol::UniswapV2);
+        assert_eq!(pool.get_tokens(), vec![TokenAddressEth::USDC, TokenAddressEth::WETH]);
+
+        // responses of other pools are not recorded
+        assert!(loader.fetch_pool_by_id(PoolId::Address(UniswapV2PoolAddress::WETH_USDT)).await.is_err());
+
+        Ok(())
+    }
 }
@@ -1,12 +1,13 @@
 use crate::protocols::{fetch_uni3_factory, UniswapV3Protocol};
 use crate::{pool_loader, MaverickPool, PancakeV3Pool, UniswapV3Pool};
-use alloy::primitives::Bytes;
 use alloy::primitives::Log as EVMLog;
+use alloy::primitives::{Address, Bytes};
 use alloy::providers::network::Ethereum;
 use alloy::sol_types::SolEventInterface;
 use eyre::{eyre, ErrReport};
 use futures::Stream;
 use loom_defi_abi::uniswap3::IUniswapV3Pool::IUniswapV3PoolEvents;
+use loom_defi_address_book::FactoryAddress;
 use loom_types_blockchain::{LoomDataTypes, LoomDataTypesEthereum};
 use loom_types_entities::{get_protocol_by_factory, PoolClass, PoolId, PoolLoader, PoolProtocol, PoolWrapper};
 use revm::primitives::Env;
@@ -98,4 +99,8 @@ where
     fn protocol_loader(&self) -> eyre::Result<Pin<Box<dyn Stream<Item = (PoolId, PoolClass)> + Send>>> {
         Err(eyre!("NOT_IMPLEMENTED"))
     }
+
+    fn factories(&self) -> Vec<Address> {
+        vec![FactoryAddress::UNISWAP_V3, FactoryAddress::SUSHISWAP_V3, FactoryAddress::PANCAKE_V3]
+    }
 }
@@ -0,0 +1,137 @@
+use crate::WooFiV2Pool;
+use alloy::primitives::Log as EVMLog;
+use alloy::primitives::{Address, Bytes};
+use alloy::providers::network::Ethereum;
+use alloy::providers::{Network, Provider};
+use alloy::sol_types::SolEventInterface;
+use eyre::{eyre, ErrReport, Result};
+use loom_defi_abi::woofi::IWooPPV2::IWooPPV2Events;
+use loom_defi_address_book::WooFiV2Address;
+use loom_types_blockchain::{LoomDataTypes, LoomDataTypesEthereum};
+use loom_types_entities::{PoolClass, PoolId, PoolLoader, PoolWrapper};
+use revm::primitives::Env;
+use revm::DatabaseRef;
+use std::future::Future;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::sync::Arc;
+use tokio_stream::Stream;
+
+/// Loads WooPPV2 pools with the configured base tokens, the address book tokens of the chain if not set
+#[derive(Clone)]
+pub struct WooFiV2PoolLoader<P, N, LDT = LoomDataTypesEthereum>
+where
+    N: Network,
+    P: Provider<N> + Clone,
+    LDT: LoomDataTypes,
+{
+    provider: Option<P>,
+    base_tokens: Option<Vec<Address>>,
+    phantom_data: PhantomData<(P, N, LDT)>,
+}
+
+impl<P, N, LDT> WooFiV2PoolLoader<P, N, LDT>
+where
+    N: Network,
+    P: Provider<N> + Clone,
+    LDT: LoomDataTypes,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_provider(provider: P) -> Self {
+        Self { provider: Some(provider), ..Self::default() }
+    }
+
+    pub fn with_base_tokens(self, base_tokens: Option<Vec<Address>>) -> Self {
+        Self { base_tokens, ..self }
+    }
+
+    /// Base tokens of the address book for the chain
+    fn chain_base_tokens(chain_id: u64) -> Result<Vec<Address>> {
+        match chain_id {
+            42161 => Ok(WooFiV2Address::BASE_TOKENS_ARBITRUM.to_vec()),
+            56 => Ok(WooFiV2Address::BASE_TOKENS_BSC.to_vec()),
+            8453 => Ok(WooFiV2Address::BASE_TOKENS_BASE.to_vec()),
+            _ => Err(eyre!("WOOFI_NO_BASE_TOKENS_FOR_CHAIN : {chain_id}")),
+        }
+    }
+}
+
+impl<P, N, LDT> Default for WooFiV2PoolLoader<P, N, LDT>
+where
+    N: Network,
+    P: Provider<N> + Clone,
+    LDT: LoomDataTypes,
+{
+    fn default() -> Self {
+        Self { provider: None, base_tokens: None, phantom_data: PhantomData }
+    }
+}
+
+impl<P> PoolLoader<P, Ethereum, LoomDataTypesEthereum> for WooFiV2PoolLoader<P, Ethereum, LoomDataTypesEthereum>
+where
+    P: Provider<Ethereum> + Clone + 'static,
+{
+    fn get_pool_class_by_log(
+        &self,
+        log_entry: &<LoomDataTypesEthereum as LoomDataTypes>::Log,
+    ) -> Option<(PoolId<LoomDataTypesEthereum>, PoolClass)> {
+        let log_entry: Option<EVMLog> = EVMLog::new(log_entry.address(), log_entry.topics().to_vec(), log_entry.data().data.clone());
+        match log_entry {
+            Some(log_entry) => match IWooPPV2Events::decode_log(&log_entry, false) {
+                Ok(event) => match event.data {
+                    IWooPPV2Events::WooSwap(_) => Some((PoolId::Address(log_entry.address), PoolClass::WooFiV2)),
+                },
+                Err(_) => None,
+            },
+            None => None,
+        }
+    }
+
+    fn fetch_pool_by_id<'a>(
+        &'a self,
+        pool_id: PoolId<LoomDataTypesEthereum>,
+    ) -> Pin<Box<dyn Future<Output = Result<PoolWrapper<LoomDataTypesEthereum>>> + Send + 'a>> {
+        Box::pin(async move {
+            if let Some(provider) = self.provider.clone() {
+                self.fetch_pool_by_id_from_provider(pool_id, provider).await
+            } else {
+                Err(eyre!("NO_PROVIDER"))
+            }
+        })
+    }
+
+    fn fetch_pool_by_id_from_provider(
+        &self,
+        pool_id: PoolId<LoomDataTypesEthereum>,
+        provider: P,
+    ) -> Pin<Box<dyn Future<Output = Result<PoolWrapper<LoomDataTypesEthereum>>> + Send>> {
+        let base_tokens = self.base_tokens.clone();
+        Box::pin(async move {
+            let candidate_tokens = match base_tokens {
+                Some(base_tokens) => base_tokens,
+                None => Self::chain_base_tokens(provider.get_chain_id().await?)?,
+            };
+            Ok(PoolWrapper::new(Arc::new(WooFiV2Pool::fetch_pool_data(provider.clone(), pool_id.address()?, &candidate_tokens).await?)))
+        })
+    }
+
+    fn fetch_pool_by_id_from_evm(
+        &self,
+        _pool_id: PoolId<LoomDataTypesEthereum>,
+        _db: &dyn DatabaseRef<Error = ErrReport>,
+        _env: Env,
+    ) -> Result<PoolWrapper<LoomDataTypesEthereum>> {
+        Err(eyre!("NOT_IMPLEMENTED"))
+    }
+
+    fn is_code(&self, _code: &Bytes) -> bool {
+        false
+    }
+
+    fn protocol_loader(&self) -> Result<Pin<Box<dyn Stream<Item = (PoolId, PoolClass)> + Send>>> {
+        Err(eyre!("NOT_IMPLEMENTED"))
+    }
+}
@@ -156,6 +156,10 @@ impl Pool for MaverickPool {
         self.protocol
     }
 
+    fn get_factory(&self) -> Option<Address> {
+        (!self.factory.is_zero()).then_some(self.factory)
+    }
+
     fn get_address(&self) -> Address {
         self.address
     }
@@ -194,6 +194,10 @@ impl Pool for PancakeV3Pool {
         self.protocol
     }
 
+    fn get_factory(&self) -> Option<Address> {
+        (!self.factory.is_zero()).then_some(self.factory)
+    }
+
     fn get_address(&self) -> Address {
         self.address
     }
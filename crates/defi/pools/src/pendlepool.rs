@@ -0,0 +1,593 @@
+use alloy::primitives::{Address, Bytes, I256, U256};
+use alloy::providers::{Network, Provider};
+use alloy::sol_types::SolCall;
+use eyre::{eyre, ErrReport, Result};
+use loom_defi_abi::pendle::IPendleMarket::readStateCall;
+use loom_defi_abi::pendle::IPendleRouter::{swapExactPtForTokenCall, swapExactTokenForPtCall};
+use loom_defi_abi::pendle::IPendleYieldToken::pyIndexStoredCall;
+use loom_defi_abi::pendle::IStandardizedYield::exchangeRateCall;
+use loom_defi_abi::pendle::{
+    ApproxParams, IPendleMarket, IStandardizedYield, LimitOrderData, MarketState, SwapData, TokenInput, TokenOutput,
+};
+use loom_defi_abi::IERC20;
+use loom_defi_address_book::PeripheryAddress;
+use loom_evm_utils::evm::evm_call;
+use loom_types_entities::required_state::RequiredState;
+use loom_types_entities::{
+    hop_amount_out_bounds, Pool, PoolAbiEncoder, PoolClass, PoolId, PoolProtocol, PreswapRequirement, SwapDirection,
+};
+use revm::primitives::Env;
+use revm::DatabaseRef;
+use std::any::Any;
+
+const ONE: i128 = 1_000_000_000_000_000_000;
+const LN_2: i128 = 693_147_180_559_945_309;
+const IMPLIED_RATE_TIME: i128 = 365 * 86400;
+const MAX_MARKET_PROPORTION: i128 = 960_000_000_000_000_000;
+
+// the Pendle router V4 is deployed at the address book address on these chains
+const PENDLE_CHAIN_IDS: [u64; 3] = [1, 56, 42161];
+
+fn fixed(value: i128) -> I256 {
+    I256::unchecked_from(value)
+}
+
+fn mul_down(a: I256, b: I256) -> Result<I256> {
+    a.checked_mul(b).map(|value| value / fixed(ONE)).ok_or_else(|| eyre!("PENDLE_MATH_OVERFLOW"))
+}
+
+fn div_down(a: I256, b: I256) -> Result<I256> {
+    if b.is_zero() {
+        return Err(eyre!("PENDLE_DIVISION_BY_ZERO"));
+    }
+    a.checked_mul(fixed(ONE)).map(|value| value / b).ok_or_else(|| eyre!("PENDLE_MATH_OVERFLOW"))
+}
+
+/// Natural logarithm of the 18 decimals fixed point `x`
+fn ln(x: I256) -> Result<I256> {
+    if !x.is_positive() {
+        return Err(eyre!("PENDLE_LN_OUT_OF_RANGE"));
+    }
+
+    // x = y * 2^k with y in [1, 2)
+    let (mut y, mut k) = (x, 0i128);
+    while y >= fixed(2 * ONE) {
+        y /= fixed(2);
+        k += 1;
+    }
+    while y < fixed(ONE) {
+        y *= fixed(2);
+        k -= 1;
+    }
+
+    // ln(y) = 2 * atanh(z) with z = (y - 1) / (y + 1) <= 1/3
+    let z = div_down(y - fixed(ONE), y + fixed(ONE))?;
+    let z_squared = mul_down(z, z)?;
+    let (mut sum, mut term, mut n) = (z, z, 1i128);
+    loop {
+        term = mul_down(term, z_squared)?;
+        n += 2;
+        if (term / fixed(n)).is_zero() {
+            break;
+        }
+        sum += term / fixed(n);
+    }
+
+    Ok(sum * fixed(2) + fixed(k * LN_2))
+}
+
+/// Exponent of the 18 decimals fixed point `x`
+fn exp(x: I256) -> Result<I256> {
+    // x = k * ln(2) + r with |r| <= ln(2) / 2
+    let k = (x + fixed(if x.is_negative() { -LN_2 / 2 } else { LN_2 / 2 })) / fixed(LN_2);
+    let k = i128::try_from(k).map_err(|_| eyre!("PENDLE_EXP_OUT_OF_RANGE"))?;
+    if k > 120 {
+        return Err(eyre!("PENDLE_EXP_OUT_OF_RANGE"));
+    }
+    if k < -120 {
+        return Ok(I256::ZERO);
+    }
+    let r = x - fixed(k * LN_2);
+
+    let (mut sum, mut term, mut n) = (fixed(ONE), fixed(ONE), 0i128);
+    loop {
+        n += 1;
+        term = mul_down(term, r)? / fixed(n);
+        if term.is_zero() {
+            break;
+        }
+        sum += term;
+    }
+
+    Ok(if k >= 0 { sum * fixed(1 << k) } else { sum / fixed(1 << -k) })
+}
+
+/// Port of Pendle `MarketMathCore` swap math with 18 decimals fixed point, amounts are in SY units.
+///
+/// The PT price in asset terms decays towards 1 as the market approaches the expiry.
+#[derive(Clone, Debug)]
+pub struct PendleMarketMath {
+    total_pt: I256,
+    total_asset: I256,
+    // max(SY.exchangeRate, YT.pyIndexStored), asset per SY
+    index: I256,
+    rate_scalar: I256,
+    rate_anchor: I256,
+    fee_rate: I256,
+}
+
+impl PendleMarketMath {
+    pub fn new(state: &MarketState, index: U256, timestamp: u64) -> Result<Self> {
+        let expiry: u64 = state.expiry.saturating_to();
+        if timestamp >= expiry {
+            return Err(eyre!("PENDLE_MARKET_EXPIRED"));
+        }
+        let time_to_expiry = fixed((expiry - timestamp) as i128);
+
+        let index = I256::try_from(index)?;
+        let (total_pt, total_sy) = (state.totalPt, state.totalSy);
+        if !total_pt.is_positive() || !total_sy.is_positive() || !index.is_positive() {
+            return Err(eyre!("PENDLE_EMPTY_MARKET"));
+        }
+
+        let rate_scalar = state.scalarRoot * fixed(IMPLIED_RATE_TIME) / time_to_expiry;
+        if !rate_scalar.is_positive() {
+            return Err(eyre!("PENDLE_RATE_SCALAR_NOT_POSITIVE"));
+        }
+        let total_asset = mul_down(total_sy, index)?;
+
+        // rate anchor keeps the implied rate of the last trade for the current proportion
+        let last_ln_implied_rate = I256::try_from(state.lastLnImpliedRate)?;
+        let last_exchange_rate = exp(last_ln_implied_rate * time_to_expiry / fixed(IMPLIED_RATE_TIME))?;
+        let proportion = div_down(total_pt, total_pt + total_asset)?;
+        let rate_anchor = last_exchange_rate - div_down(ln(div_down(proportion, fixed(ONE) - proportion)?)?, rate_scalar)?;
+
+        let ln_fee_rate_root = I256::try_from(state.lnFeeRateRoot)?;
+        let fee_rate = exp(ln_fee_rate_root * time_to_expiry / fixed(IMPLIED_RATE_TIME))?;
+
+        Ok(Self { total_pt, total_asset, index, rate_scalar, rate_anchor, fee_rate })
+    }
+
+    /// SY received by the account for `net_pt_to_account` PT sent to the account, negative if SY is paid
+    pub fn sy_to_account(&self, net_pt_to_account: I256) -> Result<I256> {
+        let proportion = div_down(self.total_pt - net_pt_to_account, self.total_pt + self.total_asset)?;
+        if !proportion.is_positive() || proportion > fixed(MAX_MARKET_PROPORTION) {
+            return Err(eyre!("PENDLE_PROPORTION_OUT_OF_RANGE"));
+        }
+
+        let exchange_rate = div_down(ln(div_down(proportion, fixed(ONE) - proportion)?)?, self.rate_scalar)? + self.rate_anchor;
+        if exchange_rate < fixed(ONE) {
+            return Err(eyre!("PENDLE_EXCHANGE_RATE_BELOW_ONE"));
+        }
+
+        let pre_fee_asset_to_account = -div_down(net_pt_to_account, exchange_rate)?;
+        let fee = if net_pt_to_account.is_positive() {
+            if div_down(exchange_rate, self.fee_rate)? < fixed(ONE) {
+                return Err(eyre!("PENDLE_EXCHANGE_RATE_BELOW_ONE"));
+            }
+            mul_down(pre_fee_asset_to_account, fixed(ONE) - self.fee_rate)?
+        } else {
+            -div_down(mul_down(pre_fee_asset_to_account, fixed(ONE) - self.fee_rate)?, self.fee_rate)?
+        };
+
+        // SY paid by the account is rounded up
+        let net_asset_to_account = pre_fee_asset_to_account - fee;
+        if net_asset_to_account.is_negative() {
+            let asset = (-net_asset_to_account).checked_mul(fixed(ONE)).ok_or_else(|| eyre!("PENDLE_MATH_OVERFLOW"))?;
+            Ok(-((asset + self.index - fixed(1)) / self.index))
+        } else {
+            div_down(net_asset_to_account, self.index)
+        }
+    }
+
+    /// SY received for exact `pt_in`
+    pub fn swap_exact_pt_for_sy(&self, pt_in: U256) -> Result<U256> {
+        let sy_out = self.sy_to_account(-I256::try_from(pt_in)?)?;
+        Ok(if sy_out.is_negative() { U256::ZERO } else { sy_out.into_raw() })
+    }
+
+    /// PT received for exact `sy_in`, solved by bisection as the router does with `ApproxParams`
+    pub fn swap_exact_sy_for_pt(&self, sy_in: U256) -> Result<U256> {
+        let sy_in = I256::try_from(sy_in)?;
+        let (mut low, mut high) = (I256::ZERO, self.total_pt);
+        while high - low > fixed(1) {
+            let mid = (low + high) / fixed(2);
+            match self.sy_to_account(mid) {
+                Ok(sy_to_account) if -sy_to_account <= sy_in => low = mid,
+                _ => high = mid,
+            }
+        }
+        if low.is_zero() {
+            Err(eyre!("ZERO_OUT_AMOUNT"))
+        } else {
+            Ok(low.into_raw())
+        }
+    }
+}
+
+/// Pendle V2 market swapping PT to the SY yield token and back through the Pendle router.
+///
+/// SY wraps the yield token 1:1, the router mints and redeems SY within the swap.
+#[derive(Clone)]
+pub struct PendleV2Pool {
+    address: Address,
+    pt: Address,
+    sy: Address,
+    yt: Address,
+    yield_token: Address,
+    expiry: u64,
+    liquidity_pt: U256,
+    liquidity_sy: U256,
+    encoder: PendleAbiSwapEncoder,
+}
+
+impl PendleV2Pool {
+    pub fn new(address: Address, pt: Address, sy: Address, yt: Address, yield_token: Address) -> Self {
+        PendleV2Pool {
+            address,
+            pt,
+            sy,
+            yt,
+            yield_token,
+            expiry: 0,
+            liquidity_pt: U256::ZERO,
+            liquidity_sy: U256::ZERO,
+            encoder: PendleAbiSwapEncoder::new(address, pt, yield_token),
+        }
+    }
+
+    pub fn with_expiry(self, expiry: u64) -> Self {
+        Self { expiry, ..self }
+    }
+
+    pub fn get_expiry(&self) -> u64 {
+        self.expiry
+    }
+
+    pub fn get_sy(&self) -> Address {
+        self.sy
+    }
+
+    fn market_math(&self, state_db: &dyn DatabaseRef<Error = ErrReport>, env: &Env) -> Result<PendleMarketMath> {
+        let (value, _) =
+            evm_call(state_db, env.clone(), self.address, readStateCall { router: PeripheryAddress::PENDLE_ROUTER_V4 }.abi_encode())?;
+        let state = readStateCall::abi_decode_returns(&value, false)?.market;
+
+        let (value, _) = evm_call(state_db, env.clone(), self.sy, exchangeRateCall {}.abi_encode())?;
+        let exchange_rate = exchangeRateCall::abi_decode_returns(&value, false)?.res;
+
+        let (value, _) = evm_call(state_db, env.clone(), self.yt, pyIndexStoredCall {}.abi_encode())?;
+        let py_index = pyIndexStoredCall::abi_decode_returns(&value, false)?._0;
+
+        PendleMarketMath::new(&state, exchange_rate.max(py_index), env.block.timestamp.saturating_to())
+    }
+
+    pub async fn fetch_pool_data<N: Network, P: Provider<N> + Send + Sync + Clone + 'static>(client: P, address: Address) -> Result<Self> {
+        let chain_id = client.get_chain_id().await?;
+        if !PENDLE_CHAIN_IDS.contains(&chain_id) {
+            return Err(eyre!("PENDLE_UNSUPPORTED_CHAIN : {chain_id}"));
+        }
+
+        let market = IPendleMarket::IPendleMarketInstance::new(address, client.clone());
+
+        let tokens = market.readTokens().call().await?;
+        let expiry = market.expiry().call().await?._0;
+        let yield_token = IStandardizedYield::IStandardizedYieldInstance::new(tokens._SY, client.clone()).yieldToken().call().await?._0;
+
+        let mut pool = PendleV2Pool::new(address, tokens._PT, tokens._SY, tokens._YT, yield_token).with_expiry(expiry.saturating_to());
+
+        pool.liquidity_pt = IERC20::IERC20Instance::new(pool.pt, client.clone()).balanceOf(address).call().await?._0;
+        pool.liquidity_sy = IERC20::IERC20Instance::new(pool.sy, client.clone()).balanceOf(address).call().await?._0;
+
+        Ok(pool)
+    }
+}
+
+impl Pool for PendleV2Pool {
+    fn as_any<'a>(&self) -> &dyn Any {
+        self
+    }
+
+    fn get_class(&self) -> PoolClass {
+        PoolClass::PendleV2
+    }
+
+    fn get_protocol(&self) -> PoolProtocol {
+        PoolProtocol::PendleV2
+    }
+
+    fn get_address(&self) -> Address {
+        self.address
+    }
+
+    fn get_pool_id(&self) -> PoolId {
+        PoolId::Address(self.address)
+    }
+
+    fn get_fee(&self) -> U256 {
+        U256::ZERO
+    }
+
+    fn get_tokens(&self) -> Vec<Address> {
+        vec![self.pt, self.yield_token]
+    }
+
+    fn get_swap_directions(&self) -> Vec<SwapDirection> {
+        vec![(self.pt, self.yield_token).into(), (self.yield_token, self.pt).into()]
+    }
+
+    fn calculate_out_amount(
+        &self,
+        state_db: &dyn DatabaseRef<Error = ErrReport>,
+        env: Env,
+        token_address_from: &Address,
+        token_address_to: &Address,
+        in_amount: U256,
+    ) -> Result<(U256, u64), ErrReport> {
+        let math = self.market_math(state_db, &env)?;
+
+        let (ret, gas_used) = if *token_address_from == self.pt && *token_address_to == self.yield_token {
+            (math.swap_exact_pt_for_sy(in_amount)?, 250_000)
+        } else if *token_address_from == self.yield_token && *token_address_to == self.pt {
+            (math.swap_exact_sy_for_pt(in_amount)?, 400_000)
+        } else {
+            return Err(eyre!("PENDLE_TOKEN_NOT_FOUND"));
+        };
+
+        if ret.is_zero() {
+            Err(eyre!("ZERO_OUT_AMOUNT"))
+        } else {
+            Ok((ret, gas_used))
+        }
+    }
+
+    fn calculate_in_amount(
+        &self,
+        _state_db: &dyn DatabaseRef<Error = ErrReport>,
+        _env: Env,
+        _token_address_from: &Address,
+        _token_address_to: &Address,
+        _out_amount: U256,
+    ) -> Result<(U256, u64), ErrReport> {
+        Err(eyre!("NOT_IMPLEMENTED"))
+    }
+
+    fn can_flash_swap(&self) -> bool {
+        false
+    }
+
+    fn can_calculate_in_amount(&self) -> bool {
+        false
+    }
+
+    fn get_abi_encoder(&self) -> Option<&dyn PoolAbiEncoder> {
+        Some(&self.encoder)
+    }
+
+    fn get_read_only_cell_vec(&self) -> Vec<U256> {
+        Vec::new()
+    }
+
+    fn get_state_required(&self) -> Result<RequiredState> {
+        let mut state_required = RequiredState::new();
+
+        state_required
+            .add_call(self.address, readStateCall { router: PeripheryAddress::PENDLE_ROUTER_V4 }.abi_encode())
+            .add_call(self.sy, exchangeRateCall {}.abi_encode())
+            .add_call(self.yt, pyIndexStoredCall {}.abi_encode());
+
+        for token_address in [self.pt, self.sy] {
+            state_required.add_call(token_address, IERC20::balanceOfCall { account: self.address }.abi_encode());
+        }
+
+        Ok(state_required)
+    }
+
+    fn is_native(&self) -> bool {
+        false
+    }
+
+    fn preswap_requirement(&self) -> PreswapRequirement {
+        PreswapRequirement::Allowance
+    }
+}
+
+/// Encodes Pendle router swaps, the router has to be approved for the input token
+#[derive(Clone, Copy)]
+struct PendleAbiSwapEncoder {
+    market: Address,
+    pt: Address,
+    yield_token: Address,
+}
+
+impl PendleAbiSwapEncoder {
+    pub fn new(market: Address, pt: Address, yield_token: Address) -> Self {
+        Self { market, pt, yield_token }
+    }
+
+    fn empty_limit_order_data() -> LimitOrderData {
+        LimitOrderData {
+            limitRouter: Address::ZERO,
+            epsSkipMarket: U256::ZERO,
+            normalFills: vec![],
+            flashFills: vec![],
+            optData: Bytes::new(),
+        }
+    }
+
+    fn empty_swap_data() -> SwapData {
+        SwapData { swapType: 0, extRouter: Address::ZERO, extCalldata: Bytes::new(), needScale: false }
+    }
+}
+
+impl PoolAbiEncoder for PendleAbiSwapEncoder {
+    fn encode_swap_in_amount_provided(
+        &self,
+        token_from_address: Address,
+        token_to_address: Address,
+        amount: U256,
+        recipient: Address,
+        payload: Bytes,
+    ) -> Result<Bytes> {
+        // the payload is the calculated out amount of the swap
+        let quoted_amount_out =
+            U256::try_from_be_slice(&payload).filter(|amount| !amount.is_zero()).ok_or_else(|| eyre!("PENDLE_AMOUNT_OUT_NOT_QUOTED"))?;
+        let (min_amount_out, max_amount_out) = hop_amount_out_bounds(quoted_amount_out);
+
+        if token_from_address == self.pt && token_to_address == self.yield_token {
+            let swap_call = swapExactPtForTokenCall {
+                receiver: recipient,
+                market: self.market,
+                exactPtIn: amount,
+                output: TokenOutput {
+                    tokenOut: self.yield_token,
+                    minTokenOut: min_amount_out,
+                    tokenRedeemSy: self.yield_token,
+                    pendleSwap: Address::ZERO,
+                    swapData: Self::empty_swap_data(),
+                },
+                limit: Self::empty_limit_order_data(),
+            };
+            Ok(Bytes::from(swap_call.abi_encode()))
+        } else if token_from_address == self.yield_token && token_to_address == self.pt {
+            let swap_call = swapExactTokenForPtCall {
+                receiver: recipient,
+                market: self.market,
+                minPtOut: min_amount_out,
+                guessPtOut: ApproxParams {
+                    guessMin: min_amount_out,
+                    guessMax: max_amount_out,
+                    guessOffchain: quoted_amount_out,
+                    maxIteration: U256::from(30),
+                    eps: U256::from(1e14 as u64),
+                },
+                input: TokenInput {
+                    tokenIn: self.yield_token,
+                    netTokenIn: amount,
+                    tokenMintSy: self.yield_token,
+                    pendleSwap: Address::ZERO,
+                    swapData: Self::empty_swap_data(),
+                },
+                limit: Self::empty_limit_order_data(),
+            };
+            Ok(Bytes::from(swap_call.abi_encode()))
+        } else {
+            Err(eyre!("PENDLE_TOKEN_NOT_FOUND"))
+        }
+    }
+
+    fn swap_in_amount_offset(&self, token_from_address: Address, _token_to_address: Address) -> Option<u32> {
+        if token_from_address == self.pt {
+            Some(0x44)
+        } else {
+            // TokenInput.netTokenIn, the first word after the 10 head words
+            Some(0x164)
+        }
+    }
+
+    fn swap_out_amount_return_offset(&self, _token_from_address: Address, _token_to_address: Address) -> Option<u32> {
+        Some(0x00)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn e18(value: u128) -> U256 {
+        U256::from(value) * U256::from(10u128.pow(18))
+    }
+
+    fn market_state(ln_fee_rate_root: U256) -> MarketState {
+        MarketState {
+            totalPt: I256::from_raw(e18(1_000_000)),
+            totalSy: I256::from_raw(e18(1_000_000)),
+            totalLp: I256::ZERO,
+            treasury: Address::ZERO,
+            scalarRoot: I256::from_raw(e18(50)),
+            expiry: U256::from(IMPLIED_RATE_TIME as u64),
+            lnFeeRateRoot: ln_fee_rate_root,
+            reserveFeePercent: U256::from(80),
+            // ln(1.05)
+            lastLnImpliedRate: U256::from(48_790_164_169_432_003u128),
+        }
+    }
+
+    fn assert_close(value: I256, expected: I256, tolerance: i128) {
+        assert!((value - expected).abs() <= fixed(tolerance), "{value} != {expected}");
+    }
+
+    #[test]
+    fn test_ln_exp() {
+        assert_eq!(ln(fixed(ONE)).unwrap(), I256::ZERO);
+        assert_close(ln(fixed(2 * ONE)).unwrap(), fixed(LN_2), 10);
+        assert_close(ln(fixed(ONE / 4)).unwrap(), fixed(-2 * LN_2), 10);
+        assert!(ln(I256::ZERO).is_err());
+
+        assert_eq!(exp(I256::ZERO).unwrap(), fixed(ONE));
+        assert_close(exp(fixed(-ONE)).unwrap(), fixed(367_879_441_171_442_321), 10);
+        assert_close(exp(ln(fixed(5 * ONE)).unwrap()).unwrap(), fixed(5 * ONE), 100);
+    }
+
+    #[test]
+    fn test_spot_price_decays_to_expiry() {
+        // half a year to expiry, 5% implied rate and index of 1
+        let timestamp = (IMPLIED_RATE_TIME / 2) as u64;
+        let math = PendleMarketMath::new(&market_state(U256::ZERO), e18(1), timestamp).unwrap();
+
+        // pt_in / sqrt(1.05) with the price impact of the trade
+        let pt_in = U256::from(10u128.pow(15));
+        let sy_out = math.swap_exact_pt_for_sy(pt_in).unwrap();
+        assert_close(I256::from_raw(sy_out), fixed(975_900_072_948_533), 1_000_000_000);
+
+        let math = PendleMarketMath::new(&market_state(U256::ZERO), e18(1), IMPLIED_RATE_TIME as u64 - 3600).unwrap();
+        let sy_out_near_expiry = math.swap_exact_pt_for_sy(pt_in).unwrap();
+        assert!(sy_out_near_expiry > sy_out);
+        assert!(sy_out_near_expiry < pt_in);
+
+        assert!(PendleMarketMath::new(&market_state(U256::ZERO), e18(1), IMPLIED_RATE_TIME as u64).is_err());
+    }
+
+    #[test]
+    fn test_round_trip_pays_fee() {
+        let timestamp = (IMPLIED_RATE_TIME / 2) as u64;
+        let math = PendleMarketMath::new(&market_state(U256::from(10u128.pow(15))), e18(1), timestamp).unwrap();
+
+        let sy_in = e18(100);
+        let pt_out = math.swap_exact_sy_for_pt(sy_in).unwrap();
+        assert!(pt_out > sy_in);
+        let sy_paid = -math.sy_to_account(I256::from_raw(pt_out)).unwrap();
+        assert!(sy_paid <= I256::from_raw(sy_in));
+        assert_close(sy_paid, I256::from_raw(sy_in), 100_000_000_000);
+
+        let sy_out = math.swap_exact_pt_for_sy(pt_out).unwrap();
+        assert!(sy_out < sy_in);
+    }
+
+    #[test]
+    fn test_encoder_min_out() {
+        let (market, pt, yield_token) = (Address::repeat_byte(1), Address::repeat_byte(2), Address::repeat_byte(3));
+        let encoder = PendleAbiSwapEncoder::new(market, pt, yield_token);
+        let amount = U256::from(0x1234567890u64);
+        let quoted_amount_out = e18(1000);
+        let payload = Bytes::from(quoted_amount_out.to_be_bytes::<32>().to_vec());
+
+        for (from, to) in [(pt, yield_token), (yield_token, pt)] {
+            let call_data = encoder.encode_swap_in_amount_provided(from, to, amount, Address::ZERO, payload.clone()).unwrap();
+            let offset = encoder.swap_in_amount_offset(from, to).unwrap() as usize;
+            assert_eq!(U256::from_be_slice(&call_data[offset..offset + 0x20]), amount);
+            assert!(encoder.encode_swap_in_amount_provided(from, to, amount, Address::ZERO, Bytes::new()).is_err());
+        }
+
+        let call_data = encoder.encode_swap_in_amount_provided(pt, yield_token, amount, Address::ZERO, payload.clone()).unwrap();
+        let swap_call = swapExactPtForTokenCall::abi_decode(&call_data, true).unwrap();
+        assert_eq!(swap_call.output.minTokenOut, e18(995));
+
+        let call_data = encoder.encode_swap_in_amount_provided(yield_token, pt, amount, Address::ZERO, payload).unwrap();
+        let swap_call = swapExactTokenForPtCall::abi_decode(&call_data, true).unwrap();
+        assert_eq!(swap_call.minPtOut, e18(995));
+        assert_eq!(swap_call.guessPtOut.guessMin, e18(995));
+        assert_eq!(swap_call.guessPtOut.guessMax, e18(1005));
+        assert_eq!(swap_call.guessPtOut.guessOffchain, quoted_amount_out);
+    }
+}
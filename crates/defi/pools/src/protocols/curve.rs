@@ -4,8 +4,8 @@ use std::marker::PhantomData;
 use alloy::primitives::{address, Address, Bytes, U256};
 use alloy::providers::{Network, Provider};
 use alloy::rpc::types::{BlockId, BlockNumberOrTag};
-use alloy::sol_types::SolInterface;
-use eyre::{eyre, Report, Result};
+use alloy::sol_types::{SolCall, SolInterface};
+use eyre::{eyre, OptionExt, Report, Result};
 use tracing::{debug, error, trace};
 
 use loom_defi_abi::curve::ICurveAddressProvider::ICurveAddressProviderInstance;
@@ -17,6 +17,7 @@ use loom_defi_abi::curve::ICurveI128_2_To::{ICurveI128_2_ToCalls, ICurveI128_2_T
 use loom_defi_abi::curve::ICurveI128_2_To_Meta::ICurveI128_2_To_MetaInstance;
 use loom_defi_abi::curve::ICurveI128_3::{ICurveI128_3Calls, ICurveI128_3Instance};
 use loom_defi_abi::curve::ICurveI128_4::{ICurveI128_4Calls, ICurveI128_4Instance};
+use loom_defi_abi::curve::ICurveLiquidity::ICurveLiquidityInstance;
 use loom_defi_abi::curve::ICurveU256_2::{ICurveU256_2Calls, ICurveU256_2Instance};
 use loom_defi_abi::curve::ICurveU256_2_Eth_To::{ICurveU256_2_Eth_ToCalls, ICurveU256_2_Eth_ToInstance};
 use loom_defi_abi::curve::ICurveU256_2_To::{ICurveU256_2_ToCalls, ICurveU256_2_ToInstance};
@@ -24,8 +25,8 @@ use loom_defi_abi::curve::ICurveU256_3_Eth::{ICurveU256_3_EthCalls, ICurveU256_3
 use loom_defi_abi::curve::ICurveU256_3_Eth_To::{ICurveU256_3_Eth_ToCalls, ICurveU256_3_Eth_ToInstance};
 use loom_defi_abi::curve::ICurveU256_3_Eth_To2::{ICurveU256_3_Eth_To2Calls, ICurveU256_3_Eth_To2Instance};
 use loom_defi_abi::curve::{
-    ICurveI128_2, ICurveI128_2_To, ICurveI128_2_To_Meta, ICurveI128_3, ICurveI128_4, ICurveU256_2, ICurveU256_2_Eth_To, ICurveU256_2_To,
-    ICurveU256_3_Eth, ICurveU256_3_Eth_To, ICurveU256_3_Eth_To2,
+    ICurveI128_2, ICurveI128_2_To, ICurveI128_2_To_Meta, ICurveI128_3, ICurveI128_4, ICurveLiquidity, ICurveU256_2, ICurveU256_2_Eth_To,
+    ICurveU256_2_To, ICurveU256_3_Eth, ICurveU256_3_Eth_To, ICurveU256_3_Eth_To2,
 };
 
 #[derive(Clone, Debug)]
@@ -84,11 +85,26 @@ where
     N: Network,
     P: Provider<N> + Send + Sync + Clone + 'static,
 {
-    pub async fn lp_token(address: Address) -> Result<Address> {
+    /// LP token minted by the pool, exposed as `lp_token()` or `token()`. Pools being the LP token themselves, e.g. factory
+    /// pools, are not detected
+    pub async fn lp_token(client: P, address: Address) -> Result<Address> {
         if address == address!("bEbc44782C7dB0a1A60Cb6fe97d0b483032FF1C7") {
             return Ok(address!("6c3F90f043a72FA612cbac8115EE7e52BDe6E490"));
         }
-        Err(eyre!("NO_LP_TOKEN"))
+        let liquidity_contract = ICurveLiquidityInstance::new(address, client);
+        if let Some(lp_token) =
+            liquidity_contract.lp_token().call().await.ok().map(|lp_token| lp_token._0).filter(|lp_token| !lp_token.is_zero())
+        {
+            return Ok(lp_token);
+        }
+        liquidity_contract
+            .token()
+            .call()
+            .await
+            .ok()
+            .map(|lp_token| lp_token._0)
+            .filter(|lp_token| !lp_token.is_zero())
+            .ok_or_eyre("NO_LP_TOKEN")
     }
 
     pub async fn coin128(client: P, address: Address, coin_id: u32) -> Result<Address> {
@@ -332,30 +348,80 @@ where
         }
     }
 
+    /// `calc_token_amount` of stable pools does not charge the imbalance fee of a deposit
+    pub fn calc_token_amount_without_fee(&self) -> bool {
+        matches!(
+            self,
+            CurveContract::I128_2(_)
+                | CurveContract::I128_2To(_)
+                | CurveContract::I128_2ToMeta(_)
+                | CurveContract::I128_3(_)
+                | CurveContract::I128_4(_)
+        )
+    }
+
+    pub fn can_remove_liquidity_one_coin(&self) -> bool {
+        !matches!(self, CurveContract::I128_4(_))
+    }
+
+    fn liquidity_amounts<const C: usize>(i: u32, amount: U256) -> Result<[U256; C]> {
+        let mut amounts: [U256; C] = [U256::ZERO; C];
+        *amounts.get_mut(i as usize).ok_or_eyre("COIN_NOT_FOUND")? = amount;
+        Ok(amounts)
+    }
+
+    pub fn get_fee_call_data(&self) -> Bytes {
+        ICurveLiquidity::feeCall {}.abi_encode().into()
+    }
+
     pub fn calc_token_amount_call_data(&self, i: u32, amount: U256) -> Result<Bytes> {
         match self {
             CurveContract::I128_2(interface) => {
-                let mut amounts: [U256; 2] = Default::default();
-                amounts[i as usize] = amount;
-                Ok(interface.calc_token_amount(amounts, true).calldata().clone())
+                Ok(interface.calc_token_amount(Self::liquidity_amounts(i, amount)?, true).calldata().clone())
+            }
+            CurveContract::I128_2ToMeta(interface) => {
+                Ok(interface.calc_token_amount(Self::liquidity_amounts(i, amount)?, true).calldata().clone())
+            }
+            CurveContract::I128_2To(interface) => {
+                Ok(interface.calc_token_amount(Self::liquidity_amounts(i, amount)?, true).calldata().clone())
             }
             CurveContract::I128_3(interface) => {
-                let mut amounts: [U256; 3] = Default::default();
-                amounts[i as usize] = amount;
-                Ok(interface.calc_token_amount(amounts, true).calldata().clone())
+                Ok(interface.calc_token_amount(Self::liquidity_amounts(i, amount)?, true).calldata().clone())
             }
             CurveContract::I128_4(interface) => {
-                let mut amounts: [U256; 4] = Default::default();
-                amounts[i as usize] = amount;
-                Ok(interface.calc_token_amount(amounts, true).calldata().clone())
+                Ok(interface.calc_token_amount(Self::liquidity_amounts(i, amount)?, true).calldata().clone())
+            }
+            CurveContract::U256_2(interface) => Ok(interface.calc_token_amount(Self::liquidity_amounts(i, amount)?).calldata().clone()),
+            CurveContract::U256_2To(interface) => {
+                Ok(interface.calc_token_amount(Self::liquidity_amounts(i, amount)?, true).calldata().clone())
+            }
+            CurveContract::U256_2EthTo(interface) => {
+                Ok(interface.calc_token_amount(Self::liquidity_amounts(i, amount)?, true).calldata().clone())
+            }
+            CurveContract::U256_3Eth(interface) => {
+                Ok(interface.calc_token_amount(Self::liquidity_amounts(i, amount)?, true).calldata().clone())
+            }
+            CurveContract::U256_3EthTo(interface) => {
+                Ok(interface.calc_token_amount(Self::liquidity_amounts(i, amount)?, true).calldata().clone())
+            }
+            CurveContract::U256_3EthTo2(interface) => {
+                Ok(interface.calc_token_amount(Self::liquidity_amounts(i, amount)?, true).calldata().clone())
             }
-            _ => Err(eyre!("CURVE_TOKEN_AMOUNT_CALL_DATA_NOT_SUPPORTED")),
         }
     }
 
     pub fn calc_withdraw_one_coin_call_data(&self, i: u32, amount: U256) -> Result<Bytes> {
         match self {
+            CurveContract::I128_2(interface) => Ok(interface.calc_withdraw_one_coin(amount, i.into()).calldata().clone()),
+            CurveContract::I128_2ToMeta(interface) => Ok(interface.calc_withdraw_one_coin(amount, i.into()).calldata().clone()),
+            CurveContract::I128_2To(interface) => Ok(interface.calc_withdraw_one_coin(amount, i.into()).calldata().clone()),
             CurveContract::I128_3(interface) => Ok(interface.calc_withdraw_one_coin(amount, i.into()).calldata().clone()),
+            CurveContract::U256_2(interface) => Ok(interface.calc_withdraw_one_coin(amount, U256::from(i)).calldata().clone()),
+            CurveContract::U256_2To(interface) => Ok(interface.calc_withdraw_one_coin(amount, i.into()).calldata().clone()),
+            CurveContract::U256_2EthTo(interface) => Ok(interface.calc_withdraw_one_coin(amount, i.into()).calldata().clone()),
+            CurveContract::U256_3Eth(interface) => Ok(interface.calc_withdraw_one_coin(amount, U256::from(i)).calldata().clone()),
+            CurveContract::U256_3EthTo(interface) => Ok(interface.calc_withdraw_one_coin(amount, i.into()).calldata().clone()),
+            CurveContract::U256_3EthTo2(interface) => Ok(interface.calc_withdraw_one_coin(amount, U256::from(i)).calldata().clone()),
             _ => Err(eyre!("CURVE_WITHDRAW_ONE_COIN_NOT_SUPPORTED")),
         }
     }
@@ -397,18 +463,66 @@ where
 
     pub fn get_add_liquidity_call_data(&self, i: u32, amount: U256, _to: Address) -> Result<Bytes> {
         match self {
+            CurveContract::I128_2(interface) => {
+                Ok(interface.add_liquidity(Self::liquidity_amounts(i, amount)?, U256::ZERO).calldata().clone())
+            }
+            CurveContract::I128_2ToMeta(interface) => {
+                Ok(interface.add_liquidity(Self::liquidity_amounts(i, amount)?, U256::ZERO).calldata().clone())
+            }
+            CurveContract::I128_2To(interface) => {
+                Ok(interface.add_liquidity(Self::liquidity_amounts(i, amount)?, U256::ZERO).calldata().clone())
+            }
             CurveContract::I128_3(interface) => {
-                let mut amounts: [U256; 3] = Default::default();
-                amounts[i as usize] = amount;
-                Ok(interface.add_liquidity(amounts, U256::ZERO).calldata().clone())
+                Ok(interface.add_liquidity(Self::liquidity_amounts(i, amount)?, U256::ZERO).calldata().clone())
+            }
+            CurveContract::I128_4(interface) => {
+                Ok(interface.add_liquidity(Self::liquidity_amounts(i, amount)?, U256::ZERO).calldata().clone())
+            }
+            CurveContract::U256_2(interface) => {
+                Ok(interface.add_liquidity(Self::liquidity_amounts(i, amount)?, U256::ZERO).calldata().clone())
+            }
+            CurveContract::U256_2To(interface) => {
+                Ok(interface.add_liquidity(Self::liquidity_amounts(i, amount)?, U256::ZERO).calldata().clone())
+            }
+            CurveContract::U256_2EthTo(interface) => {
+                Ok(interface.add_liquidity(Self::liquidity_amounts(i, amount)?, U256::ZERO).calldata().clone())
+            }
+            CurveContract::U256_3Eth(interface) => {
+                Ok(interface.add_liquidity(Self::liquidity_amounts(i, amount)?, U256::ZERO).calldata().clone())
+            }
+            CurveContract::U256_3EthTo(interface) => {
+                Ok(interface.add_liquidity(Self::liquidity_amounts(i, amount)?, U256::ZERO).calldata().clone())
+            }
+            CurveContract::U256_3EthTo2(interface) => {
+                Ok(interface.add_liquidity(Self::liquidity_amounts(i, amount)?, U256::ZERO).calldata().clone())
             }
-            _ => Err(eyre!("ADD_LIQUIDITY_NOT_SUPPORTED")),
         }
     }
 
     pub fn get_remove_liquidity_one_coin_call_data(&self, i: u32, amount: U256, _to: Address) -> Result<Bytes> {
         match self {
-            CurveContract::I128_3(interface) => Ok(interface.remove_liquidity_one_coin(amount, i as i128, U256::ZERO).calldata().clone()),
+            CurveContract::I128_2(interface) => Ok(interface.remove_liquidity_one_coin(amount, i.into(), U256::ZERO).calldata().clone()),
+            CurveContract::I128_2ToMeta(interface) => {
+                Ok(interface.remove_liquidity_one_coin(amount, i.into(), U256::ZERO).calldata().clone())
+            }
+            CurveContract::I128_2To(interface) => Ok(interface.remove_liquidity_one_coin(amount, i.into(), U256::ZERO).calldata().clone()),
+            CurveContract::I128_3(interface) => Ok(interface.remove_liquidity_one_coin(amount, i.into(), U256::ZERO).calldata().clone()),
+            CurveContract::U256_2(interface) => {
+                Ok(interface.remove_liquidity_one_coin(amount, U256::from(i), U256::ZERO).calldata().clone())
+            }
+            CurveContract::U256_2To(interface) => Ok(interface.remove_liquidity_one_coin(amount, i.into(), U256::ZERO).calldata().clone()),
+            CurveContract::U256_2EthTo(interface) => {
+                Ok(interface.remove_liquidity_one_coin(amount, i.into(), U256::ZERO).calldata().clone())
+            }
+            CurveContract::U256_3Eth(interface) => {
+                Ok(interface.remove_liquidity_one_coin(amount, U256::from(i), U256::ZERO).calldata().clone())
+            }
+            CurveContract::U256_3EthTo(interface) => {
+                Ok(interface.remove_liquidity_one_coin(amount, U256::from(i), U256::ZERO).calldata().clone())
+            }
+            CurveContract::U256_3EthTo2(interface) => {
+                Ok(interface.remove_liquidity_one_coin(amount, U256::from(i), U256::ZERO).calldata().clone())
+            }
             _ => Err(eyre!("REMOVE_LIQUIDITY_ONE_COIN_NOT_SUPPORTED")),
         }
     }
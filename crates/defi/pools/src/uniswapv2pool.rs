@@ -1,18 +1,21 @@
+use alloy::network::TransactionBuilder;
 use alloy::primitives::{Address, Bytes, U256};
 use alloy::providers::{Network, Provider};
 use alloy::rpc::types::BlockNumberOrTag;
-use alloy::sol_types::SolInterface;
+use alloy::sol_types::{SolCall, SolInterface};
 use eyre::{eyre, ErrReport, OptionExt, Result};
 use lazy_static::lazy_static;
+use loom_defi_abi::solidly::{IEqualizerPairFactory, IRamsesPairFactory, ISolidlyPair, IThenaPairFactory};
 use loom_defi_abi::uniswap2::IUniswapV2Pair;
 use loom_defi_abi::IERC20;
 use loom_defi_address_book::FactoryAddress;
+use loom_defi_swap_math::uniswap_v2;
+use loom_evm_utils::evm::evm_call;
 use loom_types_entities::required_state::RequiredState;
 use loom_types_entities::{Pool, PoolAbiEncoder, PoolClass, PoolId, PoolProtocol, PreswapRequirement, SwapDirection};
 use revm::primitives::Env;
 use revm::DatabaseRef;
 use std::any::Any;
-use std::ops::Div;
 use tracing::debug;
 
 use crate::state_readers::UniswapV2StateReader;
@@ -20,6 +23,61 @@ use crate::state_readers::UniswapV2StateReader;
 lazy_static! {
     static ref U112_MASK: U256 = (U256::from(1) << 112) - U256::from(1);
     static ref U256_ONE: U256 = U256::from(1);
+    static ref SOLIDLY_FEE_DENOMINATOR: U256 = U256::from(10000);
+}
+
+/// Fee module of ve(3,3) forks, the factory sets the fee per pool and can change it at any time
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SolidlyFeeModule {
+    /// `pairFee(address pair)`, per pool fee set by the fee module
+    Ramses,
+    /// `getFee(bool stable)`, one fee for all volatile pairs
+    Thena,
+    /// `getRealFee(address pair)`, per pool fee with the default fee as fallback
+    Equalizer,
+}
+
+impl SolidlyFeeModule {
+    pub fn by_protocol(protocol: PoolProtocol) -> Option<Self> {
+        match protocol {
+            PoolProtocol::Ramses => Some(Self::Ramses),
+            PoolProtocol::Thena => Some(Self::Thena),
+            PoolProtocol::Equalizer => Some(Self::Equalizer),
+            _ => None,
+        }
+    }
+
+    /// Call data of the factory fee getter for a volatile `pair`
+    pub fn fee_call_data(&self, pair: Address) -> Vec<u8> {
+        match self {
+            Self::Ramses => IRamsesPairFactory::pairFeeCall { _pair: pair }.abi_encode(),
+            Self::Thena => IThenaPairFactory::getFeeCall { _stable: false }.abi_encode(),
+            Self::Equalizer => IEqualizerPairFactory::getRealFeeCall { _pair: pair }.abi_encode(),
+        }
+    }
+
+    /// Fee in basis points from the fee getter output
+    pub fn decode_fee(&self, data: &[u8]) -> Result<U256> {
+        let fee = match self {
+            Self::Ramses => IRamsesPairFactory::pairFeeCall::abi_decode_returns(data, false)?.fee,
+            Self::Thena => IThenaPairFactory::getFeeCall::abi_decode_returns(data, false)?._0,
+            Self::Equalizer => IEqualizerPairFactory::getRealFeeCall::abi_decode_returns(data, false)?._0,
+        };
+        if fee >= *SOLIDLY_FEE_DENOMINATOR {
+            return Err(eyre!("SOLIDLY_FEE_TOO_HIGH"));
+        }
+        Ok(fee)
+    }
+
+    /// Volatile pair `getAmountOut`, the fee is taken from the input before the x*y=k swap
+    pub fn amount_out(amount_in: U256, reserve_in: U256, reserve_out: U256, fee: U256) -> Result<U256> {
+        Ok(uniswap_v2::get_amount_out_fee_on_input(amount_in, reserve_in, reserve_out, fee)?)
+    }
+
+    /// Smallest input of a volatile pair giving at least `amount_out`
+    pub fn amount_in(amount_out: U256, reserve_in: U256, reserve_out: U256, fee: U256) -> Result<U256> {
+        Ok(uniswap_v2::get_amount_in_fee_on_input(amount_out, reserve_in, reserve_out, fee)?)
+    }
 }
 #[allow(dead_code)]
 #[derive(Clone)]
@@ -30,6 +88,10 @@ pub struct UniswapV2Pool {
     factory: Address,
     protocol: PoolProtocol,
     fee: U256,
+    // fee of token1 -> token0 swaps for forks with direction dependent fees
+    fee_1_to_0: Option<U256>,
+    // ve(3,3) pools read the current fee from the factory
+    fee_module: Option<SolidlyFeeModule>,
     encoder: UniswapV2PoolAbiEncoder,
     reserves_cell: Option<U256>,
     liquidity0: U256,
@@ -45,6 +107,8 @@ impl UniswapV2Pool {
             factory: Address::ZERO,
             protocol: PoolProtocol::UniswapV2Like,
             fee: U256::from(9970),
+            fee_1_to_0: None,
+            fee_module: None,
             encoder: UniswapV2PoolAbiEncoder {},
             reserves_cell: None,
             liquidity0: U256::ZERO,
@@ -67,6 +131,8 @@ impl UniswapV2Pool {
             factory,
             protocol: PoolProtocol::UniswapV2Like,
             fee: U256::from(9970),
+            fee_1_to_0: None,
+            fee_module: None,
             encoder: UniswapV2PoolAbiEncoder {},
             reserves_cell: None,
             liquidity0,
@@ -78,6 +144,14 @@ impl UniswapV2Pool {
         Self { fee, ..self }
     }
 
+    pub fn set_direction_fees(self, fee_0_to_1: U256, fee_1_to_0: U256) -> Self {
+        Self { fee: fee_0_to_1, fee_1_to_0: Some(fee_1_to_0), ..self }
+    }
+
+    pub fn get_fee_module(&self) -> Option<SolidlyFeeModule> {
+        self.fee_module
+    }
+
     pub fn get_zero_for_one(token_address_from: Address, token_address_to: Address) -> bool {
         token_address_from < token_address_to
     }
@@ -103,6 +177,12 @@ impl UniswapV2Pool {
             PoolProtocol::AntFarm
         } else if factory_address == FactoryAddress::INTEGRAL {
             PoolProtocol::Integral
+        } else if factory_address == FactoryAddress::RAMSES {
+            PoolProtocol::Ramses
+        } else if factory_address == FactoryAddress::THENA {
+            PoolProtocol::Thena
+        } else if factory_address == FactoryAddress::EQUALIZER {
+            PoolProtocol::Equalizer
         } else {
             PoolProtocol::UniswapV2Like
         }
@@ -126,13 +206,24 @@ impl UniswapV2Pool {
         let factory = UniswapV2StateReader::factory(&db, env.clone(), address)?;
         let protocol = Self::get_uni2_protocol_by_factory(factory);
 
-        let fee = Self::get_fee_by_protocol(protocol);
+        let mut fee = Self::get_fee_by_protocol(protocol);
+
+        if let Some(fee_module) = SolidlyFeeModule::by_protocol(protocol) {
+            let (stable, _) = evm_call(db, env.clone(), address, ISolidlyPair::stableCall {}.abi_encode())?;
+            if ISolidlyPair::stableCall::abi_decode_returns(&stable, false)?._0 {
+                return Err(eyre!("SOLIDLY_STABLE_POOL_NOT_SUPPORTED"));
+            }
+            let (fee_data, _) = evm_call(db, env, factory, fee_module.fee_call_data(address))?;
+            fee = *SOLIDLY_FEE_DENOMINATOR - fee_module.decode_fee(&fee_data)?;
+        }
 
         let ret = UniswapV2Pool {
             address,
             token0,
             token1,
             fee,
+            fee_1_to_0: None,
+            fee_module: SolidlyFeeModule::by_protocol(protocol),
             factory,
             protocol,
             encoder: UniswapV2PoolAbiEncoder {},
@@ -167,7 +258,16 @@ impl UniswapV2Pool {
 
         let protocol = UniswapV2Pool::get_uni2_protocol_by_factory(factory);
 
-        let fee = Self::get_fee_by_protocol(protocol);
+        let mut fee = Self::get_fee_by_protocol(protocol);
+
+        if let Some(fee_module) = SolidlyFeeModule::by_protocol(protocol) {
+            if ISolidlyPair::ISolidlyPairInstance::new(address, client.clone()).stable().call().await?._0 {
+                return Err(eyre!("SOLIDLY_STABLE_POOL_NOT_SUPPORTED"));
+            }
+            let fee_call = N::TransactionRequest::default().with_to(factory).with_input(fee_module.fee_call_data(address));
+            let fee_data = client.call(&fee_call).await?;
+            fee = *SOLIDLY_FEE_DENOMINATOR - fee_module.decode_fee(&fee_data)?;
+        }
 
         let ret = UniswapV2Pool {
             address,
@@ -176,6 +276,8 @@ impl UniswapV2Pool {
             factory,
             protocol,
             fee,
+            fee_1_to_0: None,
+            fee_module: SolidlyFeeModule::by_protocol(protocol),
             reserves_cell,
             liquidity0: U256::from(reserves.reserve0),
             liquidity1: U256::from(reserves.reserve1),
@@ -197,6 +299,12 @@ impl UniswapV2Pool {
         };
         Ok((reserve_0, reserve_1))
     }
+
+    /// Current fee of ve(3,3) pools from the factory fee module
+    fn fetch_solidly_fee(&self, fee_module: SolidlyFeeModule, state_db: &dyn DatabaseRef<Error = ErrReport>, env: Env) -> Result<U256> {
+        let (value, _) = evm_call(state_db, env, self.factory, fee_module.fee_call_data(self.address))?;
+        fee_module.decode_fee(&value)
+    }
 }
 
 impl Pool for UniswapV2Pool {
@@ -211,6 +319,10 @@ impl Pool for UniswapV2Pool {
         self.protocol
     }
 
+    fn get_factory(&self) -> Option<Address> {
+        (!self.factory.is_zero()).then_some(self.factory)
+    }
+
     fn get_address(&self) -> Address {
         self.address
     }
@@ -222,6 +334,13 @@ impl Pool for UniswapV2Pool {
         self.fee
     }
 
+    fn get_fee_by_direction(&self, token_from: &Address, _token_to: &Address) -> U256 {
+        match self.fee_1_to_0 {
+            Some(fee_1_to_0) if *token_from == self.token1 => fee_1_to_0,
+            _ => self.fee,
+        }
+    }
+
     fn get_tokens(&self) -> Vec<Address> {
         vec![self.token0, self.token1]
     }
@@ -238,19 +357,27 @@ impl Pool for UniswapV2Pool {
         token_address_to: &Address,
         in_amount: U256,
     ) -> Result<(U256, u64), ErrReport> {
-        let (reserves_0, reserves_1) = self.fetch_reserves(state_db, env)?;
+        let (reserves_0, reserves_1) = self.fetch_reserves(state_db, env.clone())?;
 
         let (reserve_in, reserve_out) = match token_address_from < token_address_to {
             true => (reserves_0, reserves_1),
             false => (reserves_1, reserves_0),
         };
 
-        let amount_in_with_fee = in_amount.checked_mul(self.fee).ok_or(eyre!("AMOUNT_IN_WITH_FEE_OVERFLOW"))?;
-        let numerator = amount_in_with_fee.checked_mul(reserve_out).ok_or(eyre!("NUMERATOR_OVERFLOW"))?;
-        let denominator = reserve_in.checked_mul(U256::from(10000)).ok_or(eyre!("DENOMINATOR_OVERFLOW"))?;
-        let denominator = denominator.checked_add(amount_in_with_fee).ok_or(eyre!("DENOMINATOR_OVERFLOW_FEE"))?;
+        if let Some(fee_module) = self.fee_module {
+            let fee = self.fetch_solidly_fee(fee_module, state_db, env)?;
+            let out_amount = SolidlyFeeModule::amount_out(in_amount, reserve_in, reserve_out, fee)?;
+            return if out_amount >= reserve_out {
+                Err(eyre!("RESERVE_EXCEEDED"))
+            } else if out_amount.is_zero() {
+                Err(eyre!("OUT_AMOUNT_IS_ZERO"))
+            } else {
+                Ok((out_amount, 120_000))
+            };
+        }
 
-        let out_amount = numerator.checked_div(denominator).ok_or(eyre!("CANNOT_CALCULATE_ZERO_RESERVE"))?;
+        let fee = self.get_fee_by_direction(token_address_from, token_address_to);
+        let out_amount = uniswap_v2::get_amount_out(in_amount, reserve_in, reserve_out, fee)?;
         if out_amount > reserve_out {
             Err(eyre!("RESERVE_EXCEEDED"))
         } else if out_amount.is_zero() {
@@ -268,7 +395,7 @@ impl Pool for UniswapV2Pool {
         token_address_to: &Address,
         out_amount: U256,
     ) -> Result<(U256, u64), ErrReport> {
-        let (reserves_0, reserves_1) = self.fetch_reserves(state_db, env)?;
+        let (reserves_0, reserves_1) = self.fetch_reserves(state_db, env.clone())?;
 
         let (reserve_in, reserve_out) = match token_address_from < token_address_to {
             true => (reserves_0, reserves_1),
@@ -278,25 +405,23 @@ impl Pool for UniswapV2Pool {
         if out_amount > reserve_out {
             return Err(eyre!("RESERVE_OUT_EXCEEDED"));
         }
-        let numerator = reserve_in.checked_mul(out_amount).ok_or(eyre!("NUMERATOR_OVERFLOW"))?;
-        let numerator = numerator.checked_mul(U256::from(10000)).ok_or(eyre!("NUMERATOR_OVERFLOW_FEE"))?;
-        let denominator = reserve_out.checked_sub(out_amount).ok_or(eyre!("DENOMINATOR_UNDERFLOW"))?;
-        let denominator = denominator.checked_mul(self.fee).ok_or(eyre!("DENOMINATOR_OVERFLOW_FEE"))?;
 
-        if denominator.is_zero() {
-            Err(eyre!("CANNOT_CALCULATE_ZERO_RESERVE"))
+        if let Some(fee_module) = self.fee_module {
+            let fee = self.fetch_solidly_fee(fee_module, state_db, env)?;
+            return Ok((SolidlyFeeModule::amount_in(out_amount, reserve_in, reserve_out, fee)?, 120_000));
+        }
+        let fee = self.get_fee_by_direction(token_address_from, token_address_to);
+        let in_amount = uniswap_v2::get_amount_in(out_amount, reserve_in, reserve_out, fee)?;
+        if in_amount == *U256_ONE {
+            Err(eyre!("IN_AMOUNT_IS_ZERO"))
         } else {
-            let in_amount = numerator.div(denominator); // We assure before that denominator is not zero
-            if in_amount.is_zero() {
-                Err(eyre!("IN_AMOUNT_IS_ZERO"))
-            } else {
-                Ok((in_amount.checked_add(*U256_ONE).ok_or_eyre("ADD_OVERFLOWN")?, 100_000))
-            }
+            Ok((in_amount, 100_000))
         }
     }
 
     fn can_flash_swap(&self) -> bool {
-        true
+        // ve(3,3) pairs call a different callback
+        self.fee_module.is_none()
     }
 
     fn can_calculate_in_amount(&self) -> bool {
@@ -318,6 +443,10 @@ impl Pool for UniswapV2Pool {
 
         state_required.add_call(self.get_address(), reserves_call_data_vec).add_slot_range(self.get_address(), U256::from(0), 0x20);
 
+        if let Some(fee_module) = self.fee_module {
+            state_required.add_call(self.factory, fee_module.fee_call_data(self.address));
+        }
+
         for token_address in self.get_tokens() {
             state_required.add_call(
                 token_address,
@@ -396,6 +525,32 @@ mod test {
         address!("ddd23787a6b80a794d952f5fb036d0b31a8e6aff"), // PEPE/WETH pool
     ];
 
+    #[test]
+    fn test_fee_by_direction() {
+        let (token0, token1) = (Address::repeat_byte(1), Address::repeat_byte(2));
+        let pool = UniswapV2Pool::new_with_data(Address::ZERO, token0, token1, Address::ZERO, U256::ZERO, U256::ZERO);
+        assert_eq!(pool.get_fee_by_direction(&token1, &token0), U256::from(9970));
+
+        let pool = pool.set_direction_fees(U256::from(9970), U256::from(9900));
+        assert_eq!(pool.get_fee_by_direction(&token0, &token1), U256::from(9970));
+        assert_eq!(pool.get_fee_by_direction(&token1, &token0), U256::from(9900));
+    }
+
+    #[test]
+    fn test_solidly_amounts() {
+        let (reserve_in, reserve_out) = (U256::from(1_000_000_000u64), U256::from(2_000_000_000u64));
+
+        // 0.2% fee : (1_000_000 - 2_000) * 2e9 / (1e9 + 998_000)
+        let amount_out = SolidlyFeeModule::amount_out(U256::from(1_000_000), reserve_in, reserve_out, U256::from(20)).unwrap();
+        assert_eq!(amount_out, U256::from(1_994_009));
+
+        let amount_in = SolidlyFeeModule::amount_in(amount_out, reserve_in, reserve_out, U256::from(20)).unwrap();
+        assert!(amount_in <= U256::from(1_000_000));
+        assert!(SolidlyFeeModule::amount_out(amount_in, reserve_in, reserve_out, U256::from(20)).unwrap() >= amount_out);
+
+        assert!(SolidlyFeeModule::Thena.decode_fee(&U256::from(10000).to_be_bytes::<32>()).is_err());
+    }
+
     #[tokio::test]
     async fn test_fetch_reserves() -> Result<()> {
         let block_number = 20935488u64;
@@ -23,6 +23,11 @@ use tracing::debug;
 #[cfg(feature = "debug-calculation")]
 use tracing::error;
 
+/// Tick bitmap words preloaded on each side of the current tick word
+const TICK_WORDS_RADIUS: i16 = 4;
+/// Tick bitmap words loaded on each side of a missing word reached by a calculation
+const MISSING_TICK_WORDS_RADIUS: i16 = 1;
+
 lazy_static! {
     static ref U256_ONE: U256 = U256::from(1);
     static ref LOWER_LIMIT: U160 = U160::from(4295128740u64);
@@ -136,6 +141,21 @@ impl UniswapV3Pool {
         }
     }
 
+    fn populated_ticks_call(pool: Address, word: i16) -> Vec<u8> {
+        ITickLens::ITickLensCalls::getPopulatedTicksInWord(ITickLens::getPopulatedTicksInWordCall { pool, tickBitmapIndex: word })
+            .abi_encode()
+    }
+
+    /// State of the tick bitmap `word` and its neighbours within `MISSING_TICK_WORDS_RADIUS`, used to extend the tick range
+    /// loaded by `get_state_required` when a calculation reaches a missing word
+    pub fn get_tick_words_state_required(&self, word: i16) -> RequiredState {
+        let mut state_required = RequiredState::new();
+        for word in word.saturating_sub(MISSING_TICK_WORDS_RADIUS)..=word.saturating_add(MISSING_TICK_WORDS_RADIUS) {
+            state_required.add_call(PeripheryAddress::UNISWAP_V3_TICK_LENS, Self::populated_ticks_call(self.address, word));
+        }
+        state_required
+    }
+
     pub fn get_price_limit(token_address_from: &Address, token_address_to: &Address) -> U160 {
         if token_address_from.lt(token_address_to) {
             *LOWER_LIMIT
@@ -232,6 +252,10 @@ impl Pool for UniswapV3Pool {
         self.protocol
     }
 
+    fn get_factory(&self) -> Option<Address> {
+        (!self.factory.is_zero()).then_some(self.factory)
+    }
+
     fn get_address(&self) -> Address {
         self.address
     }
@@ -392,15 +416,10 @@ impl Pool for UniswapV3Pool {
             .add_call(self.get_address(), IUniswapV3Pool::IUniswapV3PoolCalls::slot0(IUniswapV3Pool::slot0Call {}).abi_encode())
             .add_call(self.get_address(), IUniswapV3Pool::IUniswapV3PoolCalls::liquidity(IUniswapV3Pool::liquidityCall {}).abi_encode());
 
-        for i in -4..=3 {
-            state_required.add_call(
-                PeripheryAddress::UNISWAP_V3_TICK_LENS,
-                ITickLens::ITickLensCalls::getPopulatedTicksInWord(ITickLens::getPopulatedTicksInWordCall {
-                    pool: pool_address,
-                    tickBitmapIndex: tick_bitmap_index + i,
-                })
-                .abi_encode(),
-            );
+        // calculations reaching further words fail with TICK_WORD_NOT_LOADED until the word is fetched with
+        // `get_tick_words_state_required`
+        for word in tick_bitmap_index.saturating_sub(TICK_WORDS_RADIUS)..=tick_bitmap_index.saturating_add(TICK_WORDS_RADIUS) {
+            state_required.add_call(PeripheryAddress::UNISWAP_V3_TICK_LENS, Self::populated_ticks_call(pool_address, word));
         }
         state_required
             .add_call(self.token0, balance_call_data.clone())
@@ -531,6 +550,7 @@ mod test {
     use loom_evm_db::{AlloyDB, LoomDB};
     use loom_node_debug_provider::{AnvilDebugProviderFactory, AnvilDebugProviderType};
     use loom_types_entities::required_state::RequiredStateReader;
+    use loom_types_entities::SwapError;
     use revm::db::EmptyDBTyped;
     use std::env;
 
@@ -784,4 +804,56 @@ mod test {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_tick_words_on_demand() -> Result<()> {
+        let node_url = env::var("MAINNET_WS")?;
+        let client = AnvilDebugProviderFactory::from_node_on_block(node_url, BlockNumber::from(BLOCK_NUMBER)).await?;
+
+        let pool = UniswapV3Pool::fetch_pool_data(client.clone(), UniswapV3PoolAddress::USDC_WETH_3000).await?;
+        let state_required = pool.get_state_required()?;
+        let state_update = RequiredStateReader::fetch_calls_and_slots(client.clone(), state_required, Some(BLOCK_NUMBER)).await?;
+
+        let mut state_db = LoomDBType::default();
+        state_db.apply_geth_update(state_update);
+
+        // large swap crossing the words loaded around the current tick
+        let amount_in = U256::from(10u64).pow(U256::from(18)) * U256::from(20000);
+        let contract_amount_out = fetch_original_contract_amounts(
+            client.clone(),
+            UniswapV3PoolAddress::USDC_WETH_3000,
+            pool.token1,
+            pool.token0,
+            amount_in,
+            BLOCK_NUMBER,
+            true,
+        )
+        .await?;
+
+        let mut words_loaded = 0;
+        let amount_out = loop {
+            let error = match pool.calculate_out_amount(&state_db, Env::default(), &pool.token1, &pool.token0, amount_in) {
+                Ok((amount_out, _)) => break amount_out,
+                Err(error) => error,
+            };
+            let swap_error = SwapError {
+                msg: error.to_string(),
+                pool: pool.get_pool_id(),
+                token_from: pool.token1,
+                token_to: pool.token0,
+                is_in_amount: true,
+                amount: amount_in,
+            };
+            let word = swap_error.missing_tick_word().ok_or(error)?;
+            let state_update =
+                RequiredStateReader::fetch_calls_and_slots(client.clone(), pool.get_tick_words_state_required(word), Some(BLOCK_NUMBER))
+                    .await?;
+            state_db.apply_geth_update(state_update);
+            words_loaded += 1;
+            assert!(words_loaded < 100, "TOO_MANY_WORDS_LOADED");
+        };
+
+        assert_eq!(amount_out, contract_amount_out);
+        Ok(())
+    }
 }
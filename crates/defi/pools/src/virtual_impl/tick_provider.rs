@@ -1,6 +1,9 @@
 use crate::db_reader::UniswapV3DBReader;
 use alloy::primitives::{Address, U256};
+use eyre::Result;
+use loom_defi_uniswap_v3_math::tick_bitmap::position;
 use loom_defi_uniswap_v3_math::tick_provider::TickProvider;
+use loom_types_entities::tick_word_not_loaded;
 use revm::DatabaseRef;
 
 pub struct TickProviderEVMDB<DB> {
@@ -15,12 +18,41 @@ where
     pub fn new(db: DB, pool_address: Address) -> Self {
         TickProviderEVMDB { db, pool_address }
     }
+
+    /// Reads the bitmap word searched for the next initialized tick, fails with `TICK_WORD_NOT_LOADED` if it is missing in
+    /// the db. The math reads missing words as empty, so it is given the returned word instead of the db
+    pub fn load_word(&self, tick: i32, tick_spacing: i32, lte: bool) -> Result<TickWord> {
+        let compressed = if tick < 0 && tick % tick_spacing != 0 { (tick / tick_spacing) - 1 } else { tick / tick_spacing };
+        let (pos, _) = if lte { position(compressed) } else { position(compressed + 1) };
+        let word = self.get_tick(pos).map_err(|_| tick_word_not_loaded(pos))?;
+        Ok(TickWord { pos, word })
+    }
+}
+
+/// Single tick bitmap word read from the db
+pub struct TickWord {
+    pub pos: i16,
+    pub word: U256,
+}
+
+impl TickProvider for TickWord {
+    type Error = eyre::Report;
+
+    fn get_tick(&self, tick: i16) -> eyre::Result<U256> {
+        if tick == self.pos {
+            Ok(self.word)
+        } else {
+            Err(tick_word_not_loaded(tick))
+        }
+    }
 }
 
 impl<DB> TickProvider for TickProviderEVMDB<DB>
 where
     DB: DatabaseRef,
 {
+    type Error = eyre::Report;
+
     fn get_tick(&self, tick: i16) -> eyre::Result<U256> {
         UniswapV3DBReader::tick_bitmap(&self.db, self.pool_address, tick)
     }
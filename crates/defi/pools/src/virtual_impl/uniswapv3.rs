@@ -122,9 +122,11 @@ impl UniswapV3PoolVirtual {
                 ..Default::default()
             };
 
+            let tick_word = tick_provider.load_word(current_state.tick, tick_spacing as i32, zero_for_one)?;
+
             // Get the next tick from the current tick
             (step.tick_next, step.initialized) = loom_defi_uniswap_v3_math::tick_bitmap::next_initialized_tick_within_one_word(
-                &tick_provider,
+                &tick_word,
                 current_state.tick,
                 tick_spacing as i32,
                 zero_for_one,
@@ -248,9 +250,11 @@ impl UniswapV3PoolVirtual {
 
             let tick_provider = TickProviderEVMDB::new(&db, pool_address);
 
+            let tick_word = tick_provider.load_word(current_state.tick, tick_spacing as i32, zero_for_one)?;
+
             // Get the next tick from the current tick
             (step.tick_next, step.initialized) = loom_defi_uniswap_v3_math::tick_bitmap::next_initialized_tick_within_one_word(
-                &tick_provider,
+                &tick_word,
                 current_state.tick,
                 tick_spacing as i32,
                 zero_for_one,
@@ -0,0 +1,420 @@
+use alloy::primitives::{Address, Bytes, U256};
+use alloy::providers::{Network, Provider};
+use alloy::sol_types::SolCall;
+use eyre::{eyre, ErrReport, Result};
+use lazy_static::lazy_static;
+use loom_defi_abi::woofi::IWooPPV2::{swapCall, tokenInfosCall};
+use loom_defi_abi::woofi::IWooracleV2::stateCall;
+use loom_defi_abi::woofi::{IWooPPV2, IWooracleV2};
+use loom_defi_abi::IERC20;
+use loom_evm_utils::evm::evm_call;
+use loom_types_entities::required_state::RequiredState;
+use loom_types_entities::{
+    hop_amount_out_bounds, Pool, PoolAbiEncoder, PoolClass, PoolId, PoolProtocol, PreswapRequirement, SwapDirection,
+};
+use revm::primitives::Env;
+use revm::DatabaseRef;
+use std::any::Any;
+use std::collections::HashMap;
+
+lazy_static! {
+    static ref E18: U256 = U256::from(10).pow(U256::from(18));
+    static ref FEE_RATE_DENOMINATOR: U256 = U256::from(100_000);
+}
+
+/// Oracle state and pool parameters of a WooFi base token
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct WooFiBaseState {
+    /// Oracle price of the base token in quote with `price_dec` precision
+    pub price: U256,
+    /// Spread with 1e18 precision
+    pub spread: U256,
+    /// Slippage coefficient k with 1e18 precision
+    pub coeff: U256,
+    /// Swap fee with 1e5 precision
+    pub fee_rate: U256,
+    pub base_dec: U256,
+    pub price_dec: U256,
+}
+
+impl WooFiBaseState {
+    /// Quote amount for `base_amount` before fees, `quote = base * price * (1 - k * base * price - spread)`
+    pub fn sell_base(&self, base_amount: U256, quote_dec: U256) -> Result<U256> {
+        let gamma = base_amount * self.price * self.coeff / self.price_dec / self.base_dec;
+        let factor = E18.checked_sub(gamma + self.spread).ok_or_else(|| eyre!("WOOFI_GAMMA_TOO_HIGH"))?;
+        Ok(base_amount * quote_dec * self.price / self.price_dec * factor / *E18 / self.base_dec)
+    }
+
+    /// Base amount for `quote_amount` after fees, `base = quote / price * (1 - k * quote - spread)`
+    pub fn sell_quote(&self, quote_amount: U256, quote_dec: U256) -> Result<U256> {
+        if self.price.is_zero() {
+            return Err(eyre!("WOOFI_PRICE_ZERO"));
+        }
+        let gamma = quote_amount * self.coeff / quote_dec;
+        let factor = E18.checked_sub(gamma + self.spread).ok_or_else(|| eyre!("WOOFI_GAMMA_TOO_HIGH"))?;
+        Ok(quote_amount * self.base_dec * self.price_dec / self.price * factor / *E18 / quote_dec)
+    }
+}
+
+/// Out amount of a WooPPV2 swap, base to base swaps go through the quote token with the max fee rate and half of
+/// the max spread of both tokens
+pub fn woofi_out_amount(
+    from_state: Option<&WooFiBaseState>,
+    to_state: Option<&WooFiBaseState>,
+    quote_dec: U256,
+    amount_in: U256,
+) -> Result<U256> {
+    match (from_state, to_state) {
+        (Some(from_state), None) => {
+            let quote_amount = from_state.sell_base(amount_in, quote_dec)?;
+            Ok(quote_amount - quote_amount * from_state.fee_rate / *FEE_RATE_DENOMINATOR)
+        }
+        (None, Some(to_state)) => {
+            let quote_amount = amount_in - amount_in * to_state.fee_rate / *FEE_RATE_DENOMINATOR;
+            to_state.sell_quote(quote_amount, quote_dec)
+        }
+        (Some(from_state), Some(to_state)) => {
+            let spread = from_state.spread.max(to_state.spread) / U256::from(2);
+            let fee_rate = from_state.fee_rate.max(to_state.fee_rate);
+            let quote_amount = WooFiBaseState { spread, ..*from_state }.sell_base(amount_in, quote_dec)?;
+            let quote_amount = quote_amount - quote_amount * fee_rate / *FEE_RATE_DENOMINATOR;
+            WooFiBaseState { spread, ..*to_state }.sell_quote(quote_amount, quote_dec)
+        }
+        (None, None) => Err(eyre!("WOOFI_QUOTE_TO_QUOTE")),
+    }
+}
+
+/// WooFi V2 synthetic proactive market maker, all base tokens are swapped against the quote token at wooracle prices.
+///
+/// One WooPPV2 contract holds all tokens, tokens are transferred to the pool before the swap.
+#[derive(Clone)]
+pub struct WooFiV2Pool {
+    address: Address,
+    wooracle: Address,
+    quote_token: Address,
+    base_tokens: Vec<Address>,
+    // token decimals and price decimals of base tokens as powers of ten
+    token_dec: HashMap<Address, U256>,
+    price_dec: HashMap<Address, U256>,
+    encoder: WooFiAbiSwapEncoder,
+}
+
+impl WooFiV2Pool {
+    pub fn new(address: Address, wooracle: Address, quote_token: Address) -> Self {
+        WooFiV2Pool {
+            address,
+            wooracle,
+            quote_token,
+            base_tokens: Vec::new(),
+            token_dec: HashMap::new(),
+            price_dec: HashMap::new(),
+            encoder: WooFiAbiSwapEncoder::new(address),
+        }
+    }
+
+    /// Add a base token with its token decimals and wooracle price decimals
+    pub fn with_base_token(self, token: Address, decimals: u8, price_decimals: u8) -> Self {
+        let mut pool = self;
+        pool.base_tokens.push(token);
+        pool.token_dec.insert(token, U256::from(10).pow(U256::from(decimals)));
+        pool.price_dec.insert(token, U256::from(10).pow(U256::from(price_decimals)));
+        pool
+    }
+
+    pub fn with_quote_decimals(self, decimals: u8) -> Self {
+        let mut pool = self;
+        pool.token_dec.insert(pool.quote_token, U256::from(10).pow(U256::from(decimals)));
+        pool
+    }
+
+    fn token_info(&self, state_db: &dyn DatabaseRef<Error = ErrReport>, env: &Env, token: Address) -> Result<(U256, U256)> {
+        let (value, _) = evm_call(state_db, env.clone(), self.address, tokenInfosCall { token }.abi_encode())?;
+        let token_info = tokenInfosCall::abi_decode_returns(&value, false)?;
+        Ok((U256::from(token_info.reserve), U256::from(token_info.feeRate)))
+    }
+
+    fn base_state(&self, state_db: &dyn DatabaseRef<Error = ErrReport>, env: &Env, token: Address) -> Result<Option<WooFiBaseState>> {
+        if token == self.quote_token {
+            return Ok(None);
+        }
+        let (value, _) = evm_call(state_db, env.clone(), self.wooracle, stateCall { base: token }.abi_encode())?;
+        let state = stateCall::abi_decode_returns(&value, false)?._0;
+        if !state.woFeasible {
+            return Err(eyre!("WOOFI_ORACLE_NOT_FEASIBLE"));
+        }
+        let (_, fee_rate) = self.token_info(state_db, env, token)?;
+
+        Ok(Some(WooFiBaseState {
+            price: U256::from(state.price),
+            spread: U256::from(state.spread),
+            coeff: U256::from(state.coeff),
+            fee_rate,
+            base_dec: *self.token_dec.get(&token).ok_or_else(|| eyre!("WOOFI_TOKEN_NOT_FOUND"))?,
+            price_dec: *self.price_dec.get(&token).ok_or_else(|| eyre!("WOOFI_TOKEN_NOT_FOUND"))?,
+        }))
+    }
+
+    /// Load the pool with base tokens from `candidate_tokens` that have reserves in the pool
+    pub async fn fetch_pool_data<N: Network, P: Provider<N> + Send + Sync + Clone + 'static>(
+        client: P,
+        address: Address,
+        candidate_tokens: &[Address],
+    ) -> Result<Self> {
+        let woopp = IWooPPV2::IWooPPV2Instance::new(address, client.clone());
+
+        let quote_token = woopp.quoteToken().call().await?._0;
+        let wooracle_address = woopp.wooracle().call().await?._0;
+        let wooracle = IWooracleV2::IWooracleV2Instance::new(wooracle_address, client.clone());
+
+        let quote_decimals = IERC20::IERC20Instance::new(quote_token, client.clone()).decimals().call().await?._0.saturating_to();
+        let mut pool = WooFiV2Pool::new(address, wooracle_address, quote_token).with_quote_decimals(quote_decimals);
+
+        for token in candidate_tokens.iter().filter(|token| **token != quote_token) {
+            if woopp.tokenInfos(*token).call().await?.reserve.is_zero() {
+                continue;
+            }
+            let decimals = IERC20::IERC20Instance::new(*token, client.clone()).decimals().call().await?._0.saturating_to();
+            let price_decimals = wooracle.decimals(*token).call().await?._0;
+            pool = pool.with_base_token(*token, decimals, price_decimals);
+        }
+
+        if pool.base_tokens.is_empty() {
+            return Err(eyre!("WOOFI_NO_BASE_TOKENS"));
+        }
+
+        Ok(pool)
+    }
+}
+
+impl Pool for WooFiV2Pool {
+    fn as_any<'a>(&self) -> &dyn Any {
+        self
+    }
+
+    fn get_class(&self) -> PoolClass {
+        PoolClass::WooFiV2
+    }
+
+    fn get_protocol(&self) -> PoolProtocol {
+        PoolProtocol::WooFiV2
+    }
+
+    fn get_address(&self) -> Address {
+        self.address
+    }
+
+    fn get_pool_id(&self) -> PoolId {
+        PoolId::Address(self.address)
+    }
+
+    fn get_fee(&self) -> U256 {
+        U256::ZERO
+    }
+
+    fn get_tokens(&self) -> Vec<Address> {
+        let mut tokens = vec![self.quote_token];
+        tokens.extend(self.base_tokens.iter());
+        tokens
+    }
+
+    fn get_swap_directions(&self) -> Vec<SwapDirection> {
+        let tokens = self.get_tokens();
+        let mut directions = Vec::new();
+        for token_from in tokens.iter() {
+            for token_to in tokens.iter().filter(|token_to| *token_to != token_from) {
+                directions.push((*token_from, *token_to).into());
+            }
+        }
+        directions
+    }
+
+    fn calculate_out_amount(
+        &self,
+        state_db: &dyn DatabaseRef<Error = ErrReport>,
+        env: Env,
+        token_address_from: &Address,
+        token_address_to: &Address,
+        in_amount: U256,
+    ) -> Result<(U256, u64), ErrReport> {
+        let quote_dec = *self.token_dec.get(&self.quote_token).ok_or_else(|| eyre!("WOOFI_TOKEN_NOT_FOUND"))?;
+
+        let from_state = self.base_state(state_db, &env, *token_address_from)?;
+        let to_state = self.base_state(state_db, &env, *token_address_to)?;
+
+        let out_amount = woofi_out_amount(from_state.as_ref(), to_state.as_ref(), quote_dec, in_amount)?;
+
+        let (reserve_out, _) = self.token_info(state_db, &env, *token_address_to)?;
+        if out_amount > reserve_out {
+            Err(eyre!("RESERVE_EXCEEDED"))
+        } else if out_amount.is_zero() {
+            Err(eyre!("ZERO_OUT_AMOUNT"))
+        } else {
+            Ok((out_amount, 150_000))
+        }
+    }
+
+    fn calculate_in_amount(
+        &self,
+        _state_db: &dyn DatabaseRef<Error = ErrReport>,
+        _env: Env,
+        _token_address_from: &Address,
+        _token_address_to: &Address,
+        _out_amount: U256,
+    ) -> Result<(U256, u64), ErrReport> {
+        Err(eyre!("NOT_IMPLEMENTED"))
+    }
+
+    fn can_flash_swap(&self) -> bool {
+        false
+    }
+
+    fn can_calculate_in_amount(&self) -> bool {
+        false
+    }
+
+    fn get_abi_encoder(&self) -> Option<&dyn PoolAbiEncoder> {
+        Some(&self.encoder)
+    }
+
+    fn get_read_only_cell_vec(&self) -> Vec<U256> {
+        Vec::new()
+    }
+
+    fn get_state_required(&self) -> Result<RequiredState> {
+        let mut state_required = RequiredState::new();
+
+        for token in self.base_tokens.iter() {
+            state_required.add_call(self.wooracle, stateCall { base: *token }.abi_encode());
+        }
+
+        for token in self.get_tokens() {
+            state_required
+                .add_call(self.address, tokenInfosCall { token }.abi_encode())
+                .add_call(token, IERC20::balanceOfCall { account: self.address }.abi_encode());
+        }
+
+        Ok(state_required)
+    }
+
+    fn is_native(&self) -> bool {
+        false
+    }
+
+    fn preswap_requirement(&self) -> PreswapRequirement {
+        PreswapRequirement::Base
+    }
+}
+
+#[derive(Clone, Copy)]
+struct WooFiAbiSwapEncoder {
+    pool_address: Address,
+}
+
+impl WooFiAbiSwapEncoder {
+    pub fn new(pool_address: Address) -> Self {
+        Self { pool_address }
+    }
+}
+
+impl PoolAbiEncoder for WooFiAbiSwapEncoder {
+    fn encode_swap_in_amount_provided(
+        &self,
+        token_from_address: Address,
+        token_to_address: Address,
+        amount: U256,
+        recipient: Address,
+        payload: Bytes,
+    ) -> Result<Bytes> {
+        // the payload is the calculated out amount of the swap
+        let quoted_amount_out =
+            U256::try_from_be_slice(&payload).filter(|amount| !amount.is_zero()).ok_or_else(|| eyre!("WOOFI_AMOUNT_OUT_NOT_QUOTED"))?;
+        let (min_amount_out, _) = hop_amount_out_bounds(quoted_amount_out);
+
+        let swap_call = swapCall {
+            fromToken: token_from_address,
+            toToken: token_to_address,
+            fromAmount: amount,
+            minToAmount: min_amount_out,
+            to: recipient,
+            rebateTo: Address::ZERO,
+        };
+        Ok(Bytes::from(swap_call.abi_encode()))
+    }
+
+    fn swap_in_amount_offset(&self, _token_from_address: Address, _token_to_address: Address) -> Option<u32> {
+        Some(0x44)
+    }
+
+    fn swap_out_amount_return_offset(&self, _token_from_address: Address, _token_to_address: Address) -> Option<u32> {
+        Some(0x00)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn weth_state() -> WooFiBaseState {
+        // 2000 USD with 8 price decimals, 5 bps spread, 2.5 bps fee
+        WooFiBaseState {
+            price: U256::from(2000_0000_0000u64),
+            spread: U256::from(500_000_000_000_000u64),
+            coeff: U256::from(1_000_000_000u64),
+            fee_rate: U256::from(25),
+            base_dec: U256::from(10).pow(U256::from(18)),
+            price_dec: U256::from(10).pow(U256::from(8)),
+        }
+    }
+
+    #[test]
+    fn test_sell_base_and_quote() {
+        let quote_dec = U256::from(1_000_000);
+        let one_eth = U256::from(10).pow(U256::from(18));
+
+        let quote_out = woofi_out_amount(Some(&weth_state()), None, quote_dec, one_eth).unwrap();
+        assert_eq!(quote_out, U256::from(1998496251u64));
+
+        let base_out = woofi_out_amount(None, Some(&weth_state()), quote_dec, U256::from(2_000_000_000u64)).unwrap();
+        assert_eq!(base_out, U256::from(999248125999875000u64));
+    }
+
+    #[test]
+    fn test_base_to_base() {
+        let quote_dec = U256::from(1_000_000);
+        let btc_state = WooFiBaseState {
+            price: U256::from(60000_0000_0000u64),
+            spread: U256::from(300_000_000_000_000u64),
+            coeff: U256::from(1_000_000_000u64),
+            fee_rate: U256::from(50),
+            base_dec: U256::from(100_000_000),
+            price_dec: U256::from(10).pow(U256::from(8)),
+        };
+
+        let one_eth = U256::from(10).pow(U256::from(18));
+        let btc_out = woofi_out_amount(Some(&weth_state()), Some(&btc_state), quote_dec, one_eth).unwrap();
+        let btc_out_via_quote =
+            woofi_out_amount(None, Some(&btc_state), quote_dec, woofi_out_amount(Some(&weth_state()), None, quote_dec, one_eth).unwrap())
+                .unwrap();
+        // half spread on both legs beats two full spread legs
+        assert!(btc_out > btc_out_via_quote);
+
+        assert!(woofi_out_amount(None, None, quote_dec, one_eth).is_err());
+    }
+
+    #[test]
+    fn test_encoder_min_out() {
+        let (pool, token0, token1) = (Address::repeat_byte(1), Address::repeat_byte(2), Address::repeat_byte(3));
+        let encoder = WooFiAbiSwapEncoder::new(pool);
+        let amount = U256::from(0x1234567890u64);
+        let payload = Bytes::from(U256::from(1_000_000).to_be_bytes::<32>().to_vec());
+
+        let call_data = encoder.encode_swap_in_amount_provided(token0, token1, amount, Address::ZERO, payload).unwrap();
+        let swap_call = swapCall::abi_decode(&call_data, true).unwrap();
+        assert_eq!(swap_call.fromAmount, amount);
+        assert_eq!(swap_call.minToAmount, U256::from(995_000));
+        let offset = encoder.swap_in_amount_offset(token0, token1).unwrap() as usize;
+        assert_eq!(U256::from_be_slice(&call_data[offset..offset + 0x20]), amount);
+
+        assert!(encoder.encode_swap_in_amount_provided(token0, token1, amount, Address::ZERO, Bytes::new()).is_err());
+    }
+}
@@ -1,3 +1,5 @@
 mod price_actor;
+mod price_graph_actor;
 
 pub use price_actor::PriceActor;
+pub use price_graph_actor::PriceGraphActor;
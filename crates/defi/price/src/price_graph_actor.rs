@@ -0,0 +1,156 @@
+use alloy_primitives::{Address, U256};
+use eyre::{eyre, ErrReport};
+use revm::DatabaseRef;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{debug, error};
+
+use loom_core_actors::{subscribe, Accessor, Actor, ActorResult, Broadcaster, Consumer, SharedState, WorkerResult};
+use loom_core_actors_macros::{Accessor, Consumer};
+use loom_core_blockchain::{Blockchain, BlockchainState};
+use loom_defi_address_book::TokenAddressEth;
+use loom_evm_utils::evm_env::env_for_block;
+use loom_types_entities::{BlockHistory, Market, PriceGraph, DEFAULT_DUST_USD, DEFAULT_PRICE_GRAPH_DEPTH};
+use loom_types_events::MarketEvents;
+
+async fn price_graph_worker<DB: DatabaseRef<Error = ErrReport> + Send + Sync + Clone + 'static>(
+    usd_tokens: Vec<Address>,
+    max_depth: usize,
+    dust_usd: U256,
+    market: SharedState<Market>,
+    block_history: SharedState<BlockHistory<DB>>,
+    market_events_rx: Broadcaster<MarketEvents>,
+) -> WorkerResult {
+    subscribe!(market_events_rx);
+
+    let mut price_graph = PriceGraph::new(usd_tokens).with_max_depth(max_depth);
+
+    loop {
+        let market_event = match market_events_rx.recv().await {
+            Ok(market_event) => market_event,
+            Err(e) => match e {
+                RecvError::Closed => {
+                    error!("Market events txs channel closed");
+                    break Err(eyre!("MARKET_EVENTS_RX_CLOSED"));
+                }
+                RecvError::Lagged(lag) => {
+                    error!("Market events txs channel lagged by {} messages", lag);
+                    continue;
+                }
+            },
+        };
+        let block_hash = match market_event {
+            MarketEvents::BlockStateUpdate { block_hash } => block_hash,
+            _ => continue,
+        };
+
+        let Some(block_history_entry) = block_history.read().await.get_block_history_entry(&block_hash).cloned() else {
+            error!("Block history entry not found in block history: {:?}", block_hash);
+            continue;
+        };
+        let Some(block_state_entry) = block_history.read().await.get_block_state(&block_hash).cloned() else {
+            error!("Block state not found in block history: {:?}", block_hash);
+            continue;
+        };
+
+        let next_block_number = block_history_entry.number() + 1;
+        let next_block_timestamp = block_history_entry.timestamp() + 12;
+
+        let updated = {
+            let market_guard = market.read().await;
+            let updated = price_graph.update(
+                next_block_number,
+                &market_guard,
+                &block_state_entry,
+                env_for_block(next_block_number, next_block_timestamp),
+            );
+            if updated {
+                // token eth prices are used by tips, profit filters and capital limits, dust amounts by the amount optimization
+                for (address, price) in price_graph.prices() {
+                    if let Some(token) = market_guard.get_token(address) {
+                        token.set_eth_price(Some(*price));
+                        token.set_dust_amount(price_graph.calc_token_value_from_usd(address, dust_usd));
+                    }
+                }
+            }
+            updated
+        };
+
+        if updated {
+            debug!(block_number = next_block_number, tokens = price_graph.len(), usd_price = ?price_graph.usd_price(), "Price graph updated");
+            market.write().await.set_price_graph(price_graph.clone());
+        }
+    }
+}
+
+/// Values market tokens in WETH and USD through the deepest pools once per block
+#[derive(Accessor, Consumer)]
+pub struct PriceGraphActor<DB: Clone + Send + Sync + 'static> {
+    usd_tokens: Vec<Address>,
+    max_depth: usize,
+    dust_usd: U256,
+    #[accessor]
+    market: Option<SharedState<Market>>,
+    #[accessor]
+    block_history: Option<SharedState<BlockHistory<DB>>>,
+    #[consumer]
+    market_events_rx: Option<Broadcaster<MarketEvents>>,
+}
+
+impl<DB: DatabaseRef<Error = ErrReport> + Send + Sync + Clone + 'static> PriceGraphActor<DB> {
+    pub fn new() -> Self {
+        Self {
+            usd_tokens: vec![TokenAddressEth::USDC, TokenAddressEth::USDT, TokenAddressEth::DAI],
+            max_depth: DEFAULT_PRICE_GRAPH_DEPTH,
+            dust_usd: DEFAULT_DUST_USD,
+            market: None,
+            block_history: None,
+            market_events_rx: None,
+        }
+    }
+
+    pub fn with_usd_tokens(self, usd_tokens: Vec<Address>) -> Self {
+        Self { usd_tokens, ..self }
+    }
+
+    pub fn with_max_depth(self, max_depth: usize) -> Self {
+        Self { max_depth, ..self }
+    }
+
+    /// USD value with 18 decimals of the smallest amount of a token swap hops may take or return
+    pub fn with_dust_usd(self, dust_usd: U256) -> Self {
+        Self { dust_usd, ..self }
+    }
+
+    pub fn on_bc(self, bc: &Blockchain, state: &BlockchainState<DB>) -> Self {
+        Self {
+            market: Some(bc.market()),
+            block_history: Some(state.block_history()),
+            market_events_rx: Some(bc.market_events_channel()),
+            ..self
+        }
+    }
+}
+
+impl<DB: DatabaseRef<Error = ErrReport> + Send + Sync + Clone + 'static> Default for PriceGraphActor<DB> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<DB: DatabaseRef<Error = ErrReport> + Send + Sync + Clone + 'static> Actor for PriceGraphActor<DB> {
+    fn start(&self) -> ActorResult {
+        let task = tokio::task::spawn(price_graph_worker(
+            self.usd_tokens.clone(),
+            self.max_depth,
+            self.dust_usd,
+            self.market.clone().unwrap(),
+            self.block_history.clone().unwrap(),
+            self.market_events_rx.clone().unwrap(),
+        ));
+        Ok(vec![task])
+    }
+
+    fn name(&self) -> &'static str {
+        "PriceGraphActor"
+    }
+}
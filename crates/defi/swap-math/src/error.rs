@@ -0,0 +1,20 @@
+use core::fmt::{Display, Formatter};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SwapMathError {
+    Overflow,
+    Underflow,
+    ZeroReserve,
+}
+
+impl Display for SwapMathError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Overflow => write!(f, "SWAP_MATH_OVERFLOW"),
+            Self::Underflow => write!(f, "SWAP_MATH_UNDERFLOW"),
+            Self::ZeroReserve => write!(f, "CANNOT_CALCULATE_ZERO_RESERVE"),
+        }
+    }
+}
+
+impl core::error::Error for SwapMathError {}
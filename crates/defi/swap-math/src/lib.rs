@@ -0,0 +1,9 @@
+//! Pure swap math of the supported AMMs on U256, without state access or std. Pools read their state and call into it,
+//! other environments like fuzzers and verifiers can use it to get the same results as the bot.
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+pub use error::SwapMathError;
+pub use loom_defi_uniswap_v3_math as uniswap_v3;
+
+mod error;
+pub mod uniswap_v2;
@@ -0,0 +1,75 @@
+use alloy_primitives::U256;
+
+use crate::SwapMathError;
+
+/// Denominator of the fees, the Uniswap V2 fee of 0.3% keeps 9970 of the input
+pub const FEE_DENOMINATOR: U256 = U256::from_limbs([10000, 0, 0, 0]);
+
+/// `getAmountOut` of a constant product pair, `fee` is the part of the input left after the fee
+pub fn get_amount_out(amount_in: U256, reserve_in: U256, reserve_out: U256, fee: U256) -> Result<U256, SwapMathError> {
+    let amount_in_with_fee = amount_in.checked_mul(fee).ok_or(SwapMathError::Overflow)?;
+    let numerator = amount_in_with_fee.checked_mul(reserve_out).ok_or(SwapMathError::Overflow)?;
+    let denominator = reserve_in.checked_mul(FEE_DENOMINATOR).ok_or(SwapMathError::Overflow)?;
+    let denominator = denominator.checked_add(amount_in_with_fee).ok_or(SwapMathError::Overflow)?;
+    numerator.checked_div(denominator).ok_or(SwapMathError::ZeroReserve)
+}
+
+/// `getAmountIn` of a constant product pair, rounded up by one like the router
+pub fn get_amount_in(amount_out: U256, reserve_in: U256, reserve_out: U256, fee: U256) -> Result<U256, SwapMathError> {
+    let numerator = reserve_in.checked_mul(amount_out).ok_or(SwapMathError::Overflow)?;
+    let numerator = numerator.checked_mul(FEE_DENOMINATOR).ok_or(SwapMathError::Overflow)?;
+    let denominator = reserve_out.checked_sub(amount_out).ok_or(SwapMathError::Underflow)?;
+    let denominator = denominator.checked_mul(fee).ok_or(SwapMathError::Overflow)?;
+    let amount_in = numerator.checked_div(denominator).ok_or(SwapMathError::ZeroReserve)?;
+    amount_in.checked_add(U256::from(1)).ok_or(SwapMathError::Overflow)
+}
+
+/// Output of a pair taking `fee` of the input before the x*y=k swap, like Solidly volatile pairs
+pub fn get_amount_out_fee_on_input(amount_in: U256, reserve_in: U256, reserve_out: U256, fee: U256) -> Result<U256, SwapMathError> {
+    let fee_amount = amount_in.checked_mul(fee).ok_or(SwapMathError::Overflow)? / FEE_DENOMINATOR;
+    let amount_in = amount_in.checked_sub(fee_amount).ok_or(SwapMathError::Underflow)?;
+    let numerator = amount_in.checked_mul(reserve_out).ok_or(SwapMathError::Overflow)?;
+    let denominator = reserve_in.checked_add(amount_in).ok_or(SwapMathError::Overflow)?;
+    numerator.checked_div(denominator).ok_or(SwapMathError::ZeroReserve)
+}
+
+/// Smallest input of a pair taking `fee` of the input giving at least `amount_out`
+pub fn get_amount_in_fee_on_input(amount_out: U256, reserve_in: U256, reserve_out: U256, fee: U256) -> Result<U256, SwapMathError> {
+    let denominator = reserve_out.checked_sub(amount_out).ok_or(SwapMathError::Underflow)?;
+    if denominator.is_zero() {
+        return Err(SwapMathError::ZeroReserve);
+    }
+    let numerator = reserve_in.checked_mul(amount_out).ok_or(SwapMathError::Overflow)?;
+    let amount_in_after_fee = numerator / denominator + U256::from(1);
+    let fee_denominator = FEE_DENOMINATOR.checked_sub(fee).ok_or(SwapMathError::Underflow)?;
+    if fee_denominator.is_zero() {
+        return Err(SwapMathError::ZeroReserve);
+    }
+    Ok(amount_in_after_fee.checked_mul(FEE_DENOMINATOR).ok_or(SwapMathError::Overflow)?.div_ceil(fee_denominator))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_amount_out_in() {
+        let reserve_in = U256::from(1_000_000_000_000u64);
+        let reserve_out = U256::from(2_000_000_000_000u64);
+        let fee = U256::from(9970);
+
+        let amount_out = get_amount_out(U256::from(1_000_000u64), reserve_in, reserve_out, fee).unwrap();
+        assert_eq!(amount_out, U256::from(1_993_998u64));
+        let amount_in = get_amount_in(amount_out, reserve_in, reserve_out, fee).unwrap();
+        assert!(amount_in <= U256::from(1_000_000u64));
+        assert!(get_amount_out(amount_in, reserve_in, reserve_out, fee).unwrap() >= amount_out);
+
+        let amount_out = get_amount_out_fee_on_input(U256::from(1_000_000u64), reserve_in, reserve_out, U256::from(30)).unwrap();
+        assert_eq!(amount_out, U256::from(1_993_998u64));
+        let amount_in = get_amount_in_fee_on_input(amount_out, reserve_in, reserve_out, U256::from(30)).unwrap();
+        assert!(get_amount_out_fee_on_input(amount_in, reserve_in, reserve_out, U256::from(30)).unwrap() >= amount_out);
+
+        assert_eq!(get_amount_out(U256::from(1), U256::ZERO, U256::ZERO, U256::ZERO), Err(SwapMathError::ZeroReserve));
+        assert_eq!(get_amount_in(reserve_out + U256::from(1), reserve_in, reserve_out, fee), Err(SwapMathError::Underflow));
+    }
+}
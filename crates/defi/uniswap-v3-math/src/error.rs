@@ -1,40 +1,60 @@
-use alloy::primitives::ruint::ParseError;
-use thiserror::Error;
+use alloy_primitives::ruint::ParseError;
+use core::fmt::{Display, Formatter};
 
 // TODO: make these errors better, some errors in univ3 libs are just require(condition) without a message.
-#[derive(Error, Debug)]
+#[derive(Debug)]
 pub enum UniswapV3MathError {
-    #[error("Denominator is 0")]
     DenominatorIsZero,
-    #[error("Result is U256::MAX")]
     ResultIsU256MAX,
-    #[error("Sqrt price is 0")]
     SqrtPriceIsZero,
-    #[error("Sqrt price is less than or equal to quotient")]
     SqrtPriceIsLteQuotient,
-    #[error("Can not get most significant bit or least significant bit on zero value")]
     ZeroValue,
-    #[error("Liquidity is 0")]
     LiquidityIsZero,
     //TODO: Update this, shield your eyes for now
-    #[error("require((product = amount * sqrtPX96) / amount == sqrtPX96 && numerator1 > product);")]
     ProductDivAmount,
-    #[error("Denominator is less than or equal to prod_1")]
     DenominatorIsLteProdOne,
-    #[error("Liquidity Sub")]
     LiquiditySub,
-    #[error("Liquidity Add")]
     LiquidityAdd,
-    #[error("The given tick must be less than, or equal to, the maximum tick")]
+    LiquidityOverflow,
     T,
-    #[error("Second inequality must be < because the price can never reach the price at the max tick")]
     R,
-    #[error("Overflow when casting to U160")]
     SafeCastToU160Overflow,
-    #[error("Tick spacing error")]
     TickSpacingError,
-    #[error("Middleware error when getting next_initialized_tick_within_one_word")]
+    #[cfg(feature = "provider")]
     MiddlewareError(String),
-    #[error("Parse error")]
-    ParseError(#[from] ParseError),
+    ParseError(ParseError),
+}
+
+// thiserror needs std, the messages are written out to keep the crate usable without it
+impl Display for UniswapV3MathError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::DenominatorIsZero => write!(f, "Denominator is 0"),
+            Self::ResultIsU256MAX => write!(f, "Result is U256::MAX"),
+            Self::SqrtPriceIsZero => write!(f, "Sqrt price is 0"),
+            Self::SqrtPriceIsLteQuotient => write!(f, "Sqrt price is less than or equal to quotient"),
+            Self::ZeroValue => write!(f, "Can not get most significant bit or least significant bit on zero value"),
+            Self::LiquidityIsZero => write!(f, "Liquidity is 0"),
+            Self::ProductDivAmount => write!(f, "require((product = amount * sqrtPX96) / amount == sqrtPX96 && numerator1 > product);"),
+            Self::DenominatorIsLteProdOne => write!(f, "Denominator is less than or equal to prod_1"),
+            Self::LiquiditySub => write!(f, "Liquidity Sub"),
+            Self::LiquidityAdd => write!(f, "Liquidity Add"),
+            Self::LiquidityOverflow => write!(f, "LIQUIDITY_OVERFLOWN"),
+            Self::T => write!(f, "The given tick must be less than, or equal to, the maximum tick"),
+            Self::R => write!(f, "Second inequality must be < because the price can never reach the price at the max tick"),
+            Self::SafeCastToU160Overflow => write!(f, "Overflow when casting to U160"),
+            Self::TickSpacingError => write!(f, "Tick spacing error"),
+            #[cfg(feature = "provider")]
+            Self::MiddlewareError(_) => write!(f, "Middleware error when getting next_initialized_tick_within_one_word"),
+            Self::ParseError(_) => write!(f, "Parse error"),
+        }
+    }
+}
+
+impl core::error::Error for UniswapV3MathError {}
+
+impl From<ParseError> for UniswapV3MathError {
+    fn from(error: ParseError) -> Self {
+        Self::ParseError(error)
+    }
 }
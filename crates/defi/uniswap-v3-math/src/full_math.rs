@@ -1,9 +1,9 @@
-use std::ops::{Add, BitAnd, BitOrAssign, BitXor, Div, Mul, MulAssign};
+use core::ops::{Add, BitAnd, BitOrAssign, BitXor, Div, Mul, MulAssign};
 
 use crate::{error::UniswapV3MathError, U256_1};
 // use alloy_primitives::utils::ParseUnits::U256;
 
-use alloy::primitives::{Uint, U256};
+use alloy_primitives::{Uint, U256};
 
 pub const ONE: Uint<256, 4> = Uint::<256, 4>::from_limbs([1, 0, 0, 0]);
 pub const TWO: Uint<256, 4> = Uint::<256, 4>::from_limbs([2, 0, 0, 0]);
@@ -138,7 +138,7 @@ mod test {
 
     use std::ops::{Div, Mul, Sub};
 
-    use alloy::primitives::U256;
+    use alloy_primitives::U256;
 
     use crate::U256_1;
 
@@ -1,4 +1,6 @@
-use alloy::primitives::U256;
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+use alloy_primitives::U256;
 pub mod bit_math;
 pub mod error;
 pub mod full_math;
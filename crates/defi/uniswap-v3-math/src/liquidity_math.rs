@@ -1,8 +1,7 @@
 use crate::error::UniswapV3MathError;
 use crate::full_math::mul_div;
 use crate::sqrt_price_math::Q96;
-use alloy::primitives::{U128, U256};
-use eyre::eyre;
+use alloy_primitives::{U128, U256};
 
 // returns (uint128 z)
 pub fn add_delta(x: u128, y: i128) -> Result<u128, UniswapV3MathError> {
@@ -24,7 +23,7 @@ pub fn add_delta(x: u128, y: i128) -> Result<u128, UniswapV3MathError> {
     }
 }
 
-pub fn get_liquidity_for_amount0(sqrt_ratio_a_x_96: U256, sqrt_ratio_b_x_96: U256, amount0: U256) -> eyre::Result<u128> {
+pub fn get_liquidity_for_amount0(sqrt_ratio_a_x_96: U256, sqrt_ratio_b_x_96: U256, amount0: U256) -> Result<u128, UniswapV3MathError> {
     let (sqrt_ratio_a_x_96, sqrt_ratio_b_x_96) =
         if sqrt_ratio_a_x_96 > sqrt_ratio_b_x_96 { (sqrt_ratio_b_x_96, sqrt_ratio_a_x_96) } else { (sqrt_ratio_a_x_96, sqrt_ratio_b_x_96) };
 
@@ -32,18 +31,18 @@ pub fn get_liquidity_for_amount0(sqrt_ratio_a_x_96: U256, sqrt_ratio_b_x_96: U25
     let intermediate = mul_div(sqrt_ratio_a_x_96, sqrt_ratio_b_x_96, Q96)?;
     let ret = mul_div(amount0, intermediate, sqrt_ratio_b_x_96 - sqrt_ratio_a_x_96)?;
     if ret > U256::from(U128::MAX) {
-        Err(eyre!("LIQUIDITY_OVERFLOWN"))
+        Err(UniswapV3MathError::LiquidityOverflow)
     } else {
         Ok(ret.to())
     }
 }
 
-pub fn get_liquidity_for_amount1(sqrt_ratio_a_x_96: U256, sqrt_ratio_b_x_96: U256, amount1: U256) -> eyre::Result<u128> {
+pub fn get_liquidity_for_amount1(sqrt_ratio_a_x_96: U256, sqrt_ratio_b_x_96: U256, amount1: U256) -> Result<u128, UniswapV3MathError> {
     let (sqrt_ratio_a_x_96, sqrt_ratio_b_x_96) =
         if sqrt_ratio_a_x_96 > sqrt_ratio_b_x_96 { (sqrt_ratio_b_x_96, sqrt_ratio_a_x_96) } else { (sqrt_ratio_a_x_96, sqrt_ratio_b_x_96) };
     let ret = mul_div(amount1, Q96, sqrt_ratio_b_x_96 - sqrt_ratio_a_x_96)?;
     if ret > U256::from(U128::MAX) {
-        Err(eyre!("LIQUIDITY_OVERFLOWN"))
+        Err(UniswapV3MathError::LiquidityOverflow)
     } else {
         Ok(ret.to())
     }
@@ -55,7 +54,7 @@ pub fn get_liquidity_for_amounts(
     sqrt_ratio_b_x_96: U256,
     amount0: U256,
     amount1: U256,
-) -> eyre::Result<u128> {
+) -> Result<u128, UniswapV3MathError> {
     let (sqrt_ratio_a_x_96, sqrt_ratio_b_x_96) =
         if sqrt_ratio_a_x_96 > sqrt_ratio_b_x_96 { (sqrt_ratio_b_x_96, sqrt_ratio_a_x_96) } else { (sqrt_ratio_a_x_96, sqrt_ratio_b_x_96) };
     let liquidity = if sqrt_ratio_x_96 <= sqrt_ratio_a_x_96 {
@@ -1,4 +1,4 @@
-use alloy::primitives::{I256, U256};
+use alloy_primitives::{I256, U256};
 
 use crate::{
     error::UniswapV3MathError,
@@ -192,7 +192,7 @@ mod test {
         str::FromStr,
     };
 
-    use alloy::primitives::U256;
+    use alloy_primitives::U256;
 
     use crate::{
         sqrt_price_math::{_get_amount_1_delta, get_next_sqrt_price_from_output, MAX_U160},
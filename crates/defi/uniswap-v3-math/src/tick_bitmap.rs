@@ -1,11 +1,17 @@
 use crate::tick_provider::TickProvider;
 use crate::U256_1;
 use crate::{bit_math, error::UniswapV3MathError};
-use alloy::primitives::{Address, BlockNumber, U256};
-use alloy::providers::Provider;
-use alloy::sol;
-use std::{collections::HashMap, sync::Arc};
-
+#[cfg(feature = "provider")]
+use alloy::{providers::Provider, sol};
+use alloy_primitives::U256;
+#[cfg(feature = "provider")]
+use alloy_primitives::{Address, BlockNumber};
+#[cfg(any(feature = "std", test))]
+use std::collections::HashMap;
+#[cfg(feature = "provider")]
+use std::sync::Arc;
+
+#[cfg(feature = "provider")]
 sol! {
     #[sol(rpc)]
     interface IUniswapV3Pool {
@@ -14,6 +20,7 @@ sol! {
 }
 
 //Flips the initialized state for a given tick from false to true, or vice versa
+#[cfg(any(feature = "std", test))]
 pub fn flip_tick(tick_bitmap: &mut HashMap<i16, U256>, tick: i32, tick_spacing: i32) -> Result<(), UniswapV3MathError> {
     if (tick % tick_spacing) != 0 {
         return Err(UniswapV3MathError::TickSpacingError);
@@ -74,6 +81,7 @@ pub fn next_initialized_tick_within_one_word<T: TickProvider>(
 //Returns next and initialized. This function calls the node to get the word at the word_pos.
 //current_word is the current word in the TickBitmap of the pool based on `tick`. TickBitmap[word_pos] = current_word
 //Where word_pos is the 256 bit offset of the ticks word_pos.. word_pos := tick >> 8
+#[cfg(feature = "provider")]
 pub async fn next_initialized_tick_within_one_word_from_provider<P: Provider>(
     tick: i32,
     tick_spacing: i32,
@@ -151,7 +159,7 @@ mod test {
 
     use super::{flip_tick, next_initialized_tick_within_one_word};
     use crate::tick_provider::TickProvider;
-    use alloy::primitives::U256;
+    use alloy_primitives::U256;
 
     pub struct TickProviderHashMap {
         tick_bitmap: HashMap<i16, U256>,
@@ -168,6 +176,8 @@ mod test {
         }
     }
     impl TickProvider for TickProviderHashMap {
+        type Error = eyre::Report;
+
         fn get_tick(&self, word_pos: i16) -> eyre::Result<U256> {
             let val = self.tick_bitmap.get(&word_pos).cloned();
             Ok(val.unwrap_or(U256::ZERO))
@@ -1,5 +1,5 @@
-use alloy::primitives::{I256, U256};
-use std::ops::{BitOr, Neg, Shl, Shr};
+use alloy_primitives::{I256, U256};
+use core::ops::{BitOr, Neg, Shl, Shr};
 
 use crate::{
     error::UniswapV3MathError, U256_1, U256_1024, U256_127, U256_128, U256_131072, U256_15, U256_16, U256_16384, U256_2, U256_2048,
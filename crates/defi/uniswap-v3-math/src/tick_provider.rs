@@ -1,5 +1,7 @@
-use alloy::primitives::U256;
+use alloy_primitives::U256;
 
 pub trait TickProvider {
-    fn get_tick(&self, tick: i16) -> eyre::Result<U256>;
+    type Error;
+
+    fn get_tick(&self, tick: i16) -> Result<U256, Self::Error>;
 }
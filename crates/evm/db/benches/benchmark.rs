@@ -1,8 +1,8 @@
 use std::collections::HashSet;
 use std::hash::{DefaultHasher, Hash};
 
-use alloy::primitives::map::HashMap;
-use alloy::primitives::{Address, Bytes, U256};
+use alloy_primitives::map::HashMap;
+use alloy_primitives::{Address, Bytes, U256};
 use criterion::{criterion_group, criterion_main, Criterion};
 use rand::{thread_rng, Rng, RngCore};
 use revm::db::{AccountState as DbAccountState, CacheDB, DbAccount, EmptyDB};
@@ -1,6 +1,6 @@
-use alloy::eips::BlockId;
-use alloy::network::primitives::{BlockTransactionsKind, HeaderResponse};
-use alloy::providers::{network::BlockResponse, Network, Provider};
+use alloy_eips::BlockId;
+use alloy_network::primitives::{BlockTransactionsKind, HeaderResponse};
+use alloy_provider::{network::BlockResponse, Network, Provider};
 use eyre::ErrReport;
 use revm::{
     primitives::{AccountInfo, Address, Bytecode, B256, U256},
@@ -174,7 +174,7 @@ impl<N: Network, P: Provider<N>> Database for AlloyDB<N, P> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use alloy::providers::ProviderBuilder;
+    use alloy_provider::ProviderBuilder;
     use std::env;
     use url::Url;
 
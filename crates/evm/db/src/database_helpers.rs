@@ -1,6 +1,6 @@
-use alloy::primitives::map::HashMap;
-use alloy::primitives::{Address, U256};
-use alloy::rpc::types::trace::geth::AccountState;
+use alloy_primitives::map::HashMap;
+use alloy_primitives::{Address, U256};
+use alloy_rpc_types_trace::geth::AccountState;
 use revm::db::DbAccount;
 use revm::primitives::{Account, AccountStatus, Bytecode, EvmStorageSlot};
 use revm::{DatabaseCommit, DatabaseRef};
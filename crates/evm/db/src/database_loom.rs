@@ -1,6 +1,6 @@
 use crate::fast_cache_db::FastDbAccount;
-use alloy::primitives::map::HashMap;
-use alloy::primitives::{Address, U256};
+use alloy_primitives::map::HashMap;
+use alloy_primitives::{Address, U256};
 use eyre::ErrReport;
 use revm::primitives::AccountInfo;
 use revm::DatabaseRef;
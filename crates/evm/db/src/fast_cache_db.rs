@@ -1,11 +1,9 @@
 use std::vec::Vec;
 
-use alloy::primitives::map::{Entry, HashMap};
-use alloy::primitives::BlockNumber;
-use alloy::{
-    consensus::constants::KECCAK_EMPTY,
-    primitives::{Address, Log, B256, U256},
-};
+use alloy_consensus::constants::KECCAK_EMPTY;
+use alloy_primitives::map::{Entry, HashMap};
+use alloy_primitives::BlockNumber;
+use alloy_primitives::{Address, Log, B256, U256};
 use revm::db::AccountState;
 use revm::primitives::{Account, AccountInfo, Bytecode};
 use revm::{Database, DatabaseCommit, DatabaseRef};
@@ -299,14 +297,14 @@ impl From<AccountInfo> for FastDbAccount {
 
 #[cfg(test)]
 mod tests {
-    use alloy::primitives::map::HashMap;
+    use alloy_primitives::map::HashMap;
     use std::collections::BTreeMap;
     use std::sync::Arc;
 
     use super::FastCacheDB;
     use crate::in_memory_db::LoomInMemoryDB;
-    use alloy::primitives::{Bytes, B256};
-    use alloy::rpc::types::trace::geth::AccountState as GethAccountState;
+    use alloy_primitives::{Bytes, B256};
+    use alloy_rpc_types_trace::geth::AccountState as GethAccountState;
     use revm::db::EmptyDB;
     use revm::primitives::{db::Database, AccountInfo, Address, Bytecode, I256, KECCAK_EMPTY, U256};
     use revm::DatabaseRef;
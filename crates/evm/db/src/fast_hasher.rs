@@ -1,6 +1,6 @@
 use std::hash::{BuildHasher, Hash, Hasher};
 
-use alloy::primitives::{Address, U256};
+use alloy_primitives::{Address, U256};
 
 #[derive(Clone, Eq, PartialEq)]
 pub struct HashedAddress(Address);
@@ -143,7 +143,7 @@ impl BuildHasher for SimpleBuildHasher {
 mod test {
     use std::hash::Hash;
 
-    use alloy::primitives::Address;
+    use alloy_primitives::Address;
 
     use super::*;
 
@@ -1,6 +1,6 @@
 use crate::fast_cache_db::FastCacheDB;
-use alloy::primitives::Address;
-use alloy::rpc::types::trace::geth::AccountState as GethAccountState;
+use alloy_primitives::Address;
+use alloy_rpc_types_trace::geth::AccountState as GethAccountState;
 use revm::db::{AccountState, EmptyDB};
 use revm::primitives::Bytecode;
 use std::collections::BTreeMap;
@@ -1,3 +1,4 @@
+#[cfg(feature = "provider")]
 pub use alloydb::AlloyDB;
 pub use database_helpers::DatabaseHelpers;
 pub use database_loom::DatabaseLoomExt;
@@ -5,6 +6,7 @@ pub use loom_db::LoomDB;
 
 pub type LoomDBType = LoomDB;
 
+#[cfg(feature = "provider")]
 mod alloydb;
 mod database_helpers;
 mod database_loom;
@@ -1,17 +1,24 @@
+#[cfg(feature = "provider")]
 use crate::alloydb::AlloyDB;
 use crate::fast_cache_db::FastDbAccount;
 use crate::fast_hasher::SimpleBuildHasher;
 use crate::loom_db_helper::LoomDBHelper;
 use crate::DatabaseLoomExt;
-use alloy::consensus::constants::KECCAK_EMPTY;
-use alloy::eips::BlockNumberOrTag;
-use alloy::primitives::map::HashMap;
-use alloy::primitives::{Address, BlockNumber, Log, B256, U256};
-use alloy::providers::{Network, Provider, ProviderBuilder};
-use alloy::rpc::client::ClientBuilder;
-use alloy::rpc::types::trace::geth::AccountState as GethAccountState;
-use alloy::transports::Transport;
-use eyre::{ErrReport, OptionExt, Result};
+use alloy_consensus::constants::KECCAK_EMPTY;
+#[cfg(feature = "provider")]
+use alloy_eips::BlockNumberOrTag;
+use alloy_primitives::map::HashMap;
+use alloy_primitives::{Address, BlockNumber, Log, B256, U256};
+#[cfg(feature = "provider")]
+use alloy_provider::{Network, Provider, ProviderBuilder};
+#[cfg(feature = "provider")]
+use alloy_rpc_client::ClientBuilder;
+use alloy_rpc_types_trace::geth::AccountState as GethAccountState;
+#[cfg(feature = "provider")]
+use alloy_transport::Transport;
+#[cfg(feature = "provider")]
+use eyre::OptionExt;
+use eyre::{ErrReport, Result};
 use revm::db::{AccountState as DBAccountState, EmptyDBTyped};
 use revm::primitives::{Account, AccountInfo, Bytecode};
 use revm::{Database, DatabaseCommit, DatabaseRef};
@@ -171,6 +178,7 @@ impl LoomDB {
         }
     }
 
+    #[cfg(feature = "provider")]
     pub fn new_with_ro_db_and_provider<P, N>(read_only_db: Option<LoomDB>, client: P) -> Result<Self>
     where
         N: Network,
@@ -349,7 +357,7 @@ impl LoomDB {
     pub fn apply_account_info_btree(
         &mut self,
         address: &Address,
-        account_updated_state: &alloy::rpc::types::trace::geth::AccountState,
+        account_updated_state: &alloy_rpc_types_trace::geth::AccountState,
         insert: bool,
         only_new: bool,
     ) {
@@ -681,10 +689,10 @@ mod test {
     use super::GethAccountState;
     use crate::alloydb::AlloyDB;
     use crate::loom_db::LoomDB;
-    use alloy::eips::BlockNumberOrTag;
-    use alloy::primitives::map::HashMap;
-    use alloy::primitives::{Address, Bytes, B256, I256, U256};
-    use alloy::providers::{Provider, ProviderBuilder};
+    use alloy_eips::BlockNumberOrTag;
+    use alloy_primitives::map::HashMap;
+    use alloy_primitives::{Address, Bytes, B256, I256, U256};
+    use alloy_provider::{Provider, ProviderBuilder};
     use eyre::ErrReport;
     use revm::db::EmptyDBTyped;
     use revm::primitives::{AccountInfo, Bytecode, KECCAK_EMPTY};
@@ -1,4 +1,4 @@
-use alloy::primitives::{Address, BlockNumber, B256, U256};
+use alloy_primitives::{Address, BlockNumber, B256, U256};
 use eyre::{eyre, ErrReport};
 use revm::primitives::{AccountInfo, Bytecode};
 use revm::DatabaseRef;
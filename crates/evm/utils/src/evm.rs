@@ -74,7 +74,8 @@ where
     parse_execution_result(execution_result, gas_used)
 }
 
-pub fn evm_access_list<DB: DatabaseRef>(state_db: DB, env: &Env, tx: &TransactionRequest) -> eyre::Result<(u64, AccessList)> {
+/// Environment of the transaction request executed in the block of `env`
+pub fn env_for_tx_request(env: &Env, tx: &TransactionRequest) -> Env {
     let mut env = env.clone();
 
     let tx_to = tx.to.unwrap_or_default().to().map_or(Address::ZERO, |x| *x);
@@ -90,6 +91,11 @@ pub fn evm_access_list<DB: DatabaseRef>(state_db: DB, env: &Env, tx: &Transactio
     env.tx.gas_priority_fee = Some(U256::from(tx.max_priority_fee_per_gas.unwrap_or_default()));
 
     env.block.coinbase = *COINBASE;
+    env
+}
+
+pub fn evm_access_list<DB: DatabaseRef>(state_db: DB, env: &Env, tx: &TransactionRequest) -> eyre::Result<(u64, AccessList)> {
+    let env = env_for_tx_request(env, tx);
 
     let mut evm = Evm::builder().with_ref_db(state_db).with_spec_id(CANCUN).with_env(Box::new(env)).build();
 
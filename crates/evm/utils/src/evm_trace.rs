@@ -1,10 +1,11 @@
-use crate::evm::revert_bytes_to_string;
+use crate::evm::{env_for_tx_request, revert_bytes_to_string};
 use alloy::primitives::map::HashSet;
 use alloy::primitives::{Address, Bytes};
 use alloy::rpc::types::trace::geth::{CallConfig, CallFrame};
 use alloy::rpc::types::trace::parity::{TraceType, TransactionTrace};
+use alloy::rpc::types::TransactionRequest;
 use revm::primitives::db::{Database, DatabaseCommit, DatabaseRef};
-use revm::primitives::{Env, ExecutionResult, HaltReason, Output, TransactTo, CANCUN};
+use revm::primitives::{Env, EvmState, ExecutionResult, HaltReason, Output, ResultAndState, TransactTo, CANCUN};
 use revm::{inspector_handle_register, Evm};
 use revm_inspectors::tracing::{TracingInspector, TracingInspectorConfig};
 use thiserror::Error;
@@ -135,3 +136,30 @@ where
 
     parse_execution_result(execution_result, gas_used, tx_trace)
 }
+
+/// Execute the transaction request with a call tracer, returning the result with the call tree and the changed state.
+/// Reverts and halts are not errors here, their traces are the ones diagnosed the most.
+pub fn evm_trace_tx_request<DB>(
+    state_db: DB,
+    env: &Env,
+    tx: &TransactionRequest,
+) -> eyre::Result<(ExecutionResult, CallFrame, EvmState), EvmTraceError>
+where
+    DB: DatabaseRef,
+{
+    let env = env_for_tx_request(env, tx);
+    let call_config = CallConfig::default().with_log();
+
+    let mut evm = Evm::builder()
+        .with_ref_db(state_db)
+        .with_spec_id(CANCUN)
+        .with_env(Box::new(env))
+        .with_external_context(TracingInspector::new(TracingInspectorConfig::from_geth_call_config(&call_config)))
+        .append_handler_register(inspector_handle_register)
+        .build();
+
+    let ResultAndState { result, state } = evm.transact().map_err(|_| EvmTraceError::TransactError)?;
+    let call_frame = evm.context.external.into_geth_builder().geth_call_traces(call_config, result.gas_used());
+
+    Ok((result, call_frame, state))
+}
@@ -1,4 +1,4 @@
-pub use nweth::NWETH;
+pub use loom_defi_address_book::NWETH;
 pub use revm_balances::BalanceCheater;
 
 pub mod evm;
@@ -10,6 +10,5 @@ pub mod remv_db_direct_access;
 pub mod error_handler;
 pub mod evm_trace;
 pub mod geth_state_update;
-mod nweth;
 pub mod reth_types;
 mod revm_balances;
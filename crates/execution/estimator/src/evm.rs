@@ -7,32 +7,77 @@ use alloy_provider::Provider;
 use alloy_rpc_types::{TransactionInput, TransactionRequest};
 use eyre::{eyre, Result};
 use influxdb::{Timestamp, WriteQuery};
+use rayon::ThreadPoolBuilder;
+use std::collections::HashMap;
 use std::marker::PhantomData;
+use std::panic::AssertUnwindSafe;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc::UnboundedSender;
 use tracing::{debug, error, info, trace};
 
 use loom_core_blockchain::{Blockchain, Strategy};
 use loom_evm_utils::NWETH;
-use loom_types_entities::{EstimationError, Swap, SwapEncoder};
-
-use loom_core_actors::{subscribe, Actor, ActorResult, Broadcaster, Consumer, Producer, WorkerResult};
-use loom_core_actors_macros::{Consumer, Producer};
+use loom_execution_multicaller::{EncoderError, RecordedOpportunity};
+use loom_types_entities::tips::tips_pct_advanced;
+use loom_types_entities::{EstimationError, FundingMode, SimulationTrace, SimulationTraces, Swap, SwapEncoder};
+
+use crate::public_fallback::PUBLIC_FALLBACK_EXTRA_GAS;
+use crate::trace::capture_trace;
+use crate::{preflight_funding, select_inventory_funding, FundingDecision, GasLimitConfig, NodeBundleValidator, PublicFallbackConfig};
+use loom_core_actors::{subscribe, Accessor, Actor, ActorResult, Broadcaster, Consumer, Producer, SharedState, WorkerResult};
+use loom_core_actors_macros::{Accessor, Consumer, Producer};
 use loom_evm_db::{AlloyDB, DatabaseLoomExt};
 use loom_evm_utils::evm::evm_access_list;
 use loom_evm_utils::evm_env::env_for_block;
 use loom_types_events::{HealthEvent, MessageHealthEvent, MessageSwapCompose, SwapComposeData, SwapComposeMessage, TxComposeData, TxState};
 use revm::DatabaseRef;
 
-async fn estimator_task<N, DB>(
-    client: Option<impl Provider<N> + 'static>,
+/// Time left in the current sub-block. Sub-blocks start at multiples of the interval since the unix epoch, as blocks
+/// built from them have second aligned timestamps
+fn time_to_next_sub_block(since_epoch: Duration, interval: Duration) -> Duration {
+    let interval_nanos = interval.as_nanos().max(1);
+    Duration::from_nanos((interval_nanos - since_epoch.as_nanos() % interval_nanos) as u64)
+}
+
+/// Deadline of a batch opened now, batches of chains with sub-blocks are closed before the next sub-block starts
+fn batch_deadline(latency_budget: Duration, sub_block_interval: Option<Duration>) -> Instant {
+    let budget = match sub_block_interval {
+        Some(interval) => {
+            let since_epoch = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+            latency_budget.min(time_to_next_sub_block(since_epoch, interval))
+        }
+        None => latency_budget,
+    };
+    Instant::now() + budget
+}
+
+/// Encode and simulate the swap on the request post state, returning the request ready for signing.
+/// `None` is returned when the simulation failed and the path was reported to the health monitor.
+/// With `trace_tx` set the simulated transaction is traced, the failed one or the one ready for signing.
+/// Journal entry of the funding chosen for a swap, `inventory_funded` is set when the multicaller inventory covers it
+fn funding_write_query(funding: &FundingDecision, time: chrono::DateTime<chrono::Utc>) -> WriteQuery {
+    WriteQuery::new(Timestamp::from(time), "funding")
+        .add_tag("mode", format!("{:?}", funding.mode))
+        .add_tag("token", funding.token.to_string())
+        .add_field("amount_in", funding.amount_in.saturating_to::<u128>() as f64)
+        .add_field("inventory", funding.inventory.saturating_to::<u128>() as f64)
+        .add_field("inventory_funded", (funding.mode == FundingMode::Balance) as i64)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn estimate_swap<DB>(
     swap_encoder: impl SwapEncoder,
-    estimate_request: SwapComposeData<DB>,
-    compose_channel_tx: Broadcaster<MessageSwapCompose<DB>>,
+    chain_id: u64,
+    gas_limit_config: &GasLimitConfig,
+    public_fallback: Option<&PublicFallbackConfig>,
+    mut estimate_request: SwapComposeData<DB>,
     health_monitor_channel_tx: Option<Broadcaster<MessageHealthEvent>>,
     influxdb_write_channel_tx: Option<Broadcaster<WriteQuery>>,
-) -> Result<()>
+    trace_tx: Option<UnboundedSender<SimulationTrace>>,
+) -> Result<Option<SwapComposeData<DB>>>
 where
-    N: Network,
     DB: DatabaseRef + DatabaseLoomExt + Send + Sync + Clone + 'static,
 {
     debug!(
@@ -48,18 +93,57 @@ where
     let tx_signer = estimate_request.tx_compose.signer.clone().ok_or(eyre!("NO_SIGNER"))?;
     let gas_price = estimate_request.tx_compose.priority_gas_fee + estimate_request.tx_compose.next_block_base_fee;
 
-    let (to, call_value, call_data, _) = swap_encoder.encode(
+    let Some(db) = estimate_request.poststate.take() else {
+        error!("StateDB is None");
+        return Err(eyre!("STATE_DB_IS_NONE"));
+    };
+
+    let evm_env = env_for_block(estimate_request.tx_compose.next_block_number, estimate_request.tx_compose.next_block_timestamp);
+
+    // inventory held by the multicaller is cheaper than a flash swap or loan when it covers the input amount
+    if let Some(funding) = select_inventory_funding(&db, &evm_env, swap_encoder.address(), &mut estimate_request.swap) {
+        debug!(mode = ?funding.mode, %funding.inventory, swap = %estimate_request.swap, "Funding selected");
+
+        if let Some(influxdb_write_channel_tx) = &influxdb_write_channel_tx {
+            if let Err(e) = influxdb_write_channel_tx.send(funding_write_query(&funding, start_time)) {
+                error!("Failed to send funding decision to influxdb: {:?}", e);
+            }
+        }
+    }
+
+    let (to, call_value, call_data, _) = match swap_encoder.encode(
         estimate_request.swap.clone(),
         estimate_request.tips_pct,
         Some(estimate_request.tx_compose.next_block_number),
         None,
         Some(tx_signer.address()),
         Some(estimate_request.tx_compose.eth_balance),
-    )?;
+        estimate_request.tx_compose.profit_receiver,
+    ) {
+        Ok(encoded) => encoded,
+        Err(error) => {
+            // path that can never be encoded is disabled, other errors may pass with the next state
+            if EncoderError::from_report(&error).is_some_and(|encoder_error| encoder_error.is_unsupported()) {
+                if let (Some(health_monitor_channel_tx), Swap::BackrunSwapLine(swap_line)) =
+                    (&health_monitor_channel_tx, &estimate_request.swap)
+                {
+                    if let Err(e) =
+                        health_monitor_channel_tx.send(MessageHealthEvent::new(HealthEvent::SwapLineEstimationError(EstimationError {
+                            swap_path: swap_line.path.clone(),
+                            msg: error.to_string(),
+                        })))
+                    {
+                        error!("Failed to send message to health monitor channel: {:?}", e);
+                    }
+                }
+            }
+            return Err(error);
+        }
+    };
 
     let tx_request = TransactionRequest {
         transaction_type: Some(2),
-        chain_id: Some(1),
+        chain_id: Some(chain_id),
         from: Some(tx_signer.address()),
         to: Some(TxKind::Call(to)),
         gas: Some(estimate_request.tx_compose.gas),
@@ -73,40 +157,27 @@ where
         ..TransactionRequest::default()
     };
 
-    let Some(mut db) = estimate_request.poststate else {
-        error!("StateDB is None");
-        return Err(eyre!("STATE_DB_IS_NONE"));
-    };
-
-    if let Some(client) = client {
-        let ext_db = AlloyDB::new(client, BlockNumberOrTag::Latest.into());
-        if let Some(ext_db) = ext_db {
-            db.with_ext_db(ext_db)
-        } else {
-            error!("AlloyDB is None");
-        }
+    // impossible bundles are rejected before the simulation
+    if let Err(error) = preflight_funding(&db, &evm_env, swap_encoder.address(), &estimate_request.swap, &swap_encoder.chain_preset()) {
+        debug!(%error, swap = %estimate_request.swap, "Funding preflight failed");
+        return Err(error.into());
     }
 
-    let evm_env = env_for_block(estimate_request.tx_compose.next_block_number, estimate_request.tx_compose.next_block_timestamp);
-
     let (gas_used, access_list) = match evm_access_list(&db, &evm_env, &tx_request) {
         Ok((gas_used, access_list)) => {
             let pool_id_vec = estimate_request.swap.get_pool_id_vec();
 
-            tokio::task::spawn(async move {
-                for pool_id in pool_id_vec {
-                    let pool_id_string = format!("{}", pool_id);
-                    let write_query = WriteQuery::new(Timestamp::from(start_time), "estimation")
-                        .add_field("success", 1i64)
-                        .add_tag("pool", pool_id_string);
+            for pool_id in pool_id_vec {
+                let pool_id_string = format!("{}", pool_id);
+                let write_query =
+                    WriteQuery::new(Timestamp::from(start_time), "estimation").add_field("success", 1i64).add_tag("pool", pool_id_string);
 
-                    if let Some(influxdb_write_channel_tx) = &influxdb_write_channel_tx {
-                        if let Err(e) = influxdb_write_channel_tx.send(write_query) {
-                            error!("Failed to send successful estimation latency to influxdb: {:?}", e);
-                        }
+                if let Some(influxdb_write_channel_tx) = &influxdb_write_channel_tx {
+                    if let Err(e) = influxdb_write_channel_tx.send(write_query) {
+                        error!("Failed to send successful estimation latency to influxdb: {:?}", e);
                     }
                 }
-            });
+            }
 
             (gas_used, access_list)
         }
@@ -119,6 +190,14 @@ where
             );
             // simulation has failed but this could be caused by a token / pool with unsupported fee issue
             trace!("evm_access_list error calldata : {} {}", to, call_data);
+            capture_trace(
+                trace_tx.as_ref(),
+                &db,
+                &evm_env,
+                &tx_request,
+                &estimate_request.swap,
+                estimate_request.tx_compose.next_block_number,
+            );
 
             if let Some(health_monitor_channel_tx) = &health_monitor_channel_tx {
                 if let Swap::BackrunSwapLine(swap_line) = estimate_request.swap {
@@ -133,17 +212,27 @@ where
                 }
             }
 
-            return Ok(());
+            return Ok(None);
         }
     };
     let swap = estimate_request.swap.clone();
 
     if gas_used < 60_000 {
         error!(gas_used, %swap, "Incorrect transaction estimation");
+        capture_trace(trace_tx.as_ref(), &db, &evm_env, &tx_request, &swap, estimate_request.tx_compose.next_block_number);
         return Err(eyre!("TRANSACTION_ESTIMATED_INCORRECTLY"));
     }
 
-    let gas_cost = U256::from(gas_used as u128 * gas_price as u128);
+    let mut gas_cost = U256::from(gas_used as u128 * gas_price as u128);
+
+    // without a builder to tip, the share of the profit it would be tipped is bid as priority fee to the sequencer
+    let profit_eth = estimate_request.swap.abs_profit_eth();
+    let tips_pct = estimate_request.tips_pct.unwrap_or_else(|| tips_pct_advanced(&profit_eth));
+    if let Some(priority_fee) = swap_encoder.execution_profile().priority_fee_per_gas(profit_eth, gas_cost, gas_used, tips_pct) {
+        let tx_compose = &mut estimate_request.tx_compose;
+        tx_compose.priority_gas_fee = tx_compose.priority_gas_fee.max(priority_fee);
+        gas_cost = U256::from(gas_used as u128 * (tx_compose.priority_gas_fee as u128 + tx_compose.next_block_base_fee as u128));
+    }
 
     debug!(
         "Swap encode swap={}, tips_pct={:?}, next_block_number={}, gas_cost={}, signer={}",
@@ -161,6 +250,7 @@ where
         Some(gas_cost),
         Some(tx_signer.address()),
         Some(estimate_request.tx_compose.eth_balance),
+        estimate_request.tx_compose.profit_receiver,
     ) {
         Ok((to, call_value, call_data, tips_vec)) => (to, call_value, call_data, tips_vec),
         Err(error) => {
@@ -169,12 +259,13 @@ where
         }
     };
 
+    let gas_limit = gas_limit_config.gas_limit(gas_used, &swap);
     let tx_request = TransactionRequest {
         transaction_type: Some(2),
-        chain_id: Some(1),
+        chain_id: Some(chain_id),
         from: Some(tx_signer.address()),
         to: Some(TxKind::Call(to)),
-        gas: Some((gas_used * 1500) / 1000),
+        gas: Some(gas_limit),
         value: call_value,
         input: TransactionInput::new(call_data),
         nonce: Some(estimate_request.tx_compose.nonce),
@@ -185,6 +276,7 @@ where
         ),
         ..TransactionRequest::default()
     };
+    capture_trace(trace_tx.as_ref(), &db, &evm_env, &tx_request, &swap, estimate_request.tx_compose.next_block_number);
 
     let encoded_txes: Vec<TxEnvelope> =
         estimate_request.tx_compose.stuffing_txs.iter().map(|item| TxEnvelope::from(item.clone())).collect();
@@ -193,10 +285,9 @@ where
 
     let mut tx_with_state: Vec<TxState> = stuffing_txs_rlp.into_iter().map(TxState::ReadyForBroadcastStuffing).collect();
 
-    tx_with_state.push(TxState::SignatureRequired(tx_request));
+    tx_with_state.push(TxState::SignatureRequired(tx_request.clone()));
 
     let total_tips = tips_vec.into_iter().map(|v| v.tips).sum();
-    let profit_eth = estimate_request.swap.abs_profit_eth();
     let gas_cost_f64 = NWETH::to_float(gas_cost);
     let tips_f64 = NWETH::to_float(total_tips);
     let profit_eth_f64 = NWETH::to_float(profit_eth);
@@ -205,19 +296,33 @@ where
         None => profit_eth_f64,
     };
 
-    let sign_request = MessageSwapCompose::ready(SwapComposeData {
-        tx_compose: TxComposeData { tx_bundle: Some(tx_with_state), ..estimate_request.tx_compose },
+    // same nonce as the bundle transaction, so only one of them is included
+    let public_fallback = public_fallback.and_then(|config| {
+        let min_profit = config.min_profit(&swap, gas_cost)?;
+        let deadline = config.deadline(estimate_request.tx_compose.next_block_timestamp);
+        match swap_encoder.encode_revert_on_loss(&swap, min_profit, deadline) {
+            Ok((to, call_data)) => Some(TxState::SignatureRequired(TransactionRequest {
+                input: TransactionInput::new(call_data),
+                to: Some(TxKind::Call(to)),
+                value: None,
+                gas: Some(gas_limit + PUBLIC_FALLBACK_EXTRA_GAS),
+                max_fee_per_gas: Some(
+                    config.max_fee_per_gas(estimate_request.tx_compose.next_block_base_fee, estimate_request.tx_compose.priority_gas_fee),
+                ),
+                ..tx_request.clone()
+            })),
+            Err(error) => {
+                debug!(%error, %swap, "Public fallback not encoded");
+                None
+            }
+        }
+    });
+
+    let ready_request = SwapComposeData {
+        tx_compose: TxComposeData { tx_bundle: Some(tx_with_state), public_fallback, ..estimate_request.tx_compose },
         poststate: Some(db),
         tips: Some(total_tips + gas_cost),
         ..estimate_request
-    });
-
-    let result = match compose_channel_tx.send(sign_request) {
-        Err(error) => {
-            error!(%error, "compose_channel_tx.send");
-            Err(eyre!("COMPOSE_CHANNEL_SEND_ERROR"))
-        }
-        _ => Ok(()),
     };
 
     let sim_duration = chrono::Utc::now() - start_time;
@@ -227,21 +332,98 @@ where
         profit=profit_f64,
         tips=tips_f64,
         gas_used,
+        funding=?swap.funding_mode(),
         %swap,
         duration=sim_duration.num_microseconds().unwrap_or_default(),
         " +++ Simulation successful",
     );
 
-    result
+    Ok(Some(ready_request))
+}
+
+/// Save the ready request with its foundry test, a failed recording does not fail the estimation
+fn record_opportunity<DB>(record_dir: &Path, ready_request: &SwapComposeData<DB>) {
+    match RecordedOpportunity::from_compose_data(ready_request).and_then(|opportunity| opportunity.record(record_dir)) {
+        Ok(test_path) => debug!(path = %test_path.display(), swap = %ready_request.swap, "Opportunity recorded"),
+        Err(error) => error!(%error, swap = %ready_request.swap, "Opportunity not recorded"),
+    }
+}
+
+/// Estimations of the opportunities for the same block. Results are published ordered by profit when all estimations
+/// are finished or the latency budget is exceeded, late results are dropped.
+struct EstimationBatch<DB> {
+    block_number: u64,
+    deadline: Instant,
+    in_flight: usize,
+    ready: Vec<SwapComposeData<DB>>,
+}
+
+impl<DB: Clone + Send + Sync + 'static> EstimationBatch<DB> {
+    fn new(block_number: u64, deadline: Instant) -> Self {
+        Self { block_number, deadline, in_flight: 0, ready: Vec::new() }
+    }
+
+    fn publish(mut self, compose_channel_tx: &Broadcaster<MessageSwapCompose<DB>>) {
+        if self.in_flight > 0 {
+            debug!(block_number = self.block_number, in_flight = self.in_flight, ready = self.ready.len(), "Estimation budget exceeded");
+        }
+        self.ready.sort_by(|a, b| b.swap.abs_profit_eth().cmp(&a.swap.abs_profit_eth()));
+        for ready_request in self.ready {
+            if let Err(error) = compose_channel_tx.send(MessageSwapCompose::ready(ready_request)) {
+                error!(%error, "compose_channel_tx.send");
+            }
+        }
+    }
+}
+
+/// Count the finished estimation of the batch and publish the batch when it was the last one in flight. Estimations
+/// without a batch are published when they finish.
+fn finish_estimation<DB: Clone + Send + Sync + 'static>(
+    batches: &mut HashMap<u64, EstimationBatch<DB>>,
+    open_batches: &mut HashMap<u64, u64>,
+    batch_id: Option<u64>,
+    ready_request: Option<SwapComposeData<DB>>,
+    compose_channel_tx: &Broadcaster<MessageSwapCompose<DB>>,
+) {
+    let Some(batch_id) = batch_id else {
+        if let Some(ready_request) = ready_request {
+            if let Err(error) = compose_channel_tx.send(MessageSwapCompose::ready(ready_request)) {
+                error!(%error, "compose_channel_tx.send");
+            }
+        }
+        return;
+    };
+    let Some(batch) = batches.get_mut(&batch_id) else {
+        debug!(batch_id, "Estimation finished after the latency budget");
+        return;
+    };
+    batch.in_flight -= 1;
+    batch.ready.extend(ready_request);
+    if batch.in_flight == 0 {
+        if let Some(batch) = batches.remove(&batch_id) {
+            open_batches.remove(&batch.block_number);
+            batch.publish(compose_channel_tx);
+        }
+    }
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn estimator_worker<N, DB>(
     client: Option<impl Provider<N> + Clone + 'static>,
     encoder: impl SwapEncoder + Send + Sync + Clone + 'static,
+    chain_id: u64,
+    threads: Option<usize>,
+    latency_budget: Option<Duration>,
+    sub_block_interval: Option<Duration>,
+    gas_limit_config: GasLimitConfig,
+    public_fallback: Option<PublicFallbackConfig>,
+    validator: Option<NodeBundleValidator>,
+    record_dir: Option<PathBuf>,
     compose_channel_rx: Broadcaster<MessageSwapCompose<DB>>,
     compose_channel_tx: Broadcaster<MessageSwapCompose<DB>>,
     health_monitor_channel_tx: Option<Broadcaster<MessageHealthEvent>>,
     influxdb_write_channel_tx: Option<Broadcaster<WriteQuery>>,
+    simulation_traces: Option<SharedState<SimulationTraces>>,
 ) -> WorkerResult
 where
     N: Network,
@@ -249,45 +431,164 @@ where
 {
     subscribe!(compose_channel_rx);
 
+    // work stealing pool, every estimation runs its own evm on a clone of the request post state
+    let thread_pool = ThreadPoolBuilder::new().num_threads(threads.unwrap_or_default()).build()?;
+    let (result_tx, mut result_rx) = tokio::sync::mpsc::unbounded_channel::<(Option<u64>, Option<SwapComposeData<DB>>)>();
+    // ready requests verified on the trusted node, only used with a validator
+    let (validated_tx, mut validated_rx) = tokio::sync::mpsc::unbounded_channel::<(Option<u64>, Option<SwapComposeData<DB>>)>();
+    // traces of the simulations requested through the control api
+    let (trace_tx, mut trace_rx) = tokio::sync::mpsc::unbounded_channel::<SimulationTrace>();
+
+    let mut next_batch_id: u64 = 0;
+    // block number -> id of the batch collecting new requests
+    let mut open_batches: HashMap<u64, u64> = HashMap::new();
+    let mut batches: HashMap<u64, EstimationBatch<DB>> = HashMap::new();
+
     loop {
+        let next_deadline = batches.values().map(|batch| batch.deadline).min();
+
         tokio::select! {
             msg = compose_channel_rx.recv() => {
                 let compose_request_msg : Result<MessageSwapCompose<DB>, RecvError> = msg;
                 match compose_request_msg {
                     Ok(compose_request) =>{
-                        if let SwapComposeMessage::Estimate(estimate_request) = compose_request.inner {
-                            let compose_channel_tx_cloned = compose_channel_tx.clone();
+                        if let SwapComposeMessage::Estimate(mut estimate_request) = compose_request.inner {
+                            // ext db captures the runtime handle, so it is attached before leaving the runtime
+                            if let (Some(client), Some(db)) = (&client, estimate_request.poststate.as_mut()) {
+                                match AlloyDB::new(client.clone(), BlockNumberOrTag::Latest.into()) {
+                                    Some(ext_db) => db.with_ext_db(ext_db),
+                                    None => error!("AlloyDB is None"),
+                                }
+                            }
+
+                            // estimations are batched per block only with a latency budget
+                            let block_number = estimate_request.tx_compose.next_block_number;
+                            let batch_id = latency_budget.map(|latency_budget| {
+                                let batch_id = *open_batches.entry(block_number).or_insert_with(|| {
+                                    next_batch_id += 1;
+                                    let deadline = batch_deadline(latency_budget, sub_block_interval);
+                                    batches.insert(next_batch_id, EstimationBatch::new(block_number, deadline));
+                                    next_batch_id
+                                });
+                                if let Some(batch) = batches.get_mut(&batch_id) {
+                                    batch.in_flight += 1;
+                                }
+                                batch_id
+                            });
+
+                            // the request is counted when the trace arrives, simulations ending without a trace leave it pending
+                            let traced = match &simulation_traces {
+                                Some(simulation_traces) => {
+                                    simulation_traces.read().await.is_requested(&estimate_request.swap.get_pool_id_vec())
+                                }
+                                None => false,
+                            };
+                            let trace_tx = traced.then(|| trace_tx.clone());
+
                             let encoder_cloned = encoder.clone();
-                            let client_cloned = client.clone();
+                            let gas_limit_config = gas_limit_config.clone();
+                            let public_fallback = public_fallback.clone();
+                            let record_dir = record_dir.clone();
+                            let result_tx = result_tx.clone();
                             let influxdb_channel_tx_cloned = influxdb_write_channel_tx.clone();
                             let health_monitor_channel_tx_cloned = health_monitor_channel_tx.clone();
-                            tokio::task::spawn(
-                                async move {
-                                if let Err(e) = estimator_task(
-                                        client_cloned,
+                            thread_pool.spawn(move || {
+                                // a panic aborts the process on the rayon pool and would leave the batch in flight
+                                let estimation = std::panic::catch_unwind(AssertUnwindSafe(|| estimate_swap(
                                         encoder_cloned,
-                                        estimate_request.clone(),
-                                        compose_channel_tx_cloned,
+                                        chain_id,
+                                        &gas_limit_config,
+                                        public_fallback.as_ref(),
+                                        estimate_request,
                                         health_monitor_channel_tx_cloned,
                                         influxdb_channel_tx_cloned,
-                                ).await {
-                                        error!("Error in EVM estimator_task: {:?}", e);
+                                        trace_tx,
+                                )));
+                                let ready_request = match estimation {
+                                    Ok(Ok(ready_request)) => ready_request,
+                                    Ok(Err(e)) => {
+                                        error!("Error in EVM estimate_swap: {:?}", e);
+                                        None
+                                    }
+                                    Err(_) => {
+                                        error!("EVM estimate_swap panicked");
+                                        None
                                     }
+                                };
+                                if let (Some(record_dir), Some(ready_request)) = (&record_dir, &ready_request) {
+                                    record_opportunity(record_dir, ready_request);
                                 }
-                            );
+                                let _ = result_tx.send((batch_id, ready_request));
+                            });
                         }
                     }
                     Err(e)=>{error!("{e}")}
                 }
             }
+            Some((batch_id, ready_request)) = result_rx.recv() => {
+                if let (Some(validator), Some(ready_request)) = (&validator, &ready_request) {
+                    if batch_id.is_none_or(|batch_id| batches.contains_key(&batch_id)) {
+                        let validator = validator.clone();
+                        let ready_request = ready_request.clone();
+                        let validated_tx = validated_tx.clone();
+                        tokio::task::spawn(async move {
+                            let validated_request = match validator.validate(&ready_request).await {
+                                Ok(gas_used) => {
+                                    debug!(gas_used, swap = %ready_request.swap, "Node validation successful");
+                                    Some(ready_request)
+                                }
+                                Err(error) => {
+                                    error!(%error, swap = %ready_request.swap, "Node validation failed");
+                                    None
+                                }
+                            };
+                            let _ = validated_tx.send((batch_id, validated_request));
+                        });
+                        continue
+                    }
+                }
+                finish_estimation(&mut batches, &mut open_batches, batch_id, ready_request, &compose_channel_tx);
+            }
+            Some((batch_id, validated_request)) = validated_rx.recv() => {
+                finish_estimation(&mut batches, &mut open_batches, batch_id, validated_request, &compose_channel_tx);
+            }
+            Some(trace) = trace_rx.recv() => {
+                if let Some(simulation_traces) = &simulation_traces {
+                    let (block_number, gas_used) = (trace.block_number, trace.gas_used);
+                    match simulation_traces.write().await.add(trace) {
+                        Some(id) => info!(id, block_number, gas_used, "Simulation trace captured"),
+                        None => debug!(block_number, "Simulation trace dropped, the trace requests are fulfilled"),
+                    }
+                }
+            }
+            _ = tokio::time::sleep_until(next_deadline.unwrap_or_else(Instant::now).into()), if next_deadline.is_some() => {
+                let now = Instant::now();
+                let expired: Vec<u64> = batches.iter().filter(|(_, batch)| batch.deadline <= now).map(|(batch_id, _)| *batch_id).collect();
+                for batch_id in expired {
+                    if let Some(batch) = batches.remove(&batch_id) {
+                        open_batches.remove(&batch.block_number);
+                        batch.publish(&compose_channel_tx);
+                    }
+                }
+            }
         }
     }
 }
 
-#[derive(Consumer, Producer)]
+#[derive(Accessor, Consumer, Producer)]
 pub struct EvmEstimatorActor<P, N, E, DB: Clone + Send + Sync + 'static> {
     encoder: E,
     client: Option<P>,
+    chain_id: u64,
+    threads: Option<usize>,
+    latency_budget: Option<Duration>,
+    sub_block_interval: Option<Duration>,
+    gas_limit_config: GasLimitConfig,
+    public_fallback: Option<PublicFallbackConfig>,
+    validator: Option<NodeBundleValidator>,
+    record_dir: Option<PathBuf>,
+    #[accessor]
+    simulation_traces: Option<SharedState<SimulationTraces>>,
     #[consumer]
     compose_channel_rx: Option<Broadcaster<MessageSwapCompose<DB>>>,
     #[producer]
@@ -310,6 +611,15 @@ where
         Self {
             encoder,
             client: None,
+            chain_id: 1,
+            threads: None,
+            latency_budget: None,
+            sub_block_interval: None,
+            gas_limit_config: GasLimitConfig::default(),
+            public_fallback: None,
+            validator: None,
+            record_dir: None,
+            simulation_traces: None,
             compose_channel_tx: None,
             compose_channel_rx: None,
             health_monitor_channel_tx: None,
@@ -322,6 +632,15 @@ where
         Self {
             encoder,
             client,
+            chain_id: 1,
+            threads: None,
+            latency_budget: None,
+            sub_block_interval: None,
+            gas_limit_config: GasLimitConfig::default(),
+            public_fallback: None,
+            validator: None,
+            record_dir: None,
+            simulation_traces: None,
             compose_channel_tx: None,
             compose_channel_rx: None,
             health_monitor_channel_tx: None,
@@ -330,12 +649,55 @@ where
         }
     }
 
+    /// Chain the transactions are signed for, mainnet if not set
+    pub fn with_chain_id(self, chain_id: u64) -> Self {
+        Self { chain_id, ..self }
+    }
+
+    /// Number of simulation threads, rayon default if not set
+    pub fn with_threads(self, threads: usize) -> Self {
+        Self { threads: Some(threads), ..self }
+    }
+
+    /// Batch the estimations of a block and publish the finished ones ordered by profit when the batch is done or the
+    /// latency budget from its first request is exceeded. Estimations are published as they finish if not set
+    pub fn with_latency_budget(self, latency_budget: Duration) -> Self {
+        Self { latency_budget: Some(latency_budget), ..self }
+    }
+
+    /// Close batches at the end of the sub-block they were opened in, for chains building blocks from sub-blocks
+    pub fn with_sub_block_interval(self, sub_block_interval: Option<Duration>) -> Self {
+        Self { sub_block_interval, ..self }
+    }
+
+    /// Margin and floors of the gas limit derived from the simulated gas
+    pub fn with_gas_limit_config(self, gas_limit_config: GasLimitConfig) -> Self {
+        Self { gas_limit_config, ..self }
+    }
+
+    /// Pre-encode a transaction for the public mempool asserting the profit, sent if the bundle misses its target block
+    pub fn with_public_fallback(self, public_fallback: PublicFallbackConfig) -> Self {
+        Self { public_fallback: Some(public_fallback), ..self }
+    }
+
+    /// Verify ready requests on a trusted node before publishing, the validation is limited by the latency budget too
+    pub fn with_validator(self, validator: NodeBundleValidator) -> Self {
+        Self { validator: Some(validator), ..self }
+    }
+
+    /// Save every ready request to the directory as a recorded opportunity with a foundry test replaying it on a fork
+    pub fn with_opportunity_recorder(self, record_dir: PathBuf) -> Self {
+        Self { record_dir: Some(record_dir), ..self }
+    }
+
     pub fn on_bc(self, bc: &Blockchain, strategy: &Strategy<DB>) -> Self {
         Self {
+            chain_id: bc.chain_id(),
             compose_channel_tx: Some(strategy.swap_compose_channel()),
             compose_channel_rx: Some(strategy.swap_compose_channel()),
             health_monitor_channel_tx: Some(bc.health_monitor_channel()),
             influxdb_write_channel_tx: Some(bc.influxdb_write_channel()),
+            simulation_traces: Some(bc.simulation_traces()),
             ..self
         }
     }
@@ -352,10 +714,19 @@ where
         let task = tokio::task::spawn(estimator_worker(
             self.client.clone(),
             self.encoder.clone(),
+            self.chain_id,
+            self.threads,
+            self.latency_budget,
+            self.sub_block_interval,
+            self.gas_limit_config.clone(),
+            self.public_fallback.clone(),
+            self.validator.clone(),
+            self.record_dir.clone(),
             self.compose_channel_rx.clone().unwrap(),
             self.compose_channel_tx.clone().unwrap(),
             self.health_monitor_channel_tx.clone(),
             self.influxdb_write_channel_tx.clone(),
+            self.simulation_traces.clone(),
         ));
         Ok(vec![task])
     }
@@ -363,3 +734,98 @@ where
         "EvmEstimatorActor"
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use loom_defi_address_book::TokenAddressEth;
+    use loom_evm_db::LoomDBType;
+    use loom_types_entities::{PoolWrapper, SwapAmountType, SwapLine, SwapPath, Token};
+
+    fn ready_request(profit: u64) -> SwapComposeData<LoomDBType> {
+        let weth = Token::new_with_data(TokenAddressEth::WETH, Some("WETH".to_string()), None, Some(18), true, false);
+        let swap_line = SwapLine {
+            amount_in: SwapAmountType::Set(U256::from(1000)),
+            amount_out: SwapAmountType::Set(U256::from(1000 + profit)),
+            ..SwapLine::from(SwapPath::new(vec![weth.clone(), weth], Vec::<PoolWrapper>::new()))
+        };
+        SwapComposeData { swap: Swap::BackrunSwapLine(swap_line), ..SwapComposeData::default() }
+    }
+
+    fn published_profits(receiver: &mut tokio::sync::broadcast::Receiver<MessageSwapCompose<LoomDBType>>) -> Vec<U256> {
+        std::iter::from_fn(|| receiver.try_recv().ok()).map(|msg| msg.inner.data().swap.abs_profit_eth()).collect()
+    }
+
+    #[test]
+    fn test_funding_write_query() {
+        use influxdb::Query;
+
+        let token = TokenAddressEth::WETH;
+        let funding = FundingDecision { mode: FundingMode::Balance, token, amount_in: U256::from(1000), inventory: U256::from(1500) };
+        let line = funding_write_query(&funding, chrono::Utc::now()).build().unwrap().get();
+        assert!(line.starts_with(&format!("funding,mode=Balance,token={token} ")));
+        assert!(line.contains("inventory_funded=1i"));
+
+        let funding = FundingDecision { mode: FundingMode::FlashSwap, inventory: U256::from(10), ..funding };
+        let line = funding_write_query(&funding, chrono::Utc::now()).build().unwrap().get();
+        assert!(line.starts_with("funding,mode=FlashSwap,"));
+        assert!(line.contains("inventory_funded=0i"));
+    }
+
+    #[test]
+    fn test_estimation_batch_order() {
+        let compose_channel: Broadcaster<MessageSwapCompose<LoomDBType>> = Broadcaster::new(10);
+        let mut receiver = compose_channel.subscribe();
+        let mut batches = HashMap::from([(1, EstimationBatch::new(100, Instant::now() + Duration::from_secs(1)))]);
+        let mut open_batches = HashMap::from([(100, 1)]);
+        batches.get_mut(&1).unwrap().in_flight = 4;
+
+        for ready in [Some(ready_request(2)), None, Some(ready_request(5)), Some(ready_request(3))] {
+            finish_estimation(&mut batches, &mut open_batches, Some(1), ready, &compose_channel);
+        }
+
+        // published by the last estimation in flight, the most profitable first
+        assert!(batches.is_empty() && open_batches.is_empty());
+        assert_eq!(published_profits(&mut receiver), vec![U256::from(5), U256::from(3), U256::from(2)]);
+    }
+
+    #[test]
+    fn test_estimation_batch_deadline() {
+        let compose_channel: Broadcaster<MessageSwapCompose<LoomDBType>> = Broadcaster::new(10);
+        let mut receiver = compose_channel.subscribe();
+        let mut batches = HashMap::from([(1, EstimationBatch::new(100, Instant::now()))]);
+        let mut open_batches = HashMap::from([(100, 1)]);
+        batches.get_mut(&1).unwrap().in_flight = 2;
+
+        finish_estimation(&mut batches, &mut open_batches, Some(1), Some(ready_request(1)), &compose_channel);
+        assert!(published_profits(&mut receiver).is_empty());
+
+        // the budget is exceeded with an estimation in flight, it is dropped when it finishes
+        batches.remove(&1).unwrap().publish(&compose_channel);
+        assert_eq!(published_profits(&mut receiver), vec![U256::from(1)]);
+        finish_estimation(&mut batches, &mut open_batches, Some(1), Some(ready_request(4)), &compose_channel);
+        assert!(published_profits(&mut receiver).is_empty());
+
+        // without a latency budget estimations are published as they finish
+        finish_estimation(&mut batches, &mut open_batches, None, Some(ready_request(4)), &compose_channel);
+        assert_eq!(published_profits(&mut receiver), vec![U256::from(4)]);
+    }
+
+    #[test]
+    fn test_batch_deadline() {
+        let now = Instant::now();
+        let budget = Duration::from_millis(100);
+        let deadline = batch_deadline(budget, None);
+        assert!(deadline >= now + budget && deadline <= Instant::now() + budget);
+        // closed by the end of the sub-block
+        assert!(batch_deadline(budget, Some(Duration::from_millis(10))) <= Instant::now() + Duration::from_millis(10));
+    }
+
+    #[test]
+    fn test_time_to_next_sub_block() {
+        let interval = Duration::from_millis(200);
+        assert_eq!(time_to_next_sub_block(Duration::from_millis(1_000), interval), interval);
+        assert_eq!(time_to_next_sub_block(Duration::from_millis(1_050), interval), Duration::from_millis(150));
+        assert_eq!(time_to_next_sub_block(Duration::from_millis(1_199), interval), Duration::from_millis(1));
+    }
+}
@@ -0,0 +1,72 @@
+use std::collections::HashMap;
+
+use loom_types_entities::{PoolClass, Swap};
+use serde::Deserialize;
+
+const DEFAULT_MARGIN_BPS: u64 = 1_000;
+
+fn default_margin_bps() -> u64 {
+    DEFAULT_MARGIN_BPS
+}
+
+/// Gas limit of the swap transaction derived from the simulated gas. Builders score bundles by the gas price of the
+/// gas limit, so the limit is kept close to the gas used instead of a blanket high value
+#[derive(Clone, Debug, Deserialize)]
+pub struct GasLimitConfig {
+    /// Safety margin over the simulated gas in basis points
+    #[serde(default = "default_margin_bps")]
+    pub margin_bps: u64,
+    /// Minimal gas limit of transactions swapping through a pool of the class, e.g. for pools with state dependent gas
+    #[serde(default)]
+    pub class_floors: HashMap<PoolClass, u64>,
+}
+
+impl Default for GasLimitConfig {
+    fn default() -> Self {
+        Self { margin_bps: DEFAULT_MARGIN_BPS, class_floors: HashMap::new() }
+    }
+}
+
+impl GasLimitConfig {
+    pub fn with_margin_bps(self, margin_bps: u64) -> Self {
+        Self { margin_bps, ..self }
+    }
+
+    pub fn with_class_floor(mut self, pool_class: PoolClass, gas_limit: u64) -> Self {
+        self.class_floors.insert(pool_class, gas_limit);
+        self
+    }
+
+    /// Simulated gas with the margin, raised to the highest floor of the pool classes of the swap
+    pub fn gas_limit(&self, gas_used: u64, swap: &Swap) -> u64 {
+        let with_margin = (gas_used as u128 * (10_000 + self.margin_bps) as u128).div_ceil(10_000) as u64;
+        let floor =
+            swap.get_pools_vec().iter().filter_map(|pool| self.class_floors.get(&pool.get_class()).copied()).max().unwrap_or_default();
+        with_margin.max(floor)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloy_primitives::Address;
+    use loom_types_entities::{MockPool, SwapLine, SwapPath, Token};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_gas_limit() {
+        let token0 = Arc::new(Token::new(Address::repeat_byte(1)));
+        let token1 = Arc::new(Token::new(Address::repeat_byte(2)));
+        let pool = MockPool::new(token0.get_address(), token1.get_address(), Address::repeat_byte(3));
+        let swap = Swap::ExchangeSwapLine(SwapLine::from(SwapPath::new_swap(token0, token1, pool.into())));
+
+        let config = GasLimitConfig::default();
+        assert_eq!(config.gas_limit(150_000, &swap), 165_000);
+        assert_eq!(config.clone().with_margin_bps(0).gas_limit(150_001, &swap), 150_001);
+
+        let config = config.with_class_floor(PoolClass::Curve, 300_000);
+        assert_eq!(config.gas_limit(150_000, &swap), 165_000);
+        let config = config.with_class_floor(PoolClass::UniswapV2, 200_000);
+        assert_eq!(config.gas_limit(150_000, &swap), 200_000);
+    }
+}
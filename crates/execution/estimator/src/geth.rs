@@ -21,10 +21,14 @@ use loom_core_actors_macros::{Consumer, Producer};
 use loom_types_blockchain::LoomTx;
 use loom_types_events::{MessageSwapCompose, SwapComposeData, SwapComposeMessage, TxComposeData, TxState};
 
+use crate::GasLimitConfig;
+
 async fn estimator_task<P: Provider<Ethereum> + Send + Sync + Clone + 'static, DB: DatabaseRef + Send + Sync + Clone>(
     estimate_request: SwapComposeData<DB>,
     client: Arc<Flashbots<P>>,
     swap_encoder: impl SwapEncoder,
+    chain_id: u64,
+    gas_limit_config: GasLimitConfig,
     compose_channel_tx: Broadcaster<MessageSwapCompose<DB>>,
 ) -> Result<()> {
     let token_in = estimate_request.swap.get_first_token().cloned().ok_or(eyre!("NO_TOKEN"))?;
@@ -48,11 +52,12 @@ async fn estimator_task<P: Provider<Ethereum> + Send + Sync + Clone + 'static, D
         Some(gas_cost),
         Some(tx_signer.address()),
         Some(estimate_request.tx_compose.eth_balance),
+        estimate_request.tx_compose.profit_receiver,
     )?;
 
     let mut tx_request = TransactionRequest {
         transaction_type: Some(2),
-        chain_id: Some(1),
+        chain_id: Some(chain_id),
         from: Some(tx_signer.address()),
         to: Some(TxKind::Call(to)),
         gas: Some(estimate_request.tx_compose.gas),
@@ -127,15 +132,16 @@ async fn estimator_task<P: Provider<Ethereum> + Send + Sync + Clone + 'static, D
                                 Some(gas_cost),
                                 Some(tx_signer.address()),
                                 Some(estimate_request.tx_compose.eth_balance),
+                                estimate_request.tx_compose.profit_receiver,
                             )?,
                         };
 
                         let tx_request = TransactionRequest {
                             transaction_type: Some(2),
-                            chain_id: Some(1),
+                            chain_id: Some(chain_id),
                             from: Some(tx_signer.address()),
                             to: Some(TxKind::Call(to)),
-                            gas: Some((gas * 1500) / 1000),
+                            gas: Some(gas_limit_config.gas_limit(gas, &swap)),
                             value: call_value,
                             input: TransactionInput::new(call_data),
                             nonce: Some(estimate_request.tx_compose.nonce),
@@ -200,6 +206,8 @@ async fn estimator_task<P: Provider<Ethereum> + Send + Sync + Clone + 'static, D
 async fn estimator_worker<P: Provider<Ethereum> + Send + Sync + Clone + 'static, DB: DatabaseRef + Send + Sync + Clone>(
     client: Arc<Flashbots<P>>,
     encoder: impl SwapEncoder + Send + Sync + Clone + 'static,
+    chain_id: u64,
+    gas_limit_config: GasLimitConfig,
     compose_channel_rx: Broadcaster<MessageSwapCompose<DB>>,
     compose_channel_tx: Broadcaster<MessageSwapCompose<DB>>,
 ) -> WorkerResult {
@@ -215,11 +223,14 @@ async fn estimator_worker<P: Provider<Ethereum> + Send + Sync + Clone + 'static,
                             let compose_channel_tx_cloned = compose_channel_tx.clone();
                             let client_cloned = client.clone();
                             let encoder_cloned = encoder.clone();
+                            let gas_limit_config = gas_limit_config.clone();
                             tokio::task::spawn(async move {
                                 if let Err(e) = estimator_task(
                                     estimate_request.clone(),
                                     client_cloned,
                                     encoder_cloned,
+                                    chain_id,
+                                    gas_limit_config,
                                     compose_channel_tx_cloned,
                                 ).await {
                                         error!("Error in Geth estimator_task: {:?}", e);
@@ -239,6 +250,8 @@ async fn estimator_worker<P: Provider<Ethereum> + Send + Sync + Clone + 'static,
 pub struct GethEstimatorActor<P, E, DB: Clone + Send + Sync + 'static> {
     client: Arc<Flashbots<P>>,
     encoder: E,
+    chain_id: u64,
+    gas_limit_config: GasLimitConfig,
     #[consumer]
     compose_channel_rx: Option<Broadcaster<MessageSwapCompose<DB>>>,
     #[producer]
@@ -252,7 +265,24 @@ where
     DB: DatabaseRef + Send + Sync + Clone,
 {
     pub fn new(client: Arc<Flashbots<P>>, encoder: E) -> Self {
-        Self { client, encoder, compose_channel_tx: None, compose_channel_rx: None }
+        Self {
+            client,
+            encoder,
+            chain_id: 1,
+            gas_limit_config: GasLimitConfig::default(),
+            compose_channel_tx: None,
+            compose_channel_rx: None,
+        }
+    }
+
+    /// Chain the transactions are signed for, mainnet if not set
+    pub fn with_chain_id(self, chain_id: u64) -> Self {
+        Self { chain_id, ..self }
+    }
+
+    /// Margin and floors of the gas limit derived from the simulated gas
+    pub fn with_gas_limit_config(self, gas_limit_config: GasLimitConfig) -> Self {
+        Self { gas_limit_config, ..self }
     }
 
     pub fn on_bc(self, strategy: &Strategy<DB>) -> Self {
@@ -274,6 +304,8 @@ where
         let task = tokio::task::spawn(estimator_worker(
             self.client.clone(),
             self.encoder.clone(),
+            self.chain_id,
+            self.gas_limit_config.clone(),
             self.compose_channel_rx.clone().unwrap(),
             self.compose_channel_tx.clone().unwrap(),
         ));
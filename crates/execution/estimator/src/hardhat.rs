@@ -49,6 +49,7 @@ async fn estimator_worker<DB: DatabaseRef + Send + Sync + Clone>(
                                         Some(gas_cost),
                                         Some(tx_signer.address()),
                                         Some(estimate_request.tx_compose.eth_balance),
+                                        estimate_request.tx_compose.profit_receiver,
                                     )?;
 
                                     let tx_request = TransactionRequest {
@@ -1,7 +1,16 @@
 mod evm;
+mod gas_limit;
 mod geth;
 mod hardhat;
+mod node_validator;
+mod preflight;
+mod public_fallback;
+mod trace;
 
 pub use evm::EvmEstimatorActor;
+pub use gas_limit::GasLimitConfig;
 pub use geth::GethEstimatorActor;
 pub use hardhat::HardhatEstimatorActor;
+pub use node_validator::{NodeBundleValidator, NodeValidationMethod, DEFAULT_VALIDATION_TIMEOUT_MS};
+pub use preflight::{check_approval, preflight_funding, select_inventory_funding, FundingDecision, PreflightError};
+pub use public_fallback::PublicFallbackConfig;
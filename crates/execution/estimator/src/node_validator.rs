@@ -0,0 +1,150 @@
+use alloy_primitives::{Bytes, U256, U64};
+use alloy_provider::{Provider, RootProvider};
+use alloy_rpc_types::{BlockId, BlockOverrides, Bundle, StateContext, TransactionIndex, TransactionRequest};
+use alloy_rpc_types_trace::geth::{
+    GethDebugBuiltInTracerType, GethDebugTracerType, GethDebugTracingCallOptions, GethDebugTracingOptions, GethTrace,
+};
+use eyre::{eyre, Result};
+use serde::Deserialize;
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+use loom_broadcast_flashbots::client::{BundleRequest, SimulatedBundle};
+use loom_types_blockchain::LoomTx;
+use loom_types_events::{SwapComposeData, TxState};
+
+pub const DEFAULT_VALIDATION_TIMEOUT_MS: u64 = 100;
+
+/// RPC method used to simulate the bundle on the trusted node
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NodeValidationMethod {
+    /// `eth_callBundle` with the signed transactions
+    #[default]
+    CallBundle,
+    /// `debug_traceCallMany` with the call tracer, the transactions are not signed
+    TraceCallMany,
+}
+
+/// Verifies bundles estimated on local revm state on a trusted node before they are published.
+/// Catches paths that local state cannot simulate correctly, e.g. with missing accounts or exotic precompiles.
+#[derive(Clone, Debug)]
+pub struct NodeBundleValidator {
+    client: RootProvider,
+    method: NodeValidationMethod,
+    timeout: Duration,
+}
+
+impl NodeBundleValidator {
+    pub fn new(client: RootProvider) -> Self {
+        Self { client, method: NodeValidationMethod::default(), timeout: Duration::from_millis(DEFAULT_VALIDATION_TIMEOUT_MS) }
+    }
+
+    pub fn with_method(self, method: NodeValidationMethod) -> Self {
+        Self { method, ..self }
+    }
+
+    /// Bundles not validated within the timeout are dropped
+    pub fn with_timeout(self, timeout: Duration) -> Self {
+        Self { timeout, ..self }
+    }
+
+    pub fn method(&self) -> NodeValidationMethod {
+        self.method
+    }
+
+    /// Simulate the bundle of the ready request on the node, returns the gas used by the backrun transaction
+    pub async fn validate<DB>(&self, request: &SwapComposeData<DB>) -> Result<u64> {
+        let start_time = Instant::now();
+        let result = match tokio::time::timeout(self.timeout, async {
+            match self.method {
+                NodeValidationMethod::CallBundle => self.call_bundle(request).await,
+                NodeValidationMethod::TraceCallMany => self.trace_call_many(request).await,
+            }
+        })
+        .await
+        {
+            Ok(result) => result,
+            Err(_) => Err(eyre!("VALIDATION_TIMEOUT")),
+        };
+        debug!(method = ?self.method, elapsed = ?start_time.elapsed(), ok = result.is_ok(), "Node bundle validation");
+        result
+    }
+
+    fn backrun_request<DB>(request: &SwapComposeData<DB>) -> Result<TransactionRequest> {
+        request
+            .tx_compose
+            .tx_bundle
+            .iter()
+            .flatten()
+            .find_map(|tx_state| match tx_state {
+                TxState::SignatureRequired(tx_request) => Some(tx_request.clone()),
+                _ => None,
+            })
+            .ok_or(eyre!("NO_BACKRUN_TX"))
+    }
+
+    async fn call_bundle<DB>(&self, request: &SwapComposeData<DB>) -> Result<u64> {
+        let tx_signer = request.tx_compose.signer.clone().ok_or(eyre!("NO_SIGNER"))?;
+        let tx = tx_signer.sign(Self::backrun_request(request)?).await?;
+        let tx_hash = LoomTx::tx_hash(&tx);
+
+        let next_block_number = request.tx_compose.next_block_number;
+        let mut bundle = BundleRequest::new()
+            .set_target_block(U64::from(next_block_number))
+            .set_simulation_block(U64::from(next_block_number - 1))
+            .set_simulation_timestamp(request.tx_compose.next_block_timestamp)
+            .set_simulation_basefee(request.tx_compose.next_block_base_fee);
+        for tx_state in request.tx_compose.tx_bundle.iter().flatten() {
+            if let TxState::ReadyForBroadcastStuffing(rlp) = tx_state {
+                bundle = bundle.push_transaction(rlp.clone());
+            }
+        }
+        bundle = bundle.push_transaction(Bytes::from(tx.encode()));
+
+        let sim_result: SimulatedBundle = self.client.client().request("eth_callBundle", [bundle]).await?;
+        let tx_sim_result = sim_result.find_tx(tx_hash).ok_or(eyre!("TX_NOT_FOUND_IN_SIMULATION"))?;
+        if let Some(error) = &tx_sim_result.error {
+            return Err(eyre!("TX_SIMULATION_ERROR : {error}"));
+        }
+        if let Some(revert) = &tx_sim_result.revert {
+            return Err(eyre!("TX_SIMULATION_REVERT : {revert}"));
+        }
+        Ok(tx_sim_result.gas_used.to())
+    }
+
+    async fn trace_call_many<DB>(&self, request: &SwapComposeData<DB>) -> Result<u64> {
+        let mut transactions: Vec<TransactionRequest> =
+            request.tx_compose.stuffing_txs.iter().map(|tx| tx.clone().into_request()).collect();
+        transactions.push(Self::backrun_request(request)?);
+
+        let bundle = Bundle {
+            transactions,
+            block_override: Some(BlockOverrides {
+                number: Some(U256::from(request.tx_compose.next_block_number)),
+                time: Some(request.tx_compose.next_block_timestamp),
+                base_fee: Some(U256::from(request.tx_compose.next_block_base_fee)),
+                ..BlockOverrides::default()
+            }),
+        };
+        let state_context = StateContext { block_number: Some(BlockId::latest()), transaction_index: Some(TransactionIndex::All) };
+        let trace_options = GethDebugTracingCallOptions {
+            tracing_options: GethDebugTracingOptions::default()
+                .with_tracer(GethDebugTracerType::BuiltInTracer(GethDebugBuiltInTracerType::CallTracer)),
+            ..GethDebugTracingCallOptions::default()
+        };
+
+        let traces: Vec<Vec<GethTrace>> =
+            self.client.client().request("debug_traceCallMany", (vec![bundle], state_context, trace_options)).await?;
+
+        match traces.into_iter().flatten().last() {
+            Some(GethTrace::CallTracer(call_frame)) => {
+                if let Some(error) = call_frame.error {
+                    return Err(eyre!("TX_SIMULATION_REVERT : {error} {}", call_frame.revert_reason.unwrap_or_default()));
+                }
+                Ok(call_frame.gas_used.to())
+            }
+            _ => Err(eyre!("TRACE_RESULT_FAILED")),
+        }
+    }
+}
@@ -0,0 +1,326 @@
+use alloy_primitives::{Address, U256};
+use eyre::Report;
+use revm::primitives::Env;
+use revm::DatabaseRef;
+use thiserror::Error;
+
+use loom_defi_abi::AbiEncoderHelper;
+use loom_defi_address_book::PeripheryAddress;
+use loom_evm_utils::evm::evm_call;
+use loom_types_entities::{ChainPreset, FundingMode, PoolClass, PreswapKind, Swap, SwapLine, SwapStep};
+
+/// Funding checks failed before the simulation. They are returned wrapped into [`Report`], use
+/// [`PreflightError::from_report`] to match on them.
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum PreflightError {
+    #[error("Swap input amount is not set")]
+    AmountInNotSet,
+    #[error("Swap has no input token")]
+    NoInputToken,
+    #[error("Multicaller balance {available} of {token} does not cover amount in {required}")]
+    InsufficientBalance { token: Address, required: U256, available: U256 },
+    #[error("{mode:?} liquidity {available} of {token} does not cover amount in {required}")]
+    InsufficientLiquidity { mode: FundingMode, token: Address, required: U256, available: U256 },
+    #[error("Balance of {token} held by {owner} cannot be read : {reason}")]
+    BalanceUnavailable { token: Address, owner: Address, reason: String },
+    #[error("Flash loan is not supported on the chain")]
+    NoFlashLoanProvider,
+    #[error("Allowance {allowance} of {token} given to {spender} cannot be replaced by the swap approval")]
+    ApprovalRejected { token: Address, spender: Address, allowance: U256 },
+    #[error("Allowance of {token} given to {spender} cannot be read : {reason}")]
+    AllowanceUnavailable { token: Address, spender: Address, reason: String },
+}
+
+impl PreflightError {
+    pub fn from_report(report: &Report) -> Option<&Self> {
+        report.downcast_ref::<Self>()
+    }
+}
+
+/// Input token and amount of every funded part of the swap with the funding mode the encoder chooses for it
+fn funding_requirements(swap: &Swap) -> Result<Vec<(FundingMode, Address, U256)>, PreflightError> {
+    match swap {
+        Swap::BackrunSwapLine(swap_line) => {
+            let amount_in = match swap_line.amount_in.is_set() {
+                true => swap_line.amount_in.unwrap(),
+                false => return Err(PreflightError::AmountInNotSet),
+            };
+            let token = swap_line.get_first_token().ok_or(PreflightError::NoInputToken)?;
+            Ok(vec![(swap_line.funding_mode(amount_in), token.get_address(), amount_in)])
+        }
+        Swap::BackrunSwapSteps((swap_step_0, swap_step_1)) => {
+            let amount_in = swap_step_0.get_in_amount().map_err(|_| PreflightError::AmountInNotSet)?;
+            let token = swap_step_0.get_first_token().ok_or(PreflightError::NoInputToken)?;
+            Ok(vec![(SwapStep::funding_mode(swap_step_0, swap_step_1), token.get_address(), amount_in)])
+        }
+        Swap::Multiple(swaps) => {
+            let mut requirements = Vec::new();
+            for swap in swaps.iter() {
+                requirements.extend(funding_requirements(swap)?);
+            }
+            Ok(requirements)
+        }
+        Swap::ExchangeSwapLine(swap_line) => {
+            let amount_in = match swap_line.amount_in.is_set() {
+                true => swap_line.amount_in.unwrap(),
+                false => return Err(PreflightError::AmountInNotSet),
+            };
+            let token = swap_line.get_first_token().ok_or(PreflightError::NoInputToken)?;
+            // exchange swaps are funded by the multicaller balance
+            Ok(vec![(FundingMode::Balance, token.get_address(), amount_in)])
+        }
+        Swap::None => Ok(Vec::new()),
+    }
+}
+
+/// Swap lines of the swap, both steps of a two step swap and every swap of multiple swaps
+fn swap_lines(swap: &Swap) -> Vec<&SwapLine> {
+    match swap {
+        Swap::BackrunSwapLine(swap_line) | Swap::ExchangeSwapLine(swap_line) => vec![swap_line],
+        Swap::BackrunSwapSteps((swap_step_0, swap_step_1)) => {
+            swap_step_0.swap_line_vec().iter().chain(swap_step_1.swap_line_vec().iter()).collect()
+        }
+        Swap::Multiple(swaps) => swaps.iter().flat_map(swap_lines).collect(),
+        Swap::None => Vec::new(),
+    }
+}
+
+/// Token and spender of every approval the encoders give for hops through pools pulling the input by allowance
+fn approvals(swap: &Swap) -> Vec<(Address, Address)> {
+    let mut approvals = Vec::new();
+    for swap_line in swap_lines(swap) {
+        for (pool, token) in swap_line.pools().iter().zip(swap_line.tokens().iter()) {
+            if pool.get_class().capabilities().preswap_requirement != PreswapKind::Allowance {
+                continue;
+            }
+            // Pendle markets are swapped through the router
+            let spender = match pool.get_class() {
+                PoolClass::PendleV2 => PeripheryAddress::PENDLE_ROUTER_V4,
+                _ => pool.get_address(),
+            };
+            if !approvals.contains(&(token.get_address(), spender)) {
+                approvals.push((token.get_address(), spender));
+            }
+        }
+    }
+    approvals
+}
+
+fn read_u256<DB: DatabaseRef>(state: &DB, env: &Env, token: Address, call_data: Vec<u8>) -> Result<U256, String> {
+    match evm_call(state, env.clone(), token, call_data) {
+        Ok((output, _)) if output.len() >= 32 => Ok(U256::from_be_slice(&output[..32])),
+        Ok(_) => Err("SHORT_OUTPUT".to_string()),
+        Err(error) => Err(error.to_string()),
+    }
+}
+
+fn token_balance<DB: DatabaseRef>(state: &DB, env: &Env, token: Address, owner: Address) -> Result<U256, PreflightError> {
+    read_u256(state, env, token, AbiEncoderHelper::encode_erc20_balance_of(owner).to_vec())
+        .map_err(|reason| PreflightError::BalanceUnavailable { token, owner, reason })
+}
+
+/// Verify that the multicaller can approve the spender. Tokens like USDT reject an approval while a previous allowance
+/// is left, the approval is simulated from the multicaller when the allowance is not zero
+pub fn check_approval<DB: DatabaseRef>(
+    state: &DB,
+    env: &Env,
+    multicaller: Address,
+    token: Address,
+    spender: Address,
+) -> Result<(), PreflightError> {
+    let allowance = read_u256(state, env, token, AbiEncoderHelper::encode_erc20_allowance(multicaller, spender).to_vec())
+        .map_err(|reason| PreflightError::AllowanceUnavailable { token, spender, reason })?;
+    if allowance.is_zero() {
+        return Ok(());
+    }
+
+    let mut env = env.clone();
+    env.tx.caller = multicaller;
+    match evm_call(state, env, token, AbiEncoderHelper::encode_erc20_approve(spender, U256::from(1)).to_vec()) {
+        // tokens returning nothing from approve are accepted
+        Ok((output, _)) if output.len() < 32 || !U256::from_be_slice(&output[..32]).is_zero() => Ok(()),
+        _ => Err(PreflightError::ApprovalRejected { token, spender, allowance }),
+    }
+}
+
+/// Funding chosen for a backrun swap line with the multicaller inventory of its input token it was chosen on
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FundingDecision {
+    pub mode: FundingMode,
+    pub token: Address,
+    pub amount_in: U256,
+    pub inventory: U256,
+}
+
+/// Fund a backrun swap line from the multicaller inventory of its input token when the balance covers the input amount,
+/// a flash swap or loan is used otherwise. Returns the decision or `None` when the swap funding is not selectable
+pub fn select_inventory_funding<DB: DatabaseRef>(state: &DB, env: &Env, multicaller: Address, swap: &mut Swap) -> Option<FundingDecision> {
+    let Swap::BackrunSwapLine(swap_line) = swap else {
+        return None;
+    };
+    if !swap_line.amount_in.is_set() {
+        return None;
+    }
+    let amount_in = swap_line.amount_in.unwrap();
+    let token = swap_line.get_first_token()?.get_address();
+    // unreadable balance is the same as no inventory
+    let inventory = token_balance(state, env, token, multicaller).unwrap_or_default();
+    Some(FundingDecision { mode: swap_line.select_funding(inventory), token, amount_in, inventory })
+}
+
+/// Verify that the funding source of every part of the swap can cover its input amount at the target block state:
+/// the multicaller balance for balance funding and the vault balance for Balancer flash loans. Flash loans are taken
+/// from the lender of the chain preset.
+/// Flash swaps borrow from a pool of the path whose reserves are already checked by the swap calculation,
+/// Aave liquidity is held by the aToken of the reserve and is left to the simulation.
+/// Approvals given to pools pulling the input by allowance are checked with [`check_approval`].
+pub fn preflight_funding<DB: DatabaseRef>(
+    state: &DB,
+    env: &Env,
+    multicaller: Address,
+    swap: &Swap,
+    chain_preset: &ChainPreset,
+) -> Result<(), PreflightError> {
+    for (funding_mode, token, amount_in) in funding_requirements(swap)? {
+        match chain_preset.funding_mode(funding_mode) {
+            None => return Err(PreflightError::NoFlashLoanProvider),
+            Some(FundingMode::FlashSwap | FundingMode::AaveFlashLoan) => {}
+            Some(FundingMode::Balance) => {
+                let available = token_balance(state, env, token, multicaller)?;
+                if available < amount_in {
+                    return Err(PreflightError::InsufficientBalance { token, required: amount_in, available });
+                }
+            }
+            Some(mode @ FundingMode::BalancerFlashLoan) => {
+                let vault = chain_preset.flash_loan.map(|flash_loan| flash_loan.lender).ok_or(PreflightError::NoFlashLoanProvider)?;
+                let available = token_balance(state, env, token, vault)?;
+                if available < amount_in {
+                    return Err(PreflightError::InsufficientLiquidity { mode, token, required: amount_in, available });
+                }
+            }
+        }
+    }
+    for (token, spender) in approvals(swap) {
+        check_approval(state, env, multicaller, token, spender)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use loom_evm_db::LoomDBType;
+    use loom_types_entities::{MockPool, SwapAmountType, SwapPath, Token};
+    use revm::primitives::{AccountInfo, Bytecode, Bytes};
+    use std::sync::Arc;
+
+    const MULTICALLER: Address = Address::repeat_byte(0x10);
+
+    // token answering every call with `value`, approvals revert when `reject_approve` is set
+    fn token_db(token: Address, value: u16, reject_approve: bool) -> LoomDBType {
+        let selector: [u8; 4] = if reject_approve { [0x09, 0x5e, 0xa7, 0xb3] } else { [0; 4] };
+        // selector == approve ? revert : return value
+        let mut code = vec![0x60, 0x00, 0x35, 0x60, 0xe0, 0x1c, 0x63];
+        code.extend(selector);
+        code.extend([0x14, 0x60, 0x1a, 0x57, 0x61]);
+        code.extend(value.to_be_bytes());
+        code.extend([0x60, 0x00, 0x52, 0x60, 0x20, 0x60, 0x00, 0xf3, 0x5b, 0x60, 0x00, 0x80, 0xfd]);
+
+        let mut db = LoomDBType::default();
+        db.insert_account_info(token, AccountInfo { code: Some(Bytecode::new_raw(Bytes::from(code))), ..AccountInfo::default() });
+        db
+    }
+
+    fn swap(token: Address, amount_in: u64, funding: FundingMode) -> Swap {
+        let token0 = Arc::new(Token::new(token));
+        let token1 = Arc::new(Token::new(Address::repeat_byte(2)));
+        let pool = MockPool::new(token0.get_address(), token1.get_address(), Address::repeat_byte(3));
+        Swap::BackrunSwapLine(SwapLine {
+            amount_in: SwapAmountType::Set(U256::from(amount_in)),
+            funding: Some(funding),
+            ..SwapLine::from(SwapPath::new(vec![token0.clone(), token1, token0], vec![pool.clone(), pool]))
+        })
+    }
+
+    #[test]
+    fn test_preflight_funding() {
+        let token = Address::repeat_byte(1);
+        let db = token_db(token, 1000, false);
+        let env = Env::default();
+        let chain_preset = ChainPreset::default();
+
+        assert_eq!(preflight_funding(&db, &env, MULTICALLER, &swap(token, 1000, FundingMode::Balance), &chain_preset), Ok(()));
+        assert_eq!(
+            preflight_funding(&db, &env, MULTICALLER, &swap(token, 1001, FundingMode::Balance), &chain_preset),
+            Err(PreflightError::InsufficientBalance { token, required: U256::from(1001), available: U256::from(1000) })
+        );
+        let multiple = Swap::Multiple(vec![swap(token, 1000, FundingMode::FlashSwap), swap(token, 1001, FundingMode::Balance)]);
+        assert!(matches!(
+            preflight_funding(&db, &env, MULTICALLER, &multiple, &chain_preset),
+            Err(PreflightError::InsufficientBalance { .. })
+        ));
+
+        // flash swaps are not checked, flash loans need a lender
+        let empty_db = LoomDBType::default();
+        assert_eq!(preflight_funding(&empty_db, &env, MULTICALLER, &swap(token, 1001, FundingMode::FlashSwap), &chain_preset), Ok(()));
+        let no_flash_loan = ChainPreset { flash_loan: None, ..ChainPreset::default() };
+        assert_eq!(
+            preflight_funding(&empty_db, &env, MULTICALLER, &swap(token, 1, FundingMode::BalancerFlashLoan), &no_flash_loan),
+            Err(PreflightError::NoFlashLoanProvider)
+        );
+        assert!(matches!(
+            preflight_funding(&empty_db, &env, MULTICALLER, &swap(token, 1, FundingMode::Balance), &chain_preset),
+            Err(PreflightError::BalanceUnavailable { .. })
+        ));
+    }
+
+    #[test]
+    fn test_select_inventory_funding() {
+        let token = Address::repeat_byte(1);
+        let db = token_db(token, 1000, false);
+        let env = Env::default();
+
+        let mut covered = swap(token, 1000, FundingMode::FlashSwap);
+        let decision = select_inventory_funding(&db, &env, MULTICALLER, &mut covered).unwrap();
+        assert_eq!(
+            decision,
+            FundingDecision { mode: FundingMode::Balance, token, amount_in: U256::from(1000), inventory: U256::from(1000) }
+        );
+        assert_eq!(covered.funding_mode(), Some(FundingMode::Balance));
+
+        let mut uncovered = swap(token, 1001, FundingMode::Balance);
+        let decision = select_inventory_funding(&db, &env, MULTICALLER, &mut uncovered).unwrap();
+        assert_ne!(decision.mode, FundingMode::Balance);
+        assert_eq!(decision.inventory, U256::from(1000));
+        assert_eq!(uncovered.funding_mode(), Some(decision.mode));
+
+        // unreadable inventory falls back to flash funding
+        let mut unreadable = swap(token, 1, FundingMode::Balance);
+        let decision = select_inventory_funding(&LoomDBType::default(), &env, MULTICALLER, &mut unreadable).unwrap();
+        assert_ne!(decision.mode, FundingMode::Balance);
+        assert!(decision.inventory.is_zero());
+
+        assert_eq!(select_inventory_funding(&db, &env, MULTICALLER, &mut Swap::None), None);
+    }
+
+    #[test]
+    fn test_check_approval() {
+        let token = Address::repeat_byte(1);
+        let spender = Address::repeat_byte(4);
+        let env = Env::default();
+
+        // no allowance left, the approval is not simulated
+        assert_eq!(check_approval(&token_db(token, 0, true), &env, MULTICALLER, token, spender), Ok(()));
+        // an allowance is left and the token accepts approvals over it
+        assert_eq!(check_approval(&token_db(token, 1000, false), &env, MULTICALLER, token, spender), Ok(()));
+        // USDT like token rejecting approvals over a left allowance
+        assert_eq!(
+            check_approval(&token_db(token, 1000, true), &env, MULTICALLER, token, spender),
+            Err(PreflightError::ApprovalRejected { token, spender, allowance: U256::from(1000) })
+        );
+        assert!(matches!(
+            check_approval(&LoomDBType::default(), &env, MULTICALLER, token, spender),
+            Err(PreflightError::AllowanceUnavailable { .. })
+        ));
+    }
+}
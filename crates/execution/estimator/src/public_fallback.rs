@@ -0,0 +1,102 @@
+use alloy_primitives::U256;
+use loom_types_entities::Swap;
+use serde::Deserialize;
+
+const DEFAULT_MIN_PROFIT_BPS: u64 = 9_000;
+const DEFAULT_DEADLINE_SECS: u64 = 12;
+const DEFAULT_MAX_FEE_BLOCKS: u64 = 2;
+
+/// Gas of the deadline check and the profit assertion over the simulated gas of the swap
+pub const PUBLIC_FALLBACK_EXTRA_GAS: u64 = 15_000;
+
+fn default_min_profit_bps() -> u64 {
+    DEFAULT_MIN_PROFIT_BPS
+}
+
+fn default_deadline_secs() -> u64 {
+    DEFAULT_DEADLINE_SECS
+}
+
+fn default_max_fee_blocks() -> u64 {
+    DEFAULT_MAX_FEE_BLOCKS
+}
+
+/// Backrun transaction for the public mempool pre-encoded next to the bundle. It is sent when the bundle misses its
+/// target block and reverts instead of executing with a loss after a front-run or a state change
+#[derive(Clone, Debug, Deserialize)]
+pub struct PublicFallbackConfig {
+    /// Share of the simulated profit the transaction has to make, in basis points
+    #[serde(default = "default_min_profit_bps")]
+    pub min_profit_bps: u64,
+    /// Seconds after the timestamp of the target block the transaction is valid for
+    #[serde(default = "default_deadline_secs")]
+    pub deadline_secs: u64,
+    /// Blocks after the target block the max fee per gas covers the base fee increase of, the transaction is sent once
+    /// the target block is built without the bundle
+    #[serde(default = "default_max_fee_blocks")]
+    pub max_fee_blocks: u64,
+}
+
+impl Default for PublicFallbackConfig {
+    fn default() -> Self {
+        Self { min_profit_bps: DEFAULT_MIN_PROFIT_BPS, deadline_secs: DEFAULT_DEADLINE_SECS, max_fee_blocks: DEFAULT_MAX_FEE_BLOCKS }
+    }
+}
+
+impl PublicFallbackConfig {
+    /// Profit in the first token of the swap the transaction has to make, never below the gas cost. `None` if the
+    /// simulated profit does not cover the gas cost
+    pub fn min_profit(&self, swap: &Swap, gas_cost: U256) -> Option<U256> {
+        let token_in = swap.get_first_token()?;
+        let profit = swap.abs_profit();
+        let gas_cost = token_in.calc_token_value_from_eth(gas_cost)?;
+        if profit <= gas_cost {
+            return None;
+        }
+        Some((profit * U256::from(self.min_profit_bps.min(10_000)) / U256::from(10_000)).max(gas_cost + U256::from(1)))
+    }
+
+    pub fn deadline(&self, next_block_timestamp: u64) -> u64 {
+        next_block_timestamp + self.deadline_secs
+    }
+
+    /// Max fee per gas with the base fee of the target block raised by the maximal EIP-1559 increase of 12.5% per block
+    pub fn max_fee_per_gas(&self, next_block_base_fee: u64, priority_fee: u64) -> u128 {
+        let base_fee = (0..self.max_fee_blocks).fold(next_block_base_fee as u128, |base_fee, _| base_fee + base_fee.div_ceil(8));
+        base_fee + priority_fee as u128
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloy_primitives::Address;
+    use loom_types_entities::{MockPool, SwapAmountType, SwapLine, SwapPath, Token};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_min_profit() {
+        let token0 = Arc::new(Token::new(Address::repeat_byte(1)));
+        // 2 tokens per ETH
+        token0.set_eth_price(Some(U256::from(2_000_000_000_000_000_000u128)));
+        let token1 = Arc::new(Token::new(Address::repeat_byte(2)));
+        let pool = MockPool::new(token0.get_address(), token1.get_address(), Address::repeat_byte(3));
+        let swap_line = SwapLine {
+            amount_in: SwapAmountType::Set(U256::from(1_000_000)),
+            amount_out: SwapAmountType::Set(U256::from(2_000_000)),
+            ..SwapLine::from(SwapPath::new(vec![token0.clone(), token1, token0], vec![pool.clone(), pool]))
+        };
+        let swap = Swap::BackrunSwapLine(swap_line);
+
+        let profit = swap.abs_profit();
+        let config = PublicFallbackConfig::default();
+        assert_eq!(config.min_profit(&swap, U256::from(100)), Some(profit * U256::from(9) / U256::from(10)));
+        // the gas cost in the token is twice the gas cost in ETH
+        let gas_cost = profit * U256::from(48) / U256::from(100);
+        assert_eq!(config.min_profit(&swap, gas_cost), Some(gas_cost * U256::from(2) + U256::from(1)));
+        assert_eq!(config.min_profit(&swap, profit), None);
+        assert_eq!(config.deadline(1_000), 1_012);
+        assert_eq!(config.max_fee_per_gas(64_000, 10), 81_010);
+        assert_eq!(PublicFallbackConfig { max_fee_blocks: 0, ..config }.max_fee_per_gas(64_000, 10), 64_010);
+    }
+}
@@ -0,0 +1,74 @@
+use alloy_rpc_types::TransactionRequest;
+use eyre::Result;
+use revm::primitives::{Env, ExecutionResult};
+use revm::DatabaseRef;
+use tokio::sync::mpsc::UnboundedSender;
+use tracing::error;
+
+use loom_evm_utils::evm::revert_bytes_to_string;
+use loom_evm_utils::evm_trace::evm_trace_tx_request;
+use loom_types_entities::{SimulationTrace, StorageWrite, Swap};
+
+/// Simulate the swap transaction again with a call tracer, capturing the gas of every frame and the storage written.
+/// The trace gets its id when it is stored.
+pub(crate) fn trace_swap_tx<DB: DatabaseRef>(
+    db: DB,
+    env: &Env,
+    tx_request: &TransactionRequest,
+    swap: &Swap,
+    block_number: u64,
+) -> Result<SimulationTrace> {
+    let (result, call_trace, state) = evm_trace_tx_request(db, env, tx_request)?;
+
+    let error = match &result {
+        ExecutionResult::Success { .. } => None,
+        ExecutionResult::Revert { output, .. } => Some(revert_bytes_to_string(output)),
+        ExecutionResult::Halt { reason, .. } => Some(format!("{reason:?}")),
+    };
+
+    let mut storage_writes: Vec<StorageWrite> = state
+        .into_iter()
+        .flat_map(|(address, account)| {
+            account.storage.into_iter().filter(|(_, value)| value.is_changed()).map(move |(slot, value)| StorageWrite {
+                address,
+                slot,
+                original: value.original_value(),
+                present: value.present_value(),
+            })
+        })
+        .collect();
+    storage_writes.sort_by(|a, b| (a.address, a.slot).cmp(&(b.address, b.slot)));
+
+    Ok(SimulationTrace {
+        id: 0,
+        block_number,
+        swap: swap.to_string(),
+        pools: swap.get_pool_id_vec(),
+        gas_used: result.gas_used(),
+        error,
+        call_trace,
+        storage_writes,
+    })
+}
+
+/// Trace the swap transaction when its simulation was requested to be traced, and send the trace to be stored
+pub(crate) fn capture_trace<DB: DatabaseRef>(
+    trace_tx: Option<&UnboundedSender<SimulationTrace>>,
+    db: DB,
+    env: &Env,
+    tx_request: &TransactionRequest,
+    swap: &Swap,
+    block_number: u64,
+) {
+    let Some(trace_tx) = trace_tx else {
+        return;
+    };
+    match trace_swap_tx(db, env, tx_request, swap, block_number) {
+        Ok(trace) => {
+            if let Err(error) = trace_tx.send(trace) {
+                error!(%error, "trace_tx.send");
+            }
+        }
+        Err(error) => error!(%error, %swap, "Simulation trace failed"),
+    }
+}
@@ -0,0 +1,113 @@
+use alloy_primitives::{Address, Bytes, U256};
+use eyre::{eyre, Result};
+use tracing::trace;
+
+use loom_defi_abi::AbiEncoderHelper;
+use loom_types_blockchain::{MulticallerCall, MulticallerCalls};
+
+/// Swap hop routed by an external aggregator (1inch, 0x, ...) with the call data of the aggregator quote.
+///
+/// The aggregator call data is opaque, so the input amount is fixed by the quote and cannot be taken from the stack.
+/// The output balance is pushed to the stack, a following local hop can use it with `SwapAmountType::RelativeStack(0)`.
+#[derive(Clone, Debug)]
+pub struct AggregatorHop {
+    pub token_in: Address,
+    pub token_out: Address,
+    pub amount_in: U256,
+    /// Minimal balance of `token_out` of the multicaller after the call, including the balance before the hop
+    pub min_balance_out: U256,
+    /// Contract that receives the approval, e.g. the 0x allowance holder. Usually the same as `to`
+    pub spender: Address,
+    /// Aggregator router called with `call_data`
+    pub to: Address,
+    pub call_data: Bytes,
+    pub value: Option<U256>,
+}
+
+impl AggregatorHop {
+    pub fn new(token_in: Address, token_out: Address, amount_in: U256, to: Address, call_data: Bytes) -> Self {
+        Self { token_in, token_out, amount_in, min_balance_out: U256::ZERO, spender: to, to, call_data, value: None }
+    }
+
+    pub fn with_spender(self, spender: Address) -> Self {
+        Self { spender, ..self }
+    }
+
+    pub fn with_min_balance_out(self, min_balance_out: U256) -> Self {
+        Self { min_balance_out, ..self }
+    }
+
+    pub fn with_value(self, value: U256) -> Self {
+        Self { value: Some(value), ..self }
+    }
+
+    /// Encode approve + aggregator call + balance check and push the `token_out` balance of the multicaller to the stack
+    pub fn encode(&self, multicaller_address: Address) -> Result<MulticallerCalls> {
+        if self.call_data.is_empty() {
+            return Err(eyre!("AGGREGATOR_CALL_DATA_EMPTY"));
+        }
+        if self.amount_in.is_zero() {
+            return Err(eyre!("AGGREGATOR_AMOUNT_IN_NOT_SET"));
+        }
+
+        trace!(
+            "aggregator hop token_in={} token_out={} amount_in={} router={} spender={}",
+            self.token_in,
+            self.token_out,
+            self.amount_in,
+            self.to,
+            self.spender
+        );
+
+        let mut calls = MulticallerCalls::new();
+
+        calls.add(MulticallerCall::new_call(self.token_in, &AbiEncoderHelper::encode_erc20_approve(self.spender, self.amount_in)));
+
+        let aggregator_call = match self.value {
+            Some(value) => MulticallerCall::new_call_with_value(self.to, &self.call_data, value),
+            None => MulticallerCall::new_call(self.to, &self.call_data),
+        };
+        calls.add(aggregator_call);
+
+        if !self.min_balance_out.is_zero() {
+            calls.add(MulticallerCall::new_internal_call(&AbiEncoderHelper::encode_multicaller_transfer_tips_no_payout(
+                self.token_out,
+                self.min_balance_out,
+                U256::ZERO,
+            )));
+        }
+
+        let mut balance_call =
+            MulticallerCall::new_static_call(self.token_out, &AbiEncoderHelper::encode_erc20_balance_of(multicaller_address));
+        balance_call.set_return_stack(true, 0, 0x0, 0x20);
+        calls.add(balance_call);
+
+        Ok(calls)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use loom_types_blockchain::CallType;
+
+    #[test]
+    fn test_encode_aggregator_hop() -> Result<()> {
+        let (token_in, token_out, router, multicaller) =
+            (Address::repeat_byte(1), Address::repeat_byte(2), Address::repeat_byte(3), Address::repeat_byte(4));
+        let hop = AggregatorHop::new(token_in, token_out, U256::from(1000), router, Bytes::from(vec![0x12, 0x34, 0x56, 0x78]))
+            .with_min_balance_out(U256::from(900));
+
+        let calls = hop.encode(multicaller)?;
+        let call_types: Vec<CallType> = calls.opcodes_vec.iter().map(|call| call.call_type.clone()).collect();
+        assert_eq!(call_types, vec![CallType::Call, CallType::Call, CallType::InternalCall, CallType::StaticCall]);
+        assert_eq!(calls.opcodes_vec[0].call_data, AbiEncoderHelper::encode_erc20_approve(router, U256::from(1000)));
+        assert_eq!(calls.opcodes_vec[1].to, router);
+        assert_eq!(calls.opcodes_vec[3].to, token_out);
+        assert!(calls.opcodes_vec[3].return_stack.is_some());
+
+        assert_eq!(hop.clone().with_min_balance_out(U256::ZERO).encode(multicaller)?.len(), 3);
+        assert!(AggregatorHop::new(token_in, token_out, U256::from(1000), router, Bytes::new()).encode(multicaller).is_err());
+        Ok(())
+    }
+}
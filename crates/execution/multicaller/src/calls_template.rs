@@ -0,0 +1,317 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::num::NonZeroUsize;
+use std::sync::Mutex;
+
+use alloy_primitives::{Bytes, U256};
+use eyre::Result;
+use lru::LruCache;
+use tracing::trace;
+
+use loom_types_blockchain::MulticallerCalls;
+use loom_types_entities::{Swap, SwapAmountType, SwapLine};
+
+use crate::{OpcodesEncoder, OpcodesEncoderV2};
+
+const PLACEHOLDER_TAG: u64 = 0x5EA1_5EA1_5EA1_5EA1;
+
+/// Unique amount that is replaced with the actual amount when the template is patched
+fn placeholder(amount_idx: usize) -> U256 {
+    U256::from_limbs([amount_idx as u64, PLACEHOLDER_TAG, PLACEHOLDER_TAG, 0])
+}
+
+fn hash_amount_type(amount: &SwapAmountType, state: &mut DefaultHasher) {
+    match amount {
+        SwapAmountType::NotSet => 0u8.hash(state),
+        SwapAmountType::Set(_) => 1u8.hash(state),
+        SwapAmountType::Stack0 => 2u8.hash(state),
+        SwapAmountType::RelativeStack(offset) => {
+            3u8.hash(state);
+            offset.hash(state);
+        }
+        SwapAmountType::Balance(address) => {
+            4u8.hash(state);
+            address.hash(state);
+        }
+    }
+}
+
+fn hash_swap_line(swap_line: &SwapLine, state: &mut DefaultHasher) {
+    swap_line.path.get_hash().hash(state);
+    hash_amount_type(&swap_line.amount_in, state);
+    hash_amount_type(&swap_line.amount_out, state);
+    swap_line.swap_to.hash(state);
+    // the payout of exchange swaps is encoded with the min out and the native payout, they are not patched into templates
+    swap_line.min_amount_out.hash(state);
+    swap_line.native_payout.hash(state);
+    swap_line.funding.hash(state);
+}
+
+fn hash_swap(swap: &Swap, state: &mut DefaultHasher) {
+    match swap {
+        Swap::None => 0u8.hash(state),
+        Swap::ExchangeSwapLine(swap_line) => {
+            1u8.hash(state);
+            hash_swap_line(swap_line, state);
+        }
+        Swap::BackrunSwapLine(swap_line) => {
+            2u8.hash(state);
+            hash_swap_line(swap_line, state);
+        }
+        Swap::BackrunSwapSteps((sp0, sp1)) => {
+            3u8.hash(state);
+            for swap_step in [sp0, sp1] {
+                swap_step.len().hash(state);
+                swap_step.swap_line_vec().iter().for_each(|swap_line| hash_swap_line(swap_line, state));
+            }
+        }
+        Swap::Multiple(swap_vec) => {
+            4u8.hash(state);
+            swap_vec.len().hash(state);
+            swap_vec.iter().for_each(|swap| hash_swap(swap, state));
+        }
+    }
+}
+
+fn has_quoted_hops(swap: &Swap) -> bool {
+    let is_quoted = |swap_line: &SwapLine| swap_line.pools().iter().any(|pool| pool.get_class().capabilities().quoted_min_out);
+    match swap {
+        Swap::None => false,
+        Swap::ExchangeSwapLine(swap_line) | Swap::BackrunSwapLine(swap_line) => is_quoted(swap_line),
+        Swap::BackrunSwapSteps((sp0, sp1)) => [sp0, sp1].iter().any(|swap_step| swap_step.swap_line_vec().iter().any(is_quoted)),
+        Swap::Multiple(swap_vec) => swap_vec.iter().any(has_quoted_hops),
+    }
+}
+
+/// Hash of the swap shape: paths, amount kinds, receivers with their payout and funding, but not the amount values
+fn swap_shape_hash(swap: &Swap) -> u64 {
+    let mut state = DefaultHasher::new();
+    hash_swap(swap, &mut state);
+    state.finish()
+}
+
+fn visit_swap_line_amounts(swap_line: &mut SwapLine, f: &mut impl FnMut(&mut U256)) {
+    if let SwapAmountType::Set(amount) = &mut swap_line.amount_in {
+        f(amount)
+    }
+    if let SwapAmountType::Set(amount) = &mut swap_line.amount_out {
+        f(amount)
+    }
+}
+
+/// Visit all set amounts of the swap in a stable order
+fn visit_swap_amounts(swap: &mut Swap, f: &mut impl FnMut(&mut U256)) {
+    match swap {
+        Swap::None => {}
+        Swap::ExchangeSwapLine(swap_line) | Swap::BackrunSwapLine(swap_line) => visit_swap_line_amounts(swap_line, f),
+        Swap::BackrunSwapSteps((sp0, sp1)) => {
+            for swap_step in [sp0, sp1] {
+                for idx in 0..swap_step.len() {
+                    visit_swap_line_amounts(swap_step.get_mut_swap_line_by_index(idx), f)
+                }
+            }
+        }
+        Swap::Multiple(swap_vec) => swap_vec.iter_mut().for_each(|swap| visit_swap_amounts(swap, f)),
+    }
+}
+
+fn swap_amounts(swap: &Swap) -> Vec<U256> {
+    let mut amounts = Vec::new();
+    visit_swap_amounts(&mut swap.clone(), &mut |amount| amounts.push(*amount));
+    amounts
+}
+
+fn with_placeholders(swap: &Swap) -> Swap {
+    let mut swap = swap.clone();
+    let mut amount_idx = 0;
+    visit_swap_amounts(&mut swap, &mut |amount| {
+        *amount = placeholder(amount_idx);
+        amount_idx += 1;
+    });
+    swap
+}
+
+fn packed(calls: &MulticallerCalls) -> Option<Bytes> {
+    OpcodesEncoderV2::pack_do_calls_data(calls).ok()
+}
+
+/// Encoded calls of a swap shape with the positions of the swap amounts in the call data.
+#[derive(Clone, Debug)]
+pub struct CallsTemplate {
+    calls: MulticallerCalls,
+    // (call index, call data offset, amount index)
+    placeholders: Vec<(usize, usize, usize)>,
+}
+
+impl CallsTemplate {
+    /// Locate the placeholders of `amounts_count` amounts. Returns `None` if an amount is not encoded as is,
+    /// e.g. negated or used in a calculation by the pool encoder.
+    fn build(calls: MulticallerCalls, amounts_count: usize) -> Option<Self> {
+        let mut placeholders = Vec::new();
+        for amount_idx in 0..amounts_count {
+            let word = placeholder(amount_idx).to_be_bytes::<32>();
+            let found = placeholders.len();
+            for (call_idx, call) in calls.opcodes_vec.iter().enumerate() {
+                let call_data = call.call_data.as_ref();
+                placeholders.extend(
+                    call_data
+                        .windows(32)
+                        .enumerate()
+                        .filter(|(_, window)| *window == word)
+                        .map(|(offset, _)| (call_idx, offset, amount_idx)),
+                );
+            }
+            if placeholders.len() == found {
+                return None;
+            }
+        }
+        Some(Self { calls, placeholders })
+    }
+
+    /// Calls with the placeholders replaced by `amounts`
+    pub fn patch(&self, amounts: &[U256]) -> MulticallerCalls {
+        let mut calls = self.calls.clone();
+        let mut call_data: Vec<Option<Vec<u8>>> = vec![None; calls.len()];
+        for (call_idx, offset, amount_idx) in self.placeholders.iter() {
+            let data = call_data[*call_idx].get_or_insert_with(|| calls.opcodes_vec[*call_idx].call_data.to_vec());
+            data[*offset..*offset + 32].copy_from_slice(&amounts[*amount_idx].to_be_bytes::<32>());
+        }
+        for (call_idx, data) in call_data.into_iter().enumerate() {
+            if let Some(data) = data {
+                calls.opcodes_vec[call_idx].call_data = data.into();
+            }
+        }
+        calls
+    }
+}
+
+/// Cache of encoded swap calls templates by swap shape. Swaps of a known shape are encoded by patching
+/// the amounts into the template instead of running the pool encoders again.
+pub struct CallsTemplateCache {
+    // `None` marks shapes that can not be templated
+    templates: Mutex<LruCache<u64, Option<CallsTemplate>>>,
+}
+
+impl CallsTemplateCache {
+    pub fn new(capacity: NonZeroUsize) -> Self {
+        Self { templates: Mutex::new(LruCache::new(capacity)) }
+    }
+
+    /// Encode the swap calls with the cached template of the swap shape. On a cache miss the swap is encoded with `encode`
+    /// and a template is created if patching it reproduces the same calls.
+    pub fn get_or_encode<F>(&self, swap: &Swap, encode: F) -> Result<MulticallerCalls>
+    where
+        F: Fn(&Swap) -> Result<MulticallerCalls>,
+    {
+        // min outs of quoted hops are derived from the calculated amounts, they are not patched into templates
+        if has_quoted_hops(swap) {
+            return encode(swap);
+        }
+
+        let shape_hash = swap_shape_hash(swap);
+        let amounts = swap_amounts(swap);
+
+        let cached = self.templates.lock().ok().and_then(|mut templates| templates.get(&shape_hash).cloned());
+        match cached {
+            Some(Some(template)) => return Ok(template.patch(&amounts)),
+            Some(None) => return encode(swap),
+            None => {}
+        }
+
+        let calls = encode(swap)?;
+        let template = encode(&with_placeholders(swap))
+            .ok()
+            .and_then(|template_calls| CallsTemplate::build(template_calls, amounts.len()))
+            .filter(|template| packed(&template.patch(&amounts)).is_some_and(|patched| Some(patched) == packed(&calls)));
+        trace!(shape_hash, templated = template.is_some(), "Swap calls template created");
+
+        if let Ok(mut templates) = self.templates.lock() {
+            templates.put(shape_hash, template);
+        }
+        Ok(calls)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloy_primitives::Address;
+    use loom_types_blockchain::MulticallerCall;
+    use loom_types_entities::{MockPool, SwapPath, Token};
+    use std::sync::Arc;
+
+    fn swap(amount_in: U256) -> Swap {
+        let token0 = Arc::new(Token::new(Address::repeat_byte(1)));
+        let token1 = Arc::new(Token::new(Address::repeat_byte(2)));
+        let pool = MockPool::new(token0.get_address(), token1.get_address(), Address::repeat_byte(3));
+        let mut swap_line = SwapLine::from(SwapPath::new_swap(token0, token1, pool.into()));
+        swap_line.amount_in = SwapAmountType::Set(amount_in);
+        Swap::ExchangeSwapLine(swap_line)
+    }
+
+    fn encode(swap: &Swap) -> Result<MulticallerCalls> {
+        let Swap::ExchangeSwapLine(swap_line) = swap else { unreachable!() };
+        let mut call_data = vec![0xAA; 4];
+        call_data.extend_from_slice(&swap_line.amount_in.unwrap().to_be_bytes::<32>());
+        let mut calls = MulticallerCalls::new();
+        calls.add(MulticallerCall::new_call(Address::ZERO, &call_data.into()));
+        Ok(calls)
+    }
+
+    #[test]
+    fn test_patch_cached_template() {
+        let cache = CallsTemplateCache::new(NonZeroUsize::new(10).unwrap());
+        let first = cache.get_or_encode(&swap(U256::from(100)), encode).unwrap();
+        assert_eq!(packed(&first), packed(&encode(&swap(U256::from(100))).unwrap()));
+
+        let patched =
+            cache.get_or_encode(&swap(U256::from(200)), |_: &Swap| -> Result<MulticallerCalls> { panic!("template not used") }).unwrap();
+        assert_eq!(packed(&patched), packed(&encode(&swap(U256::from(200))).unwrap()));
+    }
+
+    #[test]
+    fn test_not_templated_amount() {
+        let cache = CallsTemplateCache::new(NonZeroUsize::new(10).unwrap());
+        let encode_doubled = |swap: &Swap| -> Result<MulticallerCalls> {
+            let Swap::ExchangeSwapLine(swap_line) = swap else { unreachable!() };
+            let mut calls = MulticallerCalls::new();
+            let call_data = (swap_line.amount_in.unwrap() * U256::from(2)).to_be_bytes::<32>().to_vec();
+            calls.add(MulticallerCall::new_call(Address::ZERO, &call_data.into()));
+            Ok(calls)
+        };
+        cache.get_or_encode(&swap(U256::from(100)), encode_doubled).unwrap();
+        let calls = cache.get_or_encode(&swap(U256::from(200)), encode_doubled).unwrap();
+        assert_eq!(packed(&calls), packed(&encode_doubled(&swap(U256::from(200))).unwrap()));
+    }
+
+    #[test]
+    fn test_payout_not_shared() {
+        let with_payout = |min_amount_out: Option<U256>, native_payout: bool| {
+            let Swap::ExchangeSwapLine(mut swap_line) = swap(U256::from(100)) else { unreachable!() };
+            swap_line.swap_to = Some(Address::repeat_byte(4));
+            swap_line.min_amount_out = min_amount_out;
+            swap_line.native_payout = native_payout;
+            Swap::ExchangeSwapLine(swap_line)
+        };
+        let encode_payout = |swap: &Swap| -> Result<MulticallerCalls> {
+            let Swap::ExchangeSwapLine(swap_line) = swap else { unreachable!() };
+            let mut calls = encode(swap)?;
+            let mut call_data = swap_line.min_amount_out.unwrap_or_default().to_be_bytes::<32>().to_vec();
+            call_data.push(swap_line.native_payout as u8);
+            calls.add(MulticallerCall::new_call(Address::ZERO, &call_data.into()));
+            Ok(calls)
+        };
+
+        let cache = CallsTemplateCache::new(NonZeroUsize::new(10).unwrap());
+        let first = with_payout(Some(U256::from(90)), false);
+        cache.get_or_encode(&first, encode_payout).unwrap();
+        assert_eq!(swap_shape_hash(&first), swap_shape_hash(&with_payout(Some(U256::from(90)), false)));
+
+        // lines differing only in the min out or the payout mode are encoded instead of patched into the cached template
+        for other in [with_payout(Some(U256::from(95)), false), with_payout(Some(U256::from(90)), true), with_payout(None, false)] {
+            assert_ne!(swap_shape_hash(&first), swap_shape_hash(&other));
+            let calls = cache.get_or_encode(&other, encode_payout).unwrap();
+            assert_eq!(packed(&calls), packed(&encode_payout(&other).unwrap()));
+        }
+    }
+}
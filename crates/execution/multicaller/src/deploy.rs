@@ -4,10 +4,10 @@ use alloy_eips::eip1559::BaseFeeParams;
 use alloy_network::eip2718::Encodable2718;
 use alloy_network::primitives::BlockTransactionsKind;
 use alloy_network::{Ethereum, EthereumWallet, TransactionBuilder, TxSigner};
-use alloy_primitives::{hex, Address, Bytes, TxKind, B256};
+use alloy_primitives::{hex, keccak256, Address, Bytes, TxKind, B256, U256};
 use alloy_provider::ext::AnvilApi;
 use alloy_provider::Provider;
-use alloy_rpc_types::{BlockId, BlockNumberOrTag, TransactionInput, TransactionRequest};
+use alloy_rpc_types::{BlockId, BlockNumberOrTag, TransactionInput, TransactionReceipt, TransactionRequest};
 use alloy_rpc_types_trace::geth::AccountState;
 use alloy_signer_local::PrivateKeySigner;
 use eyre::{eyre, OptionExt, Result};
@@ -29,7 +29,7 @@ pub struct MulticallerDeployer {
 
 impl MulticallerDeployer {
     pub fn with_address(self, address: Address) -> Self {
-        Self { address: Some(address), ..Default::default() }
+        Self { address: Some(address), ..self }
     }
     pub fn new() -> Self {
         Self { code: Bytes::from(NO_OWNER_CODE.clone()), ..Default::default() }
@@ -40,6 +40,63 @@ impl MulticallerDeployer {
     }
 
     pub async fn deploy<P>(self, client: P, priv_key: SecretKey) -> Result<Self>
+    where
+        P: Provider<Ethereum> + Send + Sync + Clone + 'static,
+    {
+        let mut tx_request = TransactionRequest::default().gas_limit(5_000_000).input(TransactionInput::new(self.deploy_code()));
+        tx_request.to = Some(TxKind::Create);
+
+        let receipt = Self::send_transaction(client, priv_key, tx_request).await?;
+        let address = receipt.contract_address.ok_or_eyre("NOT_DEPLOYED")?;
+        info!("Multicaller deployed at {}", address);
+
+        Ok(Self { address: Some(address), ..self })
+    }
+
+    /// Deploy the multicaller, check the deployed code and send `funding` wei to it
+    pub async fn deploy_verified<P>(self, client: P, priv_key: SecretKey, funding: U256) -> Result<Self>
+    where
+        P: Provider<Ethereum> + Send + Sync + Clone + 'static,
+    {
+        let deployer = self.deploy(client.clone(), priv_key.clone()).await?;
+        deployer.verify(client.clone()).await?;
+        if !funding.is_zero() {
+            deployer.fund(client, priv_key, funding).await?;
+        }
+        Ok(deployer)
+    }
+
+    /// Check that the code at the multicaller address matches the embedded code
+    pub async fn verify<P>(&self, client: P) -> Result<()>
+    where
+        P: Provider<Ethereum> + Send + Sync + Clone + 'static,
+    {
+        let address = self.address.ok_or_eyre("NOT_DEPLOYED")?;
+        let code = client.get_code_at(address).await.map_err(|e| {
+            error!("{e}");
+            eyre!("CANNOT_GET_CODE")
+        })?;
+
+        if keccak256(&code) != self.code_hash() {
+            error!("Code hash mismatch at {} : {} != {}", address, keccak256(&code), self.code_hash());
+            return Err(eyre!("CODE_HASH_MISMATCH"));
+        }
+        Ok(())
+    }
+
+    pub async fn fund<P>(&self, client: P, priv_key: SecretKey, amount: U256) -> Result<()>
+    where
+        P: Provider<Ethereum> + Send + Sync + Clone + 'static,
+    {
+        let address = self.address.ok_or_eyre("NOT_DEPLOYED")?;
+        let tx_request = TransactionRequest::default().gas_limit(50_000).to(address).value(amount);
+
+        Self::send_transaction(client, priv_key, tx_request).await?;
+        info!("Multicaller {} funded with {} wei", address, amount);
+        Ok(())
+    }
+
+    async fn send_transaction<P>(client: P, priv_key: SecretKey, tx_request: TransactionRequest) -> Result<TransactionReceipt>
     where
         P: Provider<Ethereum> + Send + Sync + Clone + 'static,
     {
@@ -77,15 +134,7 @@ impl MulticallerDeployer {
                 eyre!("CANNOT_GET_NONCE")
             })?;
 
-        let mut tx_request = TransactionRequest::default()
-            .gas_limit(3_000_000)
-            .transaction_type(2)
-            .max_fee_per_gas(next_base_fee as u128)
-            .max_priority_fee_per_gas(1)
-            .input(TransactionInput::new(self.code.clone()))
-            //.to(Address::ZERO)
-            .nonce(nonce);
-        tx_request.to = Some(TxKind::Create);
+        let tx_request = tx_request.transaction_type(2).max_fee_per_gas(next_base_fee as u128).max_priority_fee_per_gas(1).nonce(nonce);
 
         let tx = tx_request.build(&wallet).await.map_err(|e| {
             error!("{e}");
@@ -103,18 +152,16 @@ impl MulticallerDeployer {
         while block_number < final_block {
             let receipt = client.get_transaction_receipt(*pending_tx.tx_hash()).await?;
             if let Some(receipt) = receipt {
-                let address = receipt.contract_address.ok_or_eyre("NOT_DEPLOYED")?;
-                return Ok(Self { address: Some(address), ..self });
+                if !receipt.status() {
+                    return Err(eyre!("TX_REVERTED"));
+                }
+                return Ok(receipt);
             }
             tokio::time::sleep(Duration::from_secs(12)).await;
             block_number = client.get_block_number().await?;
         }
 
         Err(eyre!("NO_RECEIPT_FOUND"))
-        //let receipt = pending_tx.with_timeout(Some(Duration::new(10, 0))).get_receipt().await.map_err(|_| eyre!("CANNOT_GET_RECEIPT"))?;
-        //let receipt = pending_tx.with_timeout(Some(Duration::new(100, 0))).watch().await.map_err(|_| eyre!("CANNOT_GET_RECEIPT"))?;
-
-        //let address = Address::repeat_byte(3);
     }
 
     pub async fn set_code<P>(self, client: P, address: Address) -> Result<Self>
@@ -134,6 +181,11 @@ impl MulticallerDeployer {
         Bytes::from(ret)
     }
 
+    /// Hash of the runtime code the deployed multicaller must have
+    pub fn code_hash(&self) -> B256 {
+        keccak256(&self.code)
+    }
+
     pub fn address(&self) -> Option<Address> {
         self.address
     }
@@ -163,9 +215,11 @@ mod test {
 
         let multicaller = MulticallerDeployer::new();
 
-        let multicaller = multicaller.deploy(anvil_provider.clone(), priv_key).await?;
+        let multicaller = multicaller.deploy_verified(anvil_provider.clone(), priv_key, U256::from(10u64.pow(18))).await?;
 
-        assert_ne!(multicaller.address.unwrap_or_default(), Address::ZERO);
+        let address = multicaller.address.unwrap_or_default();
+        assert_ne!(address, Address::ZERO);
+        assert_eq!(anvil_provider.get_balance(address).await?, U256::from(10u64.pow(18)));
 
         Ok(())
     }
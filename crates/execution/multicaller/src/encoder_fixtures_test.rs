@@ -0,0 +1,239 @@
+//! Whole pipeline fixtures on an anvil fork: pools are loaded, swap paths are built, the most profitable swap is encoded
+//! and executed. The call data is compared with the recorded fixture in `fixtures/`, run with `UPDATE_FIXTURES=1` to
+//! record it again after an intended encoding change. The pool ABI call data fixtures do not need a node.
+use std::collections::BTreeMap;
+use std::env;
+use std::path::PathBuf;
+
+use alloy_eips::eip1559::BaseFeeParams;
+use alloy_network::eip2718::Encodable2718;
+use alloy_network::primitives::BlockTransactionsKind;
+use alloy_network::{EthereumWallet, TransactionBuilder, TxSigner};
+use alloy_primitives::{hex, Address, Bytes, B256, U256};
+use alloy_provider::ext::AnvilApi;
+use alloy_provider::Provider;
+use alloy_rpc_types::{BlockNumberOrTag, TransactionRequest};
+use alloy_signer_local::PrivateKeySigner;
+use eyre::{eyre, OptionExt, Result};
+use tracing::info;
+
+use loom_defi_address_book::{CurvePoolAddress, TokenAddressEth, UniswapV2PoolAddress, UniswapV3PoolAddress};
+use loom_defi_pools::{PoolLoadersBuilder, PoolsLoadingConfig, UniswapV2Pool, UniswapV3Pool};
+use loom_evm_db::LoomDBType;
+use loom_evm_utils::evm_env::env_for_block;
+use loom_evm_utils::BalanceCheater;
+use loom_node_debug_provider::{AnvilDebugProviderFactory, AnvilDebugProviderType};
+use loom_types_entities::required_state::RequiredStateReader;
+use loom_types_entities::{Market, MarketState, PoolClass, PoolId, PoolWrapper, Swap, SwapEncoder, SwapLine, Token};
+
+use crate::pool_abi_encoder::ProtocolAbiSwapEncoderTrait;
+use crate::{MulticallerDeployer, MulticallerSwapEncoder, ProtocolABIEncoderV2, DEFAULT_VIRTUAL_ADDRESS};
+
+const FORK_BLOCK: u64 = 20935488;
+// UniswapV2 reserves slot: reserve0 (112 bits), reserve1 (112 bits), blockTimestampLast (32 bits)
+const UNISWAP_V2_RESERVES_SLOT: u64 = 8;
+// Share of the pool balance donated to create the arbitrage, 1/20
+const DONATION_DIVISOR: u64 = 20;
+const START_AMOUNT: u64 = 100_000_000_000_000_000; // 0.1 ETH
+
+struct FixtureCase {
+    name: &'static str,
+    pools: &'static [(Address, PoolClass)],
+    // UniswapV2 pool that receives the donation and the donated token
+    imbalanced_pool: Address,
+    donated_token: Address,
+    // pools of the expected swap path in swap order, starting and ending with WETH
+    path: &'static [Address],
+}
+
+const FIXTURE_CASES: [FixtureCase; 3] = [
+    FixtureCase {
+        name: "uniswap2_uniswap3_usdc_weth",
+        pools: &[(UniswapV2PoolAddress::USDC_WETH, PoolClass::UniswapV2), (UniswapV3PoolAddress::USDC_WETH_500, PoolClass::UniswapV3)],
+        imbalanced_pool: UniswapV2PoolAddress::USDC_WETH,
+        donated_token: TokenAddressEth::USDC,
+        path: &[UniswapV2PoolAddress::USDC_WETH, UniswapV3PoolAddress::USDC_WETH_500],
+    },
+    FixtureCase {
+        name: "uniswap3_uniswap2_weth_usdt",
+        pools: &[(UniswapV2PoolAddress::WETH_USDT, PoolClass::UniswapV2), (UniswapV3PoolAddress::WETH_USDT_3000, PoolClass::UniswapV3)],
+        imbalanced_pool: UniswapV2PoolAddress::WETH_USDT,
+        donated_token: TokenAddressEth::WETH,
+        path: &[UniswapV3PoolAddress::WETH_USDT_3000, UniswapV2PoolAddress::WETH_USDT],
+    },
+    FixtureCase {
+        name: "uniswap2_curve_weth_usdt",
+        pools: &[(UniswapV2PoolAddress::WETH_USDT, PoolClass::UniswapV2), (CurvePoolAddress::USDT_BTC_ETH, PoolClass::Curve)],
+        imbalanced_pool: UniswapV2PoolAddress::WETH_USDT,
+        donated_token: TokenAddressEth::USDT,
+        path: &[UniswapV2PoolAddress::WETH_USDT, CurvePoolAddress::USDT_BTC_ETH],
+    },
+];
+
+/// Donate a share of the pool balance of `token` and sync the reserves, as `transfer` followed by `sync()` would do
+async fn donate_to_uniswap2_pool(client: &AnvilDebugProviderType, pool_address: Address, token: Address, is_token0: bool) -> Result<()> {
+    let balance = BalanceCheater::get_anvil_token_balance(client.clone(), token, pool_address).await?;
+    let balance = balance + balance / U256::from(DONATION_DIVISOR);
+    BalanceCheater::set_anvil_token_balance(client.clone(), token, pool_address, balance).await?;
+
+    let reserve_mask = (U256::from(1) << 112) - U256::from(1);
+    let reserves = client.get_storage_at(pool_address, U256::from(UNISWAP_V2_RESERVES_SLOT)).await?;
+    let reserves = if is_token0 { (reserves & !reserve_mask) | balance } else { (reserves & !(reserve_mask << 112)) | (balance << 112) };
+    client.anvil_set_storage_at(pool_address, U256::from(UNISWAP_V2_RESERVES_SLOT), B256::from(reserves)).await?;
+    Ok(())
+}
+
+async fn load_pool(client: &AnvilDebugProviderType, address: Address, pool_class: PoolClass) -> Result<PoolWrapper> {
+    let pool_loaders = PoolLoadersBuilder::default_pool_loaders(client.clone(), PoolsLoadingConfig::default());
+    pool_loaders.load_pool_without_provider(PoolId::Address(address), &pool_class).await
+}
+
+/// Sign and send the transaction from the first anvil account, returns if it succeeded
+async fn execute(client: &AnvilDebugProviderType, to: Address, call_data: Bytes) -> Result<bool> {
+    let header = client.get_block_by_number(BlockNumberOrTag::Latest, BlockTransactionsKind::Hashes).await?.ok_or_eyre("NO_BLOCK")?.header;
+    let next_base_fee =
+        BaseFeeParams::ethereum().next_block_base_fee(header.gas_used, header.gas_limit, header.base_fee_per_gas.unwrap_or_default());
+
+    let signer = PrivateKeySigner::from_bytes(&B256::from_slice(client.privkey()?.to_bytes().as_slice()))?;
+    let wallet = EthereumWallet::new(signer);
+    let nonce = client.get_transaction_count(wallet.default_signer().address()).await?;
+
+    let tx = TransactionRequest::default()
+        .to(to)
+        .input(call_data.into())
+        .gas_limit(3_000_000)
+        .transaction_type(2)
+        .max_fee_per_gas(next_base_fee as u128 * 2)
+        .max_priority_fee_per_gas(1)
+        .nonce(nonce)
+        .build(&wallet)
+        .await?;
+
+    let pending_tx = client.send_raw_transaction(tx.encoded_2718().as_slice()).await?;
+    let receipt = pending_tx.get_receipt().await?;
+    Ok(receipt.status())
+}
+
+/// Compare the call data with the recorded fixture, fails on a missing fixture unless `UPDATE_FIXTURES` is set
+fn assert_fixture(name: &str, call_data: &Bytes) -> Result<()> {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("fixtures").join(format!("{name}.calldata"));
+    let encoded = hex::encode_prefixed(call_data);
+
+    if env::var("UPDATE_FIXTURES").is_ok() {
+        std::fs::create_dir_all(path.parent().ok_or_eyre("NO_FIXTURES_DIR")?)?;
+        std::fs::write(&path, format!("{encoded}\n"))?;
+        info!("Fixture recorded : {}", path.display());
+        return Ok(());
+    }
+
+    if !path.exists() {
+        return Err(eyre!("FIXTURE_MISSING : {}, run with UPDATE_FIXTURES=1 to record it", path.display()));
+    }
+    let expected = std::fs::read_to_string(&path)?;
+    assert_eq!(expected.trim(), encoded, "Call data does not match fixture {}", path.display());
+    Ok(())
+}
+
+async fn run_fixture_case(node_url: String, case: &FixtureCase) -> Result<()> {
+    let client = AnvilDebugProviderFactory::from_node_on_block(node_url, FORK_BLOCK).await?;
+    let multicaller_address = MulticallerDeployer::new().set_code(client.clone(), DEFAULT_VIRTUAL_ADDRESS).await?.address().unwrap();
+
+    // pool loading
+    let mut market = Market::default();
+    market.add_token(Token::new_with_data(TokenAddressEth::WETH, Some("WETH".to_string()), None, Some(18), true, false));
+
+    let imbalanced_pool = load_pool(&client, case.imbalanced_pool, PoolClass::UniswapV2).await?;
+    let is_token0 = imbalanced_pool.get_tokens().first() == Some(&case.donated_token);
+    donate_to_uniswap2_pool(&client, case.imbalanced_pool, case.donated_token, is_token0).await?;
+
+    let mut market_state = MarketState::new(LoomDBType::new());
+    let mut directions = BTreeMap::new();
+    for (pool_address, pool_class) in case.pools.iter() {
+        let pool = load_pool(&client, *pool_address, *pool_class).await?;
+        let required_state = pool.get_state_required()?;
+        let state = RequiredStateReader::fetch_calls_and_slots(client.clone(), required_state, None).await?;
+        market_state.state_db.apply_geth_update(state);
+
+        for token_address in pool.get_tokens() {
+            if market.get_token(&token_address).is_none() {
+                market.add_token(Token::new(token_address));
+            }
+        }
+        directions.insert(pool.clone(), pool.get_swap_directions());
+        market.add_pool(pool).map_err(|e| eyre!("{e}"))?;
+    }
+
+    // path building
+    let swap_paths = market.build_swap_path_vec(&directions)?;
+    let swap_path = swap_paths
+        .into_iter()
+        .find(|swap_path| swap_path.pools.iter().map(|pool| pool.get_address()).eq(case.path.iter().copied()))
+        .ok_or_eyre("PATH_NOT_BUILT")?;
+
+    // simulation
+    let header = client.get_block_by_number(BlockNumberOrTag::Latest, BlockTransactionsKind::Hashes).await?.ok_or_eyre("NO_BLOCK")?.header;
+    let evm_env = env_for_block(header.number + 1, header.timestamp + 12);
+
+    let mut swap_line = SwapLine::from(swap_path);
+    swap_line
+        .optimize_with_in_amount(&market_state.state_db, evm_env, U256::from(START_AMOUNT))
+        .map_err(|e| eyre!("OPTIMIZATION_FAILED : {}", e.msg))?;
+    let profit = swap_line.abs_profit();
+    assert!(profit > U256::ZERO, "No profit for {}", case.name);
+
+    // encoding
+    let encoder = MulticallerSwapEncoder::default_with_address(multicaller_address);
+    let (to, _, call_data, _) = encoder.encode(Swap::BackrunSwapLine(swap_line), None, None, None, None, None, None)?;
+    assert_fixture(case.name, &call_data)?;
+
+    // execution
+    let balance_before = BalanceCheater::get_anvil_token_balance(client.clone(), TokenAddressEth::WETH, multicaller_address).await?;
+    assert!(execute(&client, to, call_data).await?, "Swap reverted for {}", case.name);
+    let balance_after = BalanceCheater::get_anvil_token_balance(client.clone(), TokenAddressEth::WETH, multicaller_address).await?;
+
+    info!("{} : calculated profit {} executed profit {}", case.name, profit, balance_after.saturating_sub(balance_before));
+    assert!(balance_after > balance_before, "No executed profit for {}", case.name);
+
+    Ok(())
+}
+
+#[test]
+fn test_abi_encoder_fixtures() -> Result<()> {
+    let encoder = ProtocolABIEncoderV2::default();
+
+    let pool = UniswapV2Pool::new(UniswapV2PoolAddress::USDC_WETH);
+    let call_data = encoder.encode_swap_out_amount_provided(
+        &pool,
+        TokenAddressEth::WETH,
+        TokenAddressEth::USDC,
+        U256::from(1_000_000_000u64),
+        DEFAULT_VIRTUAL_ADDRESS,
+        Bytes::from(vec![0x01, 0x02, 0x03, 0x04]),
+    )?;
+    assert_fixture("uniswap2_swap_out_weth_usdc", &call_data)?;
+
+    let pool = UniswapV3Pool::new(UniswapV3PoolAddress::USDC_WETH_500);
+    let call_data = encoder.encode_swap_in_amount_provided(
+        &pool,
+        TokenAddressEth::WETH,
+        TokenAddressEth::USDC,
+        U256::from(START_AMOUNT),
+        DEFAULT_VIRTUAL_ADDRESS,
+        Bytes::new(),
+    )?;
+    assert_fixture("uniswap3_swap_in_weth_usdc", &call_data)?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_encoder_fixtures() -> Result<()> {
+    let _ = env_logger::try_init_from_env(env_logger::Env::default().default_filter_or("info,alloy_rpc_client=off"));
+
+    let node_url = env::var("MAINNET_WS")?;
+
+    for case in FIXTURE_CASES.iter() {
+        run_fixture_case(node_url.clone(), case).await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,70 @@
+use alloy_primitives::Address;
+use eyre::Report;
+use loom_types_entities::{FundingMode, PoolClass, PoolId};
+use thiserror::Error;
+
+/// Swap encoding errors. They are returned wrapped into [`Report`], use [`EncoderError::from_report`] to match on them.
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum EncoderError {
+    #[error("Pool {pool} of class {class} is not supported by {encoder}")]
+    UnsupportedPoolClass { pool: PoolId, class: PoolClass, encoder: &'static str },
+    #[error("Pool {pool} of class {class} does not support {capability}")]
+    MissingCapability { pool: PoolId, class: PoolClass, capability: &'static str },
+    #[error("Encoding of {operation} is not implemented for pool {pool}")]
+    NotImplemented { pool: PoolId, operation: &'static str },
+    #[error("Cannot encode swap {token_from} -> {token_to} for pool {pool}")]
+    UnsupportedSwapDirection { pool: PoolId, token_from: Address, token_to: Address },
+    #[error("Pool {pool} has no abi encoder")]
+    NoPoolEncoder { pool: PoolId },
+    #[error("Pool {pool} has no amount offset for {token_from} -> {token_to}")]
+    NoAmountOffset { pool: PoolId, token_from: Address, token_to: Address },
+    #[error("Swap type is not supported")]
+    UnsupportedSwapType,
+    #[error("Chain has no flash loan provider to fund the swap")]
+    NoFlashLoanProvider,
+    #[error("Multicaller has no callback for flash loans of mode {mode:?}")]
+    UnsupportedFlashLoan { mode: FundingMode },
+    #[error("Swap has no swap steps")]
+    NoSwapSteps,
+    #[error("Swap line has no tokens")]
+    EmptySwapLine,
+    #[error("Balance of token is not set for amount from stack")]
+    BalanceOfTokenNotSet,
+    #[error("Out amount of pool {pool} checking a min out is not calculated")]
+    HopNotCalculated { pool: PoolId },
+}
+
+impl EncoderError {
+    pub fn from_report(report: &Report) -> Option<&EncoderError> {
+        report.downcast_ref::<EncoderError>()
+    }
+
+    /// The swap cannot be encoded for any state of the pools, e.g. the pool class has no encoder
+    pub fn is_unsupported(&self) -> bool {
+        matches!(
+            self,
+            Self::UnsupportedPoolClass { .. }
+                | Self::MissingCapability { .. }
+                | Self::NotImplemented { .. }
+                | Self::UnsupportedSwapDirection { .. }
+                | Self::NoPoolEncoder { .. }
+                | Self::NoAmountOffset { .. }
+                | Self::UnsupportedSwapType
+                | Self::NoFlashLoanProvider
+                | Self::UnsupportedFlashLoan { .. }
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use eyre::eyre;
+
+    #[test]
+    fn test_from_report() {
+        let report: Report = EncoderError::NotImplemented { pool: PoolId::default(), operation: "flash_swap" }.into();
+        assert!(EncoderError::from_report(&report).is_some_and(|error| error.is_unsupported()));
+        assert!(EncoderError::from_report(&eyre!("OTHER")).is_none());
+    }
+}
@@ -0,0 +1,249 @@
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+use alloy_primitives::{hex, keccak256, Address, Bytes, U256};
+use eyre::{OptionExt, Result};
+use loom_types_events::{SwapComposeData, TxState};
+use serde::{Deserialize, Serialize};
+
+/// Opportunity found by a searcher with the transaction executing it, enough to replay the swap on a fork of the block it
+/// was computed on
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RecordedOpportunity {
+    /// Block the opportunity was found on, the swap targets the next block
+    pub block_number: u64,
+    pub next_block_timestamp: u64,
+    pub next_block_base_fee: u64,
+    /// Raw signed transactions backrun by the swap, executed before it
+    pub stuffing_txs: Vec<Bytes>,
+    pub caller: Address,
+    pub multicaller: Address,
+    pub value: U256,
+    pub gas_limit: u64,
+    pub calldata: Bytes,
+    /// Swap path and amounts as displayed by the searcher
+    pub swap: String,
+    /// Token the profit is taken in, its multicaller balance is logged by the test
+    pub profit_token: Option<Address>,
+    pub profit: U256,
+}
+
+impl RecordedOpportunity {
+    /// Record the encoded swap transaction of the compose data
+    pub fn from_compose_data<DB>(compose_data: &SwapComposeData<DB>) -> Result<Self> {
+        let tx_compose = &compose_data.tx_compose;
+        let request = tx_compose
+            .tx_bundle
+            .iter()
+            .flatten()
+            .find_map(|tx_state| match tx_state {
+                TxState::SignatureRequired(request) => Some(request),
+                _ => None,
+            })
+            .ok_or_eyre("NO_SWAP_TRANSACTION")?;
+        let multicaller = request.to.as_ref().and_then(|to| to.to().copied()).ok_or_eyre("NO_SWAP_TARGET")?;
+
+        Ok(Self {
+            block_number: tx_compose.next_block_number.saturating_sub(1),
+            next_block_timestamp: tx_compose.next_block_timestamp,
+            next_block_base_fee: tx_compose.next_block_base_fee,
+            stuffing_txs: tx_compose
+                .tx_bundle
+                .iter()
+                .flatten()
+                .filter_map(|tx_state| match tx_state {
+                    TxState::ReadyForBroadcastStuffing(raw_tx) => Some(raw_tx.clone()),
+                    _ => None,
+                })
+                .collect(),
+            caller: request.from.or(tx_compose.eoa).unwrap_or_default(),
+            multicaller,
+            value: request.value.unwrap_or_default(),
+            gas_limit: request.gas.unwrap_or(tx_compose.gas),
+            calldata: request.input.input().cloned().unwrap_or_default(),
+            swap: compose_data.swap.to_string(),
+            profit_token: compose_data.swap.get_first_token().map(|token| token.get_address()),
+            profit: compose_data.swap.abs_profit(),
+        })
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        Ok(serde_json::from_slice(&std::fs::read(path)?)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        std::fs::write(path, serde_json::to_vec_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Contract name of the foundry test, unique for the calldata of the block
+    pub fn contract_name(&self) -> String {
+        format!("Replay{}_{}", self.block_number, hex::encode(&keccak256(&self.calldata)[..4]))
+    }
+
+    /// Save the opportunity and its foundry test to the directory as `<contract_name>.json` and `<contract_name>.t.sol`,
+    /// returning the path of the test
+    pub fn record(&self, dir: impl AsRef<Path>) -> Result<PathBuf> {
+        let dir = dir.as_ref();
+        std::fs::create_dir_all(dir)?;
+        let contract_name = self.contract_name();
+        self.save(dir.join(format!("{contract_name}.json")))?;
+        let test_path = dir.join(format!("{contract_name}.t.sol"));
+        std::fs::write(&test_path, self.to_foundry_test(&contract_name))?;
+        Ok(test_path)
+    }
+
+    /// Foundry test forking the block of the opportunity, executing the signed backrun transactions in the next block and
+    /// calling the multicaller with the recorded calldata from the caller. Run with `ETH_RPC_URL` set to an archive node:
+    /// `forge test --match-contract <contract_name> -vvvv`
+    pub fn to_foundry_test(&self, contract_name: &str) -> String {
+        let mut stuffing_txs = String::new();
+        for raw_tx in self.stuffing_txs.iter() {
+            let _ = writeln!(stuffing_txs, "        vm.broadcastRawTransaction(hex\"{}\");", hex::encode(raw_tx));
+        }
+        let profit_token = self.profit_token.map_or("address(0)".to_string(), |token| token.to_string());
+
+        format!(
+            r#"// SPDX-License-Identifier: UNLICENSED
+pragma solidity ^0.8.15;
+
+import "forge-std/Test.sol";
+import "forge-std/console.sol";
+
+interface IERC20 {{
+    function balanceOf(address account) external view returns (uint256);
+}}
+
+// {swap}
+// expected profit {profit}
+contract {contract_name} is Test {{
+    uint256 constant FORK_BLOCK = {block_number};
+    address constant CALLER = {caller};
+    address constant MULTICALLER = {multicaller};
+    address constant PROFIT_TOKEN = {profit_token};
+    uint256 constant VALUE = {value};
+    uint256 constant GAS_LIMIT = {gas_limit};
+    bytes constant CALLDATA = hex"{calldata}";
+
+    function setUp() public {{
+        vm.createSelectFork(vm.envString("ETH_RPC_URL"), FORK_BLOCK);
+    }}
+
+    function test_replay() public {{
+        vm.roll(FORK_BLOCK + 1);
+        vm.warp({next_block_timestamp});
+        vm.fee({next_block_base_fee});
+
+        // backrun transactions, signed and executed in the order they preceded the swap
+{stuffing_txs}
+        vm.deal(CALLER, CALLER.balance + VALUE);
+
+        uint256 balanceBefore = PROFIT_TOKEN == address(0) ? 0 : IERC20(PROFIT_TOKEN).balanceOf(MULTICALLER);
+
+        vm.prank(CALLER, CALLER);
+        uint256 gasBefore = gasleft();
+        (bool success, bytes memory result) = MULTICALLER.call{{value: VALUE, gas: GAS_LIMIT}}(CALLDATA);
+        uint256 gasUsed = gasBefore - gasleft();
+
+        if (!success) {{
+            console.logBytes(result);
+        }}
+        assertTrue(success, "swap reverted");
+        console.log("gas used", gasUsed);
+        if (PROFIT_TOKEN != address(0)) {{
+            console.log("balance before", balanceBefore);
+            console.log("balance after", IERC20(PROFIT_TOKEN).balanceOf(MULTICALLER));
+        }}
+    }}
+}}
+"#,
+            swap = self.swap,
+            profit = self.profit,
+            block_number = self.block_number,
+            caller = self.caller,
+            multicaller = self.multicaller,
+            value = self.value,
+            gas_limit = self.gas_limit,
+            calldata = hex::encode(&self.calldata),
+            next_block_timestamp = self.next_block_timestamp,
+            next_block_base_fee = self.next_block_base_fee,
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloy_primitives::TxKind;
+    use alloy_rpc_types::{TransactionInput, TransactionRequest};
+    use loom_evm_db::LoomDBType;
+    use loom_types_events::TxComposeData;
+
+    #[test]
+    fn test_to_foundry_test() {
+        let opportunity = RecordedOpportunity {
+            block_number: 21_000_000,
+            next_block_timestamp: 1_729_000_000,
+            next_block_base_fee: 10_000_000_000,
+            stuffing_txs: vec![Bytes::from(vec![0x02, 0xF8, 0x70])],
+            caller: Address::repeat_byte(1),
+            multicaller: Address::repeat_byte(2),
+            value: U256::ZERO,
+            gas_limit: 300_000,
+            calldata: Bytes::from(vec![0xDE, 0xAD, 0xBE, 0xEF]),
+            swap: "WETH -> USDC -> WETH".to_string(),
+            profit_token: None,
+            profit: U256::from(1_000),
+        };
+
+        let source = opportunity.to_foundry_test("Replay21000000");
+        assert!(source.contains("contract Replay21000000 is Test {"));
+        assert!(source.contains("uint256 constant FORK_BLOCK = 21000000;"));
+        assert!(source.contains("bytes constant CALLDATA = hex\"deadbeef\";"));
+        assert!(source.contains("vm.broadcastRawTransaction(hex\"02f870\");"));
+        assert!(source.contains("address constant PROFIT_TOKEN = address(0);"));
+        // the backrun transactions are executed in the block of the swap
+        assert!(source.find("vm.roll(FORK_BLOCK + 1);") < source.find("vm.broadcastRawTransaction"));
+
+        let decoded: RecordedOpportunity = serde_json::from_str(&serde_json::to_string(&opportunity).unwrap()).unwrap();
+        assert_eq!(decoded.calldata, opportunity.calldata);
+        assert_eq!(decoded.stuffing_txs, opportunity.stuffing_txs);
+    }
+
+    #[test]
+    fn test_record() -> Result<()> {
+        let raw_tx = Bytes::from(vec![0x02, 0xF8, 0x70, 0x01]);
+        let request = TransactionRequest {
+            from: Some(Address::repeat_byte(1)),
+            to: Some(TxKind::Call(Address::repeat_byte(2))),
+            gas: Some(250_000),
+            input: TransactionInput::new(Bytes::from(vec![0xDE, 0xAD, 0xBE, 0xEF])),
+            ..TransactionRequest::default()
+        };
+        let compose_data: SwapComposeData<LoomDBType> = SwapComposeData {
+            tx_compose: TxComposeData {
+                next_block_number: 21_000_001,
+                tx_bundle: Some(vec![TxState::ReadyForBroadcastStuffing(raw_tx.clone()), TxState::SignatureRequired(request)]),
+                ..TxComposeData::default()
+            },
+            ..SwapComposeData::default()
+        };
+
+        let opportunity = RecordedOpportunity::from_compose_data(&compose_data)?;
+        assert_eq!(opportunity.block_number, 21_000_000);
+        assert_eq!(opportunity.stuffing_txs, vec![raw_tx]);
+        assert_eq!(opportunity.multicaller, Address::repeat_byte(2));
+        assert_eq!(opportunity.gas_limit, 250_000);
+
+        let dir = std::env::temp_dir().join(format!("loom_foundry_export_{}", std::process::id()));
+        let test_path = opportunity.record(&dir)?;
+        let contract_name = opportunity.contract_name();
+        assert_eq!(test_path, dir.join(format!("{contract_name}.t.sol")));
+        assert!(std::fs::read_to_string(&test_path)?.contains("vm.broadcastRawTransaction(hex\"02f87001\");"));
+        let loaded = RecordedOpportunity::load(dir.join(format!("{contract_name}.json")))?;
+        assert_eq!(loaded.calldata, opportunity.calldata);
+        std::fs::remove_dir_all(dir)?;
+
+        Ok(())
+    }
+}
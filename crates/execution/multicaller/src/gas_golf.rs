@@ -0,0 +1,161 @@
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+
+use alloy_primitives::Address;
+use loom_types_blockchain::LoomDataTypesEthereum;
+use loom_types_entities::{PoolProtocol, SwapAmountType, SwapLine};
+
+// gas of uni2GetOutAmount helpers including the getReserves staticcall, measured on a mainnet fork
+const INTERNAL_HELPER_GAS: u64 = 4_300;
+// getAmountOut(uint256,address) of solidly pairs reads the fee from the factory
+const EXTERNAL_STATIC_CALL_GAS: u64 = 7_600;
+
+/// How the out amount of a hop is calculated before the swap call
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AmountQuoteSource {
+    /// Multicaller helper calculating the amount from the pool reserves, like `uni2GetOutAmountFrom0`
+    InternalHelper,
+    /// Staticcall of the view function of the pool, like `getAmountOut(uint256,address)` of solidly pairs
+    ExternalStaticCall,
+}
+
+impl AmountQuoteSource {
+    /// Pools providing their own out amount calculation
+    pub fn external_available(protocol: PoolProtocol) -> bool {
+        matches!(protocol, PoolProtocol::Ramses | PoolProtocol::Thena | PoolProtocol::Equalizer)
+    }
+}
+
+/// Gas used by the opcodes calculating hop amounts, used to pick the cheaper variant per protocol. Sources without a
+/// measurement fall back to the gas measured on a mainnet fork
+#[derive(Clone, Debug, Default)]
+pub struct OpcodeGasTable {
+    measured: HashMap<(PoolProtocol, AmountQuoteSource), u64>,
+}
+
+impl OpcodeGasTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_measured(mut self, protocol: PoolProtocol, source: AmountQuoteSource, gas: u64) -> Self {
+        self.measured.insert((protocol, source), gas);
+        self
+    }
+
+    /// Measured gas or the default for the source
+    pub fn gas(&self, protocol: PoolProtocol, source: AmountQuoteSource) -> u64 {
+        self.measured.get(&(protocol, source)).copied().unwrap_or(match source {
+            AmountQuoteSource::InternalHelper => INTERNAL_HELPER_GAS,
+            AmountQuoteSource::ExternalStaticCall => EXTERNAL_STATIC_CALL_GAS,
+        })
+    }
+
+    /// Cheapest quote source for the protocol, the internal helper wins ties
+    pub fn select(&self, protocol: PoolProtocol) -> AmountQuoteSource {
+        if AmountQuoteSource::external_available(protocol)
+            && self.gas(protocol, AmountQuoteSource::ExternalStaticCall) < self.gas(protocol, AmountQuoteSource::InternalHelper)
+        {
+            AmountQuoteSource::ExternalStaticCall
+        } else {
+            AmountQuoteSource::InternalHelper
+        }
+    }
+}
+
+fn touched_addresses(swap_line: &SwapLine<LoomDataTypesEthereum>) -> impl Iterator<Item = Address> + '_ {
+    swap_line.pools().iter().map(|pool| pool.get_address()).chain(swap_line.tokens().iter().map(|token| token.get_address()))
+}
+
+/// Order swap lines so each line reuses as many already accessed (warm) pool and token addresses as possible.
+/// Lines reading their amount from the balance or the stack depend on the lines before them and keep their position,
+/// only lines with a set amount are reordered between them.
+pub fn warm_access_order(swap_lines: &[SwapLine<LoomDataTypesEthereum>]) -> Vec<&SwapLine<LoomDataTypesEthereum>> {
+    let mut ordered = Vec::with_capacity(swap_lines.len());
+    let mut warm: HashSet<Address> = HashSet::new();
+
+    for segment in swap_lines.split_inclusive(|swap_line| !matches!(swap_line.amount_in, SwapAmountType::Set(_))) {
+        let (mut independent, fixed) = match segment.split_last() {
+            Some((last, rest)) if !matches!(last.amount_in, SwapAmountType::Set(_)) => (rest.iter().collect::<Vec<_>>(), Some(last)),
+            _ => (segment.iter().collect::<Vec<_>>(), None),
+        };
+
+        while !independent.is_empty() {
+            let best = independent
+                .iter()
+                .enumerate()
+                .max_by_key(|(idx, swap_line)| {
+                    (touched_addresses(swap_line).filter(|address| warm.contains(address)).count(), Reverse(*idx))
+                })
+                .map(|(idx, _)| idx)
+                .unwrap_or_default();
+            let swap_line = independent.remove(best);
+            warm.extend(touched_addresses(swap_line));
+            ordered.push(swap_line);
+        }
+
+        if let Some(swap_line) = fixed {
+            warm.extend(touched_addresses(swap_line));
+            ordered.push(swap_line);
+        }
+    }
+
+    ordered
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloy_primitives::U256;
+    use loom_types_entities::{MockPool, SwapPath, Token};
+    use std::sync::Arc;
+
+    fn swap_line(token_from: u8, token_to: u8, pool: u8, amount_in: SwapAmountType) -> SwapLine<LoomDataTypesEthereum> {
+        let token_from = Arc::new(Token::new(Address::repeat_byte(token_from)));
+        let token_to = Arc::new(Token::new(Address::repeat_byte(token_to)));
+        let pool = MockPool::new(token_from.get_address(), token_to.get_address(), Address::repeat_byte(pool));
+        let mut swap_line = SwapLine::from(SwapPath::new_swap(token_from, token_to, pool.into()));
+        swap_line.amount_in = amount_in;
+        swap_line
+    }
+
+    #[test]
+    fn test_select_quote_source() {
+        let table = OpcodeGasTable::new();
+        assert_eq!(table.select(PoolProtocol::UniswapV2), AmountQuoteSource::InternalHelper);
+        assert_eq!(table.select(PoolProtocol::Thena), AmountQuoteSource::InternalHelper);
+
+        let table = table.with_measured(PoolProtocol::Thena, AmountQuoteSource::ExternalStaticCall, 3_000);
+        assert_eq!(table.select(PoolProtocol::Thena), AmountQuoteSource::ExternalStaticCall);
+        assert_eq!(table.select(PoolProtocol::Ramses), AmountQuoteSource::InternalHelper);
+
+        let table = table.with_measured(PoolProtocol::UniswapV2, AmountQuoteSource::ExternalStaticCall, 1_000);
+        assert_eq!(table.select(PoolProtocol::UniswapV2), AmountQuoteSource::InternalHelper);
+    }
+
+    #[test]
+    fn test_warm_access_order() {
+        let amount = SwapAmountType::Set(U256::from(100));
+        let balance = SwapAmountType::Balance(Address::repeat_byte(0xFF));
+        let swap_lines = vec![
+            swap_line(1, 2, 0x10, amount),
+            swap_line(3, 4, 0x11, amount),
+            swap_line(2, 5, 0x12, amount),
+            swap_line(4, 1, 0x13, balance),
+            swap_line(6, 7, 0x14, amount),
+        ];
+
+        let ordered: Vec<Address> =
+            warm_access_order(&swap_lines).iter().map(|swap_line| swap_line.get_first_pool().unwrap().get_address()).collect();
+        assert_eq!(
+            ordered,
+            vec![
+                Address::repeat_byte(0x10),
+                Address::repeat_byte(0x12),
+                Address::repeat_byte(0x11),
+                Address::repeat_byte(0x13),
+                Address::repeat_byte(0x14)
+            ]
+        );
+    }
+}
@@ -1,18 +1,32 @@
 #![allow(dead_code)]
+pub use aggregator_encoder::AggregatorHop;
+pub use calls_template::{CallsTemplate, CallsTemplateCache};
 pub use deploy::{MulticallerDeployer, DEFAULT_VIRTUAL_ADDRESS};
+pub use errors::EncoderError;
+pub use foundry_export::RecordedOpportunity;
+pub use gas_golf::{warm_access_order, AmountQuoteSource, OpcodeGasTable};
 pub use multicaller_encoder::MulticallerEncoder;
 pub use multicaller_encoder::MulticallerSwapEncoder;
 pub use opcodes_encoder::{OpcodesEncoder, OpcodesEncoderV2};
 pub use pool_abi_encoder::ProtocolABIEncoderV2;
+pub use revm_multicaller::RevmMulticaller;
 pub use swapline_encoder::SwapLineEncoder;
 pub use swapstep_encoder::SwapStepEncoder;
 
+mod aggregator_encoder;
+mod calls_template;
 mod deploy;
+#[cfg(test)]
+mod encoder_fixtures_test;
+mod errors;
+mod foundry_export;
+mod gas_golf;
 mod multicaller_encoder;
 mod opcodes_encoder;
 mod opcodes_helpers;
 pub mod pool_abi_encoder;
 pub mod pool_opcodes_encoder;
+mod revm_multicaller;
 mod swap_encoder;
 mod swapline_encoder;
 mod swapstep_encoder;
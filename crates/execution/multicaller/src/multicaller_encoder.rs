@@ -1,13 +1,17 @@
 use alloy_primitives::{Address, Bytes};
-use eyre::{eyre, OptionExt, Result};
+use eyre::Result;
+use std::num::NonZeroUsize;
 use std::sync::Arc;
 use tracing::error;
 
+use crate::gas_golf::OpcodeGasTable;
 use crate::pool_abi_encoder::ProtocolABIEncoderV2;
 use crate::pool_opcodes_encoder::ProtocolSwapOpcodesEncoderV2;
-use crate::{SwapLineEncoder, SwapStepEncoder, DEFAULT_VIRTUAL_ADDRESS};
+use crate::{CallsTemplateCache, EncoderError, SwapLineEncoder, SwapStepEncoder, DEFAULT_VIRTUAL_ADDRESS};
 use loom_types_blockchain::MulticallerCalls;
-use loom_types_entities::Swap;
+use loom_types_entities::{ChainPreset, ExecutionProfile, FundingMode, SharedTipRecipients, Swap, TipRecipient};
+
+const CALLS_TEMPLATES_CAPACITY: NonZeroUsize = NonZeroUsize::new(10_000).unwrap();
 
 pub trait MulticallerEncoder {
     fn encode_calls(&self, calls: MulticallerCalls) -> Result<(Address, Bytes)>;
@@ -19,11 +23,31 @@ pub trait MulticallerEncoder {
 pub struct MulticallerSwapEncoder {
     pub multicaller_address: Address,
     pub swap_step_encoder: SwapStepEncoder,
+    pub calls_templates: Arc<CallsTemplateCache>,
+    opcodes_encoder: ProtocolSwapOpcodesEncoderV2,
+    pub(crate) execution_profile: ExecutionProfile,
+    pub(crate) chain_preset: ChainPreset,
+    pub(crate) unwrap_native_payout: bool,
+    pub(crate) deadline_router: Option<Address>,
+    pub(crate) tip_recipients: Option<SharedTipRecipients>,
+    /// Builder the encoded swaps are sent to, picks its tip recipient
+    pub(crate) tip_builder: Option<String>,
 }
 
 impl MulticallerSwapEncoder {
     pub fn new(multicaller_address: Address, swap_step_encoder: SwapStepEncoder) -> Self {
-        Self { multicaller_address, swap_step_encoder }
+        Self {
+            multicaller_address,
+            swap_step_encoder,
+            calls_templates: Arc::new(CallsTemplateCache::new(CALLS_TEMPLATES_CAPACITY)),
+            opcodes_encoder: ProtocolSwapOpcodesEncoderV2::default(),
+            execution_profile: ExecutionProfile::default(),
+            chain_preset: ChainPreset::default(),
+            unwrap_native_payout: false,
+            deadline_router: ChainPreset::default().deadline_router,
+            tip_recipients: None,
+            tip_builder: None,
+        }
     }
 
     pub fn default_with_address(multicaller_address: Address) -> Self {
@@ -34,7 +58,75 @@ impl MulticallerSwapEncoder {
 
         let swap_step_encoder = SwapStepEncoder::new(multicaller_address, swap_line_encoder);
 
-        Self { multicaller_address, swap_step_encoder }
+        Self::new(multicaller_address, swap_step_encoder)
+    }
+
+    /// Revoke the allowances given to pools by the multicaller after swaps
+    pub fn with_revoke_approvals(self, revoke_approvals: bool) -> Self {
+        let mut swap_step_encoder = self.swap_step_encoder;
+        swap_step_encoder.swap_line_encoder = swap_step_encoder.swap_line_encoder.with_revoke_approvals(revoke_approvals);
+        Self { swap_step_encoder, calls_templates: Arc::new(CallsTemplateCache::new(CALLS_TEMPLATES_CAPACITY)), ..self }
+    }
+
+    /// Pick hop amount calls by measured gas and order independent swap lines for warm address access
+    pub fn with_gas_golf(self, gas_table: OpcodeGasTable) -> Self {
+        let opcodes_encoder = self.opcodes_encoder.clone().with_gas_table(Arc::new(gas_table));
+        let mut encoder = self.with_opcodes_encoder(opcodes_encoder);
+        encoder.swap_step_encoder = encoder.swap_step_encoder.with_warm_access_order(true);
+        encoder
+    }
+
+    /// Tips are encoded only for chains where bundles are sent to builders
+    pub fn with_execution_profile(self, execution_profile: ExecutionProfile) -> Self {
+        Self { execution_profile, ..self }
+    }
+
+    /// Flash loan lender, deadline router and pool class encoders of the chain, the mainnet setup by default.
+    /// Swaps through pools of disabled classes or needing a flash loan on chains without a lender fail as unsupported
+    pub fn with_chain_preset(self, chain_preset: ChainPreset) -> Self {
+        let opcodes_encoder = self.opcodes_encoder.clone().with_disabled_pool_classes(chain_preset.disabled_pool_classes.clone());
+        let mut encoder = self.with_opcodes_encoder(opcodes_encoder);
+        encoder.swap_step_encoder = encoder.swap_step_encoder.with_flash_loan(chain_preset.flash_loan);
+        Self {
+            deadline_router: chain_preset.deadline_router,
+            chain_preset,
+            calls_templates: Arc::new(CallsTemplateCache::new(CALLS_TEMPLATES_CAPACITY)),
+            ..encoder
+        }
+    }
+
+    /// Withdraw the wrapped native output of swaps paid out to a recipient and send it as native ETH. Recipients must
+    /// accept ETH, e.g. EOAs
+    pub fn with_unwrap_native_payout(self, unwrap_native_payout: bool) -> Self {
+        Self { unwrap_native_payout, calls_templates: Arc::new(CallsTemplateCache::new(CALLS_TEMPLATES_CAPACITY)), ..self }
+    }
+
+    /// Uniswap SwapRouter02 checking the deadline of swaps encoded for the public mempool, the router of the chain preset by default
+    pub fn with_deadline_router(self, deadline_router: Address) -> Self {
+        Self {
+            deadline_router: Some(deadline_router),
+            calls_templates: Arc::new(CallsTemplateCache::new(CALLS_TEMPLATES_CAPACITY)),
+            ..self
+        }
+    }
+
+    /// Pay the tips to the recipient of the builder updated at runtime, to the block coinbase if not set
+    pub fn with_tip_recipients(self, tip_recipients: SharedTipRecipients, tip_builder: Option<String>) -> Self {
+        Self { tip_recipients: Some(tip_recipients), tip_builder, ..self }
+    }
+
+    /// Current tip recipient of the builder
+    pub(crate) fn tip_recipient(&self) -> TipRecipient {
+        let Some(tip_recipients) = &self.tip_recipients else {
+            return TipRecipient::Coinbase;
+        };
+        tip_recipients.read().map(|tip_recipients| tip_recipients.recipient(self.tip_builder.as_deref())).unwrap_or_default()
+    }
+
+    fn with_opcodes_encoder(self, opcodes_encoder: ProtocolSwapOpcodesEncoderV2) -> Self {
+        let mut swap_step_encoder = self.swap_step_encoder;
+        swap_step_encoder.swap_line_encoder = swap_step_encoder.swap_line_encoder.with_opcodes_encoder(Arc::new(opcodes_encoder.clone()));
+        Self { swap_step_encoder, opcodes_encoder, ..self }
     }
 
     pub fn get_contract_address(&self) -> Address {
@@ -53,13 +145,19 @@ impl MulticallerEncoder for MulticallerSwapEncoder {
 
     fn make_calls(&self, swap: &Swap) -> Result<MulticallerCalls> {
         match swap {
+            Swap::BackrunSwapLine(swap_line) if swap_line.funding == Some(FundingMode::Balance) => {
+                self.swap_step_encoder.swap_line_encoder.encode_swap_line_in_amount(swap_line, None)
+            }
             Swap::BackrunSwapLine(swap_line) => {
-                let (swap_step_0, swap_step_1) = swap_line.to_swap_steps(self.multicaller_address).ok_or_eyre("SWAP_TYPE_NOT_COVERED")?;
+                let (swap_step_0, swap_step_1) =
+                    swap_line.to_swap_steps(self.multicaller_address).ok_or(EncoderError::UnsupportedSwapType)?;
                 self.swap_step_encoder.encode_swap_steps(&swap_step_0, &swap_step_1)
             }
             Swap::BackrunSwapSteps((swap_step_0, swap_step_1)) => self.swap_step_encoder.encode_swap_steps(swap_step_0, swap_step_1),
             Swap::Multiple(swap_vec) => {
-                if swap_vec.len() == 1 {
+                if let Some(swap_lines) = swap.independent_swap_lines() {
+                    self.swap_step_encoder.encode_multiple_swap_lines(&swap_lines)
+                } else if swap_vec.len() == 1 {
                     self.make_calls(&swap_vec[0])
                 } else {
                     let mut multicaller_calls = MulticallerCalls::new();
@@ -71,7 +169,7 @@ impl MulticallerEncoder for MulticallerSwapEncoder {
             }
             _ => {
                 error!("Swap type not supported");
-                Err(eyre!("SWAP_TYPE_NOT_SUPPORTED"))
+                Err(EncoderError::UnsupportedSwapType.into())
             }
         }
     }
@@ -1,7 +1,8 @@
+use crate::EncoderError;
 use alloy_primitives::{Address, U256};
 use eyre::Result;
 use loom_defi_abi::AbiEncoderHelper;
-use loom_types_blockchain::{MulticallerCall, MulticallerCalls};
+use loom_types_blockchain::{MulticallerCall, MulticallerCalls, MulticallerCallsBuilder};
 use loom_types_entities::SwapAmountType;
 
 pub struct OpcodesHelpers {}
@@ -24,34 +25,7 @@ impl OpcodesHelpers {
         len: usize,
         balance_of_token: Option<Address>,
     ) -> Result<MulticallerCalls> {
-        let mut calls = MulticallerCalls::new();
-        let mut call = call;
-
-        match amount {
-            SwapAmountType::Set(_value) => {}
-            SwapAmountType::Balance(balance_of_owner) => {
-                let mut balance_opcode = MulticallerCall::new_static_call(
-                    balance_of_token.unwrap(),
-                    &AbiEncoderHelper::encode_erc20_balance_of(balance_of_owner),
-                );
-                balance_opcode.set_return_stack(true, 0, 0x0, 0x20);
-                calls.add(balance_opcode);
-
-                call.set_call_stack(true, 0, offset, len);
-            }
-            SwapAmountType::RelativeStack(stack_offset) => {
-                call.set_call_stack(true, stack_offset, offset, len);
-            }
-            SwapAmountType::NotSet => {
-                call.set_call_stack(true, 0, offset, len);
-            }
-            SwapAmountType::Stack0 => {
-                call.set_call_stack(false, 0, offset, len);
-            }
-        }
-        calls.add(call);
-
-        Ok(calls)
+        Self::build_multiple_stack(amount, vec![(call, offset, len)], balance_of_token)
     }
 
     pub fn build_multiple_stack(
@@ -59,35 +33,26 @@ impl OpcodesHelpers {
         calls: Vec<(MulticallerCall, u32, usize)>,
         balance_of_token: Option<Address>,
     ) -> Result<MulticallerCalls> {
-        let mut multicaller_calls = MulticallerCalls::new();
+        let mut builder = MulticallerCallsBuilder::new().with_inherited_slots(amount.inherited_stack_slots());
 
         if let SwapAmountType::Balance(balance_of_owner) = amount {
-            let mut balance_opcode =
-                MulticallerCall::new_static_call(balance_of_token.unwrap(), &AbiEncoderHelper::encode_erc20_balance_of(balance_of_owner));
-            balance_opcode.set_return_stack(true, 0, 0x0, 0x20);
-            multicaller_calls.add(balance_opcode);
+            let balance_of_token = balance_of_token.ok_or(EncoderError::BalanceOfTokenNotSet)?;
+            builder
+                .call(MulticallerCall::new_static_call(balance_of_token, &AbiEncoderHelper::encode_erc20_balance_of(balance_of_owner)))
+                .push_result(0x0)
+                .add();
         }
 
+        let amount_slot = amount.stack_slot();
         for (call, offset, len) in calls {
-            let mut call = call;
-            match amount {
-                SwapAmountType::Set(_value) => {}
-                SwapAmountType::Balance(_balance_of_owner) => {
-                    call.set_call_stack(true, 0, offset, len);
-                }
-                SwapAmountType::RelativeStack(stack_offset) => {
-                    call.set_call_stack(true, stack_offset, offset, len);
-                }
-                SwapAmountType::NotSet => {
-                    call.set_call_stack(true, 0, offset, len);
-                }
-                SwapAmountType::Stack0 => {
-                    call.set_call_stack(false, 0, offset, len);
+            match amount_slot {
+                Some(slot) => builder.call(call).input_from(slot, offset, len).add(),
+                None => {
+                    builder.add(call);
                 }
             }
-            multicaller_calls.add(call);
         }
 
-        Ok(multicaller_calls)
+        Ok(builder.build()?)
     }
 }
@@ -1,10 +1,10 @@
 use crate::pool_abi_encoder::pools::{
-    CurveProtocolAbiEncoder, MaverickProtocolAbiEncoder, PancakeV3ProtocolAbiEncoder, UniswapV2ProtocolAbiEncoder,
-    UniswapV3ProtocolAbiEncoder,
+    CurveProtocolAbiEncoder, MaverickProtocolAbiEncoder, PancakeV3ProtocolAbiEncoder, PendleProtocolAbiEncoder,
+    UniswapV2ProtocolAbiEncoder, UniswapV3ProtocolAbiEncoder, WooFiProtocolAbiEncoder,
 };
 use crate::pool_abi_encoder::ProtocolAbiSwapEncoderTrait;
+use crate::EncoderError;
 use alloy_primitives::{Address, Bytes, U256};
-use eyre::OptionExt;
 use loom_types_entities::{Pool, PoolClass};
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -22,6 +22,8 @@ impl Default for ProtocolABIEncoderV2 {
             (PoolClass::Maverick, Arc::new(MaverickProtocolAbiEncoder) as Arc<dyn ProtocolAbiSwapEncoderTrait>),
             (PoolClass::PancakeV3, Arc::new(PancakeV3ProtocolAbiEncoder) as Arc<dyn ProtocolAbiSwapEncoderTrait>),
             (PoolClass::Curve, Arc::new(CurveProtocolAbiEncoder) as Arc<dyn ProtocolAbiSwapEncoderTrait>),
+            (PoolClass::PendleV2, Arc::new(PendleProtocolAbiEncoder) as Arc<dyn ProtocolAbiSwapEncoderTrait>),
+            (PoolClass::WooFiV2, Arc::new(WooFiProtocolAbiEncoder) as Arc<dyn ProtocolAbiSwapEncoderTrait>),
         ]
         .into_iter()
         .collect();
@@ -42,14 +44,14 @@ impl ProtocolAbiSwapEncoderTrait for ProtocolABIEncoderV2 {
         recipient: Address,
         payload: Bytes,
     ) -> eyre::Result<Bytes> {
-        self.pool_classes.get(&pool.get_class()).ok_or_eyre("CLASS_NOT_SUPPORTED")?.encode_swap_in_amount_provided(
-            pool,
-            token_from_address,
-            token_to_address,
-            amount,
-            recipient,
-            payload,
-        )
+        self.pool_classes
+            .get(&pool.get_class())
+            .ok_or_else(|| EncoderError::UnsupportedPoolClass {
+                pool: pool.get_pool_id(),
+                class: pool.get_class(),
+                encoder: "ProtocolABIEncoderV2",
+            })?
+            .encode_swap_in_amount_provided(pool, token_from_address, token_to_address, amount, recipient, payload)
     }
 
     fn encode_swap_out_amount_provided(
@@ -61,14 +63,14 @@ impl ProtocolAbiSwapEncoderTrait for ProtocolABIEncoderV2 {
         recipient: Address,
         payload: Bytes,
     ) -> eyre::Result<Bytes> {
-        self.pool_classes.get(&pool.get_class()).ok_or_eyre("CLASS_NOT_SUPPORTED")?.encode_swap_out_amount_provided(
-            pool,
-            token_from_address,
-            token_to_address,
-            amount,
-            recipient,
-            payload,
-        )
+        self.pool_classes
+            .get(&pool.get_class())
+            .ok_or_else(|| EncoderError::UnsupportedPoolClass {
+                pool: pool.get_pool_id(),
+                class: pool.get_class(),
+                encoder: "ProtocolABIEncoderV2",
+            })?
+            .encode_swap_out_amount_provided(pool, token_from_address, token_to_address, amount, recipient, payload)
     }
 
     fn swap_in_amount_offset(&self, pool: &dyn Pool, token_from_address: Address, token_to_address: Address) -> Option<u32> {
@@ -1,6 +1,6 @@
 use crate::pool_abi_encoder::ProtocolAbiSwapEncoderTrait;
+use crate::EncoderError;
 use alloy_primitives::{Address, Bytes, U256};
-use eyre::OptionExt;
 use loom_types_blockchain::LoomDataTypesEthereum;
 use loom_types_entities::Pool;
 
@@ -16,7 +16,7 @@ impl ProtocolAbiSwapEncoderTrait for CurveProtocolAbiEncoder {
         recipient: Address,
         payload: Bytes,
     ) -> eyre::Result<Bytes> {
-        pool.get_abi_encoder().ok_or_eyre("NO_POOL_ENCODER")?.encode_swap_in_amount_provided(
+        pool.get_abi_encoder().ok_or_else(|| EncoderError::NoPoolEncoder { pool: pool.get_pool_id() })?.encode_swap_in_amount_provided(
             token_from_address,
             token_to_address,
             amount,
@@ -34,7 +34,7 @@ impl ProtocolAbiSwapEncoderTrait for CurveProtocolAbiEncoder {
         recipient: Address,
         payload: Bytes,
     ) -> eyre::Result<Bytes> {
-        pool.get_abi_encoder().ok_or_eyre("NO_POOL_ENCODER")?.encode_swap_out_amount_provided(
+        pool.get_abi_encoder().ok_or_else(|| EncoderError::NoPoolEncoder { pool: pool.get_pool_id() })?.encode_swap_out_amount_provided(
             token_from_address,
             token_to_address,
             amount,
@@ -43,8 +43,8 @@ impl ProtocolAbiSwapEncoderTrait for CurveProtocolAbiEncoder {
         )
     }
 
-    fn swap_in_amount_offset(&self, _pool: &dyn Pool, _token_from_address: Address, _token_to_address: Address) -> Option<u32> {
-        Some(0x44)
+    fn swap_in_amount_offset(&self, pool: &dyn Pool, token_from_address: Address, token_to_address: Address) -> Option<u32> {
+        pool.get_abi_encoder()?.swap_in_amount_offset(token_from_address, token_to_address)
     }
 
     fn swap_out_amount_offset(&self, _pool: &dyn Pool, _token_from_address: Address, _token_to_address: Address) -> Option<u32> {
@@ -1,10 +1,14 @@
 pub use curve::CurveProtocolAbiEncoder;
 pub use maverick::MaverickProtocolAbiEncoder;
 pub use pancake3::PancakeV3ProtocolAbiEncoder;
+pub use pendle::PendleProtocolAbiEncoder;
 pub use uniswapv2::UniswapV2ProtocolAbiEncoder;
 pub use uniswapv3::UniswapV3ProtocolAbiEncoder;
+pub use woofi::WooFiProtocolAbiEncoder;
 mod curve;
 mod maverick;
 mod pancake3;
+mod pendle;
 mod uniswapv2;
 mod uniswapv3;
+mod woofi;
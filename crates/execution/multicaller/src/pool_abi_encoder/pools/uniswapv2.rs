@@ -1,7 +1,7 @@
 use crate::pool_abi_encoder::ProtocolAbiSwapEncoderTrait;
+use crate::EncoderError;
 use alloy_primitives::{Address, Bytes, U256};
 use alloy_sol_types::SolInterface;
-use eyre::eyre;
 use loom_defi_abi::uniswap2::IUniswapV2Pair;
 use loom_types_blockchain::LoomDataTypesEthereum;
 use loom_types_entities::Pool;
@@ -18,14 +18,14 @@ impl UniswapV2ProtocolAbiEncoder {
 impl ProtocolAbiSwapEncoderTrait for UniswapV2ProtocolAbiEncoder {
     fn encode_swap_in_amount_provided(
         &self,
-        _pool: &dyn Pool<LoomDataTypesEthereum>,
+        pool: &dyn Pool<LoomDataTypesEthereum>,
         _token_from_address: Address,
         _token_to_address: Address,
         _amount: U256,
         _recipient: Address,
         _payload: Bytes,
     ) -> eyre::Result<Bytes> {
-        Err(eyre!("NOT_SUPPORTED"))
+        Err(EncoderError::NotImplemented { pool: pool.get_pool_id(), operation: "swap_in_amount_provided" }.into())
     }
 
     fn encode_swap_out_amount_provided(
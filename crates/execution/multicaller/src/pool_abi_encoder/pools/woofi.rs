@@ -0,0 +1,63 @@
+use crate::pool_abi_encoder::ProtocolAbiSwapEncoderTrait;
+use crate::EncoderError;
+use alloy_primitives::{Address, Bytes, U256};
+use loom_types_entities::Pool;
+
+/// WooPPV2 swap calls are encoded by the pool
+pub struct WooFiProtocolAbiEncoder;
+
+impl ProtocolAbiSwapEncoderTrait for WooFiProtocolAbiEncoder {
+    fn encode_swap_in_amount_provided(
+        &self,
+        pool: &dyn Pool,
+        token_from_address: Address,
+        token_to_address: Address,
+        amount: U256,
+        recipient: Address,
+        payload: Bytes,
+    ) -> eyre::Result<Bytes> {
+        pool.get_abi_encoder().ok_or_else(|| EncoderError::NoPoolEncoder { pool: pool.get_pool_id() })?.encode_swap_in_amount_provided(
+            token_from_address,
+            token_to_address,
+            amount,
+            recipient,
+            payload,
+        )
+    }
+
+    fn encode_swap_out_amount_provided(
+        &self,
+        pool: &dyn Pool,
+        _token_from_address: Address,
+        _token_to_address: Address,
+        _amount: U256,
+        _recipient: Address,
+        _payload: Bytes,
+    ) -> eyre::Result<Bytes> {
+        Err(EncoderError::NotImplemented { pool: pool.get_pool_id(), operation: "swap_out_amount_provided" }.into())
+    }
+
+    fn swap_in_amount_offset(&self, pool: &dyn Pool, token_from_address: Address, token_to_address: Address) -> Option<u32> {
+        pool.get_abi_encoder()?.swap_in_amount_offset(token_from_address, token_to_address)
+    }
+
+    fn swap_out_amount_offset(&self, _pool: &dyn Pool, _token_from_address: Address, _token_to_address: Address) -> Option<u32> {
+        None
+    }
+
+    fn swap_out_amount_return_offset(&self, pool: &dyn Pool, token_from_address: Address, token_to_address: Address) -> Option<u32> {
+        pool.get_abi_encoder()?.swap_out_amount_return_offset(token_from_address, token_to_address)
+    }
+
+    fn swap_in_amount_return_offset(&self, _pool: &dyn Pool, _token_from_address: Address, _token_to_address: Address) -> Option<u32> {
+        None
+    }
+
+    fn swap_out_amount_return_script(&self, _pool: &dyn Pool, _token_from_address: Address, _token_to_address: Address) -> Option<Bytes> {
+        None
+    }
+
+    fn swap_in_amount_return_script(&self, _pool: &dyn Pool, _token_from_address: Address, _token_to_address: Address) -> Option<Bytes> {
+        None
+    }
+}
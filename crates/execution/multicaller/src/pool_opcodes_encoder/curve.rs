@@ -1,5 +1,5 @@
 use alloy_primitives::{Address, U256};
-use eyre::{eyre, Result};
+use eyre::Result;
 use lazy_static::lazy_static;
 use std::collections::HashMap;
 use tracing::trace;
@@ -8,12 +8,14 @@ use crate::opcodes_helpers::OpcodesHelpers;
 use crate::pool_abi_encoder::ProtocolAbiSwapEncoderTrait;
 use crate::pool_opcodes_encoder::swap_opcodes_encoders::MulticallerOpcodesPayload;
 use crate::pool_opcodes_encoder::SwapOpcodesEncoderTrait;
+use crate::EncoderError;
 use loom_defi_abi::AbiEncoderHelper;
 use loom_defi_address_book::TokenAddressEth;
-use loom_types_blockchain::{MulticallerCall, MulticallerCalls};
+use loom_types_blockchain::{MulticallerCall, MulticallerCalls, MulticallerCallsBuilder, StackSlot};
 use loom_types_entities::Pool;
 use loom_types_entities::{PreswapRequirement, SwapAmountType};
 
+#[derive(Clone, Default)]
 pub struct CurveSwapOpcodesEncoder;
 
 lazy_static! {
@@ -27,8 +29,14 @@ lazy_static! {
 }
 
 impl CurveSwapOpcodesEncoder {
-    fn need_balance(address: Address) -> bool {
-        *NEED_BALANCE_MAP.get(&address).unwrap_or(&false)
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Swaps of pools without a return value, and mints of the LP token that older pools do not return, are followed by
+    /// the balance of the out token. The multicaller does not hold LP tokens between swaps
+    fn need_balance(pool: &dyn Pool, token_to_address: Address) -> bool {
+        *NEED_BALANCE_MAP.get(&pool.get_address()).unwrap_or(&false) || pool.get_lp_token() == Some(token_to_address)
     }
 }
 
@@ -61,6 +69,11 @@ impl SwapOpcodesEncoderTrait for CurveSwapOpcodesEncoder {
             token_to_address
         );
 
+        // add_liquidity and remove_liquidity_one_coin take the amount at another offset than exchange
+        let swap_in_amount_offset = abi_encoder.swap_in_amount_offset(cur_pool, token_from_address, token_to_address).ok_or_else(|| {
+            EncoderError::NoAmountOffset { pool: cur_pool.get_pool_id(), token_from: token_from_address, token_to: token_to_address }
+        })?;
+
         let mut opcodes: Vec<(MulticallerCall, u32, usize)> = Vec::new();
 
         if in_native {
@@ -78,7 +91,7 @@ impl SwapOpcodesEncoderTrait for CurveSwapOpcodesEncoder {
                 amount_in.unwrap_or_default(),
             );
 
-            if !Self::need_balance(cur_pool.get_address()) {
+            if !Self::need_balance(cur_pool, token_to_address) {
                 swap_opcode.set_return_stack(true, 0, 0x0, 0x20);
             }
 
@@ -88,7 +101,7 @@ impl SwapOpcodesEncoderTrait for CurveSwapOpcodesEncoder {
                 0x4,
                 0x20,
             ));
-            opcodes.push((swap_opcode, abi_encoder.swap_in_amount_offset(cur_pool, token_from_address, token_to_address).unwrap(), 0x20));
+            opcodes.push((swap_opcode, swap_in_amount_offset, 0x20));
         } else {
             //Approve
             opcodes.push((
@@ -113,38 +126,39 @@ impl SwapOpcodesEncoderTrait for CurveSwapOpcodesEncoder {
                 )?,
             );
 
-            if !Self::need_balance(cur_pool.get_address()) {
+            if !Self::need_balance(cur_pool, token_to_address) {
                 swap_opcode.set_return_stack(true, 0, 0x0, 0x20);
             }
-            opcodes.push((swap_opcode, abi_encoder.swap_in_amount_offset(cur_pool, token_from_address, token_to_address).unwrap(), 0x20));
+            opcodes.push((swap_opcode, swap_in_amount_offset, 0x20));
         }
 
-        swap_opcodes.merge(OpcodesHelpers::build_multiple_stack(amount_in, opcodes, Some(token_from_address))?);
+        let mut builder =
+            MulticallerCallsBuilder::from_calls(OpcodesHelpers::build_multiple_stack(amount_in, opcodes, Some(token_from_address))?)
+                .with_inherited_slots(amount_in.inherited_stack_slots());
 
         if out_native {
-            let mut weth_deposit_opcode =
+            let weth_deposit_opcode =
                 MulticallerCall::new_call_with_value(token_to_address, &AbiEncoderHelper::encode_weth_deposit(), U256::ZERO);
-            weth_deposit_opcode.set_call_stack(true, 0, 0x0, 0x0);
-            swap_opcodes.add(weth_deposit_opcode);
+            builder.call(weth_deposit_opcode).value_from(StackSlot::Last).add();
         }
 
         if let Some(next_pool) = next_pool {
-            if Self::need_balance(cur_pool.get_address()) {
-                let mut balance_opcode =
+            if Self::need_balance(cur_pool, token_to_address) {
+                let balance_opcode =
                     MulticallerCall::new_static_call(token_to_address, &AbiEncoderHelper::encode_erc20_balance_of(multicaller));
-                balance_opcode.set_return_stack(true, 0, 0x0, 0x20);
-                swap_opcodes.add(balance_opcode);
+                builder.call(balance_opcode).push_result(0x0).add();
             }
 
             if let PreswapRequirement::Transfer(addr) = next_pool.preswap_requirement() {
                 trace!("transfer token={:?}, to={:?}, amount=stack_rel_0", token_to_address, addr);
 
-                let mut transfer_opcode =
+                let transfer_opcode =
                     MulticallerCall::new_call(token_to_address, &AbiEncoderHelper::encode_erc20_transfer(addr, U256::ZERO));
-                transfer_opcode.set_call_stack(true, 0, 0x24, 0x20);
-                swap_opcodes.add(transfer_opcode);
+                builder.call(transfer_opcode).amount_from(StackSlot::Last, 0x24).add();
             }
         }
+
+        swap_opcodes.merge(builder.build()?);
         Ok(())
     }
 
@@ -155,11 +169,11 @@ impl SwapOpcodesEncoderTrait for CurveSwapOpcodesEncoder {
         _token_from_address: Address,
         _token_to_address: Address,
         _amount_out: SwapAmountType,
-        _cur_pool: &dyn Pool,
+        cur_pool: &dyn Pool,
         _next_pool: Option<&dyn Pool>,
         _payload: MulticallerOpcodesPayload,
         _multicaller_address: Address,
     ) -> Result<()> {
-        Err(eyre!("NOT_IMPLEMENTED"))
+        Err(EncoderError::NotImplemented { pool: cur_pool.get_pool_id(), operation: "swap_out_amount_provided" }.into())
     }
 }
@@ -1,20 +1,25 @@
 use crate::pool_abi_encoder::ProtocolAbiSwapEncoderTrait;
 pub use crate::pool_opcodes_encoder::swap_opcodes_encoders::MulticallerOpcodesPayload;
+use crate::EncoderError;
 use alloy_primitives::Address;
 pub use curve::CurveSwapOpcodesEncoder;
-use eyre::{eyre, Result};
+use eyre::Result;
 use loom_types_blockchain::MulticallerCalls;
 use loom_types_entities::{Pool, SwapAmountType};
+pub use pendle::PendleSwapOpcodesEncoder;
 pub use steth::StEthSwapEncoder;
 pub use swap_opcodes_encoders::ProtocolSwapOpcodesEncoderV2;
 pub use uniswap2::UniswapV2SwapOpcodesEncoder;
 pub use uniswap3::UniswapV3SwapOpcodesEncoder;
+pub use woofi::WooFiSwapOpcodesEncoder;
 pub use wsteth::WstEthSwapEncoder;
 
 mod curve;
+mod pendle;
 mod steth;
 mod uniswap2;
 mod uniswap3;
+mod woofi;
 mod wsteth;
 
 mod swap_opcodes_encoders;
@@ -56,12 +61,12 @@ pub trait SwapOpcodesEncoderTrait: Send + Sync + 'static {
         _token_from_address: Address,
         _token_to_address: Address,
         _amount_in: SwapAmountType,
-        _flash_pool: &dyn Pool,
+        flash_pool: &dyn Pool,
         _prev_pool: Option<&dyn Pool>,
         _payload: MulticallerOpcodesPayload,
         _multicaller_address: Address,
     ) -> Result<()> {
-        Err(eyre!("NOT_IMPLEMENTED"))
+        Err(EncoderError::NotImplemented { pool: flash_pool.get_pool_id(), operation: "flash_swap_in_amount_provided" }.into())
     }
 
     #[allow(clippy::too_many_arguments)]
@@ -72,11 +77,31 @@ pub trait SwapOpcodesEncoderTrait: Send + Sync + 'static {
         _token_from_address: Address,
         _token_to_address: Address,
         _amount_out: SwapAmountType,
-        _flash_pool: &dyn Pool,
+        flash_pool: &dyn Pool,
         _next_pool: Option<&dyn Pool>,
         _payload: MulticallerOpcodesPayload,
         _multicaller_address: Address,
     ) -> Result<()> {
-        Err(eyre!("NOT_IMPLEMENTED"))
+        Err(EncoderError::NotImplemented { pool: flash_pool.get_pool_id(), operation: "flash_swap_out_amount_provided" }.into())
+    }
+
+    /// Exact out flash swap of a multi-hop line with the callbacks chained as the `exactOutput` of the Uniswap V3 router.
+    /// The output is sent straight to `chained_next_pool`, which owes it to the next pool and calls this swap in its
+    /// callback. With `input_chained` the swap in the callback of this pool pays the input, no transfer is encoded.
+    #[allow(clippy::too_many_arguments)]
+    fn encode_chained_flash_swap_out_amount_provided(
+        &self,
+        _swap_opcodes: &mut MulticallerCalls,
+        _abi_encoder: &dyn ProtocolAbiSwapEncoderTrait,
+        _token_from_address: Address,
+        _token_to_address: Address,
+        _amount_out: SwapAmountType,
+        flash_pool: &dyn Pool,
+        _chained_next_pool: Option<&dyn Pool>,
+        _input_chained: bool,
+        _payload: MulticallerOpcodesPayload,
+        _multicaller_address: Address,
+    ) -> Result<()> {
+        Err(EncoderError::NotImplemented { pool: flash_pool.get_pool_id(), operation: "chained_flash_swap_out_amount_provided" }.into())
     }
 }
@@ -0,0 +1,114 @@
+use alloy_primitives::{Address, U256};
+use eyre::Result;
+use tracing::trace;
+
+use crate::opcodes_helpers::OpcodesHelpers;
+use crate::pool_abi_encoder::ProtocolAbiSwapEncoderTrait;
+use crate::pool_opcodes_encoder::swap_opcodes_encoders::MulticallerOpcodesPayload;
+use crate::pool_opcodes_encoder::SwapOpcodesEncoderTrait;
+use crate::EncoderError;
+use loom_defi_abi::AbiEncoderHelper;
+use loom_defi_address_book::PeripheryAddress;
+use loom_types_blockchain::{MulticallerCall, MulticallerCalls, MulticallerCallsBuilder, StackSlot};
+use loom_types_entities::Pool;
+use loom_types_entities::{PreswapRequirement, SwapAmountType};
+
+/// Swaps Pendle PT through the Pendle router, the router is approved instead of the market
+#[derive(Clone, Default)]
+pub struct PendleSwapOpcodesEncoder;
+
+impl PendleSwapOpcodesEncoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl SwapOpcodesEncoderTrait for PendleSwapOpcodesEncoder {
+    #[allow(clippy::too_many_arguments)]
+    fn encode_swap_in_amount_provided(
+        &self,
+        swap_opcodes: &mut MulticallerCalls,
+        abi_encoder: &dyn ProtocolAbiSwapEncoderTrait,
+        token_from_address: Address,
+        token_to_address: Address,
+        amount_in: SwapAmountType,
+        cur_pool: &dyn Pool,
+        next_pool: Option<&dyn Pool>,
+        payload: MulticallerOpcodesPayload,
+        multicaller: Address,
+    ) -> Result<()> {
+        let router = PeripheryAddress::PENDLE_ROUTER_V4;
+
+        trace!(
+            "pendle swap for market={:?} amount={:?} from {} to {}",
+            cur_pool.get_address(),
+            amount_in,
+            token_from_address,
+            token_to_address
+        );
+
+        let swap_in_amount_offset = abi_encoder.swap_in_amount_offset(cur_pool, token_from_address, token_to_address).ok_or_else(|| {
+            EncoderError::UnsupportedSwapDirection {
+                pool: cur_pool.get_pool_id(),
+                token_from: token_from_address,
+                token_to: token_to_address,
+            }
+        })?;
+
+        let mut opcodes: Vec<(MulticallerCall, u32, usize)> = Vec::new();
+
+        // Approve
+        opcodes.push((
+            MulticallerCall::new_call(token_from_address, &AbiEncoderHelper::encode_erc20_approve(router, amount_in.unwrap_or_default())),
+            0x24,
+            0x20,
+        ));
+
+        // SWAP, netPtOut or netTokenOut is the first returned word
+        let mut swap_opcode = MulticallerCall::new_call(
+            router,
+            &abi_encoder.encode_swap_in_amount_provided(
+                cur_pool,
+                token_from_address,
+                token_to_address,
+                amount_in.unwrap_or_default(),
+                multicaller,
+                payload.encode()?,
+            )?,
+        );
+        swap_opcode.set_return_stack(true, 0, 0x0, 0x20);
+        opcodes.push((swap_opcode, swap_in_amount_offset, 0x20));
+
+        let mut builder =
+            MulticallerCallsBuilder::from_calls(OpcodesHelpers::build_multiple_stack(amount_in, opcodes, Some(token_from_address))?)
+                .with_inherited_slots(amount_in.inherited_stack_slots());
+
+        if let Some(next_pool) = next_pool {
+            if let PreswapRequirement::Transfer(addr) = next_pool.preswap_requirement() {
+                trace!("transfer token={:?}, to={:?}, amount=stack_rel_0", token_to_address, addr);
+
+                let transfer_opcode =
+                    MulticallerCall::new_call(token_to_address, &AbiEncoderHelper::encode_erc20_transfer(addr, U256::ZERO));
+                builder.call(transfer_opcode).amount_from(StackSlot::Last, 0x24).add();
+            }
+        }
+
+        swap_opcodes.merge(builder.build()?);
+        Ok(())
+    }
+
+    fn encode_swap_out_amount_provided(
+        &self,
+        _swap_opcodes: &mut MulticallerCalls,
+        _abi_encoder: &dyn ProtocolAbiSwapEncoderTrait,
+        _token_from_address: Address,
+        _token_to_address: Address,
+        _amount_out: SwapAmountType,
+        cur_pool: &dyn Pool,
+        _next_pool: Option<&dyn Pool>,
+        _payload: MulticallerOpcodesPayload,
+        _multicaller_address: Address,
+    ) -> Result<()> {
+        Err(EncoderError::NotImplemented { pool: cur_pool.get_pool_id(), operation: "swap_out_amount_provided" }.into())
+    }
+}
@@ -2,11 +2,12 @@ use crate::opcodes_helpers::OpcodesHelpers;
 use crate::pool_abi_encoder::ProtocolAbiSwapEncoderTrait;
 use crate::pool_opcodes_encoder::swap_opcodes_encoders::MulticallerOpcodesPayload;
 use crate::pool_opcodes_encoder::SwapOpcodesEncoderTrait;
+use crate::EncoderError;
 use alloy_primitives::{Address, Bytes};
-use eyre::{eyre, Result};
+use eyre::Result;
 use loom_defi_abi::AbiEncoderHelper;
 use loom_defi_address_book::TokenAddressEth;
-use loom_types_blockchain::{MulticallerCall, MulticallerCalls};
+use loom_types_blockchain::{MulticallerCall, MulticallerCalls, MulticallerCallsBuilder};
 use loom_types_entities::{Pool, SwapAmountType};
 
 pub struct StEthSwapEncoder();
@@ -48,16 +49,23 @@ impl SwapOpcodesEncoderTrait for StEthSwapEncoder {
             swap_opcodes.merge(OpcodesHelpers::build_multiple_stack(amount_in, opcodes_vec, Some(token_from_address))?);
 
             if next_pool.is_some() {
-                let mut steth_balance_opcode =
-                    MulticallerCall::new_static_call(token_to_address, &AbiEncoderHelper::encode_erc20_balance_of(multicaller));
-                steth_balance_opcode.set_return_stack(true, 0, 0, 0x20);
-                swap_opcodes.add(steth_balance_opcode);
+                let mut builder = MulticallerCallsBuilder::new();
+                builder
+                    .call(MulticallerCall::new_static_call(token_to_address, &AbiEncoderHelper::encode_erc20_balance_of(multicaller)))
+                    .push_result(0x0)
+                    .add();
+                swap_opcodes.merge(builder.build()?);
             }
 
             return Ok(());
         }
 
-        Err(eyre!("CANNOT_ENCODE_STETH_SWAP"))
+        Err(EncoderError::UnsupportedSwapDirection {
+            pool: cur_pool.get_pool_id(),
+            token_from: token_from_address,
+            token_to: token_to_address,
+        }
+        .into())
     }
 
     fn encode_swap_out_amount_provided(
@@ -67,11 +75,11 @@ impl SwapOpcodesEncoderTrait for StEthSwapEncoder {
         _token_from_address: Address,
         _token_to_address: Address,
         _amount_out: SwapAmountType,
-        _cur_pool: &dyn Pool,
+        cur_pool: &dyn Pool,
         _next_pool: Option<&dyn Pool>,
         _payload: MulticallerOpcodesPayload,
         _multicaller_address: Address,
     ) -> Result<()> {
-        Err(eyre!("NOT_IMPLEMENTED"))
+        Err(EncoderError::NotImplemented { pool: cur_pool.get_pool_id(), operation: "swap_out_amount_provided" }.into())
     }
 }
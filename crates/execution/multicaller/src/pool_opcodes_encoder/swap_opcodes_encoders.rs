@@ -1,10 +1,11 @@
+use crate::gas_golf::OpcodeGasTable;
 use crate::pool_abi_encoder::ProtocolAbiSwapEncoderTrait;
 use crate::pool_opcodes_encoder::{
-    CurveSwapOpcodesEncoder, SwapOpcodesEncoderTrait, UniswapV2SwapOpcodesEncoder, UniswapV3SwapOpcodesEncoder,
+    CurveSwapOpcodesEncoder, PendleSwapOpcodesEncoder, SwapOpcodesEncoderTrait, UniswapV2SwapOpcodesEncoder, UniswapV3SwapOpcodesEncoder,
+    WooFiSwapOpcodesEncoder,
 };
-use crate::{OpcodesEncoder, OpcodesEncoderV2};
-use alloy_primitives::{Address, Bytes};
-use eyre::OptionExt;
+use crate::{EncoderError, OpcodesEncoder, OpcodesEncoderV2};
+use alloy_primitives::{Address, Bytes, U256};
 use eyre::Result;
 use loom_types_blockchain::MulticallerCalls;
 use loom_types_entities::{Pool, PoolClass, SwapAmountType};
@@ -18,6 +19,8 @@ pub enum MulticallerOpcodesPayload {
     Opcodes(MulticallerCalls),
     Bytes(Bytes),
     Address(Address),
+    /// Calculated out amount of the hop, the min out of pools checking it is derived from it
+    AmountOut(U256),
 }
 
 impl MulticallerOpcodesPayload {
@@ -25,7 +28,7 @@ impl MulticallerOpcodesPayload {
         match self {
             Self::Empty => true,
             Self::Bytes(b) => b.is_empty(),
-            Self::Address(_) => false,
+            Self::Address(_) | Self::AmountOut(_) => false,
             Self::Opcodes(o) => o.is_empty(),
         }
     }
@@ -38,6 +41,7 @@ impl MulticallerOpcodesPayload {
             Self::Opcodes(opcodes) => OpcodesEncoderV2::pack_do_calls_data(opcodes),
             Self::Bytes(bytes) => Ok(bytes.clone()),
             Self::Address(address) => Ok(Bytes::from(address.to_vec())),
+            Self::AmountOut(amount) => Ok(Bytes::from(amount.to_be_bytes::<32>().to_vec())),
         }
     }
 }
@@ -45,23 +49,58 @@ impl MulticallerOpcodesPayload {
 #[derive(Clone)]
 pub struct ProtocolSwapOpcodesEncoderV2 {
     pool_classes: HashMap<PoolClass, Arc<dyn SwapOpcodesEncoderTrait>>,
+    gas_table: Option<Arc<OpcodeGasTable>>,
+    disabled_pool_classes: Vec<PoolClass>,
 }
 
 impl Default for ProtocolSwapOpcodesEncoderV2 {
     fn default() -> Self {
+        Self::build(None, Vec::new())
+    }
+}
+
+impl ProtocolSwapOpcodesEncoderV2 {
+    fn build(gas_table: Option<Arc<OpcodeGasTable>>, disabled_pool_classes: Vec<PoolClass>) -> Self {
         let mut pool_classes: HashMap<PoolClass, Arc<dyn SwapOpcodesEncoderTrait>> = HashMap::new();
 
-        let uni2_opcodes_encoder = Arc::new(UniswapV2SwapOpcodesEncoder {});
+        let uni2_opcodes_encoder = match &gas_table {
+            Some(gas_table) => Arc::new(UniswapV2SwapOpcodesEncoder::new().with_gas_table(gas_table.clone())),
+            None => Arc::new(UniswapV2SwapOpcodesEncoder::new()),
+        };
         let uni3_opcodes_encoder = Arc::new(UniswapV3SwapOpcodesEncoder {});
-        let curve_opcodes_encoder = Arc::new(CurveSwapOpcodesEncoder {});
+        let curve_opcodes_encoder = Arc::new(CurveSwapOpcodesEncoder::new());
 
         pool_classes.insert(PoolClass::UniswapV2, uni2_opcodes_encoder.clone());
         pool_classes.insert(PoolClass::Maverick, uni3_opcodes_encoder.clone());
         pool_classes.insert(PoolClass::UniswapV3, uni3_opcodes_encoder.clone());
         pool_classes.insert(PoolClass::PancakeV3, uni3_opcodes_encoder.clone());
         pool_classes.insert(PoolClass::Curve, curve_opcodes_encoder.clone());
+        pool_classes.insert(PoolClass::PendleV2, Arc::new(PendleSwapOpcodesEncoder::new()));
+        pool_classes.insert(PoolClass::WooFiV2, Arc::new(WooFiSwapOpcodesEncoder));
+        // swaps through disabled classes fail as unsupported before any call is encoded
+        pool_classes.retain(|pool_class, _| !disabled_pool_classes.contains(pool_class));
+
+        Self { pool_classes, gas_table, disabled_pool_classes }
+    }
+
+    fn require_capability(pool: &dyn Pool, supported: bool, capability: &'static str) -> Result<()> {
+        if supported {
+            Ok(())
+        } else {
+            Err(EncoderError::MissingCapability { pool: pool.get_pool_id(), class: pool.get_class(), capability }.into())
+        }
+    }
+}
+
+impl ProtocolSwapOpcodesEncoderV2 {
+    /// Choose between multicaller helpers and pool view functions for hop amounts with the measured gas
+    pub fn with_gas_table(self, gas_table: Arc<OpcodeGasTable>) -> Self {
+        Self::build(Some(gas_table), self.disabled_pool_classes)
+    }
 
-        Self { pool_classes }
+    /// Drop the encoders of pool classes that cannot be encoded on the chain
+    pub fn with_disabled_pool_classes(self, disabled_pool_classes: Vec<PoolClass>) -> Self {
+        Self::build(self.gas_table, disabled_pool_classes)
     }
 }
 
@@ -78,7 +117,11 @@ impl SwapOpcodesEncoderTrait for ProtocolSwapOpcodesEncoderV2 {
         payload: MulticallerOpcodesPayload,
         multicaller_address: Address,
     ) -> eyre::Result<()> {
-        let opcodes_encoder = self.pool_classes.get(&cur_pool.get_class()).ok_or_eyre("OPCODES_ENCODER_NOT_FOUND")?;
+        let opcodes_encoder = self.pool_classes.get(&cur_pool.get_class()).ok_or_else(|| EncoderError::UnsupportedPoolClass {
+            pool: cur_pool.get_pool_id(),
+            class: cur_pool.get_class(),
+            encoder: "ProtocolSwapOpcodesEncoderV2",
+        })?;
         opcodes_encoder.encode_swap_in_amount_provided(
             swap_opcodes,
             abi_encoder,
@@ -104,7 +147,12 @@ impl SwapOpcodesEncoderTrait for ProtocolSwapOpcodesEncoderV2 {
         payload: MulticallerOpcodesPayload,
         multicaller_address: Address,
     ) -> eyre::Result<()> {
-        let opcodes_encoder = self.pool_classes.get(&cur_pool.get_class()).ok_or_eyre("OPCODES_ENCODER_NOT_FOUND")?;
+        Self::require_capability(cur_pool, cur_pool.get_class().capabilities().exact_out_support, "exact out swap")?;
+        let opcodes_encoder = self.pool_classes.get(&cur_pool.get_class()).ok_or_else(|| EncoderError::UnsupportedPoolClass {
+            pool: cur_pool.get_pool_id(),
+            class: cur_pool.get_class(),
+            encoder: "ProtocolSwapOpcodesEncoderV2",
+        })?;
         opcodes_encoder.encode_swap_out_amount_provided(
             swap_opcodes,
             abi_encoder,
@@ -130,7 +178,12 @@ impl SwapOpcodesEncoderTrait for ProtocolSwapOpcodesEncoderV2 {
         payload: MulticallerOpcodesPayload,
         multicaller_address: Address,
     ) -> Result<()> {
-        let opcodes_encoder = self.pool_classes.get(&flash_pool.get_class()).ok_or_eyre("OPCODES_ENCODER_NOT_FOUND")?;
+        Self::require_capability(flash_pool, flash_pool.get_class().capabilities().supports_flash_swap, "flash swap")?;
+        let opcodes_encoder = self.pool_classes.get(&flash_pool.get_class()).ok_or_else(|| EncoderError::UnsupportedPoolClass {
+            pool: flash_pool.get_pool_id(),
+            class: flash_pool.get_class(),
+            encoder: "ProtocolSwapOpcodesEncoderV2",
+        })?;
         opcodes_encoder.encode_flash_swap_in_amount_provided(
             swap_opcodes,
             abi_encoder,
@@ -156,7 +209,12 @@ impl SwapOpcodesEncoderTrait for ProtocolSwapOpcodesEncoderV2 {
         payload: MulticallerOpcodesPayload,
         multicaller_address: Address,
     ) -> Result<()> {
-        let opcodes_encoder = self.pool_classes.get(&flash_pool.get_class()).ok_or_eyre("OPCODES_ENCODER_NOT_FOUND")?;
+        Self::require_capability(flash_pool, flash_pool.get_class().capabilities().supports_flash_swap, "flash swap")?;
+        let opcodes_encoder = self.pool_classes.get(&flash_pool.get_class()).ok_or_else(|| EncoderError::UnsupportedPoolClass {
+            pool: flash_pool.get_pool_id(),
+            class: flash_pool.get_class(),
+            encoder: "ProtocolSwapOpcodesEncoderV2",
+        })?;
         opcodes_encoder.encode_flash_swap_out_amount_provided(
             swap_opcodes,
             abi_encoder,
@@ -169,4 +227,37 @@ impl SwapOpcodesEncoderTrait for ProtocolSwapOpcodesEncoderV2 {
             multicaller_address,
         )
     }
+
+    fn encode_chained_flash_swap_out_amount_provided(
+        &self,
+        swap_opcodes: &mut MulticallerCalls,
+        abi_encoder: &dyn ProtocolAbiSwapEncoderTrait,
+        token_from_address: Address,
+        token_to_address: Address,
+        amount_out: SwapAmountType,
+        flash_pool: &dyn Pool,
+        chained_next_pool: Option<&dyn Pool>,
+        input_chained: bool,
+        payload: MulticallerOpcodesPayload,
+        multicaller_address: Address,
+    ) -> Result<()> {
+        Self::require_capability(flash_pool, flash_pool.get_class().capabilities().supports_flash_swap, "flash swap")?;
+        let opcodes_encoder = self.pool_classes.get(&flash_pool.get_class()).ok_or_else(|| EncoderError::UnsupportedPoolClass {
+            pool: flash_pool.get_pool_id(),
+            class: flash_pool.get_class(),
+            encoder: "ProtocolSwapOpcodesEncoderV2",
+        })?;
+        opcodes_encoder.encode_chained_flash_swap_out_amount_provided(
+            swap_opcodes,
+            abi_encoder,
+            token_from_address,
+            token_to_address,
+            amount_out,
+            flash_pool,
+            chained_next_pool,
+            input_chained,
+            payload,
+            multicaller_address,
+        )
+    }
 }
@@ -1,15 +1,64 @@
+use crate::gas_golf::{AmountQuoteSource, OpcodeGasTable};
 use crate::opcodes_helpers::OpcodesHelpers;
 use crate::pool_abi_encoder::ProtocolAbiSwapEncoderTrait;
 use crate::pool_opcodes_encoder::swap_opcodes_encoders::MulticallerOpcodesPayload;
 use crate::pool_opcodes_encoder::SwapOpcodesEncoderTrait;
+use crate::EncoderError;
 use alloy_primitives::{Address, Bytes, U256};
-use eyre::eyre;
 use loom_defi_abi::AbiEncoderHelper;
-use loom_types_blockchain::{MulticallerCall, MulticallerCalls};
+use loom_types_blockchain::{MulticallerCall, MulticallerCalls, MulticallerCallsBuilder, StackSlot};
 use loom_types_entities::{Pool, PreswapRequirement, SwapAmountType};
+use std::sync::Arc;
 use tracing::{trace, warn};
 
-pub struct UniswapV2SwapOpcodesEncoder;
+#[derive(Clone, Default)]
+pub struct UniswapV2SwapOpcodesEncoder {
+    // picks the internal helper or the pool view function for out amounts
+    gas_table: Option<Arc<OpcodeGasTable>>,
+}
+
+impl UniswapV2SwapOpcodesEncoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Calculate out amounts with the cheapest call measured in the table instead of always using the internal helper
+    pub fn with_gas_table(self, gas_table: Arc<OpcodeGasTable>) -> Self {
+        Self { gas_table: Some(gas_table), ..self }
+    }
+
+    /// Call pushing the out amount to the stack and offset of the in amount in its call data
+    fn get_out_amount_call(
+        &self,
+        token_from_address: Address,
+        token_to_address: Address,
+        amount_in: SwapAmountType,
+        pool: &dyn Pool,
+    ) -> (MulticallerCall, u32) {
+        let source = self.gas_table.as_ref().map_or(AmountQuoteSource::InternalHelper, |table| table.select(pool.get_protocol()));
+        match source {
+            AmountQuoteSource::InternalHelper => (
+                MulticallerCall::new_internal_call(&AbiEncoderHelper::encode_multicaller_uni2_get_out_amount(
+                    token_from_address,
+                    token_to_address,
+                    pool.get_address(),
+                    amount_in.unwrap_or_default(),
+                    pool.get_fee_by_direction(&token_from_address, &token_to_address),
+                )),
+                0x24,
+            ),
+            AmountQuoteSource::ExternalStaticCall => {
+                trace!("uniswap v2 get out amount with staticcall of pool={:?}", pool.get_address());
+                let mut call = MulticallerCall::new_static_call(
+                    pool.get_address(),
+                    &AbiEncoderHelper::encode_solidly_get_amount_out(amount_in.unwrap_or_default(), token_from_address),
+                );
+                call.set_return_stack(true, 0, 0x0, 0x20);
+                (call, 0x4)
+            }
+        }
+    }
+}
 
 impl SwapOpcodesEncoderTrait for UniswapV2SwapOpcodesEncoder {
     fn encode_swap_in_amount_provided(
@@ -36,19 +85,19 @@ impl SwapOpcodesEncoderTrait for UniswapV2SwapOpcodesEncoder {
         );
 
         // calculating out amount for in amount provided
-        let get_out_amount_opcode = MulticallerCall::new_internal_call(&AbiEncoderHelper::encode_multicaller_uni2_get_out_amount(
-            token_from_address,
-            token_to_address,
-            cur_pool.get_address(),
-            amount_in.unwrap_or_default(),
-            cur_pool.get_fee(),
-        ));
+        let (get_out_amount_opcode, amount_offset) = self.get_out_amount_call(token_from_address, token_to_address, amount_in, cur_pool);
 
         // setting argument from stack if it is required
-        swap_opcodes.merge(OpcodesHelpers::build_call_stack(amount_in, get_out_amount_opcode, 0x24, 0x20, Some(token_from_address))?);
+        let mut builder = MulticallerCallsBuilder::from_calls(OpcodesHelpers::build_call_stack(
+            amount_in,
+            get_out_amount_opcode,
+            amount_offset,
+            0x20,
+            Some(token_from_address),
+        )?);
 
         // abi encode and add uniswap swap opcode
-        let mut swap_opcode = MulticallerCall::new_call(
+        let swap_opcode = MulticallerCall::new_call(
             cur_pool.get_address(),
             &abi_encoder.encode_swap_out_amount_provided(
                 cur_pool,
@@ -61,14 +110,12 @@ impl SwapOpcodesEncoderTrait for UniswapV2SwapOpcodesEncoder {
         );
 
         // setting stack swap argument based on calculated out amount
-        swap_opcode.set_call_stack(
-            true,
-            0,
-            abi_encoder.swap_out_amount_offset(cur_pool, token_from_address, token_to_address).unwrap(),
-            0x20,
-        );
+        builder
+            .call(swap_opcode)
+            .amount_from(StackSlot::Last, abi_encoder.swap_out_amount_offset(cur_pool, token_from_address, token_to_address).unwrap())
+            .add();
 
-        swap_opcodes.add(swap_opcode);
+        swap_opcodes.merge(builder.build()?);
 
         Ok(())
     }
@@ -80,12 +127,12 @@ impl SwapOpcodesEncoderTrait for UniswapV2SwapOpcodesEncoder {
         _token_from_address: Address,
         _token_to_address: Address,
         _amount_out: SwapAmountType,
-        _cur_pool: &dyn Pool,
+        cur_pool: &dyn Pool,
         _next_pool: Option<&dyn Pool>,
         _payload: MulticallerOpcodesPayload,
         _multicaller_address: Address,
     ) -> eyre::Result<()> {
-        Err(eyre!("NOT_IMPLEMENTED"))
+        Err(EncoderError::NotImplemented { pool: cur_pool.get_pool_id(), operation: "swap_out_amount_provided" }.into())
     }
 
     fn encode_flash_swap_in_amount_provided(
@@ -122,10 +169,14 @@ impl SwapOpcodesEncoderTrait for UniswapV2SwapOpcodesEncoder {
             if let Some(prev_pool) = prev_pool {
                 if let PreswapRequirement::Transfer(swap_to) = prev_pool.preswap_requirement() {
                     trace!("uniswap v2 transfer token_to_address={:?}, funds_to={:?} amount=stack_norel_0", token_to_address, swap_to);
-                    let mut transfer_opcode =
-                        MulticallerCall::new_call(token_to_address, &AbiEncoderHelper::encode_erc20_transfer(swap_to, U256::ZERO));
-                    transfer_opcode.set_call_stack(false, 0, 0x24, 0x20);
-                    inside_opcodes.insert(transfer_opcode);
+                    let mut transfer_builder = MulticallerCallsBuilder::new().with_inherited_slots(1);
+                    transfer_builder
+                        .call(MulticallerCall::new_call(token_to_address, &AbiEncoderHelper::encode_erc20_transfer(swap_to, U256::ZERO)))
+                        .amount_from(StackSlot::Absolute(0), 0x24)
+                        .add();
+                    let mut transfer_opcodes = transfer_builder.build()?;
+                    transfer_opcodes.merge(inside_opcodes);
+                    inside_opcodes = transfer_opcodes;
                 }
             }
             MulticallerOpcodesPayload::Opcodes(inside_opcodes)
@@ -138,21 +189,19 @@ impl SwapOpcodesEncoderTrait for UniswapV2SwapOpcodesEncoder {
         // getting out amount for in amount provided
 
         trace!("uniswap v2 get out amount for pool={:?}, amount={:?}", flash_pool.get_address(), amount_in);
-        let mut get_out_amount_opcode = MulticallerCall::new_internal_call(&AbiEncoderHelper::encode_multicaller_uni2_get_out_amount(
-            token_from_address,
-            token_to_address,
-            flash_pool.get_address(),
-            amount_in.unwrap_or_default(),
-            flash_pool.get_fee(),
-        ));
+        let (get_out_amount_opcode, amount_offset) = self.get_out_amount_call(token_from_address, token_to_address, amount_in, flash_pool);
 
         // setting up stack, in amount is out amount for previous swap and is located in stack0
+        let mut builder = MulticallerCallsBuilder::new();
         if amount_in.is_not_set() {
-            get_out_amount_opcode.set_call_stack(false, 0, 0x24, 0x20);
+            builder.ensure_inherited_slots(1);
+            builder.call(get_out_amount_opcode).amount_from(StackSlot::Absolute(0), amount_offset).add();
+        } else {
+            builder.add(get_out_amount_opcode);
         }
 
         // abi encode uniswap2 out amount provided swap.
-        let mut swap_opcode = MulticallerCall::new_call(
+        let swap_opcode = MulticallerCall::new_call(
             flash_pool.get_address(),
             &abi_encoder.encode_swap_out_amount_provided(
                 flash_pool,
@@ -165,14 +214,12 @@ impl SwapOpcodesEncoderTrait for UniswapV2SwapOpcodesEncoder {
         );
 
         // setting call stack to calculated out amount.
-        swap_opcode.set_call_stack(
-            true,
-            0,
-            abi_encoder.swap_out_amount_offset(flash_pool, token_from_address, token_to_address).unwrap(),
-            0x20,
-        );
+        builder
+            .call(swap_opcode)
+            .amount_from(StackSlot::Last, abi_encoder.swap_out_amount_offset(flash_pool, token_from_address, token_to_address).unwrap())
+            .add();
 
-        swap_opcodes.add(get_out_amount_opcode).add(swap_opcode);
+        swap_opcodes.merge(builder.build()?);
 
         Ok(())
     }
@@ -194,34 +241,32 @@ impl SwapOpcodesEncoderTrait for UniswapV2SwapOpcodesEncoder {
 
         // add get_in amount to keep amount we should return in stack.
         let payload = if let MulticallerOpcodesPayload::Opcodes(inside_opcodes) = &payload {
-            let mut inside_opcodes = inside_opcodes.clone();
-
-            let mut get_in_amount_opcode = MulticallerCall::new_internal_call(&AbiEncoderHelper::encode_multicaller_uni2_get_in_amount(
+            let get_in_amount_opcode = MulticallerCall::new_internal_call(&AbiEncoderHelper::encode_multicaller_uni2_get_in_amount(
                 token_from_address,
                 token_to_address,
                 flash_pool.get_address(),
                 amount_out.unwrap_or_default(),
-                flash_pool.get_fee(),
+                flash_pool.get_fee_by_direction(&token_from_address, &token_to_address),
             ));
 
-            // is not set, will use stack0
-            if amount_out.is_not_set() {
-                get_in_amount_opcode.set_call_stack(false, 0, 0x24, 0x20);
-            }
+            // stack0 keeps the out amount received from the flash pool
+            let mut builder = MulticallerCallsBuilder::new().with_inherited_slots(1);
 
-            inside_opcodes.insert(get_in_amount_opcode);
+            // is not set, will use stack0
+            builder.call(get_in_amount_opcode).amount_from_opt(amount_out.is_not_set().then_some(StackSlot::Absolute(0)), 0x24).add();
+            builder.merge(inside_opcodes.clone());
 
             // if we need funds somewhere else, we transfer it because cannot set destination in uniswap2 with parameters
             if swap_to != multicaller_address {
                 trace!("retflash transfer token={:?}, to={:?}, amount=stack_norel_0", token_to_address, swap_to);
 
-                let mut transfer_opcode =
-                    MulticallerCall::new_call(token_to_address, &AbiEncoderHelper::encode_erc20_transfer(swap_to, U256::ZERO));
-                transfer_opcode.set_call_stack(false, 0, 0x24, 0x20);
-                inside_opcodes.add(transfer_opcode);
+                builder
+                    .call(MulticallerCall::new_call(token_to_address, &AbiEncoderHelper::encode_erc20_transfer(swap_to, U256::ZERO)))
+                    .amount_from(StackSlot::Absolute(0), 0x24)
+                    .add();
             }
 
-            MulticallerOpcodesPayload::Opcodes(inside_opcodes)
+            MulticallerOpcodesPayload::Opcodes(builder.build()?)
         } else {
             payload
         };
@@ -239,7 +284,7 @@ impl SwapOpcodesEncoderTrait for UniswapV2SwapOpcodesEncoder {
             swap_to,
             inside_call_bytes.len()
         );
-        let mut swap_opcode = MulticallerCall::new_call(
+        let swap_opcode = MulticallerCall::new_call(
             flash_pool.get_address(),
             &abi_encoder.encode_swap_out_amount_provided(
                 flash_pool,
@@ -252,17 +297,20 @@ impl SwapOpcodesEncoderTrait for UniswapV2SwapOpcodesEncoder {
         );
 
         // set rel_stack(0) is amount is not set
-        if amount_out.is_not_set() {
+        let mut builder = MulticallerCallsBuilder::from_calls(swap_opcodes.clone());
+        let amount_slot = if amount_out.is_not_set() {
             trace!("uniswap v2 amount not set");
-            swap_opcode.set_call_stack(
-                true,
-                0,
-                abi_encoder.swap_out_amount_offset(flash_pool, token_from_address, token_to_address).unwrap(),
-                0x20,
-            );
-        }
+            builder.ensure_inherited_slots(1);
+            Some(StackSlot::Last)
+        } else {
+            None
+        };
+        builder
+            .call(swap_opcode)
+            .amount_from_opt(amount_slot, abi_encoder.swap_out_amount_offset(flash_pool, token_from_address, token_to_address).unwrap())
+            .add();
 
-        swap_opcodes.add(swap_opcode);
+        *swap_opcodes = builder.build()?;
         Ok(())
     }
 }
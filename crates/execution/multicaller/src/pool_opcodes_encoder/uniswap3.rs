@@ -2,10 +2,10 @@ use crate::opcodes_helpers::OpcodesHelpers;
 use crate::pool_abi_encoder::ProtocolAbiSwapEncoderTrait;
 use crate::pool_opcodes_encoder::swap_opcodes_encoders::MulticallerOpcodesPayload;
 use crate::pool_opcodes_encoder::SwapOpcodesEncoderTrait;
+use crate::EncoderError;
 use alloy_primitives::{Address, Bytes, U256};
-use eyre::{eyre, OptionExt};
 use loom_defi_abi::AbiEncoderHelper;
-use loom_types_blockchain::{MulticallerCall, MulticallerCalls};
+use loom_types_blockchain::{MulticallerCall, MulticallerCalls, MulticallerCallsBuilder, StackSlot};
 use loom_types_entities::{Pool, PreswapRequirement, SwapAmountType};
 use tracing::trace;
 
@@ -57,7 +57,9 @@ impl SwapOpcodesEncoderTrait for UniswapV3SwapOpcodesEncoder {
         swap_opcodes.merge(OpcodesHelpers::build_call_stack(
             amount_in,
             swap_opcode,
-            abi_encoder.swap_in_amount_offset(cur_pool, token_from_address, token_to_address).ok_or_eyre("NO_OFFSET")?,
+            abi_encoder.swap_in_amount_offset(cur_pool, token_from_address, token_to_address).ok_or_else(|| {
+                EncoderError::NoAmountOffset { pool: cur_pool.get_pool_id(), token_from: token_from_address, token_to: token_to_address }
+            })?,
             0x20,
             Some(token_from_address),
         )?);
@@ -79,12 +81,12 @@ impl SwapOpcodesEncoderTrait for UniswapV3SwapOpcodesEncoder {
         _token_from_address: Address,
         _token_to_address: Address,
         _amount_out: SwapAmountType,
-        _cur_pool: &dyn Pool,
+        cur_pool: &dyn Pool,
         _next_pool: Option<&dyn Pool>,
         _payload: MulticallerOpcodesPayload,
         _multicaller_address: Address,
     ) -> eyre::Result<()> {
-        Err(eyre!("NOT_IMPLEMENTED"))
+        Err(EncoderError::NotImplemented { pool: cur_pool.get_pool_id(), operation: "swap_out_amount_provided" }.into())
     }
 
     fn encode_flash_swap_in_amount_provided(
@@ -116,19 +118,16 @@ impl SwapOpcodesEncoderTrait for UniswapV3SwapOpcodesEncoder {
         let payload = match payload {
             MulticallerOpcodesPayload::Opcodes(payload) => {
                 trace!("uniswap v3 transfer token={:?}, to={:?}, amount={:?}", token_from_address, flash_pool.get_address(), amount_in);
-                let mut payload = payload;
-                let mut transfer_opcode = MulticallerCall::new_call(
+                // uniswap v3 callback provides amount0 and amount1 deltas in stack0 and stack1
+                let mut builder = MulticallerCallsBuilder::from_calls(payload).with_inherited_slots(2);
+                let transfer_opcode = MulticallerCall::new_call(
                     token_from_address,
                     &AbiEncoderHelper::encode_erc20_transfer(flash_pool.get_address(), amount_in.unwrap_or_default()),
                 );
 
-                if amount_in.is_not_set() {
-                    transfer_opcode.set_call_stack(false, 1, 0x24, 0x20);
-                }
-
-                payload.add(transfer_opcode);
+                builder.call(transfer_opcode).amount_from_opt(amount_in.is_not_set().then_some(StackSlot::Absolute(1)), 0x24).add();
 
-                MulticallerOpcodesPayload::Opcodes(payload)
+                MulticallerOpcodesPayload::Opcodes(builder.build()?)
             }
             _ => payload,
         };
@@ -142,7 +141,7 @@ impl SwapOpcodesEncoderTrait for UniswapV3SwapOpcodesEncoder {
             token_to_address,
             amount_in,
         );
-        let mut swap_opcode = MulticallerCall::new_call(
+        let swap_opcode = MulticallerCall::new_call(
             flash_pool.get_address(),
             &abi_encoder.encode_swap_in_amount_provided(
                 flash_pool,
@@ -154,16 +153,19 @@ impl SwapOpcodesEncoderTrait for UniswapV3SwapOpcodesEncoder {
             )?,
         );
 
-        if amount_in.is_not_set() {
-            swap_opcode.set_call_stack(
-                false,
-                0,
-                abi_encoder.swap_in_amount_offset(flash_pool, token_from_address, token_to_address).unwrap(),
-                0x20,
-            );
-        }
+        let mut builder = MulticallerCallsBuilder::from_calls(swap_opcodes.clone());
+        let amount_slot = if amount_in.is_not_set() {
+            builder.ensure_inherited_slots(1);
+            Some(StackSlot::Absolute(0))
+        } else {
+            None
+        };
+        builder
+            .call(swap_opcode)
+            .amount_from_opt(amount_slot, abi_encoder.swap_in_amount_offset(flash_pool, token_from_address, token_to_address).unwrap())
+            .add();
 
-        swap_opcodes.add(swap_opcode);
+        *swap_opcodes = builder.build()?;
 
         Ok(())
     }
@@ -182,21 +184,80 @@ impl SwapOpcodesEncoderTrait for UniswapV3SwapOpcodesEncoder {
     ) -> eyre::Result<()> {
         let swap_to = next_pool.and_then(|next_pool| next_pool.preswap_requirement().address()).unwrap_or(multicaller_address);
 
-        let payload = if let MulticallerOpcodesPayload::Opcodes(inside_opcodes) = payload {
-            let mut inside_opcodes = inside_opcodes;
-            //if next_pool.is_none() {
-            trace!("retflash transfer token={:?}, to={:?}, amount=stack_norel_1", token_from_address, flash_pool.get_address());
-            let mut transfer_opcode = MulticallerCall::new_call(
-                token_from_address,
-                &AbiEncoderHelper::encode_erc20_transfer(flash_pool.get_address(), U256::ZERO),
-            );
-            transfer_opcode.set_call_stack(false, 1, 0x24, 0x20);
+        self.encode_flash_swap_out(
+            swap_opcodes,
+            abi_encoder,
+            token_from_address,
+            token_to_address,
+            amount_out,
+            flash_pool,
+            swap_to,
+            true,
+            payload,
+        )
+    }
 
-            inside_opcodes.add(transfer_opcode);
+    fn encode_chained_flash_swap_out_amount_provided(
+        &self,
+        swap_opcodes: &mut MulticallerCalls,
+        abi_encoder: &dyn ProtocolAbiSwapEncoderTrait,
+        token_from_address: Address,
+        token_to_address: Address,
+        amount_out: SwapAmountType,
+        flash_pool: &dyn Pool,
+        chained_next_pool: Option<&dyn Pool>,
+        input_chained: bool,
+        payload: MulticallerOpcodesPayload,
+        multicaller_address: Address,
+    ) -> eyre::Result<()> {
+        // the next pool checks its balance after the callback, the output sent to it pays the owed amount
+        let swap_to = chained_next_pool.map(|next_pool| next_pool.get_address()).unwrap_or(multicaller_address);
 
-            MulticallerOpcodesPayload::Opcodes(inside_opcodes)
-        } else {
-            payload
+        self.encode_flash_swap_out(
+            swap_opcodes,
+            abi_encoder,
+            token_from_address,
+            token_to_address,
+            amount_out,
+            flash_pool,
+            swap_to,
+            !input_chained,
+            payload,
+        )
+    }
+}
+
+impl UniswapV3SwapOpcodesEncoder {
+    /// Exact out swap with the payload called in the callback, followed by the transfer of the owed input if `pay_input`
+    #[allow(clippy::too_many_arguments)]
+    fn encode_flash_swap_out(
+        &self,
+        swap_opcodes: &mut MulticallerCalls,
+        abi_encoder: &dyn ProtocolAbiSwapEncoderTrait,
+        token_from_address: Address,
+        token_to_address: Address,
+        amount_out: SwapAmountType,
+        flash_pool: &dyn Pool,
+        swap_to: Address,
+        pay_input: bool,
+        payload: MulticallerOpcodesPayload,
+    ) -> eyre::Result<()> {
+        let payload = match payload {
+            MulticallerOpcodesPayload::Opcodes(inside_opcodes) => {
+                // uniswap v3 callback provides amount0 and amount1 deltas in stack0 and stack1
+                let mut builder = MulticallerCallsBuilder::from_calls(inside_opcodes).with_inherited_slots(2);
+                if pay_input {
+                    trace!("retflash transfer token={:?}, to={:?}, amount=stack_norel_1", token_from_address, flash_pool.get_address());
+                    let transfer_opcode = MulticallerCall::new_call(
+                        token_from_address,
+                        &AbiEncoderHelper::encode_erc20_transfer(flash_pool.get_address(), U256::ZERO),
+                    );
+                    builder.call(transfer_opcode).amount_from(StackSlot::Absolute(1), 0x24).add();
+                }
+
+                MulticallerOpcodesPayload::Opcodes(builder.build()?)
+            }
+            _ => payload,
         };
 
         let inside_call_bytes = payload.encode()?;
@@ -211,7 +272,7 @@ impl SwapOpcodesEncoderTrait for UniswapV3SwapOpcodesEncoder {
             inside_call_bytes.len()
         );
 
-        let mut swap_opcode = MulticallerCall::new_call(
+        let swap_opcode = MulticallerCall::new_call(
             flash_pool.get_address(),
             &abi_encoder.encode_swap_out_amount_provided(
                 flash_pool,
@@ -223,21 +284,83 @@ impl SwapOpcodesEncoderTrait for UniswapV3SwapOpcodesEncoder {
             )?,
         );
 
-        if amount_out.is_not_set() {
+        let mut builder = MulticallerCallsBuilder::from_calls(swap_opcodes.clone());
+        let amount_slot = if amount_out.is_not_set() {
             trace!("uniswap v3 swap out amount is not set");
 
-            swap_opcode.set_call_stack(
-                true,
-                0,
-                abi_encoder.swap_out_amount_offset(flash_pool, token_from_address, token_to_address).unwrap(),
-                0x20,
-            );
-
-            swap_opcodes.add(MulticallerCall::new_calculation_call(&Bytes::from(vec![0x2, 0x2A, 0x00])));
+            // calculation call pushes the out amount derived from the amount on top of the stack
+            builder.ensure_inherited_slots(1);
+            builder.add(MulticallerCall::new_calculation_call(&Bytes::from(vec![0x2, 0x2A, 0x00])));
+            Some(StackSlot::Last)
+        } else {
+            None
         };
+        builder
+            .call(swap_opcode)
+            .amount_from_opt(amount_slot, abi_encoder.swap_out_amount_offset(flash_pool, token_from_address, token_to_address).unwrap())
+            .add();
 
-        swap_opcodes.add(swap_opcode);
+        *swap_opcodes = builder.build()?;
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ProtocolABIEncoderV2;
+    use alloy_primitives::I256;
+    use alloy_sol_types::SolCall;
+    use loom_defi_abi::uniswap3::IUniswapV3Pool;
+    use loom_defi_pools::UniswapV3Pool;
+
+    const MULTICALLER: Address = Address::repeat_byte(0xAA);
+
+    fn encode_swap(chained_next_pool: Option<&dyn Pool>, input_chained: bool) -> IUniswapV3Pool::swapCall {
+        let (token0, token1) = (Address::repeat_byte(1), Address::repeat_byte(2));
+        let pool = UniswapV3Pool::new_with_data(Address::repeat_byte(0x10), token0, token1, 0, 3000, None, Address::ZERO);
+        let mut inside_opcodes = MulticallerCalls::new();
+        inside_opcodes.add(MulticallerCall::new_call(Address::repeat_byte(0x20), &Bytes::new()));
+
+        let mut swap_opcodes = MulticallerCalls::new();
+        UniswapV3SwapOpcodesEncoder {}
+            .encode_chained_flash_swap_out_amount_provided(
+                &mut swap_opcodes,
+                &ProtocolABIEncoderV2::default(),
+                token0,
+                token1,
+                SwapAmountType::Set(U256::from(1000)),
+                &pool,
+                chained_next_pool,
+                input_chained,
+                MulticallerOpcodesPayload::Opcodes(inside_opcodes),
+                MULTICALLER,
+            )
+            .unwrap();
+        IUniswapV3Pool::swapCall::abi_decode(&swap_opcodes.get(swap_opcodes.len() - 1).unwrap().call_data, true).unwrap()
+    }
+
+    #[test]
+    fn test_chained_flash_swap_out() {
+        let next_pool = UniswapV3Pool::new_with_data(
+            Address::repeat_byte(0x11),
+            Address::repeat_byte(2),
+            Address::repeat_byte(3),
+            0,
+            500,
+            None,
+            Address::ZERO,
+        );
+
+        let relayed = encode_swap(None, false);
+        assert_eq!(relayed.recipient, MULTICALLER);
+        assert_eq!(relayed.amountSpecified, -I256::from_raw(U256::from(1000)));
+
+        // output sent to the next pool, the input paid by the swap in the callback
+        let chained = encode_swap(Some(&next_pool), true);
+        assert_eq!(chained.recipient, next_pool.get_address());
+        assert_eq!(chained.amountSpecified, relayed.amountSpecified);
+        assert!(chained.data.len() < relayed.data.len());
+    }
+}
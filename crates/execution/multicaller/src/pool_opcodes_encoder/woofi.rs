@@ -0,0 +1,96 @@
+use alloy_primitives::Address;
+use eyre::Result;
+use tracing::trace;
+
+use crate::opcodes_helpers::OpcodesHelpers;
+use crate::pool_abi_encoder::ProtocolAbiSwapEncoderTrait;
+use crate::pool_opcodes_encoder::swap_opcodes_encoders::MulticallerOpcodesPayload;
+use crate::pool_opcodes_encoder::SwapOpcodesEncoderTrait;
+use crate::EncoderError;
+use loom_defi_abi::AbiEncoderHelper;
+use loom_types_blockchain::{MulticallerCall, MulticallerCalls, MulticallerCallsBuilder};
+use loom_types_entities::Pool;
+use loom_types_entities::SwapAmountType;
+
+/// Transfers the input to WooPPV2 and swaps, the pool checks its balance against the stored reserve
+pub struct WooFiSwapOpcodesEncoder;
+
+impl SwapOpcodesEncoderTrait for WooFiSwapOpcodesEncoder {
+    #[allow(clippy::too_many_arguments)]
+    fn encode_swap_in_amount_provided(
+        &self,
+        swap_opcodes: &mut MulticallerCalls,
+        abi_encoder: &dyn ProtocolAbiSwapEncoderTrait,
+        token_from_address: Address,
+        token_to_address: Address,
+        amount_in: SwapAmountType,
+        cur_pool: &dyn Pool,
+        next_pool: Option<&dyn Pool>,
+        payload: MulticallerOpcodesPayload,
+        multicaller: Address,
+    ) -> Result<()> {
+        let pool_address = cur_pool.get_address();
+
+        trace!("woofi swap for pool={:?} amount={:?} from {} to {}", pool_address, amount_in, token_from_address, token_to_address);
+
+        // the pool sends the output directly to the next pool if it expects a transfer
+        let swap_to = next_pool.and_then(|next_pool| next_pool.preswap_requirement().address()).unwrap_or(multicaller);
+
+        let swap_in_amount_offset = abi_encoder.swap_in_amount_offset(cur_pool, token_from_address, token_to_address).ok_or_else(|| {
+            EncoderError::UnsupportedSwapDirection {
+                pool: cur_pool.get_pool_id(),
+                token_from: token_from_address,
+                token_to: token_to_address,
+            }
+        })?;
+
+        let mut opcodes: Vec<(MulticallerCall, u32, usize)> = Vec::new();
+
+        // Transfer
+        opcodes.push((
+            MulticallerCall::new_call(
+                token_from_address,
+                &AbiEncoderHelper::encode_erc20_transfer(pool_address, amount_in.unwrap_or_default()),
+            ),
+            0x24,
+            0x20,
+        ));
+
+        // SWAP, realToAmount is returned
+        let mut swap_opcode = MulticallerCall::new_call(
+            pool_address,
+            &abi_encoder.encode_swap_in_amount_provided(
+                cur_pool,
+                token_from_address,
+                token_to_address,
+                amount_in.unwrap_or_default(),
+                swap_to,
+                payload.encode()?,
+            )?,
+        );
+        swap_opcode.set_return_stack(true, 0, 0x0, 0x20);
+        opcodes.push((swap_opcode, swap_in_amount_offset, 0x20));
+
+        let builder =
+            MulticallerCallsBuilder::from_calls(OpcodesHelpers::build_multiple_stack(amount_in, opcodes, Some(token_from_address))?)
+                .with_inherited_slots(amount_in.inherited_stack_slots());
+
+        swap_opcodes.merge(builder.build()?);
+        Ok(())
+    }
+
+    fn encode_swap_out_amount_provided(
+        &self,
+        _swap_opcodes: &mut MulticallerCalls,
+        _abi_encoder: &dyn ProtocolAbiSwapEncoderTrait,
+        _token_from_address: Address,
+        _token_to_address: Address,
+        _amount_out: SwapAmountType,
+        cur_pool: &dyn Pool,
+        _next_pool: Option<&dyn Pool>,
+        _payload: MulticallerOpcodesPayload,
+        _multicaller_address: Address,
+    ) -> Result<()> {
+        Err(EncoderError::NotImplemented { pool: cur_pool.get_pool_id(), operation: "swap_out_amount_provided" }.into())
+    }
+}
@@ -2,8 +2,9 @@ use crate::opcodes_helpers::OpcodesHelpers;
 use crate::pool_abi_encoder::ProtocolAbiSwapEncoderTrait;
 use crate::pool_opcodes_encoder::swap_opcodes_encoders::MulticallerOpcodesPayload;
 use crate::pool_opcodes_encoder::SwapOpcodesEncoderTrait;
+use crate::EncoderError;
 use alloy_primitives::{Address, Bytes};
-use eyre::{eyre, Result};
+use eyre::Result;
 use loom_defi_abi::AbiEncoderHelper;
 use loom_defi_address_book::TokenAddressEth;
 use loom_types_blockchain::{MulticallerCall, MulticallerCalls};
@@ -83,7 +84,12 @@ impl SwapOpcodesEncoderTrait for WstEthSwapEncoder {
             return Ok(());
         }
 
-        Err(eyre!("CANNOT_ENCODE_WSTETH_SWAP"))
+        Err(EncoderError::UnsupportedSwapDirection {
+            pool: cur_pool.get_pool_id(),
+            token_from: token_from_address,
+            token_to: token_to_address,
+        }
+        .into())
     }
 
     fn encode_swap_out_amount_provided(
@@ -93,11 +99,11 @@ impl SwapOpcodesEncoderTrait for WstEthSwapEncoder {
         _token_from_address: Address,
         _token_to_address: Address,
         _amount_out: SwapAmountType,
-        _cur_pool: &dyn Pool,
+        cur_pool: &dyn Pool,
         _next_pool: Option<&dyn Pool>,
         _payload: MulticallerOpcodesPayload,
         _multicaller_address: Address,
     ) -> Result<()> {
-        Err(eyre!("NOT_IMPLEMENTED"))
+        Err(EncoderError::NotImplemented { pool: cur_pool.get_pool_id(), operation: "swap_out_amount_provided" }.into())
     }
 }
@@ -0,0 +1,132 @@
+use alloy_primitives::{Address, Bytes, U256};
+use eyre::Result;
+use loom_evm_db::LoomDB;
+use loom_evm_utils::evm::{evm_call, evm_transact};
+use loom_evm_utils::BalanceCheater;
+use loom_types_blockchain::MulticallerCalls;
+use revm::primitives::{AccountInfo, Bytecode, Env, TransactTo, CANCUN};
+use revm::Evm;
+
+use crate::{MulticallerDeployer, OpcodesEncoder, OpcodesEncoderV2, DEFAULT_VIRTUAL_ADDRESS};
+
+/// Multicaller installed into a local [`LoomDB`] to execute encoded calls without a node or a fork.
+/// Pools and tokens have to be inserted into the db by the test.
+pub struct RevmMulticaller {
+    address: Address,
+    db: LoomDB,
+    env: Env,
+}
+
+impl Default for RevmMulticaller {
+    fn default() -> Self {
+        Self::install(LoomDB::new(), DEFAULT_VIRTUAL_ADDRESS)
+    }
+}
+
+impl RevmMulticaller {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Install the multicaller code at `address` of the db
+    pub fn install(mut db: LoomDB, address: Address) -> Self {
+        Self::set_account(&mut db, address, U256::ZERO);
+        Self { address, db, env: Env::default() }
+    }
+
+    fn set_account(db: &mut LoomDB, address: Address, balance: U256) {
+        let code = MulticallerDeployer::new().account_info().code.unwrap_or_default();
+        db.insert_account_info(address, AccountInfo { balance, code: Some(Bytecode::new_raw(code)), ..Default::default() });
+    }
+
+    pub fn with_balance(mut self, balance: U256) -> Self {
+        Self::set_account(&mut self.db, self.address, balance);
+        self
+    }
+
+    pub fn with_token_balance(mut self, token: Address, balance: U256) -> Result<Self> {
+        BalanceCheater::set_evm_token_balance(&mut self.db, token, self.address, balance)?;
+        Ok(self)
+    }
+
+    pub fn with_env(self, env: Env) -> Self {
+        Self { env, ..self }
+    }
+
+    pub fn address(&self) -> Address {
+        self.address
+    }
+
+    pub fn db(&self) -> &LoomDB {
+        &self.db
+    }
+
+    pub fn db_mut(&mut self) -> &mut LoomDB {
+        &mut self.db
+    }
+
+    /// Execute the calls without changing the db, returns output and gas used
+    pub fn call(&self, calls: &MulticallerCalls) -> Result<(Vec<u8>, u64)> {
+        let call_data = OpcodesEncoderV2::pack_do_calls(calls)?;
+        evm_call(&self.db, self.env.clone(), self.address, call_data.to_vec())
+    }
+
+    /// Execute the calls and commit the state changes to the db
+    pub fn transact(&mut self, calls: &MulticallerCalls) -> Result<(Vec<u8>, u64)> {
+        let mut env = self.env.clone();
+        env.tx.transact_to = TransactTo::Call(self.address);
+        env.tx.data = OpcodesEncoderV2::pack_do_calls(calls)?;
+
+        let mut evm = Evm::builder().with_spec_id(CANCUN).with_db(&mut self.db).with_env(Box::new(env)).build();
+        evm_transact(&mut evm)
+    }
+
+    /// Send a callback of the multicaller from `caller`, e.g. a lender calling back with the encoded swap calls, and commit
+    /// the state changes to the db
+    pub fn transact_callback(&mut self, caller: Address, call_data: Bytes) -> Result<(Vec<u8>, u64)> {
+        let mut env = self.env.clone();
+        env.tx.caller = caller;
+        env.tx.transact_to = TransactTo::Call(self.address);
+        env.tx.data = call_data;
+
+        let mut evm = Evm::builder().with_spec_id(CANCUN).with_db(&mut self.db).with_env(Box::new(env)).build();
+        evm_transact(&mut evm)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use loom_evm_utils::NWETH;
+    use loom_types_blockchain::MulticallerCall;
+    use revm::DatabaseRef;
+
+    #[test]
+    fn test_value_call() -> Result<()> {
+        let one_eth = NWETH::get_exp();
+        let mut multicaller = RevmMulticaller::new().with_balance(one_eth);
+        assert!(multicaller.db().basic_ref(DEFAULT_VIRTUAL_ADDRESS)?.is_some_and(|account| !account.is_empty_code_hash()));
+
+        let recipient = Address::repeat_byte(0x11);
+        let mut calls = MulticallerCalls::new();
+        calls.add(MulticallerCall::new_call_with_value(recipient, &Bytes::new(), one_eth / U256::from(4)));
+
+        multicaller.call(&calls)?;
+        assert!(multicaller.db().basic_ref(recipient)?.is_none_or(|account| account.balance.is_zero()));
+
+        multicaller.transact(&calls)?;
+        assert_eq!(multicaller.db().basic_ref(recipient)?.unwrap_or_default().balance, one_eth / U256::from(4));
+        assert_eq!(multicaller.db().basic_ref(multicaller.address())?.unwrap_or_default().balance, one_eth * U256::from(3) / U256::from(4));
+        Ok(())
+    }
+
+    #[test]
+    fn test_plain_eth_receive() -> Result<()> {
+        // raw ETH sent by a pool, e.g. a Curve ETH pool paying out inside its reentrancy lock, runs no calls of the multicaller
+        let mut multicaller = RevmMulticaller::new();
+        let (output, gas_used) = multicaller.transact_callback(Address::repeat_byte(0xC0), Bytes::new())?;
+        assert!(output.is_empty());
+        assert!(gas_used < 21_100);
+        Ok(())
+    }
+}
@@ -1,10 +1,11 @@
-use crate::MulticallerSwapEncoder;
+use crate::{EncoderError, MulticallerSwapEncoder};
 use alloy_primitives::{Address, BlockNumber, Bytes, U256};
-use eyre::{eyre, OptionExt, Result};
+use eyre::{eyre, Result};
+use loom_defi_abi::AbiEncoderHelper;
 use loom_types_blockchain::LoomDataTypesEthereum;
-use loom_types_blockchain::MulticallerCalls;
+use loom_types_blockchain::{MulticallerCall, MulticallerCalls, MulticallerCallsBuilder, StackSlot};
 use loom_types_entities::tips::{tips_and_value_for_swap_type, Tips};
-use loom_types_entities::{Swap, SwapEncoder, SwapStep};
+use loom_types_entities::{ChainPreset, ExecutionProfile, FundingMode, PoolClass, SharedTipRecipients, Swap, SwapEncoder, SwapStep};
 use tracing::{debug, error, trace};
 
 impl SwapEncoder for MulticallerSwapEncoder {
@@ -14,10 +15,36 @@ impl SwapEncoder for MulticallerSwapEncoder {
         self.swap_step_encoder.swap_line_encoder.multicaller_address = multicaller_address;
     }
 
+    fn set_execution_profile(&mut self, execution_profile: ExecutionProfile) {
+        self.execution_profile = execution_profile;
+    }
+
+    fn execution_profile(&self) -> ExecutionProfile {
+        self.execution_profile
+    }
+
+    fn set_revoke_approvals(&mut self, revoke_approvals: bool) {
+        *self = self.clone().with_revoke_approvals(revoke_approvals);
+    }
+
+    fn set_tip_recipients(&mut self, tip_recipients: SharedTipRecipients, builder: Option<String>) {
+        self.tip_recipients = Some(tip_recipients);
+        self.tip_builder = builder;
+    }
+
+    fn set_chain_preset(&mut self, chain_preset: ChainPreset) {
+        *self = self.clone().with_chain_preset(chain_preset);
+    }
+
+    fn chain_preset(&self) -> ChainPreset {
+        self.chain_preset.clone()
+    }
+
     fn address(&self) -> Address {
         self.multicaller_address
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn encode(
         &self,
         swap: Swap,
@@ -26,15 +53,110 @@ impl SwapEncoder for MulticallerSwapEncoder {
         gas_cost: Option<U256>,
         sender_address: Option<Address>,
         sender_eth_balance: Option<U256>,
+        profit_receiver: Option<Address>,
     ) -> Result<(Address, Option<U256>, Bytes, Vec<Tips>)> {
-        let swap_vec = match &swap {
+        let mut swap_opcodes = self.calls_templates.get_or_encode(&swap, |swap| self.encode_swap_calls(swap))?;
+
+        // without builders the tips would be wasted
+        let tips_pct = tips_pct.filter(|_| self.execution_profile.pays_tips());
+        let (tips_vec, tips_to) =
+            if let (Some(tips_pct), Some(sender_address), Some(sender_eth_balance)) = (tips_pct, sender_address, sender_eth_balance) {
+                let (tips_vec, _call_value) = tips_and_value_for_swap_type(&swap, Some(tips_pct), gas_cost, sender_eth_balance)?;
+                (tips_vec, Some(sender_address))
+            } else {
+                (vec![], None)
+            };
+
+        // paid out before the tips, the share of the profit covering the gas and tips stays for the tips balance check
+        if let Some(profit_receiver) = profit_receiver.filter(|profit_receiver| *profit_receiver != self.multicaller_address) {
+            let payouts = Self::profit_payouts(&swap, &tips_vec, gas_cost);
+            if !payouts.is_empty() {
+                swap_opcodes = self.swap_step_encoder.swap_line_encoder.encode_profit_payout(swap_opcodes, &payouts, profit_receiver)?;
+            }
+        }
+
+        if let Some(tips_to) = tips_to {
+            let tip_recipient = self.tip_recipient();
+            for tips in &tips_vec {
+                swap_opcodes = self.swap_step_encoder.encode_tips(
+                    swap_opcodes,
+                    tips.token_in.get_address(),
+                    tips.min_change,
+                    tips.tips,
+                    tips_to,
+                    tip_recipient,
+                )?;
+            }
+        }
+
+        let (to, call_data) = self.swap_step_encoder.to_call_data(&swap_opcodes)?;
+
+        Ok((to, None, call_data, tips_vec))
+    }
+
+    fn encode_revert_on_loss(&self, swap: &Swap, min_profit: U256, deadline: u64) -> Result<(Address, Bytes)> {
+        if !matches!(swap, Swap::BackrunSwapLine(_) | Swap::BackrunSwapSteps(_)) {
+            return Err(EncoderError::UnsupportedSwapType.into());
+        }
+        if min_profit.is_zero() {
+            return Err(eyre!("ZERO_MIN_PROFIT"));
+        }
+        let token_in = swap.get_first_token().ok_or(EncoderError::EmptySwapLine)?;
+        let deadline_router = self.deadline_router.ok_or(eyre!("NO_DEADLINE_ROUTER"))?;
+
+        let mut swap_opcodes = self.calls_templates.get_or_encode(swap, |swap| self.encode_swap_calls(swap))?;
+        // the deadline is checked before any swap
+        swap_opcodes.insert(MulticallerCall::new_call(deadline_router, &AbiEncoderHelper::encode_deadline_check(U256::from(deadline))));
+        // reverts with the profit below the minimum, no tips are paid in the public mempool
+        swap_opcodes.add(MulticallerCall::new_internal_call(&AbiEncoderHelper::encode_multicaller_transfer_tips_no_payout(
+            token_in.get_address(),
+            min_profit,
+            U256::ZERO,
+        )));
+
+        self.swap_step_encoder.to_call_data(&swap_opcodes)
+    }
+}
+
+impl MulticallerSwapEncoder {
+    /// Profit tokens of a backrun paid out to the profit receiver with the reserve kept for the gas and tips. Tokens with
+    /// a simulated profit not above the reserve are not paid out
+    fn profit_payouts(swap: &Swap, tips_vec: &[Tips], gas_cost: Option<U256>) -> Vec<(Address, U256)> {
+        let payouts: Vec<(Address, U256, U256)> = match swap {
+            Swap::BackrunSwapLine(_) | Swap::BackrunSwapSteps(_) | Swap::Multiple(_) if !tips_vec.is_empty() => {
+                tips_vec.iter().map(|tips| (tips.token_in.get_address(), tips.profit, tips.min_change)).collect()
+            }
+            Swap::BackrunSwapLine(_) | Swap::BackrunSwapSteps(_) => match swap.get_first_token() {
+                Some(token_in) => {
+                    let gas_cost = gas_cost.and_then(|gas_cost| token_in.calc_token_value_from_eth(gas_cost)).unwrap_or_default();
+                    vec![(token_in.get_address(), swap.abs_profit(), gas_cost)]
+                }
+                None => vec![],
+            },
+            _ => vec![],
+        };
+        payouts.into_iter().filter(|(_, profit, reserve)| profit > reserve).map(|(token, _, reserve)| (token, reserve)).collect()
+    }
+
+    /// Encode the swap calls without tips
+    fn encode_swap_calls(&self, swap: &Swap) -> Result<MulticallerCalls> {
+        // independent swap lines share the funding and the tips of one transaction
+        if let Some(swap_lines) = swap.independent_swap_lines() {
+            trace!("START: encode_multiple_swap_lines");
+            let swap_opcodes = self.swap_step_encoder.encode_multiple_swap_lines(&swap_lines)?;
+            return Ok(Self::sync_rebasing_pools(swap, swap_opcodes));
+        }
+
+        let swap_vec = match swap {
+            // inventory funded swap lines are encoded without a flash swap
+            Swap::BackrunSwapLine(swap_line) if swap_line.funding == Some(FundingMode::Balance) => vec![],
             Swap::BackrunSwapLine(_) | Swap::BackrunSwapSteps(_) => {
-                vec![swap.to_swap_steps(self.swap_step_encoder.get_contract_address()).ok_or_eyre("SWAP_TYPE_NOTE_COVERED")?]
+                vec![swap.to_swap_steps(self.swap_step_encoder.get_contract_address()).ok_or(EncoderError::UnsupportedSwapType)?]
             }
             Swap::Multiple(swap_vec) => {
                 let mut ret: Vec<(SwapStep<LoomDataTypesEthereum>, SwapStep<LoomDataTypesEthereum>)> = Vec::new();
                 for s in swap_vec.iter() {
-                    ret.push(s.to_swap_steps(self.swap_step_encoder.get_contract_address()).ok_or_eyre("AA")?);
+                    ret.push(s.to_swap_steps(self.swap_step_encoder.get_contract_address()).ok_or(EncoderError::UnsupportedSwapType)?);
                 }
                 ret
             }
@@ -50,19 +172,38 @@ impl SwapEncoder for MulticallerSwapEncoder {
         }
         debug!("Swaps:\n{}", swap_steps);
 
-        let mut swap_opcodes = if swap_vec.is_empty() {
-            match &swap {
+        let swap_opcodes = if swap_vec.is_empty() {
+            match swap {
                 Swap::ExchangeSwapLine(swap_line) => {
                     trace!("START: exchange swap line");
-                    match self.swap_step_encoder.swap_line_encoder.encode_swap_line_in_amount(swap_line, None) {
+                    let calls = match self.swap_step_encoder.swap_line_encoder.encode_swap_line_in_amount(swap_line, None) {
                         Ok(calls) => calls,
                         Err(e) => {
                             error!("swap_line_encoder.encode_swap_line_in_amount : {}", e);
-                            return Err(eyre!("ENCODING_FAILED"));
+                            return Err(e);
+                        }
+                    };
+
+                    // send the output to the order recipient
+                    match swap_line.swap_to {
+                        Some(recipient) if recipient != self.multicaller_address => {
+                            let token_out = swap_line.get_last_token().ok_or(EncoderError::EmptySwapLine)?;
+                            self.encode_exchange_payout(
+                                calls,
+                                token_out.get_address(),
+                                token_out.is_weth() && swap_line.native_payout,
+                                recipient,
+                                swap_line.min_amount_out.unwrap_or_default(),
+                            )?
                         }
+                        _ => calls,
                     }
                 }
-                _ => return Err(eyre!("NO_SWAP_STEPS")),
+                Swap::BackrunSwapLine(swap_line) => {
+                    trace!("START: inventory funded swap line");
+                    self.swap_step_encoder.swap_line_encoder.encode_swap_line_in_amount(swap_line, None)?
+                }
+                _ => return Err(EncoderError::NoSwapSteps.into()),
             }
         } else if swap_vec.len() == 1 {
             trace!("START: encode_swap_steps two-hop");
@@ -78,26 +219,214 @@ impl SwapEncoder for MulticallerSwapEncoder {
             ret
         };
         trace!("END: swap_opcodes");
+        Ok(Self::sync_rebasing_pools(swap, swap_opcodes))
+    }
 
-        let tips_vec =
-            if let (Some(tips_pct), Some(sender_address), Some(sender_eth_balance)) = (tips_pct, sender_address, sender_eth_balance) {
-                let (tips_vec, _call_value) = tips_and_value_for_swap_type(&swap, Some(tips_pct), gas_cost, sender_eth_balance)?;
-                for tips in &tips_vec {
-                    swap_opcodes = self.swap_step_encoder.encode_tips(
-                        swap_opcodes,
-                        tips.token_in.get_address(),
-                        tips.min_change,
-                        tips.tips,
-                        sender_address,
-                    )?;
-                }
-                tips_vec
-            } else {
-                vec![]
-            };
+    /// Sync the reserves of uniswap v2 pools with rebasing tokens before any funds are transferred to them,
+    /// otherwise the pool output is calculated on stale reserves
+    fn sync_rebasing_pools(swap: &Swap, mut swap_opcodes: MulticallerCalls) -> MulticallerCalls {
+        let mut synced: Vec<Address> = Vec::new();
+        for pool in swap.get_rebasing_pools_vec().iter().filter(|pool| pool.get_class() == PoolClass::UniswapV2) {
+            if !synced.contains(&pool.get_address()) {
+                synced.push(pool.get_address());
+            }
+        }
+        for pool_address in synced.into_iter().rev() {
+            trace!("uniswap v2 sync rebasing pool={:?}", pool_address);
+            swap_opcodes.insert(MulticallerCall::new_call(pool_address, &AbiEncoderHelper::encode_uniswap2_sync()));
+        }
+        swap_opcodes
+    }
 
-        let (to, call_data) = self.swap_step_encoder.to_call_data(&swap_opcodes)?;
+    /// Pay the output of the last hop of an exchange swap to the order recipient. The output is moved to the router of
+    /// the chain and swept to the recipient, reverting the swap when it is below `min_amount_out`. Native payouts are
+    /// unwrapped by the router and paid out as ETH
+    fn encode_exchange_payout(
+        &self,
+        calls: MulticallerCalls,
+        token_out: Address,
+        native_payout: bool,
+        recipient: Address,
+        min_amount_out: U256,
+    ) -> Result<MulticallerCalls> {
+        let deadline_router = self.deadline_router.ok_or(eyre!("NO_DEADLINE_ROUTER"))?;
+        let mut builder = MulticallerCallsBuilder::from_calls(calls);
+        trace!("exchange payout to={:?} min_amount_out={} native_payout={}", recipient, min_amount_out, native_payout);
+        builder
+            .call(MulticallerCall::new_call(token_out, &AbiEncoderHelper::encode_erc20_transfer(deadline_router, U256::ZERO)))
+            .amount_from(StackSlot::Last, 0x24)
+            .add();
+        if self.unwrap_native_payout && native_payout {
+            // spendable as gas without a separate unwrap transaction
+            builder.add(MulticallerCall::new_call(deadline_router, &AbiEncoderHelper::encode_unwrap_weth9(min_amount_out, recipient)));
+        } else {
+            builder.add(MulticallerCall::new_call(
+                deadline_router,
+                &AbiEncoderHelper::encode_sweep_token(token_out, min_amount_out, recipient),
+            ));
+        }
+        Ok(builder.build()?)
+    }
+}
 
-        Ok((to, None, call_data, tips_vec))
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloy_sol_types::SolCall;
+    use loom_defi_abi::balancer::IVault;
+    use loom_defi_abi::uniswap2::IUniswapV2Pair;
+    use loom_defi_abi::uniswap_periphery::ISwapRouter02;
+    use loom_defi_abi::IERC20;
+    use loom_defi_pools::UniswapV2Pool;
+    use loom_types_entities::{CalculationResult, SwapAmountType, SwapLine, SwapPath, Token};
+    use std::sync::Arc;
+
+    fn backrun_swap() -> Swap {
+        let (token, middle) = (Arc::new(Token::new(Address::repeat_byte(1))), Arc::new(Token::new(Address::repeat_byte(2))));
+        let pools = vec![
+            UniswapV2Pool::new_with_data(
+                Address::repeat_byte(3),
+                token.get_address(),
+                middle.get_address(),
+                Address::ZERO,
+                U256::ZERO,
+                U256::ZERO,
+            ),
+            UniswapV2Pool::new_with_data(
+                Address::repeat_byte(4),
+                middle.get_address(),
+                token.get_address(),
+                Address::ZERO,
+                U256::ZERO,
+                U256::ZERO,
+            ),
+        ];
+        let mut swap_line = SwapLine::from(SwapPath::new(vec![token.clone(), middle, token], pools));
+        swap_line.amount_in = SwapAmountType::Set(U256::from(100));
+        swap_line.calculation_results =
+            vec![CalculationResult::new(U256::from(100), U256::from(200)), CalculationResult::new(U256::from(200), U256::from(110))];
+        Swap::BackrunSwapLine(swap_line)
+    }
+
+    #[test]
+    fn test_profit_payouts() {
+        let tips = |token: u8, profit: u64, min_change: u64| Tips {
+            token_in: Arc::new(Token::new(Address::repeat_byte(token))),
+            profit: U256::from(profit),
+            profit_eth: U256::from(profit),
+            tips: U256::ZERO,
+            min_change: U256::from(min_change),
+        };
+
+        // the gas and tips are kept, tokens without profit over them are not paid out
+        let payouts = MulticallerSwapEncoder::profit_payouts(&backrun_swap(), &[tips(1, 100, 40), tips(2, 40, 40)], None);
+        assert_eq!(payouts, vec![(Address::repeat_byte(1), U256::from(40))]);
+        assert!(MulticallerSwapEncoder::profit_payouts(&Swap::None, &[tips(1, 100, 40)], None).is_empty());
+    }
+
+    #[test]
+    fn test_encode_inventory_funded_swap_line() -> Result<()> {
+        let encoder = MulticallerSwapEncoder::default_with_address(Address::repeat_byte(0x11));
+        let pool_swaps = |calls: &MulticallerCalls| -> Vec<IUniswapV2Pair::swapCall> {
+            calls
+                .opcodes_vec
+                .iter()
+                .filter(|call| call.to == Address::repeat_byte(3) || call.to == Address::repeat_byte(4))
+                .filter_map(|call| IUniswapV2Pair::swapCall::abi_decode(&call.call_data, true).ok())
+                .collect()
+        };
+        let flash_loans = |calls: &MulticallerCalls| {
+            calls.opcodes_vec.iter().filter(|call| IVault::flashLoanCall::abi_decode(&call.call_data, true).is_ok()).count()
+        };
+
+        // the flash swap runs the rest of the path in the callback of the borrowing pool
+        let flash_calls = encoder.encode_swap_calls(&backrun_swap())?;
+        assert!(pool_swaps(&flash_calls).iter().any(|swap| !swap.data.is_empty()));
+
+        let Swap::BackrunSwapLine(mut swap_line) = backrun_swap() else { unreachable!() };
+        swap_line.funding = Some(FundingMode::Balance);
+        let calls = encoder.encode_swap_calls(&Swap::BackrunSwapLine(swap_line))?;
+        // both pools are swapped by the multicaller without a callback or a flash loan
+        let swaps = pool_swaps(&calls);
+        assert_eq!(swaps.len(), 2);
+        assert!(swaps.iter().all(|swap| swap.data.is_empty()));
+        assert_eq!(flash_loans(&calls), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_encode_revert_on_loss() -> Result<()> {
+        let multicaller = Address::repeat_byte(0x11);
+        let mut encoder = MulticallerSwapEncoder::default_with_address(multicaller);
+        let swap = backrun_swap();
+        let contains = |call_data: &Bytes, call: Bytes| call_data.windows(call.len()).any(|window| window == call.as_ref());
+
+        let (to, call_data) = encoder.encode_revert_on_loss(&swap, U256::from(5), 1_012)?;
+        assert_eq!(to, multicaller);
+        assert!(contains(&call_data, AbiEncoderHelper::encode_deadline_check(U256::from(1_012))));
+        assert!(contains(
+            &call_data,
+            AbiEncoderHelper::encode_multicaller_transfer_tips_no_payout(Address::repeat_byte(1), U256::from(5), U256::ZERO)
+        ));
+        // no tips are paid in the public mempool
+        let (_, _, bundle_call_data, _) = encoder.encode(swap.clone(), None, None, None, None, None, None)?;
+        assert_ne!(call_data, bundle_call_data);
+
+        assert!(encoder.encode_revert_on_loss(&swap, U256::ZERO, 1_012).is_err());
+        assert!(encoder.encode_revert_on_loss(&Swap::None, U256::from(5), 1_012).is_err());
+
+        // the mainnet router has no code on chains without a preset, the deadline would never be checked
+        encoder.set_chain_preset(ChainPreset::for_chain_id(137));
+        assert!(encoder.encode_revert_on_loss(&swap, U256::from(5), 1_012).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_encode_exchange_payout() -> Result<()> {
+        let multicaller = Address::repeat_byte(0x11);
+        let (token_out, recipient, min_amount_out) = (Address::repeat_byte(0x22), Address::repeat_byte(0x33), U256::from(1_000_000));
+        let encoder = MulticallerSwapEncoder::default_with_address(multicaller);
+        let deadline_router = encoder.deadline_router.unwrap();
+
+        // the output of the last hop
+        let last_hop = || {
+            let mut builder = MulticallerCallsBuilder::new();
+            builder.call(MulticallerCall::new_call(Address::repeat_byte(0x44), &Bytes::new())).push_result(0x0).add();
+            builder.build()
+        };
+        let calls = encoder.encode_exchange_payout(last_hop()?, token_out, false, recipient, min_amount_out)?;
+        assert_eq!(calls.len(), 3);
+
+        let transfer_call = calls.get(1).unwrap();
+        assert_eq!(transfer_call.to, token_out);
+        let transfer = IERC20::transferCall::abi_decode(&transfer_call.call_data, true)?;
+        assert_eq!(transfer.to, deadline_router);
+        // the transferred amount is the swap output, not the balance of the multicaller
+        assert!(transfer_call.call_stack.is_some());
+
+        let sweep_call = calls.get(2).unwrap();
+        assert_eq!(sweep_call.to, deadline_router);
+        let sweep = ISwapRouter02::sweepTokenCall::abi_decode(&sweep_call.call_data, true)?;
+        assert_eq!((sweep.token, sweep.amountMinimum, sweep.recipient), (token_out, min_amount_out, recipient));
+
+        // native payouts are swept too unless the encoder unwraps them
+        let calls = encoder.encode_exchange_payout(last_hop()?, token_out, true, recipient, min_amount_out)?;
+        assert!(ISwapRouter02::sweepTokenCall::abi_decode(&calls.get(2).unwrap().call_data, true).is_ok());
+
+        // only the swap output is unwrapped by the router, with the min out checked on the ETH paid out
+        let encoder = encoder.with_unwrap_native_payout(true);
+        let calls = encoder.encode_exchange_payout(last_hop()?, token_out, false, recipient, min_amount_out)?;
+        assert!(ISwapRouter02::sweepTokenCall::abi_decode(&calls.get(2).unwrap().call_data, true).is_ok());
+
+        let calls = encoder.encode_exchange_payout(last_hop()?, token_out, true, recipient, min_amount_out)?;
+        assert_eq!(calls.len(), 3);
+        let transfer_call = calls.get(1).unwrap();
+        assert_eq!(IERC20::transferCall::abi_decode(&transfer_call.call_data, true)?.to, deadline_router);
+        assert!(transfer_call.call_stack.is_some());
+        let unwrap_call = calls.get(2).unwrap();
+        assert_eq!(unwrap_call.to, deadline_router);
+        let unwrap = ISwapRouter02::unwrapWETH9Call::abi_decode(&unwrap_call.call_data, true)?;
+        assert_eq!((unwrap.amountMinimum, unwrap.recipient), (min_amount_out, recipient));
+        Ok(())
     }
 }
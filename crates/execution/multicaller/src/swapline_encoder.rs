@@ -1,24 +1,95 @@
 use std::sync::Arc;
 
-use alloy_primitives::{Address, U256};
+use alloy_primitives::{Address, Bytes, U256};
 use eyre::{eyre, Result};
 use tracing::trace;
 
 use crate::pool_abi_encoder::ProtocolAbiSwapEncoderTrait;
 use crate::pool_opcodes_encoder::{MulticallerOpcodesPayload, ProtocolSwapOpcodesEncoderV2, SwapOpcodesEncoderTrait};
-use crate::ProtocolABIEncoderV2;
-use loom_defi_abi::AbiEncoderHelper;
+use crate::{EncoderError, ProtocolABIEncoderV2};
+use alloy_sol_types::SolCall;
+use loom_defi_abi::{AbiEncoderHelper, IERC20};
 use loom_defi_address_book::TokenAddressEth;
 use loom_types_blockchain::LoomDataTypesEthereum;
-use loom_types_blockchain::{MulticallerCall, MulticallerCalls};
+use loom_types_blockchain::{CallType, MulticallerCall, MulticallerCalls, MulticallerCallsBuilder, StackSlot};
 use loom_types_entities::SwapAmountType::RelativeStack;
-use loom_types_entities::{PoolWrapper, SwapAmountType, SwapLine, Token};
+use loom_types_entities::{CallbackStyle, PoolWrapper, SwapAmountType, SwapLine, TipRecipient, Token};
+
+// Highest absolute stack slot a call can reference
+const MAX_ABSOLUTE_SLOT: u32 = 0x7;
+
+// Operations of calculation calls. They are evaluated on a stack of their own, the result is pushed to the multicaller stack
+const CALC_END: u8 = 0x00;
+// Push the value of absolute slot 0 of the multicaller stack, 0x02 to 0x07 push slots 1 to 6
+const CALC_LOAD_ABSOLUTE: u8 = 0x01;
+const CALC_LOAD_ABSOLUTE_MAX: u8 = 0x07;
+// Pop the last value of the multicaller stack
+const CALC_POP_LAST: u8 = 0x08;
+// First pushed value minus the second one
+const CALC_SUB: u8 = 0x1e;
+// Push the 32 bytes following the operation
+const CALC_PUSH32: u8 = 0x20;
+
+/// Calculation of the profit realized over the balance before the swap in absolute slot `before_slot` less `reserve`, from
+/// the balance after the swap popped from the stack. Below the reserve the result wraps and the transfer of it fails
+fn realized_profit_script(before_slot: u8, reserve: U256) -> Bytes {
+    let mut script = vec![CALC_POP_LAST, CALC_LOAD_ABSOLUTE + before_slot, CALC_SUB, CALC_PUSH32];
+    script.extend_from_slice(&reserve.to_be_bytes::<32>());
+    script.extend_from_slice(&[CALC_SUB, CALC_END]);
+    script.into()
+}
+
+/// Shift the absolute slots loaded by a calculation script by `slots`
+fn shift_calculation_slots(script: &Bytes, slots: u32) -> Result<Bytes> {
+    let mut shifted = script.to_vec();
+    let mut idx = 0;
+    while idx < shifted.len() {
+        match shifted[idx] {
+            CALC_END => break,
+            op @ CALC_LOAD_ABSOLUTE..=CALC_LOAD_ABSOLUTE_MAX => {
+                let op = op as u32 + slots;
+                if op > CALC_LOAD_ABSOLUTE_MAX as u32 {
+                    return Err(eyre!("CALCULATION_SLOT_OUT_OF_RANGE"));
+                }
+                shifted[idx] = op as u8;
+            }
+            CALC_PUSH32 => idx += 32,
+            // shifts by the next byte
+            0x1a | 0x1b => idx += 1,
+            0x08..=0x19 | 0x1c..=0x1f | 0x2a => {}
+            _ => return Err(eyre!("CALCULATION_NOT_SHIFTABLE")),
+        }
+        idx += 1;
+    }
+    Ok(shifted.into())
+}
+
+/// Shift the absolute stack references of the calls over `slots` values pushed in front of them. Calls inside flash
+/// callbacks run on the stack of the callback and are not affected
+fn shift_absolute_slots(calls: &mut MulticallerCalls, slots: u32) -> Result<()> {
+    for call in calls.opcodes_vec.iter_mut() {
+        for call_stack in [call.call_stack.as_mut(), call.return_stack.as_mut()].into_iter().flatten() {
+            if !call_stack.is_relative {
+                call_stack.stack_offset += slots;
+                if call_stack.stack_offset > MAX_ABSOLUTE_SLOT {
+                    return Err(eyre!("STACK_OFFSET_OUT_OF_RANGE"));
+                }
+            }
+        }
+        if call.call_type == CallType::CalculationCall {
+            call.call_data = shift_calculation_slots(&call.call_data, slots)?;
+        }
+    }
+    Ok(())
+}
 
 #[derive(Clone)]
 pub struct SwapLineEncoder {
     pub multicaller_address: Address,
     abi_encoder: Arc<dyn ProtocolAbiSwapEncoderTrait>,
     opcodes_encoder: Arc<dyn SwapOpcodesEncoderTrait>,
+    // reset the allowances given by the hops to zero after each hop
+    revoke_approvals: bool,
 }
 
 impl SwapLineEncoder {
@@ -27,14 +98,55 @@ impl SwapLineEncoder {
         abi_encoder: Arc<dyn ProtocolAbiSwapEncoderTrait>,
         opcodes_encoder: Arc<dyn SwapOpcodesEncoderTrait>,
     ) -> SwapLineEncoder {
-        SwapLineEncoder { multicaller_address, abi_encoder, opcodes_encoder }
+        SwapLineEncoder { multicaller_address, abi_encoder, opcodes_encoder, revoke_approvals: false }
     }
 
     pub fn default_with_address(multicaller_address: Address) -> SwapLineEncoder {
         let abi_encoder = Arc::new(ProtocolABIEncoderV2::default());
         let opcodes_encoder = Arc::new(ProtocolSwapOpcodesEncoderV2::default());
 
-        SwapLineEncoder { multicaller_address, abi_encoder, opcodes_encoder }
+        SwapLineEncoder { multicaller_address, abi_encoder, opcodes_encoder, revoke_approvals: false }
+    }
+
+    pub fn with_opcodes_encoder(self, opcodes_encoder: Arc<dyn SwapOpcodesEncoderTrait>) -> Self {
+        Self { opcodes_encoder, ..self }
+    }
+
+    /// Append `approve(spender, 0)` after every hop approving a spender, so the multicaller does not leave allowances
+    pub fn with_revoke_approvals(self, revoke_approvals: bool) -> Self {
+        Self { revoke_approvals, ..self }
+    }
+
+    /// Revoke the allowances given by the calls from `first_call` on. Approvals of a zero amount taken from the call data
+    /// are revokes already
+    fn revoke_approvals_from(&self, calls: &mut MulticallerCalls, first_call: usize) {
+        if !self.revoke_approvals {
+            return;
+        }
+        let mut revokes: Vec<MulticallerCall> = Vec::new();
+        for call in calls.opcodes_vec.iter().skip(first_call) {
+            let Ok(approve) = IERC20::approveCall::abi_decode(&call.call_data, false) else {
+                continue;
+            };
+            if approve.amount.is_zero() && call.call_stack.is_none() {
+                continue;
+            }
+            trace!("revoke approval token={:?}, spender={:?}", call.to, approve.spender);
+            revokes.push(MulticallerCall::new_call(call.to, &AbiEncoderHelper::encode_erc20_approve(approve.spender, U256::ZERO)));
+        }
+        for revoke in revokes {
+            calls.add(revoke);
+        }
+    }
+
+    /// Calculated out amount of the hop `idx` of a line calculated with its in amount
+    fn hop_amount_out(swap_path: &SwapLine<LoomDataTypesEthereum>, idx: usize) -> Result<U256> {
+        swap_path
+            .calculation_results
+            .get(idx)
+            .filter(|_| swap_path.calculation_results.len() == swap_path.pools().len())
+            .map(|calculation_result| calculation_result.amount_out)
+            .ok_or_else(|| EncoderError::HopNotCalculated { pool: swap_path.pools()[idx].get_pool_id() }.into())
     }
 
     pub fn encode_flash_swap_line_in_amount(
@@ -60,6 +172,7 @@ impl SwapLineEncoder {
 
             let amount_in = if pool_idx == swap_path.pools().len() - 1 { swap_path.amount_in } else { SwapAmountType::RelativeStack(0) };
 
+            let first_call = flash_swap_opcodes.len();
             self.opcodes_encoder.encode_flash_swap_in_amount_provided(
                 &mut flash_swap_opcodes,
                 self.abi_encoder.as_ref(),
@@ -71,6 +184,7 @@ impl SwapLineEncoder {
                 MulticallerOpcodesPayload::Opcodes(inside_opcodes),
                 self.multicaller_address,
             )?;
+            self.revoke_approvals_from(&mut flash_swap_opcodes, first_call);
 
             prev_pool = Some(flash_pool);
             inside_opcodes = flash_swap_opcodes.clone();
@@ -79,6 +193,13 @@ impl SwapLineEncoder {
         Ok(flash_swap_opcodes)
     }
 
+    /// Exact out swaps of consecutive pools paying their input inside the callback are chained as the `exactOutput` of the
+    /// Uniswap V3 router: the swap in the callback of the next pool sends the output straight to it
+    fn chains_callbacks(pool: &PoolWrapper, next_pool: &PoolWrapper) -> bool {
+        pool.get_class().capabilities().callback_style == CallbackStyle::UniswapV3
+            && next_pool.get_class().capabilities().callback_style == CallbackStyle::UniswapV3
+    }
+
     pub fn encode_flash_swap_line_out_amount(
         &self,
         swap_path: &SwapLine<LoomDataTypesEthereum>,
@@ -102,169 +223,38 @@ impl SwapLineEncoder {
 
             let amount_out = if pool_idx == pools.len() - 1 { swap_path.amount_out } else { SwapAmountType::RelativeStack(0) };
 
-            self.opcodes_encoder.encode_flash_swap_out_amount_provided(
-                &mut flash_swap_opcodes,
-                self.abi_encoder.as_ref(),
-                token_from_address,
-                token_to_address,
-                amount_out,
-                flash_pool.as_ref(),
-                next_pool.map(|v| v.as_ref()),
-                MulticallerOpcodesPayload::Opcodes(inside_opcodes),
-                self.multicaller_address,
-            )?;
-
-            inside_opcodes = flash_swap_opcodes.clone();
-
-            /*let swap_to = match next_pool {
-                Some(next_pool) => next_pool.get_address(),
-                None => self.multicaller_address,
-            };
-
-            match flash_pool.get_class() {
-                PoolClass::UniswapV2 => {
-                    let mut get_in_amount_opcode =
-                        MulticallerCall::new_internal_call(&AbiEncoderHelper::encode_multicaller_uni2_get_in_amount(
-                            token_from_address,
-                            token_to_address,
-                            flash_pool.get_address(),
-                            amount_out.unwrap_or_default(),
-                            flash_pool.get_fee(),
-                        ));
-
-                    if amount_out.is_not_set() {
-                        get_in_amount_opcode.set_call_stack(false, 0, 0x24, 0x20);
-                    }
-
-                    inside_opcodes.insert(get_in_amount_opcode);
-
-                    if pool_idx == 0 && swap_to != flash_pool.get_address() {
-                        trace!(
-                            "retflash transfer token={:?}, to={:?}, amount=stack_no_rel_1",
-                            token_from_address,
-                            flash_pool.get_address()
-                        );
-
-                        let mut transfer_opcode = MulticallerCall::new_call(
-                            token_from_address,
-                            &AbiEncoderHelper::encode_erc20_transfer(flash_pool.get_address(), U256::ZERO),
-                        );
-                        transfer_opcode.set_call_stack(false, 1, 0x24, 0x20);
-                        inside_opcodes.add(transfer_opcode);
-                    };
-
-                    if swap_to != self.multicaller_address {
-                        trace!("retflash transfer token={:?}, to={:?}, amount=stack_norel_0", token_to_address, swap_to);
-
-                        let mut transfer_opcode =
-                            MulticallerCall::new_call(token_to_address, &AbiEncoderHelper::encode_erc20_transfer(swap_to, U256::ZERO));
-                        transfer_opcode.set_call_stack(false, 0, 0x24, 0x20);
-                        inside_opcodes.add(transfer_opcode);
-                    }
-                }
-                PoolClass::UniswapV3 | PoolClass::Maverick | PoolClass::PancakeV3 => {
-                    if pool_idx == 0 {
-                        trace!("retflash transfer token={:?}, to={:?}, amount=stack_norel_1", token_from_address, flash_pool.get_address());
-                        let mut transfer_opcode = MulticallerCall::new_call(
-                            token_from_address,
-                            &AbiEncoderHelper::encode_erc20_transfer(flash_pool.get_address(), U256::ZERO),
-                        );
-                        transfer_opcode.set_call_stack(false, 1, 0x24, 0x20);
-
-                        inside_opcodes.add(transfer_opcode);
-                    }
-                }
-
-                _ => {
-                    return Err(eyre!("CANNOT_ENCODE_FLASH_CALL"));
-                }
-            }
-
-            let inside_call_bytes = OpcodesEncoderV2::pack_do_calls_data(&inside_opcodes)?;
-            flash_swap_opcodes = MulticallerCalls::new();
-
-            trace!("flash swap_to {:?}", swap_to);
-
-
-             */
-            /*match flash_pool.get_class() {
-                PoolClass::UniswapV2 => {
-                    trace!(
-                        "uniswap v2 swap out amount provided for pool={:?}, amount_out={:?} receiver={} inside_opcodes_len={}",
-                        flash_pool.get_address(),
-                        amount_out,
-                        self.multicaller_address,
-                        inside_opcodes.len()
-                    );
-                    let mut swap_opcode = MulticallerCall::new_call(
-                        flash_pool.get_address(),
-                        &self.abi_encoder.encode_swap_out_amount_provided(
-                            flash_pool.as_ref(),
-                            token_from_address,
-                            token_to_address,
-                            amount_out.unwrap_or_default(),
-                            self.multicaller_address,
-                            inside_call_bytes,
-                        )?,
-                    );
-
-                    if amount_out.is_not_set() {
-                        trace!("uniswap v2 amount not set");
-                        swap_opcode.set_call_stack(
-                            true,
-                            0,
-                            self.abi_encoder.swap_out_amount_offset(flash_pool.as_ref(), token_from_address, token_to_address).unwrap(),
-                            0x20,
-                        );
-                    }
-
-                    flash_swap_opcodes.add(swap_opcode);
-
-                    inside_opcodes = flash_swap_opcodes.clone();
-                }
-                PoolClass::UniswapV3 | PoolClass::PancakeV3 | PoolClass::Maverick => {
-                    trace!(
-                        "uniswap v3 swap out amount provided for pool={:?}, amount_out={:?} receiver={} inside_opcodes_len={}",
-                        flash_pool.get_address(),
-                        amount_out,
-                        swap_to,
-                        inside_opcodes.len()
-                    );
-                    let mut swap_opcode = MulticallerCall::new_call(
-                        flash_pool.get_address(),
-                        &self.abi_encoder.encode_swap_out_amount_provided(
-                            flash_pool.as_ref(),
-                            token_from_address,
-                            token_to_address,
-                            amount_out.unwrap_or_default(),
-                            swap_to,
-                            inside_call_bytes,
-                        )?,
-                    );
-
-                    if amount_out.is_not_set() {
-                        trace!("uniswap v3 swap out amount is not set");
-
-                        flash_swap_opcodes.add(MulticallerCall::new_calculation_call(&Bytes::from(vec![0x8, 0x2A, 0x00])));
-
-                        swap_opcode.set_call_stack(
-                            true,
-                            0,
-                            self.abi_encoder.swap_out_amount_offset(flash_pool.as_ref(), token_from_address, token_to_address).unwrap(),
-                            0x20,
-                        );
-                    };
-
-                    flash_swap_opcodes.add(swap_opcode);
-
-                    inside_opcodes = flash_swap_opcodes.clone();
-                }
-                _ => {
-                    return Err(eyre!("CANNOT_ENCODE_FLASH_CALL"));
-                }
+            let chained_next_pool = next_pool.filter(|next_pool| Self::chains_callbacks(flash_pool, next_pool));
+            let input_chained = pool_idx > 0 && Self::chains_callbacks(&pools[pool_idx - 1], flash_pool);
+
+            if chained_next_pool.is_some() || input_chained {
+                self.opcodes_encoder.encode_chained_flash_swap_out_amount_provided(
+                    &mut flash_swap_opcodes,
+                    self.abi_encoder.as_ref(),
+                    token_from_address,
+                    token_to_address,
+                    amount_out,
+                    flash_pool.as_ref(),
+                    chained_next_pool.map(|v| v.as_ref()),
+                    input_chained,
+                    MulticallerOpcodesPayload::Opcodes(inside_opcodes),
+                    self.multicaller_address,
+                )?;
+            } else {
+                self.opcodes_encoder.encode_flash_swap_out_amount_provided(
+                    &mut flash_swap_opcodes,
+                    self.abi_encoder.as_ref(),
+                    token_from_address,
+                    token_to_address,
+                    amount_out,
+                    flash_pool.as_ref(),
+                    next_pool.map(|v| v.as_ref()),
+                    MulticallerOpcodesPayload::Opcodes(inside_opcodes),
+                    self.multicaller_address,
+                )?;
             }
+            self.revoke_approvals_from(&mut flash_swap_opcodes, 0);
 
-             */
+            inside_opcodes = flash_swap_opcodes.clone();
         }
 
         Ok(flash_swap_opcodes)
@@ -288,7 +278,36 @@ impl SwapLineEncoder {
             let token_to_address = swap_path.tokens()[i + 1].get_address();
 
             let cur_pool = &swap_path.pools()[i].clone();
-            let next_pool: Option<&PoolWrapper> = if i < swap_path.pools().len() - 1 { Some(&swap_path.pools()[i + 1]) } else { funds_to };
+            // received amounts of tokens calling transfer hooks may differ from the calculated ones, they are swapped to
+            // the multicaller and the next swap spends the balance
+            let next_pool: Option<&PoolWrapper> = if i < swap_path.pools().len() - 1 {
+                (!swap_path.tokens()[i + 1].has_transfer_hook()).then(|| &swap_path.pools()[i + 1])
+            } else {
+                funds_to
+            };
+
+            if i > 0 && swap_path.tokens()[i].has_transfer_hook() {
+                trace!("balance of transfer hook token={:?}", token_from_address);
+                let mut builder = MulticallerCallsBuilder::new();
+                builder
+                    .call(MulticallerCall::new_static_call(
+                        token_from_address,
+                        &AbiEncoderHelper::encode_erc20_balance_of(self.multicaller_address),
+                    ))
+                    .push_result(0x0)
+                    .add();
+                if let Some(funds_needed_at) = cur_pool.preswap_requirement().address() {
+                    builder
+                        .call(MulticallerCall::new_call(
+                            token_from_address,
+                            &AbiEncoderHelper::encode_erc20_transfer(funds_needed_at, U256::ZERO),
+                        ))
+                        .amount_from(StackSlot::Last, 0x24)
+                        .add();
+                }
+                swap_opcodes.merge(builder.build()?);
+                amount_in = RelativeStack(0);
+            }
 
             trace!(
                 "encode_swap_line_in_amount for from={} to={} pool={}, next_pool={:?} funds_to {:?}",
@@ -374,6 +393,13 @@ impl SwapLineEncoder {
 
             trace!("swap_to {:?}", swap_to);
 
+            let payload = if cur_pool.get_class().capabilities().quoted_min_out {
+                MulticallerOpcodesPayload::AmountOut(Self::hop_amount_out(swap_path, i)?)
+            } else {
+                MulticallerOpcodesPayload::Empty
+            };
+
+            let first_call = swap_opcodes.len();
             self.opcodes_encoder.encode_swap_in_amount_provided(
                 &mut swap_opcodes,
                 self.abi_encoder.as_ref(),
@@ -382,15 +408,18 @@ impl SwapLineEncoder {
                 amount_in,
                 cur_pool.as_ref(),
                 next_pool.map(|next_pool| next_pool.as_ref()),
-                MulticallerOpcodesPayload::Empty,
+                payload,
                 self.multicaller_address,
             )?;
+            self.revoke_approvals_from(&mut swap_opcodes, first_call);
 
             amount_in = RelativeStack(0);
         }
         Ok(swap_opcodes)
     }
 
+    /// Check the balance left for the tips and pay them to the recipient. Tips paid to the block coinbase are paid by the
+    /// multicaller with the rest of the balance paid out to `to`, tips paid to an address keep the rest in the multicaller
     pub fn encode_tips(
         &self,
         swap_opcodes: MulticallerCalls,
@@ -398,9 +427,25 @@ impl SwapLineEncoder {
         min_balance: U256,
         tips: U256,
         to: Address,
+        recipient: TipRecipient,
     ) -> Result<MulticallerCalls> {
         let mut tips_opcodes = swap_opcodes.clone();
 
+        if let TipRecipient::Address(recipient) = recipient {
+            trace!("encode_tips recipient={:?}", recipient);
+            tips_opcodes.add(MulticallerCall::new_internal_call(&AbiEncoderHelper::encode_multicaller_transfer_tips_no_payout(
+                token_address,
+                min_balance,
+                U256::ZERO,
+            )));
+            // tips of other tokens are paid from the value of the transaction
+            if token_address == TokenAddressEth::WETH {
+                tips_opcodes.add(MulticallerCall::new_call(token_address, &AbiEncoderHelper::encode_weth_withdraw(tips)));
+            }
+            tips_opcodes.add(MulticallerCall::new_call_with_value(recipient, &Bytes::new(), tips));
+            return Ok(tips_opcodes);
+        }
+
         let call_data = if token_address == TokenAddressEth::WETH {
             trace!("encode_multicaller_transfer_tips_weth");
             AbiEncoderHelper::encode_multicaller_transfer_tips_weth(min_balance, tips, to)
@@ -411,4 +456,222 @@ impl SwapLineEncoder {
         tips_opcodes.add(MulticallerCall::new_internal_call(&call_data));
         Ok(tips_opcodes)
     }
+
+    /// Pay the profit realized in each token over the multicaller balance before the swap, less the reserve kept for the
+    /// gas and tips, to the profit receiver. The balances before the swap are pushed in front of the swap calls and their
+    /// absolute stack references are shifted over them. A realized profit below the reserve fails the transfer and reverts
+    /// the swap, the inventory of the multicaller is never paid out
+    pub fn encode_profit_payout(
+        &self,
+        mut swap_opcodes: MulticallerCalls,
+        payouts: &[(Address, U256)],
+        receiver: Address,
+    ) -> Result<MulticallerCalls> {
+        if payouts.len() > CALC_LOAD_ABSOLUTE_MAX as usize {
+            return Err(eyre!("TOO_MANY_PAYOUT_TOKENS"));
+        }
+        shift_absolute_slots(&mut swap_opcodes, payouts.len() as u32)?;
+
+        let balance_of = AbiEncoderHelper::encode_erc20_balance_of(self.multicaller_address);
+        let mut builder = MulticallerCallsBuilder::new();
+        for (token_address, _) in payouts.iter() {
+            builder.call(MulticallerCall::new_static_call(*token_address, &balance_of)).push_result(0x0).add();
+        }
+        builder.merge(swap_opcodes);
+
+        // the calculation pops the balance after the swap, the builder counts one slot more than the stack holds
+        for (before_slot, (token_address, reserve)) in payouts.iter().enumerate() {
+            trace!("encode_profit_payout token={:?} reserve={} receiver={:?}", token_address, reserve, receiver);
+            builder.call(MulticallerCall::new_static_call(*token_address, &balance_of)).push_result(0x0).add();
+            builder.add(MulticallerCall::new_calculation_call(&realized_profit_script(before_slot as u8, *reserve)));
+            builder
+                .call(MulticallerCall::new_call(*token_address, &AbiEncoderHelper::encode_erc20_transfer(receiver, U256::ZERO)))
+                .amount_from(StackSlot::Last, 0x24)
+                .add();
+        }
+        Ok(builder.build()?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use loom_defi_pools::UniswapV2Pool;
+    use loom_types_entities::{CalculationResult, MockPool, SwapPath};
+
+    #[test]
+    fn test_revoke_approvals_from() {
+        let (token, pool) = (Address::repeat_byte(0x11), Address::repeat_byte(0x22));
+        let encoder = SwapLineEncoder::default_with_address(Address::repeat_byte(0x33));
+
+        // the approved amount is read from the stack
+        let mut approve = MulticallerCall::new_call(token, &AbiEncoderHelper::encode_erc20_approve(pool, U256::ZERO));
+        approve.set_call_stack(true, 0, 0x24, 0x20);
+        let swap = MulticallerCall::new_call(pool, &Bytes::new());
+        let revoke = MulticallerCall::new_call(token, &AbiEncoderHelper::encode_erc20_approve(pool, U256::ZERO));
+
+        let mut calls = MulticallerCalls::new();
+        calls.add(approve).add(swap);
+        encoder.revoke_approvals_from(&mut calls, 0);
+        assert_eq!(calls.len(), 2);
+
+        let encoder = encoder.with_revoke_approvals(true);
+        encoder.revoke_approvals_from(&mut calls, 0);
+        assert_eq!(calls.len(), 3);
+        let appended = calls.get(2).unwrap();
+        assert_eq!((appended.to, &appended.call_data), (token, &revoke.call_data));
+        assert!(appended.call_stack.is_none());
+
+        // revokes and calls before the hop are not revoked again
+        encoder.revoke_approvals_from(&mut calls, 1);
+        assert_eq!(calls.len(), 3);
+    }
+
+    #[test]
+    fn test_hop_amount_out() {
+        let (token0, token1, token2) = (Address::repeat_byte(1), Address::repeat_byte(2), Address::repeat_byte(3));
+        let path = SwapPath::new(
+            vec![Token::new(token0), Token::new(token1), Token::new(token2)],
+            vec![MockPool::new(token0, token1, Address::repeat_byte(4)), MockPool::new(token1, token2, Address::repeat_byte(5))],
+        );
+        let mut swap_line = SwapLine::from(path);
+
+        let error = SwapLineEncoder::hop_amount_out(&swap_line, 1).unwrap_err();
+        assert!(matches!(EncoderError::from_report(&error), Some(EncoderError::HopNotCalculated { .. })));
+
+        // lines cut at crossing hops keep the results of the remaining hops only
+        swap_line.calculation_results = vec![CalculationResult::new(U256::from(100), U256::from(200))];
+        assert!(SwapLineEncoder::hop_amount_out(&swap_line, 0).is_err());
+
+        swap_line.calculation_results.push(CalculationResult::new(U256::from(200), U256::from(300)));
+        assert_eq!(SwapLineEncoder::hop_amount_out(&swap_line, 1).unwrap(), U256::from(300));
+    }
+
+    fn hook_swap_line(transfer_hook: bool) -> SwapLine<LoomDataTypesEthereum> {
+        let (token, mut middle) = (Token::new(Address::repeat_byte(1)), Token::new(Address::repeat_byte(2)));
+        if transfer_hook {
+            middle.set_transfer_hook();
+        }
+        let (token, middle) = (Arc::new(token), Arc::new(middle));
+        let pools = vec![
+            UniswapV2Pool::new_with_data(
+                Address::repeat_byte(3),
+                token.get_address(),
+                middle.get_address(),
+                Address::ZERO,
+                U256::ZERO,
+                U256::ZERO,
+            ),
+            UniswapV2Pool::new_with_data(
+                Address::repeat_byte(4),
+                middle.get_address(),
+                token.get_address(),
+                Address::ZERO,
+                U256::ZERO,
+                U256::ZERO,
+            ),
+        ];
+        let mut swap_line = SwapLine::from(SwapPath::new(vec![token.clone(), middle, token], pools));
+        swap_line.amount_in = SwapAmountType::Set(U256::from(100));
+        swap_line.calculation_results =
+            vec![CalculationResult::new(U256::from(100), U256::from(200)), CalculationResult::new(U256::from(200), U256::from(110))];
+        swap_line
+    }
+
+    #[test]
+    fn test_profit_payout() -> Result<()> {
+        let multicaller = Address::repeat_byte(0x33);
+        let (token, receiver, pool, reserve) =
+            (Address::repeat_byte(0x11), Address::repeat_byte(0x22), Address::repeat_byte(0x44), U256::from(1_000));
+        let encoder = SwapLineEncoder::default_with_address(multicaller);
+
+        // swap reading the amount pushed by its first call
+        let mut builder = MulticallerCallsBuilder::new();
+        builder.call(MulticallerCall::new_static_call(pool, &Bytes::new())).push_result(0x0).add();
+        builder.call(MulticallerCall::new_call(pool, &Bytes::from(vec![0; 0x44]))).amount_from(StackSlot::Absolute(0), 0x24).add();
+        builder.add(MulticallerCall::new_calculation_call(&Bytes::from(vec![0x2, 0x2A, 0x00])));
+
+        let calls = encoder.encode_profit_payout(builder.build()?, &[(token, reserve)], receiver)?;
+        calls.validate()?;
+        assert_eq!(calls.len(), 7);
+
+        // the balance before the swap takes the first slot, the swap references are shifted over it
+        let balance_of = AbiEncoderHelper::encode_erc20_balance_of(multicaller);
+        let before = calls.get(0).unwrap();
+        assert_eq!((before.call_type.clone(), before.to, &before.call_data), (CallType::StaticCall, token, &balance_of));
+        assert_eq!(calls.get(2).unwrap().call_stack.as_ref().unwrap().stack_offset, 1);
+        assert_eq!(calls.get(3).unwrap().call_data, Bytes::from(vec![0x3, 0x2A, 0x00]));
+
+        let after = calls.get(4).unwrap();
+        assert_eq!((after.to, &after.call_data), (token, &balance_of));
+        let script = &calls.get(5).unwrap().call_data;
+        assert_eq!(script[..4], [CALC_POP_LAST, CALC_LOAD_ABSOLUTE, CALC_SUB, CALC_PUSH32]);
+        assert_eq!(U256::from_be_slice(&script[4..36]), reserve);
+        assert_eq!(script[36..], [CALC_SUB, CALC_END]);
+
+        // the realized profit is transferred
+        let transfer = calls.get(6).unwrap();
+        assert_eq!((transfer.to, IERC20::transferCall::abi_decode(&transfer.call_data, true)?.to), (token, receiver));
+        let amount_stack = transfer.call_stack.as_ref().unwrap();
+        assert!(amount_stack.is_relative && amount_stack.stack_offset == 0 && amount_stack.data_offset == 0x24);
+
+        // slots above the encodable range are rejected instead of reverting on chain
+        assert!(shift_calculation_slots(&Bytes::from(vec![0x7, 0x00]), 1).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_encode_tips() -> Result<()> {
+        let encoder = SwapLineEncoder::default_with_address(Address::repeat_byte(0x33));
+        let (token, to, payment) = (Address::repeat_byte(0x11), Address::repeat_byte(0x22), Address::repeat_byte(0x42));
+        let (min_balance, tips) = (U256::from(1_000), U256::from(100));
+        let mut swap = MulticallerCalls::new();
+        swap.add(MulticallerCall::new_call(Address::repeat_byte(0x44), &Bytes::new()));
+
+        // coinbase tips are paid by the multicaller together with the payout to the sender
+        let calls = encoder.encode_tips(swap.clone(), token, min_balance, tips, to, TipRecipient::Coinbase)?;
+        assert_eq!(calls.len(), 2);
+        let transfer_tips = AbiEncoderHelper::encode_multicaller_transfer_tips(token, min_balance, tips, to);
+        assert_eq!((calls.get(1).unwrap().call_type.clone(), &calls.get(1).unwrap().call_data), (CallType::InternalCall, &transfer_tips));
+
+        // tips paid to an address check the balance without a payout and are sent from the value of the transaction
+        let calls = encoder.encode_tips(swap.clone(), token, min_balance, tips, to, TipRecipient::Address(payment))?;
+        assert_eq!(calls.len(), 3);
+        let check_balance = AbiEncoderHelper::encode_multicaller_transfer_tips_no_payout(token, min_balance, U256::ZERO);
+        assert_eq!((calls.get(1).unwrap().call_type.clone(), &calls.get(1).unwrap().call_data), (CallType::InternalCall, &check_balance));
+        let payment_call = calls.get(2).unwrap();
+        assert_eq!((payment_call.to, payment_call.value, payment_call.call_data.is_empty()), (payment, Some(tips), true));
+
+        // WETH tips are unwrapped before they are sent
+        let calls = encoder.encode_tips(swap, TokenAddressEth::WETH, min_balance, tips, to, TipRecipient::Address(payment))?;
+        assert_eq!(calls.len(), 4);
+        let withdraw = calls.get(2).unwrap();
+        assert_eq!((withdraw.to, &withdraw.call_data), (TokenAddressEth::WETH, &AbiEncoderHelper::encode_weth_withdraw(tips)));
+        assert_eq!((calls.get(3).unwrap().to, calls.get(3).unwrap().value), (payment, Some(tips)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_transfer_hook_balance() -> Result<()> {
+        let multicaller = Address::repeat_byte(0x33);
+        let encoder = SwapLineEncoder::default_with_address(multicaller);
+        let middle_balance = |calls: &MulticallerCalls| {
+            (0..calls.len())
+                .filter_map(|idx| calls.get(idx))
+                .any(|call| call.to == Address::repeat_byte(2) && call.call_data == AbiEncoderHelper::encode_erc20_balance_of(multicaller))
+        };
+
+        // the first hop swaps directly to the next pool
+        let calls = encoder.encode_swap_line_in_amount(&hook_swap_line(false), None)?;
+        assert!(!middle_balance(&calls));
+
+        // with a hook the first hop swaps to the multicaller and the received balance is transferred to the next pool
+        let calls = encoder.encode_swap_line_in_amount(&hook_swap_line(true), None)?;
+        assert!(middle_balance(&calls));
+        let transfer = AbiEncoderHelper::encode_erc20_transfer(Address::repeat_byte(4), U256::ZERO);
+        assert!((0..calls.len())
+            .filter_map(|idx| calls.get(idx))
+            .any(|call| call.to == Address::repeat_byte(2) && call.call_data == transfer));
+        Ok(())
+    }
 }
@@ -1,33 +1,52 @@
+use std::collections::BTreeMap;
+
 use alloy_primitives::{Address, Bytes, U256};
-use eyre::Result;
-use lazy_static::lazy_static;
+use eyre::{OptionExt, Result};
 use tracing::trace;
 
+use crate::gas_golf::warm_access_order;
 use crate::opcodes_encoder::{OpcodesEncoder, OpcodesEncoderV2};
-use crate::SwapLineEncoder;
+use crate::{EncoderError, SwapLineEncoder};
 use loom_defi_abi::AbiEncoderHelper;
 use loom_types_blockchain::LoomDataTypesEthereum;
 use loom_types_blockchain::{MulticallerCall, MulticallerCalls};
-use loom_types_entities::{SwapAmountType, SwapStep};
-
-lazy_static! {
-    static ref BALANCER_VAULT_ADDRESS: Address = "0xBA12222222228d8Ba445958a75a0704d566BF2C8".parse().unwrap();
-}
+use loom_types_entities::{ChainPreset, FlashLoanProvider, FundingMode, SwapAmountType, SwapLine, SwapStep, TipRecipient};
 
 #[derive(Clone)]
 pub struct SwapStepEncoder {
     pub multicaller_address: Address,
     pub swap_line_encoder: SwapLineEncoder,
+    // reorder independent swap lines of a step to reuse warm addresses
+    warm_access_order: bool,
+    flash_loan: Option<FlashLoanProvider>,
 }
 
 impl SwapStepEncoder {
     pub fn new(multicaller_address: Address, swap_line_encoder: SwapLineEncoder) -> Self {
-        Self { multicaller_address, swap_line_encoder }
+        Self { multicaller_address, swap_line_encoder, warm_access_order: false, flash_loan: ChainPreset::default().flash_loan }
     }
 
     pub fn default_with_address(multicaller_address: Address) -> Self {
         let swap_line_encoder = SwapLineEncoder::default_with_address(multicaller_address);
-        Self { multicaller_address, swap_line_encoder }
+        Self::new(multicaller_address, swap_line_encoder)
+    }
+
+    pub fn with_warm_access_order(self, warm_access_order: bool) -> Self {
+        Self { warm_access_order, ..self }
+    }
+
+    /// Lender of the flash loans funding swaps without a flash swap, the Balancer vault by default
+    pub fn with_flash_loan(self, flash_loan: Option<FlashLoanProvider>) -> Self {
+        Self { flash_loan, ..self }
+    }
+
+    /// Swap lines of a step in encoding order
+    fn ordered_swap_lines<'a>(&self, swap_lines: &'a [SwapLine<LoomDataTypesEthereum>]) -> Vec<&'a SwapLine<LoomDataTypesEthereum>> {
+        if self.warm_access_order {
+            warm_access_order(swap_lines)
+        } else {
+            swap_lines.iter().collect()
+        }
     }
 
     pub fn get_contract_address(&self) -> Address {
@@ -48,11 +67,13 @@ impl SwapStepEncoder {
         min_balance: U256,
         tips: U256,
         funds_to: Address,
+        recipient: TipRecipient,
     ) -> Result<MulticallerCalls> {
-        self.swap_line_encoder.encode_tips(swap_opcodes, token_address, min_balance, tips, funds_to)
+        self.swap_line_encoder.encode_tips(swap_opcodes, token_address, min_balance, tips, funds_to, recipient)
     }
 
-    pub fn encode_balancer_flash_loan(&self, steps: Vec<SwapStep<LoomDataTypesEthereum>>) -> Result<MulticallerCalls> {
+    /// Fund the steps by the flash loan of the chain
+    pub fn encode_flash_loan(&self, steps: Vec<SwapStep<LoomDataTypesEthereum>>) -> Result<MulticallerCalls> {
         let flash_funds_to = self.multicaller_address;
 
         let mut swap_opcodes = MulticallerCalls::new();
@@ -72,7 +93,7 @@ impl SwapStepEncoder {
             if swap.swap_line_vec().len() == 1 {
                 swap_opcodes.merge(self.swap_line_encoder.encode_swap_line_in_amount(swap.swap_line_vec().first().unwrap(), None)?);
             } else {
-                for swap_path in swap.swap_line_vec().iter() {
+                for swap_path in self.ordered_swap_lines(swap.swap_line_vec()) {
                     let opcodes = self.swap_line_encoder.encode_swap_line_in_amount(swap_path, None)?;
                     let call_bytes = OpcodesEncoderV2::pack_do_calls(&opcodes)?;
                     swap_opcodes.add(MulticallerCall::new_call(self.multicaller_address, &call_bytes));
@@ -80,14 +101,51 @@ impl SwapStepEncoder {
             }
         }
 
-        let inside_call_bytes = OpcodesEncoderV2::pack_do_calls_data(&swap_opcodes)?;
+        self.wrap_flash_loan(BTreeMap::from([(token.get_address(), in_amount)]), swap_opcodes)
+    }
 
-        let mut flash_opcodes = MulticallerCalls::new();
+    /// Encode independent swap lines into one call list. Borrowed input tokens are funded by a single flash loan with
+    /// one amount per token shared by the lines swapping it, inventory funded lines use the multicaller balance
+    pub fn encode_multiple_swap_lines(&self, swap_lines: &[SwapLine<LoomDataTypesEthereum>]) -> Result<MulticallerCalls> {
+        // sorted by address as required by the vault
+        let mut borrowed: BTreeMap<Address, U256> = BTreeMap::new();
+        for swap_line in swap_lines.iter().filter(|swap_line| swap_line.funding != Some(FundingMode::Balance)) {
+            let token = swap_line.get_first_token().ok_or(EncoderError::EmptySwapLine)?;
+            let amount_in = swap_line.amount_in.unwrap();
+            let amount = borrowed.entry(token.get_address()).or_default();
+            *amount = amount.checked_add(amount_in).ok_or_eyre("AMOUNT_OVERFLOW")?;
+        }
 
+        let mut swap_opcodes = MulticallerCalls::new();
+        for swap_line in self.ordered_swap_lines(swap_lines) {
+            let opcodes = self.swap_line_encoder.encode_swap_line_in_amount(swap_line, None)?;
+            let call_bytes = OpcodesEncoderV2::pack_do_calls(&opcodes)?;
+            swap_opcodes.add(MulticallerCall::new_call(self.multicaller_address, &call_bytes));
+        }
+
+        if borrowed.is_empty() {
+            return Ok(swap_opcodes);
+        }
+
+        self.wrap_flash_loan(borrowed, swap_opcodes)
+    }
+
+    /// Call the lender to borrow the amounts of the tokens and run the swap calls in its callback
+    fn wrap_flash_loan(&self, borrowed: BTreeMap<Address, U256>, swap_opcodes: MulticallerCalls) -> Result<MulticallerCalls> {
+        let flash_loan = self.flash_loan.ok_or(EncoderError::NoFlashLoanProvider)?;
+
+        // the multicaller runs the calls passed to the Balancer callback only, other lenders would revert in theirs
+        if flash_loan.mode != FundingMode::BalancerFlashLoan {
+            return Err(EncoderError::UnsupportedFlashLoan { mode: flash_loan.mode }.into());
+        }
+
+        let inside_call_bytes = OpcodesEncoderV2::pack_do_calls_data(&swap_opcodes)?;
+        let (tokens, amounts) = borrowed.into_iter().unzip();
         let flash_call_data =
-            AbiEncoderHelper::encode_balancer_flashloan(token.get_address(), in_amount, inside_call_bytes, self.multicaller_address);
+            AbiEncoderHelper::encode_balancer_flashloan_tokens(tokens, amounts, inside_call_bytes, self.multicaller_address);
 
-        flash_opcodes.add(MulticallerCall::new_call(*BALANCER_VAULT_ADDRESS, &flash_call_data));
+        let mut flash_opcodes = MulticallerCalls::new();
+        flash_opcodes.add(MulticallerCall::new_call(flash_loan.lender, &flash_call_data));
 
         Ok(flash_opcodes)
     }
@@ -112,7 +170,7 @@ impl SwapStepEncoder {
             swap_opcodes.merge(self.swap_line_encoder.encode_swap_line_in_amount(swap_step.swap_line_vec().first().unwrap(), None)?);
         } else {
             trace!("swap.swap_line_vec().len() != 1");
-            for swap_path in swap_step.swap_line_vec().iter() {
+            for swap_path in self.ordered_swap_lines(swap_step.swap_line_vec()) {
                 let opcodes = self.swap_line_encoder.encode_swap_line_in_amount(swap_path, None)?;
                 let call_bytes = OpcodesEncoderV2::pack_do_calls(&opcodes)?;
                 swap_opcodes.add(MulticallerCall::new_call(self.multicaller_address, &call_bytes));
@@ -148,7 +206,7 @@ impl SwapStepEncoder {
                     .encode_swap_line_in_amount(swap_step.swap_line_vec().first().unwrap(), flash_step.get_first_pool())?,
             );
         } else {
-            for swap_path in swap_step.swap_line_vec().iter() {
+            for swap_path in self.ordered_swap_lines(swap_step.swap_line_vec()) {
                 let opcodes = self.swap_line_encoder.encode_swap_line_in_amount(swap_path, None)?;
                 let call_bytes = OpcodesEncoderV2::pack_do_calls(&opcodes)?;
                 swap_opcodes.add(MulticallerCall::new_call(self.multicaller_address, &call_bytes));
@@ -170,6 +228,7 @@ impl SwapStepEncoder {
     }
 
     pub fn to_call_data(&self, opcodes: &MulticallerCalls) -> Result<(Address, Bytes)> {
+        opcodes.validate()?;
         let call_data = OpcodesEncoderV2::pack_do_calls(opcodes)?;
         Ok((self.multicaller_address, call_data))
     }
@@ -186,8 +245,8 @@ impl SwapStepEncoder {
             trace!("encode_swap_steps -> sp1.can_flash_swap()");
             self.encode_out_amount(sp0.clone(), sp1.clone())
         } else {
-            trace!("encode_swap_steps -> encode_balancer_flash_loan");
-            self.encode_balancer_flash_loan(vec![sp0.clone(), sp1.clone()])
+            trace!("encode_swap_steps -> encode_flash_loan");
+            self.encode_flash_loan(vec![sp0.clone(), sp1.clone()])
         }
     }
 
@@ -201,3 +260,132 @@ impl SwapStepEncoder {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::RevmMulticaller;
+    use alloy_sol_types::SolCall;
+    use loom_defi_abi::balancer::IVault;
+    use loom_defi_abi::IMultiCaller;
+    use loom_defi_address_book::PeripheryAddress;
+    use loom_defi_pools::UniswapV2Pool;
+    use loom_evm_utils::NWETH;
+    use loom_types_entities::{SwapPath, Token};
+    use revm::DatabaseRef;
+    use std::sync::Arc;
+
+    fn swap_line(token: Address, pool: u8, amount_in: u64, funding: Option<FundingMode>) -> SwapLine<LoomDataTypesEthereum> {
+        let (token, middle) = (Arc::new(Token::new(token)), Arc::new(Token::new(Address::repeat_byte(0x01))));
+        let pools = vec![
+            UniswapV2Pool::new_with_data(
+                Address::repeat_byte(pool),
+                token.get_address(),
+                middle.get_address(),
+                Address::ZERO,
+                U256::ZERO,
+                U256::ZERO,
+            ),
+            UniswapV2Pool::new_with_data(
+                Address::repeat_byte(pool + 1),
+                middle.get_address(),
+                token.get_address(),
+                Address::ZERO,
+                U256::ZERO,
+                U256::ZERO,
+            ),
+        ];
+        let mut swap_line = SwapLine::from(SwapPath::new(vec![token.clone(), middle, token], pools));
+        swap_line.amount_in = SwapAmountType::Set(U256::from(amount_in));
+        swap_line.funding = funding;
+        swap_line
+    }
+
+    #[test]
+    fn test_encode_multiple_swap_lines() -> Result<()> {
+        let multicaller = Address::repeat_byte(0x11);
+        let (token_a, token_b) = (Address::repeat_byte(0x22), Address::repeat_byte(0x33));
+        let swap_lines =
+            vec![swap_line(token_b, 0x40, 300, None), swap_line(token_a, 0x50, 100, None), swap_line(token_b, 0x60, 200, None)];
+
+        // one flash loan of the chain lender borrowing the summed amount of every input token, sorted by address
+        let encoder = SwapStepEncoder::default_with_address(multicaller);
+        let calls = encoder.encode_multiple_swap_lines(&swap_lines)?;
+        assert_eq!(calls.len(), 1);
+        let flash_call = calls.get(0).unwrap();
+        assert_eq!(flash_call.to, PeripheryAddress::BALANCER_VAULT);
+        let flash_loan = IVault::flashLoanCall::abi_decode(&flash_call.call_data, true)?;
+        assert_eq!(flash_loan.tokens, vec![token_a, token_b]);
+        assert_eq!(flash_loan.amounts, vec![U256::from(100), U256::from(500)]);
+        assert_eq!(flash_loan.recipient, multicaller);
+
+        // inventory funded lines are not borrowed, without borrowed tokens every line is a call of the multicaller
+        let swap_lines = vec![swap_line(token_a, 0x50, 100, Some(FundingMode::Balance)), swap_line(token_b, 0x60, 200, None)];
+        let flash_loan =
+            IVault::flashLoanCall::abi_decode(&encoder.encode_multiple_swap_lines(&swap_lines)?.get(0).unwrap().call_data, true)?;
+        assert_eq!((flash_loan.tokens, flash_loan.amounts), (vec![token_b], vec![U256::from(200)]));
+
+        let error = encoder.clone().with_flash_loan(None).encode_multiple_swap_lines(&swap_lines).unwrap_err();
+        assert_eq!(EncoderError::from_report(&error), Some(&EncoderError::NoFlashLoanProvider));
+
+        let swap_lines =
+            vec![swap_line(token_a, 0x50, 100, Some(FundingMode::Balance)), swap_line(token_b, 0x60, 200, Some(FundingMode::Balance))];
+        let calls = encoder.with_flash_loan(None).encode_multiple_swap_lines(&swap_lines)?;
+        assert_eq!(calls.len(), 2);
+        assert!((0..calls.len()).all(|idx| calls.get(idx).unwrap().to == multicaller));
+        Ok(())
+    }
+
+    #[test]
+    fn test_wrap_flash_loan() -> Result<()> {
+        let multicaller = Address::repeat_byte(0x11);
+        let (token, amount) = (Address::repeat_byte(0x22), U256::from(1_000_000));
+        let encoder = SwapStepEncoder::default_with_address(multicaller);
+
+        let calls = encoder.wrap_flash_loan(BTreeMap::from([(token, amount)]), MulticallerCalls::new())?;
+        let flash_call = calls.get(0).unwrap();
+        assert_eq!(flash_call.to, PeripheryAddress::BALANCER_VAULT);
+        assert!(flash_call.call_data.starts_with(&IVault::flashLoanCall::SELECTOR));
+
+        // the multicaller has no callback for the other lenders
+        let lender = Address::repeat_byte(0x33);
+        let aave = FlashLoanProvider { mode: FundingMode::AaveFlashLoan, lender };
+        let error = encoder
+            .clone()
+            .with_flash_loan(Some(aave))
+            .wrap_flash_loan(BTreeMap::from([(token, amount)]), MulticallerCalls::new())
+            .unwrap_err();
+        assert_eq!(EncoderError::from_report(&error), Some(&EncoderError::UnsupportedFlashLoan { mode: FundingMode::AaveFlashLoan }));
+
+        let encoder = encoder.with_flash_loan(None);
+        let error = encoder.wrap_flash_loan(BTreeMap::from([(token, amount)]), MulticallerCalls::new()).unwrap_err();
+        assert_eq!(EncoderError::from_report(&error), Some(&EncoderError::NoFlashLoanProvider));
+        Ok(())
+    }
+
+    #[test]
+    fn test_flash_loan_callback() -> Result<()> {
+        let one_eth = NWETH::get_exp();
+        let mut multicaller = RevmMulticaller::new().with_balance(one_eth);
+        let (token, amount) = (Address::repeat_byte(0x22), U256::from(1_000_000));
+        let recipient = Address::repeat_byte(0x33);
+        let mut swap_opcodes = MulticallerCalls::new();
+        swap_opcodes.add(MulticallerCall::new_call_with_value(recipient, &Bytes::new(), one_eth / U256::from(4)));
+
+        let encoder = SwapStepEncoder::default_with_address(multicaller.address());
+        let calls = encoder.wrap_flash_loan(BTreeMap::from([(token, amount)]), swap_opcodes)?;
+        let flash_call = calls.get(0).unwrap();
+        let flash_loan = IVault::flashLoanCall::abi_decode(&flash_call.call_data, true)?;
+
+        // the vault lends and calls back the recipient with the user data, the multicaller runs the swap calls passed in it
+        let callback = IMultiCaller::receiveFlashLoanCall {
+            _0: flash_loan.tokens,
+            _1: flash_loan.amounts,
+            _2: vec![U256::ZERO],
+            _3: flash_loan.userData,
+        };
+        multicaller.transact_callback(flash_call.to, callback.abi_encode().into())?;
+        assert_eq!(multicaller.db().basic_ref(recipient)?.unwrap_or_default().balance, one_eth / U256::from(4));
+        Ok(())
+    }
+}
@@ -1,3 +1,32 @@
+//! Facade over the loom crates. The modules below re-export the internal crates as they are, their interfaces change
+//! between releases. Downstream bots should prefer [`prelude`], enabled by the `sdk` feature, which only re-exports the
+//! types that are kept stable across minor releases.
+
+/// Curated stable api: market and pools, swap encoding, strategies and the topology builder
+pub mod prelude {
+    #[cfg(feature = "core-actors")]
+    pub use loom_core_actors::{Accessor, Actor, ActorResult, ActorsManager, Broadcaster, Consumer, Producer, SharedState, WorkerResult};
+    #[cfg(feature = "core-blockchain")]
+    pub use loom_core_blockchain::{Blockchain, BlockchainState, Strategy};
+    #[cfg(feature = "core-blockchain-actors")]
+    pub use loom_core_blockchain_actors::BlockchainActors;
+    #[cfg(feature = "core-topology")]
+    pub use loom_core_topology::{Topology, TopologyConfig};
+    #[cfg(feature = "execution-multicaller")]
+    pub use loom_execution_multicaller::{MulticallerSwapEncoder, SwapLineEncoder};
+    #[cfg(feature = "strategy-backrun")]
+    pub use loom_strategy_backrun::{BackrunConfig, BackrunConfigSection, ProfitReceiverConfig, StateChangeArbActor};
+    #[cfg(feature = "strategy-merger")]
+    pub use loom_strategy_merger::{ArbSwapPathMergerActor, DiffPathMergerActor, SamePathMergerActor};
+    #[cfg(feature = "types-entities")]
+    pub use loom_types_entities::{
+        Market, MarketState, Pool, PoolClass, PoolId, PoolProtocol, PoolWrapper, Swap, SwapDirection, SwapEncoder, SwapError, SwapLine,
+        SwapPath, Token,
+    };
+    #[cfg(feature = "types-events")]
+    pub use loom_types_events::{LoomTask, MarketEvents, Message, MessageSwapCompose, SwapComposeData, SwapComposeMessage};
+}
+
 #[cfg(feature = "broadcast")]
 pub mod broadcast {
     #[cfg(feature = "broadcast-accounts")]
@@ -44,6 +73,8 @@ pub mod defi {
     pub use loom_defi_preloader as preloader;
     #[cfg(feature = "defi-price")]
     pub use loom_defi_price as price;
+    #[cfg(feature = "defi-swap-math")]
+    pub use loom_defi_swap_math as swap_math;
     #[cfg(feature = "defi-uniswap-v3-math")]
     pub use loom_defi_uniswap_v3_math as uniswap_v3_math;
 }
@@ -97,6 +128,8 @@ pub mod rpc {
 
 #[cfg(feature = "storage")]
 pub mod storage {
+    #[cfg(feature = "storage-archive")]
+    pub use loom_storage_archive as archive;
     #[cfg(feature = "storage-db")]
     pub use loom_storage_db as db;
 }
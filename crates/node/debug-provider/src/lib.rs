@@ -1,9 +1,11 @@
 pub use anvilprovider::AnvilProviderExt;
 pub use debugprovider::{AnvilDebugProvider, AnvilDebugProviderFactory, AnvilDebugProviderType, DebugProviderExt};
 pub use httpcached::HttpCachedTransport;
+pub use rpc_fixture::{RecordingLayer, RecordingTransport, ReplayTransport};
 
 mod anvilprovider;
 mod archiveprovider;
 mod cachefolder;
 mod debugprovider;
 mod httpcached;
+mod rpc_fixture;
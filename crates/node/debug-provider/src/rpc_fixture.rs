@@ -0,0 +1,243 @@
+use std::collections::{HashMap, VecDeque};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+use alloy::primitives::B256;
+use alloy::rpc::json_rpc::{RequestPacket, Response, ResponsePacket, SerializedRequest};
+use alloy::transports::{TransportError, TransportErrorKind, TransportFut};
+use eyre::{eyre, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::value::RawValue;
+use tower::{Layer, Service};
+use tracing::{debug, error};
+
+/// Fixture file line
+#[derive(Serialize, Deserialize)]
+struct RecordedResponse {
+    method: String,
+    params_hash: B256,
+    params: Option<Box<RawValue>>,
+    response: Response,
+}
+
+#[derive(Clone)]
+struct FixtureWriter {
+    file: Arc<Mutex<BufWriter<File>>>,
+}
+
+impl FixtureWriter {
+    fn record(&self, req: &SerializedRequest, response: &Response) -> Result<()> {
+        let recorded = RecordedResponse {
+            method: req.method().to_string(),
+            params_hash: req.params_hash(),
+            params: req.params().map(|params| params.to_owned()),
+            response: response.clone(),
+        };
+        let line = serde_json::to_string(&recorded)?;
+        let mut file = self.file.lock().map_err(|_| eyre!("FIXTURE_LOCK_POISONED"))?;
+        writeln!(file, "{line}")?;
+        file.flush()?;
+        Ok(())
+    }
+
+    fn record_packet(&self, req: &RequestPacket, resp: &ResponsePacket) -> Result<()> {
+        match (req, resp) {
+            (RequestPacket::Single(req), ResponsePacket::Single(response)) => self.record(req, response),
+            (RequestPacket::Batch(reqs), ResponsePacket::Batch(responses)) => {
+                for req in reqs.iter() {
+                    if let Some(response) = responses.iter().find(|response| &response.id == req.id()) {
+                        self.record(req, response)?;
+                    }
+                }
+                Ok(())
+            }
+            _ => Err(eyre!("RESPONSE_PACKET_MISMATCH")),
+        }
+    }
+}
+
+/// Layer that records all RPC responses of the inner transport to a fixture file, one JSON line per response.
+///
+/// ```ignore
+/// let client = ClientBuilder::default().layer(RecordingLayer::new("fixtures/pools.jsonl")?).ws(WsConnect::new(node_url)).await?;
+/// ```
+#[derive(Clone)]
+pub struct RecordingLayer {
+    writer: FixtureWriter,
+}
+
+impl RecordingLayer {
+    /// Create the fixture file, an existing file is overwritten
+    pub fn new<T: AsRef<Path>>(path: T) -> Result<Self> {
+        let file = File::create(path)?;
+        Ok(Self { writer: FixtureWriter { file: Arc::new(Mutex::new(BufWriter::new(file))) } })
+    }
+}
+
+impl<S> Layer<S> for RecordingLayer {
+    type Service = RecordingTransport<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RecordingTransport { inner, writer: self.writer.clone() }
+    }
+}
+
+#[derive(Clone)]
+pub struct RecordingTransport<S> {
+    inner: S,
+    writer: FixtureWriter,
+}
+
+impl<S> Service<RequestPacket> for RecordingTransport<S>
+where
+    S: Service<RequestPacket, Response = ResponsePacket, Error = TransportError, Future = TransportFut<'static>>
+        + Clone
+        + Send
+        + Sync
+        + 'static,
+{
+    type Response = ResponsePacket;
+    type Error = TransportError;
+    type Future = TransportFut<'static>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: RequestPacket) -> Self::Future {
+        let mut inner = self.inner.clone();
+        let writer = self.writer.clone();
+        Box::pin(async move {
+            let resp = inner.call(req.clone()).await?;
+            if let Err(e) = writer.record_packet(&req, &resp) {
+                error!("Cannot record response : {}", e);
+            }
+            Ok(resp)
+        })
+    }
+}
+
+/// Transport that answers requests with the responses from a fixture file recorded by [`RecordingLayer`] without network
+/// access. Responses to the same request are replayed in the recorded order, the last one is repeated when exhausted.
+///
+/// ```ignore
+/// let client = RpcClient::new(ReplayTransport::from_file("fixtures/pools.jsonl")?, true);
+/// let provider = ProviderBuilder::new().disable_recommended_fillers().on_client(client);
+/// ```
+#[derive(Clone)]
+pub struct ReplayTransport {
+    responses: Arc<Mutex<HashMap<(String, B256), VecDeque<Response>>>>,
+}
+
+impl ReplayTransport {
+    pub fn from_file<T: AsRef<Path>>(path: T) -> Result<Self> {
+        let mut responses: HashMap<(String, B256), VecDeque<Response>> = HashMap::new();
+        for line in BufReader::new(File::open(path)?).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let recorded: RecordedResponse = serde_json::from_str(&line)?;
+            responses.entry((recorded.method, recorded.params_hash)).or_default().push_back(recorded.response);
+        }
+        debug!("Replay responses loaded : {}", responses.len());
+        Ok(Self { responses: Arc::new(Mutex::new(responses)) })
+    }
+
+    fn replay(&self, req: &SerializedRequest) -> Result<Response, TransportError> {
+        let mut responses = self.responses.lock().map_err(|_| TransportErrorKind::custom_str("FIXTURE_LOCK_POISONED"))?;
+        let queue = responses
+            .get_mut(&(req.method().to_string(), req.params_hash()))
+            .ok_or_else(|| TransportErrorKind::custom_str(format!("REPLAY_RESPONSE_NOT_FOUND {}", req.method()).as_str()))?;
+        let mut response = if queue.len() > 1 { queue.pop_front() } else { queue.front().cloned() }
+            .ok_or_else(|| TransportErrorKind::custom_str(format!("REPLAY_RESPONSE_NOT_FOUND {}", req.method()).as_str()))?;
+        response.id = req.id().clone();
+        Ok(response)
+    }
+
+    fn replay_packet(&self, req: &RequestPacket) -> Result<ResponsePacket, TransportError> {
+        match req {
+            RequestPacket::Single(req) => Ok(ResponsePacket::Single(self.replay(req)?)),
+            RequestPacket::Batch(reqs) => Ok(ResponsePacket::Batch(reqs.iter().map(|req| self.replay(req)).collect::<Result<_, _>>()?)),
+        }
+    }
+}
+
+impl Service<RequestPacket> for ReplayTransport {
+    type Response = ResponsePacket;
+    type Error = TransportError;
+    type Future = TransportFut<'static>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: RequestPacket) -> Self::Future {
+        let resp = self.replay_packet(&req);
+        Box::pin(async move { resp })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloy::providers::{Provider, ProviderBuilder};
+    use alloy::rpc::client::RpcClient;
+    use alloy::rpc::json_rpc::{Id, ResponsePayload};
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    // Answers every request with an increasing number
+    #[derive(Clone, Default)]
+    struct CounterTransport {
+        counter: Arc<AtomicU64>,
+    }
+
+    impl Service<RequestPacket> for CounterTransport {
+        type Response = ResponsePacket;
+        type Error = TransportError;
+        type Future = TransportFut<'static>;
+
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, req: RequestPacket) -> Self::Future {
+            let value = self.counter.fetch_add(1, Ordering::Relaxed) + 1;
+            let RequestPacket::Single(req) = req else { unreachable!() };
+            let payload = RawValue::from_string(format!("\"0x{value:x}\"")).unwrap();
+            Box::pin(
+                async move { Ok(ResponsePacket::Single(Response { id: req.id().clone(), payload: ResponsePayload::Success(payload) })) },
+            )
+        }
+    }
+
+    #[tokio::test]
+    async fn test_record_replay() -> Result<()> {
+        let path = std::env::temp_dir().join(format!("loom_rpc_fixture_{}.jsonl", B256::random()));
+
+        let transport = RecordingLayer::new(&path)?.layer(CounterTransport::default());
+        let provider = ProviderBuilder::new().disable_recommended_fillers().on_client(RpcClient::new(transport, true));
+        let recorded = vec![provider.get_block_number().await?, provider.get_block_number().await?, provider.get_chain_id().await?];
+        assert_eq!(recorded, vec![1, 2, 3]);
+
+        let provider =
+            ProviderBuilder::new().disable_recommended_fillers().on_client(RpcClient::new(ReplayTransport::from_file(&path)?, true));
+        let replayed = vec![
+            provider.get_block_number().await?,
+            provider.get_block_number().await?,
+            provider.get_chain_id().await?,
+            provider.get_block_number().await?,
+        ];
+        assert_eq!(replayed, vec![1, 2, 3, 2]);
+
+        let not_recorded = ReplayTransport::from_file(&path)?
+            .replay(&alloy::rpc::json_rpc::Request::new("eth_gasPrice", Id::Number(1), ()).try_into().unwrap());
+        assert!(not_recorded.is_err());
+
+        std::fs::remove_file(path)?;
+        Ok(())
+    }
+}
@@ -222,6 +222,7 @@ pub async fn node_exex_grpc_worker(
                         logs: None,
                         mined: None,
                         failed: None,
+                        replaced_by: None,
                         state_update: None,
                         pre_state: None,
                     };
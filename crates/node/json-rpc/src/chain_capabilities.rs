@@ -0,0 +1,161 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use alloy_network::Ethereum;
+use alloy_provider::Provider;
+use alloy_rpc_types::{BlockNumberOrTag, BlockTransactionsKind};
+use eyre::{eyre, Result};
+use tracing::{info, warn};
+
+use loom_types_entities::ExecutionProfile;
+
+// chains with bundle relays of block builders
+const BUNDLE_RELAY_CHAIN_IDS: [u64; 3] = [1, 17000, 11155111];
+
+/// Source of the state updates of new blocks
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BlockStateSource {
+    /// Post state of the block traced with `debug_traceBlock`
+    StateDiff,
+    /// State of the pools emitting logs in the block, read at the block
+    Logs,
+}
+
+/// How swap transactions are submitted
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SubmissionMode {
+    /// Bundles sent to builder relays
+    Bundle,
+    /// Single transactions sent to the node or sequencer
+    Public,
+}
+
+impl fmt::Display for BlockStateSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::StateDiff => write!(f, "state diff"),
+            Self::Logs => write!(f, "logs"),
+        }
+    }
+}
+
+impl fmt::Display for SubmissionMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Bundle => write!(f, "bundle"),
+            Self::Public => write!(f, "public"),
+        }
+    }
+}
+
+/// Parameters and RPC capabilities of a node queried on connect
+#[derive(Clone, Debug)]
+pub struct ChainCapabilities {
+    pub chain_id: u64,
+    /// Namespaces reported by `rpc_modules`, `None` if the node does not support the method
+    pub namespaces: Option<Vec<String>>,
+    /// The latest block has a base fee
+    pub eip1559: bool,
+    pub block_gas_limit: u64,
+}
+
+impl ChainCapabilities {
+    /// Query the chain id, RPC namespaces and latest block of the node
+    pub async fn discover<P: Provider<Ethereum>>(client: &P) -> Result<Self> {
+        let chain_id = client.get_chain_id().await?;
+        let namespaces = match client.raw_request::<_, HashMap<String, String>>("rpc_modules".into(), ()).await {
+            Ok(modules) => {
+                let mut namespaces: Vec<String> = modules.into_keys().collect();
+                namespaces.sort();
+                Some(namespaces)
+            }
+            Err(error) => {
+                warn!(%error, "rpc_modules is not supported, assuming all namespaces are available");
+                None
+            }
+        };
+        let latest_block = client
+            .get_block_by_number(BlockNumberOrTag::Latest, BlockTransactionsKind::Hashes)
+            .await?
+            .ok_or_else(|| eyre!("LATEST_BLOCK_NOT_FOUND"))?;
+
+        Ok(Self {
+            chain_id,
+            namespaces,
+            eip1559: latest_block.header.base_fee_per_gas.is_some(),
+            block_gas_limit: latest_block.header.gas_limit,
+        })
+    }
+
+    /// Nodes not reporting their namespaces are assumed to support all of them
+    pub fn has_namespace(&self, namespace: &str) -> bool {
+        self.namespaces.as_ref().is_none_or(|namespaces| namespaces.iter().any(|n| n == namespace))
+    }
+
+    pub fn block_state_source(&self) -> BlockStateSource {
+        if self.has_namespace("debug") {
+            BlockStateSource::StateDiff
+        } else {
+            BlockStateSource::Logs
+        }
+    }
+
+    /// Bundles need builder relays for the chain and EIP-1559 transactions paying tips
+    pub fn submission_mode(&self, execution_profile: ExecutionProfile) -> SubmissionMode {
+        if execution_profile.uses_bundles() && self.eip1559 && BUNDLE_RELAY_CHAIN_IDS.contains(&self.chain_id) {
+            SubmissionMode::Bundle
+        } else {
+            SubmissionMode::Public
+        }
+    }
+
+    /// Log the capabilities and the subsystems selected for them
+    pub fn report(&self, name: &str) {
+        let execution_profile = ExecutionProfile::for_chain_id(self.chain_id);
+        info!(
+            client = name,
+            chain_id = self.chain_id,
+            namespaces = self.namespaces.as_ref().map_or("unknown".to_string(), |namespaces| namespaces.join(",")),
+            debug = self.has_namespace("debug"),
+            trace = self.has_namespace("trace"),
+            eip1559 = self.eip1559,
+            block_gas_limit = self.block_gas_limit,
+            block_state = %self.block_state_source(),
+            submission = %self.submission_mode(execution_profile),
+            ?execution_profile,
+            "Chain capabilities"
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn capabilities(chain_id: u64, namespaces: Option<&[&str]>, eip1559: bool) -> ChainCapabilities {
+        ChainCapabilities {
+            chain_id,
+            namespaces: namespaces.map(|namespaces| namespaces.iter().map(|n| n.to_string()).collect()),
+            eip1559,
+            block_gas_limit: 30_000_000,
+        }
+    }
+
+    #[test]
+    fn test_selection() {
+        let full_node = capabilities(1, Some(&["debug", "eth", "net"]), true);
+        assert_eq!(full_node.block_state_source(), BlockStateSource::StateDiff);
+        assert_eq!(full_node.submission_mode(ExecutionProfile::for_chain_id(1)), SubmissionMode::Bundle);
+
+        let public_node = capabilities(1, Some(&["eth", "net"]), true);
+        assert_eq!(public_node.block_state_source(), BlockStateSource::Logs);
+        assert!(!public_node.has_namespace("trace"));
+
+        // namespaces are not reported
+        assert_eq!(capabilities(1, None, true).block_state_source(), BlockStateSource::StateDiff);
+
+        assert_eq!(capabilities(1, None, false).submission_mode(ExecutionProfile::BundleTips), SubmissionMode::Public);
+        assert_eq!(capabilities(56, None, true).submission_mode(ExecutionProfile::for_chain_id(56)), SubmissionMode::Public);
+        assert_eq!(capabilities(42161, None, true).submission_mode(ExecutionProfile::for_chain_id(42161)), SubmissionMode::Public);
+    }
+}
@@ -0,0 +1,126 @@
+use alloy_network::{Ethereum, TransactionResponse};
+use alloy_provider::Provider;
+use alloy_rpc_types::{BlockId, BlockNumberOrTag, Transaction};
+use futures::StreamExt;
+use tracing::{debug, error};
+
+use loom_core_actors::{Actor, ActorResult, Broadcaster, Producer, WorkerResult};
+use loom_core_actors_macros::Producer;
+use loom_core_blockchain::Blockchain;
+use loom_node_debug_provider::DebugProviderExt;
+use loom_types_blockchain::{debug_trace_block_post_states, LoomDataTypesEthereum, MempoolTx};
+use loom_types_events::{Flashblock, Message, MessageFlashblock, MessageMempoolDataUpdate, NodeMempoolDataUpdate};
+
+// most transactions of one sub-block, larger batches are split
+const MAX_FLASHBLOCK_TXS: usize = 1024;
+
+/// Worker subscribes to the transactions a node serving preconfirmed Flashblocks state adds to the pending block. The
+/// transactions received together are traced on the pending block and broadcast as [`MessageFlashblock`] with their post
+/// states and as mempool updates, so they can be backrun within the next sub-block. Post states are matched to the
+/// transactions by hash, transactions without a trace are skipped.
+pub async fn flashblocks_worker<P>(
+    client: P,
+    name: String,
+    flashblocks_tx: Broadcaster<MessageFlashblock>,
+    mempool_tx: Broadcaster<MessageMempoolDataUpdate>,
+) -> WorkerResult
+where
+    P: Provider<Ethereum> + DebugProviderExt<Ethereum> + Send + Sync + Clone + 'static,
+{
+    let subscription = client.subscribe::<_, Transaction>(("newFlashblockTransactions", true)).await?;
+    let mut stream = subscription.into_stream().ready_chunks(MAX_FLASHBLOCK_TXS);
+
+    let mut cur_block_number: u64 = 0;
+    let mut cur_index: u64 = 0;
+
+    while let Some(txs) = stream.next().await {
+        let mut post_states = match debug_trace_block_post_states(client.clone(), BlockId::Number(BlockNumberOrTag::Pending)).await {
+            Ok(post_states) => post_states,
+            Err(error) => {
+                error!(%error, "debug_trace_block error for pending block");
+                continue;
+            }
+        };
+
+        let mut new_txs = Vec::with_capacity(txs.len());
+        let mut new_state_update = Vec::with_capacity(txs.len());
+        for tx in txs {
+            let Some(tx_state_update) = post_states.remove(&tx.tx_hash()) else {
+                debug!(tx_hash = %tx.tx_hash(), "Flashblock transaction not found in pending block trace");
+                continue;
+            };
+            let update_msg: MessageMempoolDataUpdate = MessageMempoolDataUpdate::new_with_source(
+                NodeMempoolDataUpdate {
+                    tx_hash: tx.tx_hash(),
+                    mempool_tx: MempoolTx { tx: Some(tx.clone()), state_update: Some(tx_state_update.clone()), ..MempoolTx::default() },
+                },
+                name.clone(),
+            );
+            if let Err(e) = mempool_tx.send(update_msg) {
+                error!("mempool_tx.send error : {}", e);
+            }
+            new_txs.push(tx);
+            new_state_update.push(tx_state_update);
+        }
+
+        let Some(block_number) = new_txs.first().and_then(|tx| tx.block_number()) else { continue };
+        if block_number != cur_block_number {
+            cur_block_number = block_number;
+            cur_index = 0;
+        }
+
+        debug!(block_number, index = cur_index, txs = new_txs.len(), "Flashblock received");
+        let flashblock = Flashblock { block_number, index: cur_index, txs: new_txs, state_update: new_state_update };
+        if let Err(e) = flashblocks_tx.send(Message::new_with_time(flashblock)) {
+            error!("flashblocks_tx.send error : {}", e);
+        }
+        cur_index += 1;
+    }
+    Ok(name)
+}
+
+#[derive(Producer)]
+pub struct FlashblocksActor<P> {
+    name: &'static str,
+    client: P,
+    #[producer]
+    flashblocks_tx: Option<Broadcaster<MessageFlashblock>>,
+    #[producer]
+    mempool_tx: Option<Broadcaster<MessageMempoolDataUpdate>>,
+}
+
+impl<P> FlashblocksActor<P>
+where
+    P: Provider<Ethereum> + DebugProviderExt<Ethereum> + Send + Sync + Clone + 'static,
+{
+    pub fn new(client: P) -> FlashblocksActor<P> {
+        FlashblocksActor { name: "FlashblocksActor", client, flashblocks_tx: None, mempool_tx: None }
+    }
+
+    pub fn with_name(self, name: String) -> Self {
+        Self { name: Box::leak(name.into_boxed_str()), ..self }
+    }
+
+    pub fn on_bc(self, bc: &Blockchain<LoomDataTypesEthereum>) -> Self {
+        Self { flashblocks_tx: Some(bc.flashblocks_channel()), mempool_tx: Some(bc.new_mempool_tx_channel()), ..self }
+    }
+}
+
+impl<P> Actor for FlashblocksActor<P>
+where
+    P: Provider<Ethereum> + DebugProviderExt<Ethereum> + Send + Sync + Clone + 'static,
+{
+    fn start(&self) -> ActorResult {
+        let task = tokio::task::spawn(flashblocks_worker(
+            self.client.clone(),
+            self.name.to_string(),
+            self.flashblocks_tx.clone().unwrap(),
+            self.mempool_tx.clone().unwrap(),
+        ));
+        Ok(vec![task])
+    }
+
+    fn name(&self) -> &'static str {
+        self.name
+    }
+}
@@ -1,9 +1,14 @@
+pub use chain_capabilities::{BlockStateSource, ChainCapabilities, SubmissionMode};
+pub use flashblocks_actor::FlashblocksActor;
 pub use node_block_actor::NodeBlockActor;
 pub use node_mempool_actor::NodeMempoolActor;
 pub use wait_for_node_sync_actor::WaitForNodeSyncOneShotBlockingActor;
 
+mod chain_capabilities;
+mod flashblocks_actor;
 mod node_block_actor;
 mod node_block_hash_worker;
+mod node_block_logs_state_worker;
 mod node_block_logs_worker;
 mod node_block_state_worker;
 mod node_block_with_tx_worker;
@@ -3,15 +3,17 @@ use alloy_provider::Provider;
 use tokio::task::JoinHandle;
 
 use crate::node_block_hash_worker::new_node_block_header_worker;
+use crate::node_block_logs_state_worker::new_node_block_logs_state_worker;
 use crate::node_block_logs_worker::new_node_block_logs_worker;
 use crate::node_block_state_worker::new_node_block_state_worker;
 use crate::node_block_with_tx_worker::new_block_with_tx_worker;
-use loom_core_actors::{Actor, ActorResult, Broadcaster, Producer, WorkerResult};
+use loom_core_actors::{Actor, ActorResult, Broadcaster, Producer, SharedState, WorkerResult};
 use loom_core_actors_macros::Producer;
 use loom_core_blockchain::Blockchain;
 use loom_node_actor_config::NodeBlockActorConfig;
 use loom_node_debug_provider::DebugProviderExt;
 use loom_types_blockchain::LoomDataTypesEthereum;
+use loom_types_entities::Market;
 use loom_types_events::{MessageBlock, MessageBlockHeader, MessageBlockLogs, MessageBlockStateUpdate};
 
 pub fn new_node_block_workers_starter<P>(
@@ -20,6 +22,7 @@ pub fn new_node_block_workers_starter<P>(
     new_block_with_tx_channel: Option<Broadcaster<MessageBlock>>,
     new_block_logs_channel: Option<Broadcaster<MessageBlockLogs>>,
     new_block_state_update_channel: Option<Broadcaster<MessageBlockStateUpdate>>,
+    state_from_logs: Option<SharedState<Market>>,
 ) -> ActorResult
 where
     P: Provider<Ethereum> + DebugProviderExt + Send + Sync + Clone + 'static,
@@ -40,7 +43,17 @@ where
     }
 
     if let Some(channel) = new_block_state_update_channel {
-        tasks.push(tokio::task::spawn(new_node_block_state_worker(client.clone(), new_header_internal_channel.clone(), channel)));
+        match state_from_logs {
+            Some(market) => tasks.push(tokio::task::spawn(new_node_block_logs_state_worker(
+                client.clone(),
+                market,
+                new_header_internal_channel.clone(),
+                channel,
+            ))),
+            None => {
+                tasks.push(tokio::task::spawn(new_node_block_state_worker(client.clone(), new_header_internal_channel.clone(), channel)))
+            }
+        }
     }
 
     Ok(tasks)
@@ -50,6 +63,7 @@ where
 pub struct NodeBlockActor<P> {
     client: P,
     config: NodeBlockActorConfig,
+    state_from_logs: Option<SharedState<Market>>,
     #[producer]
     block_header_channel: Option<Broadcaster<MessageBlockHeader>>,
     #[producer]
@@ -72,6 +86,7 @@ where
         NodeBlockActor {
             client,
             config,
+            state_from_logs: None,
             block_header_channel: None,
             block_with_tx_channel: None,
             block_logs_channel: None,
@@ -79,6 +94,11 @@ where
         }
     }
 
+    /// Read block state updates from the pools of the market emitting logs, for nodes without `debug_traceBlock`
+    pub fn with_state_from_logs(self, market: SharedState<Market>) -> Self {
+        Self { state_from_logs: Some(market), ..self }
+    }
+
     pub fn on_bc(self, bc: &Blockchain<LoomDataTypesEthereum>) -> Self {
         Self {
             block_header_channel: if self.config.block_header { Some(bc.new_block_headers_channel()) } else { None },
@@ -101,6 +121,7 @@ where
             self.block_with_tx_channel.clone(),
             self.block_logs_channel.clone(),
             self.block_state_update_channel.clone(),
+            self.state_from_logs.clone(),
         )
     }
     fn name(&self) -> &'static str {
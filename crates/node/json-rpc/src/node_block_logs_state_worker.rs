@@ -0,0 +1,195 @@
+use std::collections::HashSet;
+
+use alloy_network::Network;
+use alloy_primitives::Address;
+use alloy_provider::Provider;
+use alloy_rpc_types::{Filter, Header};
+use tracing::{debug, error};
+
+use loom_core_actors::{subscribe, Broadcaster, SharedState, WorkerResult};
+use loom_types_entities::required_state::{RequiredState, RequiredStateReader};
+use loom_types_entities::state_proof::ProvenBlock;
+use loom_types_entities::{Market, PoolId};
+use loom_types_events::{BlockStateUpdate, Message, MessageBlockStateUpdate};
+
+/// Block state updates for nodes without the `debug` namespace. The required state of the known pools emitting logs in the
+/// block is read at the block with `eth_getProof` and verified against the state root of the block header, instead of
+/// tracing the post state of all transactions.
+pub async fn new_node_block_logs_state_worker<P, N>(
+    client: P,
+    market: SharedState<Market>,
+    block_header_receiver: Broadcaster<Header>,
+    sender: Broadcaster<MessageBlockStateUpdate>,
+) -> WorkerResult
+where
+    N: Network,
+    P: Provider<N> + Send + Sync + Clone + 'static,
+{
+    subscribe!(block_header_receiver);
+
+    loop {
+        if let Ok(block_header) = block_header_receiver.recv().await {
+            let (block_number, block_hash) = (block_header.number, block_header.hash);
+            debug!("BlockState from logs header received {} {}", block_number, block_hash);
+
+            let logs = match client.get_logs(&Filter::new().at_block_hash(block_hash)).await {
+                Ok(logs) => logs,
+                Err(e) => {
+                    error!("client.get_logs error : {e}");
+                    continue;
+                }
+            };
+            let emitters: HashSet<Address> = logs.iter().map(|log| log.address()).collect();
+
+            let mut required_state = RequiredState::new();
+            let mut pools = 0;
+            {
+                let market_guard = market.read().await;
+                for address in emitters {
+                    let Some(pool) = market_guard.get_pool(&PoolId::Address(address)) else { continue };
+                    match pool.get_state_required() {
+                        Ok(pool_required_state) => {
+                            required_state.merge(pool_required_state);
+                            pools += 1;
+                        }
+                        Err(e) => error!(%address, "get_state_required error : {e}"),
+                    }
+                }
+            }
+
+            let state_update = if pools == 0 {
+                Vec::new()
+            } else {
+                let proven_block = ProvenBlock::from_header(&block_header);
+                match RequiredStateReader::fetch_calls_and_slots_verified(client.clone(), required_state, proven_block).await {
+                    Ok(update) => vec![update],
+                    Err(e) => {
+                        error!(block_number, pools, "fetch_calls_and_slots_verified error : {e}");
+                        continue;
+                    }
+                }
+            };
+
+            if let Err(e) = sender.send(Message::new_with_time(BlockStateUpdate { block_header, state_update })) {
+                error!("Broadcaster error {}", e)
+            }
+            debug!(pools, "BlockState from logs processing finished {} {}", block_number, block_hash);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloy_consensus::constants::EMPTY_ROOT_HASH;
+    use alloy_json_rpc::{ErrorPayload, RequestPacket, Response, ResponsePacket, ResponsePayload};
+    use alloy_primitives::{B256, U256};
+    use alloy_provider::ProviderBuilder;
+    use alloy_rpc_client::RpcClient;
+    use alloy_transport::{TransportError, TransportFut};
+    use loom_types_entities::MockPool;
+    use serde_json::json;
+    use serde_json::value::RawValue;
+    use std::sync::{Arc, Mutex};
+    use std::task::{Context, Poll};
+    use std::time::Duration;
+    use tower::Service;
+
+    // A node without the debug namespace, answering logs, proofs and code of an empty state
+    #[derive(Clone)]
+    struct NoDebugTransport {
+        pool: Address,
+        methods: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl NoDebugTransport {
+        fn result(&self, method: &str, params: Option<&RawValue>) -> Option<serde_json::Value> {
+            match method {
+                "eth_getLogs" => Some(json!([{
+                    "address": self.pool,
+                    "topics": [],
+                    "data": "0x",
+                    "blockHash": B256::repeat_byte(0x01),
+                    "blockNumber": "0x1",
+                    "transactionHash": B256::repeat_byte(0x02),
+                    "transactionIndex": "0x0",
+                    "logIndex": "0x0",
+                    "removed": false
+                }])),
+                "eth_getProof" => {
+                    let (address, slots, _): (Address, Vec<B256>, serde_json::Value) = serde_json::from_str(params.unwrap().get()).unwrap();
+                    let storage_proof: Vec<_> = slots.iter().map(|slot| json!({ "key": slot, "value": "0x0", "proof": [] })).collect();
+                    Some(json!({
+                        "address": address,
+                        "balance": "0x0",
+                        "nonce": "0x0",
+                        "codeHash": B256::ZERO,
+                        "storageHash": B256::ZERO,
+                        "accountProof": [],
+                        "storageProof": storage_proof
+                    }))
+                }
+                "eth_getCode" => Some(json!("0x")),
+                _ => None,
+            }
+        }
+    }
+
+    impl Service<RequestPacket> for NoDebugTransport {
+        type Response = ResponsePacket;
+        type Error = TransportError;
+        type Future = TransportFut<'static>;
+
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, req: RequestPacket) -> Self::Future {
+            let RequestPacket::Single(req) = req else { unreachable!() };
+            self.methods.lock().unwrap().push(req.method().to_string());
+            let payload = match self.result(req.method(), req.params()) {
+                Some(result) => ResponsePayload::Success(RawValue::from_string(result.to_string()).unwrap()),
+                None => ResponsePayload::Failure(ErrorPayload {
+                    code: -32601,
+                    message: format!("the method {} does not exist/is not available", req.method()).into(),
+                    data: None,
+                }),
+            };
+            Box::pin(async move { Ok(ResponsePacket::Single(Response { id: req.id().clone(), payload })) })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_state_without_debug_namespace() -> eyre::Result<()> {
+        let pool = Address::repeat_byte(0x10);
+        let methods = Arc::new(Mutex::new(Vec::new()));
+        let transport = NoDebugTransport { pool, methods: methods.clone() };
+        let client = ProviderBuilder::new().disable_recommended_fillers().on_client(RpcClient::new(transport, true));
+
+        let mut market = Market::default();
+        market.add_pool(MockPool::new(Address::repeat_byte(0x01), Address::repeat_byte(0x02), pool))?;
+
+        let header_channel: Broadcaster<Header> = Broadcaster::new(10);
+        let update_channel: Broadcaster<MessageBlockStateUpdate> = Broadcaster::new(10);
+        let mut update_receiver = update_channel.subscribe();
+        tokio::spawn(new_node_block_logs_state_worker(client, SharedState::new(market), header_channel.clone(), update_channel));
+
+        let header = Header::new(alloy_consensus::Header { number: 1, state_root: EMPTY_ROOT_HASH, ..Default::default() });
+        // the worker subscribes after being spawned, the header is resent until it is processed
+        let update = tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                let _ = header_channel.send(header.clone());
+                if let Ok(Ok(update)) = tokio::time::timeout(Duration::from_millis(100), update_receiver.recv()).await {
+                    break update;
+                }
+            }
+        })
+        .await?;
+
+        let state = &update.inner.state_update[0];
+        let storage = &state.get(&pool).unwrap().storage;
+        assert_eq!(storage.keys().copied().collect::<Vec<_>>(), vec![B256::ZERO, B256::from(U256::from(1))]);
+        assert!(methods.lock().unwrap().iter().all(|method| !method.starts_with("debug_")));
+        Ok(())
+    }
+}
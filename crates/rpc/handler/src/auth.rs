@@ -0,0 +1,38 @@
+use axum::http::header::AUTHORIZATION;
+use axum::http::{HeaderMap, StatusCode};
+
+/// Check the bearer token of a route changing the state of the bot, routes are rejected until `webserver.auth_token` is set
+pub(crate) fn require_auth(auth_token: Option<&str>, headers: &HeaderMap) -> Result<(), (StatusCode, String)> {
+    let Some(auth_token) = auth_token else {
+        return Err((StatusCode::FORBIDDEN, "Route requires webserver.auth_token to be set".to_string()));
+    };
+    let provided = headers.get(AUTHORIZATION).and_then(|value| value.to_str().ok()).and_then(|value| value.strip_prefix("Bearer "));
+    match provided {
+        Some(provided) if constant_time_eq(provided.as_bytes(), auth_token.as_bytes()) => Ok(()),
+        _ => Err((StatusCode::UNAUTHORIZED, "Invalid bearer token".to_string())),
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn headers(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(AUTHORIZATION, value.parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_require_auth() {
+        assert_eq!(require_auth(None, &headers("Bearer secret")).unwrap_err().0, StatusCode::FORBIDDEN);
+        assert_eq!(require_auth(Some("secret"), &HeaderMap::new()).unwrap_err().0, StatusCode::UNAUTHORIZED);
+        assert_eq!(require_auth(Some("secret"), &headers("Bearer other")).unwrap_err().0, StatusCode::UNAUTHORIZED);
+        assert_eq!(require_auth(Some("secret"), &headers("secret")).unwrap_err().0, StatusCode::UNAUTHORIZED);
+        assert!(require_auth(Some("secret"), &headers("Bearer secret")).is_ok());
+    }
+}
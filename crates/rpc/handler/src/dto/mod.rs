@@ -1,5 +1,12 @@
 pub mod block;
 pub mod flashbots;
+pub mod ops_call;
 pub mod pagination;
 pub mod pool;
 pub mod quote;
+pub mod score;
+pub mod snapshot;
+pub mod swap;
+pub mod task;
+pub mod tip_recipients;
+pub mod trace;
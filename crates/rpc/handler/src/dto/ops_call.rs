@@ -0,0 +1,24 @@
+use alloy_primitives::{Address, Bytes, U256};
+use serde::{Deserialize, Serialize};
+use utoipa::PartialSchema;
+use utoipa::ToSchema;
+
+/// Operational call executed by the smart account of the user operation broadcaster
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct OpsCallRequest {
+    #[schema(value_type = String)]
+    pub to: Address,
+    #[schema(value_type = Option<String>)]
+    pub value: Option<U256>,
+    #[schema(schema_with = String::schema)]
+    #[serde(default)]
+    pub data: Bytes,
+    /// Purpose of the call for the logs, e.g. `sweep`
+    pub label: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct OpsCallResponse {
+    /// Broadcasters the call was queued for
+    pub receivers: usize,
+}
@@ -4,9 +4,9 @@ use serde::{Deserialize, Serialize};
 use utoipa::openapi::schema::SchemaType;
 use utoipa::openapi::{Array, Object, ToArray, Type};
 use utoipa::PartialSchema;
-use utoipa::{schema, ToSchema};
+use utoipa::{schema, IntoParams, ToSchema};
 
-#[derive(Debug, Serialize, ToSchema)]
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
 pub struct PoolResponse {
     pub pools: Vec<Pool>,
     pub total: usize,
@@ -24,7 +24,7 @@ pub struct PoolDetailsResponse {
     pub tokens: Vec<Address>,
 }
 
-#[derive(Debug, Serialize, ToSchema)]
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
 pub struct Pool {
     #[schema(schema_with = String::schema)]
     pub address: Address,
@@ -34,13 +34,26 @@ pub struct Pool {
     pub tokens: Vec<Address>,
     pub protocol: PoolProtocol,
     pub pool_class: PoolClass,
+    pub disabled: bool,
+    /// Number of swap paths through the pool
+    pub paths: usize,
+    /// Best score of the swap paths through the pool
+    pub score: Option<f64>,
+}
+
+/// Order of the listed pools, descending
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum PoolSort {
+    Score,
+    Paths,
 }
 
 pub fn array_of_strings() -> Array {
     Object::with_type(SchemaType::Type(Type::String)).to_array()
 }
 
-#[derive(Debug, Serialize, ToSchema)]
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum PoolClass {
     Unknown,
@@ -56,6 +69,9 @@ pub enum PoolClass {
     RocketPool,
     BalancerV1,
     BalancerV2,
+    GmxV2,
+    PendleV2,
+    WooFiV2,
     Custom(u64),
 }
 impl From<loom_types_entities::PoolClass> for PoolClass {
@@ -74,11 +90,38 @@ impl From<loom_types_entities::PoolClass> for PoolClass {
             loom_types_entities::PoolClass::RocketPool => PoolClass::RocketPool,
             loom_types_entities::PoolClass::BalancerV1 => PoolClass::BalancerV1,
             loom_types_entities::PoolClass::BalancerV2 => PoolClass::BalancerV2,
+            loom_types_entities::PoolClass::GmxV2 => PoolClass::GmxV2,
+            loom_types_entities::PoolClass::PendleV2 => PoolClass::PendleV2,
+            loom_types_entities::PoolClass::WooFiV2 => PoolClass::WooFiV2,
             loom_types_entities::PoolClass::Custom(id) => PoolClass::Custom(id),
         }
     }
 }
 
+impl From<&PoolClass> for loom_types_entities::PoolClass {
+    fn from(pool_class: &PoolClass) -> Self {
+        match pool_class {
+            PoolClass::Unknown => loom_types_entities::PoolClass::Unknown,
+            PoolClass::UniswapV2 => loom_types_entities::PoolClass::UniswapV2,
+            PoolClass::UniswapV3 => loom_types_entities::PoolClass::UniswapV3,
+            PoolClass::UniswapV4 => loom_types_entities::PoolClass::UniswapV4,
+            PoolClass::PancakeV3 => loom_types_entities::PoolClass::PancakeV3,
+            PoolClass::Maverick => loom_types_entities::PoolClass::Maverick,
+            PoolClass::MaverickV2 => loom_types_entities::PoolClass::MaverickV2,
+            PoolClass::Curve => loom_types_entities::PoolClass::Curve,
+            PoolClass::LidoStEth => loom_types_entities::PoolClass::LidoStEth,
+            PoolClass::LidoWstEth => loom_types_entities::PoolClass::LidoWstEth,
+            PoolClass::RocketPool => loom_types_entities::PoolClass::RocketPool,
+            PoolClass::BalancerV1 => loom_types_entities::PoolClass::BalancerV1,
+            PoolClass::BalancerV2 => loom_types_entities::PoolClass::BalancerV2,
+            PoolClass::GmxV2 => loom_types_entities::PoolClass::GmxV2,
+            PoolClass::PendleV2 => loom_types_entities::PoolClass::PendleV2,
+            PoolClass::WooFiV2 => loom_types_entities::PoolClass::WooFiV2,
+            PoolClass::Custom(id) => loom_types_entities::PoolClass::Custom(*id),
+        }
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize, ToSchema)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum PoolProtocol {
@@ -99,6 +142,9 @@ pub enum PoolProtocol {
     UniswapV4,
     PancakeV3,
     Integral,
+    Ramses,
+    Thena,
+    Equalizer,
     Maverick,
     MaverickV2,
     Curve,
@@ -109,6 +155,9 @@ pub enum PoolProtocol {
     AntFarm,
     BalancerV1,
     BalancerV2,
+    GmxV2,
+    PendleV2,
+    WooFiV2,
     Custom(u64),
 }
 
@@ -132,6 +181,9 @@ impl From<loom_types_entities::PoolProtocol> for PoolProtocol {
             loom_types_entities::PoolProtocol::UniswapV4 => PoolProtocol::UniswapV4,
             loom_types_entities::PoolProtocol::PancakeV3 => PoolProtocol::PancakeV3,
             loom_types_entities::PoolProtocol::Integral => PoolProtocol::Integral,
+            loom_types_entities::PoolProtocol::Ramses => PoolProtocol::Ramses,
+            loom_types_entities::PoolProtocol::Thena => PoolProtocol::Thena,
+            loom_types_entities::PoolProtocol::Equalizer => PoolProtocol::Equalizer,
             loom_types_entities::PoolProtocol::Maverick => PoolProtocol::Maverick,
             loom_types_entities::PoolProtocol::MaverickV2 => PoolProtocol::MaverickV2,
             loom_types_entities::PoolProtocol::Curve => PoolProtocol::Curve,
@@ -142,6 +194,9 @@ impl From<loom_types_entities::PoolProtocol> for PoolProtocol {
             loom_types_entities::PoolProtocol::AntFarm => PoolProtocol::AntFarm,
             loom_types_entities::PoolProtocol::BalancerV1 => PoolProtocol::BalancerV1,
             loom_types_entities::PoolProtocol::BalancerV2 => PoolProtocol::BalancerV2,
+            loom_types_entities::PoolProtocol::GmxV2 => PoolProtocol::GmxV2,
+            loom_types_entities::PoolProtocol::PendleV2 => PoolProtocol::PendleV2,
+            loom_types_entities::PoolProtocol::WooFiV2 => PoolProtocol::WooFiV2,
             loom_types_entities::PoolProtocol::Custom(id) => PoolProtocol::Custom(id),
         }
     }
@@ -167,6 +222,9 @@ impl From<&PoolProtocol> for loom_types_entities::PoolProtocol {
             PoolProtocol::UniswapV4 => loom_types_entities::PoolProtocol::UniswapV4,
             PoolProtocol::PancakeV3 => loom_types_entities::PoolProtocol::PancakeV3,
             PoolProtocol::Integral => loom_types_entities::PoolProtocol::Integral,
+            PoolProtocol::Ramses => loom_types_entities::PoolProtocol::Ramses,
+            PoolProtocol::Thena => loom_types_entities::PoolProtocol::Thena,
+            PoolProtocol::Equalizer => loom_types_entities::PoolProtocol::Equalizer,
             PoolProtocol::Maverick => loom_types_entities::PoolProtocol::Maverick,
             PoolProtocol::MaverickV2 => loom_types_entities::PoolProtocol::MaverickV2,
             PoolProtocol::Curve => loom_types_entities::PoolProtocol::Curve,
@@ -177,12 +235,78 @@ impl From<&PoolProtocol> for loom_types_entities::PoolProtocol {
             PoolProtocol::AntFarm => loom_types_entities::PoolProtocol::AntFarm,
             PoolProtocol::BalancerV1 => loom_types_entities::PoolProtocol::BalancerV1,
             PoolProtocol::BalancerV2 => loom_types_entities::PoolProtocol::BalancerV2,
+            PoolProtocol::GmxV2 => loom_types_entities::PoolProtocol::GmxV2,
+            PoolProtocol::PendleV2 => loom_types_entities::PoolProtocol::PendleV2,
+            PoolProtocol::WooFiV2 => loom_types_entities::PoolProtocol::WooFiV2,
             PoolProtocol::Custom(id) => loom_types_entities::PoolProtocol::Custom(*id),
         }
     }
 }
 
-#[derive(Debug, Serialize, ToSchema)]
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
 pub struct MarketStats {
     pub total_pools: usize,
+    pub disabled_pools: usize,
+    pub total_paths: usize,
+    pub disabled_paths: usize,
+}
+
+/// Disable or enable every pool of the class
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct KillSwitchRequest {
+    pub pool_class: PoolClass,
+    pub disabled: bool,
+    /// Label of the authenticated operator toggling the class, kept in the audit log
+    pub operator: String,
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct KillSwitchEntry {
+    pub pool_class: PoolClass,
+    pub disabled: bool,
+    pub operator: String,
+    pub reason: Option<String>,
+    /// Unix timestamp in seconds
+    pub timestamp: u64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct KillSwitchResponse {
+    pub disabled: Vec<PoolClass>,
+    pub log: Vec<KillSwitchEntry>,
+}
+
+const fn _pool_events_limit_default() -> usize {
+    100
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct PoolEventsQuery {
+    /// Events after the sequence number, from the oldest kept one by default
+    #[serde(default)]
+    pub since: u64,
+    #[serde(default = "_pool_events_limit_default")]
+    pub limit: usize,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PoolEventEntry {
+    pub seq: u64,
+    /// One of added, updated, disabled, enabled and removed
+    pub kind: String,
+    pub pool_id: String,
+    /// Class of an added pool
+    pub pool_class: Option<PoolClass>,
+    /// Swap paths added through an updated pool
+    pub paths_added: Option<usize>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PoolEventsResponse {
+    pub events: Vec<PoolEventEntry>,
+    /// Sequence number of the latest event of the market
+    pub last_seq: u64,
+    /// Sequence number of the oldest event kept, older events were dropped
+    pub first_seq: Option<u64>,
 }
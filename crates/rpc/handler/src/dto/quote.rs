@@ -1,4 +1,4 @@
-use crate::dto::pool::PoolProtocol;
+use crate::dto::pool::{array_of_strings, PoolProtocol, PoolSort};
 use alloy_primitives::{Address, U256};
 use serde::{Deserialize, Serialize};
 use utoipa::PartialSchema;
@@ -7,6 +7,12 @@ use utoipa::{IntoParams, ToSchema};
 #[derive(Debug, Deserialize, IntoParams)]
 pub struct Filter {
     pub protocol: Option<PoolProtocol>,
+    /// Pools of the token
+    #[param(value_type = Option<String>)]
+    pub token: Option<Address>,
+    /// Only disabled or only enabled pools
+    pub disabled: Option<bool>,
+    pub sort: Option<PoolSort>,
 }
 
 #[derive(Debug, Deserialize, ToSchema)]
@@ -25,3 +31,17 @@ pub struct QuoteResponse {
     pub out_amount: U256,
     pub gas_used: u64,
 }
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RouteQuoteResponse {
+    #[schema(schema_with = String::schema)]
+    pub out_amount: U256,
+    pub gas_used: u64,
+    /// Block of the market state the quote was calculated at
+    pub block_number: u64,
+    /// Tokens of the best route in the swap order
+    #[schema(schema_with = array_of_strings)]
+    pub tokens: Vec<Address>,
+    /// Pool ids of the best route in the swap order
+    pub pools: Vec<String>,
+}
@@ -0,0 +1,48 @@
+use alloy_primitives::Address;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Half life of adjustments pushed without one
+pub const DEFAULT_HALF_LIFE_SECS: u64 = 600;
+
+/// Score adjustment of a pool or of a swap path, exactly one of them is set
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ScoreAdjustmentUpdate {
+    #[schema(value_type = Option<String>)]
+    pub pool: Option<Address>,
+    /// Key of the path as in the market snapshots, its tokens interleaved with its pool ids joined by `>`
+    pub path: Option<String>,
+    /// Added to the scores of the paths, zero removes the adjustment
+    pub value: f64,
+    /// Seconds for the adjustment to decay to half of its value, zero for no decay
+    pub half_life_secs: Option<u64>,
+    /// Signal source, e.g. the name of the model
+    pub source: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ScoreAdjustmentsRequest {
+    pub adjustments: Vec<ScoreAdjustmentUpdate>,
+    /// Remove the adjustments not in the request
+    #[serde(default)]
+    pub replace: bool,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ScoreAdjustmentEntry {
+    /// Pool id or path key, the hash of paths no longer in the market
+    pub key: String,
+    pub value: f64,
+    /// Value after the decay
+    pub current: f64,
+    pub half_life_secs: u64,
+    pub source: Option<String>,
+    /// Unix timestamp in seconds
+    pub timestamp: u64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ScoreAdjustmentsResponse {
+    pub pools: Vec<ScoreAdjustmentEntry>,
+    pub paths: Vec<ScoreAdjustmentEntry>,
+}
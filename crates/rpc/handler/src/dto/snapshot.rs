@@ -0,0 +1,23 @@
+use loom_types_entities::MarketSnapshot;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Page of the market snapshot, pages are merged until the totals are reached
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MarketSnapshotPage {
+    #[schema(value_type = Object)]
+    pub snapshot: MarketSnapshot,
+    pub pools_total: usize,
+    pub tokens_total: usize,
+    pub paths_total: usize,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct MarketSnapshotDiffRequest {
+    /// Snapshot merged from the pages of `GET /snapshot`, e.g. before an upgrade or a config edit
+    #[schema(value_type = Object)]
+    pub before: MarketSnapshot,
+    /// Snapshot to compare with, the current market view if not set
+    #[schema(value_type = Option<Object>)]
+    pub after: Option<MarketSnapshot>,
+}
@@ -0,0 +1,34 @@
+use alloy_primitives::{Address, U256};
+use loom_types_entities::PoolId;
+use serde::{Deserialize, Serialize};
+use utoipa::PartialSchema;
+use utoipa::ToSchema;
+
+/// Route to swap `amount_in` of the first token through the pools, pool `i` swaps token `i` to token `i + 1`
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ManualSwapRequest {
+    #[schema(value_type = Vec<String>)]
+    pub tokens: Vec<Address>,
+    #[schema(value_type = Vec<String>)]
+    pub pools: Vec<PoolId>,
+    #[schema(schema_with = String::schema)]
+    pub amount_in: U256,
+    #[schema(value_type = Option<String>)]
+    pub min_amount_out: Option<U256>,
+    /// Receiver of the last token, multicaller if not set
+    #[schema(value_type = Option<String>)]
+    pub recipient: Option<Address>,
+    /// Only calculate the route without executing it
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ManualSwapResponse {
+    #[schema(schema_with = String::schema)]
+    pub amount_out: U256,
+    pub gas_used: u64,
+    pub target_block: u64,
+    /// Sent to the router for preflight, simulation and broadcast
+    pub submitted: bool,
+}
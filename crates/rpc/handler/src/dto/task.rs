@@ -0,0 +1,28 @@
+use alloy_primitives::Address;
+use serde::{Deserialize, Serialize};
+use utoipa::PartialSchema;
+use utoipa::ToSchema;
+
+/// Maintenance command for the pool loader
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+#[serde(tag = "task", rename_all = "snake_case")]
+pub enum TaskRequest {
+    /// Re-fetch the required state of a loaded pool
+    RefreshPoolState {
+        #[schema(schema_with = String::schema)]
+        pool: Address,
+    },
+    /// Build the swap paths missing for the pools of the token
+    RebuildPathsForToken {
+        #[schema(schema_with = String::schema)]
+        token: Address,
+    },
+    /// Re-apply the pools loading config and retry pools whose loads failed
+    ReloadConfig,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TaskResponse {
+    /// Number of actors the task was sent to
+    pub receivers: usize,
+}
@@ -0,0 +1,41 @@
+use std::collections::BTreeMap;
+
+use alloy_primitives::{Address, Bytes};
+use loom_types_entities::TipRecipient;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Tip recipients replacing the current ones, `coinbase` or an address. The admin signs `TipRecipientsUpdate(uint64 nonce,
+/// uint64 deadline,address defaultRecipient,string[] builders,address[] recipients)` with EIP-712 in the domain `Loom Tip
+/// Recipients` version `1` of the chain with the multicaller as verifying contract, with the builders sorted by name and
+/// the zero address for the coinbase
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct TipRecipientsUpdateRequest {
+    /// Above the nonce of the last update
+    pub nonce: u64,
+    /// Unix timestamp the update expires at, at most an hour away
+    pub deadline: u64,
+    #[schema(value_type = String)]
+    #[serde(default)]
+    pub default: TipRecipient,
+    #[schema(value_type = Object)]
+    #[serde(default)]
+    pub builders: BTreeMap<String, TipRecipient>,
+    /// 65 bytes signature of the admin
+    #[schema(value_type = String)]
+    pub signature: Bytes,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TipRecipientsResponse {
+    pub chain_id: u64,
+    /// Multicaller the updates are signed for
+    #[schema(value_type = Option<String>)]
+    pub verifying_contract: Option<Address>,
+    #[schema(value_type = Option<String>)]
+    pub admin: Option<Address>,
+    pub default: String,
+    pub builders: BTreeMap<String, String>,
+    /// Nonce of the last update
+    pub nonce: u64,
+}
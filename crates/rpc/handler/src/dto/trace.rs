@@ -0,0 +1,36 @@
+use alloy_primitives::Address;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Trace the next simulations of swaps through the pool, or of any swap if no pool is set
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct TraceCaptureRequest {
+    #[schema(value_type = Option<String>)]
+    pub pool: Option<Address>,
+    /// Simulations to trace, one if not set
+    pub count: Option<usize>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TraceRequestEntry {
+    pub pool: Option<String>,
+    /// Simulations left to trace
+    pub remaining: usize,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SimulationTraceSummary {
+    pub id: u64,
+    pub block_number: u64,
+    pub swap: String,
+    pub gas_used: u64,
+    /// Revert or halt reason of a failed simulation
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SimulationTracesResponse {
+    pub requests: Vec<TraceRequestEntry>,
+    /// Captured traces, the latest first
+    pub traces: Vec<SimulationTraceSummary>,
+}
@@ -1,4 +1,11 @@
 pub mod blocks;
 pub mod flashbots;
+pub mod ops_calls;
 pub mod pools;
+pub mod scores;
+pub mod snapshots;
+pub mod swaps;
+pub mod tasks;
+pub mod tip_recipients;
+pub mod traces;
 pub mod ws;
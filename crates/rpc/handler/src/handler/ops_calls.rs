@@ -0,0 +1,42 @@
+use crate::auth::require_auth;
+use crate::dto::ops_call::{OpsCallRequest, OpsCallResponse};
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode};
+use axum::Json;
+use loom_rpc_state::AppState;
+use loom_types_events::{MessageOpsCall, OpsCall};
+use revm::{DatabaseCommit, DatabaseRef};
+use tracing::info;
+
+/// Submit an ops call
+///
+/// Queue a low urgency operational call, e.g. a sweep or an approval, for the smart account sending user operations
+/// through the ERC-4337 bundler. The nonces of the swap signers are not used. Calls outside of the targets and selectors
+/// allowed by the broadcaster are dropped
+#[utoipa::path(
+    post,
+    path = "/ops_calls",
+    tag = "execution",
+    tags = [],
+    request_body = OpsCallRequest,
+    responses(
+        (status = 200, description = "Ops call queued", body = OpsCallResponse),
+        (status = 401, description = "Invalid bearer token"),
+        (status = 503, description = "No user operation broadcaster"),
+    )
+)]
+pub async fn submit_ops_call<DB: DatabaseRef + DatabaseCommit + Send + Sync + Clone + 'static>(
+    State(app_state): State<AppState<DB>>,
+    headers: HeaderMap,
+    Json(request): Json<OpsCallRequest>,
+) -> Result<Json<OpsCallResponse>, (StatusCode, String)> {
+    require_auth(app_state.auth_token.as_deref(), &headers)?;
+    let ops_call =
+        OpsCall::new(request.to, request.value.unwrap_or_default(), request.data, request.label.unwrap_or_else(|| "manual".to_string()));
+    info!(label = ops_call.label, to = %ops_call.to, value = %ops_call.value, "Ops call submitted");
+
+    match app_state.bc.ops_call_channel().send(MessageOpsCall::new(ops_call)) {
+        Ok(receivers) => Ok(Json(OpsCallResponse { receivers })),
+        Err(_) => Err((StatusCode::SERVICE_UNAVAILABLE, "No user operation broadcaster".to_string())),
+    }
+}
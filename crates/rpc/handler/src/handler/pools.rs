@@ -1,17 +1,24 @@
+use crate::auth::require_auth;
 use crate::dto::pagination::Pagination;
-use crate::dto::pool::{MarketStats, Pool, PoolClass, PoolDetailsResponse, PoolProtocol, PoolResponse};
-use crate::dto::quote::{Filter, QuoteRequest, QuoteResponse};
+use crate::dto::pool::{
+    KillSwitchEntry, KillSwitchRequest, KillSwitchResponse, MarketStats, Pool, PoolClass, PoolDetailsResponse, PoolEventEntry,
+    PoolEventsQuery, PoolEventsResponse, PoolProtocol, PoolResponse, PoolSort,
+};
+use crate::dto::quote::{Filter, QuoteRequest, QuoteResponse, RouteQuoteResponse};
 use alloy_primitives::Address;
 use axum::extract::{Path, Query, State};
-use axum::http::StatusCode;
+use axum::http::{HeaderMap, StatusCode};
 use axum::Json;
 use eyre::ErrReport;
 use loom_evm_utils::error_handler::internal_error;
 use loom_rpc_state::AppState;
-use loom_types_entities::{PoolId, PoolWrapper};
+use loom_types_entities::{KillSwitchRecord, Market, PoolEvent, PoolId, Quoter};
+use loom_types_events::MarketEvents;
 use revm::primitives::Env;
 use revm::{DatabaseCommit, DatabaseRef};
 use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::warn;
 
 /// Get latest block
 ///
@@ -33,44 +40,46 @@ pub async fn pools<DB: DatabaseRef + DatabaseCommit + Send + Sync + Clone + 'sta
     pagination: Query<Pagination>,
     filter: Query<Filter>,
 ) -> Result<Json<PoolResponse>, (StatusCode, String)> {
-    let pools: Vec<(Address, PoolWrapper)> = app_state
-        .bc
-        .market()
-        .read()
-        .await
+    let market = app_state.bc.market();
+    let market_guard = market.read().await;
+
+    let mut pools: Vec<Pool> = market_guard
         .pools()
         .iter()
         .filter(|(_, pool)| match &filter.protocol {
             None => true,
             Some(protocol) => pool.pool.get_protocol() == protocol.into(),
         })
-        .skip(pagination.start())
-        .take(pagination.limit)
-        .map(|(address, pool)| (address.address_or_zero(), pool.clone()))
+        .filter(|(_, pool)| filter.token.is_none_or(|token| pool.get_tokens().contains(&token)))
+        .filter(|(pool_id, _)| filter.disabled.is_none_or(|disabled| market_guard.is_pool_disabled(pool_id) == disabled))
+        .map(|(pool_id, pool)| {
+            let paths_idx = market_guard.pool_swap_paths_idx_vec(pool_id).unwrap_or_default();
+            let score = paths_idx
+                .iter()
+                .filter_map(|idx| market_guard.swap_paths().get_path_by_idx(*idx).and_then(|path| path.score))
+                .reduce(f64::max);
+            Pool {
+                address: pool_id.address_or_zero(),
+                fee: pool.pool.get_fee(),
+                tokens: pool.pool.get_tokens(),
+                protocol: PoolProtocol::from(pool.pool.get_protocol()),
+                pool_class: PoolClass::from(pool.get_class()),
+                disabled: market_guard.is_pool_disabled(pool_id),
+                paths: paths_idx.len(),
+                score,
+            }
+        })
         .collect();
+    drop(market_guard);
 
-    let mut ret = vec![];
-    for (pool_address, pool) in pools {
-        ret.push(Pool {
-            address: pool_address,
-            fee: pool.pool.get_fee(),
-            tokens: pool.pool.get_tokens(),
-            protocol: PoolProtocol::from(pool.pool.get_protocol()),
-            pool_class: PoolClass::from(pool.get_class()),
-        });
+    match filter.sort {
+        Some(PoolSort::Score) => pools.sort_by(|a, b| b.score.unwrap_or(f64::MIN).total_cmp(&a.score.unwrap_or(f64::MIN))),
+        Some(PoolSort::Paths) => pools.sort_by(|a, b| b.paths.cmp(&a.paths)),
+        None => {}
     }
-    let total_pools = app_state
-        .bc
-        .market()
-        .read()
-        .await
-        .pools()
-        .iter()
-        .filter(|(_, pool)| match &filter.protocol {
-            None => true,
-            Some(protocol) => pool.pool.get_protocol() == protocol.into(),
-        })
-        .count();
+
+    let total_pools = pools.len();
+    let ret = pools.into_iter().skip(pagination.start()).take(pagination.limit).collect();
 
     Ok(Json(PoolResponse { pools: ret, total: total_pools }))
 }
@@ -126,9 +135,147 @@ pub async fn pool<DB: DatabaseRef + DatabaseCommit + Send + Sync + Clone + 'stat
 pub async fn market_stats<DB: DatabaseRef + DatabaseCommit + Send + Sync + Clone + 'static>(
     State(app_state): State<AppState<DB>>,
 ) -> Result<Json<MarketStats>, (StatusCode, String)> {
-    let total_pools = app_state.bc.market().read().await.pools().len();
+    let market = app_state.bc.market();
+    let market_guard = market.read().await;
 
-    Ok(Json(MarketStats { total_pools }))
+    Ok(Json(MarketStats {
+        total_pools: market_guard.pools().len(),
+        disabled_pools: market_guard.pools().keys().filter(|pool_id| market_guard.is_pool_disabled(pool_id)).count(),
+        total_paths: market_guard.swap_paths().len(),
+        disabled_paths: market_guard.swap_paths().disabled_len(),
+    }))
+}
+
+/// Pool events
+///
+/// Get the pool lifecycle events of the market after a sequence number, the oldest first
+#[utoipa::path(
+    get,
+    path = "/pool_events",
+    tag = "market",
+    tags = [],
+    params(
+        PoolEventsQuery
+    ),
+    responses(
+        (status = 200, description = "Pool events", body = PoolEventsResponse),
+    )
+)]
+pub async fn pool_events<DB: DatabaseRef + DatabaseCommit + Send + Sync + Clone + 'static>(
+    State(app_state): State<AppState<DB>>,
+    query: Query<PoolEventsQuery>,
+) -> Result<Json<PoolEventsResponse>, (StatusCode, String)> {
+    let market = app_state.bc.market();
+    let market_guard = market.read().await;
+    let pool_events = market_guard.pool_events();
+
+    let events = pool_events
+        .since(query.since)
+        .take(query.limit)
+        .map(|record| {
+            let (pool_class, paths_added) = match &record.event {
+                PoolEvent::Added { pool_class, .. } => (Some(PoolClass::from(*pool_class)), None),
+                PoolEvent::Updated { paths_added, .. } => (None, Some(*paths_added)),
+                _ => (None, None),
+            };
+            PoolEventEntry {
+                seq: record.seq,
+                kind: record.event.kind().to_string(),
+                pool_id: record.event.pool_id().to_string(),
+                pool_class,
+                paths_added,
+            }
+        })
+        .collect();
+
+    Ok(Json(PoolEventsResponse { events, last_seq: pool_events.last_seq(), first_seq: pool_events.first_seq() }))
+}
+
+fn kill_switch_response(market: &Market) -> KillSwitchResponse {
+    let kill_switch = market.kill_switch();
+    KillSwitchResponse {
+        disabled: kill_switch.disabled().into_iter().map(PoolClass::from).collect(),
+        log: kill_switch
+            .log()
+            .iter()
+            .map(|record| KillSwitchEntry {
+                pool_class: PoolClass::from(record.pool_class),
+                disabled: record.disabled,
+                operator: record.operator.clone(),
+                reason: record.reason.clone(),
+                timestamp: record.timestamp,
+            })
+            .collect(),
+    }
+}
+
+/// Pool class kill switch
+///
+/// Get the disabled pool classes and the audit log of the toggles
+#[utoipa::path(
+    get,
+    path = "/kill_switch",
+    tag = "market",
+    tags = [],
+    responses(
+        (status = 200, description = "Disabled pool classes", body = KillSwitchResponse),
+    )
+)]
+pub async fn kill_switch<DB: DatabaseRef + DatabaseCommit + Send + Sync + Clone + 'static>(
+    State(app_state): State<AppState<DB>>,
+) -> Result<Json<KillSwitchResponse>, (StatusCode, String)> {
+    Ok(Json(kill_switch_response(&app_state.bc.market().read().await)))
+}
+
+/// Toggle a pool class
+///
+/// Disable or enable every pool of the class, e.g. during a protocol incident. Paths through the class are not
+/// searched, swaps through it are dropped before encoding and before signing and broadcast bundles through it are
+/// cancelled and not re-sent
+#[utoipa::path(
+    post,
+    path = "/kill_switch",
+    tag = "market",
+    tags = [],
+    request_body = KillSwitchRequest,
+    responses(
+        (status = 200, description = "Disabled pool classes", body = KillSwitchResponse),
+        (status = 401, description = "Invalid bearer token"),
+    )
+)]
+pub async fn toggle_kill_switch<DB: DatabaseRef + DatabaseCommit + Send + Sync + Clone + 'static>(
+    State(app_state): State<AppState<DB>>,
+    headers: HeaderMap,
+    Json(request): Json<KillSwitchRequest>,
+) -> Result<Json<KillSwitchResponse>, (StatusCode, String)> {
+    require_auth(app_state.auth_token.as_deref(), &headers)?;
+    if request.operator.trim().is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "Operator is required".to_string()));
+    }
+    let record = KillSwitchRecord {
+        pool_class: (&request.pool_class).into(),
+        disabled: request.disabled,
+        operator: request.operator,
+        reason: request.reason,
+        timestamp: SystemTime::now().duration_since(UNIX_EPOCH).map(|elapsed| elapsed.as_secs()).unwrap_or_default(),
+    };
+    warn!(
+        pool_class = %record.pool_class,
+        disabled = record.disabled,
+        operator = %record.operator,
+        reason = record.reason.as_deref().unwrap_or_default(),
+        "Pool class kill switch toggled"
+    );
+
+    let (pool_class, disabled) = (record.pool_class, record.disabled);
+    let market = app_state.bc.market();
+    let mut market_guard = market.write().await;
+    if market_guard.toggle_pool_class(record) && disabled {
+        if let Err(error) = app_state.bc.market_events_channel().send(MarketEvents::PoolClassDisabled { pool_class }) {
+            warn!(%pool_class, %error, "Broadcast bundles through the disabled pool class are not cancelled");
+        }
+    }
+    Ok(Json(kill_switch_response(&market_guard)))
 }
 
 /// Get a quote
@@ -171,3 +318,40 @@ pub async fn pool_quote<DB: DatabaseRef<Error = ErrReport> + DatabaseCommit + Se
         }
     }
 }
+
+/// Get a route quote
+///
+/// Get the best route through a pool of both tokens or the loaded paths and its out amount at the latest block
+#[utoipa::path(
+    post,
+    path = "/quote",
+    tag = "market",
+    tags = [],
+    request_body = QuoteRequest,
+    responses(
+        (status = 200, description = "Best route", body = RouteQuoteResponse),
+        (status = 404, description = "No route between the tokens"),
+    )
+)]
+pub async fn route_quote<DB: DatabaseRef<Error = ErrReport> + DatabaseCommit + Send + Sync + Clone + 'static>(
+    State(app_state): State<AppState<DB>>,
+    Json(quote_request): Json<QuoteRequest>,
+) -> Result<Json<RouteQuoteResponse>, (StatusCode, String)> {
+    let market_view = app_state.bc.market_view().latest();
+    let block_timestamp = app_state.bc.latest_block().read().await.block_header.as_ref().map_or(0, |block_header| block_header.timestamp);
+    // pools of the view have their state in the market state, which is at the block of the view or a later one
+    let market_state = app_state.state.market_state().read().await;
+    let quoter = Quoter::new(market_view.market(), &market_state.state_db, market_state.block_number).with_block_timestamp(block_timestamp);
+    let quote_result = quoter.quote_exact_in(quote_request.token_address_from, quote_request.token_address_to, quote_request.amount_in);
+    drop(market_state);
+    match quote_result {
+        Err(err) => Err((StatusCode::NOT_FOUND, err.to_string())),
+        Ok(quote) => Ok(Json(RouteQuoteResponse {
+            out_amount: quote.amount_out,
+            gas_used: quote.gas_used,
+            block_number: quote.block_number,
+            tokens: quote.swap_line.tokens().iter().map(|token| token.get_address()).collect(),
+            pools: quote.pool_ids().iter().map(|pool_id| pool_id.to_string()).collect(),
+        })),
+    }
+}
@@ -0,0 +1,168 @@
+use crate::auth::require_auth;
+use crate::dto::score::{ScoreAdjustmentEntry, ScoreAdjustmentsRequest, ScoreAdjustmentsResponse, DEFAULT_HALF_LIFE_SECS};
+use alloy_primitives::{Address, B256};
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode};
+use axum::Json;
+use loom_rpc_state::AppState;
+use loom_types_entities::{path_key, Market, PoolId, ScoreAdjustment, ScoreAdjustments};
+use revm::{DatabaseCommit, DatabaseRef};
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::info;
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|elapsed| elapsed.as_secs()).unwrap_or_default()
+}
+
+fn entry(key: String, adjustment: &ScoreAdjustment, now: u64) -> ScoreAdjustmentEntry {
+    ScoreAdjustmentEntry {
+        key,
+        value: adjustment.value,
+        current: adjustment.decayed(now),
+        half_life_secs: adjustment.half_life_secs,
+        source: adjustment.source.clone(),
+        timestamp: adjustment.timestamp,
+    }
+}
+
+/// Hash the market indexes the path with, looked up among the paths of the first pool of the key
+fn path_hash(market: &Market, key: &str) -> Option<u64> {
+    let pool = key.split('>').nth(1)?;
+    let pool_id = match pool.parse::<Address>() {
+        Ok(address) => PoolId::Address(address),
+        Err(_) => PoolId::Bytes32(pool.parse::<B256>().ok()?),
+    };
+    let swap_paths = market.swap_paths();
+    swap_paths
+        .pool_paths
+        .get(&pool_id)?
+        .iter()
+        .filter_map(|path_idx| swap_paths.get_path_by_idx(*path_idx))
+        .find(|swap_path| path_key(swap_path) == key)
+        .map(|swap_path| swap_path.get_hash())
+}
+
+fn score_adjustments_response(score_adjustments: &ScoreAdjustments, market: &Market, now: u64) -> ScoreAdjustmentsResponse {
+    let mut pools: Vec<ScoreAdjustmentEntry> =
+        score_adjustments.pools().iter().map(|(pool_id, adjustment)| entry(pool_id.to_string(), adjustment, now)).collect();
+    // paths removed from the market since they were adjusted are listed by their hash
+    let swap_paths = market.swap_paths();
+    let mut paths: Vec<ScoreAdjustmentEntry> = score_adjustments
+        .paths()
+        .iter()
+        .map(|(path_hash, adjustment)| {
+            let key = swap_paths.get_path_by_hash(*path_hash).map(path_key).unwrap_or_else(|| path_hash.to_string());
+            entry(key, adjustment, now)
+        })
+        .collect();
+    pools.sort_by(|a, b| a.key.cmp(&b.key));
+    paths.sort_by(|a, b| a.key.cmp(&b.key));
+    ScoreAdjustmentsResponse { pools, paths }
+}
+
+/// Score adjustments
+///
+/// Get the score adjustments of pools and paths pushed by external signal sources with their decayed values
+#[utoipa::path(
+    get,
+    path = "/score_adjustments",
+    tag = "market",
+    tags = [],
+    responses(
+        (status = 200, description = "Score adjustments", body = ScoreAdjustmentsResponse),
+    )
+)]
+pub async fn score_adjustments<DB: DatabaseRef + DatabaseCommit + Send + Sync + Clone + 'static>(
+    State(app_state): State<AppState<DB>>,
+) -> Result<Json<ScoreAdjustmentsResponse>, (StatusCode, String)> {
+    let market_guard = app_state.bc.market().read().await;
+    let score_adjustments = market_guard.score_adjustments().load();
+    Ok(Json(score_adjustments_response(&score_adjustments, &market_guard, now())))
+}
+
+/// Push score adjustments
+///
+/// Adjust the scores of pools and paths, e.g. from the predictions of an ML model. The decayed adjustments are added to
+/// the historical scores of the paths ordering the searches from the next block. Paths are given by their key in the market
+/// snapshots
+#[utoipa::path(
+    post,
+    path = "/score_adjustments",
+    tag = "market",
+    tags = [],
+    request_body = ScoreAdjustmentsRequest,
+    responses(
+        (status = 200, description = "Score adjustments", body = ScoreAdjustmentsResponse),
+        (status = 400, description = "Invalid or unknown pool or path"),
+        (status = 401, description = "Invalid bearer token"),
+    )
+)]
+pub async fn push_score_adjustments<DB: DatabaseRef + DatabaseCommit + Send + Sync + Clone + 'static>(
+    State(app_state): State<AppState<DB>>,
+    headers: HeaderMap,
+    Json(request): Json<ScoreAdjustmentsRequest>,
+) -> Result<Json<ScoreAdjustmentsResponse>, (StatusCode, String)> {
+    require_auth(app_state.auth_token.as_deref(), &headers)?;
+    if let Some(update) = request.adjustments.iter().find(|update| update.pool.is_some() == update.path.is_some()) {
+        return Err((StatusCode::BAD_REQUEST, format!("Exactly one of pool and path is required : {update:?}")));
+    }
+    if request.adjustments.iter().any(|update| !update.value.is_finite()) {
+        return Err((StatusCode::BAD_REQUEST, "Adjustment values must be finite".to_string()));
+    }
+    info!(adjustments = request.adjustments.len(), replace = request.replace, "Score adjustments pushed");
+
+    // the adjustments are replaced next to the market, the market is only read to resolve the paths
+    let now = now();
+    let market_guard = app_state.bc.market().read().await;
+    let mut path_hashes: HashMap<String, u64> = HashMap::new();
+    for path in request.adjustments.iter().filter_map(|update| update.path.as_ref()) {
+        match path_hash(&market_guard, path) {
+            Some(path_hash) => path_hashes.insert(path.clone(), path_hash),
+            None => return Err((StatusCode::BAD_REQUEST, format!("Unknown path : {path}"))),
+        };
+    }
+
+    let score_adjustments = market_guard.score_adjustments().update(|score_adjustments| {
+        if request.replace {
+            score_adjustments.clear();
+        }
+        for update in request.adjustments {
+            let adjustment = ScoreAdjustment {
+                value: update.value,
+                half_life_secs: update.half_life_secs.unwrap_or(DEFAULT_HALF_LIFE_SECS),
+                source: update.source,
+                timestamp: now,
+            };
+            if let Some(pool) = update.pool {
+                score_adjustments.set_pool(PoolId::Address(pool), adjustment);
+            } else if let Some(path_hash) = update.path.and_then(|path| path_hashes.get(&path).copied()) {
+                score_adjustments.set_path(path_hash, adjustment);
+            }
+        }
+        score_adjustments.prune(now);
+    });
+
+    Ok(Json(score_adjustments_response(&score_adjustments, &market_guard, now)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use loom_types_entities::{MockPool, SwapPath, Token};
+
+    #[test]
+    fn test_path_hash() {
+        let (token0, token1) = (Address::repeat_byte(1), Address::repeat_byte(2));
+        let swap_path = SwapPath::new(
+            vec![Token::new(token0), Token::new(token1), Token::new(token0)],
+            vec![MockPool::new(token0, token1, Address::repeat_byte(0x10)), MockPool::new(token0, token1, Address::repeat_byte(0x11))],
+        );
+        let mut market = Market::default();
+        market.add_paths(vec![swap_path.clone()]);
+
+        assert_eq!(path_hash(&market, &path_key(&swap_path)), Some(swap_path.get_hash()));
+        assert_eq!(path_hash(&market, &path_key(&swap_path).replace("0x1111", "0x1212")), None);
+        assert_eq!(path_hash(&market, "not a path"), None);
+    }
+}
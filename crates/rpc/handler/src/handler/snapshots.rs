@@ -0,0 +1,64 @@
+use crate::dto::pagination::Pagination;
+use crate::dto::snapshot::{MarketSnapshotDiffRequest, MarketSnapshotPage};
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use axum::Json;
+use loom_rpc_state::AppState;
+use loom_types_entities::{MarketSnapshot, MarketSnapshotDiff};
+use revm::{DatabaseCommit, DatabaseRef};
+
+fn current_snapshot<DB: DatabaseRef + DatabaseCommit + Send + Sync + Clone + 'static>(app_state: &AppState<DB>) -> MarketSnapshot {
+    let market_view = app_state.bc.market_view().latest();
+    MarketSnapshot::new(market_view.market(), market_view.block_number())
+}
+
+/// Market snapshot
+///
+/// Get a page of the pools, swap path scores and token classifications of the latest market view. The page and limit
+/// apply to the pools, the tokens and the paths alike
+#[utoipa::path(
+    get,
+    path = "/snapshot",
+    tag = "market",
+    tags = [],
+    params(
+        Pagination
+    ),
+    responses(
+        (status = 200, description = "Market snapshot page", body = MarketSnapshotPage),
+    )
+)]
+pub async fn market_snapshot<DB: DatabaseRef + DatabaseCommit + Send + Sync + Clone + 'static>(
+    State(app_state): State<AppState<DB>>,
+    pagination: Query<Pagination>,
+) -> Result<Json<MarketSnapshotPage>, (StatusCode, String)> {
+    let market_view = app_state.bc.market_view().latest();
+    let market = market_view.market();
+    Ok(Json(MarketSnapshotPage {
+        snapshot: MarketSnapshot::new_page(market, market_view.block_number(), pagination.start(), pagination.limit),
+        pools_total: market.pools().len(),
+        tokens_total: market.tokens().len(),
+        paths_total: market.swap_paths().paths.len(),
+    }))
+}
+
+/// Diff market snapshots
+///
+/// Pools added and removed, swap path score changes and tokens reclassified between two snapshots
+#[utoipa::path(
+    post,
+    path = "/snapshot/diff",
+    tag = "market",
+    tags = [],
+    request_body = MarketSnapshotDiffRequest,
+    responses(
+        (status = 200, description = "Changes between the snapshots", body = Object),
+    )
+)]
+pub async fn market_snapshot_diff<DB: DatabaseRef + DatabaseCommit + Send + Sync + Clone + 'static>(
+    State(app_state): State<AppState<DB>>,
+    Json(request): Json<MarketSnapshotDiffRequest>,
+) -> Result<Json<MarketSnapshotDiff>, (StatusCode, String)> {
+    let after = request.after.unwrap_or_else(|| current_snapshot(&app_state));
+    Ok(Json(request.before.diff(&after)))
+}
@@ -0,0 +1,211 @@
+use crate::auth::require_auth;
+use crate::dto::swap::{ManualSwapRequest, ManualSwapResponse};
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode};
+use axum::Json;
+use eyre::ErrReport;
+use loom_evm_utils::evm_env::env_for_block;
+use loom_rpc_state::{AppState, ManualSwapConfig};
+use loom_types_entities::{Market, Swap, SwapAmountType, SwapLine, SwapPath};
+use loom_types_events::{MessageSwapCompose, SwapComposeData, TxComposeData};
+use revm::{DatabaseCommit, DatabaseRef};
+use tracing::info;
+
+pub const MANUAL_SWAP_ORIGIN: &str = "manual";
+
+fn check_access(
+    auth_token: Option<&str>,
+    manual_swaps: Option<&ManualSwapConfig>,
+    headers: &HeaderMap,
+    request: &ManualSwapRequest,
+) -> Result<(), (StatusCode, String)> {
+    let Some(manual_swaps) = manual_swaps else {
+        return Err((StatusCode::SERVICE_UNAVAILABLE, "Manual swaps are not enabled".to_string()));
+    };
+    require_auth(auth_token, headers)?;
+    if !manual_swaps.is_recipient_allowed(request.recipient) {
+        return Err((StatusCode::FORBIDDEN, "Recipient is not allowed".to_string()));
+    }
+    Ok(())
+}
+
+fn build_swap_path(market: &Market, request: &ManualSwapRequest) -> Result<SwapPath, (StatusCode, String)> {
+    if request.tokens.len() < 2 || request.pools.len() + 1 != request.tokens.len() {
+        return Err((StatusCode::BAD_REQUEST, "Route needs one pool less than tokens".to_string()));
+    }
+
+    let mut swap_path = SwapPath::default();
+    for (idx, pool_id) in request.pools.iter().enumerate() {
+        let token_from = market
+            .get_token(&request.tokens[idx])
+            .ok_or_else(|| (StatusCode::NOT_FOUND, format!("Token not found : {}", request.tokens[idx])))?;
+        let token_to = market
+            .get_token(&request.tokens[idx + 1])
+            .ok_or_else(|| (StatusCode::NOT_FOUND, format!("Token not found : {}", request.tokens[idx + 1])))?;
+        let pool = market.get_pool(pool_id).ok_or_else(|| (StatusCode::NOT_FOUND, format!("Pool not found : {pool_id}")))?;
+        if market.is_pool_disabled(pool_id) {
+            return Err((StatusCode::BAD_REQUEST, format!("Pool is disabled : {pool_id}")));
+        }
+        if !pool
+            .get_swap_directions()
+            .iter()
+            .any(|direction| *direction.from() == token_from.get_address() && *direction.to() == token_to.get_address())
+        {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!("Pool {pool_id} cannot swap {} to {}", token_from.get_address(), token_to.get_address()),
+            ));
+        }
+        swap_path.push_swap_hope(token_from, token_to, pool.clone()).map_err(|error| (StatusCode::BAD_REQUEST, error.to_string()))?;
+    }
+    Ok(swap_path)
+}
+
+/// Execute a route
+///
+/// Calculate the route on the latest state and send it to the router, it is encoded, preflight checked and simulated
+/// like any other swap before the broadcast. Requires `webserver.manual_swaps` and the bearer token of the webserver,
+/// the recipient must be allowed and receives the amount out of the route, checked against the min amount out on chain
+#[utoipa::path(
+    post,
+    path = "/",
+    tag = "swap",
+    tags = [],
+    request_body = ManualSwapRequest,
+    responses(
+        (status = 200, description = "Calculated route", body = ManualSwapResponse),
+        (status = 401, description = "Invalid bearer token"),
+        (status = 403, description = "Recipient is not allowed"),
+    )
+)]
+pub async fn manual_swap<DB: DatabaseRef<Error = ErrReport> + DatabaseCommit + Send + Sync + Clone + 'static>(
+    State(app_state): State<AppState<DB>>,
+    headers: HeaderMap,
+    Json(request): Json<ManualSwapRequest>,
+) -> Result<Json<ManualSwapResponse>, (StatusCode, String)> {
+    check_access(app_state.auth_token.as_deref(), app_state.manual_swaps.as_ref(), &headers, &request)?;
+    let swap_path = build_swap_path(&app_state.bc.market().read().await, &request)?;
+
+    let latest_block = app_state
+        .bc
+        .latest_block()
+        .read()
+        .await
+        .block_header
+        .clone()
+        .ok_or_else(|| (StatusCode::SERVICE_UNAVAILABLE, "Latest block is not known yet".to_string()))?;
+    let target_block = latest_block.number + 1;
+    let target_timestamp = app_state.bc.chain_parameters().next_block_timestamp(latest_block.timestamp);
+    let target_base_fee = app_state.bc.chain_parameters().calc_next_block_base_fee(
+        latest_block.gas_used,
+        latest_block.gas_limit,
+        latest_block.base_fee_per_gas.unwrap_or_default(),
+    );
+
+    let db = app_state.state.market_state().read().await.state_db.clone();
+    let mut swap_line = SwapLine::from(swap_path);
+    let (amount_out, gas_used, calculation_results) = swap_line
+        .calculate_with_in_amount(&db, env_for_block(target_block, target_timestamp), request.amount_in)
+        .map_err(|error| (StatusCode::UNPROCESSABLE_ENTITY, format!("Route calculation failed : {}", error.msg)))?;
+    if request.min_amount_out.is_some_and(|min_amount_out| amount_out < min_amount_out) {
+        return Err((StatusCode::UNPROCESSABLE_ENTITY, format!("Amount out {amount_out} is below the minimal amount out")));
+    }
+
+    if request.dry_run {
+        return Ok(Json(ManualSwapResponse { amount_out, gas_used, target_block, submitted: false }));
+    }
+    let Some(swap_compose_tx) = &app_state.swap_compose_tx else {
+        return Err((StatusCode::SERVICE_UNAVAILABLE, "Swap execution is not enabled".to_string()));
+    };
+
+    swap_line.amount_in = SwapAmountType::Set(request.amount_in);
+    swap_line.amount_out = SwapAmountType::Set(amount_out);
+    swap_line.gas_used = Some(gas_used);
+    swap_line.calculation_results = calculation_results;
+    swap_line.swap_to = request.recipient;
+    // only recipients known to have no code are paid out as native ETH
+    swap_line.native_payout = request
+        .recipient
+        .is_some_and(|recipient| db.basic_ref(recipient).ok().flatten().is_some_and(|account| account.is_empty_code_hash()));
+    swap_line.min_amount_out = Some(request.min_amount_out.unwrap_or(amount_out));
+    let swap = Swap::ExchangeSwapLine(swap_line);
+    info!(%swap, target_block, "Manual swap requested");
+
+    let swap_compose = SwapComposeData {
+        tx_compose: TxComposeData {
+            next_block_number: target_block,
+            next_block_timestamp: target_timestamp,
+            next_block_base_fee: target_base_fee,
+            origin: Some(MANUAL_SWAP_ORIGIN.to_string()),
+            ..TxComposeData::default()
+        },
+        swap,
+        prestate: Some(db.clone()),
+        poststate: Some(db),
+        origin: Some(MANUAL_SWAP_ORIGIN.to_string()),
+        ..SwapComposeData::default()
+    };
+    swap_compose_tx
+        .send(MessageSwapCompose::prepare(swap_compose))
+        .map_err(|error| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to submit swap : {error}")))?;
+
+    Ok(Json(ManualSwapResponse { amount_out, gas_used, target_block, submitted: true }))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloy_primitives::{Address, U256};
+    use axum::http::header::AUTHORIZATION;
+    use loom_defi_pools::UniswapV2Pool;
+    use loom_types_entities::{PoolId, Token};
+
+    fn request(tokens: Vec<Address>, pools: Vec<PoolId>, recipient: Option<Address>) -> ManualSwapRequest {
+        ManualSwapRequest { tokens, pools, amount_in: U256::from(1000), min_amount_out: None, recipient, dry_run: true }
+    }
+
+    #[test]
+    fn test_check_access() {
+        let recipient = Address::repeat_byte(1);
+        let manual_swaps = ManualSwapConfig { recipients: vec![recipient] };
+        let mut headers = HeaderMap::new();
+        headers.insert(AUTHORIZATION, "Bearer secret".parse().unwrap());
+
+        let allowed = request(vec![], vec![], Some(recipient));
+        assert_eq!(check_access(Some("secret"), None, &headers, &allowed).unwrap_err().0, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(check_access(None, Some(&manual_swaps), &headers, &allowed).unwrap_err().0, StatusCode::FORBIDDEN);
+        assert_eq!(check_access(Some("secret"), Some(&manual_swaps), &HeaderMap::new(), &allowed).unwrap_err().0, StatusCode::UNAUTHORIZED);
+        assert!(check_access(Some("secret"), Some(&manual_swaps), &headers, &allowed).is_ok());
+        // the output stays in the multicaller
+        assert!(check_access(Some("secret"), Some(&manual_swaps), &headers, &request(vec![], vec![], None)).is_ok());
+
+        let denied = request(vec![], vec![], Some(Address::repeat_byte(2)));
+        assert_eq!(check_access(Some("secret"), Some(&manual_swaps), &headers, &denied).unwrap_err().0, StatusCode::FORBIDDEN);
+    }
+
+    #[test]
+    fn test_build_swap_path() {
+        let (token0, token1, token2) = (Address::repeat_byte(1), Address::repeat_byte(2), Address::repeat_byte(3));
+        let (pool0, pool1) = (Address::repeat_byte(0x10), Address::repeat_byte(0x11));
+        let mut market = Market::default();
+        for token in [token0, token1, token2] {
+            market.add_token(Token::new(token));
+        }
+        market.add_pool(UniswapV2Pool::new_with_data(pool0, token0, token1, Address::ZERO, U256::ZERO, U256::ZERO)).unwrap();
+        market.add_pool(UniswapV2Pool::new_with_data(pool1, token1, token2, Address::ZERO, U256::ZERO, U256::ZERO)).unwrap();
+        let pools = vec![PoolId::Address(pool0), PoolId::Address(pool1)];
+
+        let swap_path = build_swap_path(&market, &request(vec![token0, token1, token2], pools.clone(), None)).unwrap();
+        assert_eq!(swap_path.pools.len(), 2);
+        assert_eq!(swap_path.tokens.iter().map(|token| token.get_address()).collect::<Vec<_>>(), vec![token0, token1, token2]);
+
+        let code = |tokens, pools| build_swap_path(&market, &request(tokens, pools, None)).unwrap_err().0;
+        assert_eq!(code(vec![token0, token1], pools.clone()), StatusCode::BAD_REQUEST);
+        assert_eq!(code(vec![token0, token2], vec![PoolId::Address(pool0)]), StatusCode::BAD_REQUEST);
+        assert_eq!(code(vec![token0, Address::repeat_byte(4)], vec![PoolId::Address(pool0)]), StatusCode::NOT_FOUND);
+        assert_eq!(code(vec![token0, token1], vec![PoolId::Address(Address::repeat_byte(0x12))]), StatusCode::NOT_FOUND);
+
+        market.deny_pool(PoolId::Address(pool1));
+        assert_eq!(code(vec![token0, token1, token2], pools), StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,43 @@
+use crate::auth::require_auth;
+use crate::dto::task::{TaskRequest, TaskResponse};
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode};
+use axum::Json;
+use loom_rpc_state::AppState;
+use loom_types_entities::PoolId;
+use loom_types_events::LoomTask;
+use revm::{DatabaseCommit, DatabaseRef};
+use tracing::info;
+
+/// Send a maintenance task
+///
+/// Refresh the state of a pool, rebuild the swap paths of a token or reload the pools loading config
+#[utoipa::path(
+    post,
+    path = "/tasks",
+    tag = "market",
+    tags = [],
+    request_body = TaskRequest,
+    responses(
+        (status = 200, description = "Task sent", body = TaskResponse),
+        (status = 401, description = "Invalid bearer token"),
+    )
+)]
+pub async fn market_task<DB: DatabaseRef + DatabaseCommit + Send + Sync + Clone + 'static>(
+    State(app_state): State<AppState<DB>>,
+    headers: HeaderMap,
+    Json(request): Json<TaskRequest>,
+) -> Result<Json<TaskResponse>, (StatusCode, String)> {
+    require_auth(app_state.auth_token.as_deref(), &headers)?;
+    info!(?request, "Maintenance task requested");
+    let task = match request {
+        TaskRequest::RefreshPoolState { pool } => LoomTask::RefreshPoolState(PoolId::Address(pool)),
+        TaskRequest::RebuildPathsForToken { token } => LoomTask::RebuildPathsForToken(token),
+        TaskRequest::ReloadConfig => LoomTask::ReloadConfig,
+    };
+
+    match app_state.bc.tasks_channel().send(task) {
+        Ok(receivers) => Ok(Json(TaskResponse { receivers })),
+        Err(_) => Err((StatusCode::SERVICE_UNAVAILABLE, "No pool loader is running".to_string())),
+    }
+}
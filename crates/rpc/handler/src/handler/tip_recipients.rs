@@ -0,0 +1,86 @@
+use crate::auth::require_auth;
+use crate::dto::tip_recipients::{TipRecipientsResponse, TipRecipientsUpdateRequest};
+use alloy_primitives::{Address, PrimitiveSignature};
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode};
+use axum::Json;
+use loom_rpc_state::AppState;
+use loom_types_entities::{TipRecipients, TipRecipientsUpdate};
+use revm::{DatabaseCommit, DatabaseRef};
+use tracing::{info, warn};
+
+fn tip_recipients_response(tip_recipients: &TipRecipients) -> TipRecipientsResponse {
+    TipRecipientsResponse {
+        chain_id: tip_recipients.chain_id(),
+        verifying_contract: tip_recipients.verifying_contract(),
+        admin: tip_recipients.admin(),
+        default: tip_recipients.default_recipient().to_string(),
+        builders: tip_recipients.builders().iter().map(|(builder, recipient)| (builder.clone(), recipient.to_string())).collect(),
+        nonce: tip_recipients.nonce(),
+    }
+}
+
+/// Tip recipients
+///
+/// Get the tip recipients of the builders and the nonce of the last update
+#[utoipa::path(
+    get,
+    path = "/tip_recipients",
+    tag = "execution",
+    tags = [],
+    responses(
+        (status = 200, description = "Tip recipients", body = TipRecipientsResponse),
+    )
+)]
+pub async fn tip_recipients<DB: DatabaseRef + DatabaseCommit + Send + Sync + Clone + 'static>(
+    State(app_state): State<AppState<DB>>,
+) -> Result<Json<TipRecipientsResponse>, (StatusCode, String)> {
+    let tip_recipients = app_state.bc.tip_recipients();
+    let guard = tip_recipients.read().map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Tip recipients lock poisoned".to_string()))?;
+    Ok(Json(tip_recipients_response(&guard)))
+}
+
+/// Update tip recipients
+///
+/// Replace the tip recipients of the builders by an update signed by the admin, the swaps encoded from then pay their tips
+/// to the new recipients
+#[utoipa::path(
+    post,
+    path = "/tip_recipients",
+    tag = "execution",
+    tags = [],
+    request_body = TipRecipientsUpdateRequest,
+    responses(
+        (status = 200, description = "Tip recipients", body = TipRecipientsResponse),
+        (status = 401, description = "Invalid bearer token"),
+        (status = 403, description = "Update rejected"),
+    )
+)]
+pub async fn update_tip_recipients<DB: DatabaseRef + DatabaseCommit + Send + Sync + Clone + 'static>(
+    State(app_state): State<AppState<DB>>,
+    headers: HeaderMap,
+    Json(request): Json<TipRecipientsUpdateRequest>,
+) -> Result<Json<TipRecipientsResponse>, (StatusCode, String)> {
+    require_auth(app_state.auth_token.as_deref(), &headers)?;
+    let signature = PrimitiveSignature::try_from(request.signature.as_ref())
+        .map_err(|error| (StatusCode::BAD_REQUEST, format!("Invalid signature : {error}")))?;
+    let (builders, recipients): (Vec<String>, Vec<Address>) =
+        request.builders.into_iter().map(|(builder, recipient)| (builder, recipient.into())).unzip();
+    let update = TipRecipientsUpdate {
+        nonce: request.nonce,
+        deadline: request.deadline,
+        defaultRecipient: request.default.into(),
+        builders,
+        recipients,
+    };
+
+    let tip_recipients = app_state.bc.tip_recipients();
+    let mut guard = tip_recipients.write().map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Tip recipients lock poisoned".to_string()))?;
+    if let Err(error) = guard.apply_update(update, &signature) {
+        warn!(%error, nonce = request.nonce, "Tip recipients update rejected");
+        return Err((StatusCode::FORBIDDEN, error.to_string()));
+    }
+    info!(nonce = guard.nonce(), default = %guard.default_recipient(), builders = guard.builders().len(), "Tip recipients updated");
+
+    Ok(Json(tip_recipients_response(&guard)))
+}
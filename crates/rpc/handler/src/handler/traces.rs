@@ -0,0 +1,106 @@
+use crate::auth::require_auth;
+use crate::dto::trace::{SimulationTraceSummary, SimulationTracesResponse, TraceCaptureRequest, TraceRequestEntry};
+use axum::extract::{Path, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::Json;
+use loom_rpc_state::AppState;
+use loom_types_entities::{PoolId, SimulationTrace, SimulationTraces};
+use revm::{DatabaseCommit, DatabaseRef};
+use tracing::info;
+
+fn simulation_traces_response(simulation_traces: &SimulationTraces) -> SimulationTracesResponse {
+    let requests = simulation_traces
+        .requests()
+        .iter()
+        .map(|request| TraceRequestEntry { pool: request.pool.map(|pool_id| pool_id.to_string()), remaining: request.remaining })
+        .collect();
+    let traces = simulation_traces
+        .traces()
+        .rev()
+        .map(|trace| SimulationTraceSummary {
+            id: trace.id,
+            block_number: trace.block_number,
+            swap: trace.swap.clone(),
+            gas_used: trace.gas_used,
+            error: trace.error.clone(),
+        })
+        .collect();
+    SimulationTracesResponse { requests, traces }
+}
+
+/// Simulation traces
+///
+/// Get the pending trace requests and the summaries of the captured simulation traces
+#[utoipa::path(
+    get,
+    path = "/simulation_traces",
+    tag = "market",
+    tags = [],
+    responses(
+        (status = 200, description = "Trace requests and captured traces", body = SimulationTracesResponse),
+    )
+)]
+pub async fn simulation_traces<DB: DatabaseRef + DatabaseCommit + Send + Sync + Clone + 'static>(
+    State(app_state): State<AppState<DB>>,
+) -> Result<Json<SimulationTracesResponse>, (StatusCode, String)> {
+    Ok(Json(simulation_traces_response(&app_state.bc.simulation_traces().read().await)))
+}
+
+/// Request simulation traces
+///
+/// Trace the next simulations of swaps through a pool with the call tree, the gas of every frame and the storage
+/// writes, e.g. to diagnose why an encoded sequence uses more gas than expected
+#[utoipa::path(
+    post,
+    path = "/simulation_traces",
+    tag = "market",
+    tags = [],
+    request_body = TraceCaptureRequest,
+    responses(
+        (status = 200, description = "Trace requests and captured traces", body = SimulationTracesResponse),
+        (status = 401, description = "Invalid bearer token"),
+    )
+)]
+pub async fn request_simulation_traces<DB: DatabaseRef + DatabaseCommit + Send + Sync + Clone + 'static>(
+    State(app_state): State<AppState<DB>>,
+    headers: HeaderMap,
+    Json(request): Json<TraceCaptureRequest>,
+) -> Result<Json<SimulationTracesResponse>, (StatusCode, String)> {
+    require_auth(app_state.auth_token.as_deref(), &headers)?;
+    let count = request.count.unwrap_or(1);
+    if count == 0 {
+        return Err((StatusCode::BAD_REQUEST, "Count must be positive".to_string()));
+    }
+    info!(pool = ?request.pool, count, "Simulation traces requested");
+
+    let simulation_traces = app_state.bc.simulation_traces();
+    let mut simulation_traces_guard = simulation_traces.write().await;
+    simulation_traces_guard.request(request.pool.map(PoolId::Address), count);
+
+    Ok(Json(simulation_traces_response(&simulation_traces_guard)))
+}
+
+/// Simulation trace
+///
+/// Get a captured simulation trace with its call tree and storage writes
+#[utoipa::path(
+    get,
+    path = "/simulation_traces/{id}",
+    tag = "market",
+    tags = [],
+    params(
+        ("id" = u64, Path, description = "Id of the trace"),
+    ),
+    responses(
+        (status = 200, description = "Simulation trace", body = Object),
+    )
+)]
+pub async fn simulation_trace<DB: DatabaseRef + DatabaseCommit + Send + Sync + Clone + 'static>(
+    State(app_state): State<AppState<DB>>,
+    Path(id): Path<u64>,
+) -> Result<Json<SimulationTrace>, (StatusCode, String)> {
+    match app_state.bc.simulation_traces().read().await.get(id) {
+        Some(trace) => Ok(Json(trace.clone())),
+        None => Err((StatusCode::NOT_FOUND, "Trace not found".to_string())),
+    }
+}
@@ -1,7 +1,8 @@
 pub use router::router;
 pub use web_actor::WebServerActor;
 
-mod dto;
+mod auth;
+pub mod dto;
 mod handler;
 mod openapi;
 mod router;
@@ -1,17 +1,58 @@
 use crate::dto::block::BlockHeader;
+use crate::dto::ops_call::OpsCallRequest;
+use crate::dto::ops_call::OpsCallResponse;
+use crate::dto::pool::KillSwitchEntry;
+use crate::dto::pool::KillSwitchRequest;
+use crate::dto::pool::KillSwitchResponse;
 use crate::dto::pool::MarketStats;
 use crate::dto::pool::Pool;
 use crate::dto::pool::PoolClass;
 use crate::dto::pool::PoolDetailsResponse;
+use crate::dto::pool::PoolEventEntry;
+use crate::dto::pool::PoolEventsResponse;
 use crate::dto::pool::PoolProtocol;
 use crate::dto::pool::PoolResponse;
+use crate::dto::pool::PoolSort;
 use crate::dto::quote::QuoteRequest;
 use crate::dto::quote::QuoteResponse;
+use crate::dto::quote::RouteQuoteResponse;
+use crate::dto::score::ScoreAdjustmentEntry;
+use crate::dto::score::ScoreAdjustmentUpdate;
+use crate::dto::score::ScoreAdjustmentsRequest;
+use crate::dto::score::ScoreAdjustmentsResponse;
+use crate::dto::snapshot::MarketSnapshotDiffRequest;
+use crate::dto::snapshot::MarketSnapshotPage;
+use crate::dto::swap::ManualSwapRequest;
+use crate::dto::swap::ManualSwapResponse;
+use crate::dto::task::TaskRequest;
+use crate::dto::task::TaskResponse;
+use crate::dto::tip_recipients::TipRecipientsResponse;
+use crate::dto::tip_recipients::TipRecipientsUpdateRequest;
+use crate::dto::trace::SimulationTraceSummary;
+use crate::dto::trace::SimulationTracesResponse;
+use crate::dto::trace::TraceCaptureRequest;
+use crate::dto::trace::TraceRequestEntry;
 use crate::handler::blocks::__path_latest_block;
+use crate::handler::ops_calls::__path_submit_ops_call;
+use crate::handler::pools::__path_kill_switch;
 use crate::handler::pools::__path_market_stats;
 use crate::handler::pools::__path_pool;
+use crate::handler::pools::__path_pool_events;
 use crate::handler::pools::__path_pool_quote;
 use crate::handler::pools::__path_pools;
+use crate::handler::pools::__path_route_quote;
+use crate::handler::pools::__path_toggle_kill_switch;
+use crate::handler::scores::__path_push_score_adjustments;
+use crate::handler::scores::__path_score_adjustments;
+use crate::handler::snapshots::__path_market_snapshot;
+use crate::handler::snapshots::__path_market_snapshot_diff;
+use crate::handler::swaps::__path_manual_swap;
+use crate::handler::tasks::__path_market_task;
+use crate::handler::tip_recipients::__path_tip_recipients;
+use crate::handler::tip_recipients::__path_update_tip_recipients;
+use crate::handler::traces::__path_request_simulation_traces;
+use crate::handler::traces::__path_simulation_trace;
+use crate::handler::traces::__path_simulation_traces;
 use utoipa::OpenApi;
 
 #[derive(OpenApi)]
@@ -26,20 +67,52 @@ pub struct BlockApi;
 
 #[derive(OpenApi)]
 #[openapi(
-    paths(pool, pools, pool_quote, market_stats),
+    paths(
+        pool, pools, pool_quote, route_quote, market_stats, kill_switch, toggle_kill_switch, pool_events, market_snapshot,
+        market_snapshot_diff, market_task, score_adjustments, push_score_adjustments, simulation_traces, request_simulation_traces,
+        simulation_trace
+    ),
     tags(
         (name = "market", description = "Market")
     ),
-    components(schemas(PoolResponse, PoolDetailsResponse, Pool, PoolClass, PoolProtocol, MarketStats, QuoteRequest, QuoteResponse))
+    components(schemas(
+        PoolResponse, PoolDetailsResponse, Pool, PoolClass, PoolProtocol, PoolSort, MarketStats, QuoteRequest, QuoteResponse,
+        RouteQuoteResponse, KillSwitchRequest, KillSwitchResponse, KillSwitchEntry, PoolEventsResponse, PoolEventEntry,
+        MarketSnapshotDiffRequest, MarketSnapshotPage, TaskRequest, TaskResponse, ScoreAdjustmentsRequest, ScoreAdjustmentUpdate,
+        ScoreAdjustmentsResponse, ScoreAdjustmentEntry, TraceCaptureRequest, SimulationTracesResponse, SimulationTraceSummary,
+        TraceRequestEntry
+    ))
 )]
 pub struct MarketApi;
 
+#[derive(OpenApi)]
+#[openapi(
+    paths(manual_swap),
+    tags(
+        (name = "swap", description = "Manual swaps")
+    ),
+    components(schemas(ManualSwapRequest, ManualSwapResponse))
+)]
+pub struct SwapApi;
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(tip_recipients, update_tip_recipients, submit_ops_call),
+    tags(
+        (name = "execution", description = "Execution")
+    ),
+    components(schemas(TipRecipientsUpdateRequest, TipRecipientsResponse, OpsCallRequest, OpsCallResponse))
+)]
+pub struct ExecutionApi;
+
 #[allow(dead_code)]
 #[derive(OpenApi)]
 #[openapi(
     nest(
         (path = "/api/v1/block/", api = BlockApi),
-        (path = "/api/v1/markets", api = MarketApi)
+        (path = "/api/v1/markets", api = MarketApi),
+        (path = "/api/v1/swaps", api = SwapApi),
+        (path = "/api/v1", api = ExecutionApi)
     )
 )]
 pub struct ApiDoc;
@@ -1,6 +1,13 @@
 use crate::handler::blocks::latest_block;
 use crate::handler::flashbots::flashbots;
-use crate::handler::pools::{market_stats, pool, pool_quote, pools};
+use crate::handler::ops_calls::submit_ops_call;
+use crate::handler::pools::{kill_switch, market_stats, pool, pool_events, pool_quote, pools, route_quote, toggle_kill_switch};
+use crate::handler::scores::{push_score_adjustments, score_adjustments};
+use crate::handler::snapshots::{market_snapshot, market_snapshot_diff};
+use crate::handler::swaps::manual_swap;
+use crate::handler::tasks::market_task;
+use crate::handler::tip_recipients::{tip_recipients, update_tip_recipients};
+use crate::handler::traces::{request_simulation_traces, simulation_trace, simulation_traces};
 use crate::handler::ws::ws_handler;
 //use crate::openapi::ApiDoc;
 use axum::routing::{get, post};
@@ -18,7 +25,10 @@ pub fn router<DB: DatabaseRef<Error = ErrReport> + DatabaseCommit + Sync + Send
             Router::new()
                 .nest("/block", router_block()) // rename to node
                 .nest("/markets", router_market())
-                .nest("/flashbots", Router::new().route("/", post(flashbots))),
+                .nest("/flashbots", Router::new().route("/", post(flashbots)))
+                .nest("/swaps", Router::new().route("/", post(manual_swap)))
+                .route("/tip_recipients", get(tip_recipients).post(update_tip_recipients))
+                .route("/ops_calls", post(submit_ops_call)),
         )
         .route("/ws", get(ws_handler))
         //.merge(SwaggerUi::new("/swagger-ui").url("/api-docs/openapi.json", ApiDoc::openapi()))
@@ -34,5 +44,14 @@ pub fn router_market<DB: DatabaseRef<Error = ErrReport> + DatabaseCommit + Sync
         .route("/pools/:address", get(pool))
         .route("/pools/:address/quote", post(pool_quote))
         .route("/pools", get(pools))
+        .route("/quote", post(route_quote))
+        .route("/kill_switch", get(kill_switch).post(toggle_kill_switch))
+        .route("/pool_events", get(pool_events))
+        .route("/snapshot", get(market_snapshot))
+        .route("/snapshot/diff", post(market_snapshot_diff))
+        .route("/tasks", post(market_task))
+        .route("/score_adjustments", get(score_adjustments).post(push_score_adjustments))
+        .route("/simulation_traces", get(simulation_traces).post(request_simulation_traces))
+        .route("/simulation_traces/:id", get(simulation_trace))
         .route("/", get(market_stats))
 }
@@ -1,11 +1,12 @@
 use crate::router::router;
 use axum::Router;
 use eyre::ErrReport;
-use loom_core_actors::{Actor, ActorResult, WorkerResult};
+use loom_core_actors::{Actor, ActorResult, Broadcaster, WorkerResult};
 use loom_core_actors_macros::Consumer;
-use loom_core_blockchain::{Blockchain, BlockchainState};
-use loom_rpc_state::AppState;
+use loom_core_blockchain::{Blockchain, BlockchainState, Strategy};
+use loom_rpc_state::{AppState, ManualSwapConfig};
 use loom_storage_db::DbPool;
+use loom_types_events::MessageSwapCompose;
 use revm::{DatabaseCommit, DatabaseRef};
 use std::net::SocketAddr;
 use tokio::net::TcpListener;
@@ -13,11 +14,15 @@ use tokio_util::sync::CancellationToken;
 use tower_http::trace::{DefaultMakeSpan, TraceLayer};
 use tracing::info;
 
+#[allow(clippy::too_many_arguments)]
 pub async fn start_web_server_worker<S, DB>(
     host: String,
     extra_router: Router<S>,
     bc: Blockchain,
     state: BlockchainState<DB>,
+    swap_compose_tx: Option<Broadcaster<MessageSwapCompose<DB>>>,
+    auth_token: Option<String>,
+    manual_swaps: Option<ManualSwapConfig>,
     db_pool: DbPool,
     shutdown_token: CancellationToken,
 ) -> WorkerResult
@@ -26,7 +31,7 @@ where
     S: Clone + Send + Sync + 'static,
     Router: From<Router<S>>,
 {
-    let app_state = AppState { db: db_pool, bc, state };
+    let app_state = AppState { db: db_pool, bc, state, swap_compose_tx, auth_token, manual_swaps };
     let router = router(app_state);
     let router = router.merge(extra_router);
 
@@ -53,6 +58,9 @@ pub struct WebServerActor<S, DB: Clone + Send + Sync + 'static> {
     db_pool: DbPool,
     bc: Option<Blockchain>,
     state: Option<BlockchainState<DB>>,
+    swap_compose_tx: Option<Broadcaster<MessageSwapCompose<DB>>>,
+    auth_token: Option<String>,
+    manual_swaps: Option<ManualSwapConfig>,
 }
 
 impl<S, DB> WebServerActor<S, DB>
@@ -62,12 +70,37 @@ where
     Router: From<Router<S>>,
 {
     pub fn new(host: String, extra_router: Router<S>, db_pool: DbPool, shutdown_token: CancellationToken) -> Self {
-        Self { host, extra_router, shutdown_token, db_pool, bc: None, state: None }
+        Self {
+            host,
+            extra_router,
+            shutdown_token,
+            db_pool,
+            bc: None,
+            state: None,
+            swap_compose_tx: None,
+            auth_token: None,
+            manual_swaps: None,
+        }
     }
 
     pub fn on_bc(self, bc: &Blockchain, state: &BlockchainState<DB>) -> Self {
         Self { bc: Some(bc.clone()), state: Some(state.clone()), ..self }
     }
+
+    /// Enable manual swaps executed by the strategy
+    pub fn with_strategy(self, strategy: &Strategy<DB>) -> Self {
+        Self { swap_compose_tx: Some(strategy.swap_compose_channel()), ..self }
+    }
+
+    /// Bearer token of the routes changing the state of the bot
+    pub fn with_auth_token(self, auth_token: Option<String>) -> Self {
+        Self { auth_token, ..self }
+    }
+
+    /// Allow manual swaps paying out to the configured recipients
+    pub fn with_manual_swaps(self, manual_swaps: Option<ManualSwapConfig>) -> Self {
+        Self { manual_swaps, ..self }
+    }
 }
 
 impl<S, DB> Actor for WebServerActor<S, DB>
@@ -82,6 +115,9 @@ where
             self.extra_router.clone(),
             self.bc.clone().unwrap(),
             self.state.clone().unwrap(),
+            self.swap_compose_tx.clone(),
+            self.auth_token.clone(),
+            self.manual_swaps.clone(),
             self.db_pool.clone(),
             self.shutdown_token.clone(),
         ));
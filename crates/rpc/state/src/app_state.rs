@@ -1,10 +1,34 @@
+use alloy_primitives::Address;
+use loom_core_actors::Broadcaster;
 use loom_core_blockchain::{Blockchain, BlockchainState};
 use loom_storage_db::DbPool;
+use loom_types_events::MessageSwapCompose;
 use revm::{DatabaseCommit, DatabaseRef};
+use serde::Deserialize;
+
+/// Manual swaps through the web api, only paying out to the listed recipients
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct ManualSwapConfig {
+    #[serde(default)]
+    pub recipients: Vec<Address>,
+}
+
+impl ManualSwapConfig {
+    /// Swaps without a recipient keep the last token in the multicaller
+    pub fn is_recipient_allowed(&self, recipient: Option<Address>) -> bool {
+        recipient.is_none_or(|recipient| self.recipients.contains(&recipient))
+    }
+}
 
 #[derive(Clone)]
 pub struct AppState<DB: DatabaseRef + DatabaseCommit + Clone + Send + Sync + 'static> {
     pub db: DbPool,
     pub bc: Blockchain,
     pub state: BlockchainState<DB>,
+    /// Swap compose channel of the strategy executing manual swaps, disabled if not set
+    pub swap_compose_tx: Option<Broadcaster<MessageSwapCompose<DB>>>,
+    /// Bearer token of the routes changing the state of the bot, these routes are rejected if not set
+    pub auth_token: Option<String>,
+    /// Manual swap execution, disabled if not set
+    pub manual_swaps: Option<ManualSwapConfig>,
 }
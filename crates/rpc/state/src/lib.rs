@@ -1,3 +1,3 @@
-pub use app_state::AppState;
+pub use app_state::{AppState, ManualSwapConfig};
 
 mod app_state;
@@ -0,0 +1,258 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use alloy_primitives::{Address, BlockHash, U256};
+use eyre::{eyre, ErrReport};
+use revm::primitives::Env;
+use revm::DatabaseRef;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{debug, error, info};
+
+use loom_core_actors::{subscribe, Accessor, Actor, ActorResult, Broadcaster, Consumer, SharedState, WorkerResult};
+use loom_core_actors_macros::{Accessor, Consumer};
+use loom_core_blockchain::{Blockchain, BlockchainState};
+use loom_evm_utils::evm_env::env_for_block;
+use loom_types_blockchain::{ChainParameters, GethStateUpdateVec};
+use loom_types_entities::{BlockHistory, Market, PoolId};
+use loom_types_events::MarketEvents;
+
+use crate::archive_rows::{state_diff_rows, PoolUpdateRow};
+use crate::ArchiveWriter;
+
+const DEFAULT_CONFIRMATIONS: u64 = 2;
+
+/// Market pools with changed storage, quoted with one whole token of each side. Pools of pool managers are matched by
+/// their cells
+fn pool_update_rows<DB: DatabaseRef<Error = ErrReport>>(
+    market: &Market,
+    state: &DB,
+    env: Env,
+    block_number: u64,
+    block_timestamp: u64,
+    state_update: &GethStateUpdateVec,
+) -> Vec<PoolUpdateRow> {
+    let mut slots_changed: HashMap<PoolId, u32> = HashMap::new();
+    for tx_state_update in state_update.iter() {
+        for (address, account) in tx_state_update.iter() {
+            if market.is_pool_manager(address) {
+                for cell in account.storage.keys() {
+                    if let Some(pool_id) = market.get_pool_id_for_cell(address, &U256::from_be_slice(cell.as_slice())) {
+                        *slots_changed.entry(*pool_id).or_default() += 1;
+                    }
+                }
+            } else if !account.storage.is_empty() {
+                *slots_changed.entry(PoolId::Address(*address)).or_default() += account.storage.len() as u32;
+            }
+        }
+    }
+
+    let mut rows = Vec::new();
+    for (pool_id, slots_changed) in slots_changed.into_iter() {
+        let Some(pool) = market.get_pool(&pool_id) else { continue };
+        let tokens = pool.get_tokens();
+        let (Some(token0), Some(token1)) = (tokens.first().copied(), tokens.get(1).copied()) else { continue };
+
+        let unit_out = |token_from: Address, token_to: Address| {
+            let amount_in = market.get_token_or_default(&token_from).get_exp();
+            pool.calculate_out_amount(state, env.clone(), &token_from, &token_to, amount_in).ok().map(|(amount_out, _)| amount_out)
+        };
+
+        rows.push(PoolUpdateRow {
+            block_number,
+            block_timestamp,
+            pool_id,
+            pool_class: pool.get_class(),
+            protocol: pool.get_protocol(),
+            token0,
+            token1,
+            slots_changed,
+            unit_out_0_1: unit_out(token0, token1),
+            unit_out_1_0: unit_out(token1, token0),
+        });
+    }
+    rows
+}
+
+/// Canonical block to archive with its post state
+struct ArchiveBlock<DB> {
+    number: u64,
+    timestamp: u64,
+    state_update: GethStateUpdateVec,
+    state: DB,
+}
+
+async fn block_archive_worker<DB: DatabaseRef<Error = ErrReport> + Send + Sync + Clone + 'static>(
+    mut writer: ArchiveWriter,
+    confirmations: u64,
+    chain_parameters: ChainParameters,
+    market: SharedState<Market>,
+    block_history: SharedState<BlockHistory<DB>>,
+    market_events_rx: Broadcaster<MarketEvents>,
+) -> WorkerResult {
+    subscribe!(market_events_rx);
+
+    let mut last_archived: Option<(u64, BlockHash)> = None;
+
+    loop {
+        let market_event = match market_events_rx.recv().await {
+            Ok(market_event) => market_event,
+            Err(e) => match e {
+                RecvError::Closed => {
+                    error!("Market events txs channel closed");
+                    break Err(eyre!("MARKET_EVENTS_RX_CLOSED"));
+                }
+                RecvError::Lagged(lag) => {
+                    error!("Market events txs channel lagged by {} messages", lag);
+                    continue;
+                }
+            },
+        };
+        let block_hash = match market_event {
+            MarketEvents::BlockStateUpdate { block_hash } => block_hash,
+            _ => continue,
+        };
+
+        // blocks are archived `confirmations` blocks behind the head, a block at or below the last archived one is a
+        // reorg deeper than that and replaces the buffered rows of the old chain
+        let archive_blocks = {
+            let block_history_guard = block_history.read().await;
+            let Some(head_number) = block_history_guard.get_block_history_entry(&block_hash).map(|entry| entry.number()) else {
+                error!("Block history entry not found in block history: {:?}", block_hash);
+                continue;
+            };
+            let Some(archive_to) = head_number.checked_sub(confirmations) else { continue };
+            let archive_from = match last_archived {
+                Some((last_number, _)) if last_number < archive_to => last_number + 1,
+                Some((last_number, last_hash))
+                    if last_number == archive_to && block_history_guard.get_block_hash_for_block_number(archive_to) == Some(last_hash) =>
+                {
+                    continue
+                }
+                _ => archive_to,
+            };
+
+            let mut archive_blocks = Vec::new();
+            for block_number in archive_from..=archive_to {
+                let Some(canonical_hash) = block_history_guard.get_block_hash_for_block_number(block_number) else {
+                    error!(block_number, "Block hash not found in block history");
+                    continue;
+                };
+                let Some(entry) = block_history_guard.get_block_history_entry(&canonical_hash) else {
+                    error!("Block history entry not found in block history: {:?}", canonical_hash);
+                    continue;
+                };
+                let Some(state_update) = entry.state_update.clone() else {
+                    error!("State update not found in block history: {:?}", canonical_hash);
+                    continue;
+                };
+                let Some(state) = block_history_guard.get_block_state(&canonical_hash).cloned() else {
+                    error!("Block state not found in block history: {:?}", canonical_hash);
+                    continue;
+                };
+                last_archived = Some((block_number, canonical_hash));
+                archive_blocks.push(ArchiveBlock { number: entry.number(), timestamp: entry.timestamp(), state_update, state });
+            }
+            archive_blocks
+        };
+        if archive_blocks.is_empty() {
+            continue;
+        }
+
+        // quoting and Parquet writes are blocking, the market clone shares its pools and paths
+        let market = market.read().await.clone();
+        let chain_parameters = chain_parameters.clone();
+        writer = tokio::task::spawn_blocking(move || {
+            for block in archive_blocks {
+                let state_diffs = state_diff_rows(block.number, block.timestamp, &block.state_update);
+                let pool_updates = pool_update_rows(
+                    &market,
+                    &block.state,
+                    env_for_block(block.number + 1, chain_parameters.next_block_timestamp(block.timestamp)),
+                    block.number,
+                    block.timestamp,
+                    &block.state_update,
+                );
+                debug!(block_number = block.number, state_diffs = state_diffs.len(), pool_updates = pool_updates.len(), "Block archived");
+
+                if let Err(error) = writer.add_block(block.number, block.timestamp, state_diffs, pool_updates) {
+                    error!(%error, block_number = block.number, "Failed to write archive");
+                }
+            }
+            writer
+        })
+        .await?;
+    }
+}
+
+/// Archives state diffs and updated market pools of every canonical block to Parquet files for research. Buffered blocks
+/// are written when the actor stops
+#[derive(Accessor, Consumer)]
+pub struct BlockArchiveActor<DB: Clone + Send + Sync + 'static> {
+    base_dir: PathBuf,
+    flush_blocks: Option<usize>,
+    confirmations: u64,
+    chain_parameters: ChainParameters,
+    #[accessor]
+    market: Option<SharedState<Market>>,
+    #[accessor]
+    block_history: Option<SharedState<BlockHistory<DB>>>,
+    #[consumer]
+    market_events_rx: Option<Broadcaster<MarketEvents>>,
+}
+
+impl<DB: DatabaseRef<Error = ErrReport> + Send + Sync + Clone + 'static> BlockArchiveActor<DB> {
+    pub fn new<T: Into<PathBuf>>(base_dir: T) -> Self {
+        Self {
+            base_dir: base_dir.into(),
+            flush_blocks: None,
+            confirmations: DEFAULT_CONFIRMATIONS,
+            chain_parameters: ChainParameters::ethereum(),
+            market: None,
+            block_history: None,
+            market_events_rx: None,
+        }
+    }
+
+    pub fn with_flush_blocks(self, flush_blocks: usize) -> Self {
+        Self { flush_blocks: Some(flush_blocks), ..self }
+    }
+
+    /// Blocks behind the head a block is archived at, reorgs not deeper than that never reach the archive
+    pub fn with_confirmations(self, confirmations: u64) -> Self {
+        Self { confirmations, ..self }
+    }
+
+    pub fn on_bc(self, bc: &Blockchain, state: &BlockchainState<DB>) -> Self {
+        Self {
+            chain_parameters: bc.chain_parameters(),
+            market: Some(bc.market()),
+            block_history: Some(state.block_history()),
+            market_events_rx: Some(bc.market_events_channel()),
+            ..self
+        }
+    }
+}
+
+impl<DB: DatabaseRef<Error = ErrReport> + Send + Sync + Clone + 'static> Actor for BlockArchiveActor<DB> {
+    fn start(&self) -> ActorResult {
+        let mut writer = ArchiveWriter::new(self.base_dir.clone());
+        if let Some(flush_blocks) = self.flush_blocks {
+            writer = writer.with_flush_blocks(flush_blocks);
+        }
+        info!(base_dir = %self.base_dir.display(), "Starting block archive");
+
+        let task = tokio::task::spawn(block_archive_worker(
+            writer,
+            self.confirmations,
+            self.chain_parameters.clone(),
+            self.market.clone().unwrap(),
+            self.block_history.clone().unwrap(),
+            self.market_events_rx.clone().unwrap(),
+        ));
+        Ok(vec![task])
+    }
+
+    fn name(&self) -> &'static str {
+        "BlockArchiveActor"
+    }
+}
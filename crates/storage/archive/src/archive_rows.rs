@@ -0,0 +1,123 @@
+use std::sync::Arc;
+
+use alloy_primitives::{Address, B256, U256};
+use arrow_array::{ArrayRef, RecordBatch, StringArray, UInt32Array, UInt64Array};
+use arrow_schema::{DataType, Field, Schema};
+use eyre::Result;
+use loom_types_blockchain::GethStateUpdateVec;
+use loom_types_entities::{PoolClass, PoolId, PoolProtocol};
+
+/// Change of an account by a transaction of the block, either a storage slot or the balance and nonce
+#[derive(Clone, Debug, PartialEq)]
+pub struct StateDiffRow {
+    pub block_number: u64,
+    pub block_timestamp: u64,
+    pub tx_index: u32,
+    pub address: Address,
+    pub slot: Option<B256>,
+    pub value: Option<B256>,
+    pub balance: Option<U256>,
+    pub nonce: Option<u64>,
+}
+
+/// Market pool with storage changed by the block, quoted with one whole token in both directions on the block state
+#[derive(Clone, Debug)]
+pub struct PoolUpdateRow {
+    pub block_number: u64,
+    pub block_timestamp: u64,
+    pub pool_id: PoolId,
+    pub pool_class: PoolClass,
+    pub protocol: PoolProtocol,
+    pub token0: Address,
+    pub token1: Address,
+    pub slots_changed: u32,
+    pub unit_out_0_1: Option<U256>,
+    pub unit_out_1_0: Option<U256>,
+}
+
+/// Post state changes of the transactions of a block, one row per storage slot and one per account with changed balance
+/// or nonce
+pub fn state_diff_rows(block_number: u64, block_timestamp: u64, state_update: &GethStateUpdateVec) -> Vec<StateDiffRow> {
+    let mut rows = Vec::new();
+    for (tx_index, tx_state_update) in state_update.iter().enumerate() {
+        for (address, account) in tx_state_update.iter() {
+            let row = StateDiffRow {
+                block_number,
+                block_timestamp,
+                tx_index: tx_index as u32,
+                address: *address,
+                slot: None,
+                value: None,
+                balance: account.balance,
+                nonce: account.nonce,
+            };
+            for (slot, value) in account.storage.iter() {
+                rows.push(StateDiffRow { slot: Some(*slot), value: Some(*value), balance: None, nonce: None, ..row.clone() });
+            }
+            if row.balance.is_some() || row.nonce.is_some() {
+                rows.push(row);
+            }
+        }
+    }
+    rows
+}
+
+fn string_column<T: ToString>(values: impl Iterator<Item = T>) -> ArrayRef {
+    Arc::new(StringArray::from_iter_values(values.map(|value| value.to_string())))
+}
+
+fn optional_string_column<T: ToString>(values: impl Iterator<Item = Option<T>>) -> ArrayRef {
+    Arc::new(values.map(|value| value.map(|value| value.to_string())).collect::<StringArray>())
+}
+
+pub fn state_diffs_batch(rows: &[StateDiffRow]) -> Result<RecordBatch> {
+    let schema = Schema::new(vec![
+        Field::new("block_number", DataType::UInt64, false),
+        Field::new("block_timestamp", DataType::UInt64, false),
+        Field::new("tx_index", DataType::UInt32, false),
+        Field::new("address", DataType::Utf8, false),
+        Field::new("slot", DataType::Utf8, true),
+        Field::new("value", DataType::Utf8, true),
+        Field::new("balance", DataType::Utf8, true),
+        Field::new("nonce", DataType::UInt64, true),
+    ]);
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(UInt64Array::from_iter_values(rows.iter().map(|row| row.block_number))),
+        Arc::new(UInt64Array::from_iter_values(rows.iter().map(|row| row.block_timestamp))),
+        Arc::new(UInt32Array::from_iter_values(rows.iter().map(|row| row.tx_index))),
+        string_column(rows.iter().map(|row| row.address)),
+        optional_string_column(rows.iter().map(|row| row.slot)),
+        optional_string_column(rows.iter().map(|row| row.value)),
+        optional_string_column(rows.iter().map(|row| row.balance)),
+        Arc::new(rows.iter().map(|row| row.nonce).collect::<UInt64Array>()),
+    ];
+    Ok(RecordBatch::try_new(Arc::new(schema), columns)?)
+}
+
+pub fn pool_updates_batch(rows: &[PoolUpdateRow]) -> Result<RecordBatch> {
+    let schema = Schema::new(vec![
+        Field::new("block_number", DataType::UInt64, false),
+        Field::new("block_timestamp", DataType::UInt64, false),
+        Field::new("pool_id", DataType::Utf8, false),
+        Field::new("pool_class", DataType::Utf8, false),
+        Field::new("protocol", DataType::Utf8, false),
+        Field::new("token0", DataType::Utf8, false),
+        Field::new("token1", DataType::Utf8, false),
+        Field::new("slots_changed", DataType::UInt32, false),
+        Field::new("unit_out_0_1", DataType::Utf8, true),
+        Field::new("unit_out_1_0", DataType::Utf8, true),
+    ]);
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(UInt64Array::from_iter_values(rows.iter().map(|row| row.block_number))),
+        Arc::new(UInt64Array::from_iter_values(rows.iter().map(|row| row.block_timestamp))),
+        string_column(rows.iter().map(|row| row.pool_id)),
+        string_column(rows.iter().map(|row| row.pool_class)),
+        string_column(rows.iter().map(|row| row.protocol)),
+        string_column(rows.iter().map(|row| row.token0)),
+        string_column(rows.iter().map(|row| row.token1)),
+        Arc::new(UInt32Array::from_iter_values(rows.iter().map(|row| row.slots_changed))),
+        optional_string_column(rows.iter().map(|row| row.unit_out_0_1)),
+        optional_string_column(rows.iter().map(|row| row.unit_out_1_0)),
+    ];
+    Ok(RecordBatch::try_new(Arc::new(schema), columns)?)
+}
@@ -0,0 +1,220 @@
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+use arrow_array::RecordBatch;
+use chrono::{DateTime, NaiveDate};
+use eyre::{eyre, Result};
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use tracing::{debug, error, warn};
+
+use crate::archive_rows::{pool_updates_batch, state_diffs_batch, PoolUpdateRow, StateDiffRow};
+
+const DEFAULT_FLUSH_BLOCKS: usize = 300;
+
+pub const STATE_DIFFS_TABLE: &str = "state_diffs";
+pub const POOL_UPDATES_TABLE: &str = "pool_updates";
+
+/// Buffers archive rows of consecutive blocks and writes them to Parquet files partitioned by the UTC date of the blocks,
+/// `<base_dir>/<table>/date=<YYYY-MM-DD>/<first block>-<last block>.parquet`. Buffered rows are written when the writer
+/// is dropped
+pub struct ArchiveWriter {
+    base_dir: PathBuf,
+    flush_blocks: usize,
+    date: Option<NaiveDate>,
+    first_block: u64,
+    last_block: u64,
+    blocks: usize,
+    written_block: Option<u64>,
+    state_diffs: Vec<StateDiffRow>,
+    pool_updates: Vec<PoolUpdateRow>,
+}
+
+impl ArchiveWriter {
+    pub fn new<T: Into<PathBuf>>(base_dir: T) -> Self {
+        Self {
+            base_dir: base_dir.into(),
+            flush_blocks: DEFAULT_FLUSH_BLOCKS,
+            date: None,
+            first_block: 0,
+            last_block: 0,
+            blocks: 0,
+            written_block: None,
+            state_diffs: Vec::new(),
+            pool_updates: Vec::new(),
+        }
+    }
+
+    /// Number of blocks written to one file
+    pub fn with_flush_blocks(self, flush_blocks: usize) -> Self {
+        Self { flush_blocks: flush_blocks.max(1), ..self }
+    }
+
+    /// Buffer rows of a block. Buffered rows are written when the date changes or `flush_blocks` blocks are buffered.
+    /// A block at or below the last buffered one is a reorg and replaces the buffered rows from its number on
+    pub fn add_block(
+        &mut self,
+        block_number: u64,
+        block_timestamp: u64,
+        state_diffs: Vec<StateDiffRow>,
+        pool_updates: Vec<PoolUpdateRow>,
+    ) -> Result<()> {
+        let date = DateTime::from_timestamp(block_timestamp as i64, 0).ok_or_else(|| eyre!("INVALID_BLOCK_TIMESTAMP"))?.date_naive();
+        if self.written_block.is_some_and(|written_block| block_number <= written_block) {
+            warn!(block_number, "Reorged block is already written to the archive");
+        }
+        if self.blocks > 0 && block_number <= self.last_block {
+            self.discard_from(block_number);
+        }
+        if self.date.is_some_and(|buffered_date| buffered_date != date) {
+            self.flush()?;
+        }
+        if self.blocks == 0 {
+            self.date = Some(date);
+            self.first_block = block_number;
+        }
+        self.last_block = block_number;
+        self.blocks += 1;
+        self.state_diffs.extend(state_diffs);
+        self.pool_updates.extend(pool_updates);
+
+        if self.blocks >= self.flush_blocks {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Write buffered rows, returns paths of the written files
+    pub fn flush(&mut self) -> Result<Vec<PathBuf>> {
+        let Some(date) = self.date.take() else {
+            return Ok(Vec::new());
+        };
+        let file_name = format!("{}-{}.parquet", self.first_block, self.last_block);
+        let mut written = Vec::new();
+
+        if !self.state_diffs.is_empty() {
+            let path = self.partition_dir(STATE_DIFFS_TABLE, date).join(&file_name);
+            write_parquet(&path, state_diffs_batch(&self.state_diffs)?)?;
+            written.push(path);
+        }
+        if !self.pool_updates.is_empty() {
+            let path = self.partition_dir(POOL_UPDATES_TABLE, date).join(&file_name);
+            write_parquet(&path, pool_updates_batch(&self.pool_updates)?)?;
+            written.push(path);
+        }
+        debug!(first_block = self.first_block, last_block = self.last_block, files = written.len(), "Archive flushed");
+
+        self.written_block = Some(self.last_block);
+        self.blocks = 0;
+        self.state_diffs.clear();
+        self.pool_updates.clear();
+        Ok(written)
+    }
+
+    // buffered blocks are consecutive, so the blocks from `block_number` on are the tail of the buffer
+    fn discard_from(&mut self, block_number: u64) {
+        self.state_diffs.retain(|row| row.block_number < block_number);
+        self.pool_updates.retain(|row| row.block_number < block_number);
+        if block_number <= self.first_block {
+            self.date = None;
+            self.blocks = 0;
+        } else {
+            self.blocks -= (self.last_block - block_number + 1) as usize;
+            self.last_block = block_number - 1;
+        }
+    }
+
+    fn partition_dir(&self, table: &str, date: NaiveDate) -> PathBuf {
+        self.base_dir.join(table).join(format!("date={}", date.format("%Y-%m-%d")))
+    }
+}
+
+impl Drop for ArchiveWriter {
+    fn drop(&mut self) {
+        if let Err(error) = self.flush() {
+            error!(%error, "Failed to flush archive");
+        }
+    }
+}
+
+// written under a temporary name and renamed, so readers never see a partial file
+fn write_parquet(path: &Path, batch: RecordBatch) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let tmp_path = path.with_extension("parquet.tmp");
+    let properties = WriterProperties::builder().set_compression(Compression::SNAPPY).build();
+    let mut writer = ArrowWriter::try_new(File::create(&tmp_path)?, batch.schema(), Some(properties))?;
+    writer.write(&batch)?;
+    writer.close()?;
+    std::fs::rename(tmp_path, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::archive_rows::state_diff_rows;
+    use alloy_primitives::{Address, B256, U256};
+    use loom_types_blockchain::GethStateUpdate;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+    fn rows_count(path: &Path) -> usize {
+        let reader = ParquetRecordBatchReaderBuilder::try_new(File::open(path).unwrap()).unwrap().build().unwrap();
+        reader.map(|batch| batch.unwrap().num_rows()).sum()
+    }
+
+    #[test]
+    fn test_archive_writer() {
+        let base_dir = std::env::temp_dir().join(format!("loom-archive-test-{}", std::process::id()));
+        let mut writer = ArchiveWriter::new(&base_dir).with_flush_blocks(10);
+
+        let mut tx_state_update = GethStateUpdate::new();
+        let account = tx_state_update.entry(Address::repeat_byte(1)).or_default();
+        account.balance = Some(U256::from(1));
+        account.storage.insert(B256::repeat_byte(2), B256::repeat_byte(3));
+        account.storage.insert(B256::repeat_byte(4), B256::repeat_byte(5));
+        let state_update = vec![tx_state_update];
+
+        // 2024-01-01 23:59:48 and 2024-01-02 00:00:00
+        let rows = state_diff_rows(100, 1_704_153_588, &state_update);
+        assert_eq!(rows.len(), 3);
+        writer.add_block(100, 1_704_153_588, rows, vec![]).unwrap();
+        writer.add_block(101, 1_704_153_600, state_diff_rows(101, 1_704_153_600, &state_update), vec![]).unwrap();
+
+        let first_file = base_dir.join(STATE_DIFFS_TABLE).join("date=2024-01-01").join("100-100.parquet");
+        assert_eq!(rows_count(&first_file), 3);
+
+        let written = writer.flush().unwrap();
+        assert_eq!(written, vec![base_dir.join(STATE_DIFFS_TABLE).join("date=2024-01-02").join("101-101.parquet")]);
+        assert_eq!(rows_count(&written[0]), 3);
+        assert!(writer.flush().unwrap().is_empty());
+
+        std::fs::remove_dir_all(base_dir).unwrap();
+    }
+
+    #[test]
+    fn test_archive_writer_reorg() {
+        let base_dir = std::env::temp_dir().join(format!("loom-archive-reorg-test-{}", std::process::id()));
+        let mut writer = ArchiveWriter::new(&base_dir).with_flush_blocks(10);
+
+        let mut tx_state_update = GethStateUpdate::new();
+        tx_state_update.entry(Address::repeat_byte(1)).or_default().nonce = Some(1);
+        let state_update = vec![tx_state_update];
+
+        for block_number in 100..103 {
+            let block_timestamp = 1_704_153_600 + block_number;
+            writer.add_block(block_number, block_timestamp, state_diff_rows(block_number, block_timestamp, &state_update), vec![]).unwrap();
+        }
+        // 101 and 102 are replaced by the new 101
+        writer.add_block(101, 1_704_153_701, state_diff_rows(101, 1_704_153_701, &state_update), vec![]).unwrap();
+        // buffered rows are written on drop
+        drop(writer);
+
+        let path = base_dir.join(STATE_DIFFS_TABLE).join("date=2024-01-02").join("100-101.parquet");
+        assert_eq!(rows_count(&path), 2);
+
+        std::fs::remove_dir_all(base_dir).unwrap();
+    }
+}
@@ -0,0 +1,7 @@
+pub use archive_actor::BlockArchiveActor;
+pub use archive_rows::{pool_updates_batch, state_diff_rows, state_diffs_batch, PoolUpdateRow, StateDiffRow};
+pub use archive_writer::ArchiveWriter;
+
+mod archive_actor;
+mod archive_rows;
+mod archive_writer;
@@ -0,0 +1,3 @@
+DROP TABLE path_scores;
+DROP TABLE pools;
+DROP TABLE tokens;
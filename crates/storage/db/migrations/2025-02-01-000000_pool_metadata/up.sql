@@ -0,0 +1,31 @@
+CREATE TABLE tokens
+(
+    chain_id BIGINT  NOT NULL,
+    address  BYTEA   NOT NULL,
+    symbol   TEXT,
+    name     TEXT,
+    decimals INTEGER NOT NULL,
+    basic    BOOLEAN NOT NULL DEFAULT FALSE,
+    middle   BOOLEAN NOT NULL DEFAULT FALSE,
+    PRIMARY KEY (chain_id, address)
+);
+
+-- pool_id is the 20 bytes address of the pool or the 32 bytes id of a singleton pool
+CREATE TABLE pools
+(
+    chain_id         BIGINT  NOT NULL,
+    pool_id          BYTEA   NOT NULL,
+    pool_class       TEXT    NOT NULL,
+    disabled         BOOLEAN NOT NULL DEFAULT FALSE,
+    first_seen_block BIGINT,
+    PRIMARY KEY (chain_id, pool_id)
+);
+
+-- path is the sequence of tokens and pools of the swap path
+CREATE TABLE path_scores
+(
+    chain_id BIGINT           NOT NULL,
+    path     TEXT             NOT NULL,
+    score    DOUBLE PRECISION NOT NULL,
+    PRIMARY KEY (chain_id, path)
+);
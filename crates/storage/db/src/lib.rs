@@ -1,3 +1,11 @@
+pub use models::{PathScoreRecord, PoolRecord, TokenRecord};
 pub use pool::{init_db_pool, DbPool};
+pub use pool_store::{
+    insert_pools, insert_tokens, load_path_scores, load_pools, load_tokens, run_migrations, set_pools_disabled, upsert_path_scores,
+    StoreError, MIGRATIONS,
+};
 
+mod models;
 mod pool;
+mod pool_store;
+pub mod schema;
@@ -0,0 +1,36 @@
+use diesel::prelude::*;
+
+use crate::schema::{path_scores, pools, tokens};
+
+#[derive(Clone, Debug, Queryable, Selectable, Insertable)]
+#[diesel(table_name = tokens)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct TokenRecord {
+    pub chain_id: i64,
+    pub address: Vec<u8>,
+    pub symbol: Option<String>,
+    pub name: Option<String>,
+    pub decimals: i32,
+    pub basic: bool,
+    pub middle: bool,
+}
+
+#[derive(Clone, Debug, Queryable, Selectable, Insertable)]
+#[diesel(table_name = pools)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct PoolRecord {
+    pub chain_id: i64,
+    pub pool_id: Vec<u8>,
+    pub pool_class: String,
+    pub disabled: bool,
+    pub first_seen_block: Option<i64>,
+}
+
+#[derive(Clone, Debug, Queryable, Selectable, Insertable)]
+#[diesel(table_name = path_scores)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct PathScoreRecord {
+    pub chain_id: i64,
+    pub path: String,
+    pub score: f64,
+}
@@ -0,0 +1,128 @@
+use diesel::prelude::*;
+use diesel::upsert::excluded;
+use diesel_async::async_connection_wrapper::AsyncConnectionWrapper;
+use diesel_async::pooled_connection::PoolError as ConnectionError;
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
+use thiserror::Error;
+
+use crate::models::{PathScoreRecord, PoolRecord, TokenRecord};
+use crate::schema::{path_scores, pools, tokens};
+use crate::DbPool;
+
+pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations");
+
+/// Bind parameters Postgres accepts in one statement, multi-row inserts are split to stay below it
+const MAX_BIND_PARAMS: usize = 65535;
+const TOKEN_COLUMNS: usize = 7;
+const POOL_COLUMNS: usize = 5;
+const PATH_SCORE_COLUMNS: usize = 3;
+
+#[derive(Debug, Error)]
+pub enum StoreError {
+    #[error("Failed to get connection: {0}")]
+    Connection(#[from] bb8::RunError<ConnectionError>),
+    #[error("Query failed: {0}")]
+    Query(#[from] diesel::result::Error),
+    #[error("Migration failed: {0}")]
+    Migration(String),
+}
+
+/// Apply the pending schema migrations, the migration harness is synchronous and runs on a blocking thread
+pub async fn run_migrations(db_url: String) -> Result<(), StoreError> {
+    tokio::task::spawn_blocking(move || {
+        let mut conn =
+            AsyncConnectionWrapper::<AsyncPgConnection>::establish(&db_url).map_err(|error| StoreError::Migration(error.to_string()))?;
+        conn.run_pending_migrations(MIGRATIONS).map(|_| ()).map_err(|error| StoreError::Migration(error.to_string()))
+    })
+    .await
+    .map_err(|error| StoreError::Migration(error.to_string()))?
+}
+
+pub async fn load_tokens(db_pool: &DbPool, chain_id: i64) -> Result<Vec<TokenRecord>, StoreError> {
+    let mut conn = db_pool.get().await?;
+    Ok(tokens::table.filter(tokens::chain_id.eq(chain_id)).select(TokenRecord::as_select()).load(&mut conn).await?)
+}
+
+/// Insert tokens not persisted yet, metadata of persisted tokens is kept
+pub async fn insert_tokens(db_pool: &DbPool, records: &[TokenRecord]) -> Result<usize, StoreError> {
+    let mut conn = db_pool.get().await?;
+    let mut inserted = 0;
+    for chunk in records.chunks(MAX_BIND_PARAMS / TOKEN_COLUMNS) {
+        inserted += diesel::insert_into(tokens::table).values(chunk).on_conflict_do_nothing().execute(&mut conn).await?;
+    }
+    Ok(inserted)
+}
+
+pub async fn load_pools(db_pool: &DbPool, chain_id: i64) -> Result<Vec<PoolRecord>, StoreError> {
+    let mut conn = db_pool.get().await?;
+    Ok(pools::table.filter(pools::chain_id.eq(chain_id)).select(PoolRecord::as_select()).load(&mut conn).await?)
+}
+
+/// Insert pools not persisted yet, the disable flag of persisted pools is changed with [`set_pools_disabled`]
+pub async fn insert_pools(db_pool: &DbPool, records: &[PoolRecord]) -> Result<usize, StoreError> {
+    let mut conn = db_pool.get().await?;
+    let mut inserted = 0;
+    for chunk in records.chunks(MAX_BIND_PARAMS / POOL_COLUMNS) {
+        inserted += diesel::insert_into(pools::table).values(chunk).on_conflict_do_nothing().execute(&mut conn).await?;
+    }
+    Ok(inserted)
+}
+
+pub async fn set_pools_disabled(db_pool: &DbPool, chain_id: i64, pool_ids: Vec<Vec<u8>>, disabled: bool) -> Result<usize, StoreError> {
+    let mut conn = db_pool.get().await?;
+    Ok(diesel::update(pools::table.filter(pools::chain_id.eq(chain_id)).filter(pools::pool_id.eq_any(pool_ids)))
+        .set(pools::disabled.eq(disabled))
+        .execute(&mut conn)
+        .await?)
+}
+
+pub async fn load_path_scores(db_pool: &DbPool, chain_id: i64) -> Result<Vec<PathScoreRecord>, StoreError> {
+    let mut conn = db_pool.get().await?;
+    Ok(path_scores::table.filter(path_scores::chain_id.eq(chain_id)).select(PathScoreRecord::as_select()).load(&mut conn).await?)
+}
+
+pub async fn upsert_path_scores(db_pool: &DbPool, records: &[PathScoreRecord]) -> Result<usize, StoreError> {
+    let mut conn = db_pool.get().await?;
+    let mut upserted = 0;
+    for chunk in records.chunks(MAX_BIND_PARAMS / PATH_SCORE_COLUMNS) {
+        upserted += diesel::insert_into(path_scores::table)
+            .values(chunk)
+            .on_conflict((path_scores::chain_id, path_scores::path))
+            .do_update()
+            .set(path_scores::score.eq(excluded(path_scores::score)))
+            .execute(&mut conn)
+            .await?;
+    }
+    Ok(upserted)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::init_db_pool;
+
+    #[tokio::test]
+    async fn test_path_scores_round_trip() -> eyre::Result<()> {
+        let db_url = std::env::var("LOOM_TEST_DATABASE_URL")?;
+        run_migrations(db_url.clone()).await?;
+        let db_pool = init_db_pool(db_url).await?;
+
+        // more rows than fit in one statement
+        let chain_id = 0x10ad;
+        let records: Vec<PathScoreRecord> = (0..MAX_BIND_PARAMS / PATH_SCORE_COLUMNS + 100)
+            .map(|idx| PathScoreRecord { chain_id, path: format!("path{idx}"), score: idx as f64 })
+            .collect();
+        assert_eq!(upsert_path_scores(&db_pool, &records).await?, records.len());
+
+        let updated: Vec<PathScoreRecord> =
+            records.iter().map(|record| PathScoreRecord { score: record.score + 1.0, ..record.clone() }).collect();
+        assert_eq!(upsert_path_scores(&db_pool, &updated).await?, updated.len());
+
+        let mut loaded = load_path_scores(&db_pool, chain_id).await?;
+        loaded.sort_by(|a, b| a.score.total_cmp(&b.score));
+        assert_eq!(loaded.len(), updated.len());
+        assert!(loaded.iter().zip(updated.iter()).all(|(loaded, updated)| loaded.path == updated.path && loaded.score == updated.score));
+        Ok(())
+    }
+}
@@ -8,25 +8,29 @@ use revm::{Database, DatabaseCommit, DatabaseRef};
 use tokio::task::JoinHandle;
 use tracing::info;
 
-use loom_core_actors::{Accessor, Actor, ActorResult, Broadcaster, Consumer, Producer, SharedState, WorkerResult};
+use loom_core_actors::{Accessor, Actor, ActorResult, Broadcaster, Consumer, Producer, SharedState, Snapshot, WorkerResult};
 use loom_core_actors_macros::{Accessor, Consumer, Producer};
 use loom_node_debug_provider::DebugProviderExt;
-use loom_types_blockchain::Mempool;
-use loom_types_entities::{BlockHistory, LatestBlock, Market, MarketState};
+use loom_types_blockchain::{ChainParameters, Mempool};
+use loom_types_entities::{BlockHistory, LatestBlock, Market, MarketState, MarketView};
 use loom_types_events::{MarketEvents, MempoolEvents, MessageHealthEvent, MessageSwapCompose};
 
 use super::{PendingTxStateChangeProcessorActor, StateChangeArbSearcherActor};
 use crate::block_state_change_processor::BlockStateChangeProcessorActor;
-use crate::BackrunConfig;
+use crate::{BackrunConfig, DepegMonitorActor};
 
 #[derive(Accessor, Consumer, Producer)]
 pub struct StateChangeArbActor<P, N, DB: Clone + Send + Sync + 'static> {
     backrun_config: BackrunConfig,
+    /// Strategy instances of namespaces searching the same state updates next to `backrun_config`
+    namespaced_configs: Vec<BackrunConfig>,
     client: P,
+    chain_parameters: ChainParameters,
     use_blocks: bool,
     use_mempool: bool,
     #[accessor]
     market: Option<SharedState<Market>>,
+    market_view: Option<Snapshot<MarketView>>,
     #[accessor]
     mempool: Option<SharedState<Mempool>>,
     #[accessor]
@@ -58,10 +62,13 @@ where
     pub fn new(client: P, use_blocks: bool, use_mempool: bool, backrun_config: BackrunConfig) -> StateChangeArbActor<P, N, DB> {
         StateChangeArbActor {
             backrun_config,
+            namespaced_configs: Vec::new(),
             client,
+            chain_parameters: ChainParameters::ethereum(),
             use_blocks,
             use_mempool,
             market: None,
+            market_view: None,
             mempool: None,
             latest_block: None,
             block_history: None,
@@ -74,6 +81,18 @@ where
             _n: PhantomData,
         }
     }
+
+    pub fn with_market_view(self, market_view: Snapshot<MarketView>) -> Self {
+        Self { market_view: Some(market_view), ..self }
+    }
+
+    pub fn with_chain_parameters(self, chain_parameters: ChainParameters) -> Self {
+        Self { chain_parameters, ..self }
+    }
+
+    pub fn with_namespaces(self, namespaced_configs: Vec<BackrunConfig>) -> Self {
+        Self { namespaced_configs, ..self }
+    }
 }
 
 impl<P, N, DB> Actor for StateChangeArbActor<P, N, DB>
@@ -86,26 +105,36 @@ where
         let searcher_pool_update_channel = Broadcaster::new(100);
         let mut tasks: Vec<JoinHandle<WorkerResult>> = Vec::new();
 
-        let mut state_update_searcher = StateChangeArbSearcherActor::new(self.backrun_config.clone());
-        match state_update_searcher
-            .access(self.market.clone().unwrap())
-            .consume(searcher_pool_update_channel.clone())
-            .produce(self.compose_channel_tx.clone().unwrap())
-            .produce(self.pool_health_monitor_tx.clone().unwrap())
-            .produce(self.influxdb_write_channel_tx.clone().unwrap())
-            .start()
-        {
-            Err(e) => {
-                panic!("{}", e)
+        for backrun_config in std::iter::once(&self.backrun_config).chain(self.namespaced_configs.iter()) {
+            let mut state_update_searcher = StateChangeArbSearcherActor::new(backrun_config.clone());
+            if let Some(market_view) = &self.market_view {
+                state_update_searcher = state_update_searcher.with_market_view(market_view.clone());
             }
-            Ok(r) => {
-                tasks.extend(r);
-                info!("State change searcher actor started successfully")
+            if let Some(mempool) = &self.mempool {
+                state_update_searcher = state_update_searcher.access(mempool.clone());
+            }
+            match state_update_searcher
+                .access(self.market.clone().unwrap())
+                .consume(searcher_pool_update_channel.clone())
+                .produce(self.compose_channel_tx.clone().unwrap())
+                .produce(self.pool_health_monitor_tx.clone().unwrap())
+                .produce(self.influxdb_write_channel_tx.clone().unwrap())
+                .start()
+            {
+                Err(e) => {
+                    panic!("{}", e)
+                }
+                Ok(r) => {
+                    tasks.extend(r);
+                    info!(namespace = backrun_config.namespace(), "State change searcher actor started successfully")
+                }
             }
         }
 
         if self.mempool_events_tx.is_some() && self.use_mempool {
-            let mut pending_tx_state_processor = PendingTxStateChangeProcessorActor::new(self.client.clone());
+            let mut pending_tx_state_processor = PendingTxStateChangeProcessorActor::new(self.client.clone())
+                .with_intra_block_state(self.backrun_config.intra_block_state())
+                .with_mempool_sampling(self.backrun_config.mempool_sampling().copied());
             match pending_tx_state_processor
                 .access(self.mempool.clone().unwrap())
                 .access(self.latest_block.clone().unwrap())
@@ -114,6 +143,7 @@ where
                 .consume(self.mempool_events_tx.clone().unwrap())
                 .consume(self.market_events_tx.clone().unwrap())
                 .produce(searcher_pool_update_channel.clone())
+                .produce(self.influxdb_write_channel_tx.clone().unwrap())
                 .start()
             {
                 Err(e) => {
@@ -145,6 +175,27 @@ where
             }
         }
 
+        // the paths of depeg searches are built on the market view, without the market lock
+        if let (Some(depeg_config), true, true) = (self.backrun_config.depeg(), self.market_events_tx.is_some(), self.market_view.is_some())
+        {
+            let mut depeg_monitor = DepegMonitorActor::new(depeg_config.clone()).with_chain_parameters(self.chain_parameters.clone());
+            match depeg_monitor
+                .access(self.market.clone().unwrap())
+                .access(self.block_history.clone().unwrap())
+                .consume(self.market_events_tx.clone().unwrap())
+                .produce(searcher_pool_update_channel.clone())
+                .start()
+            {
+                Err(e) => {
+                    panic!("{}", e)
+                }
+                Ok(r) => {
+                    tasks.extend(r);
+                    info!("Depeg monitor actor started successfully")
+                }
+            }
+        }
+
         Ok(tasks)
     }
 
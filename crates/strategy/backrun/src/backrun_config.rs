@@ -1,16 +1,88 @@
-use alloy_primitives::Address;
+use std::collections::BTreeMap;
+
+use alloy_network::Network;
+use alloy_primitives::utils::parse_units;
+use alloy_primitives::{Address, U256};
+use alloy_provider::Provider;
+use eyre::{Result, WrapErr};
 use loom_types_entities::strategy_config::StrategyConfig;
+use loom_types_entities::{RiskConfig, SwapPath};
 use serde::Deserialize;
 
+use crate::{DepegConfig, ExtraGasConfig, MempoolSamplingConfig, ProfitReceiverConfig, SearchBudgetConfig};
+
 #[derive(Clone, Deserialize, Debug)]
 pub struct BackrunConfigSection {
     pub backrun_strategy: BackrunConfig,
+    /// Strategy instances running next to `backrun_strategy` by namespace
+    #[serde(default)]
+    pub backrun_namespaces: BTreeMap<String, BackrunConfig>,
+}
+
+impl BackrunConfigSection {
+    /// Strategy instances of the namespaces, each with its namespace set
+    pub fn namespaced_strategies(&self) -> Vec<BackrunConfig> {
+        self.backrun_namespaces.iter().map(|(namespace, config)| config.clone().with_namespace(namespace.clone())).collect()
+    }
+
+    /// Validate the profit receivers of all strategy instances
+    pub async fn validate_profit_receivers<P, N>(&self, client: &P) -> Result<()>
+    where
+        N: Network,
+        P: Provider<N>,
+    {
+        let configs = std::iter::once((None, &self.backrun_strategy))
+            .chain(self.backrun_namespaces.iter().map(|(namespace, config)| (Some(namespace), config)));
+        for (namespace, config) in configs {
+            if let Some(profit_receiver) = config.profit_receiver() {
+                profit_receiver.validate_on_chain(client).await.wrap_err_with(|| format!("namespace {namespace:?}"))?;
+            }
+        }
+        Ok(())
+    }
 }
 
 #[derive(Clone, Deserialize, Debug)]
 pub struct BackrunConfig {
     eoa: Option<Address>,
     smart: bool,
+    /// Maximum amount in ETH used for a single swap, opportunities above it are downscaled
+    #[serde(default)]
+    max_capital_eth: Option<String>,
+    /// Number of historically most profitable paths precomputed on every new block state, disabled if not set
+    #[serde(default)]
+    warm_up_paths: Option<usize>,
+    /// Simulate pending txs on top of the pending txs expected before them in the next block
+    #[serde(default)]
+    intra_block_state: bool,
+    /// Drop risky paths before simulation, disabled if not set
+    #[serde(default)]
+    risk: Option<RiskConfig>,
+    /// Tokens the cycles start and end with, every basic token of the market if not set. Tokens without an ETH price are skipped,
+    /// the profits of all base tokens are compared in ETH
+    #[serde(default)]
+    base_tokens: Option<Vec<Address>>,
+    /// Search pools of stablecoin pairs trading off the peg on every block, disabled if not set
+    #[serde(default)]
+    depeg: Option<DepegConfig>,
+    /// Calculate paths through pools of the same protocol and pair once, with the best pool of every hop
+    #[serde(default)]
+    group_pools: bool,
+    /// Stop searches after a latency budget by trigger, paths are calculated exhaustively if not set
+    #[serde(default)]
+    search_budget: Option<SearchBudgetConfig>,
+    /// Namespace of the strategy instance limiting its signers and capital, signers of no namespace are used if not set
+    #[serde(default)]
+    namespace: Option<String>,
+    /// Receiver of the profits, the profits stay in the multicaller if not set
+    #[serde(default)]
+    profit_receiver: Option<ProfitReceiverConfig>,
+    /// Sample pending txs when they arrive faster than they are traced, every pending tx is traced if not set
+    #[serde(default)]
+    mempool_sampling: Option<MempoolSamplingConfig>,
+    /// Extra gas of annotated pools and pool classes charged to the profit of the calculated opportunities
+    #[serde(default)]
+    extra_gas: ExtraGasConfig,
 }
 
 impl StrategyConfig for BackrunConfig {
@@ -24,13 +96,133 @@ impl BackrunConfig {
         self.smart
     }
 
+    pub fn max_capital_eth(&self) -> Option<U256> {
+        self.max_capital_eth.as_ref().and_then(|value| parse_units(value, "ether").ok()).map(|value| value.get_absolute())
+    }
+
+    pub fn warm_up_paths(&self) -> Option<usize> {
+        self.warm_up_paths
+    }
+
+    pub fn intra_block_state(&self) -> bool {
+        self.intra_block_state
+    }
+
+    pub fn risk(&self) -> Option<&RiskConfig> {
+        self.risk.as_ref()
+    }
+
+    /// The cycle of `swap_path` starting with the first of its base tokens with an ETH price, `None` if it has none
+    pub fn base_cycle(&self, swap_path: SwapPath) -> Option<SwapPath> {
+        match &self.base_tokens {
+            Some(base_tokens) => {
+                swap_path.rotated_cycle(|token| base_tokens.contains(&token.get_address()) && token.get_eth_price().is_some())
+            }
+            None => Some(swap_path),
+        }
+    }
+
+    pub fn depeg(&self) -> Option<&DepegConfig> {
+        self.depeg.as_ref()
+    }
+
+    pub fn group_pools(&self) -> bool {
+        self.group_pools
+    }
+
+    pub fn search_budget(&self) -> Option<&SearchBudgetConfig> {
+        self.search_budget.as_ref()
+    }
+
+    pub fn namespace(&self) -> Option<&str> {
+        self.namespace.as_deref()
+    }
+
+    pub fn with_namespace(self, namespace: String) -> Self {
+        Self { namespace: Some(namespace), ..self }
+    }
+
+    pub fn profit_receiver(&self) -> Option<&ProfitReceiverConfig> {
+        self.profit_receiver.as_ref()
+    }
+
+    pub fn mempool_sampling(&self) -> Option<&MempoolSamplingConfig> {
+        self.mempool_sampling.as_ref()
+    }
+
+    pub fn extra_gas(&self) -> &ExtraGasConfig {
+        &self.extra_gas
+    }
+
     pub fn new_dumb() -> Self {
-        Self { eoa: None, smart: false }
+        Self {
+            eoa: None,
+            smart: false,
+            max_capital_eth: None,
+            warm_up_paths: None,
+            intra_block_state: false,
+            risk: None,
+            base_tokens: None,
+            depeg: None,
+            group_pools: false,
+            search_budget: None,
+            namespace: None,
+            profit_receiver: None,
+            mempool_sampling: None,
+            extra_gas: ExtraGasConfig::default(),
+        }
     }
 }
 
 impl Default for BackrunConfig {
     fn default() -> Self {
-        Self { eoa: None, smart: true }
+        Self {
+            eoa: None,
+            smart: true,
+            max_capital_eth: None,
+            warm_up_paths: None,
+            intra_block_state: false,
+            risk: None,
+            base_tokens: None,
+            depeg: None,
+            group_pools: false,
+            search_budget: None,
+            namespace: None,
+            profit_receiver: None,
+            mempool_sampling: None,
+            extra_gas: ExtraGasConfig::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use loom_types_entities::{MockPool, Token};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_base_cycle() {
+        let weth = Arc::new(Token::new(Address::repeat_byte(1)));
+        let usdc = Arc::new(Token::new(Address::repeat_byte(2)));
+        let wbtc = Arc::new(Token::new(Address::repeat_byte(3)));
+        weth.set_eth_price(Some(U256::from(10).pow(U256::from(18))));
+        usdc.set_eth_price(Some(U256::from(2_000_000_000u64)));
+        let pools = [(&weth, &usdc), (&usdc, &wbtc), (&wbtc, &weth)]
+            .into_iter()
+            .enumerate()
+            .map(|(i, (token0, token1))| MockPool::new(token0.get_address(), token1.get_address(), Address::repeat_byte(0x10 + i as u8)))
+            .collect::<Vec<_>>();
+        let cycle = SwapPath::new(vec![weth.clone(), usdc.clone(), wbtc.clone(), weth.clone()], pools);
+
+        assert_eq!(BackrunConfig::default().base_cycle(cycle.clone()), Some(cycle.clone()));
+
+        let usdc_config = BackrunConfig { base_tokens: Some(vec![usdc.get_address()]), ..BackrunConfig::default() };
+        let usdc_cycle = usdc_config.base_cycle(cycle.clone()).unwrap();
+        assert_eq!(usdc_cycle.tokens, vec![usdc.clone(), wbtc.clone(), weth.clone(), usdc.clone()]);
+
+        // the profits of base tokens without an ETH price cannot be compared
+        let wbtc_config = BackrunConfig { base_tokens: Some(vec![wbtc.get_address()]), ..BackrunConfig::default() };
+        assert_eq!(wbtc_config.base_cycle(cycle), None);
     }
 }
@@ -11,6 +11,8 @@ use revm::DatabaseRef;
 use tokio::sync::broadcast::error::RecvError;
 use tracing::error;
 
+pub(crate) const BLOCK_SEARCHER_ORIGIN: &str = "block_searcher";
+
 pub async fn block_state_change_worker<DB: DatabaseRef + Send + Sync + Clone + 'static>(
     chain_parameters: ChainParameters,
     market: SharedState<Market>,
@@ -75,7 +77,7 @@ pub async fn block_state_change_worker<DB: DatabaseRef + Send + Sync + Clone + '
             affected_pools,
             Vec::new(),
             Vec::new(),
-            "block_searcher".to_string(),
+            BLOCK_SEARCHER_ORIGIN.to_string(),
             90_00,
         );
         run_sync!(state_updates_broadcaster.send(request));
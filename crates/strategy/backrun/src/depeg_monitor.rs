@@ -0,0 +1,427 @@
+use std::collections::BTreeMap;
+
+use alloy_primitives::utils::parse_units;
+use alloy_primitives::{Address, U256};
+use eyre::{eyre, ErrReport};
+use revm::primitives::Env;
+use revm::DatabaseRef;
+use serde::Deserialize;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{error, info, trace};
+
+use loom_core_actors::{run_sync, subscribe, Accessor, Actor, ActorResult, Broadcaster, Consumer, Producer, SharedState, WorkerResult};
+use loom_core_actors_macros::{Accessor, Consumer, Producer};
+use loom_core_blockchain::{Blockchain, BlockchainState, Strategy};
+use loom_defi_address_book::{TokenAddressArbitrum, TokenAddressAvalanche, TokenAddressBsc, TokenAddressEth};
+use loom_evm_utils::evm_env::env_for_block;
+use loom_types_blockchain::ChainParameters;
+use loom_types_entities::{BlockHistory, Market, PathGasBudget, PoolWrapper, SwapDirection};
+use loom_types_events::{MarketEvents, StateUpdateEvent};
+
+pub(crate) const DEPEG_MONITOR_ORIGIN: &str = "depeg_monitor";
+
+/// Stablecoin peg monitoring. Pools of pairs trading off the peg are searched with a larger capital and longer paths.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct DepegConfig {
+    /// Stablecoins pegged to the same value, the USD stablecoins of the address book of the chain if not set
+    pub tokens: Option<Vec<Address>>,
+    /// Deviation of a pool rate from the peg in basis points that triggers a search, should exceed the pool fees
+    pub threshold_bps: u64,
+    /// Amount in whole tokens quoted to measure the pool rate
+    pub quote_amount: u64,
+    /// Capital limit of depeg searches in ETH
+    pub max_capital_eth: Option<String>,
+    /// Start amount of the optimization in ETH
+    pub start_amount_eth: Option<String>,
+    pub max_hops: usize,
+    /// Maximum estimated gas of depeg paths longer than three hops
+    pub max_gas: u64,
+}
+
+impl Default for DepegConfig {
+    fn default() -> Self {
+        Self {
+            tokens: None,
+            threshold_bps: 50,
+            quote_amount: 1000,
+            max_capital_eth: Some("50".to_string()),
+            start_amount_eth: Some("1".to_string()),
+            max_hops: 5,
+            max_gas: 600_000,
+        }
+    }
+}
+
+impl DepegConfig {
+    pub fn tokens(&self, chain_id: u64) -> Vec<Address> {
+        self.tokens.clone().unwrap_or_else(|| chain_stablecoins(chain_id))
+    }
+
+    pub fn max_capital_eth(&self) -> Option<U256> {
+        self.max_capital_eth.as_ref().and_then(|value| parse_units(value, "ether").ok()).map(|value| value.get_absolute())
+    }
+
+    pub fn start_amount_eth(&self) -> Option<U256> {
+        self.start_amount_eth.as_ref().and_then(|value| parse_units(value, "ether").ok()).map(|value| value.get_absolute())
+    }
+
+    pub fn path_gas_budget(&self) -> PathGasBudget {
+        PathGasBudget::new(self.max_hops, self.max_gas)
+    }
+}
+
+/// USD stablecoins of the address book of the chain, empty for chains without them
+pub fn chain_stablecoins(chain_id: u64) -> Vec<Address> {
+    match chain_id {
+        1 => vec![TokenAddressEth::USDC, TokenAddressEth::USDT, TokenAddressEth::DAI, TokenAddressEth::FRAX],
+        56 => vec![TokenAddressBsc::USDC, TokenAddressBsc::USDT, TokenAddressBsc::DAI],
+        42161 => vec![TokenAddressArbitrum::USDC, TokenAddressArbitrum::USDT, TokenAddressArbitrum::DAI],
+        43114 => vec![TokenAddressAvalanche::USDC, TokenAddressAvalanche::USDT, TokenAddressAvalanche::DAI, TokenAddressAvalanche::FRAX],
+        _ => Vec::new(),
+    }
+}
+
+/// Deviation of the exchange rate from the peg in basis points, amounts are normalized by the token decimals
+pub(crate) fn peg_deviation_bps(amount_in: U256, decimals_in: u8, amount_out: U256, decimals_out: u8) -> u64 {
+    let decimals = decimals_in.max(decimals_out);
+    let amount_in = amount_in * U256::from(10).pow(U256::from(decimals - decimals_in));
+    let amount_out = amount_out * U256::from(10).pow(U256::from(decimals - decimals_out));
+    if amount_in.is_zero() {
+        return 0;
+    }
+    let deviation = if amount_out > amount_in { amount_out - amount_in } else { amount_in - amount_out };
+    (deviation * U256::from(10000) / amount_in).saturating_to()
+}
+
+// Both directions of the pools of stablecoin pairs of `tokens` with a rate off the peg
+fn depegged_pool_directions<DB: DatabaseRef<Error = ErrReport>>(
+    depeg_config: &DepegConfig,
+    tokens: &[Address],
+    market: &Market,
+    state: &DB,
+    env: Env,
+) -> BTreeMap<PoolWrapper, Vec<SwapDirection>> {
+    let mut directions: BTreeMap<PoolWrapper, Vec<SwapDirection>> = BTreeMap::new();
+
+    for (idx, token_in_address) in tokens.iter().enumerate() {
+        for token_out_address in tokens.iter().skip(idx + 1) {
+            let Some(pools) = market.get_token_token_pools(token_in_address, token_out_address) else { continue };
+            let (Some(token_in), Some(token_out)) = (market.get_token(token_in_address), market.get_token(token_out_address)) else {
+                continue;
+            };
+            let amount_in = U256::from(depeg_config.quote_amount) * token_in.get_exp();
+
+            for pool_id in pools.iter() {
+                if market.is_pool_disabled(pool_id) {
+                    continue;
+                }
+                let Some(pool) = market.get_pool(pool_id) else { continue };
+
+                match pool.calculate_out_amount(state, env.clone(), token_in_address, token_out_address, amount_in) {
+                    Ok((amount_out, _)) => {
+                        let deviation_bps = peg_deviation_bps(amount_in, token_in.get_decimals(), amount_out, token_out.get_decimals());
+                        if deviation_bps > depeg_config.threshold_bps {
+                            info!(pool = %pool_id, token_in = %token_in_address, token_out = %token_out_address, deviation_bps, "Pool rate is off the peg");
+                            directions.insert(
+                                pool.clone(),
+                                vec![(*token_in_address, *token_out_address).into(), (*token_out_address, *token_in_address).into()],
+                            );
+                        }
+                    }
+                    Err(error) => {
+                        trace!(%error, pool = %pool_id, "Peg rate calculation failed")
+                    }
+                }
+            }
+        }
+    }
+    directions
+}
+
+pub async fn depeg_monitor_worker<DB: DatabaseRef<Error = ErrReport> + Send + Sync + Clone + 'static>(
+    chain_parameters: ChainParameters,
+    depeg_config: DepegConfig,
+    market: SharedState<Market>,
+    block_history: SharedState<BlockHistory<DB>>,
+    market_events_rx: Broadcaster<MarketEvents>,
+    state_updates_broadcaster: Broadcaster<StateUpdateEvent<DB>>,
+) -> WorkerResult {
+    subscribe!(market_events_rx);
+
+    let tokens = depeg_config.tokens(chain_parameters.chain_id);
+    if tokens.len() < 2 {
+        error!(chain_id = chain_parameters.chain_id, "No stablecoin pairs to monitor");
+        return Ok("DepegMonitorWorker finished".to_string());
+    }
+
+    loop {
+        let market_event = match market_events_rx.recv().await {
+            Ok(market_event) => market_event,
+            Err(e) => match e {
+                RecvError::Closed => {
+                    error!("Market events txs channel closed");
+                    break Err(eyre!("MARKET_EVENTS_RX_CLOSED"));
+                }
+                RecvError::Lagged(lag) => {
+                    error!("Market events txs channel lagged by {} messages", lag);
+                    continue;
+                }
+            },
+        };
+        let block_hash = match market_event {
+            MarketEvents::BlockStateUpdate { block_hash } => block_hash,
+            _ => continue,
+        };
+
+        let Some(block_history_entry) = block_history.read().await.get_block_history_entry(&block_hash).cloned() else {
+            error!("Block history entry not found in block history: {:?}", block_hash);
+            continue;
+        };
+
+        let Some(block_state_entry) = block_history.read().await.get_block_state(&block_hash).cloned() else {
+            error!("Block state not found in block history: {:?}", block_hash);
+            continue;
+        };
+
+        let next_block_number = block_history_entry.number() + 1;
+        let next_block_timestamp = chain_parameters.next_block_timestamp(block_history_entry.timestamp());
+        let next_base_fee = chain_parameters.calc_next_block_base_fee_from_header(&block_history_entry.header);
+
+        let directions = depegged_pool_directions(
+            &depeg_config,
+            &tokens,
+            &*market.read().await,
+            &block_state_entry,
+            env_for_block(next_block_number, next_block_timestamp),
+        );
+        if directions.is_empty() {
+            continue;
+        }
+
+        let request = StateUpdateEvent::new(
+            next_block_number,
+            next_block_timestamp,
+            next_base_fee,
+            block_state_entry,
+            Vec::new(),
+            None,
+            directions,
+            Vec::new(),
+            Vec::new(),
+            DEPEG_MONITOR_ORIGIN.to_string(),
+            90_00,
+        );
+        run_sync!(state_updates_broadcaster.send(request));
+    }
+}
+
+/// Raises searches over pools of stablecoin pairs whose rate deviates from the peg on every block
+#[derive(Accessor, Consumer, Producer)]
+pub struct DepegMonitorActor<DB: Clone + Send + Sync + 'static> {
+    chain_parameters: ChainParameters,
+    depeg_config: DepegConfig,
+    #[accessor]
+    market: Option<SharedState<Market>>,
+    #[accessor]
+    block_history: Option<SharedState<BlockHistory<DB>>>,
+    #[consumer]
+    market_events_rx: Option<Broadcaster<MarketEvents>>,
+    #[producer]
+    state_updates_tx: Option<Broadcaster<StateUpdateEvent<DB>>>,
+}
+
+impl<DB: DatabaseRef<Error = ErrReport> + Send + Sync + Clone + 'static> DepegMonitorActor<DB> {
+    pub fn new(depeg_config: DepegConfig) -> DepegMonitorActor<DB> {
+        DepegMonitorActor {
+            chain_parameters: ChainParameters::ethereum(),
+            depeg_config,
+            market: None,
+            block_history: None,
+            market_events_rx: None,
+            state_updates_tx: None,
+        }
+    }
+
+    pub fn with_chain_parameters(self, chain_parameters: ChainParameters) -> Self {
+        Self { chain_parameters, ..self }
+    }
+
+    pub fn on_bc(self, bc: &Blockchain, state: &BlockchainState<DB>, strategy: &Strategy<DB>) -> Self {
+        Self {
+            chain_parameters: bc.chain_parameters(),
+            market: Some(bc.market()),
+            market_events_rx: Some(bc.market_events_channel()),
+            state_updates_tx: Some(strategy.state_update_channel()),
+            block_history: Some(state.block_history()),
+            ..self
+        }
+    }
+}
+
+impl<DB: DatabaseRef<Error = ErrReport> + Send + Sync + Clone + 'static> Actor for DepegMonitorActor<DB> {
+    fn start(&self) -> ActorResult {
+        let task = tokio::task::spawn(depeg_monitor_worker(
+            self.chain_parameters.clone(),
+            self.depeg_config.clone(),
+            self.market.clone().unwrap(),
+            self.block_history.clone().unwrap(),
+            self.market_events_rx.clone().unwrap(),
+            self.state_updates_tx.clone().unwrap(),
+        ));
+        Ok(vec![task])
+    }
+
+    fn name(&self) -> &'static str {
+        "DepegMonitorActor"
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use eyre::{eyre, Result};
+    use loom_evm_db::LoomDBType;
+    use loom_types_entities::required_state::RequiredState;
+    use loom_types_entities::{Pool, PoolAbiEncoder, PoolClass, PoolId, PoolProtocol, PreswapRequirement, Token};
+    use std::any::Any;
+
+    // pays `rate_bps` of the raw in amount of token0 in token1 and back
+    struct FixedRatePool {
+        address: Address,
+        token0: Address,
+        token1: Address,
+        rate_bps: u64,
+    }
+
+    impl Pool for FixedRatePool {
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+
+        fn get_class(&self) -> PoolClass {
+            PoolClass::Curve
+        }
+
+        fn get_protocol(&self) -> PoolProtocol {
+            PoolProtocol::Curve
+        }
+
+        fn get_address(&self) -> Address {
+            self.address
+        }
+
+        fn get_pool_id(&self) -> PoolId {
+            PoolId::Address(self.address)
+        }
+
+        fn get_fee(&self) -> U256 {
+            U256::ZERO
+        }
+
+        fn get_tokens(&self) -> Vec<Address> {
+            vec![self.token0, self.token1]
+        }
+
+        fn get_swap_directions(&self) -> Vec<SwapDirection> {
+            vec![(self.token0, self.token1).into(), (self.token1, self.token0).into()]
+        }
+
+        fn calculate_out_amount(
+            &self,
+            _state: &dyn DatabaseRef<Error = ErrReport>,
+            _env: Env,
+            token_address_from: &Address,
+            _token_address_to: &Address,
+            in_amount: U256,
+        ) -> Result<(U256, u64)> {
+            if *token_address_from == self.token0 {
+                Ok((in_amount * U256::from(self.rate_bps) / U256::from(10000), 100_000))
+            } else {
+                Ok((in_amount * U256::from(10000) / U256::from(self.rate_bps), 100_000))
+            }
+        }
+
+        fn calculate_in_amount(
+            &self,
+            _state: &dyn DatabaseRef<Error = ErrReport>,
+            _env: Env,
+            _token_address_from: &Address,
+            _token_address_to: &Address,
+            _out_amount: U256,
+        ) -> Result<(U256, u64)> {
+            Err(eyre!("NOT_IMPLEMENTED"))
+        }
+
+        fn can_flash_swap(&self) -> bool {
+            false
+        }
+
+        fn can_calculate_in_amount(&self) -> bool {
+            false
+        }
+
+        fn get_abi_encoder(&self) -> Option<&dyn PoolAbiEncoder> {
+            None
+        }
+
+        fn get_read_only_cell_vec(&self) -> Vec<U256> {
+            Vec::new()
+        }
+
+        fn get_state_required(&self) -> Result<RequiredState> {
+            Ok(RequiredState::new())
+        }
+
+        fn is_native(&self) -> bool {
+            false
+        }
+
+        fn preswap_requirement(&self) -> PreswapRequirement {
+            PreswapRequirement::Base
+        }
+    }
+
+    #[test]
+    fn test_depegged_pool_directions() -> Result<()> {
+        let (usdc, usdt, dai) = (Address::repeat_byte(1), Address::repeat_byte(2), Address::repeat_byte(3));
+        let mut market = Market::default();
+        market.add_token(Token::new_with_data(usdc, Some("USDC".to_string()), None, Some(6), true, false));
+        market.add_token(Token::new_with_data(usdt, Some("USDT".to_string()), None, Some(6), true, false));
+        // pegged USDC/USDT pool, USDC/USDT and USDT/DAI pools 1% off the peg, DAI is not monitored
+        market.add_pool(FixedRatePool { address: Address::repeat_byte(0x10), token0: usdc, token1: usdt, rate_bps: 10000 })?;
+        market.add_pool(FixedRatePool { address: Address::repeat_byte(0x11), token0: usdc, token1: usdt, rate_bps: 9900 })?;
+        market.add_pool(FixedRatePool { address: Address::repeat_byte(0x12), token0: usdt, token1: dai, rate_bps: 9900 })?;
+
+        let depeg_config = DepegConfig { tokens: Some(vec![usdc, usdt]), ..DepegConfig::default() };
+        let directions = depegged_pool_directions(&depeg_config, &depeg_config.tokens(1), &market, &LoomDBType::default(), Env::default());
+
+        assert_eq!(directions.len(), 1);
+        let (pool, pool_directions) = directions.iter().next().unwrap();
+        assert_eq!(pool.get_address(), Address::repeat_byte(0x11));
+        assert_eq!(pool_directions.len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_chain_tokens() {
+        assert_eq!(DepegConfig::default().tokens(1).len(), 4);
+        assert_eq!(DepegConfig::default().tokens(42161), chain_stablecoins(42161));
+        assert!(DepegConfig::default().tokens(8453).len() < 2);
+        let tokens = vec![TokenAddressEth::USDC, TokenAddressEth::DAI];
+        assert_eq!(DepegConfig { tokens: Some(tokens.clone()), ..DepegConfig::default() }.tokens(42161), tokens);
+    }
+
+    #[test]
+    fn test_peg_deviation_bps() {
+        // 1000 USDC -> 1000 DAI
+        let usdc = U256::from(1_000_000_000u64);
+        let dai = U256::from(1000) * U256::from(10).pow(U256::from(18));
+        assert_eq!(peg_deviation_bps(usdc, 6, dai, 18), 0);
+
+        // 1000 USDC -> 990 USDT
+        assert_eq!(peg_deviation_bps(usdc, 6, U256::from(990_000_000u64), 6), 100);
+        // 1000 DAI -> 1005 USDC
+        assert_eq!(peg_deviation_bps(dai, 18, U256::from(1_005_000_000u64), 6), 50);
+    }
+}
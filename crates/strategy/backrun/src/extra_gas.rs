@@ -0,0 +1,64 @@
+use std::collections::HashMap;
+
+use loom_types_entities::{PoolClass, PoolId, SwapPath};
+use serde::Deserialize;
+
+/// Gas of pools not covered by the calculated hop gas, e.g. pools of tokens with expensive transfer logic. Charged to the profit
+/// of opportunities before they are simulated, the simulated gas already includes it
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct ExtraGasConfig {
+    /// Extra gas of every swap through the pool
+    #[serde(default)]
+    pub pools: HashMap<PoolId, u64>,
+    /// Extra gas of every swap through a pool of the class without its own annotation
+    #[serde(default)]
+    pub classes: HashMap<PoolClass, u64>,
+}
+
+impl ExtraGasConfig {
+    pub fn with_pool(mut self, pool_id: PoolId, extra_gas: u64) -> Self {
+        self.pools.insert(pool_id, extra_gas);
+        self
+    }
+
+    pub fn with_class(mut self, pool_class: PoolClass, extra_gas: u64) -> Self {
+        self.classes.insert(pool_class, extra_gas);
+        self
+    }
+
+    /// Annotated extra gas of the pools of the path
+    pub fn extra_gas(&self, swap_path: &SwapPath) -> u64 {
+        swap_path
+            .pools
+            .iter()
+            .map(|pool| self.pools.get(&pool.get_pool_id()).or_else(|| self.classes.get(&pool.get_class())).copied().unwrap_or_default())
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloy_primitives::Address;
+    use loom_types_entities::{MockPool, Token};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_extra_gas() {
+        let token0 = Arc::new(Token::new(Address::repeat_byte(1)));
+        let token1 = Arc::new(Token::new(Address::repeat_byte(2)));
+        let pools = [3u8, 4]
+            .into_iter()
+            .map(|byte| MockPool::new(token0.get_address(), token1.get_address(), Address::repeat_byte(byte)))
+            .collect::<Vec<_>>();
+        let swap_path = SwapPath::new(vec![token0.clone(), token1, token0], pools);
+
+        assert_eq!(ExtraGasConfig::default().extra_gas(&swap_path), 0);
+
+        let config = ExtraGasConfig::default().with_class(PoolClass::UniswapV2, 20_000);
+        assert_eq!(config.extra_gas(&swap_path), 40_000);
+        // the pool annotation replaces the one of its class
+        let config = config.with_pool(PoolId::Address(Address::repeat_byte(3)), 5_000);
+        assert_eq!(config.extra_gas(&swap_path), 25_000);
+    }
+}
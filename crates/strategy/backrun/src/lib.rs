@@ -1,11 +1,18 @@
 pub use arb_actor::StateChangeArbActor;
 pub use backrun_config::{BackrunConfig, BackrunConfigSection};
 pub use block_state_change_processor::BlockStateChangeProcessorActor;
+pub use depeg_monitor::{DepegConfig, DepegMonitorActor};
+pub use extra_gas::ExtraGasConfig;
+pub use mempool_sampling::MempoolSamplingConfig;
 pub use pending_tx_state_change_processor::PendingTxStateChangeProcessorActor;
+pub use profit_receiver::ProfitReceiverConfig;
+pub use search_budget::SearchBudgetConfig;
 pub use state_change_arb_searcher::StateChangeArbSearcherActor;
 pub use swap_calculator::SwapCalculator;
 
 mod block_state_change_processor;
+mod depeg_monitor;
+mod mempool_sampling;
 mod pending_tx_state_change_processor;
 mod state_change_arb_searcher;
 
@@ -14,4 +21,9 @@ mod affected_pools_logs;
 mod affected_pools_state;
 mod arb_actor;
 mod backrun_config;
+mod extra_gas;
+mod pool_groups;
+mod profit_receiver;
+mod search_budget;
 mod swap_calculator;
+mod warm_up;
@@ -0,0 +1,294 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+
+use alloy_consensus::Transaction;
+use alloy_primitives::{Address, TxHash};
+use serde::Deserialize;
+
+use loom_types_blockchain::{LoomDataTypesEthereum, MempoolTx, TouchedAddresses};
+use loom_types_entities::{Market, PoolId, ScoreAdjustments};
+
+fn default_max_in_flight() -> usize {
+    256
+}
+
+fn default_max_deferred() -> usize {
+    1024
+}
+
+fn default_hot_pools() -> usize {
+    1000
+}
+
+// seconds after which the hot pools are ranked again by the decayed adjustments
+const HOT_POOLS_REFRESH_SECS: u64 = 60;
+
+/// Load shedding of pending txs when they arrive faster than they are traced. Above half of `max_in_flight` traces a
+/// decreasing share of the pending txs is processed, txs touching pools of the highest scored paths are processed up to
+/// `max_in_flight`. Txs not processed are deferred until the load drops and dropped with the next block. Hot pools are
+/// the pools with the highest positive score adjustments pushed through the control API, of their own or of their paths
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct MempoolSamplingConfig {
+    /// Pending txs traced at the same time
+    #[serde(default = "default_max_in_flight")]
+    max_in_flight: usize,
+    /// Deferred txs kept, the oldest are dropped above it
+    #[serde(default = "default_max_deferred")]
+    max_deferred: usize,
+    /// Pools with the highest score adjustments prioritizing the txs touching them
+    #[serde(default = "default_hot_pools")]
+    hot_pools: usize,
+}
+
+impl Default for MempoolSamplingConfig {
+    fn default() -> Self {
+        Self { max_in_flight: default_max_in_flight(), max_deferred: default_max_deferred(), hot_pools: default_hot_pools() }
+    }
+}
+
+/// Pending txs by sampling outcome since the last block
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SamplingStats {
+    pub processed: u64,
+    /// Processed under load for touching hot pools
+    pub prioritized: u64,
+    pub deferred: u64,
+    /// Deferred and dropped, for the queue overflow or the next block
+    pub dropped: u64,
+    /// Mempool events missed by the processor lagging behind the channel
+    pub lagged: u64,
+    pub max_in_flight: usize,
+}
+
+/// Addresses a pending tx is known to touch before it is traced
+pub fn tx_addresses(mempool_tx: &MempoolTx<LoomDataTypesEthereum>) -> Vec<Address> {
+    let mut addresses = Vec::new();
+    if let Some(tx) = &mempool_tx.tx {
+        addresses.extend(tx.to());
+        if let Some(access_list) = tx.access_list() {
+            addresses.extend(access_list.iter().map(|item| item.address));
+        }
+    }
+    if let Some(state_update) = &mempool_tx.state_update {
+        addresses.extend(state_update.keys());
+    }
+    if let Some(logs) = &mempool_tx.logs {
+        addresses.extend(logs.iter().map(|log| log.inner.address));
+    }
+    addresses
+}
+
+pub struct MempoolSampler {
+    config: MempoolSamplingConfig,
+    /// Pools with the highest score adjustments, the bloom of the index rejects most addresses of pending txs
+    hot_pools: TouchedAddresses,
+    /// Adjustments the hot pools were ranked with and the time of the ranking
+    ranked_adjustments: Option<(Arc<ScoreAdjustments>, u64)>,
+    hot_deferred: VecDeque<TxHash>,
+    deferred: VecDeque<TxHash>,
+    /// Share of the cold txs accumulated until one is processed
+    credit: f64,
+    stats: SamplingStats,
+}
+
+impl MempoolSampler {
+    pub fn new(config: MempoolSamplingConfig) -> Self {
+        Self {
+            config: MempoolSamplingConfig { max_in_flight: config.max_in_flight.max(1), ..config },
+            hot_pools: TouchedAddresses::new(),
+            ranked_adjustments: None,
+            hot_deferred: VecDeque::new(),
+            deferred: VecDeque::new(),
+            credit: 0.0,
+            stats: SamplingStats::default(),
+        }
+    }
+
+    /// Index the pools with the highest positive score adjustments, the adjustments of a path count for each of its pools.
+    /// The pools are ranked again when the adjustments are replaced and periodically for their decay
+    pub fn update_hot_pools(&mut self, market: &Market, now: u64) {
+        let score_adjustments = market.score_adjustments().load();
+        if let Some((ranked_adjustments, ranked_at)) = &self.ranked_adjustments {
+            if Arc::ptr_eq(ranked_adjustments, &score_adjustments) && now < ranked_at + HOT_POOLS_REFRESH_SECS {
+                return;
+            }
+        }
+
+        let mut pool_adjustments: HashMap<Address, f64> = HashMap::new();
+        for (pool_id, adjustment) in score_adjustments.pools().iter() {
+            if let PoolId::Address(address) = pool_id {
+                *pool_adjustments.entry(*address).or_default() += adjustment.decayed(now);
+            }
+        }
+        for (path_hash, adjustment) in score_adjustments.paths().iter() {
+            let Some(swap_path) = market.swap_paths().get_path_by_hash(*path_hash) else { continue };
+            for pool in swap_path.pools.iter() {
+                if let PoolId::Address(address) = pool.get_pool_id() {
+                    *pool_adjustments.entry(address).or_default() += adjustment.decayed(now);
+                }
+            }
+        }
+        let mut hot_pools: Vec<(Address, f64)> = pool_adjustments.into_iter().filter(|(_, adjustment)| *adjustment > 0.0).collect();
+        hot_pools.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        self.hot_pools = TouchedAddresses::new();
+        for (address, _) in hot_pools.into_iter().take(self.config.hot_pools) {
+            self.hot_pools.insert(address);
+        }
+        self.ranked_adjustments = Some((score_adjustments, now));
+    }
+
+    pub fn hot_pools_len(&self) -> usize {
+        self.hot_pools.len()
+    }
+
+    pub fn is_hot(&self, addresses: &[Address]) -> bool {
+        addresses.iter().any(|address| self.hot_pools.contains(address))
+    }
+
+    fn soft_limit(&self) -> usize {
+        self.config.max_in_flight / 2
+    }
+
+    /// Whether the tx is processed now with `in_flight` traces running, the tx is deferred otherwise
+    pub fn sample(&mut self, in_flight: usize, hot: bool) -> bool {
+        self.stats.max_in_flight = self.stats.max_in_flight.max(in_flight);
+        let (soft_limit, max_in_flight) = (self.soft_limit(), self.config.max_in_flight);
+
+        let process = if in_flight < soft_limit {
+            true
+        } else if in_flight >= max_in_flight {
+            false
+        } else if hot {
+            self.stats.prioritized += 1;
+            true
+        } else {
+            // share of cold txs processed falls from all at the soft limit to none at the capacity
+            self.credit += (max_in_flight - in_flight) as f64 / (max_in_flight - soft_limit) as f64;
+            if self.credit >= 1.0 {
+                self.credit -= 1.0;
+                true
+            } else {
+                false
+            }
+        };
+
+        if process {
+            self.stats.processed += 1;
+        }
+        process
+    }
+
+    pub fn defer(&mut self, tx_hash: TxHash, hot: bool) {
+        self.stats.deferred += 1;
+        match hot {
+            true => self.hot_deferred.push_back(tx_hash),
+            false => self.deferred.push_back(tx_hash),
+        }
+        while self.hot_deferred.len() + self.deferred.len() > self.config.max_deferred {
+            if self.deferred.pop_front().is_none() {
+                self.hot_deferred.pop_front();
+            }
+            self.stats.dropped += 1;
+        }
+    }
+
+    /// Deferred tx to process with `in_flight` traces running, the hot ones first
+    pub fn next_deferred(&mut self, in_flight: usize) -> Option<TxHash> {
+        if in_flight >= self.soft_limit().max(1) {
+            return None;
+        }
+        let tx_hash = self.hot_deferred.pop_front().or_else(|| self.deferred.pop_front())?;
+        self.stats.processed += 1;
+        Some(tx_hash)
+    }
+
+    pub fn add_lagged(&mut self, lagged: u64) {
+        self.stats.lagged += lagged;
+    }
+
+    /// Drop the txs deferred for the previous block and return the stats since the previous block
+    pub fn next_block(&mut self) -> SamplingStats {
+        self.stats.dropped += (self.hot_deferred.len() + self.deferred.len()) as u64;
+        self.hot_deferred.clear();
+        self.deferred.clear();
+        self.credit = 0.0;
+        std::mem::take(&mut self.stats)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use loom_types_entities::{MockPool, ScoreAdjustment, SwapPath, Token};
+
+    fn sampler(max_in_flight: usize, max_deferred: usize) -> MempoolSampler {
+        MempoolSampler::new(MempoolSamplingConfig { max_in_flight, max_deferred, hot_pools: 2 })
+    }
+
+    #[test]
+    fn test_sample() {
+        let mut sampler = sampler(8, 16);
+        assert!(sampler.sample(3, false));
+        // 3/4 of the cold txs at 5 in flight
+        let processed = (0..8).filter(|_| sampler.sample(5, false)).count();
+        assert_eq!(processed, 6);
+        // hot txs use the whole capacity
+        assert!(sampler.sample(7, true));
+        assert!(!sampler.sample(8, true));
+
+        let stats = sampler.next_block();
+        assert_eq!(stats.processed, 8);
+        assert_eq!(stats.prioritized, 1);
+        assert_eq!(stats.max_in_flight, 8);
+    }
+
+    #[test]
+    fn test_update_hot_pools() {
+        let (token0, token1) = (Address::repeat_byte(1), Address::repeat_byte(2));
+        let pools: Vec<Address> = (0x10..0x14).map(Address::repeat_byte).collect();
+        let swap_path = SwapPath::new(
+            vec![Token::new(token0), Token::new(token1), Token::new(token0)],
+            vec![MockPool::new(token0, token1, pools[0]), MockPool::new(token0, token1, pools[1])],
+        );
+        let mut market = Market::default();
+        market.add_paths(vec![swap_path.clone()]);
+        let adjustment = |value: f64| ScoreAdjustment { value, half_life_secs: 0, source: None, timestamp: 1000 };
+
+        let mut sampler = sampler(8, 16);
+        sampler.update_hot_pools(&market, 1000);
+        assert_eq!(sampler.hot_pools_len(), 0);
+
+        market.score_adjustments().update(|score_adjustments| {
+            score_adjustments.set_path(swap_path.get_hash(), adjustment(0.5));
+            score_adjustments.set_pool(PoolId::Address(pools[1]), adjustment(0.25));
+            score_adjustments.set_pool(PoolId::Address(pools[2]), adjustment(0.6));
+            score_adjustments.set_pool(PoolId::Address(pools[3]), adjustment(-1.0));
+        });
+        sampler.update_hot_pools(&market, 1000);
+        // pool 1 with its path adjustment and pool 2 rank above pool 0, pool 3 is lowered
+        assert_eq!(sampler.hot_pools_len(), 2);
+        assert!(sampler.is_hot(&[pools[1]]) && sampler.is_hot(&[pools[2]]));
+        assert!(!sampler.is_hot(&[pools[0]]) && !sampler.is_hot(&[pools[3]]));
+    }
+
+    #[test]
+    fn test_deferred() {
+        let mut sampler = sampler(4, 3);
+        let (hot, cold0, cold1, cold2) = (TxHash::repeat_byte(1), TxHash::repeat_byte(2), TxHash::repeat_byte(3), TxHash::repeat_byte(4));
+        sampler.defer(cold0, false);
+        sampler.defer(hot, true);
+        sampler.defer(cold1, false);
+        // oldest cold tx dropped
+        sampler.defer(cold2, false);
+
+        assert_eq!(sampler.next_deferred(2), None);
+        assert_eq!(sampler.next_deferred(1), Some(hot));
+        assert_eq!(sampler.next_deferred(1), Some(cold1));
+
+        let stats = sampler.next_block();
+        assert_eq!(stats.deferred, 4);
+        assert_eq!(stats.dropped, 2);
+        assert_eq!(sampler.next_deferred(0), None);
+    }
+}
@@ -7,32 +7,115 @@ use alloy_rpc_types::state::StateOverride;
 use alloy_rpc_types::{BlockOverrides, TransactionRequest};
 use alloy_rpc_types_trace::geth::GethDebugTracingCallOptions;
 use eyre::{eyre, Result};
+use influxdb::{Timestamp, WriteQuery};
 use lazy_static::lazy_static;
 use revm::primitives::bitvec::macros::internal::funty::Fundamental;
 use revm::{Database, DatabaseCommit, DatabaseRef};
 use std::collections::hash_map::Entry;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::marker::PhantomData;
 use std::sync::Arc;
-use tokio::sync::RwLock;
-use tracing::{debug, error, warn};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{mpsc, RwLock};
+use tracing::{debug, error, trace, warn};
 
 use loom_core_actors::{subscribe, Accessor, Actor, ActorResult, Broadcaster, Consumer, Producer, SharedState, WorkerResult};
 use loom_core_actors_macros::{Accessor, Consumer, Producer};
 use loom_core_blockchain::{Blockchain, BlockchainState, Strategy};
 use loom_node_debug_provider::DebugProviderExt;
-use loom_types_blockchain::{debug_trace_call_diff, GethStateUpdateVec, Mempool, TRACING_CALL_OPTS};
+use loom_types_blockchain::{
+    apply_state_update_to_override, debug_trace_call_diff, GethStateUpdate, GethStateUpdateVec, Mempool, TRACING_CALL_OPTS,
+};
 use loom_types_entities::required_state::{accounts_vec_len, storage_vec_len};
 use loom_types_entities::{LatestBlock, Market, MarketState};
-use loom_types_events::{MarketEvents, MempoolEvents, StateUpdateEvent};
+use loom_types_events::{MarketEvents, MempoolEvents, MessageFlashblock, StateUpdateEvent};
 
 use super::affected_pools_code::{get_affected_pools_from_code, is_pool_code};
 use super::affected_pools_state::get_affected_pools_from_state_update;
+use super::mempool_sampling::{tx_addresses, MempoolSampler, MempoolSamplingConfig};
 
 lazy_static! {
     static ref COINBASE: Address = "0x1f9090aaE28b8a3dCeaDf281B0F12828e676c326".parse().unwrap();
 }
 
+// Deprioritized txs of spammers kept, the oldest are dropped above it
+const MAX_SPAM_DEFERRED: usize = 256;
+
+/// Pending txs of senders with a spammer reputation. They are processed one at a time while no other pending tx is
+/// traced and dropped with the next block
+#[derive(Default)]
+struct SpamQueue {
+    txs: VecDeque<TxHash>,
+    deprioritized: u64,
+    dropped: u64,
+}
+
+impl SpamQueue {
+    /// Queue the tx of a spammer, returns the tx to process now otherwise
+    fn route(&mut self, mempool: &Mempool, tx_hash: TxHash) -> Option<TxHash> {
+        if !mempool.is_spam_tx(&tx_hash) {
+            return Some(tx_hash);
+        }
+        self.deprioritized += 1;
+        self.txs.push_back(tx_hash);
+        if self.txs.len() > MAX_SPAM_DEFERRED {
+            self.txs.pop_front();
+            self.dropped += 1;
+        }
+        None
+    }
+
+    fn next(&mut self, in_flight: usize) -> Option<TxHash> {
+        if in_flight > 0 {
+            return None;
+        }
+        self.txs.pop_front()
+    }
+
+    /// Drop the queued txs, returns the deprioritized and dropped txs since the previous block
+    fn next_block(&mut self) -> (u64, u64) {
+        self.dropped += self.txs.len() as u64;
+        self.txs.clear();
+        (std::mem::take(&mut self.deprioritized), std::mem::take(&mut self.dropped))
+    }
+}
+
+/// Trace the transaction on top of the post states of pending transactions expected before it in the block that change
+/// the same accounts. Returns the post states of these transactions and the pre and post state of the transaction,
+/// `None` if no such transaction is known.
+async fn debug_trace_call_at_expected_index<P, N>(
+    client: P,
+    mempool: &SharedState<Mempool>,
+    tx_hash: TxHash,
+    transaction_request: TransactionRequest,
+    call_opts: GethDebugTracingCallOptions,
+    touched: &GethStateUpdate,
+    next_base_fee: u64,
+) -> Result<Option<(GethStateUpdateVec, GethStateUpdate, GethStateUpdate)>>
+where
+    N: Network,
+    P: Provider<N> + DebugProviderExt<N> + Send + Sync + Clone + 'static,
+{
+    let txs_ahead_updates: GethStateUpdateVec = mempool
+        .read()
+        .await
+        .txs_ahead(&tx_hash, next_base_fee as u128)
+        .into_iter()
+        .filter_map(|mempool_tx| mempool_tx.state_update.clone())
+        .filter(|state_update| state_update.keys().any(|address| touched.contains_key(address)))
+        .collect();
+    if txs_ahead_updates.is_empty() {
+        return Ok(None);
+    }
+
+    let mut state_override = call_opts.state_overrides.clone().unwrap_or_default();
+    txs_ahead_updates.iter().for_each(|state_update| apply_state_update_to_override(&mut state_override, state_update));
+    let call_opts = GethDebugTracingCallOptions { state_overrides: Some(state_override), ..call_opts };
+
+    let (pre, post) = debug_trace_call_diff(client, transaction_request, BlockNumberOrTag::Latest.into(), Some(call_opts)).await?;
+    Ok(Some((txs_ahead_updates, pre, post)))
+}
+
 /// Process a pending tx from the mempool
 #[allow(clippy::too_many_arguments)]
 pub async fn pending_tx_state_change_task<P, N, DB>(
@@ -47,6 +130,7 @@ pub async fn pending_tx_state_change_task<P, N, DB>(
     cur_block_time: u64,
     cur_next_base_fee: u64,
     cur_state_override: StateOverride,
+    intra_block_state: bool,
     state_updates_broadcaster: Broadcaster<StateUpdateEvent<DB>>,
 ) -> Result<()>
 where
@@ -64,6 +148,11 @@ where
         None => return Err(eyre!("MEMPOOL_TX_NOT_FOUND")),
     };
 
+    if let Some(replaced_by) = mempool_tx.replaced_by {
+        debug!(%tx_hash, %replaced_by, "Mempool tx replaced");
+        return Ok(());
+    }
+
     let tx = match mempool_tx.tx.clone() {
         Some(tx) => tx,
         None => return Err(eyre!("NO_TX_IN_MEMPOOL")),
@@ -136,7 +225,7 @@ where
     }
 
     let diff_trace_result =
-        debug_trace_call_diff(client.clone(), transaction_request, BlockNumberOrTag::Latest.into(), Some(call_opts)).await;
+        debug_trace_call_diff(client.clone(), transaction_request.clone(), BlockNumberOrTag::Latest.into(), Some(call_opts.clone())).await;
     match diff_trace_result {
         Ok((pre, post)) => {
             state_required_vec.push(pre.clone());
@@ -161,6 +250,37 @@ where
 
     affecting_tx.write().await.insert(tx_hash, !affected_pools.is_empty());
 
+    if intra_block_state && !affected_pools.is_empty() {
+        // the post state is only read to simulate the txs after it, the write lock is taken when it changed
+        if let Some(post) = state_update_vec.last().filter(|post| mempool_tx.state_update.as_ref() != Some(*post)) {
+            mempool.write().await.add_tx_state_change(tx_hash, post.clone());
+        }
+        let touched: GethStateUpdate = merged_state_update_vec.iter().flat_map(|state| state.clone()).collect();
+        match debug_trace_call_at_expected_index(
+            client.clone(),
+            &mempool,
+            tx_hash,
+            transaction_request,
+            call_opts,
+            &touched,
+            cur_next_base_fee,
+        )
+        .await
+        {
+            Ok(Some((txs_ahead_updates, pre, post))) => {
+                let tx_index = mempool.read().await.expected_tx_index(&tx_hash, cur_next_base_fee as u128);
+                debug!(%tx_hash, ?tx_index, txs_ahead = txs_ahead_updates.len(), "Pending tx traced at expected index");
+                state_required_vec = vec![pre];
+                state_update_vec = txs_ahead_updates;
+                state_update_vec.push(post);
+            }
+            Ok(None) => {}
+            Err(error) => {
+                debug!(%tx_hash, %error, "debug_trace_call at expected index error");
+            }
+        }
+    }
+
     //TODO : Fix Latest header is empty
     if let Some(latest_header) = latest_block.read().await.block_header.clone() {
         let next_block_number = latest_header.number.as_u64() + 1;
@@ -250,7 +370,11 @@ pub async fn pending_tx_state_change_worker<P, N, DB>(
     market_state: SharedState<MarketState<DB>>,
     mempool_events_rx: Broadcaster<MempoolEvents>,
     market_events_rx: Broadcaster<MarketEvents>,
+    flashblocks_rx: Option<Broadcaster<MessageFlashblock>>,
     state_updates_broadcaster: Broadcaster<StateUpdateEvent<DB>>,
+    influxdb_write_channel_tx: Broadcaster<WriteQuery>,
+    intra_block_state: bool,
+    mempool_sampling: Option<MempoolSamplingConfig>,
 ) -> WorkerResult
 where
     N: Network,
@@ -259,20 +383,70 @@ where
 {
     subscribe!(mempool_events_rx);
     subscribe!(market_events_rx);
+    let mut flashblocks_rx = flashblocks_rx.map(|flashblocks_rx| flashblocks_rx.subscribe());
 
     let affecting_tx: Arc<RwLock<HashMap<TxHash, bool>>> = Arc::new(RwLock::new(HashMap::new()));
     let mut cur_next_base_fee = 0;
     let mut cur_block_number: Option<BlockNumber> = None;
     let mut cur_block_time: Option<u64> = None;
     let mut cur_state_override: StateOverride = StateOverride::default();
+    // pending txs processed since the last block, txs of spammers are deprioritized
+    let mut processed_txs: u64 = 0;
+    let mut spam_queue = SpamQueue::default();
+
+    let mut sampler = mempool_sampling.map(MempoolSampler::new);
+    // pending txs traced at the moment, every task reports its completion
+    let mut in_flight: usize = 0;
+    let (done_tx, mut done_rx) = mpsc::unbounded_channel::<()>();
 
     loop {
+        let mut ready_txs: Vec<TxHash> = Vec::new();
+
         tokio::select! {
             msg = market_events_rx.recv() => {
                 if let Ok(msg) = msg {
                     let market_event_msg : MarketEvents = msg;
                     if let MarketEvents::BlockHeaderUpdate{ block_number, block_hash, timestamp, base_fee, next_base_fee } = market_event_msg {
                         debug!("Block header update {} {} base_fee {} ", block_number, block_hash, base_fee);
+
+                        let spammers = mempool.read().await.reputation().spammers_count();
+                        let (deprioritized, spam_dropped) = spam_queue.next_block();
+                        let write_query = WriteQuery::new(Timestamp::from(chrono::Utc::now()), "mempool_spam_filter")
+                            .add_tag("block", block_number)
+                            .add_field("processed", processed_txs)
+                            .add_field("deprioritized", deprioritized)
+                            .add_field("dropped", spam_dropped)
+                            .add_field("spammers", spammers as u64);
+                        if let Err(e) = influxdb_write_channel_tx.send(write_query) {
+                            error!("Failed to send spam filter stats to influxdb: {:?}", e);
+                        }
+                        processed_txs = 0;
+
+                        if let Some(sampler) = sampler.as_mut() {
+                            let stats = sampler.next_block();
+                            let write_query = WriteQuery::new(Timestamp::from(chrono::Utc::now()), "mempool_sampling")
+                                .add_tag("block", block_number)
+                                .add_field("processed", stats.processed)
+                                .add_field("prioritized", stats.prioritized)
+                                .add_field("deferred", stats.deferred)
+                                .add_field("dropped", stats.dropped)
+                                .add_field("lagged", stats.lagged)
+                                .add_field("max_in_flight", stats.max_in_flight as u64)
+                                .add_field("hot_pools", sampler.hot_pools_len() as u64);
+                            if let Err(e) = influxdb_write_channel_tx.send(write_query) {
+                                error!("Failed to send mempool sampling stats to influxdb: {:?}", e);
+                            }
+                            if stats.dropped > 0 || stats.lagged > 0 {
+                                warn!(
+                                    block = block_number,
+                                    dropped = stats.dropped,
+                                    lagged = stats.lagged,
+                                    "Pending txs not processed under load"
+                                );
+                            }
+                            sampler.update_hot_pools(&*market.read().await, timestamp);
+                        }
+
                         cur_block_number = Some( block_number.as_u64() + 1);
                         cur_block_time = Some(timestamp + 12 );
                         cur_next_base_fee = next_base_fee;
@@ -289,7 +463,25 @@ where
                     }
                 }
             }
+            // preconfirmed sub-blocks extend the state override, pending txs are simulated on top of them
+            msg = async { flashblocks_rx.as_mut().unwrap().recv().await }, if flashblocks_rx.is_some() => {
+                if let Ok(flashblock) = msg {
+                    if Some(flashblock.block_number) != cur_block_number {
+                        trace!(block_number = flashblock.block_number, ?cur_block_number, "Flashblock of another block skipped");
+                        continue;
+                    }
+                    flashblock.state_update.iter().for_each(|state_update| apply_state_update_to_override(&mut cur_state_override, state_update));
+                    debug!(block_number = flashblock.block_number, index = flashblock.index, txs = flashblock.txs.len(), "Flashblock state applied");
+                }
+            }
             msg = mempool_events_rx.recv() => {
+                if let Err(RecvError::Lagged(lagged)) = msg {
+                    warn!(lagged, "Mempool events channel lagged");
+                    if let Some(sampler) = sampler.as_mut() {
+                        sampler.add_lagged(lagged);
+                    }
+                    continue;
+                }
                 if let Ok(msg) = msg {
                     let mempool_event_msg : MempoolEvents = msg;
                     if let MempoolEvents::MempoolActualTxUpdate{ tx_hash }  = mempool_event_msg {
@@ -298,25 +490,71 @@ where
                             continue;
                         }
 
-                        tokio::task::spawn(
-                            pending_tx_state_change_task(
-                                client.clone(),
-                                tx_hash,
-                                market.clone(),
-                                mempool.clone(),
-                                latest_block.clone(),
-                                market_state.clone(),
-                                affecting_tx.clone(),
-                                cur_block_number.unwrap_or_default(),
-                                cur_block_time.unwrap_or_default(),
-                                cur_next_base_fee,
-                                cur_state_override.clone(),
-                                state_updates_broadcaster.clone(),
-                            )
-                        );
+                        let Some(tx_hash) = spam_queue.route(&*mempool.read().await, tx_hash) else {
+                            trace!(%tx_hash, "Pending tx from spammer deprioritized");
+                            continue;
+                        };
+                        processed_txs += 1;
+
+                        match sampler.as_mut() {
+                            Some(sampler) => {
+                                let hot = match mempool.read().await.get_tx_by_hash(&tx_hash) {
+                                    Some(mempool_tx) => sampler.is_hot(&tx_addresses(mempool_tx)),
+                                    None => false,
+                                };
+                                if sampler.sample(in_flight, hot) {
+                                    ready_txs.push(tx_hash);
+                                } else {
+                                    trace!(%tx_hash, hot, in_flight, "Pending tx deferred");
+                                    sampler.defer(tx_hash, hot);
+                                }
+                            }
+                            None => ready_txs.push(tx_hash),
+                        }
                     }
                 }
             }
+            Some(_) = done_rx.recv() => {
+                in_flight = in_flight.saturating_sub(1);
+                if let Some(sampler) = sampler.as_mut() {
+                    while let Some(tx_hash) = sampler.next_deferred(in_flight + ready_txs.len()) {
+                        ready_txs.push(tx_hash);
+                    }
+                }
+            }
+        }
+
+        // txs of spammers are traced when no other pending tx is
+        if ready_txs.is_empty() {
+            if let Some(tx_hash) = spam_queue.next(in_flight) {
+                processed_txs += 1;
+                ready_txs.push(tx_hash);
+            }
+        }
+
+        for tx_hash in ready_txs {
+            in_flight += 1;
+            let task = pending_tx_state_change_task(
+                client.clone(),
+                tx_hash,
+                market.clone(),
+                mempool.clone(),
+                latest_block.clone(),
+                market_state.clone(),
+                affecting_tx.clone(),
+                cur_block_number.unwrap_or_default(),
+                cur_block_time.unwrap_or_default(),
+                cur_next_base_fee,
+                cur_state_override.clone(),
+                intra_block_state,
+                state_updates_broadcaster.clone(),
+            );
+            let done_tx = done_tx.clone();
+            tokio::task::spawn(async move {
+                let result = task.await;
+                let _ = done_tx.send(());
+                result
+            });
         }
     }
 }
@@ -324,6 +562,8 @@ where
 #[derive(Accessor, Consumer, Producer)]
 pub struct PendingTxStateChangeProcessorActor<P, N, DB: Clone + Send + Sync + 'static> {
     client: P,
+    intra_block_state: bool,
+    mempool_sampling: Option<MempoolSamplingConfig>,
     #[accessor]
     market: Option<SharedState<Market>>,
     #[accessor]
@@ -336,8 +576,12 @@ pub struct PendingTxStateChangeProcessorActor<P, N, DB: Clone + Send + Sync + 's
     market_events_rx: Option<Broadcaster<MarketEvents>>,
     #[consumer]
     mempool_events_rx: Option<Broadcaster<MempoolEvents>>,
+    #[consumer]
+    flashblocks_rx: Option<Broadcaster<MessageFlashblock>>,
     #[producer]
     state_updates_tx: Option<Broadcaster<StateUpdateEvent<DB>>>,
+    #[producer]
+    influxdb_write_channel_tx: Option<Broadcaster<WriteQuery>>,
     _n: PhantomData<N>,
 }
 
@@ -350,17 +594,31 @@ where
     pub fn new(client: P) -> PendingTxStateChangeProcessorActor<P, N, DB> {
         PendingTxStateChangeProcessorActor {
             client,
+            intra_block_state: false,
+            mempool_sampling: None,
             market: None,
             mempool: None,
             market_state: None,
             latest_block: None,
             market_events_rx: None,
             mempool_events_rx: None,
+            flashblocks_rx: None,
             state_updates_tx: None,
+            influxdb_write_channel_tx: None,
             _n: PhantomData,
         }
     }
 
+    /// Simulate pending txs on top of the pending txs expected before them in the next block instead of the last block only
+    pub fn with_intra_block_state(self, intra_block_state: bool) -> Self {
+        Self { intra_block_state, ..self }
+    }
+
+    /// Sample pending txs when they arrive faster than they are traced instead of tracing every pending tx
+    pub fn with_mempool_sampling(self, mempool_sampling: Option<MempoolSamplingConfig>) -> Self {
+        Self { mempool_sampling, ..self }
+    }
+
     pub fn on_bc(self, bc: &Blockchain, state: &BlockchainState<DB>, strategy: &Strategy<DB>) -> Self {
         Self {
             market: Some(bc.market()),
@@ -369,7 +627,9 @@ where
             latest_block: Some(bc.latest_block()),
             market_events_rx: Some(bc.market_events_channel()),
             mempool_events_rx: Some(bc.mempool_events_channel()),
+            flashblocks_rx: bc.execution_profile().sub_block_interval().map(|_| bc.flashblocks_channel()),
             state_updates_tx: Some(strategy.state_update_channel()),
+            influxdb_write_channel_tx: Some(bc.influxdb_write_channel()),
             ..self
         }
     }
@@ -390,7 +650,11 @@ where
             self.market_state.clone().unwrap(),
             self.mempool_events_rx.clone().unwrap(),
             self.market_events_rx.clone().unwrap(),
+            self.flashblocks_rx.clone(),
             self.state_updates_tx.clone().unwrap(),
+            self.influxdb_write_channel_tx.clone().unwrap(),
+            self.intra_block_state,
+            self.mempool_sampling,
         ));
         Ok(vec![task])
     }
@@ -399,3 +663,66 @@ where
         "PendingTxStateChangeProcessorActor"
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloy_consensus::{Signed, TxEip1559, TxEnvelope};
+    use alloy_primitives::{keccak256, PrimitiveSignature};
+    use alloy_rpc_types::Transaction;
+
+    fn tx(from: Address, nonce: u64) -> Transaction {
+        let tx = TxEip1559 { nonce, ..Default::default() };
+        let tx_hash = keccak256([from.as_slice(), &nonce.to_be_bytes()].concat());
+        let signed = Signed::new_unchecked(tx, PrimitiveSignature::new(U256::from(1), U256::from(1), false), tx_hash);
+        Transaction {
+            inner: TxEnvelope::Eip1559(signed),
+            block_hash: None,
+            block_number: None,
+            transaction_index: None,
+            effective_gas_price: None,
+            from,
+        }
+    }
+
+    // adds the txs of the sender, `bad` of them failed or simulated without profit
+    fn add_sender_txs(mempool: &mut Mempool, from: Address, txs: u64, bad: u64, failed: bool) -> Vec<TxHash> {
+        (0..txs)
+            .map(|nonce| {
+                let tx = tx(from, nonce);
+                let tx_hash = tx.tx_hash();
+                mempool.add_tx(tx.clone());
+                mempool.track_tx(&tx);
+                if nonce < bad {
+                    match failed {
+                        true => mempool.set_failed(tx_hash),
+                        false => mempool.set_low_value(tx_hash),
+                    }
+                }
+                tx_hash
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_spam_queue() {
+        let mut mempool = Mempool::default();
+        let (trader, spammer) = (Address::repeat_byte(1), Address::repeat_byte(2));
+        // a busy sender with a few unprofitable txs is not a spammer
+        let trader_txs = add_sender_txs(&mut mempool, trader, 100, 20, false);
+        let spammer_txs = add_sender_txs(&mut mempool, spammer, 30, 30, true);
+
+        let mut spam_queue = SpamQueue::default();
+        assert_eq!(spam_queue.route(&mempool, spammer_txs[0]), None);
+        assert_eq!(spam_queue.route(&mempool, spammer_txs[1]), None);
+        for tx_hash in trader_txs {
+            assert_eq!(spam_queue.route(&mempool, tx_hash), Some(tx_hash));
+        }
+
+        // spam txs are processed after the other txs
+        assert_eq!(spam_queue.next(1), None);
+        assert_eq!(spam_queue.next(0), Some(spammer_txs[0]));
+        assert_eq!(spam_queue.next_block(), (2, 1));
+        assert_eq!(spam_queue.next(0), None);
+    }
+}
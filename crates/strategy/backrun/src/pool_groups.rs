@@ -0,0 +1,87 @@
+use std::collections::HashMap;
+
+use alloy_primitives::{Address, U256};
+use eyre::ErrReport;
+use revm::primitives::Env;
+use revm::DatabaseRef;
+
+use loom_types_entities::{Market, PoolId, PoolWrapper, SwapPath};
+
+/// Collapses paths that differ only in members of the same pool groups, e.g. UniswapV3 fee tiers of a pair,
+/// and picks the member with the best output for every hop of the remaining path on calculation
+#[derive(Default)]
+pub(crate) struct PoolGroupSelector {
+    // pool -> other enabled members of its group
+    alternatives: HashMap<PoolId, Vec<PoolWrapper>>,
+}
+
+impl PoolGroupSelector {
+    /// Keep one path per sequence of tokens and pool groups and snapshot the alternatives of its pools
+    pub fn new(market: &Market, swap_paths: Vec<SwapPath>) -> (Self, Vec<SwapPath>) {
+        // (token from, first pool of the group) for every hop -> path
+        let mut grouped_paths: HashMap<Vec<(Address, PoolId)>, SwapPath> = HashMap::new();
+        for swap_path in swap_paths {
+            let group_key: Vec<(Address, PoolId)> = swap_path
+                .tokens
+                .iter()
+                .zip(swap_path.pools.iter())
+                .map(|(token, pool)| {
+                    let pool_id = pool.get_pool_id();
+                    let group_id = market.get_pool_group(&pool_id).and_then(|pool_group| pool_group.first().copied()).unwrap_or(pool_id);
+                    (token.get_address(), group_id)
+                })
+                .collect();
+            grouped_paths.entry(group_key).or_insert(swap_path);
+        }
+
+        let mut alternatives: HashMap<PoolId, Vec<PoolWrapper>> = HashMap::new();
+        for swap_path in grouped_paths.values() {
+            for pool in swap_path.pools.iter() {
+                let pool_id = pool.get_pool_id();
+                if !alternatives.contains_key(&pool_id) {
+                    let pool_alternatives = market.get_pool_group_alternatives(&pool_id);
+                    if !pool_alternatives.is_empty() {
+                        alternatives.insert(pool_id, pool_alternatives);
+                    }
+                }
+            }
+        }
+
+        (Self { alternatives }, grouped_paths.into_values().collect())
+    }
+
+    /// Replace every pool of the path by the member of its group with the best output for `amount_in`
+    pub fn select<DB: DatabaseRef<Error = ErrReport>>(&self, swap_path: SwapPath, state: &DB, env: Env, amount_in: U256) -> SwapPath {
+        if self.alternatives.is_empty() {
+            return swap_path;
+        }
+
+        let mut pools = swap_path.pools.clone();
+        let mut amount = amount_in;
+        for idx in 0..pools.len() {
+            let token_from = swap_path.tokens[idx].get_address();
+            let token_to = swap_path.tokens[idx + 1].get_address();
+
+            let mut best: Option<(U256, PoolWrapper)> = None;
+            let candidates = std::iter::once(&swap_path.pools[idx])
+                .chain(self.alternatives.get(&swap_path.pools[idx].get_pool_id()).into_iter().flatten());
+            for candidate in candidates {
+                if pools.iter().enumerate().any(|(i, pool)| i != idx && pool == candidate) {
+                    continue;
+                }
+                if let Ok((amount_out, _)) = candidate.calculate_out_amount(state, env.clone(), &token_from, &token_to, amount) {
+                    if best.as_ref().is_none_or(|(best_amount_out, _)| amount_out > *best_amount_out) {
+                        best = Some((amount_out, candidate.clone()));
+                    }
+                }
+            }
+
+            // the original pools are kept for the rest of the path
+            let Some((amount_out, pool)) = best else { break };
+            pools[idx] = pool;
+            amount = amount_out;
+        }
+
+        SwapPath { pools, ..swap_path }
+    }
+}
@@ -0,0 +1,64 @@
+use alloy_network::Network;
+use alloy_primitives::{Address, Bytes};
+use alloy_provider::Provider;
+use eyre::{eyre, Result};
+use serde::Deserialize;
+
+/// Custody of the profits of a strategy instance. Backrun transactions pay the profit net of the gas and tips out of
+/// the multicaller to the receiver, e.g. a cold wallet or a splitter contract
+#[derive(Clone, Debug, Deserialize)]
+pub struct ProfitReceiverConfig {
+    pub address: Address,
+    /// Reject a receiver without contract code, for splitter contracts
+    #[serde(default)]
+    pub require_contract: bool,
+    /// Addresses never accepted as the receiver
+    #[serde(default)]
+    pub blocklist: Vec<Address>,
+}
+
+impl ProfitReceiverConfig {
+    /// Check the receiver against the blocklist and the contract requirement with the code deployed at its address
+    pub fn validate(&self, code: &Bytes) -> Result<()> {
+        if self.address.is_zero() {
+            return Err(eyre!("PROFIT_RECEIVER_ZERO_ADDRESS"));
+        }
+        if self.blocklist.contains(&self.address) {
+            return Err(eyre!("PROFIT_RECEIVER_BLOCKLISTED : {}", self.address));
+        }
+        if self.require_contract && code.is_empty() {
+            return Err(eyre!("PROFIT_RECEIVER_NOT_CONTRACT : {}", self.address));
+        }
+        Ok(())
+    }
+
+    pub async fn validate_on_chain<P, N>(&self, client: &P) -> Result<()>
+    where
+        N: Network,
+        P: Provider<N>,
+    {
+        let code = client.get_code_at(self.address).await?;
+        self.validate(&code)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_validate() {
+        let receiver = Address::repeat_byte(1);
+        let config = ProfitReceiverConfig { address: receiver, require_contract: false, blocklist: vec![Address::repeat_byte(2)] };
+        assert!(config.validate(&Bytes::new()).is_ok());
+
+        let config = ProfitReceiverConfig { require_contract: true, ..config };
+        assert!(config.validate(&Bytes::new()).is_err());
+        assert!(config.validate(&Bytes::from(vec![0x60, 0x80])).is_ok());
+
+        let config = ProfitReceiverConfig { blocklist: vec![receiver], ..config };
+        assert!(config.validate(&Bytes::from(vec![0x60, 0x80])).is_err());
+        let config = ProfitReceiverConfig { address: Address::ZERO, blocklist: vec![], require_contract: false };
+        assert!(config.validate(&Bytes::new()).is_err());
+    }
+}
@@ -0,0 +1,142 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use serde::Deserialize;
+
+use loom_types_entities::SwapPath;
+
+use crate::block_state_change_processor::BLOCK_SEARCHER_ORIGIN;
+use crate::depeg_monitor::DEPEG_MONITOR_ORIGIN;
+
+fn default_mempool_ms() -> u64 {
+    15
+}
+
+fn default_block_ms() -> u64 {
+    150
+}
+
+/// Latency budgets of a search by its trigger. Paths are evaluated from the highest adjusted historical score and the paths left
+/// when the budget expires are skipped, the opportunities found until then are kept
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct SearchBudgetConfig {
+    /// Budget of searches triggered by pending transactions in milliseconds
+    #[serde(default = "default_mempool_ms")]
+    mempool_ms: u64,
+    /// Budget of searches triggered by a new block in milliseconds
+    #[serde(default = "default_block_ms")]
+    block_ms: u64,
+}
+
+impl Default for SearchBudgetConfig {
+    fn default() -> Self {
+        Self { mempool_ms: default_mempool_ms(), block_ms: default_block_ms() }
+    }
+}
+
+impl SearchBudgetConfig {
+    pub fn budget(&self, origin: &str) -> Duration {
+        if origin == BLOCK_SEARCHER_ORIGIN || origin == DEPEG_MONITOR_ORIGIN {
+            Duration::from_millis(self.block_ms)
+        } else {
+            Duration::from_millis(self.mempool_ms)
+        }
+    }
+}
+
+/// Deadline of a single search shared by the calculation threads
+pub(crate) struct SearchController {
+    deadline: Option<Instant>,
+    expired: AtomicBool,
+    next: AtomicUsize,
+    skipped: AtomicUsize,
+}
+
+impl SearchController {
+    /// Controller expiring `budget` after `start_time`, never expiring without a budget
+    pub fn new(start_time: Instant, budget: Option<Duration>) -> Self {
+        Self {
+            deadline: budget.map(|budget| start_time + budget),
+            expired: AtomicBool::new(false),
+            next: AtomicUsize::new(0),
+            skipped: AtomicUsize::new(0),
+        }
+    }
+
+    /// Order paths for evaluation, the highest score first
+    pub fn prioritize(&self, swap_paths: &mut [SwapPath]) {
+        if self.deadline.is_some() {
+            swap_paths.sort_by(|a, b| b.score.unwrap_or_default().total_cmp(&a.score.unwrap_or_default()));
+        }
+    }
+
+    pub fn is_expired(&self) -> bool {
+        if self.expired.load(Ordering::Relaxed) {
+            return true;
+        }
+        match self.deadline {
+            Some(deadline) if Instant::now() >= deadline => {
+                self.expired.store(true, Ordering::Relaxed);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Index of the next of `len` prioritized paths to evaluate, shared by the calculation threads. Returns `None` when all
+    /// paths are taken or the budget is expired, the paths not taken on expiry are counted as skipped
+    pub fn next_index(&self, len: usize) -> Option<usize> {
+        if self.is_expired() {
+            let taken = self.next.swap(len, Ordering::Relaxed);
+            if taken < len {
+                self.skipped.fetch_add(len - taken, Ordering::Relaxed);
+            }
+            return None;
+        }
+        let idx = self.next.fetch_add(1, Ordering::Relaxed);
+        (idx < len).then_some(idx)
+    }
+
+    pub fn skipped(&self) -> usize {
+        self.skipped.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloy_primitives::Address;
+    use loom_types_entities::{MockPool, Token};
+    use std::sync::Arc;
+
+    fn scored_path(pool: u8, score: Option<f64>) -> SwapPath {
+        let token0 = Arc::new(Token::new(Address::repeat_byte(1)));
+        let token1 = Arc::new(Token::new(Address::repeat_byte(2)));
+        let pool = MockPool::new(token0.get_address(), token1.get_address(), Address::repeat_byte(pool));
+        SwapPath { score, ..SwapPath::new_swap(token0, token1, pool.into()) }
+    }
+
+    #[test]
+    fn test_search_controller() {
+        let config = SearchBudgetConfig::default();
+        assert_eq!(config.budget(BLOCK_SEARCHER_ORIGIN), Duration::from_millis(150));
+        assert_eq!(config.budget("pending_tx_searcher"), Duration::from_millis(15));
+
+        let controller = SearchController::new(Instant::now(), Some(Duration::from_secs(60)));
+        let mut paths = vec![scored_path(10, Some(0.5)), scored_path(11, None), scored_path(12, Some(0.9))];
+        controller.prioritize(&mut paths);
+        let pools: Vec<Address> = paths.iter().map(|path| path.pools[0].get_address()).collect();
+        assert_eq!(pools, vec![Address::repeat_byte(12), Address::repeat_byte(10), Address::repeat_byte(11)]);
+        assert_eq!(controller.next_index(2), Some(0));
+        assert_eq!(controller.next_index(2), Some(1));
+        assert_eq!(controller.next_index(2), None);
+        assert_eq!(controller.skipped(), 0);
+
+        let controller = SearchController::new(Instant::now() - Duration::from_millis(20), Some(Duration::from_millis(15)));
+        assert_eq!(controller.next_index(3), None);
+        assert_eq!(controller.next_index(3), None);
+        assert_eq!(controller.skipped(), 3);
+
+        assert!(!SearchController::new(Instant::now() - Duration::from_secs(60), None).is_expired());
+    }
+}
@@ -1,4 +1,4 @@
-use std::collections::{BTreeMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::sync::Arc;
 
 use alloy_primitives::U256;
@@ -14,14 +14,22 @@ use tokio::sync::broadcast::error::RecvError;
 use tracing::warn;
 use tracing::{debug, error, info, trace};
 
+use crate::block_state_change_processor::BLOCK_SEARCHER_ORIGIN;
+use crate::depeg_monitor::DEPEG_MONITOR_ORIGIN;
+use crate::pool_groups::PoolGroupSelector;
+use crate::search_budget::SearchController;
+use crate::warm_up::WarmUpCache;
 use crate::BackrunConfig;
 use crate::SwapCalculator;
-use loom_core_actors::{subscribe, Accessor, Actor, ActorResult, Broadcaster, Consumer, Producer, SharedState, WorkerResult};
+use loom_core_actors::{subscribe, Accessor, Actor, ActorResult, Broadcaster, Consumer, Producer, SharedState, Snapshot, WorkerResult};
 use loom_core_actors_macros::{Accessor, Consumer, Producer};
 use loom_core_blockchain::{Blockchain, Strategy};
 use loom_evm_db::DatabaseHelpers;
+use loom_types_blockchain::{Mempool, TouchedAddresses};
 use loom_types_entities::strategy_config::StrategyConfig;
-use loom_types_entities::{Market, PoolWrapper, Swap, SwapDirection, SwapError, SwapLine, SwapPath};
+use loom_types_entities::{
+    build_swap_path_vec_gas_budget, Market, MarketView, PoolWrapper, RiskScorer, Swap, SwapDirection, SwapError, SwapLine, SwapPath,
+};
 use loom_types_events::{
     BestTxSwapCompose, HealthEvent, Message, MessageHealthEvent, MessageSwapCompose, StateUpdateEvent, SwapComposeData, SwapComposeMessage,
     TxComposeData,
@@ -32,6 +40,9 @@ async fn state_change_arb_searcher_task<DB: DatabaseRef<Error = ErrReport> + Dat
     backrun_config: BackrunConfig,
     state_update_event: StateUpdateEvent<DB>,
     market: SharedState<Market>,
+    market_view: Option<Snapshot<MarketView>>,
+    mempool: Option<SharedState<Mempool>>,
+    warm_up_cache: Option<SharedState<WarmUpCache>>,
     swap_request_tx: Broadcaster<MessageSwapCompose<DB>>,
     pool_health_monitor_tx: Broadcaster<MessageHealthEvent>,
     influxdb_write_channel_tx: Broadcaster<WriteQuery>,
@@ -45,10 +56,41 @@ async fn state_change_arb_searcher_task<DB: DatabaseRef<Error = ErrReport> + Dat
 
     let start_time = std::time::Instant::now();
     let mut swap_path_set: HashSet<SwapPath> = HashSet::new();
+    let risk_scorer = backrun_config.risk().cloned().map(RiskScorer::new);
+    // depeg searches explore longer paths with a larger capital
+    let depeg_config = backrun_config.depeg().filter(|_| state_update_event.origin == DEPEG_MONITOR_ORIGIN);
+
+    let latest_market_view = market_view.map(|market_view| market_view.latest());
+    // depeg paths up to five hops are built on the latest view before the market lock is taken
+    let depeg_paths: Vec<SwapPath> = match (depeg_config, &latest_market_view) {
+        (Some(depeg_config), Some(latest_market_view)) => {
+            build_swap_path_vec_gas_budget(latest_market_view.market(), state_update_event.directions(), &depeg_config.path_gas_budget())
+                .unwrap_or_default()
+        }
+        _ => Vec::new(),
+    };
+
+    // the view versioned with the market state block of the event is read without the market lock, a newer view may have
+    // pools without state in the event state, the lock is only taken if the view is of another block
+    let market_view = latest_market_view.filter(|market_view| market_view.block_number() + 1 == state_update_event.next_block_number);
+    let market_guard = if market_view.is_none() { Some(market.read().await) } else { None };
+    let market_guard_read: &Market = match (&market_view, &market_guard) {
+        (Some(market_view), _) => market_view.market(),
+        (None, Some(market_guard)) => market_guard,
+        (None, None) => unreachable!(),
+    };
+    debug!(
+        from_view = market_view.is_some(),
+        epoch = market_view.as_ref().map(|market_view| market_view.epoch()),
+        elapsed = start_time.elapsed().as_micros(),
+        "market_guard market.read acquired"
+    );
 
-    let market_guard_read = market.read().await;
-    debug!(elapsed = start_time.elapsed().as_micros(), "market_guard market.read acquired");
+    // paths are ordered by their historical score with the adjustments pushed by external signal sources
+    let score_adjustments = market_guard_read.score_adjustments().load();
+    let now = start_time_utc.timestamp() as u64;
 
+    let mut candidate_paths = depeg_paths;
     for (pool, v) in state_update_event.directions().iter() {
         let pool_paths: Vec<SwapPath> = match market_guard_read.get_pool_paths(&pool.get_pool_id()) {
             Some(paths) => {
@@ -56,7 +98,7 @@ async fn state_change_arb_searcher_task<DB: DatabaseRef<Error = ErrReport> + Dat
                     .into_iter()
                     .enumerate()
                     .filter(|(idx, swap_path)| {
-                        *idx < 100 || swap_path.score.unwrap_or_default() > 0.97
+                        *idx < 100 || score_adjustments.adjusted_score(swap_path, now) > 0.97
                         //&& !swap_path.pools.iter().any(|pool| market_guard_read.is_pool_disabled(&pool.get_pool_id()))
                     })
                     .map(|(_, swap_path)| swap_path)
@@ -78,16 +120,35 @@ async fn state_change_arb_searcher_task<DB: DatabaseRef<Error = ErrReport> + Dat
                 market_guard_read.build_swap_path_vec(&pool_direction).unwrap_or_default()
             }
         };
+        candidate_paths.extend(pool_paths);
+    }
 
-        for pool_path in pool_paths {
-            swap_path_set.insert(pool_path);
+    for pool_path in candidate_paths {
+        let Some(mut pool_path) = backrun_config.base_cycle(pool_path) else {
+            continue;
+        };
+        if let Some(risk_scorer) = &risk_scorer {
+            if !risk_scorer.is_acceptable(&pool_path, &market_guard_read, state_update_event.next_block_number) {
+                trace!(path = %pool_path, "Swap path risk is too high");
+                continue;
+            }
+        }
+        if !score_adjustments.is_empty() {
+            pool_path.score = Some(score_adjustments.adjusted_score(&pool_path, now));
         }
+        swap_path_set.insert(pool_path);
     }
-    drop(market_guard_read);
+    // paths through pools of the same group are calculated once with the best member of every group
+    let (pool_group_selector, mut swap_path_vec) = if backrun_config.group_pools() {
+        PoolGroupSelector::new(&market_guard_read, swap_path_set.into_iter().collect())
+    } else {
+        (PoolGroupSelector::default(), swap_path_set.into_iter().collect::<Vec<SwapPath>>())
+    };
+    // the averages are shared with the market, hop gas is recorded without its lock
+    let pool_gas_costs = market_guard_read.pool_gas_costs().clone();
+    drop(market_guard);
     debug!(elapsed = start_time.elapsed().as_micros(), "market_guard market.read released");
 
-    let swap_path_vec: Vec<SwapPath> = swap_path_set.into_iter().collect();
-
     if swap_path_vec.is_empty() {
         debug!(
             request=?state_update_event.stuffing_txs_hashes().first().unwrap_or_default(),
@@ -99,6 +160,10 @@ async fn state_change_arb_searcher_task<DB: DatabaseRef<Error = ErrReport> + Dat
     }
     info!("Calculation started: swap_path_vec_len={} elapsed={}", swap_path_vec.len(), start_time.elapsed().as_micros());
 
+    let search_budget = backrun_config.search_budget().map(|search_budget| search_budget.budget(&state_update_event.origin));
+    let search_controller = Arc::new(SearchController::new(start_time, search_budget));
+    search_controller.prioritize(&mut swap_path_vec);
+
     let env = state_update_event.evm_env();
 
     let channel_len = swap_path_vec.len();
@@ -106,52 +171,97 @@ async fn state_change_arb_searcher_task<DB: DatabaseRef<Error = ErrReport> + Dat
 
     let market_state_clone = db.clone();
     let swap_path_vec_len = swap_path_vec.len();
+    let max_capital_eth = depeg_config.and_then(|depeg_config| depeg_config.max_capital_eth()).or(backrun_config.max_capital_eth());
+    let start_amount_eth = depeg_config.and_then(|depeg_config| depeg_config.start_amount_eth());
+    let extra_gas_config = backrun_config.extra_gas().clone();
+
+    // Amounts precomputed on the block state for the same block
+    let start_amounts: HashMap<u64, U256> = match &warm_up_cache {
+        Some(warm_up_cache) => {
+            let warm_up_guard = warm_up_cache.read().await;
+            swap_path_vec
+                .iter()
+                .filter_map(|path| {
+                    warm_up_guard.amount_in(state_update_event.next_block_number, path).map(|amount_in| (path.get_hash(), amount_in))
+                })
+                .collect()
+        }
+        None => HashMap::new(),
+    };
 
+    let search_controller_clone = search_controller.clone();
     tokio::task::spawn(async move {
-        thread_pool.install(|| {
-            swap_path_vec.into_par_iter().for_each_with((&swap_path_tx, &market_state_clone, &env), |req, item| {
-                let mut mut_item: SwapLine = SwapLine { path: item, ..Default::default() };
-                //#[cfg(not(debug_assertions))]
-                //let start_time = chrono::Local::now();
-                let calc_result = SwapCalculator::calculate(&mut mut_item, req.1, req.2.clone());
-                //#[cfg(not(debug_assertions))]
-                //let took_time = chrono::Local::now() - start_time;
-
-                match calc_result {
-                    Ok(_) => {
-                        // #[cfg(not(debug_assertions))]
-                        // {
-                        //     if took_time > TimeDelta::new(0, 50 * 1000000).unwrap() {
-                        //         warn!("Took longer than expected {} {}", took_time, mut_item.clone())
-                        //     }
-                        // }
-                        trace!("Calc result received: {}", mut_item);
-
-                        if let Ok(profit) = mut_item.profit() {
-                            if profit.is_positive() && mut_item.abs_profit_eth() > U256::from(state_update_event.next_base_fee * 100_000) {
-                                if let Err(error) = swap_path_tx.try_send(Ok(mut_item)) {
-                                    error!(%error, "swap_path_tx.try_send")
-                                }
-                            } else {
-                                trace!("profit is not enough")
+        let calculate = |item: SwapPath| {
+            let start_amount = start_amounts
+                .get(&item.get_hash())
+                .copied()
+                .or_else(|| start_amount_eth.and_then(|start_amount_eth| item.tokens.first()?.calc_token_value_from_eth(start_amount_eth)));
+            let item = match start_amount.or_else(|| SwapCalculator::default_start_amount(item.tokens.first()?)) {
+                Some(amount_in) => pool_group_selector.select(item, &market_state_clone, env.clone(), amount_in),
+                None => item,
+            };
+            let mut mut_item: SwapLine = SwapLine { path: item, ..Default::default() };
+            //#[cfg(not(debug_assertions))]
+            //let start_time = chrono::Local::now();
+            let calc_result =
+                SwapCalculator::calculate_from_amount(&mut mut_item, &market_state_clone, env.clone(), start_amount, max_capital_eth);
+            //#[cfg(not(debug_assertions))]
+            //let took_time = chrono::Local::now() - start_time;
+
+            match calc_result {
+                Ok(_) => {
+                    // #[cfg(not(debug_assertions))]
+                    // {
+                    //     if took_time > TimeDelta::new(0, 50 * 1000000).unwrap() {
+                    //         warn!("Took longer than expected {} {}", took_time, mut_item.clone())
+                    //     }
+                    // }
+                    trace!("Calc result received: {}", mut_item);
+
+                    if let Ok(profit) = mut_item.profit() {
+                        // annotated pools cost more than their calculated hop gas
+                        let extra_gas = extra_gas_config.extra_gas(&mut_item.path);
+                        mut_item.gas_used = mut_item.gas_used.map(|gas_used| gas_used + extra_gas);
+                        if profit.is_positive()
+                            && mut_item.abs_profit_eth() > U256::from(state_update_event.next_base_fee * (100_000 + extra_gas))
+                        {
+                            if let Err(error) = swap_path_tx.try_send(Ok(mut_item)) {
+                                error!(%error, "swap_path_tx.try_send")
                             }
+                        } else {
+                            trace!("profit is not enough")
                         }
                     }
-                    Err(e) => {
-                        // #[cfg(not(debug_assertions))]
-                        // {
-                        //     if took_time > TimeDelta::new(0, 10 * 5000000).unwrap() {
-                        //         warn!("Took longer than expected {:?} {}", e, mut_item.clone())
-                        //     }
-                        // }
-                        trace!("Swap error: {:?}", e);
-
-                        if let Err(error) = swap_path_tx.try_send(Err(e)) {
-                            error!(%error, "try_send to swap_path_tx")
-                        }
+                }
+                // capped opportunities without a profitable amount are not errors of the pools
+                Err(e) if e.is_not_profitable() => trace!("profit is not enough within the capital limit"),
+                Err(e) => {
+                    // #[cfg(not(debug_assertions))]
+                    // {
+                    //     if took_time > TimeDelta::new(0, 10 * 5000000).unwrap() {
+                    //         warn!("Took longer than expected {:?} {}", e, mut_item.clone())
+                    //     }
+                    // }
+                    trace!("Swap error: {:?}", e);
+
+                    if let Err(error) = swap_path_tx.try_send(Err(e)) {
+                        error!(%error, "try_send to swap_path_tx")
                     }
                 }
-            });
+            }
+        };
+
+        thread_pool.install(|| {
+            if search_budget.is_some() {
+                // paths are taken in score order through a shared index until the budget expires
+                (0..rayon::current_num_threads()).into_par_iter().for_each(|_| {
+                    while let Some(idx) = search_controller_clone.next_index(swap_path_vec.len()) {
+                        calculate(swap_path_vec[idx].clone());
+                    }
+                });
+            } else {
+                swap_path_vec.into_par_iter().for_each(calculate);
+            }
         });
         debug!(elapsed = start_time.elapsed().as_micros(), "Calculation iteration finished");
     });
@@ -166,13 +276,22 @@ async fn state_change_arb_searcher_task<DB: DatabaseRef<Error = ErrReport> + Dat
     let mut best_answers = BestTxSwapCompose::new_with_pct(U256::from(9000));
 
     let mut failed_pools: HashSet<SwapError> = HashSet::new();
+    let mut best_profit_eth = U256::ZERO;
 
     while let Some(swap_line_result) = swap_line_rx.recv().await {
         match swap_line_result {
             Ok(swap_line) => {
+                best_profit_eth = best_profit_eth.max(swap_line.abs_profit_eth());
+                pool_gas_costs.record_hops(&swap_line.hop_gas());
+                if let Some(warm_up_cache) = &warm_up_cache {
+                    warm_up_cache.write().await.record_profit(&swap_line.path, swap_line.abs_profit_eth());
+                }
+
                 let prepare_request = SwapComposeMessage::Prepare(SwapComposeData {
                     tx_compose: TxComposeData {
                         eoa: backrun_config.eoa(),
+                        namespace: backrun_config.namespace().map(str::to_string),
+                        profit_receiver: backrun_config.profit_receiver().map(|profit_receiver| profit_receiver.address),
                         next_block_number: state_update_event.next_block_number,
                         next_block_timestamp: state_update_event.next_block_timestamp,
                         next_block_base_fee: state_update_event.next_base_fee,
@@ -207,28 +326,94 @@ async fn state_change_arb_searcher_task<DB: DatabaseRef<Error = ErrReport> + Dat
         answers += 1;
     }
 
+    // pending txs simulated on known pools without any profitable path count against the reputation of their senders
+    let evaluated = swap_path_vec_len > search_controller.skipped();
+    if let Some(mempool) = mempool.as_ref().filter(|_| evaluated && best_profit_eth.is_zero()) {
+        let mut mempool_guard = mempool.write().await;
+        state_update_event.stuffing_txs_hashes.iter().for_each(|tx_hash| mempool_guard.set_low_value(*tx_hash));
+    }
+
     let stuffing_tx_hash = state_update_event.stuffing_tx_hash();
     let elapsed = start_time.elapsed().as_micros();
     info!(
         origin = %state_update_event.origin,
+        namespace = backrun_config.namespace(),
         swap_path_vec_len,
         answers,
+        skipped = search_controller.skipped(),
+        %best_profit_eth,
         elapsed,
         stuffing_hash = %stuffing_tx_hash,
         "Calculation finished"
     );
 
-    let write_query = WriteQuery::new(Timestamp::from(start_time_utc), "calculations")
+    let mut write_query = WriteQuery::new(Timestamp::from(start_time_utc), "calculations")
         .add_field("calculations", swap_path_vec_len as u64)
         .add_field("answers", answers as u64)
+        .add_field("skipped", search_controller.skipped() as u64)
         .add_field("elapsed", elapsed as u64)
         .add_tag("origin", state_update_event.origin)
         .add_tag("stuffing", stuffing_tx_hash.to_string());
+    if let Some(namespace) = backrun_config.namespace() {
+        write_query = write_query.add_tag("namespace", namespace.to_string());
+    }
 
     if let Err(e) = influxdb_write_channel_tx.send(write_query) {
         error!("Failed to send block latency to influxdb: {:?}", e);
     }
 
+    let paths_evaluated = HealthEvent::PathsEvaluated { next_block_number: state_update_event.next_block_number, paths: swap_path_vec_len };
+    if let Err(e) = pool_health_monitor_tx.send(Message::new(paths_evaluated)) {
+        error!("try_send to pool_health_monitor error : {:?}", e)
+    }
+
+    Ok(())
+}
+
+/// Precompute optimal amounts of the historically most profitable paths on the new block state
+async fn warm_up_task<DB: DatabaseRef<Error = ErrReport> + DatabaseCommit + Send + Sync + Clone + Default + 'static>(
+    thread_pool: Arc<ThreadPool>,
+    backrun_config: BackrunConfig,
+    state_update_event: StateUpdateEvent<DB>,
+    warm_up_cache: SharedState<WarmUpCache>,
+    paths_count: usize,
+) -> Result<()> {
+    let start_time = std::time::Instant::now();
+    let touched = TouchedAddresses::from_state_update(state_update_event.state_update());
+    // amounts of paths without pools touched by the block are still optimal
+    let (cached_amounts, swap_path_vec) =
+        warm_up_cache.read().await.split_touched(state_update_event.next_block_number, paths_count, &touched);
+    if swap_path_vec.is_empty() && cached_amounts.is_empty() {
+        return Ok(());
+    }
+    let swap_path_vec_len = swap_path_vec.len();
+    let cached_amounts_len = cached_amounts.len();
+
+    let mut db = state_update_event.market_state().clone();
+    DatabaseHelpers::apply_geth_state_update_vec(&mut db, state_update_event.state_update().clone());
+    let env = state_update_event.evm_env();
+    let max_capital_eth = backrun_config.max_capital_eth();
+
+    let amounts: HashMap<u64, U256> = tokio::task::spawn_blocking(move || {
+        thread_pool.install(|| {
+            swap_path_vec
+                .into_par_iter()
+                .filter_map(|path| {
+                    let mut swap_line: SwapLine = SwapLine { path, ..Default::default() };
+                    SwapCalculator::calculate_with_capital(&mut swap_line, &db, env.clone(), max_capital_eth).ok()?;
+                    Some((swap_line.path.get_hash(), swap_line.amount_in.unwrap_or_default()))
+                })
+                .collect()
+        })
+    })
+    .await?;
+
+    let mut amounts = amounts;
+    amounts.extend(cached_amounts);
+    let amounts_len = amounts.len();
+    warm_up_cache.write().await.set_amounts(state_update_event.next_block_number, amounts);
+    debug!(swap_path_vec_len, cached_amounts_len, amounts_len, elapsed = start_time.elapsed().as_micros(), "Warm up finished");
+
     Ok(())
 }
 
@@ -237,6 +422,8 @@ pub async fn state_change_arb_searcher_worker<
 >(
     backrun_config: BackrunConfig,
     market: SharedState<Market>,
+    market_view: Option<Snapshot<MarketView>>,
+    mempool: Option<SharedState<Mempool>>,
     search_request_rx: Broadcaster<StateUpdateEvent<DB>>,
     swap_request_tx: Broadcaster<MessageSwapCompose<DB>>,
     pool_health_monitor_tx: Broadcaster<MessageHealthEvent>,
@@ -248,18 +435,37 @@ pub async fn state_change_arb_searcher_worker<
     let tasks = (cpus * 5) / 10;
     info!("Starting state arb searcher cpus={cpus}, tasks={tasks}");
     let thread_pool = Arc::new(ThreadPoolBuilder::new().num_threads(tasks).build()?);
+    let warm_up_cache = backrun_config.warm_up_paths().map(|_| SharedState::new(WarmUpCache::new()));
+
+    if let Some(risk_config) = backrun_config.risk() {
+        RiskScorer::new(risk_config.clone()).apply_token_safety(&mut *market.write().await);
+    }
 
     loop {
         tokio::select! {
                 msg = search_request_rx.recv() => {
                 let pool_update_msg : Result<StateUpdateEvent<DB>, RecvError> = msg;
                 if let Ok(msg) = pool_update_msg {
+                    if let (Some(warm_up_cache), Some(paths_count)) = (&warm_up_cache, backrun_config.warm_up_paths()) {
+                        if msg.origin == BLOCK_SEARCHER_ORIGIN {
+                            tokio::task::spawn(warm_up_task(
+                                thread_pool.clone(),
+                                backrun_config.clone(),
+                                msg.clone(),
+                                warm_up_cache.clone(),
+                                paths_count,
+                            ));
+                        }
+                    }
                     tokio::task::spawn(
                         state_change_arb_searcher_task(
                             thread_pool.clone(),
                             backrun_config.clone(),
                             msg,
                             market.clone(),
+                            market_view.clone(),
+                            mempool.clone(),
+                            warm_up_cache.clone(),
                             swap_request_tx.clone(),
                             pool_health_monitor_tx.clone(),
                             influxdb_write_channel_tx.clone(),
@@ -276,6 +482,9 @@ pub struct StateChangeArbSearcherActor<DB: Clone + Send + Sync + 'static> {
     backrun_config: BackrunConfig,
     #[accessor]
     market: Option<SharedState<Market>>,
+    market_view: Option<Snapshot<MarketView>>,
+    #[accessor]
+    mempool: Option<SharedState<Mempool>>,
     #[consumer]
     state_update_rx: Option<Broadcaster<StateUpdateEvent<DB>>>,
     #[producer]
@@ -291,6 +500,8 @@ impl<DB: DatabaseRef<Error = ErrReport> + Send + Sync + Clone + 'static> StateCh
         StateChangeArbSearcherActor {
             backrun_config,
             market: None,
+            market_view: None,
+            mempool: None,
             state_update_rx: None,
             compose_tx: None,
             pool_health_monitor_tx: None,
@@ -298,9 +509,16 @@ impl<DB: DatabaseRef<Error = ErrReport> + Send + Sync + Clone + 'static> StateCh
         }
     }
 
+    /// Read pools and paths from the published market view instead of locking the market
+    pub fn with_market_view(self, market_view: Snapshot<MarketView>) -> Self {
+        Self { market_view: Some(market_view), ..self }
+    }
+
     pub fn on_bc(self, bc: &Blockchain, strategy: &Strategy<DB>) -> Self {
         Self {
             market: Some(bc.market()),
+            market_view: Some(bc.market_view()),
+            mempool: Some(bc.mempool()),
             pool_health_monitor_tx: Some(bc.health_monitor_channel()),
             compose_tx: Some(strategy.swap_compose_channel()),
             state_update_rx: Some(strategy.state_update_channel()),
@@ -317,6 +535,8 @@ impl<DB: DatabaseRef<Error = ErrReport> + DatabaseCommit + Send + Sync + Clone +
         let task = tokio::task::spawn(state_change_arb_searcher_worker(
             self.backrun_config.clone(),
             self.market.clone().unwrap(),
+            self.market_view.clone(),
+            self.mempool.clone(),
             self.state_update_rx.clone().unwrap(),
             self.compose_tx.clone().unwrap(),
             self.pool_health_monitor_tx.clone().unwrap(),
@@ -1,9 +1,9 @@
 use alloy_primitives::utils::parse_units;
-use alloy_primitives::U256;
+use alloy_primitives::{I256, U256};
 use eyre::ErrReport;
 use lazy_static::lazy_static;
 use loom_types_blockchain::LoomDataTypes;
-use loom_types_entities::{SwapError, SwapLine};
+use loom_types_entities::{SwapAmountType, SwapError, SwapLine, Token, NOT_PROFITABLE};
 use revm::primitives::Env;
 use revm::DatabaseRef;
 
@@ -11,6 +11,11 @@ lazy_static! {
     static ref START_OPTIMIZE_INPUT: U256 = parse_units("0.01", "ether").unwrap().get_absolute();
 }
 
+// Start amount is divided by 10 for every retry when the pool depth is insufficient
+const DOWNSCALE_ATTEMPTS: usize = 3;
+// Percents of the capital limit re-simulated when the optimal amount exceeds it
+const LADDER_PCT: [u64; 4] = [100, 75, 50, 25];
+
 pub struct SwapCalculator {}
 
 impl SwapCalculator {
@@ -20,12 +25,152 @@ impl SwapCalculator {
         state: &DB,
         env: Env,
     ) -> eyre::Result<&'a mut SwapLine<LDT>, SwapError<LDT>> {
-        let first_token = path.get_first_token().unwrap();
-        if let Some(amount_in) = first_token.calc_token_value_from_eth(*START_OPTIMIZE_INPUT) {
-            //trace!("calculate : {} amount in : {}",first_token.get_symbol(), first_token.to_float(amount_in) );
-            path.optimize_with_in_amount(state, env, amount_in)
-        } else {
-            Err(path.to_error("PRICE_NOT_SET".to_string()))
+        Self::calculate_with_capital(path, state, env, None)
+    }
+
+    /// Default start amount of the optimization in units of the token
+    pub fn default_start_amount<LDT: LoomDataTypes>(token: &Token<LDT>) -> Option<U256> {
+        token.calc_token_value_from_eth(*START_OPTIMIZE_INPUT)
+    }
+
+    /// Optimize the in amount, downscaling it when the pool depth or the capital limit in ETH is insufficient.
+    /// A capped opportunity fails when no amount within the limit is profitable.
+    pub fn calculate_with_capital<'a, DB: DatabaseRef<Error = ErrReport>, LDT: LoomDataTypes>(
+        path: &'a mut SwapLine<LDT>,
+        state: &DB,
+        env: Env,
+        max_capital_eth: Option<U256>,
+    ) -> eyre::Result<&'a mut SwapLine<LDT>, SwapError<LDT>> {
+        Self::calculate_from_amount(path, state, env, None, max_capital_eth)
+    }
+
+    /// Same as [`SwapCalculator::calculate_with_capital`] starting the optimization from `start_amount`,
+    /// e.g. the optimal amount precomputed on the block state.
+    pub fn calculate_from_amount<'a, DB: DatabaseRef<Error = ErrReport>, LDT: LoomDataTypes>(
+        path: &'a mut SwapLine<LDT>,
+        state: &DB,
+        env: Env,
+        start_amount: Option<U256>,
+        max_capital_eth: Option<U256>,
+    ) -> eyre::Result<&'a mut SwapLine<LDT>, SwapError<LDT>> {
+        let first_token = path.get_first_token().unwrap().clone();
+        let Some(start_amount) = start_amount.or_else(|| Self::default_start_amount(&first_token)) else {
+            return Err(path.to_error("PRICE_NOT_SET".to_string()));
+        };
+        let max_amount_in = max_capital_eth.and_then(|max_capital_eth| first_token.calc_token_value_from_eth(max_capital_eth));
+
+        let mut amount_in = max_amount_in.map_or(start_amount, |max_amount_in| start_amount.min(max_amount_in));
+        let mut attempt = 0;
+        loop {
+            match path.optimize_with_in_amount(state, env.clone(), amount_in) {
+                Ok(_) => break,
+                Err(e) => {
+                    attempt += 1;
+                    amount_in /= U256::from(10);
+                    if attempt > DOWNSCALE_ATTEMPTS || amount_in.is_zero() {
+                        return Err(e);
+                    }
+                }
+            }
+        }
+
+        if let Some(max_amount_in) = max_amount_in {
+            if path.amount_in.unwrap_or_default() > max_amount_in {
+                Self::ladder_down(path, state, env, max_amount_in)?;
+            }
+        }
+
+        Ok(path)
+    }
+
+    /// Re-simulate the swap line at fractions of `max_amount_in` and keep the most profitable one, fails when none of
+    /// them is profitable
+    fn ladder_down<DB: DatabaseRef<Error = ErrReport>, LDT: LoomDataTypes>(
+        path: &mut SwapLine<LDT>,
+        state: &DB,
+        env: Env,
+        max_amount_in: U256,
+    ) -> eyre::Result<(), SwapError<LDT>> {
+        let mut best_profit: Option<I256> = None;
+
+        for pct in LADDER_PCT {
+            let amount_in = max_amount_in * U256::from(pct) / U256::from(100);
+            if amount_in.is_zero() {
+                continue;
+            }
+
+            let Ok((amount_out, gas_used, calculation_results)) = path.calculate_with_in_amount(state, env.clone(), amount_in) else {
+                continue;
+            };
+
+            let profit = I256::from_raw(amount_out) - I256::from_raw(amount_in) - I256::from_raw(path.funding_fee(amount_in));
+            if best_profit.is_none_or(|best_profit| profit > best_profit) {
+                best_profit = Some(profit);
+                path.amount_in = SwapAmountType::Set(amount_in);
+                path.amount_out = SwapAmountType::Set(amount_out);
+                path.gas_used = Some(gas_used);
+                path.calculation_results = calculation_results;
+            }
         }
+
+        match best_profit {
+            Some(profit) if profit.is_positive() => Ok(()),
+            Some(_) => Err(path.to_error(NOT_PROFITABLE.to_string())),
+            None => Err(path.to_error("LADDER_FAILED".to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloy_primitives::Address;
+    use loom_defi_address_book::TokenAddressEth;
+    use loom_evm_db::LoomDBType;
+    use loom_types_entities::{MockPool, Pool, SwapPath};
+
+    fn ether(value: u64) -> U256 {
+        U256::from(value) * U256::from(10).pow(U256::from(18))
+    }
+
+    // weth is 2 tokens in the first pool and 1 token in the second, the optimal amount in is about 13.8 weth
+    fn arb_state(reverse: bool) -> (SwapLine, LoomDBType) {
+        let (weth, token) = (TokenAddressEth::WETH, Address::repeat_byte(1));
+        let (pool0, pool1) =
+            (MockPool::new(weth, token, Address::repeat_byte(0x10)), MockPool::new(weth, token, Address::repeat_byte(0x11)));
+        let mut state_db = LoomDBType::default();
+        for (pool, weth_reserve, token_reserve) in [(pool0.get_address(), 100, 200), (pool1.get_address(), 100, 100)] {
+            state_db.insert_account_storage(pool, U256::ZERO, ether(weth_reserve)).unwrap();
+            state_db.insert_account_storage(pool, U256::from(1), ether(token_reserve)).unwrap();
+        }
+        let pools = if reverse { vec![pool1, pool0] } else { vec![pool0, pool1] };
+        let swap_line = SwapLine::from(SwapPath::new(vec![Token::new(weth), Token::new(token), Token::new(weth)], pools));
+        (swap_line, state_db)
+    }
+
+    #[test]
+    fn test_capital_cap() {
+        let (mut swap_line, state_db) = arb_state(false);
+        SwapCalculator::calculate(&mut swap_line, &state_db, Env::default()).unwrap();
+        assert!(swap_line.amount_in.unwrap() > ether(10));
+
+        // profit grows up to the optimal amount, the whole capital is used
+        let (mut swap_line, state_db) = arb_state(false);
+        SwapCalculator::calculate_with_capital(&mut swap_line, &state_db, Env::default(), Some(ether(1))).unwrap();
+        assert_eq!(swap_line.amount_in.unwrap(), ether(1));
+        assert!(swap_line.abs_profit() > U256::ZERO);
+    }
+
+    #[test]
+    fn test_ladder_down() {
+        // 40 weth is unprofitable, 10 weth is closest to the optimal amount
+        let (mut swap_line, state_db) = arb_state(false);
+        SwapCalculator::ladder_down(&mut swap_line, &state_db, Env::default(), ether(40)).unwrap();
+        assert_eq!(swap_line.amount_in.unwrap(), ether(10));
+        assert_eq!(swap_line.amount_out.unwrap(), ether(200) / U256::from(13));
+
+        let (mut swap_line, state_db) = arb_state(true);
+        let error = SwapCalculator::ladder_down(&mut swap_line, &state_db, Env::default(), ether(40)).unwrap_err();
+        assert!(error.is_not_profitable());
     }
 }
@@ -0,0 +1,130 @@
+use std::collections::HashMap;
+
+use alloy_primitives::U256;
+use loom_types_blockchain::TouchedAddresses;
+use loom_types_entities::SwapPath;
+
+// Maximum number of paths with recorded profit
+const PROFIT_HISTORY_CAPACITY: usize = 10_000;
+
+/// Optimal in amounts of the historically most profitable paths, precomputed on the block state.
+///
+/// Searches triggered by mempool transactions for the same block start the optimization from the cached amount
+/// instead of the default start amount.
+#[derive(Default)]
+pub struct WarmUpCache {
+    // next block number the amounts were calculated for
+    block_number: u64,
+    // path hash -> optimal in amount
+    amounts: HashMap<u64, U256>,
+    // path hash -> (path, accumulated profit in ETH)
+    profit_history: HashMap<u64, (SwapPath, U256)>,
+}
+
+impl WarmUpCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record_profit(&mut self, path: &SwapPath, profit_eth: U256) {
+        let path_hash = path.get_hash();
+        if !self.profit_history.contains_key(&path_hash) && self.profit_history.len() >= PROFIT_HISTORY_CAPACITY {
+            let Some(min_hash) = self.profit_history.iter().min_by_key(|(_, (_, profit))| *profit).map(|(hash, _)| *hash) else {
+                return;
+            };
+            self.profit_history.remove(&min_hash);
+        }
+        let entry = self.profit_history.entry(path_hash).or_insert_with(|| (path.clone(), U256::ZERO));
+        entry.1 = entry.1.saturating_add(profit_eth);
+    }
+
+    /// Up to `count` paths ordered by accumulated profit
+    pub fn top_paths(&self, count: usize) -> Vec<SwapPath> {
+        let mut paths: Vec<&(SwapPath, U256)> = self.profit_history.values().collect();
+        paths.sort_by(|a, b| b.1.cmp(&a.1));
+        paths.into_iter().take(count).map(|(path, _)| path.clone()).collect()
+    }
+
+    /// Splits the top paths for `next_block_number` into cached amounts of paths with no pool touched by the block
+    /// and paths that have to be recalculated
+    pub fn split_touched(&self, next_block_number: u64, count: usize, touched: &TouchedAddresses) -> (HashMap<u64, U256>, Vec<SwapPath>) {
+        let mut cached_amounts = HashMap::new();
+        let mut paths = Vec::new();
+        for path in self.top_paths(count) {
+            let is_touched = path.pools.iter().any(|pool| touched.contains(&pool.get_address()));
+            match self.amount_in(next_block_number.saturating_sub(1), &path) {
+                Some(amount) if !is_touched => {
+                    cached_amounts.insert(path.get_hash(), amount);
+                }
+                _ => paths.push(path),
+            }
+        }
+        (cached_amounts, paths)
+    }
+
+    pub fn set_amounts(&mut self, block_number: u64, amounts: HashMap<u64, U256>) {
+        self.block_number = block_number;
+        self.amounts = amounts;
+    }
+
+    /// Cached amount for the path if it was calculated for `block_number`
+    pub fn amount_in(&self, block_number: u64, path: &SwapPath) -> Option<U256> {
+        if self.block_number != block_number {
+            return None;
+        }
+        self.amounts.get(&path.get_hash()).copied()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloy_primitives::Address;
+    use loom_types_entities::{MockPool, Token};
+    use std::sync::Arc;
+
+    fn swap_path(seed: u8) -> SwapPath {
+        let token0 = Arc::new(Token::new(Address::repeat_byte(seed)));
+        let token1 = Arc::new(Token::new(Address::repeat_byte(seed + 1)));
+        let pool = MockPool::new(token0.get_address(), token1.get_address(), Address::repeat_byte(seed + 2));
+        SwapPath::new_swap(token0, token1, pool.into())
+    }
+
+    #[test]
+    fn test_top_paths() {
+        let mut cache = WarmUpCache::new();
+        let (path0, path1) = (swap_path(1), swap_path(10));
+        cache.record_profit(&path0, U256::from(1));
+        cache.record_profit(&path1, U256::from(2));
+        cache.record_profit(&path0, U256::from(2));
+
+        assert_eq!(cache.top_paths(1), vec![path0.clone()]);
+        assert_eq!(cache.top_paths(5).len(), 2);
+    }
+
+    #[test]
+    fn test_amount_in_block() {
+        let mut cache = WarmUpCache::new();
+        let path = swap_path(1);
+        cache.set_amounts(10, HashMap::from([(path.get_hash(), U256::from(100))]));
+
+        assert_eq!(cache.amount_in(10, &path), Some(U256::from(100)));
+        assert_eq!(cache.amount_in(11, &path), None);
+    }
+
+    #[test]
+    fn test_split_touched() {
+        let mut cache = WarmUpCache::new();
+        let (path0, path1) = (swap_path(1), swap_path(10));
+        cache.record_profit(&path0, U256::from(1));
+        cache.record_profit(&path1, U256::from(2));
+        cache.set_amounts(10, HashMap::from([(path0.get_hash(), U256::from(100)), (path1.get_hash(), U256::from(200))]));
+
+        let mut touched = TouchedAddresses::new();
+        touched.insert(Address::repeat_byte(12));
+
+        let (cached_amounts, paths) = cache.split_touched(11, 2, &touched);
+        assert_eq!(cached_amounts, HashMap::from([(path0.get_hash(), U256::from(100))]));
+        assert_eq!(paths, vec![path1]);
+    }
+}
@@ -26,7 +26,8 @@ fn get_merge_list<'a, DB: Clone + Send + Sync + 'static>(
     let mut ret: Vec<&SwapComposeData<DB>> = Vec::new();
     let mut pools = request.swap.get_pool_id_vec();
     for p in swap_paths.iter() {
-        if !p.cross_pools(&pools) {
+        // the merged swap inherits the tx compose of the request, its receiver and namespace
+        if !p.cross_pools(&pools) && p.same_owner(request) {
             pools.extend(p.swap.get_pool_id_vec());
             ret.push(p);
         }
@@ -191,3 +192,22 @@ where
         "DiffPathMergerActor"
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use loom_evm_db::LoomDB;
+
+    #[test]
+    fn test_merge_list_same_namespace() {
+        let request = |namespace: &str| SwapComposeData::<LoomDB> {
+            tx_compose: TxComposeData { namespace: Some(namespace.to_string()), ..TxComposeData::default() },
+            ..SwapComposeData::default()
+        };
+        let swap_paths = vec![request("conservative"), request("aggressive")];
+
+        let merge_list = get_merge_list(&request("aggressive"), &swap_paths);
+        assert_eq!(merge_list.len(), 1);
+        assert_eq!(merge_list[0].tx_compose.namespace.as_deref(), Some("aggressive"));
+    }
+}
@@ -50,7 +50,11 @@ fn get_merge_list<'a, DB: Clone + 'static>(
         .iter()
         .filter_map(|(k, v)| {
             if *k != swap_stuffing_hash {
-                v.iter().find(|a| if let Swap::BackrunSwapLine(a_line) = &a.swap { a_line.path == swap_line.path } else { false })
+                // the merged swap inherits the tx compose of the request, its receiver and namespace
+                v.iter().find(|a| match &a.swap {
+                    Swap::BackrunSwapLine(a_line) => a_line.path == swap_line.path && a.same_owner(request),
+                    _ => false,
+                })
             } else {
                 None
             }
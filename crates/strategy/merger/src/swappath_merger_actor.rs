@@ -109,6 +109,11 @@ async fn arb_swap_path_merger_worker<DB: DatabaseRef<Error = ErrReport> + Send +
                                 continue
                             };
 
+                            // the merged swap inherits the tx compose of the request, its receiver and namespace
+                            if !compose_data.same_owner(req) {
+                                continue
+                            }
+
 
                             match SwapStep::merge_swap_paths( req_swap.clone(), swap_path.clone(), multicaller_address ){
                                 Ok((sp0, sp1)) => {
@@ -5,11 +5,32 @@ use alloy_rpc_types_eth::Header;
 pub struct ChainParameters {
     pub chain_id: u64,
     pub base_fee_params: BaseFeeParams,
+    /// Seconds between blocks, rounded up for chains with sub second blocks
+    pub block_time: u64,
 }
 
 impl ChainParameters {
     pub fn ethereum() -> ChainParameters {
-        ChainParameters { chain_id: 1, base_fee_params: BaseFeeParams::ethereum() }
+        ChainParameters { chain_id: 1, base_fee_params: BaseFeeParams::ethereum(), block_time: 12 }
+    }
+
+    /// Parameters of the chain, the base fee is calculated with the ethereum parameters on all chains
+    pub fn for_chain_id(chain_id: u64) -> ChainParameters {
+        let block_time = match chain_id {
+            // BSC
+            56 => 3,
+            // Optimism, Base, Unichain, Polygon and Avalanche
+            10 | 8453 | 130 | 137 | 43114 => 2,
+            // Arbitrum One and Nova
+            42161 | 42170 => 1,
+            _ => 12,
+        };
+        ChainParameters { chain_id, block_time, ..ChainParameters::ethereum() }
+    }
+
+    /// Expected timestamp of the block following the block with `timestamp`
+    pub fn next_block_timestamp(&self, timestamp: u64) -> u64 {
+        timestamp + self.block_time
     }
 
     pub fn calc_next_block_base_fee(&self, gas_used: u64, gas_limit: u64, base_fee: u64) -> u64 {
@@ -28,9 +49,6 @@ impl Default for ChainParameters {
 }
 impl From<u64> for ChainParameters {
     fn from(chain_id: u64) -> Self {
-        match chain_id {
-            1 => ChainParameters::ethereum(),
-            _ => unimplemented!(),
-        }
+        ChainParameters::for_chain_id(chain_id)
     }
 }
@@ -3,13 +3,22 @@ pub use chain_parameters::ChainParameters;
 pub use fetchstate::FetchState;
 pub use loom_data_types::{LoomBlock, LoomDataTypes, LoomHeader, LoomTx};
 pub use loom_data_types_ethereum::LoomDataTypesEthereum;
-pub use mempool::Mempool;
+pub use mempool::{Mempool, MempoolTxStatus};
 pub use mempool_tx::MempoolTx;
 pub use opcodes::*;
+pub use opcodes_builder::{CallSlotBuilder, MulticallerCallsBuilder, StackSlot, StackSlotError};
+pub use opcodes_validation::OpcodesValidationError;
+pub use sender_reputation::{SenderReputation, SenderStats};
 pub use state_update::{
-    debug_log_geth_state_update, debug_trace_block, debug_trace_call_diff, debug_trace_call_post_state, debug_trace_call_pre_state,
-    debug_trace_transaction, get_touched_addresses, GethStateUpdate, GethStateUpdateVec, TRACING_CALL_OPTS, TRACING_OPTS,
+    apply_state_update_to_override, debug_log_geth_state_update, get_touched_addresses, GethStateUpdate, GethStateUpdateVec,
+    TRACING_CALL_OPTS, TRACING_OPTS,
 };
+#[cfg(feature = "provider")]
+pub use state_update::{
+    debug_trace_block, debug_trace_block_post_states, debug_trace_call_diff, debug_trace_call_post_state, debug_trace_call_pre_state,
+    debug_trace_transaction,
+};
+pub use touched_addresses::TouchedAddresses;
 mod accountnoncetx;
 mod chain_parameters;
 mod fetchstate;
@@ -19,4 +28,8 @@ mod mempool;
 mod mempool_tx;
 mod new_block;
 mod opcodes;
+mod opcodes_builder;
+mod opcodes_validation;
+mod sender_reputation;
 mod state_update;
+mod touched_addresses;
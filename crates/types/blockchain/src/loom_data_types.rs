@@ -3,6 +3,7 @@ use std::fmt::{Debug, Display};
 use std::hash::Hash;
 pub trait LoomTx<LDT: LoomDataTypes> {
     fn gas_price(&self) -> u128;
+    fn max_priority_fee_per_gas(&self) -> Option<u128>;
     fn gas_limit(&self) -> u64;
 
     fn tx_hash(&self) -> LDT::TxHash;
@@ -1,8 +1,8 @@
 use crate::{ChainParameters, GethStateUpdate, LoomBlock, LoomDataTypes, LoomHeader, LoomTx};
 use alloy_consensus::{BlockHeader, Transaction as TransactionTrait};
 use alloy_eips::eip2718::Encodable2718;
+use alloy_network::TransactionResponse;
 use alloy_primitives::{hex, Address, BlockHash, TxHash};
-use alloy_provider::network::TransactionResponse;
 use alloy_rpc_types_eth::{Block as EthBlock, Header, Log, Transaction, TransactionReceipt, TransactionRequest};
 
 #[derive(Clone, Debug, Default)]
@@ -36,6 +36,10 @@ impl LoomTx<LoomDataTypesEthereum> for Transaction {
         TransactionTrait::max_fee_per_gas(self)
     }
 
+    fn max_priority_fee_per_gas(&self) -> Option<u128> {
+        TransactionTrait::max_priority_fee_per_gas(self)
+    }
+
     fn gas_limit(&self) -> u64 {
         TransactionTrait::gas_limit(self)
     }
@@ -1,21 +1,56 @@
 use crate::loom_data_types::LoomTx;
-use crate::{AccountNonceAndTransactions, FetchState, GethStateUpdate, MempoolTx};
+use crate::{AccountNonceAndTransactions, FetchState, GethStateUpdate, MempoolTx, SenderReputation};
 use crate::{LoomDataTypes, LoomDataTypesEthereum};
 use alloy_primitives::map::HashMap;
 use alloy_primitives::BlockNumber;
 use chrono::{DateTime, Utc};
 use eyre::{eyre, Result};
 use std::collections::hash_map::Entry;
+use std::collections::BTreeMap;
+
+// Minimal fee increase of a replacement transaction, same as the geth txpool default
+const REPLACEMENT_FEE_BUMP_PCT: u128 = 10;
+
+/// Result of tracking a transaction by sender and nonce
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MempoolTxStatus<TxHash> {
+    /// First transaction with the sender and nonce
+    New,
+    /// The transaction is already tracked
+    Known,
+    /// The transaction replaces `replaced` with a fee bump
+    Replacement { replaced: TxHash },
+    /// The fee bump is too low to replace `latest`, the transaction is not going to be included
+    Underpriced { latest: TxHash },
+}
 
 #[derive(Clone, Debug, Default)]
 pub struct Mempool<LDT: LoomDataTypes = LoomDataTypesEthereum> {
     pub txs: HashMap<LDT::TxHash, MempoolTx<LDT>>,
     accounts: HashMap<LDT::Address, AccountNonceAndTransactions>,
+    // sender -> nonce -> versions of the transaction in arrival order, the last one is the latest
+    nonce_txs: HashMap<LDT::Address, BTreeMap<u64, Vec<LDT::TxHash>>>,
+    reputation: SenderReputation<LDT>,
+}
+
+fn is_fee_bump(fee: u128, new_fee: u128) -> bool {
+    new_fee.saturating_mul(100) >= fee.saturating_mul(100 + REPLACEMENT_FEE_BUMP_PCT)
+}
+
+/// Priority fee per gas paid to the block builder at `base_fee`
+fn effective_tip<LDT: LoomDataTypes>(tx: &LDT::Transaction, base_fee: u128) -> u128 {
+    let max_tip = tx.gas_price().saturating_sub(base_fee);
+    tx.max_priority_fee_per_gas().map_or(max_tip, |tip| tip.min(max_tip))
 }
 
 impl<LDT: LoomDataTypes> Mempool<LDT> {
     pub fn new() -> Mempool<LoomDataTypesEthereum> {
-        Mempool { txs: HashMap::default(), accounts: HashMap::default() }
+        Mempool {
+            txs: HashMap::default(),
+            accounts: HashMap::default(),
+            nonce_txs: HashMap::default(),
+            reputation: SenderReputation::default(),
+        }
     }
 
     pub fn len(&self) -> usize {
@@ -33,6 +68,7 @@ impl<LDT: LoomDataTypes> Mempool<LDT> {
     pub fn add_tx(&mut self, tx: LDT::Transaction) -> &mut Self {
         let tx_hash: LDT::TxHash = tx.tx_hash();
         let entry = self.txs.entry(tx_hash).or_default();
+        entry.tx_hash = tx_hash;
         entry.tx = Some(tx);
         self
     }
@@ -88,6 +124,7 @@ impl<LDT: LoomDataTypes> Mempool<LDT> {
     pub fn clean(&mut self) {
         self.txs = Default::default();
         self.accounts = Default::default();
+        self.nonce_txs = Default::default();
     }
 
     pub fn clean_txs(&mut self, max_block_number: BlockNumber, max_time: DateTime<Utc>) {
@@ -97,6 +134,15 @@ impl<LDT: LoomDataTypes> Mempool<LDT> {
             .into_iter()
             .filter(|(_, v)| v.mined.unwrap_or(max_block_number + 1) > max_block_number && v.time > max_time)
             .collect();
+
+        let txs = &self.txs;
+        self.nonce_txs.retain(|_, nonce_versions| {
+            nonce_versions.retain(|_, versions| {
+                versions.retain(|tx_hash| txs.contains_key(tx_hash));
+                !versions.is_empty()
+            });
+            !nonce_versions.is_empty()
+        });
     }
 
     pub fn set_mined(&mut self, tx_hash: LDT::TxHash, block_number: BlockNumber) -> &mut Self {
@@ -108,16 +154,145 @@ impl<LDT: LoomDataTypes> Mempool<LDT> {
     pub fn set_failed(&mut self, tx_hash: LDT::TxHash) {
         if let Entry::Occupied(mut e) = self.txs.entry(tx_hash) {
             let value = e.get_mut();
+            if value.failed != Some(true) {
+                if let Some(tx) = &value.tx {
+                    self.reputation.record_failed(tx.from());
+                }
+            }
             value.failed = Some(true)
         }
     }
 
+    /// Record that the transaction was simulated on known pools without a profitable opportunity
+    pub fn set_low_value(&mut self, tx_hash: LDT::TxHash) {
+        if let Some(tx) = self.txs.get(&tx_hash).and_then(|mempool_tx| mempool_tx.tx.as_ref()) {
+            self.reputation.record_low_value(tx.from());
+        }
+    }
+
+    pub fn reputation(&self) -> &SenderReputation<LDT> {
+        &self.reputation
+    }
+
+    pub fn reputation_mut(&mut self) -> &mut SenderReputation<LDT> {
+        &mut self.reputation
+    }
+
+    /// Transaction sender is a spammer by reputation
+    pub fn is_spam_tx(&self, tx_hash: &LDT::TxHash) -> bool {
+        self.txs.get(tx_hash).and_then(|mempool_tx| mempool_tx.tx.as_ref()).is_some_and(|tx| self.reputation.is_spammer(&tx.from()))
+    }
+
+    /// Set the latest included nonce of the account. Not included versions of transactions with this or lower nonces are removed.
     pub fn set_nonce(&mut self, account: LDT::Address, nonce: u64) -> &mut Self {
         let entry = self.accounts.entry(account).or_default();
         entry.set_nonce(Some(nonce));
+
+        if let Some(nonce_versions) = self.nonce_txs.get_mut(&account) {
+            let pending = nonce_versions.split_off(&(nonce + 1));
+            let included = std::mem::replace(nonce_versions, pending);
+            for tx_hash in included.into_values().flatten() {
+                if self.txs.get(&tx_hash).is_some_and(|mempool_tx| mempool_tx.mined.is_none()) {
+                    self.txs.remove(&tx_hash);
+                }
+            }
+            if nonce_versions.is_empty() {
+                self.nonce_txs.remove(&account);
+            }
+        }
         self
     }
 
+    /// Track the transaction by sender and nonce. A transaction with the same sender and nonce replaces the latest version
+    /// if both max fee and priority fee are bumped by at least 10%, otherwise it is marked as replaced by the latest version.
+    pub fn track_tx(&mut self, tx: &LDT::Transaction) -> MempoolTxStatus<LDT::TxHash> {
+        let tx_hash = tx.tx_hash();
+        let versions = self.nonce_txs.entry(tx.from()).or_default().entry(tx.nonce()).or_default();
+        if versions.contains(&tx_hash) {
+            return MempoolTxStatus::Known;
+        }
+        self.reputation.record_tx(tx.from());
+        let Some(latest_hash) = versions.last().copied() else {
+            versions.push(tx_hash);
+            return MempoolTxStatus::New;
+        };
+
+        let is_replacement = match self.txs.get(&latest_hash).and_then(|mempool_tx| mempool_tx.tx.as_ref()) {
+            Some(latest_tx) => {
+                is_fee_bump(latest_tx.gas_price(), tx.gas_price())
+                    && is_fee_bump(
+                        latest_tx.max_priority_fee_per_gas().unwrap_or_default(),
+                        tx.max_priority_fee_per_gas().unwrap_or_default(),
+                    )
+            }
+            None => true,
+        };
+
+        let (status, superseded_hash, superseding_hash) = if is_replacement {
+            versions.push(tx_hash);
+            (MempoolTxStatus::Replacement { replaced: latest_hash }, latest_hash, tx_hash)
+        } else {
+            (MempoolTxStatus::Underpriced { latest: latest_hash }, tx_hash, latest_hash)
+        };
+        if let Some(mempool_tx) = self.txs.get_mut(&superseded_hash) {
+            mempool_tx.replaced_by = Some(superseding_hash);
+        }
+        status
+    }
+
+    pub fn is_replaced(&self, tx_hash: &LDT::TxHash) -> bool {
+        self.txs.get(tx_hash).is_some_and(|mempool_tx| mempool_tx.replaced_by.is_some())
+    }
+
+    /// Latest version of the transaction with the same sender and nonce
+    pub fn get_latest_tx(&self, tx_hash: &LDT::TxHash) -> Option<&MempoolTx<LDT>> {
+        let tx = self.txs.get(tx_hash)?.tx.as_ref()?;
+        self.get_tx_by_sender_nonce(&tx.from(), tx.nonce()).or_else(|| self.txs.get(tx_hash))
+    }
+
+    /// Latest version of the transaction of `sender` with `nonce`
+    pub fn get_tx_by_sender_nonce(&self, sender: &LDT::Address, nonce: u64) -> Option<&MempoolTx<LDT>> {
+        let latest_hash = self.nonce_txs.get(sender)?.get(&nonce)?.last()?;
+        self.txs.get(latest_hash)
+    }
+
+    /// Pending transactions expected to be included before `tx_hash` in the next block, in the expected order. Transactions
+    /// are ordered by the priority fee at `base_fee` and by arrival time, transactions of the same sender go before by nonce.
+    pub fn txs_ahead(&self, tx_hash: &LDT::TxHash, base_fee: u128) -> Vec<&MempoolTx<LDT>> {
+        let Some((target, target_tx)) = self.txs.get(tx_hash).and_then(|mempool_tx| mempool_tx.tx.as_ref().map(|tx| (mempool_tx, tx)))
+        else {
+            return Vec::new();
+        };
+        let target_tip = effective_tip::<LDT>(target_tx, base_fee);
+
+        let mut txs_ahead: Vec<(u128, &MempoolTx<LDT>)> = self
+            .txs
+            .iter()
+            .filter(|(hash, mempool_tx)| {
+                *hash != tx_hash && mempool_tx.mined.is_none() && !mempool_tx.failed.unwrap_or(false) && mempool_tx.replaced_by.is_none()
+            })
+            .filter_map(|(_, mempool_tx)| {
+                let tx = mempool_tx.tx.as_ref()?;
+                let tip = effective_tip::<LDT>(tx, base_fee);
+                let is_ahead = if tx.from() == target_tx.from() {
+                    tx.nonce() < target_tx.nonce()
+                } else {
+                    tx.gas_price() >= base_fee && (tip > target_tip || (tip == target_tip && mempool_tx.time < target.time))
+                };
+                is_ahead.then_some((tip, mempool_tx))
+            })
+            .collect();
+
+        txs_ahead.sort_by(|(tip_a, tx_a), (tip_b, tx_b)| tip_b.cmp(tip_a).then(tx_a.time.cmp(&tx_b.time)));
+        txs_ahead.into_iter().map(|(_, mempool_tx)| mempool_tx).collect()
+    }
+
+    /// Expected index of the transaction in the next block among the known pending transactions
+    pub fn expected_tx_index(&self, tx_hash: &LDT::TxHash, base_fee: u128) -> Option<usize> {
+        self.txs.get(tx_hash)?.tx.as_ref()?;
+        Some(self.txs_ahead(tx_hash, base_fee).len())
+    }
+
     pub fn is_valid_tx(&self, tx: &LDT::Transaction) -> bool {
         self.accounts.get(&tx.from()).map_or_else(|| true, |acc| acc.nonce.map_or_else(|| true, |nonce| tx.nonce() == nonce + 1))
     }
@@ -134,3 +309,84 @@ impl<LDT: LoomDataTypes> Mempool<LDT> {
         self.txs.remove(tx_hash)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloy_consensus::{Signed, TxEip1559, TxEnvelope};
+    use alloy_primitives::{Address, PrimitiveSignature, TxHash, B256, U256};
+    use alloy_rpc_types::Transaction;
+
+    fn tx(nonce: u64, max_fee_per_gas: u128, max_priority_fee_per_gas: u128, hash_byte: u8) -> Transaction {
+        let tx = TxEip1559 { nonce, max_fee_per_gas, max_priority_fee_per_gas, ..Default::default() };
+        let signed = Signed::new_unchecked(tx, PrimitiveSignature::new(U256::from(1), U256::from(1), false), B256::repeat_byte(hash_byte));
+        Transaction {
+            inner: TxEnvelope::Eip1559(signed),
+            block_hash: None,
+            block_number: None,
+            transaction_index: None,
+            effective_gas_price: None,
+            from: Address::repeat_byte(1),
+        }
+    }
+
+    #[test]
+    fn test_track_replacements() {
+        let mut mempool = Mempool::<LoomDataTypesEthereum>::default();
+        let (tx_a, tx_b, tx_c) = (tx(1, 100, 10, 0xA), tx(1, 105, 11, 0xB), tx(1, 110, 11, 0xC));
+        let (hash_a, hash_b, hash_c) = (tx_a.tx_hash(), tx_b.tx_hash(), tx_c.tx_hash());
+
+        mempool.add_tx(tx_a.clone());
+        assert_eq!(mempool.track_tx(&tx_a), MempoolTxStatus::New);
+        assert_eq!(mempool.track_tx(&tx_a), MempoolTxStatus::Known);
+
+        mempool.add_tx(tx_b.clone());
+        assert_eq!(mempool.track_tx(&tx_b), MempoolTxStatus::Underpriced { latest: hash_a });
+        assert!(mempool.is_replaced(&hash_b));
+        assert!(!mempool.is_replaced(&hash_a));
+
+        mempool.add_tx(tx_c.clone());
+        assert_eq!(mempool.track_tx(&tx_c), MempoolTxStatus::Replacement { replaced: hash_a });
+        assert!(mempool.is_replaced(&hash_a));
+        assert_eq!(mempool.get_latest_tx(&hash_a).map(|mempool_tx| mempool_tx.tx_hash), Some(hash_c));
+        assert_eq!(mempool.get_latest_tx(&hash_b).map(|mempool_tx| mempool_tx.tx_hash), Some(hash_c));
+    }
+
+    #[test]
+    fn test_expire_on_inclusion() {
+        let mut mempool = Mempool::<LoomDataTypesEthereum>::default();
+        let (tx_a, tx_b, tx_next) = (tx(1, 100, 10, 0xA), tx(1, 200, 20, 0xB), tx(2, 100, 10, 0xC));
+        for tx in [&tx_a, &tx_b, &tx_next] {
+            mempool.add_tx(tx.clone());
+            mempool.track_tx(tx);
+        }
+
+        mempool.set_mined(tx_b.tx_hash(), 100).set_nonce(tx_b.from, 1);
+        assert!(!mempool.is_tx(&tx_a.tx_hash()));
+        assert!(mempool.is_mined(&tx_b.tx_hash()));
+        assert!(mempool.get_tx_by_sender_nonce(&tx_b.from, 1).is_none());
+        assert_eq!(mempool.get_tx_by_sender_nonce(&tx_next.from, 2).map(|mempool_tx| mempool_tx.tx_hash), Some(tx_next.tx_hash()));
+    }
+
+    #[test]
+    fn test_txs_ahead() {
+        let mut mempool = Mempool::<LoomDataTypesEthereum>::default();
+        let victim = tx(1, 100, 10, 0xA);
+        let mut higher_tip = tx(1, 100, 20, 0xB);
+        higher_tip.from = Address::repeat_byte(2);
+        let mut lower_tip = tx(1, 100, 5, 0xC);
+        lower_tip.from = Address::repeat_byte(3);
+        let mut below_base_fee = tx(1, 50, 50, 0xD);
+        below_base_fee.from = Address::repeat_byte(4);
+        let same_sender_lower_nonce = tx(0, 100, 1, 0xE);
+
+        for tx in [&victim, &higher_tip, &lower_tip, &below_base_fee, &same_sender_lower_nonce] {
+            mempool.add_tx(tx.clone());
+        }
+
+        let txs_ahead: Vec<TxHash> = mempool.txs_ahead(&victim.tx_hash(), 80).iter().map(|mempool_tx| mempool_tx.tx_hash).collect();
+        assert_eq!(txs_ahead, vec![higher_tip.tx_hash(), same_sender_lower_nonce.tx_hash()]);
+        assert_eq!(mempool.expected_tx_index(&victim.tx_hash(), 80), Some(2));
+        assert_eq!(mempool.expected_tx_index(&B256::ZERO, 80), None);
+    }
+}
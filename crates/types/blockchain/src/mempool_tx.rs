@@ -13,6 +13,8 @@ pub struct MempoolTx<D: LoomDataTypes> {
     pub logs: Option<Vec<D::Log>>,
     pub mined: Option<BlockNumber>,
     pub failed: Option<bool>,
+    /// Newer version of the transaction with the same sender and nonce
+    pub replaced_by: Option<D::TxHash>,
     pub state_update: Option<D::StateUpdate>,
     pub pre_state: Option<FetchState<D::StateUpdate>>,
 }
@@ -37,6 +39,7 @@ impl<LDT: LoomDataTypes> Default for MempoolTx<LDT> {
             logs: None,
             mined: None,
             failed: None,
+            replaced_by: None,
             pre_state: None,
         }
     }
@@ -0,0 +1,256 @@
+use crate::{CallType, MulticallerCall, MulticallerCalls};
+use thiserror::Error;
+
+const MAX_STACK_OFFSET: u32 = 0x7;
+const MAX_DATA_OFFSET: u32 = 0xFFF;
+const MAX_DATA_LEN: usize = 0xFF;
+
+/// Internal and calculation calls always push their result, other calls only when a return stack is set.
+pub(crate) fn pushes_to_stack(call: &MulticallerCall) -> bool {
+    call.return_stack.is_some() || matches!(call.call_type, CallType::InternalCall | CallType::CalculationCall)
+}
+
+fn produced_slots(calls: &MulticallerCalls) -> u32 {
+    calls.opcodes_vec.iter().filter(|call| pushes_to_stack(call)).count() as u32
+}
+
+/// Symbolic reference to a multicaller stack slot.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StackSlot {
+    /// Value pushed by the most recent producing call.
+    Last,
+    /// Value pushed `n` producing calls before the most recent one. `Relative(0)` is `Last`.
+    Relative(u32),
+    /// Absolute stack position, counted from the bottom of the stack.
+    Absolute(u32),
+}
+
+impl StackSlot {
+    fn is_relative(&self) -> bool {
+        !matches!(self, StackSlot::Absolute(_))
+    }
+
+    fn offset(&self) -> u32 {
+        match self {
+            StackSlot::Last => 0,
+            StackSlot::Relative(offset) | StackSlot::Absolute(offset) => *offset,
+        }
+    }
+}
+
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum StackSlotError {
+    #[error("Call {call_idx} reads {slot:?} but only {depth} slots are available")]
+    UnresolvedSlot { call_idx: usize, slot: StackSlot, depth: u32 },
+    #[error("Call {call_idx} uses stack offset {offset} exceeding the encodable maximum")]
+    StackOffsetOutOfRange { call_idx: usize, offset: u32 },
+    #[error("Call {call_idx} uses data offset {data_offset:#x} exceeding the encodable maximum")]
+    DataOffsetOutOfRange { call_idx: usize, data_offset: u32 },
+    #[error("Call {call_idx} uses data length {data_len:#x} exceeding the encodable maximum")]
+    DataLenOutOfRange { call_idx: usize, data_len: usize },
+}
+
+/// Builds [`MulticallerCalls`] while tracking which stack slots are produced and consumed.
+///
+/// Every call reading from the stack references a [`StackSlot`] that is resolved against the slots pushed by
+/// previous calls (or declared as inherited from the enclosing execution context). Unresolvable references are
+/// reported by [`MulticallerCallsBuilder::build`] instead of reverting on-chain.
+#[derive(Clone, Debug, Default)]
+pub struct MulticallerCallsBuilder {
+    calls: MulticallerCalls,
+    depth: u32,
+    errors: Vec<StackSlotError>,
+}
+
+impl MulticallerCallsBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Continue building after already encoded calls, accounting for the slots they push.
+    pub fn from_calls(calls: MulticallerCalls) -> Self {
+        Self { depth: produced_slots(&calls), calls, errors: Vec::new() }
+    }
+
+    /// Declare slots provided by the enclosing context, e.g. the amount calculated by a previous hop or
+    /// values pushed before a flash swap callback is executed.
+    pub fn with_inherited_slots(mut self, slots: u32) -> Self {
+        self.depth += slots;
+        self
+    }
+
+    /// Declare that at least `slots` slots are available, without double counting already tracked ones.
+    pub fn ensure_inherited_slots(&mut self, slots: u32) -> &mut Self {
+        self.depth = self.depth.max(slots);
+        self
+    }
+
+    /// Number of stack slots available to the next call.
+    pub fn depth(&self) -> u32 {
+        self.depth
+    }
+
+    pub fn len(&self) -> usize {
+        self.calls.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.calls.is_empty()
+    }
+
+    /// Start adding a call. The call is appended when [`CallSlotBuilder::add`] is invoked.
+    pub fn call(&mut self, call: MulticallerCall) -> CallSlotBuilder<'_> {
+        CallSlotBuilder { builder: self, call, input: None, output: None }
+    }
+
+    /// Append a call that neither reads nor writes the stack.
+    pub fn add(&mut self, call: MulticallerCall) -> &mut Self {
+        self.call(call).add();
+        self
+    }
+
+    /// Append already built calls, accounting for the slots they push.
+    pub fn merge(&mut self, calls: MulticallerCalls) -> &mut Self {
+        self.depth += produced_slots(&calls);
+        self.calls.merge(calls);
+        self
+    }
+
+    /// Validate all slot references and return the encoded calls.
+    pub fn build(self) -> Result<MulticallerCalls, StackSlotError> {
+        match self.errors.into_iter().next() {
+            Some(error) => Err(error),
+            None => Ok(self.calls),
+        }
+    }
+
+    fn check_layout(&mut self, call_idx: usize, stack_offset: u32, data_offset: u32, data_len: usize) {
+        if stack_offset > MAX_STACK_OFFSET {
+            self.errors.push(StackSlotError::StackOffsetOutOfRange { call_idx, offset: stack_offset });
+        }
+        if data_offset > MAX_DATA_OFFSET {
+            self.errors.push(StackSlotError::DataOffsetOutOfRange { call_idx, data_offset });
+        }
+        if data_len > MAX_DATA_LEN {
+            self.errors.push(StackSlotError::DataLenOutOfRange { call_idx, data_len });
+        }
+    }
+
+    fn push_call(&mut self, mut call: MulticallerCall, input: Option<(StackSlot, u32, usize)>, output: Option<(u32, usize)>) {
+        let call_idx = self.calls.len();
+
+        if let Some((slot, data_offset, data_len)) = input {
+            if slot.offset() >= self.depth {
+                self.errors.push(StackSlotError::UnresolvedSlot { call_idx, slot, depth: self.depth });
+            }
+            self.check_layout(call_idx, slot.offset(), data_offset, data_len);
+            call.set_call_stack(slot.is_relative(), slot.offset(), data_offset, data_len);
+        }
+
+        if let Some((data_offset, data_len)) = output {
+            self.check_layout(call_idx, 0, data_offset, data_len);
+            call.set_return_stack(true, 0, data_offset, data_len);
+        }
+
+        if pushes_to_stack(&call) {
+            self.depth += 1;
+        }
+
+        self.calls.add(call);
+    }
+}
+
+/// Configures stack usage of a single call added with [`MulticallerCallsBuilder::call`].
+#[must_use = "the call is only appended by CallSlotBuilder::add"]
+pub struct CallSlotBuilder<'a> {
+    builder: &'a mut MulticallerCallsBuilder,
+    call: MulticallerCall,
+    input: Option<(StackSlot, u32, usize)>,
+    output: Option<(u32, usize)>,
+}
+
+impl CallSlotBuilder<'_> {
+    /// Read a 32 bytes amount from `slot` into the call data at `data_offset`.
+    pub fn amount_from(self, slot: StackSlot, data_offset: u32) -> Self {
+        self.input_from(slot, data_offset, 0x20)
+    }
+
+    /// Read an optional amount, leaving the call data untouched for `None`.
+    pub fn amount_from_opt(self, slot: Option<StackSlot>, data_offset: u32) -> Self {
+        match slot {
+            Some(slot) => self.amount_from(slot, data_offset),
+            None => self,
+        }
+    }
+
+    /// Use the value in `slot` as the call value.
+    pub fn value_from(self, slot: StackSlot) -> Self {
+        self.input_from(slot, 0x0, 0x0)
+    }
+
+    /// Read `data_len` bytes from `slot` into the call data at `data_offset`.
+    pub fn input_from(mut self, slot: StackSlot, data_offset: u32, data_len: usize) -> Self {
+        self.input = Some((slot, data_offset, data_len));
+        self
+    }
+
+    /// Push the 32 bytes word located at `data_offset` of the return data to the stack.
+    pub fn push_result(self, data_offset: u32) -> Self {
+        self.push_result_len(data_offset, 0x20)
+    }
+
+    /// Push `data_len` bytes located at `data_offset` of the return data to the stack.
+    pub fn push_result_len(mut self, data_offset: u32, data_len: usize) -> Self {
+        self.output = Some((data_offset, data_len));
+        self
+    }
+
+    /// Append the call to the builder.
+    pub fn add(self) {
+        self.builder.push_call(self.call, self.input, self.output);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloy_primitives::{Address, Bytes};
+
+    #[test]
+    fn test_last_slot_resolves_after_push() {
+        let mut builder = MulticallerCallsBuilder::new();
+        builder.call(MulticallerCall::new_static_call(Address::ZERO, &Bytes::new())).push_result(0x0).add();
+        builder.call(MulticallerCall::new_call(Address::ZERO, &Bytes::new())).amount_from(StackSlot::Last, 0x24).add();
+
+        let calls = builder.build().unwrap();
+        let call_stack = calls.get(1).unwrap().call_stack.clone().unwrap();
+        assert!(call_stack.is_relative);
+        assert_eq!(call_stack.stack_offset, 0);
+        assert_eq!(call_stack.data_offset, 0x24);
+    }
+
+    #[test]
+    fn test_unresolved_slot() {
+        let mut builder = MulticallerCallsBuilder::new();
+        builder.call(MulticallerCall::new_call(Address::ZERO, &Bytes::new())).amount_from(StackSlot::Last, 0x24).add();
+
+        assert_eq!(builder.build().unwrap_err(), StackSlotError::UnresolvedSlot { call_idx: 0, slot: StackSlot::Last, depth: 0 });
+    }
+
+    #[test]
+    fn test_internal_call_pushes_result() {
+        let mut builder = MulticallerCallsBuilder::new();
+        builder.add(MulticallerCall::new_internal_call(&Bytes::new()));
+        builder.call(MulticallerCall::new_call(Address::ZERO, &Bytes::new())).amount_from(StackSlot::Last, 0x24).add();
+
+        assert!(builder.build().is_ok());
+    }
+
+    #[test]
+    fn test_inherited_slots() {
+        let mut builder = MulticallerCallsBuilder::new().with_inherited_slots(2);
+        builder.call(MulticallerCall::new_call(Address::ZERO, &Bytes::new())).amount_from(StackSlot::Absolute(1), 0x24).add();
+
+        assert!(builder.build().is_ok());
+    }
+}
@@ -0,0 +1,137 @@
+use alloy_primitives::{Address, U256};
+use thiserror::Error;
+
+use crate::opcodes_builder::pushes_to_stack;
+use crate::{CallType, MulticallerCall, MulticallerCalls};
+
+// transfer(address,uint256)
+const ERC20_TRANSFER_SELECTOR: [u8; 4] = [0xa9, 0x05, 0x9c, 0xbb];
+// approve(address,uint256), transferFrom(address,address,uint256), withdraw(uint256)
+const NON_PAYABLE_SELECTORS: [[u8; 4]; 4] =
+    [ERC20_TRANSFER_SELECTOR, [0x09, 0x5e, 0xa7, 0xb3], [0x23, 0xb8, 0x72, 0xdd], [0x2e, 0x1a, 0x7d, 0x4d]];
+const TRANSFER_AMOUNT_OFFSET: u32 = 0x24;
+
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum OpcodesValidationError {
+    #[error("Call {call_idx} reads stack slot {stack_offset} (relative={is_relative}) but only {depth} slots are written")]
+    UnwrittenStackSlot { call_idx: usize, is_relative: bool, stack_offset: u32, depth: u32 },
+    #[error("Call {call_idx} sends value to non-payable function of {to}")]
+    ValueToNonPayable { call_idx: usize, to: Address },
+    #[error("Call {call_idx} sends value with {call_type:?} call")]
+    ValueWithCallType { call_idx: usize, call_type: CallType },
+    #[error("Call {call_idx} transfers zero amount of {token} without stack override")]
+    ZeroTransfer { call_idx: usize, token: Address },
+    #[error("Call {call_idx} reads stack into {data_offset:#x}..{data_end:#x} outside of call data of {call_data_len} bytes")]
+    StackOffsetOutOfCallData { call_idx: usize, data_offset: u32, data_end: usize, call_data_len: usize },
+}
+
+fn selector(call: &MulticallerCall) -> Option<[u8; 4]> {
+    call.call_data.get(0..4).map(|s| [s[0], s[1], s[2], s[3]])
+}
+
+fn is_value_from_stack(call: &MulticallerCall) -> bool {
+    call.call_stack.as_ref().is_some_and(|call_stack| call_stack.data_len == 0)
+}
+
+fn validate_call(call_idx: usize, call: &MulticallerCall, depth: u32) -> Result<(), OpcodesValidationError> {
+    if let Some(call_stack) = &call.call_stack {
+        if call_stack.stack_offset >= depth {
+            return Err(OpcodesValidationError::UnwrittenStackSlot {
+                call_idx,
+                is_relative: call_stack.is_relative,
+                stack_offset: call_stack.stack_offset,
+                depth,
+            });
+        }
+
+        let data_end = call_stack.data_offset as usize + call_stack.data_len;
+        if call_stack.data_len > 0 && data_end > call.call_data.len() {
+            return Err(OpcodesValidationError::StackOffsetOutOfCallData {
+                call_idx,
+                data_offset: call_stack.data_offset,
+                data_end,
+                call_data_len: call.call_data.len(),
+            });
+        }
+    }
+
+    let has_value = !call.value.unwrap_or_default().is_zero() || (call.value.is_some() && is_value_from_stack(call));
+    if has_value {
+        if call.call_type != CallType::Call {
+            return Err(OpcodesValidationError::ValueWithCallType { call_idx, call_type: call.call_type.clone() });
+        }
+        if selector(call).is_some_and(|selector| NON_PAYABLE_SELECTORS.contains(&selector)) {
+            return Err(OpcodesValidationError::ValueToNonPayable { call_idx, to: call.to });
+        }
+    }
+
+    if call.call_type == CallType::Call && selector(call) == Some(ERC20_TRANSFER_SELECTOR) {
+        let amount = call.call_data.get(0x24..0x44).map(U256::from_be_slice).unwrap_or_default();
+        let amount_from_stack = call.call_stack.as_ref().is_some_and(|call_stack| call_stack.data_offset == TRANSFER_AMOUNT_OFFSET);
+        if amount.is_zero() && !amount_from_stack {
+            return Err(OpcodesValidationError::ZeroTransfer { call_idx, token: call.to });
+        }
+    }
+
+    Ok(())
+}
+
+impl MulticallerCalls {
+    /// Check the sequence for invariant violations that would otherwise revert on-chain.
+    pub fn validate(&self) -> Result<(), OpcodesValidationError> {
+        self.validate_with_inherited_slots(0)
+    }
+
+    /// Same as [`MulticallerCalls::validate`] for sequences executed with `slots` values already on the stack,
+    /// e.g. inside a flash swap callback.
+    pub fn validate_with_inherited_slots(&self, slots: u32) -> Result<(), OpcodesValidationError> {
+        let mut depth = slots;
+        for (call_idx, call) in self.opcodes_vec.iter().enumerate() {
+            validate_call(call_idx, call, depth)?;
+            if pushes_to_stack(call) {
+                depth += 1;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloy_primitives::Bytes;
+
+    fn transfer_call_data(amount: U256) -> Bytes {
+        let mut call_data = ERC20_TRANSFER_SELECTOR.to_vec();
+        call_data.extend_from_slice(&[0u8; 0x20]);
+        call_data.extend_from_slice(&amount.to_be_bytes::<32>());
+        call_data.into()
+    }
+
+    #[test]
+    fn test_unwritten_stack_slot() {
+        let mut call = MulticallerCall::new_call(Address::ZERO, &transfer_call_data(U256::ZERO));
+        call.set_call_stack(true, 0, 0x24, 0x20);
+        let mut calls = MulticallerCalls::new();
+        calls.add(call);
+
+        assert!(matches!(calls.validate(), Err(OpcodesValidationError::UnwrittenStackSlot { call_idx: 0, .. })));
+        assert!(calls.validate_with_inherited_slots(1).is_ok());
+    }
+
+    #[test]
+    fn test_zero_transfer() {
+        let mut calls = MulticallerCalls::new();
+        calls.add(MulticallerCall::new_call(Address::ZERO, &transfer_call_data(U256::ZERO)));
+
+        assert!(matches!(calls.validate(), Err(OpcodesValidationError::ZeroTransfer { call_idx: 0, .. })));
+    }
+
+    #[test]
+    fn test_value_to_non_payable() {
+        let mut calls = MulticallerCalls::new();
+        calls.add(MulticallerCall::new_call_with_value(Address::ZERO, &transfer_call_data(U256::from(1)), U256::from(1)));
+
+        assert!(matches!(calls.validate(), Err(OpcodesValidationError::ValueToNonPayable { call_idx: 0, .. })));
+    }
+}
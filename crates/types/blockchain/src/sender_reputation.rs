@@ -0,0 +1,145 @@
+use alloy_primitives::map::HashMap;
+
+use crate::{LoomDataTypes, LoomDataTypesEthereum};
+
+// Senders with fewer transactions are never considered spammers
+const DEFAULT_MIN_TXS: u64 = 20;
+// Share of reverting or low value transactions of a spammer
+const DEFAULT_MAX_BAD_RATIO: f64 = 0.9;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SenderStats {
+    pub txs: u64,
+    /// Transactions reverted in simulation
+    pub failed: u64,
+    /// Transactions simulated on known pools without a profitable opportunity
+    pub low_value: u64,
+}
+
+impl SenderStats {
+    /// Share of failed and low value transactions
+    pub fn bad_ratio(&self) -> f64 {
+        if self.txs == 0 {
+            return 0.0;
+        }
+        ((self.failed + self.low_value) as f64 / self.txs as f64).min(1.0)
+    }
+}
+
+/// Transaction statistics by sender used to deprioritize senders that flood the mempool with reverting or low value
+/// transactions.
+#[derive(Clone, Debug)]
+pub struct SenderReputation<LDT: LoomDataTypes = LoomDataTypesEthereum> {
+    senders: HashMap<LDT::Address, SenderStats>,
+    min_txs: u64,
+    max_bad_ratio: f64,
+}
+
+impl<LDT: LoomDataTypes> Default for SenderReputation<LDT> {
+    fn default() -> Self {
+        Self { senders: HashMap::default(), min_txs: DEFAULT_MIN_TXS, max_bad_ratio: DEFAULT_MAX_BAD_RATIO }
+    }
+}
+
+impl<LDT: LoomDataTypes> SenderReputation<LDT> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_min_txs(self, min_txs: u64) -> Self {
+        Self { min_txs, ..self }
+    }
+
+    pub fn with_max_bad_ratio(self, max_bad_ratio: f64) -> Self {
+        Self { max_bad_ratio, ..self }
+    }
+
+    pub fn len(&self) -> usize {
+        self.senders.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.senders.is_empty()
+    }
+
+    pub fn record_tx(&mut self, sender: LDT::Address) {
+        self.senders.entry(sender).or_default().txs += 1;
+    }
+
+    pub fn record_failed(&mut self, sender: LDT::Address) {
+        self.senders.entry(sender).or_default().failed += 1;
+    }
+
+    pub fn record_low_value(&mut self, sender: LDT::Address) {
+        self.senders.entry(sender).or_default().low_value += 1;
+    }
+
+    pub fn stats(&self, sender: &LDT::Address) -> Option<&SenderStats> {
+        self.senders.get(sender)
+    }
+
+    /// Sender score from 0.0 for a spammer to 1.0 for a sender without failed or low value transactions
+    pub fn score(&self, sender: &LDT::Address) -> f64 {
+        self.senders.get(sender).map_or(1.0, |stats| 1.0 - stats.bad_ratio())
+    }
+
+    pub fn is_spammer(&self, sender: &LDT::Address) -> bool {
+        self.senders.get(sender).is_some_and(|stats| stats.txs >= self.min_txs && stats.bad_ratio() >= self.max_bad_ratio)
+    }
+
+    pub fn spammers_count(&self) -> usize {
+        self.senders.keys().filter(|sender| self.is_spammer(sender)).count()
+    }
+
+    /// Halve all counters so senders can recover, senders without transactions left are removed
+    pub fn decay(&mut self) {
+        self.senders.retain(|_, stats| {
+            stats.txs /= 2;
+            stats.failed /= 2;
+            stats.low_value /= 2;
+            stats.txs > 0
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloy_primitives::Address;
+
+    #[test]
+    fn test_spammer() {
+        let mut reputation = SenderReputation::<LoomDataTypesEthereum>::new().with_min_txs(10);
+        let (spammer, trader) = (Address::repeat_byte(1), Address::repeat_byte(2));
+        for i in 0..10 {
+            reputation.record_tx(spammer);
+            reputation.record_failed(spammer);
+            reputation.record_tx(trader);
+            if i % 2 == 0 {
+                reputation.record_low_value(trader);
+            }
+        }
+
+        assert!(reputation.is_spammer(&spammer));
+        assert!(!reputation.is_spammer(&trader));
+        assert_eq!(reputation.score(&trader), 0.5);
+        assert_eq!(reputation.score(&Address::ZERO), 1.0);
+        assert_eq!(reputation.spammers_count(), 1);
+    }
+
+    #[test]
+    fn test_decay() {
+        let mut reputation = SenderReputation::<LoomDataTypesEthereum>::new().with_min_txs(10);
+        let sender = Address::repeat_byte(1);
+        for _ in 0..10 {
+            reputation.record_tx(sender);
+            reputation.record_failed(sender);
+        }
+        reputation.decay();
+        assert!(!reputation.is_spammer(&sender));
+        assert_eq!(reputation.stats(&sender).map(|stats| stats.txs), Some(5));
+
+        (0..3).for_each(|_| reputation.decay());
+        assert!(reputation.is_empty());
+    }
+}
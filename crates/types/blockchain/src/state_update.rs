@@ -1,19 +1,36 @@
-use alloy_primitives::{Address, TxHash};
+use alloy_primitives::Address;
+#[cfg(feature = "provider")]
+use alloy_primitives::TxHash;
+#[cfg(feature = "provider")]
 use alloy_provider::ext::DebugApi;
+#[cfg(feature = "provider")]
 use alloy_provider::{Network, Provider};
+use alloy_rpc_types::state::StateOverride;
+#[cfg(feature = "provider")]
 use alloy_rpc_types::{BlockId, TransactionRequest};
+#[cfg(feature = "provider")]
 use alloy_rpc_types_trace::common::TraceResult;
+#[cfg(feature = "provider")]
 use alloy_rpc_types_trace::geth::GethDebugBuiltInTracerType::PreStateTracer;
+#[cfg(feature = "provider")]
 use alloy_rpc_types_trace::geth::GethDebugTracerType::BuiltInTracer;
 use alloy_rpc_types_trace::geth::{
     AccountState, GethDebugBuiltInTracerType, GethDebugTracerConfig, GethDebugTracerType, GethDebugTracingCallOptions,
-    GethDebugTracingOptions, GethDefaultTracingOptions, GethTrace, PreStateConfig, PreStateFrame,
+    GethDebugTracingOptions, GethDefaultTracingOptions,
 };
+#[cfg(feature = "provider")]
+use alloy_rpc_types_trace::geth::{GethTrace, PreStateConfig, PreStateFrame};
+#[cfg(feature = "provider")]
 use eyre::Result;
 use lazy_static::lazy_static;
 use std::collections::BTreeMap;
-use tracing::{debug, trace};
+#[cfg(feature = "provider")]
+use std::collections::HashMap;
+use tracing::debug;
+#[cfg(feature = "provider")]
+use tracing::trace;
 
+#[cfg(feature = "provider")]
 use loom_node_debug_provider::DebugProviderExt;
 
 pub type GethStateUpdate = BTreeMap<Address, AccountState>;
@@ -43,12 +60,32 @@ pub fn get_touched_addresses(state_update: &GethStateUpdate) -> Vec<Address> {
     ret
 }
 
+/// Apply the post state of a transaction to the state override, so a call with it is executed after the transaction
+pub fn apply_state_update_to_override(state_override: &mut StateOverride, state_update: &GethStateUpdate) {
+    for (address, account_state) in state_update {
+        let account = state_override.entry(*address).or_default();
+        if account_state.balance.is_some() {
+            account.balance = account_state.balance;
+        }
+        if account_state.nonce.is_some() {
+            account.nonce = account_state.nonce;
+        }
+        if account_state.code.is_some() {
+            account.code = account_state.code.clone();
+        }
+        if !account_state.storage.is_empty() {
+            account.state_diff.get_or_insert_with(Default::default).extend(account_state.storage.iter().map(|(k, v)| (*k, *v)));
+        }
+    }
+}
+
 pub fn debug_log_geth_state_update(state_update: &GethStateUpdate) {
     for (address, state) in state_update {
         debug!("{} nonce {:?} balance {:?} is_code {}", address, state.nonce, state.balance, state.code.is_some())
     }
 }
 
+#[cfg(feature = "provider")]
 pub async fn debug_trace_block<N: Network, P: Provider<N> + DebugProviderExt<N>>(
     client: P,
     block_id: BlockId,
@@ -92,6 +129,33 @@ pub async fn debug_trace_block<N: Network, P: Provider<N> + DebugProviderExt<N>>
     Ok((pre, post))
 }
 
+/// Post states of the transactions of the block by their hash, traces without a tx hash are skipped
+#[cfg(feature = "provider")]
+pub async fn debug_trace_block_post_states<N: Network, P: Provider<N> + DebugProviderExt<N>>(
+    client: P,
+    block_id: BlockId,
+) -> Result<HashMap<TxHash, GethStateUpdate>> {
+    let tracer_opts = GethDebugTracingOptions { config: GethDefaultTracingOptions::default(), ..GethDebugTracingOptions::default() }
+        .with_tracer(BuiltInTracer(PreStateTracer))
+        .with_prestate_config(PreStateConfig { diff_mode: Some(true), disable_code: Some(false), disable_storage: Some(false) });
+
+    let trace_result_vec = match block_id {
+        BlockId::Number(block_number) => client.geth_debug_trace_block_by_number(block_number, tracer_opts).await?,
+        BlockId::Hash(rpc_block_hash) => client.geth_debug_trace_block_by_hash(rpc_block_hash.block_hash, tracer_opts).await?,
+    };
+
+    let mut post_states = HashMap::new();
+    for trace_result in trace_result_vec {
+        if let TraceResult::Success { result: GethTrace::PreStateTracer(PreStateFrame::Diff(diff_frame)), tx_hash: Some(tx_hash) } =
+            trace_result
+        {
+            post_states.insert(tx_hash, diff_frame.post);
+        }
+    }
+    Ok(post_states)
+}
+
+#[cfg(feature = "provider")]
 async fn debug_trace_call<N: Network, C: DebugProviderExt<N>, TR: Into<TransactionRequest> + Send + Sync>(
     client: C,
     req: TR,
@@ -127,6 +191,7 @@ async fn debug_trace_call<N: Network, C: DebugProviderExt<N>, TR: Into<Transacti
     }
 }
 
+#[cfg(feature = "provider")]
 pub async fn debug_trace_call_pre_state<N: Network, C: DebugProviderExt<N>, TR: Into<TransactionRequest> + Send + Sync>(
     client: C,
     req: TR,
@@ -136,6 +201,7 @@ pub async fn debug_trace_call_pre_state<N: Network, C: DebugProviderExt<N>, TR:
     Ok(debug_trace_call(client, req, block, opts, false).await?.0)
 }
 
+#[cfg(feature = "provider")]
 pub async fn debug_trace_call_post_state<N: Network, C: DebugProviderExt<N>, TR: Into<TransactionRequest> + Send + Sync>(
     client: C,
     req: TR,
@@ -145,6 +211,7 @@ pub async fn debug_trace_call_post_state<N: Network, C: DebugProviderExt<N>, TR:
     Ok(debug_trace_call(client, req, block, opts, true).await?.1)
 }
 
+#[cfg(feature = "provider")]
 pub async fn debug_trace_call_diff<N: Network, C: DebugProviderExt<N>, TR: Into<TransactionRequest> + Send + Sync>(
     client: C,
     req: TR,
@@ -154,6 +221,7 @@ pub async fn debug_trace_call_diff<N: Network, C: DebugProviderExt<N>, TR: Into<
     debug_trace_call(client, req, block, call_opts, true).await
 }
 
+#[cfg(feature = "provider")]
 pub async fn debug_trace_transaction<N: Network, P: Provider<N> + DebugApi<N>>(
     client: P,
     req: TxHash,
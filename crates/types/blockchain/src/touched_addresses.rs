@@ -0,0 +1,84 @@
+use alloy_primitives::{Address, Bloom, BloomInput};
+use std::collections::HashSet;
+
+use crate::GethStateUpdate;
+
+/// Addresses with changed storage in the state diffs of a block.
+/// The bloom rejects most lookups of untouched addresses before the set is hashed.
+#[derive(Clone, Debug, Default)]
+pub struct TouchedAddresses {
+    bloom: Bloom,
+    addresses: HashSet<Address>,
+}
+
+impl TouchedAddresses {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_state_update(state_update: &[GethStateUpdate]) -> Self {
+        let mut touched = Self::new();
+        for state_update_record in state_update.iter() {
+            touched.add_state_update(state_update_record);
+        }
+        touched
+    }
+
+    pub fn add_state_update(&mut self, state_update: &GethStateUpdate) {
+        for (address, state) in state_update.iter() {
+            if !state.storage.is_empty() {
+                self.insert(*address);
+            }
+        }
+    }
+
+    pub fn insert(&mut self, address: Address) {
+        if self.addresses.insert(address) {
+            self.bloom.accrue(BloomInput::Raw(address.as_slice()));
+        }
+    }
+
+    pub fn contains(&self, address: &Address) -> bool {
+        self.bloom.contains_input(BloomInput::Raw(address.as_slice())) && self.addresses.contains(address)
+    }
+
+    pub fn len(&self) -> usize {
+        self.addresses.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.addresses.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Address> {
+        self.addresses.iter()
+    }
+
+    /// Touched addresses of the set, e.g. pools to invalidate
+    pub fn intersect<'a>(&self, addresses: impl IntoIterator<Item = &'a Address>) -> Vec<Address> {
+        addresses.into_iter().filter(|address| self.contains(address)).copied().collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloy_primitives::{B256, U256};
+    use alloy_rpc_types_trace::geth::AccountState;
+
+    #[test]
+    fn test_touched_addresses() {
+        let pool = Address::repeat_byte(1);
+        let balance_only = Address::repeat_byte(2);
+
+        let mut state_update = GethStateUpdate::new();
+        state_update.insert(pool, AccountState { storage: [(B256::ZERO, B256::repeat_byte(1))].into(), ..Default::default() });
+        state_update.insert(balance_only, AccountState { balance: Some(U256::from(1)), ..Default::default() });
+
+        let touched = TouchedAddresses::from_state_update(&[state_update]);
+        assert_eq!(touched.len(), 1);
+        assert!(touched.contains(&pool));
+        assert!(!touched.contains(&balance_only));
+        assert_eq!(touched.intersect(&[pool, balance_only, Address::repeat_byte(3)]), vec![pool]);
+    }
+}
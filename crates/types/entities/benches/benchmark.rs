@@ -56,11 +56,36 @@ fn test_market_fill() -> eyre::Result<()> {
     Ok(())
 }
 
+// same market as test_market_fill with paths built only through each new pool
+fn test_market_fill_incremental() -> eyre::Result<()> {
+    let mut market = Market::default();
+    market.add_token(WETH.clone());
+    market.add_token(USDT.clone());
+    for _ in 0..2 {
+        let weth_usdt_pool = PoolWrapper::new(Arc::new(create_pool(WETH.get_address(), USDT.get_address())));
+        market.add_pool(weth_usdt_pool)?;
+    }
+
+    for _ in 0..2000 {
+        let token_address = Address::random();
+        let weth_pool = PoolWrapper::new(Arc::new(create_pool(WETH.get_address(), token_address)));
+        let usdt_pool = PoolWrapper::new(Arc::new(create_pool(USDT.get_address(), token_address)));
+        for pool in [weth_pool, usdt_pool] {
+            market.add_pool(pool.clone())?;
+            let swap_paths = market.build_pool_swap_path_vec(&pool)?;
+            market.add_paths(swap_paths);
+        }
+    }
+    println!("{}", market);
+    Ok(())
+}
+
 fn benchmark_test_group_hasher(c: &mut Criterion) {
     let mut group = c.benchmark_group("market");
     group.sample_size(10);
 
     group.bench_function("test_market_fill", |b| b.iter(test_market_fill));
+    group.bench_function("test_market_fill_incremental", |b| b.iter(test_market_fill_incremental));
     group.finish();
 }
 
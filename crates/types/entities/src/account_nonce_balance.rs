@@ -1,4 +1,6 @@
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 
 use alloy_primitives::{Address, U256};
 use loom_types_blockchain::{LoomDataTypes, LoomDataTypesEthereum};
@@ -7,11 +9,12 @@ use loom_types_blockchain::{LoomDataTypes, LoomDataTypesEthereum};
 pub struct AccountNonceAndBalances<LDT: LoomDataTypes = LoomDataTypesEthereum> {
     nonce: u64,
     balance: HashMap<LDT::Address, U256>,
+    nonce_listener: Option<Arc<AtomicU64>>,
 }
 
 impl<LDT: LoomDataTypes> AccountNonceAndBalances<LDT> {
     pub fn new() -> Self {
-        Self { nonce: 0, balance: HashMap::new() }
+        Self { nonce: 0, balance: HashMap::new(), nonce_listener: None }
     }
 
     pub fn get_nonce(&self) -> u64 {
@@ -20,9 +23,23 @@ impl<LDT: LoomDataTypes> AccountNonceAndBalances<LDT> {
 
     pub fn set_nonce(&mut self, nonce: u64) -> &mut Self {
         self.nonce = nonce;
+        if let Some(nonce_listener) = &self.nonce_listener {
+            nonce_listener.store(nonce, Ordering::Relaxed);
+        }
+        self
+    }
+
+    /// Keep `nonce_listener` in sync with the nonce of the account, e.g. the authority nonce of a sponsored signer
+    pub fn set_nonce_listener(&mut self, nonce_listener: Arc<AtomicU64>) -> &mut Self {
+        nonce_listener.store(self.nonce, Ordering::Relaxed);
+        self.nonce_listener = Some(nonce_listener);
         self
     }
 
+    pub fn has_nonce_listener(&self) -> bool {
+        self.nonce_listener.is_some()
+    }
+
     pub fn set_balance(&mut self, token: LDT::Address, balance: U256) -> &mut Self {
         let entry = self.balance.entry(token).or_default();
         *entry = balance;
@@ -1,17 +1,30 @@
 use std::collections::HashMap;
 use std::fmt::Debug;
+#[cfg(feature = "provider")]
 use std::marker::PhantomData;
 
 use crate::block_history::block_history_state::BlockHistoryState;
+#[cfg(feature = "provider")]
 use crate::market_state::MarketStateConfig;
+#[cfg(feature = "provider")]
 use alloy_network::{BlockResponse, Ethereum};
 use alloy_primitives::{BlockHash, BlockNumber};
+#[cfg(feature = "provider")]
 use alloy_provider::Provider;
-use alloy_rpc_types::{Block, BlockId, BlockTransactionsKind, Filter, Header, Log};
-use eyre::{eyre, ErrReport, OptionExt, Result};
+use alloy_rpc_types::{Block, Header, Log};
+#[cfg(feature = "provider")]
+use alloy_rpc_types::{BlockId, BlockTransactionsKind, Filter};
+#[cfg(feature = "provider")]
+use eyre::OptionExt;
+use eyre::{eyre, ErrReport, Result};
+#[cfg(feature = "provider")]
 use loom_node_debug_provider::DebugProviderExt;
-use loom_types_blockchain::{debug_trace_block, GethStateUpdateVec};
-use tracing::{debug, error};
+#[cfg(feature = "provider")]
+use loom_types_blockchain::debug_trace_block;
+use loom_types_blockchain::GethStateUpdateVec;
+use tracing::debug;
+#[cfg(feature = "provider")]
+use tracing::error;
 
 #[derive(Clone, Debug, Default)]
 pub struct BlockHistoryEntry {
@@ -246,11 +259,13 @@ impl<S> BlockHistory<S> {
     }
 }
 
+#[cfg(feature = "provider")]
 pub struct BlockHistoryManager<P, D> {
     client: P,
     _td: PhantomData<D>,
 }
 
+#[cfg(feature = "provider")]
 impl<P, S> BlockHistoryManager<P, S>
 where
     P: Provider<Ethereum> + DebugProviderExt<Ethereum> + Send + Sync + Clone + 'static,
@@ -321,6 +336,7 @@ where
     }
 }
 
+#[cfg(feature = "provider")]
 impl<P, S> BlockHistoryManager<P, S>
 where
     P: Provider<Ethereum> + DebugProviderExt<Ethereum> + Send + Sync + Clone + 'static,
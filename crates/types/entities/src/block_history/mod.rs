@@ -1,4 +1,6 @@
-pub use block_history_impl::{BlockHistory, BlockHistoryEntry, BlockHistoryManager};
+#[cfg(feature = "provider")]
+pub use block_history_impl::BlockHistoryManager;
+pub use block_history_impl::{BlockHistory, BlockHistoryEntry};
 pub use block_history_state::BlockHistoryState;
 
 mod block_history_impl;
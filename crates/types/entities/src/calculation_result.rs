@@ -1,15 +1,30 @@
 use alloy_primitives::U256;
 use std::fmt::{Display, Formatter};
 
+/// Slippage allowed from the calculated out amount of a hop checking a min out, in basis points
+pub const HOP_SLIPPAGE_BPS: u64 = 50;
+
+/// Min and max out amounts of a hop with the calculated `amount_out` allowing for [`HOP_SLIPPAGE_BPS`]
+pub fn hop_amount_out_bounds(amount_out: U256) -> (U256, U256) {
+    let slippage = amount_out * U256::from(HOP_SLIPPAGE_BPS) / U256::from(10000);
+    (amount_out - slippage, amount_out.saturating_add(slippage))
+}
+
 #[derive(Debug, Clone)]
 pub struct CalculationResult {
     pub amount_in: U256,
     pub amount_out: U256,
+    /// Gas of the hop estimated by its pool
+    pub gas_used: u64,
 }
 
 impl CalculationResult {
     pub fn new(amount_in: U256, amount_out: U256) -> Self {
-        Self { amount_in, amount_out }
+        Self { amount_in, amount_out, gas_used: 0 }
+    }
+
+    pub fn with_gas_used(self, gas_used: u64) -> Self {
+        Self { gas_used, ..self }
     }
 }
 
@@ -0,0 +1,105 @@
+use alloy_primitives::Address;
+use loom_defi_address_book::{PeripheryAddress, PeripheryAddressAvalanche, PeripheryAddressBsc};
+
+use crate::{FundingMode, PoolClass};
+
+/// Lender of the flash loans funding swaps that cannot be flash swapped
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct FlashLoanProvider {
+    pub mode: FundingMode,
+    pub lender: Address,
+}
+
+impl FlashLoanProvider {
+    pub fn balancer(vault: Address) -> Self {
+        Self { mode: FundingMode::BalancerFlashLoan, lender: vault }
+    }
+}
+
+/// Chain specific setup of the swap encoding: flash loan lender, periphery contracts and pool classes whose encoders
+/// rely on mainnet addresses
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChainPreset {
+    /// `None` on chains without a supported lender, swaps are funded by flash swaps or the multicaller balance
+    pub flash_loan: Option<FlashLoanProvider>,
+    /// Uniswap SwapRouter02 checking the deadline of swaps encoded for the public mempool and the min-out of exchange payouts,
+    /// `None` on chains where its address is not known
+    pub deadline_router: Option<Address>,
+    pub disabled_pool_classes: Vec<PoolClass>,
+}
+
+impl Default for ChainPreset {
+    fn default() -> Self {
+        Self {
+            flash_loan: Some(FlashLoanProvider::balancer(PeripheryAddress::BALANCER_VAULT)),
+            deadline_router: Some(PeripheryAddress::UNISWAP_V3_SWAP_ROUTER_02),
+            disabled_pool_classes: Vec::new(),
+        }
+    }
+}
+
+impl ChainPreset {
+    /// Preset of the chain, chains without a preset use the mainnet setup without the deadline router, the mainnet router
+    /// has no code there and the deadline check would always pass
+    pub fn for_chain_id(chain_id: u64) -> Self {
+        match chain_id {
+            // BSC, the multicaller handles the callback of Balancer flash loans only and there is no vault, swaps are funded by
+            // Pancake and Uniswap flash swaps or the multicaller balance
+            56 => Self {
+                flash_loan: None,
+                deadline_router: Some(PeripheryAddressBsc::UNISWAP_V3_SWAP_ROUTER_02),
+                disabled_pool_classes: Vec::new(),
+            },
+            // Avalanche, the Balancer vault is deployed at the mainnet address
+            43114 => Self {
+                flash_loan: Some(FlashLoanProvider::balancer(PeripheryAddress::BALANCER_VAULT)),
+                deadline_router: Some(PeripheryAddressAvalanche::UNISWAP_V3_SWAP_ROUTER_02),
+                disabled_pool_classes: Vec::new(),
+            },
+            1 => Self::default(),
+            _ => Self { deadline_router: None, ..Self::default() },
+        }
+    }
+
+    /// Mode the swaps are funded with on the chain. Swap lines and steps choose a Balancer flash loan when they cannot be
+    /// flash swapped, it is replaced by the flash loan of the chain, `None` if there is none
+    pub fn funding_mode(&self, mode: FundingMode) -> Option<FundingMode> {
+        match mode {
+            FundingMode::BalancerFlashLoan | FundingMode::AaveFlashLoan => self.flash_loan.map(|flash_loan| flash_loan.mode),
+            FundingMode::FlashSwap | FundingMode::Balance => Some(mode),
+        }
+    }
+
+    pub fn is_pool_class_enabled(&self, pool_class: PoolClass) -> bool {
+        !self.disabled_pool_classes.contains(&pool_class)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_for_chain_id() {
+        let mainnet = ChainPreset::for_chain_id(1);
+        assert_eq!(mainnet, ChainPreset::default());
+        assert_eq!(mainnet.funding_mode(FundingMode::BalancerFlashLoan), Some(FundingMode::BalancerFlashLoan));
+        assert!(mainnet.is_pool_class_enabled(PoolClass::Curve));
+
+        assert_eq!(ChainPreset::for_chain_id(56).funding_mode(FundingMode::BalancerFlashLoan), None);
+        assert_eq!(ChainPreset::for_chain_id(43114).funding_mode(FundingMode::BalancerFlashLoan), Some(FundingMode::BalancerFlashLoan));
+        for chain_id in [56, 43114] {
+            let preset = ChainPreset::for_chain_id(chain_id);
+            assert_eq!(preset.funding_mode(FundingMode::FlashSwap), Some(FundingMode::FlashSwap));
+            assert!(preset.deadline_router.is_some_and(|router| router != PeripheryAddress::UNISWAP_V3_SWAP_ROUTER_02));
+            assert!(preset.is_pool_class_enabled(PoolClass::Curve));
+            assert!(preset.is_pool_class_enabled(PoolClass::PancakeV3));
+        }
+
+        assert_eq!(ChainPreset::for_chain_id(137).deadline_router, None);
+
+        let no_flash_loan = ChainPreset { flash_loan: None, ..ChainPreset::default() };
+        assert_eq!(no_flash_loan.funding_mode(FundingMode::BalancerFlashLoan), None);
+        assert_eq!(no_flash_loan.funding_mode(FundingMode::Balance), Some(FundingMode::Balance));
+    }
+}
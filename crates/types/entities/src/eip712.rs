@@ -0,0 +1,305 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt::Display;
+
+use alloy_primitives::{address, keccak256, Address, PrimitiveSignature, B256, U256};
+use alloy_sol_types::{sol, Eip712Domain, SolStruct};
+use eyre::{eyre, Result};
+use serde::Deserialize;
+
+use crate::LoomTxSigner;
+use loom_types_blockchain::LoomDataTypesEthereum;
+
+/// Permit2 verifies UniswapX order signatures, the order is the witness of a permit transfer
+pub const PERMIT2_ADDRESS: Address = address!("000000000022D473030F116dDEE9F6B43aC78BA3");
+pub const COW_SETTLEMENT_ADDRESS: Address = address!("9008D19f58AAbD9eD0D60971565AA8510560ab41");
+
+const PERMIT2_CHAIN_IDS: [u64; 5] = [1, 10, 137, 8453, 42161];
+const COW_CHAIN_IDS: [u64; 4] = [1, 100, 8453, 42161];
+
+pub mod cow {
+    use super::sol;
+
+    sol! {
+        /// CoW Protocol GPv2 order, `kind` is "sell" or "buy", token balances are "erc20", "external" or "internal"
+        #[derive(Debug, PartialEq, Eq)]
+        struct Order {
+            address sellToken;
+            address buyToken;
+            address receiver;
+            uint256 sellAmount;
+            uint256 buyAmount;
+            uint32 validTo;
+            bytes32 appData;
+            uint256 feeAmount;
+            string kind;
+            bool partiallyFillable;
+            string sellTokenBalance;
+            string buyTokenBalance;
+        }
+    }
+}
+
+pub mod uniswapx {
+    use super::sol;
+
+    sol! {
+        /// Permit2 transfer signed by the swapper, the UniswapX reactor is the spender and the order is the witness
+        #[derive(Debug, PartialEq, Eq)]
+        struct PermitWitnessTransferFrom {
+            TokenPermissions permitted;
+            address spender;
+            uint256 nonce;
+            uint256 deadline;
+            ExclusiveDutchOrder witness;
+        }
+
+        #[derive(Debug, PartialEq, Eq)]
+        struct TokenPermissions {
+            address token;
+            uint256 amount;
+        }
+
+        #[derive(Debug, PartialEq, Eq)]
+        struct ExclusiveDutchOrder {
+            OrderInfo info;
+            uint256 decayStartTime;
+            uint256 decayEndTime;
+            address exclusiveFiller;
+            uint256 exclusivityOverrideBps;
+            DutchInput input;
+            DutchOutput[] outputs;
+        }
+
+        #[derive(Debug, PartialEq, Eq)]
+        struct OrderInfo {
+            address reactor;
+            address swapper;
+            uint256 nonce;
+            uint256 deadline;
+            address additionalValidationContract;
+            bytes additionalValidationData;
+        }
+
+        #[derive(Debug, PartialEq, Eq)]
+        struct DutchInput {
+            address token;
+            uint256 startAmount;
+            uint256 endAmount;
+        }
+
+        #[derive(Debug, PartialEq, Eq)]
+        struct DutchOutput {
+            address token;
+            uint256 startAmount;
+            uint256 endAmount;
+            address recipient;
+        }
+    }
+
+    impl PermitWitnessTransferFrom {
+        /// Permit2 transfer of the order input to the reactor, signed by the swapper of the order
+        pub fn from_order(order: ExclusiveDutchOrder) -> Self {
+            Self {
+                permitted: TokenPermissions { token: order.input.token, amount: order.input.endAmount },
+                spender: order.info.reactor,
+                nonce: order.info.nonce,
+                deadline: order.info.deadline,
+                witness: order,
+            }
+        }
+    }
+}
+
+/// Venues filling signed intents
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize)]
+pub enum IntentVenue {
+    #[serde(rename = "uniswapx")]
+    UniswapX,
+    #[serde(rename = "cow")]
+    Cow,
+}
+
+impl Display for IntentVenue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UniswapX => write!(f, "uniswapx"),
+            Self::Cow => write!(f, "cow"),
+        }
+    }
+}
+
+/// EIP-712 domains of the intent venues per chain
+#[derive(Clone, Debug)]
+pub struct Eip712Domains {
+    domains: HashMap<(IntentVenue, u64), Eip712Domain>,
+}
+
+impl Default for Eip712Domains {
+    fn default() -> Self {
+        let mut domains = HashMap::new();
+        for chain_id in PERMIT2_CHAIN_IDS {
+            domains.insert(
+                (IntentVenue::UniswapX, chain_id),
+                Eip712Domain::new(Some(Cow::Borrowed("Permit2")), None, Some(U256::from(chain_id)), Some(PERMIT2_ADDRESS), None),
+            );
+        }
+        for chain_id in COW_CHAIN_IDS {
+            domains.insert(
+                (IntentVenue::Cow, chain_id),
+                Eip712Domain::new(
+                    Some(Cow::Borrowed("Gnosis Protocol")),
+                    Some(Cow::Borrowed("v2")),
+                    Some(U256::from(chain_id)),
+                    Some(COW_SETTLEMENT_ADDRESS),
+                    None,
+                ),
+            );
+        }
+        Self { domains }
+    }
+}
+
+impl Eip712Domains {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add or replace the domain of the venue, e.g. for a chain or a deployment not known by default
+    pub fn with_domain(self, venue: IntentVenue, chain_id: u64, domain: Eip712Domain) -> Self {
+        let mut domains = self.domains;
+        domains.insert((venue, chain_id), domain);
+        Self { domains }
+    }
+
+    pub fn get(&self, venue: IntentVenue, chain_id: u64) -> Option<&Eip712Domain> {
+        self.domains.get(&(venue, chain_id))
+    }
+
+    /// Sign the typed data in the domain of the venue
+    pub fn sign<T: SolStruct>(
+        &self,
+        venue: IntentVenue,
+        chain_id: u64,
+        signer: &dyn LoomTxSigner<LoomDataTypesEthereum>,
+        data: &T,
+    ) -> Result<PrimitiveSignature> {
+        let domain = self.get(venue, chain_id).ok_or_else(|| eyre!("EIP712_DOMAIN_NOT_FOUND"))?;
+        sign_typed_data(signer, domain, data)
+    }
+}
+
+/// Hash to sign for the struct hash in the domain, `keccak256(0x1901 ‖ domainSeparator ‖ structHash)`
+pub fn eip712_signing_hash(domain: &Eip712Domain, struct_hash: B256) -> B256 {
+    let mut digest_input = [0u8; 66];
+    digest_input[0..2].copy_from_slice(&[0x19, 0x01]);
+    digest_input[2..34].copy_from_slice(domain.separator().as_slice());
+    digest_input[34..66].copy_from_slice(struct_hash.as_slice());
+    keccak256(digest_input)
+}
+
+pub fn sign_typed_data<T: SolStruct>(
+    signer: &dyn LoomTxSigner<LoomDataTypesEthereum>,
+    domain: &Eip712Domain,
+    data: &T,
+) -> Result<PrimitiveSignature> {
+    signer.sign_hash_sync(&data.eip712_signing_hash(domain))
+}
+
+/// Sign a struct hash computed with the exact type string of the verifying contract, e.g. a Permit2 witness
+pub fn sign_struct_hash(
+    signer: &dyn LoomTxSigner<LoomDataTypesEthereum>,
+    domain: &Eip712Domain,
+    struct_hash: B256,
+) -> Result<PrimitiveSignature> {
+    signer.sign_hash_sync(&eip712_signing_hash(domain, struct_hash))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::TxSigners;
+    use loom_defi_address_book::TokenAddressEth;
+
+    #[test]
+    fn test_sign_cow_order() -> Result<()> {
+        let mut signers = TxSigners::new();
+        let signer = signers.add_testkey();
+
+        let order = cow::Order {
+            sellToken: TokenAddressEth::WETH,
+            buyToken: TokenAddressEth::USDC,
+            receiver: signer.address(),
+            sellAmount: U256::from(10).pow(U256::from(18)),
+            buyAmount: U256::from(2_000_000_000u64),
+            validTo: 1_700_000_000,
+            appData: B256::ZERO,
+            feeAmount: U256::ZERO,
+            kind: "sell".to_string(),
+            partiallyFillable: false,
+            sellTokenBalance: "erc20".to_string(),
+            buyTokenBalance: "erc20".to_string(),
+        };
+
+        let domains = Eip712Domains::new();
+        let domain = domains.get(IntentVenue::Cow, 1).unwrap();
+        let signature = domains.sign(IntentVenue::Cow, 1, &signer, &order)?;
+
+        let signing_hash = order.eip712_signing_hash(domain);
+        assert_eq!(signing_hash, eip712_signing_hash(domain, order.eip712_hash_struct()));
+        assert_eq!(signature.recover_address_from_prehash(&signing_hash)?, signer.address());
+        assert!(domains.sign(IntentVenue::Cow, 10, &signer, &order).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_sign_uniswapx_order() -> Result<()> {
+        let mut signers = TxSigners::new();
+        let signer = signers.add_testkey();
+
+        let order = uniswapx::ExclusiveDutchOrder {
+            info: uniswapx::OrderInfo {
+                reactor: address!("6000da47483062A0D734Ba3dc7576Ce6A0B645C4"),
+                swapper: signer.address(),
+                nonce: U256::from(1),
+                deadline: U256::from(1_700_000_000u64),
+                additionalValidationContract: Address::ZERO,
+                additionalValidationData: Default::default(),
+            },
+            decayStartTime: U256::from(1_699_999_900u64),
+            decayEndTime: U256::from(1_700_000_000u64),
+            exclusiveFiller: Address::ZERO,
+            exclusivityOverrideBps: U256::ZERO,
+            input: uniswapx::DutchInput {
+                token: TokenAddressEth::WETH,
+                startAmount: U256::from(10).pow(U256::from(18)),
+                endAmount: U256::from(10).pow(U256::from(18)),
+            },
+            outputs: vec![uniswapx::DutchOutput {
+                token: TokenAddressEth::USDC,
+                startAmount: U256::from(2_000_000_000u64),
+                endAmount: U256::from(1_990_000_000u64),
+                recipient: signer.address(),
+            }],
+        };
+        let permit = uniswapx::PermitWitnessTransferFrom::from_order(order);
+        assert_eq!(permit.spender, permit.witness.info.reactor);
+
+        // Permit2 hashes the stub with the witness type string of the reactor
+        let type_string = "PermitWitnessTransferFrom(TokenPermissions permitted,address spender,uint256 nonce,uint256 deadline,\
+            ExclusiveDutchOrder witness)DutchInput(address token,uint256 startAmount,uint256 endAmount)\
+            DutchOutput(address token,uint256 startAmount,uint256 endAmount,address recipient)\
+            ExclusiveDutchOrder(OrderInfo info,uint256 decayStartTime,uint256 decayEndTime,address exclusiveFiller,\
+            uint256 exclusivityOverrideBps,DutchInput input,DutchOutput[] outputs)\
+            OrderInfo(address reactor,address swapper,uint256 nonce,uint256 deadline,address additionalValidationContract,\
+            bytes additionalValidationData)TokenPermissions(address token,uint256 amount)";
+        assert_eq!(permit.eip712_encode_type(), type_string);
+
+        let domains = Eip712Domains::new();
+        let domain = domains.get(IntentVenue::UniswapX, 1).unwrap();
+        assert_eq!(domain.verifying_contract, Some(PERMIT2_ADDRESS));
+        let signature = domains.sign(IntentVenue::UniswapX, 1, &signer, &permit)?;
+        assert_eq!(signature.recover_address_from_prehash(&permit.eip712_signing_hash(domain))?, signer.address());
+        Ok(())
+    }
+}
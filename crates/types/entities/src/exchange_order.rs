@@ -0,0 +1,127 @@
+use std::sync::Arc;
+
+use alloy_primitives::U256;
+use eyre::{eyre, Report, Result};
+use loom_types_blockchain::{LoomDataTypes, LoomDataTypesEthereum};
+use revm::primitives::Env;
+use revm::DatabaseRef;
+use tracing::debug;
+
+use crate::{Market, PoolId, Swap, SwapAmountType, SwapLine, SwapPath, Token};
+
+/// User specified order to sell `amount_in` of `token_in` for at least `min_amount_out` of `token_out`.
+#[derive(Clone, Debug)]
+pub struct ExchangeOrder<LDT: LoomDataTypes = LoomDataTypesEthereum> {
+    pub token_in: Arc<Token<LDT>>,
+    pub token_out: Arc<Token<LDT>>,
+    pub amount_in: U256,
+    pub min_amount_out: U256,
+    /// Receiver of `token_out`, multicaller if not set
+    pub recipient: Option<LDT::Address>,
+}
+
+impl<LDT: LoomDataTypes> ExchangeOrder<LDT> {
+    pub fn new(token_in: Arc<Token<LDT>>, token_out: Arc<Token<LDT>>, amount_in: U256, min_amount_out: U256) -> Self {
+        Self { token_in, token_out, amount_in, min_amount_out, recipient: None }
+    }
+
+    pub fn with_recipient(self, recipient: LDT::Address) -> Self {
+        Self { recipient: Some(recipient), ..self }
+    }
+
+    /// Minimal accepted price of token_in denominated in token_out
+    pub fn min_price(&self) -> f64 {
+        let amount_in = self.token_in.to_float(self.amount_in);
+        if amount_in == 0.0 {
+            return 0.0;
+        }
+        self.token_out.to_float(self.min_amount_out) / amount_in
+    }
+
+    fn enabled_pools(market: &Market<LDT>, token_from: &LDT::Address, token_to: &LDT::Address) -> Vec<PoolId<LDT>> {
+        market
+            .get_token_token_pools(token_from, token_to)
+            .map(|pools| pools.iter().filter(|pool_id| !market.is_pool_disabled(pool_id)).cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Direct and two-hop paths from token_in to token_out through the market pools
+    pub fn swap_paths(&self, market: &Market<LDT>) -> Vec<SwapPath<LDT>> {
+        let token_in_address = self.token_in.get_address();
+        let token_out_address = self.token_out.get_address();
+        let mut paths = Vec::new();
+
+        for pool_id in Self::enabled_pools(market, &token_in_address, &token_out_address) {
+            if let Some(pool) = market.get_pool(&pool_id) {
+                paths.push(SwapPath::new_swap(self.token_in.clone(), self.token_out.clone(), pool.clone()));
+            }
+        }
+
+        let Some(middle_tokens) = market.get_token_tokens(&token_in_address) else {
+            return paths;
+        };
+
+        for middle_token_address in middle_tokens.iter().filter(|address| **address != token_out_address) {
+            let Some(middle_token) = market.get_token(middle_token_address) else {
+                continue;
+            };
+            let second_pools = Self::enabled_pools(market, middle_token_address, &token_out_address);
+            if second_pools.is_empty() {
+                continue;
+            }
+
+            for first_pool_id in Self::enabled_pools(market, &token_in_address, middle_token_address) {
+                let Some(first_pool) = market.get_pool(&first_pool_id) else {
+                    continue;
+                };
+                for second_pool_id in second_pools.iter() {
+                    let Some(second_pool) = market.get_pool(second_pool_id) else {
+                        continue;
+                    };
+                    let mut path = SwapPath::new_swap(self.token_in.clone(), middle_token.clone(), first_pool.clone());
+                    if path.push_swap_hope(middle_token.clone(), self.token_out.clone(), second_pool.clone()).is_ok() {
+                        paths.push(path);
+                    }
+                }
+            }
+        }
+
+        paths
+    }
+
+    /// Calculate all candidate paths and return the swap line with the best output satisfying the minimal price
+    pub fn build_swap_line<DB: DatabaseRef<Error = Report>>(&self, market: &Market<LDT>, state: &DB, env: Env) -> Result<SwapLine<LDT>> {
+        let mut best_swap_line: Option<SwapLine<LDT>> = None;
+
+        for path in self.swap_paths(market) {
+            let mut swap_line = SwapLine::from(path);
+            match swap_line.calculate_with_in_amount(state, env.clone(), self.amount_in) {
+                Ok((amount_out, gas_used, calculation_results)) => {
+                    let best_amount_out = best_swap_line.as_ref().map(|line| line.amount_out.unwrap_or_default()).unwrap_or_default();
+                    if amount_out > best_amount_out {
+                        swap_line.amount_in = SwapAmountType::Set(self.amount_in);
+                        swap_line.amount_out = SwapAmountType::Set(amount_out);
+                        swap_line.gas_used = Some(gas_used);
+                        swap_line.calculation_results = calculation_results;
+                        swap_line.swap_to = self.recipient;
+                        swap_line.min_amount_out = Some(self.min_amount_out);
+                        best_swap_line = Some(swap_line);
+                    }
+                }
+                Err(e) => {
+                    debug!("Exchange order path calculation failed : {}", e.msg);
+                }
+            }
+        }
+
+        let swap_line = best_swap_line.ok_or_else(|| eyre!("NO_EXCHANGE_PATH"))?;
+        if swap_line.amount_out.unwrap_or_default() < self.min_amount_out {
+            return Err(eyre!("MIN_AMOUNT_OUT_NOT_REACHED"));
+        }
+        Ok(swap_line)
+    }
+
+    pub fn build_swap<DB: DatabaseRef<Error = Report>>(&self, market: &Market<LDT>, state: &DB, env: Env) -> Result<Swap<LDT>> {
+        Ok(Swap::ExchangeSwapLine(self.build_swap_line(market, state, env)?))
+    }
+}
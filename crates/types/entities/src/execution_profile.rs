@@ -0,0 +1,85 @@
+use alloy_primitives::U256;
+use std::time::Duration;
+
+/// Interval of the preconfirmed sub-blocks of chains streaming Flashblocks
+pub const FLASHBLOCK_INTERVAL: Duration = Duration::from_millis(200);
+
+/// How swap transactions reach the block of a chain
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ExecutionProfile {
+    /// Bundles sent to block builders, the builder is paid by tips from the multicaller
+    #[default]
+    BundleTips,
+    /// Single transactions ordered by the sequencer by priority fee, there is no builder to tip
+    SequencerPriorityFee,
+    /// Single transactions sent to the sequencer streaming Flashblocks, there is no builder to tip
+    Flashblocks,
+}
+
+impl ExecutionProfile {
+    /// Profile of the chain, chains with builder auctions and unknown chains use bundles with tips
+    pub fn for_chain_id(chain_id: u64) -> Self {
+        match chain_id {
+            // Arbitrum One, Nova and Sepolia, Optimism
+            42161 | 42170 | 421614 | 10 => Self::SequencerPriorityFee,
+            // Base, Base Sepolia and Unichain
+            8453 | 84532 | 130 => Self::Flashblocks,
+            _ => Self::BundleTips,
+        }
+    }
+
+    /// Transactions are sent as bundles to builders
+    pub fn uses_bundles(&self) -> bool {
+        matches!(self, Self::BundleTips)
+    }
+
+    /// Profit is shared with the block builder by tips encoded into the swap
+    pub fn pays_tips(&self) -> bool {
+        matches!(self, Self::BundleTips)
+    }
+
+    /// Interval of the preconfirmed sub-blocks the sequencer builds the block from, `None` if blocks are built at once
+    pub fn sub_block_interval(&self) -> Option<Duration> {
+        match self {
+            Self::Flashblocks => Some(FLASHBLOCK_INTERVAL),
+            _ => None,
+        }
+    }
+
+    /// Priority fee per gas bidding the share of the profit above the gas cost a builder would be tipped, `None` if the
+    /// builder is paid by tips. `tips_pct` is in basis points
+    pub fn priority_fee_per_gas(&self, profit_eth: U256, gas_cost: U256, gas_used: u64, tips_pct: u32) -> Option<u64> {
+        if self.pays_tips() {
+            return None;
+        }
+        let bid = profit_eth.saturating_sub(gas_cost) * U256::from(tips_pct.min(10000)) / U256::from(10000);
+        Some((bid / U256::from(gas_used.max(1))).saturating_to())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_for_chain_id() {
+        assert_eq!(ExecutionProfile::for_chain_id(1), ExecutionProfile::BundleTips);
+        assert_eq!(ExecutionProfile::for_chain_id(42161), ExecutionProfile::SequencerPriorityFee);
+        assert_eq!(ExecutionProfile::for_chain_id(8453), ExecutionProfile::Flashblocks);
+        assert!(!ExecutionProfile::for_chain_id(8453).pays_tips());
+        assert_eq!(ExecutionProfile::for_chain_id(8453).sub_block_interval(), Some(FLASHBLOCK_INTERVAL));
+        assert_eq!(ExecutionProfile::for_chain_id(42161).sub_block_interval(), None);
+    }
+
+    #[test]
+    fn test_priority_fee_per_gas() {
+        let profit_eth = U256::from(1_000_000_000_000_000u64);
+        let gas_cost = U256::from(200_000_000_000_000u64);
+
+        assert_eq!(ExecutionProfile::BundleTips.priority_fee_per_gas(profit_eth, gas_cost, 200_000, 5000), None);
+        // half of 0.0008 eth over 200k gas
+        assert_eq!(ExecutionProfile::SequencerPriorityFee.priority_fee_per_gas(profit_eth, gas_cost, 200_000, 5000), Some(2_000_000_000));
+        assert_eq!(ExecutionProfile::Flashblocks.priority_fee_per_gas(profit_eth, gas_cost, 200_000, 20000), Some(4_000_000_000));
+        assert_eq!(ExecutionProfile::Flashblocks.priority_fee_per_gas(gas_cost, profit_eth, 200_000, 5000), Some(0));
+    }
+}
@@ -0,0 +1,85 @@
+use alloy_primitives::U256;
+
+const BPS_DENOMINATOR: u64 = 10_000;
+// Balancer vault flash loan protocol fee, currently not charged
+const BALANCER_FLASH_LOAN_FEE_BPS: u64 = 0;
+const AAVE_V3_FLASH_LOAN_FEE_BPS: u64 = 5;
+
+/// Source of the swap input amount.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum FundingMode {
+    /// Input is borrowed from a pool of the path and repaid by the swap itself.
+    /// The pool fee is already part of the swap amount calculation.
+    #[default]
+    FlashSwap,
+    /// Balancer vault flash loan
+    BalancerFlashLoan,
+    /// Aave V3 flash loan
+    AaveFlashLoan,
+    /// Token balance of the multicaller
+    Balance,
+}
+
+impl FundingMode {
+    /// Fee charged on top of the borrowed amount in basis points
+    pub fn fee_bps(&self) -> u64 {
+        match self {
+            Self::FlashSwap | Self::Balance => 0,
+            Self::BalancerFlashLoan => BALANCER_FLASH_LOAN_FEE_BPS,
+            Self::AaveFlashLoan => AAVE_V3_FLASH_LOAN_FEE_BPS,
+        }
+    }
+
+    /// Fee to repay for borrowing `amount`, rounded up
+    pub fn fee(&self, amount: U256) -> U256 {
+        let fee_bps = self.fee_bps();
+        if fee_bps == 0 {
+            return U256::ZERO;
+        }
+        (amount.saturating_mul(U256::from(fee_bps)) + U256::from(BPS_DENOMINATOR - 1)) / U256::from(BPS_DENOMINATOR)
+    }
+
+    /// Mode with the lowest fee for `amount`, the first one wins on equal fees
+    pub fn cheapest(modes: &[FundingMode], amount: U256) -> Option<FundingMode> {
+        modes.iter().copied().min_by_key(|mode| mode.fee(amount))
+    }
+
+    /// Balance funding when the `inventory` held by the multicaller covers `amount`, it saves the flash callback gas.
+    /// The cheapest of `modes` otherwise
+    pub fn with_inventory(modes: &[FundingMode], amount: U256, inventory: U256) -> Option<FundingMode> {
+        if !amount.is_zero() && inventory >= amount {
+            Some(Self::Balance)
+        } else {
+            Self::cheapest(modes, amount)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_fee() {
+        assert_eq!(FundingMode::FlashSwap.fee(U256::from(1_000_000)), U256::ZERO);
+        assert_eq!(FundingMode::AaveFlashLoan.fee(U256::from(1_000_000)), U256::from(500));
+        // rounded up
+        assert_eq!(FundingMode::AaveFlashLoan.fee(U256::from(1)), U256::from(1));
+    }
+
+    #[test]
+    fn test_cheapest() {
+        let modes = [FundingMode::AaveFlashLoan, FundingMode::BalancerFlashLoan];
+        assert_eq!(FundingMode::cheapest(&modes, U256::from(1_000_000)), Some(FundingMode::BalancerFlashLoan));
+        assert_eq!(FundingMode::cheapest(&[], U256::from(1_000_000)), None);
+    }
+
+    #[test]
+    fn test_with_inventory() {
+        let modes = [FundingMode::FlashSwap, FundingMode::BalancerFlashLoan];
+        let amount = U256::from(1_000_000);
+        assert_eq!(FundingMode::with_inventory(&modes, amount, amount), Some(FundingMode::Balance));
+        assert_eq!(FundingMode::with_inventory(&modes, amount, amount - U256::from(1)), Some(FundingMode::FlashSwap));
+        assert_eq!(FundingMode::with_inventory(&modes, U256::ZERO, amount), Some(FundingMode::FlashSwap));
+    }
+}
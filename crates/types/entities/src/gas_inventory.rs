@@ -0,0 +1,226 @@
+use std::collections::HashMap;
+
+use alloy_primitives::utils::parse_units;
+use alloy_primitives::{Address, U256};
+use serde::Deserialize;
+
+use crate::Market;
+
+fn parse_eth(value: &str) -> U256 {
+    parse_units(value, "ether").map(|value| value.get_absolute()).unwrap_or_default()
+}
+
+/// Conversion of token profits into ETH keeping the signers funded for gas
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct GasHedgeConfig {
+    /// ETH balance of a signer below which token profits are converted, in ETH
+    pub floor_eth: String,
+    /// ETH balance a conversion refills the signer to, in ETH
+    pub target_eth: String,
+    /// Largest share of the unconverted profit of a token sold by one conversion, in basis points
+    pub max_portion_bps: u32,
+    /// Smallest conversion in ETH, smaller ones are not worth their gas
+    pub min_amount_eth: String,
+    /// Largest shortfall of the conversion output below the simulated output, in basis points
+    pub max_slippage_bps: u32,
+    /// Blocks between balance checks
+    pub interval_blocks: u64,
+}
+
+impl Default for GasHedgeConfig {
+    fn default() -> Self {
+        Self {
+            floor_eth: "0.05".to_string(),
+            target_eth: "0.2".to_string(),
+            max_portion_bps: 5000,
+            min_amount_eth: "0.01".to_string(),
+            max_slippage_bps: 100,
+            interval_blocks: 10,
+        }
+    }
+}
+
+impl GasHedgeConfig {
+    pub fn floor_eth(&self) -> U256 {
+        parse_eth(&self.floor_eth)
+    }
+
+    pub fn target_eth(&self) -> U256 {
+        parse_eth(&self.target_eth)
+    }
+
+    pub fn min_amount_eth(&self) -> U256 {
+        parse_eth(&self.min_amount_eth)
+    }
+
+    /// Smallest output of a conversion simulated with `amount_out`
+    pub fn min_amount_out(&self, amount_out: U256) -> U256 {
+        amount_out - amount_out * U256::from(self.max_slippage_bps.min(10000)) / U256::from(10000)
+    }
+}
+
+/// Profits of an included bundle in its profit tokens next to the ETH it spends on gas and tips
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BundleGasAccounting {
+    pub block_number: u64,
+    /// Profits by profit token in token units
+    pub profits: Vec<(Address, U256)>,
+    pub profit_eth: U256,
+    /// Gas limit at the base fee and priority fee of the target block
+    pub gas_cost_eth: U256,
+    pub tips_eth: U256,
+}
+
+/// Conversion of a token profit into ETH for the gas inventory
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HedgeOrder {
+    pub token: Address,
+    pub amount_in: U256,
+    /// ETH value of the amount in at the token price
+    pub amount_eth: U256,
+}
+
+/// Profits of included bundles by token and the ETH spent on gas and tips. Token profits are not spendable as gas, they
+/// are drawn down by the conversions into ETH.
+#[derive(Clone, Debug, Default)]
+pub struct GasLedger {
+    token_profits: HashMap<Address, U256>,
+    bundles: u64,
+    profit_eth: U256,
+    gas_cost_eth: U256,
+    tips_eth: U256,
+}
+
+impl GasLedger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&mut self, bundle: &BundleGasAccounting) {
+        for (token, profit) in bundle.profits.iter() {
+            *self.token_profits.entry(*token).or_default() += *profit;
+        }
+        self.bundles += 1;
+        self.profit_eth += bundle.profit_eth;
+        self.gas_cost_eth += bundle.gas_cost_eth;
+        self.tips_eth += bundle.tips_eth;
+    }
+
+    /// Profit of the token not converted into ETH yet
+    pub fn token_profit(&self, token: &Address) -> U256 {
+        self.token_profits.get(token).cloned().unwrap_or_default()
+    }
+
+    pub fn bundles(&self) -> u64 {
+        self.bundles
+    }
+
+    pub fn profit_eth(&self) -> U256 {
+        self.profit_eth
+    }
+
+    pub fn gas_cost_eth(&self) -> U256 {
+        self.gas_cost_eth
+    }
+
+    pub fn tips_eth(&self) -> U256 {
+        self.tips_eth
+    }
+
+    /// ETH spent on gas and tips
+    pub fn spent_eth(&self) -> U256 {
+        self.gas_cost_eth + self.tips_eth
+    }
+
+    pub fn on_hedged(&mut self, token: &Address, amount: U256) {
+        if let Some(profit) = self.token_profits.get_mut(token) {
+            *profit = profit.saturating_sub(amount);
+        }
+    }
+
+    /// Conversion refilling a signer with an ETH balance below the floor, from the token with the largest profit value.
+    /// WETH profits are not converted, they pay the tips of the bundles
+    pub fn hedge_order(&self, config: &GasHedgeConfig, eth_balance: U256, market: &Market) -> Option<HedgeOrder> {
+        if eth_balance >= config.floor_eth() {
+            return None;
+        }
+        let needed_eth = config.target_eth().saturating_sub(eth_balance);
+
+        self.token_profits
+            .iter()
+            .filter_map(|(address, profit)| {
+                let token = market.get_token(address).filter(|token| !token.is_weth())?;
+                let max_amount = *profit * U256::from(config.max_portion_bps) / U256::from(10000);
+                let max_amount_eth = token.calc_eth_value(max_amount)?;
+                let order = if max_amount_eth > needed_eth {
+                    let amount_in = token.calc_token_value_from_eth(needed_eth)?.min(max_amount);
+                    HedgeOrder { token: *address, amount_in, amount_eth: needed_eth }
+                } else {
+                    HedgeOrder { token: *address, amount_in: max_amount, amount_eth: max_amount_eth }
+                };
+                Some(order)
+            })
+            .filter(|order| !order.amount_in.is_zero() && order.amount_eth >= config.min_amount_eth())
+            .max_by_key(|order| order.amount_eth)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Token;
+    use alloy_primitives::utils::Unit;
+    use loom_defi_address_book::TokenAddressEth;
+
+    const ONE_ETHER: U256 = Unit::ETHER.wei_const();
+
+    fn bundle(profit_token: Address, profit: U256, profit_eth: U256) -> BundleGasAccounting {
+        BundleGasAccounting {
+            block_number: 1,
+            profits: vec![(profit_token, profit)],
+            profit_eth,
+            gas_cost_eth: U256::from(10),
+            tips_eth: U256::from(20),
+        }
+    }
+
+    #[test]
+    fn test_hedge_order() {
+        let mut market = Market::default();
+        let usdc = Token::new_with_data(TokenAddressEth::USDC, None, None, Some(6), true, false);
+        // 2000 USDC per ETH
+        usdc.set_eth_price(Some(U256::from(2_000_000_000u64)));
+        market.add_token(usdc);
+        market.add_token(Token::new(TokenAddressEth::WETH));
+
+        let mut ledger = GasLedger::new();
+        // 1000 USDC
+        ledger.record(&bundle(TokenAddressEth::USDC, U256::from(1_000_000_000u64), ONE_ETHER / U256::from(2)));
+        ledger.record(&bundle(TokenAddressEth::WETH, ONE_ETHER, ONE_ETHER));
+        assert_eq!(ledger.bundles(), 2);
+        assert_eq!(ledger.spent_eth(), U256::from(60));
+
+        let config = GasHedgeConfig::default();
+        assert_eq!(ledger.hedge_order(&config, config.floor_eth(), &market), None);
+        assert_eq!(config.min_amount_out(U256::from(10000)), U256::from(9900));
+
+        // 0.16 ETH needed, 500 USDC at most by a single conversion
+        let order = ledger.hedge_order(&config, ONE_ETHER * U256::from(4) / U256::from(100), &market).unwrap();
+        assert_eq!(order.token, TokenAddressEth::USDC);
+        assert_eq!(order.amount_eth, ONE_ETHER * U256::from(16) / U256::from(100));
+        assert_eq!(order.amount_in, U256::from(320_000_000u64));
+
+        let order = ledger.hedge_order(&config, U256::ZERO, &market).unwrap();
+        assert_eq!(order.amount_in, U256::from(400_000_000u64));
+        let config = GasHedgeConfig { target_eth: "1".to_string(), ..GasHedgeConfig::default() };
+        let order = ledger.hedge_order(&config, U256::ZERO, &market).unwrap();
+        assert_eq!(order.amount_in, U256::from(500_000_000u64));
+        assert_eq!(order.amount_eth, ONE_ETHER / U256::from(4));
+
+        ledger.on_hedged(&TokenAddressEth::USDC, U256::from(990_000_000u64));
+        assert_eq!(ledger.token_profit(&TokenAddressEth::USDC), U256::from(10_000_000u64));
+        // 5 USDC is below the minimal conversion
+        assert_eq!(ledger.hedge_order(&config, U256::ZERO, &market), None);
+    }
+}
@@ -0,0 +1,94 @@
+use std::collections::{HashSet, VecDeque};
+
+use serde::Serialize;
+
+use crate::PoolClass;
+
+// Maximum number of toggles kept in the audit log, the oldest are dropped first
+const MAX_LOG_RECORDS: usize = 1000;
+
+/// Toggle of a pool class by an operator
+#[derive(Clone, Debug, Serialize)]
+pub struct KillSwitchRecord {
+    pub pool_class: PoolClass,
+    pub disabled: bool,
+    pub operator: String,
+    pub reason: Option<String>,
+    /// Unix timestamp in seconds
+    pub timestamp: u64,
+}
+
+/// Pool classes disabled at runtime by operators, e.g. during a protocol incident. Paths through pools of a disabled
+/// class are not searched and swaps through them are dropped before encoding and before signing
+#[derive(Clone, Debug, Default)]
+pub struct PoolClassKillSwitch {
+    disabled: HashSet<PoolClass>,
+    log: VecDeque<KillSwitchRecord>,
+}
+
+impl PoolClassKillSwitch {
+    /// Record the toggle, returns true if the state of the class changed
+    pub fn toggle(&mut self, record: KillSwitchRecord) -> bool {
+        let changed = if record.disabled { self.disabled.insert(record.pool_class) } else { self.disabled.remove(&record.pool_class) };
+        if self.log.len() >= MAX_LOG_RECORDS {
+            self.log.pop_front();
+        }
+        self.log.push_back(record);
+        changed
+    }
+
+    #[inline]
+    pub fn is_disabled(&self, pool_class: PoolClass) -> bool {
+        !self.is_empty() && self.disabled.contains(&pool_class)
+    }
+
+    /// No pool class is disabled
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.disabled.is_empty()
+    }
+
+    pub fn disabled(&self) -> Vec<PoolClass> {
+        self.disabled.iter().copied().collect()
+    }
+
+    /// Last toggles in order, including the ones not changing the state
+    pub fn log(&self) -> &VecDeque<KillSwitchRecord> {
+        &self.log
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn record(pool_class: PoolClass, disabled: bool) -> KillSwitchRecord {
+        KillSwitchRecord { pool_class, disabled, operator: "ops".to_string(), reason: None, timestamp: 0 }
+    }
+
+    #[test]
+    fn test_toggle() {
+        let mut kill_switch = PoolClassKillSwitch::default();
+        assert!(kill_switch.toggle(record(PoolClass::Curve, true)));
+        assert!(!kill_switch.toggle(record(PoolClass::Curve, true)));
+        assert!(kill_switch.is_disabled(PoolClass::Curve));
+        assert!(!kill_switch.is_disabled(PoolClass::UniswapV2));
+
+        assert!(kill_switch.toggle(record(PoolClass::Curve, false)));
+        assert!(!kill_switch.is_disabled(PoolClass::Curve));
+        assert_eq!(kill_switch.log().len(), 3);
+    }
+
+    #[test]
+    fn test_log_bounded() {
+        let mut kill_switch = PoolClassKillSwitch::default();
+        for idx in 0..MAX_LOG_RECORDS + 2 {
+            kill_switch.toggle(record(PoolClass::Curve, idx % 2 == 0));
+        }
+        assert_eq!(kill_switch.log().len(), MAX_LOG_RECORDS);
+        // the two oldest toggles are dropped
+        assert!(kill_switch.log().front().unwrap().disabled);
+        assert!(!kill_switch.log().back().unwrap().disabled);
+        assert!(!kill_switch.is_disabled(PoolClass::Curve));
+    }
+}
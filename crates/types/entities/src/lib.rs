@@ -3,32 +3,68 @@
 extern crate core;
 
 pub use account_nonce_balance::{AccountNonceAndBalanceState, AccountNonceAndBalances};
-pub use block_history::{BlockHistory, BlockHistoryEntry, BlockHistoryManager, BlockHistoryState};
-pub use calculation_result::CalculationResult;
+#[cfg(feature = "provider")]
+pub use block_history::BlockHistoryManager;
+pub use block_history::{BlockHistory, BlockHistoryEntry, BlockHistoryState};
+pub use calculation_result::{hop_amount_out_bounds, CalculationResult, HOP_SLIPPAGE_BPS};
+pub use chain_preset::{ChainPreset, FlashLoanProvider};
+#[cfg(feature = "provider")]
 pub use datafetcher::{DataFetcher, FetchState};
+pub use eip712::{
+    cow, eip712_signing_hash, sign_struct_hash, sign_typed_data, Eip712Domains, IntentVenue, COW_SETTLEMENT_ADDRESS, PERMIT2_ADDRESS,
+};
+pub use exchange_order::ExchangeOrder;
+pub use execution_profile::{ExecutionProfile, FLASHBLOCK_INTERVAL};
+pub use funding::FundingMode;
+pub use gas_inventory::{BundleGasAccounting, GasHedgeConfig, GasLedger, HedgeOrder};
 pub use keystore::KeyStore;
+pub use kill_switch::{KillSwitchRecord, PoolClassKillSwitch};
 pub use latest_block::LatestBlock;
 pub use market::Market;
+pub use market_error::MarketError;
+pub use market_snapshot::{
+    path_key, MarketSnapshot, MarketSnapshotDiff, PathScoreChange, PathSnapshot, PoolSnapshot, PoolToggle, TokenClassification,
+    TokenReclassification,
+};
 pub use market_state::MarketState;
+pub use market_view::MarketView;
 pub use mock_pool::MockPool;
-pub use pool::{get_protocol_by_factory, Pool, PoolAbiEncoder, PoolClass, PoolProtocol, PoolWrapper, PreswapRequirement};
+pub use path_build::PathBuildConfig;
+pub use path_gas::{default_pool_class_gas, PathGasBudget, PoolClassGasCosts, MAX_PATH_HOPS, UNBUDGETED_PATH_HOPS};
+pub use pool::{
+    get_protocol_by_factory, CallbackStyle, Pool, PoolAbiEncoder, PoolClass, PoolClassCapabilities, PoolProtocol, PoolWrapper, PreswapKind,
+    PreswapRequirement,
+};
+pub use pool_events::{PoolEvent, PoolEventLog, PoolEventRecord, DEFAULT_POOL_EVENTS_CAPACITY};
 pub use pool_id::PoolId;
+#[cfg(feature = "provider")]
 pub use pool_loader::{PoolLoader, PoolLoaders};
+pub use price_graph::{PriceGraph, DEFAULT_DUST_USD, DEFAULT_PRICE_GRAPH_DEPTH};
+pub use quoter::{Quote, Quoter};
+pub use risk::{RiskConfig, RiskScorer, TokenSafety};
+pub use score_adjustment::{ScoreAdjustment, ScoreAdjustments, SharedScoreAdjustments};
 pub use signers::{LoomTxSigner, TxSignerEth, TxSigners};
+pub use simulation_trace::{SimulationTrace, SimulationTraces, StorageWrite, TraceRequest, DEFAULT_SIMULATION_TRACES_CAPACITY};
+pub use sponsored_signer::{SponsoredTxSigner, SponsorshipMode};
+pub use strategy_namespace::{NamespaceConfig, NamespaceUsage, StrategyNamespaces};
 pub use swap::Swap;
 pub use swap_direction::SwapDirection;
 pub use swap_encoder::SwapEncoder;
-pub use swap_error::{EstimationError, SwapError};
+pub use swap_error::{tick_word_not_loaded, EstimationError, SwapError, NOT_PROFITABLE};
 pub use swap_line::{SwapAmountType, SwapLine};
 pub use swap_path::{SwapPath, SwapPaths};
-pub use swap_path_builder::build_swap_path_vec;
+pub use swap_path_builder::{build_pool_swap_path_vec, build_swap_path_vec, build_swap_path_vec_gas_budget};
 pub use swap_step::SwapStep;
+pub use tip_recipients::{SharedTipRecipients, TipRecipient, TipRecipients, TipRecipientsUpdate, MAX_TIP_RECIPIENTS_UPDATE_VALIDITY_SECS};
 pub use token::{Token, TokenWrapper};
 
 mod block_history;
 mod latest_block;
 mod market;
+mod market_error;
+mod market_snapshot;
 mod market_state;
+mod market_view;
 mod pool;
 mod swap_line;
 mod swap_path;
@@ -36,26 +72,48 @@ mod token;
 
 pub mod account_nonce_balance;
 pub mod required_state;
+pub mod state_proof;
 mod swap_path_builder;
 mod swap_step;
 
 mod signers;
+mod sponsored_signer;
 
 mod keystore;
+mod kill_switch;
 
 pub mod private;
 
 mod calculation_result;
+mod chain_preset;
+#[cfg(feature = "provider")]
 mod datafetcher;
+mod eip712;
+mod exchange_order;
+mod execution_profile;
+mod funding;
+mod gas_inventory;
 mod mock_pool;
 pub mod strategy_config;
+mod strategy_namespace;
 
+#[cfg(feature = "provider")]
 mod mock_pool_generic;
+mod path_build;
+mod path_gas;
 pub mod pool_config;
+mod pool_events;
 mod pool_id;
+#[cfg(feature = "provider")]
 mod pool_loader;
+mod price_graph;
+mod quoter;
+mod risk;
+mod score_adjustment;
+mod simulation_trace;
 mod swap;
 mod swap_direction;
 mod swap_encoder;
 mod swap_error;
+mod tip_recipients;
 pub mod tips;
@@ -2,40 +2,77 @@
 
 use alloy_primitives::map::HashMap;
 use alloy_primitives::U256;
-use eyre::{eyre, OptionExt, Result};
-use std::collections::BTreeMap;
+use eyre::Result;
+use std::collections::{BTreeMap, HashSet};
 use std::fmt::Display;
 use std::sync::Arc;
 use tracing::debug;
 
-use crate::{build_swap_path_vec, PoolId, SwapDirection};
-use crate::{PoolClass, PoolWrapper, Token};
+use crate::pool_config::PoolsLoadingConfig;
+use crate::MarketError;
+use crate::{build_pool_swap_path_vec, build_swap_path_vec, build_swap_path_vec_gas_budget, PoolClassGasCosts, PoolId, SwapDirection};
+use crate::{
+    KillSwitchRecord, PoolClass, PoolClassKillSwitch, PoolEvent, PoolEventLog, PoolProtocol, PoolWrapper, PriceGraph,
+    SharedScoreAdjustments, Token, TokenSafety,
+};
 use crate::{SwapPath, SwapPaths};
 use loom_types_blockchain::{LoomDataTypes, LoomDataTypesEthereum};
 
 /// The market struct contains all the pools and tokens.
 /// It keeps track if a pool is disabled or not and the swap paths.
+/// Pools, tokens, their indices and the paths are shared by clones until they are changed, so copies published as
+/// market views do not copy them.
 #[derive(Default, Clone)]
 pub struct Market<LDT: LoomDataTypes = LoomDataTypesEthereum> {
     // pool_address -> pool
-    pools: HashMap<PoolId<LDT>, PoolWrapper<LDT>>,
+    pools: Arc<HashMap<PoolId<LDT>, PoolWrapper<LDT>>>,
     // pool_address -> is_disabled
     pools_disabled: HashMap<PoolId<LDT>, bool>,
     // pool_address -> pool
     pools_manager_cells: HashMap<LDT::Address, HashMap<U256, PoolId<LDT>>>,
     // token_address -> token
-    tokens: HashMap<LDT::Address, Arc<Token<LDT>>>,
+    tokens: Arc<HashMap<LDT::Address, Arc<Token<LDT>>>>,
     // token_symbol -> token_address
     token_symbols: HashMap<String, LDT::Address>,
 
     // token_from -> token_to
-    token_tokens: HashMap<LDT::Address, Vec<LDT::Address>>,
+    token_tokens: Arc<HashMap<LDT::Address, Vec<LDT::Address>>>,
     // token_from -> token_to -> pool_addresses
-    token_token_pools: HashMap<LDT::Address, HashMap<LDT::Address, Vec<PoolId<LDT>>>>,
+    token_token_pools: Arc<HashMap<LDT::Address, HashMap<LDT::Address, Vec<PoolId<LDT>>>>>,
     // token -> pool
-    token_pools: HashMap<LDT::Address, Vec<PoolId<LDT>>>,
+    token_pools: Arc<HashMap<LDT::Address, Vec<PoolId<LDT>>>>,
     // swap_paths
-    swap_paths: SwapPaths<LDT>,
+    swap_paths: Arc<SwapPaths<LDT>>,
+    // enabled pool classes and factories
+    pools_config: PoolsLoadingConfig,
+    // token address -> safety verdict set by token checks
+    token_safety: HashMap<LDT::Address, TokenSafety>,
+    // proxy address -> implementation of tokens and pools that are upgradable proxies
+    proxy_implementations: HashMap<LDT::Address, LDT::Address>,
+    // proxy address -> new implementation not validated yet
+    proxy_upgrades: HashMap<LDT::Address, LDT::Address>,
+    // pool -> block the pool was first seen in new block logs
+    pools_first_seen: HashMap<PoolId<LDT>, u64>,
+    // pool class -> historical gas of a swap
+    pool_gas_costs: PoolClassGasCosts,
+    // (protocol, token0, token1) -> pools of the same protocol and pair, e.g. UniswapV3 fee tiers
+    pool_groups: HashMap<(PoolProtocol, LDT::Address, LDT::Address), Vec<PoolId<LDT>>>,
+    // prices of tokens in WETH and USD at the latest block
+    price_graph: PriceGraph<LDT>,
+    // (token0, token1) sorted -> pairs excluded from path building, e.g. tokens with transfer hooks griefing searchers
+    denied_pairs: HashSet<(LDT::Address, LDT::Address)>,
+    // pools excluded from path building, they are enabled again only if the config stops denying them
+    denied_pools: HashSet<PoolId<LDT>>,
+    // token address -> transfers call hooks of the sender or receiver (ERC-777, ERC-1363), checked on pool load
+    transfer_hooks: HashMap<LDT::Address, bool>,
+    // pool classes disabled by operators with the audit log of the toggles
+    kill_switch: PoolClassKillSwitch,
+    // score adjustments of pools and paths pushed by external signal sources, shared with the clones of the market
+    score_adjustments: SharedScoreAdjustments<LDT>,
+    // pools with their state written to the market state but not yet added with their paths
+    staged_pools: HashSet<PoolId<LDT>>,
+    // pools added, updated, disabled and removed by the mutations of the market
+    pool_events: PoolEventLog<LDT>,
 }
 
 impl<LDT: LoomDataTypes> Display for Market<LDT> {
@@ -72,7 +109,7 @@ impl<LDT: LoomDataTypes> Market<LDT> {
     pub fn add_token<T: Into<Arc<Token<LDT>>>>(&mut self, token: T) {
         let arc_token: Arc<Token<LDT>> = token.into();
         self.token_symbols.insert(arc_token.get_symbol(), arc_token.get_address());
-        self.tokens.insert(arc_token.get_address(), arc_token);
+        Arc::make_mut(&mut self.tokens).insert(arc_token.get_address(), arc_token);
     }
 
     /// Check if the token is a basic token.
@@ -84,7 +121,13 @@ impl<LDT: LoomDataTypes> Market<LDT> {
     /// Get a [`Token`] reference from the market by the address of the token or create a new one.
     #[inline]
     pub fn get_token_or_default(&self, address: &LDT::Address) -> Arc<Token<LDT>> {
-        self.tokens.get(address).map_or(Arc::new(Token::new(*address)), |t| t.clone())
+        self.tokens.get(address).cloned().unwrap_or_else(|| {
+            let mut token = Token::new(*address);
+            if self.has_transfer_hook(address) {
+                token.set_transfer_hook();
+            }
+            Arc::new(token)
+        })
     }
 
     /// Get a [`Token`] reference from the market by the address of the token.
@@ -99,38 +142,142 @@ impl<LDT: LoomDataTypes> Market<LDT> {
         self.token_symbols.get(symbol).and_then(|address| self.tokens.get(address).cloned())
     }
 
+    /// Mark the pool as partially applied. Its state is written to the market state before the pool and its paths are
+    /// added to the market, market views are not published until the pool is committed
+    pub fn stage_pool(&mut self, pool_id: PoolId<LDT>) {
+        self.staged_pools.insert(pool_id);
+    }
+
+    /// End the change of a staged pool, whether it was added or its load failed. Returns false if it was not staged
+    pub fn commit_pool(&mut self, pool_id: &PoolId<LDT>) -> bool {
+        self.staged_pools.remove(pool_id)
+    }
+
+    #[inline]
+    pub fn has_staged_pools(&self) -> bool {
+        !self.staged_pools.is_empty()
+    }
+
+    /// All tokens of the market by address
+    pub fn tokens(&self) -> &HashMap<LDT::Address, Arc<Token<LDT>>> {
+        &self.tokens
+    }
+
+    /// Set the pool classes and factories allowed for path building. Pools added afterwards are disabled
+    /// if they are not allowed.
+    pub fn set_pools_config(&mut self, pools_config: PoolsLoadingConfig) {
+        self.pools_config = pools_config;
+    }
+
+    pub fn pools_config(&self) -> &PoolsLoadingConfig {
+        &self.pools_config
+    }
+
     /// Add a new pool to the market if it does not exist or the class is unknown.
-    pub fn add_pool<T: Into<PoolWrapper<LDT>>>(&mut self, pool: T) -> Result<()> {
+    pub fn add_pool<T: Into<PoolWrapper<LDT>>>(&mut self, pool: T) -> Result<(), MarketError<LDT>> {
         let pool_contract = pool.into();
         let pool_address = pool_contract.get_pool_id();
 
-        if let Some(pool) = self.pools.get(&pool_address) {
-            return Err(eyre!("Pool already exists {:?}", pool.get_address()));
+        if self.pools.contains_key(&pool_address) {
+            return Err(MarketError::PoolAlreadyExists { pool: pool_address });
         }
 
         debug!("Adding pool {:?}", pool_address);
 
+        // indices shared with published views are copied before they are changed
+        let (token_token_pools, token_tokens, token_pools) =
+            (Arc::make_mut(&mut self.token_token_pools), Arc::make_mut(&mut self.token_tokens), Arc::make_mut(&mut self.token_pools));
         for swap_direction in pool_contract.get_swap_directions().into_iter() {
-            self.token_token_pools.entry(*swap_direction.from()).or_default().entry(*swap_direction.to()).or_default().push(pool_address);
-            self.token_tokens.entry(*swap_direction.from()).or_default().push(*swap_direction.to());
+            token_token_pools.entry(*swap_direction.from()).or_default().entry(*swap_direction.to()).or_default().push(pool_address);
+            token_tokens.entry(*swap_direction.from()).or_default().push(*swap_direction.to());
             // Swap directions are bidirectional, for that reason we only need to add the token_from_address
-            self.token_pools.entry(*swap_direction.from()).or_default().push(pool_address);
+            token_pools.entry(*swap_direction.from()).or_default().push(pool_address);
+        }
+
+        let disabled_by_config = !self.pools_config.is_pool_allowed(pool_contract.as_ref());
+        if disabled_by_config {
+            debug!("Pool {:?} disabled by config", pool_address);
+            self.pools_disabled.insert(pool_address, true);
+        }
+
+        if let Some(pool_group_key) = Self::pool_group_key(&pool_contract) {
+            self.pool_groups.entry(pool_group_key).or_default().push(pool_address);
         }
 
-        self.pools.insert(pool_address, pool_contract);
+        self.pool_events.push(PoolEvent::Added { pool_id: pool_address, pool_class: pool_contract.get_class() });
+        if disabled_by_config || self.denied_pools.contains(&pool_address) {
+            self.pool_events.push(PoolEvent::Disabled { pool_id: pool_address, disabled: true });
+        }
+        Arc::make_mut(&mut self.pools).insert(pool_address, pool_contract);
 
         Ok(())
     }
 
+    // Pools with more than two tokens are not grouped
+    fn pool_group_key(pool: &PoolWrapper<LDT>) -> Option<(PoolProtocol, LDT::Address, LDT::Address)> {
+        let tokens = pool.get_tokens();
+        if tokens.len() != 2 {
+            return None;
+        }
+        let (token0, token1) = if tokens[0] < tokens[1] { (tokens[0], tokens[1]) } else { (tokens[1], tokens[0]) };
+        Some((pool.get_protocol(), token0, token1))
+    }
+
+    /// Pools of the same protocol and token pair as the pool including the pool itself, e.g. UniswapV3 fee tiers
+    pub fn get_pool_group(&self, pool_id: &PoolId<LDT>) -> Option<&Vec<PoolId<LDT>>> {
+        let pool = self.pools.get(pool_id)?;
+        self.pool_groups.get(&Self::pool_group_key(pool)?)
+    }
+
+    /// Enabled pools of the group of the pool except the pool itself
+    pub fn get_pool_group_alternatives(&self, pool_id: &PoolId<LDT>) -> Vec<PoolWrapper<LDT>> {
+        let Some(pool_group) = self.get_pool_group(pool_id) else {
+            return Vec::new();
+        };
+        pool_group
+            .iter()
+            .filter(|member_id| *member_id != pool_id && !self.is_pool_disabled(member_id))
+            .filter_map(|member_id| self.pools.get(member_id).cloned())
+            .collect()
+    }
+
+    /// Groups with more than one pool of the same protocol and token pair
+    pub fn pool_groups(&self) -> impl Iterator<Item = &Vec<PoolId<LDT>>> {
+        self.pool_groups.values().filter(|pool_group| pool_group.len() > 1)
+    }
+
     /// Add a swap path to the market.
     pub fn add_paths(&mut self, paths: Vec<SwapPath<LDT>>) -> Vec<usize> {
-        paths.into_iter().filter_map(|path| self.swap_paths.add(path)).collect()
+        let mut paths_added: BTreeMap<PoolId<LDT>, usize> = BTreeMap::new();
+        let mut path_idx_vec = Vec::new();
+        for path in paths {
+            let pool_ids: Vec<PoolId<LDT>> = path.pools.iter().map(|pool| pool.get_pool_id()).collect();
+            if let Some(path_idx) = Arc::make_mut(&mut self.swap_paths).add(path) {
+                for pool_id in pool_ids {
+                    *paths_added.entry(pool_id).or_default() += 1;
+                }
+                path_idx_vec.push(path_idx);
+            }
+        }
+        for (pool_id, paths_added) in paths_added {
+            self.pool_events.push(PoolEvent::Updated { pool_id, paths_added });
+        }
+        path_idx_vec
     }
 
     /// Get all swap paths from the market by the pool address.
     #[inline]
     pub fn get_pool_paths(&self, pool_address: &PoolId<LDT>) -> Option<Vec<SwapPath<LDT>>> {
-        self.swap_paths.get_pool_paths_enabled_vec(pool_address)
+        let paths = self.swap_paths.get_pool_paths_enabled_vec(pool_address)?;
+        if self.kill_switch.is_empty() {
+            return Some(paths);
+        }
+        Some(
+            paths
+                .into_iter()
+                .filter(|swap_path| !swap_path.pools.iter().any(|pool| self.is_pool_class_disabled(pool.get_class())))
+                .collect(),
+        )
     }
 
     /// Get all swap paths from the market by the pool address.
@@ -183,7 +330,7 @@ impl<LDT: LoomDataTypes> Market<LDT> {
     }
 
     pub fn swap_paths_mut(&mut self) -> &mut SwapPaths<LDT> {
-        &mut self.swap_paths
+        Arc::make_mut(&mut self.swap_paths)
     }
 
     /// Set the pool status to ok or not ok.
@@ -205,23 +352,151 @@ impl<LDT: LoomDataTypes> Market<LDT> {
         };
 
         if update {
-            self.swap_paths.disable_pool_paths(&address, &token_from, &token_to, disabled);
+            Arc::make_mut(&mut self.swap_paths).disable_pool_paths(&address, &token_from, &token_to, disabled);
         }
          */
-        self.swap_paths.disable_pool_paths(&address, &token_from, &token_to, disabled);
+        Arc::make_mut(&mut self.swap_paths).disable_pool_paths(&address, &token_from, &token_to, disabled);
     }
 
     /// Set path status to ok or not ok.
     pub fn set_path_disabled(&mut self, swap_path: &SwapPath<LDT>, disabled: bool) -> bool {
-        self.swap_paths.disable_path(swap_path, disabled)
+        Arc::make_mut(&mut self.swap_paths).disable_path(swap_path, disabled)
     }
 
     /// Check if the pool is ok.
     #[inline]
     pub fn is_pool_disabled(&self, address: &PoolId<LDT>) -> bool {
+        self.denied_pools.contains(address)
+            || self.pools_disabled.get(address).is_some_and(|&is_disabled| is_disabled)
+            || self.pools.get(address).is_some_and(|pool| self.kill_switch.is_disabled(pool.get_class()))
+    }
+
+    /// Check if the pool itself is disabled, without the denied pools and the disabled pool classes
+    #[inline]
+    pub fn is_pool_flag_disabled(&self, address: &PoolId<LDT>) -> bool {
         self.pools_disabled.get(address).is_some_and(|&is_disabled| is_disabled)
     }
 
+    /// Disable or enable every pool of the class, returns true if the state of the class changed
+    pub fn toggle_pool_class(&mut self, record: KillSwitchRecord) -> bool {
+        self.kill_switch.toggle(record)
+    }
+
+    #[inline]
+    pub fn is_pool_class_disabled(&self, pool_class: PoolClass) -> bool {
+        self.kill_switch.is_disabled(pool_class)
+    }
+
+    pub fn kill_switch(&self) -> &PoolClassKillSwitch {
+        &self.kill_switch
+    }
+
+    pub fn score_adjustments(&self) -> &SharedScoreAdjustments<LDT> {
+        &self.score_adjustments
+    }
+
+    fn pair_key(token0: LDT::Address, token1: LDT::Address) -> (LDT::Address, LDT::Address) {
+        if token0 < token1 {
+            (token0, token1)
+        } else {
+            (token1, token0)
+        }
+    }
+
+    /// Exclude swaps between the tokens in both directions from path building and disable the paths already built
+    pub fn deny_pair(&mut self, token0: LDT::Address, token1: LDT::Address) {
+        if !self.denied_pairs.insert(Self::pair_key(token0, token1)) {
+            return;
+        }
+        for (token_from, token_to) in [(token0, token1), (token1, token0)] {
+            for pool_id in self.get_token_token_pools(&token_from, &token_to).cloned().unwrap_or_default() {
+                Arc::make_mut(&mut self.swap_paths).disable_pool_paths(&pool_id, &token_from, &token_to, true);
+            }
+        }
+    }
+
+    /// Exclude the pool from path building, the pool does not have to be loaded yet
+    pub fn deny_pool(&mut self, pool_id: PoolId<LDT>) {
+        if self.denied_pools.contains(&pool_id) {
+            return;
+        }
+        self.set_pool_all_disabled(pool_id, true);
+        self.denied_pools.insert(pool_id);
+    }
+
+    /// Replace the denied pairs and pools, the paths of pairs and pools no longer denied are enabled again
+    pub fn set_denied<P, I>(&mut self, pairs: P, pools: I)
+    where
+        P: IntoIterator<Item = (LDT::Address, LDT::Address)>,
+        I: IntoIterator<Item = PoolId<LDT>>,
+    {
+        let pairs: HashSet<(LDT::Address, LDT::Address)> =
+            pairs.into_iter().map(|(token0, token1)| Self::pair_key(token0, token1)).collect();
+        let pools: HashSet<PoolId<LDT>> = pools.into_iter().collect();
+
+        for pool_id in pools.iter() {
+            self.deny_pool(*pool_id);
+        }
+        for (token0, token1) in pairs.iter() {
+            self.deny_pair(*token0, *token1);
+        }
+        for pool_id in self.denied_pools.iter().filter(|pool_id| !pools.contains(pool_id)).copied().collect::<Vec<_>>() {
+            self.allow_pool(pool_id);
+        }
+        for (token0, token1) in self.denied_pairs.iter().filter(|pair| !pairs.contains(pair)).copied().collect::<Vec<_>>() {
+            self.allow_pair(token0, token1);
+        }
+    }
+
+    /// Allow swaps between the tokens again, paths of pools disabled otherwise stay disabled
+    fn allow_pair(&mut self, token0: LDT::Address, token1: LDT::Address) {
+        if !self.denied_pairs.remove(&Self::pair_key(token0, token1)) {
+            return;
+        }
+        for (token_from, token_to) in [(token0, token1), (token1, token0)] {
+            for pool_id in self.get_token_token_pools(&token_from, &token_to).cloned().unwrap_or_default() {
+                if !self.is_pool_disabled(&pool_id) {
+                    Arc::make_mut(&mut self.swap_paths).disable_pool_paths(&pool_id, &token_from, &token_to, false);
+                }
+            }
+        }
+    }
+
+    /// Use the pool in path building again unless its proxy upgrade is not validated, swaps of denied pairs stay disabled
+    fn allow_pool(&mut self, pool_id: PoolId<LDT>) {
+        if !self.denied_pools.remove(&pool_id) {
+            return;
+        }
+        let Some(pool) = self.pools.get(&pool_id).cloned() else {
+            return;
+        };
+        if self.proxy_upgrades.contains_key(&pool.get_address()) {
+            return;
+        }
+        self.set_pool_all_disabled(pool_id, false);
+        for direction in pool.get_swap_directions() {
+            if self.is_pair_denied(direction.from(), direction.to()) {
+                Arc::make_mut(&mut self.swap_paths).disable_pool_paths(&pool_id, direction.from(), direction.to(), true);
+            }
+        }
+    }
+
+    #[inline]
+    pub fn is_pair_denied(&self, token0: &LDT::Address, token1: &LDT::Address) -> bool {
+        self.denied_pairs.contains(&Self::pair_key(*token0, *token1))
+    }
+
+    /// Check if the path swaps a denied pair or through a denied pool
+    pub fn is_path_denied(&self, swap_path: &SwapPath<LDT>) -> bool {
+        swap_path.pools.iter().any(|pool| self.denied_pools.contains(&pool.get_pool_id()))
+            || swap_path.tokens.windows(2).any(|pair| self.is_pair_denied(&pair[0].get_address(), &pair[1].get_address()))
+    }
+
+    /// Check if the path is not denied and follows the token and pool repetition rules of the path build config
+    pub fn is_path_allowed(&self, swap_path: &SwapPath<LDT>) -> bool {
+        !self.is_path_denied(swap_path) && self.pools_config.path_build().allows(swap_path)
+    }
+
     /// Get all pool addresses as reference that allow to swap from `token_from_address` to `token_to_address`.
     #[inline]
     pub fn get_token_token_pools(&self, token_from_address: &LDT::Address, token_to_address: &LDT::Address) -> Option<&Vec<PoolId<LDT>>> {
@@ -243,21 +518,37 @@ impl<LDT: LoomDataTypes> Market<LDT> {
     pub fn get_token_pools_len(&self, token_address: &LDT::Address) -> usize {
         self.token_pools.get(token_address).map_or(0, |t| t.len())
     }
+    /// Build the swap paths through a newly added pool, only cycles through the pool are explored
+    pub fn build_pool_swap_path_vec(&self, pool: &PoolWrapper<LDT>) -> Result<Vec<SwapPath<LDT>>> {
+        build_pool_swap_path_vec(self, pool)
+    }
+
     /// Build a list of swap paths from the given directions.
     pub fn build_swap_path_vec(&self, directions: &BTreeMap<PoolWrapper<LDT>, Vec<SwapDirection<LDT>>>) -> Result<Vec<SwapPath<LDT>>> {
-        build_swap_path_vec(self, directions)
+        match self.pools_config.path_gas_budget() {
+            Some(path_gas_budget) => build_swap_path_vec_gas_budget(self, directions, path_gas_budget),
+            None => build_swap_path_vec(self, directions),
+        }
+    }
+
+    pub fn pool_gas_costs(&self) -> &PoolClassGasCosts {
+        &self.pool_gas_costs
     }
 
     /// get a [`SwapPath`] from the given token and pool addresses.
-    pub fn swap_path(&self, token_address_vec: Vec<LDT::Address>, pool_address_vec: Vec<PoolId<LDT>>) -> Result<SwapPath<LDT>> {
+    pub fn swap_path(
+        &self,
+        token_address_vec: Vec<LDT::Address>,
+        pool_address_vec: Vec<PoolId<LDT>>,
+    ) -> Result<SwapPath<LDT>, MarketError<LDT>> {
         let mut tokens: Vec<Arc<Token<LDT>>> = Vec::new();
         let mut pools: Vec<PoolWrapper<LDT>> = Vec::new();
 
         for token_address in token_address_vec.iter() {
-            tokens.push(self.get_token(token_address).ok_or_eyre("TOKEN_NOT_FOUND")?);
+            tokens.push(self.get_token(token_address).ok_or(MarketError::TokenNotFound { token: *token_address })?);
         }
         for pool_address in pool_address_vec.iter() {
-            pools.push(self.get_pool(pool_address).cloned().ok_or_eyre("TOKEN_NOT_FOUND")?);
+            pools.push(self.get_pool(pool_address).cloned().ok_or(MarketError::PoolNotFound { pool: *pool_address })?);
         }
 
         Ok(SwapPath { tokens, pools, ..Default::default() })
@@ -279,16 +570,191 @@ impl<LDT: LoomDataTypes> Market<LDT> {
     pub fn get_pool_id_for_cell(&self, pool_manager_address: &LDT::Address, cell: &U256) -> Option<&PoolId<LDT>> {
         self.pools_manager_cells.get(pool_manager_address).and_then(|pool_manager_cell| pool_manager_cell.get(cell))
     }
+
+    pub fn set_token_safety(&mut self, address: LDT::Address, safety: TokenSafety) {
+        self.token_safety.insert(address, safety);
+    }
+
+    /// Safety verdict of the token. Without a verdict basic tokens are trusted and rebasing tokens are suspicious.
+    pub fn token_safety(&self, address: &LDT::Address) -> TokenSafety {
+        if let Some(safety) = self.token_safety.get(address) {
+            return *safety;
+        }
+        match self.tokens.get(address) {
+            Some(token) if token.is_basic() => TokenSafety::Trusted,
+            Some(token) if token.is_rebasing() => TokenSafety::Suspicious,
+            _ if self.has_transfer_hook(address) => TokenSafety::Suspicious,
+            _ => TokenSafety::Unknown,
+        }
+    }
+
+    /// Record the transfer hook check of the token, a known token with hooks is replaced by a flagged copy
+    pub fn set_token_transfer_hook(&mut self, address: LDT::Address, transfer_hook: bool) {
+        self.transfer_hooks.insert(address, transfer_hook);
+        if transfer_hook {
+            if let Some(token) = self.tokens.get(&address).filter(|token| !token.has_transfer_hook()) {
+                let mut token = token.as_ref().clone();
+                token.set_transfer_hook();
+                Arc::make_mut(&mut self.tokens).insert(address, Arc::new(token));
+            }
+        }
+    }
+
+    /// Result of the transfer hook check, `None` if the token was not checked
+    pub fn token_transfer_hook(&self, address: &LDT::Address) -> Option<bool> {
+        self.transfer_hooks.get(address).copied()
+    }
+
+    pub fn has_transfer_hook(&self, address: &LDT::Address) -> bool {
+        self.transfer_hooks.get(address).copied().unwrap_or_default()
+            || self.tokens.get(address).is_some_and(|token| token.has_transfer_hook())
+    }
+
+    pub fn set_proxy_implementation(&mut self, address: LDT::Address, implementation: LDT::Address) {
+        self.proxy_implementations.insert(address, implementation);
+    }
+
+    pub fn proxy_implementation(&self, address: &LDT::Address) -> Option<LDT::Address> {
+        self.proxy_implementations.get(address).copied()
+    }
+
+    pub fn is_proxy(&self, address: &LDT::Address) -> bool {
+        self.proxy_implementations.contains_key(address)
+    }
+
+    /// Disable pools at the proxy address until the new implementation is revalidated.
+    /// Returns false if the address is not a known proxy or the implementation is unchanged.
+    pub fn set_proxy_upgraded(&mut self, address: LDT::Address, implementation: LDT::Address) -> bool {
+        if self.proxy_implementations.get(&address).is_none_or(|current| *current == implementation) {
+            return false;
+        }
+        self.proxy_upgrades.insert(address, implementation);
+        for pool_id in self.pool_ids_by_address(&address) {
+            self.set_pool_all_disabled(pool_id, true);
+        }
+        true
+    }
+
+    pub fn proxy_upgrade(&self, address: &LDT::Address) -> Option<LDT::Address> {
+        self.proxy_upgrades.get(address).copied()
+    }
+
+    /// Accept the new implementation of an upgraded proxy and enable its pools again
+    pub fn revalidate_proxy(&mut self, address: LDT::Address) -> bool {
+        let Some(implementation) = self.proxy_upgrades.remove(&address) else {
+            return false;
+        };
+        self.proxy_implementations.insert(address, implementation);
+        for pool_id in self.pool_ids_by_address(&address) {
+            self.set_pool_all_disabled(pool_id, false);
+        }
+        true
+    }
+
+    fn pool_ids_by_address(&self, address: &LDT::Address) -> Vec<PoolId<LDT>> {
+        self.pools.iter().filter(|(_, pool)| pool.get_address() == *address).map(|(pool_id, _)| *pool_id).collect()
+    }
+
+    /// Disable or enable all swap directions of the pool, denied pools are never enabled
+    pub fn set_pool_all_disabled(&mut self, pool_id: PoolId<LDT>, disabled: bool) {
+        if !disabled && self.denied_pools.contains(&pool_id) {
+            return;
+        }
+        let Some(pool) = self.pools.get(&pool_id).cloned() else {
+            return;
+        };
+        for direction in pool.get_swap_directions() {
+            Arc::make_mut(&mut self.swap_paths).disable_pool_paths(&pool_id, direction.from(), direction.to(), disabled);
+        }
+        let was_disabled = if disabled {
+            self.pools_disabled.insert(pool_id, true).unwrap_or_default()
+        } else {
+            self.pools_disabled.remove(&pool_id).unwrap_or_default()
+        };
+        if was_disabled != disabled {
+            self.pool_events.push(PoolEvent::Disabled { pool_id, disabled });
+        }
+    }
+
+    /// Remove the pool from the market and its indices and disable its paths, returns the removed pool
+    pub fn remove_pool(&mut self, pool_id: &PoolId<LDT>) -> Option<PoolWrapper<LDT>> {
+        let pool = Arc::make_mut(&mut self.pools).remove(pool_id)?;
+        let (swap_paths, token_token_pools, token_tokens, all_token_pools) = (
+            Arc::make_mut(&mut self.swap_paths),
+            Arc::make_mut(&mut self.token_token_pools),
+            Arc::make_mut(&mut self.token_tokens),
+            Arc::make_mut(&mut self.token_pools),
+        );
+        for direction in pool.get_swap_directions() {
+            swap_paths.disable_pool_paths(pool_id, direction.from(), direction.to(), true);
+            if let Some(token_pools) = token_token_pools.get_mut(direction.from()).and_then(|pools| pools.get_mut(direction.to())) {
+                token_pools.retain(|id| id != pool_id);
+            }
+            // tokens are listed once per pool connecting them
+            if let Some(tokens) = token_tokens.get_mut(direction.from()) {
+                if let Some(idx) = tokens.iter().position(|token| token == direction.to()) {
+                    tokens.remove(idx);
+                }
+            }
+            if let Some(token_pools) = all_token_pools.get_mut(direction.from()) {
+                token_pools.retain(|id| id != pool_id);
+            }
+        }
+        if let Some(pool_group) = Self::pool_group_key(&pool).and_then(|pool_group_key| self.pool_groups.get_mut(&pool_group_key)) {
+            pool_group.retain(|id| id != pool_id);
+        }
+        self.pools_disabled.remove(pool_id);
+        self.staged_pools.remove(pool_id);
+        self.pool_events.push(PoolEvent::Removed { pool_id: *pool_id });
+        Some(pool)
+    }
+
+    /// Latest lifecycle events of the pools of the market
+    pub fn pool_events(&self) -> &PoolEventLog<LDT> {
+        &self.pool_events
+    }
+
+    /// Token prices of the latest block
+    pub fn price_graph(&self) -> &PriceGraph<LDT> {
+        &self.price_graph
+    }
+
+    pub fn set_price_graph(&mut self, price_graph: PriceGraph<LDT>) {
+        self.price_graph = price_graph;
+    }
+
+    /// Keeps the first block only, pools loaded from history or config have no first seen block
+    pub fn set_pool_first_seen(&mut self, pool_id: PoolId<LDT>, block_number: u64) {
+        self.pools_first_seen.entry(pool_id).or_insert(block_number);
+    }
+
+    pub fn pool_first_seen(&self, pool_id: &PoolId<LDT>) -> Option<u64> {
+        self.pools_first_seen.get(pool_id).copied()
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::mock_pool::MockPool;
+    use crate::{PathBuildConfig, PathGasBudget};
     use alloy_primitives::Address;
     use eyre::Result;
     use loom_defi_address_book::TokenAddressEth;
 
+    #[test]
+    fn test_staged_pools() {
+        let mut market = Market::default();
+        let pool_id = PoolId::Address(Address::random());
+        assert!(!market.has_staged_pools());
+
+        market.stage_pool(pool_id);
+        assert!(market.has_staged_pools());
+        assert!(market.commit_pool(&pool_id));
+        assert!(!market.commit_pool(&pool_id));
+        assert!(!market.has_staged_pools());
+    }
+
     #[test]
     fn test_add_pool() {
         let mut market = Market::default();
@@ -313,6 +779,23 @@ mod tests {
         assert!(market.get_token_pools(&token1).unwrap().contains(&PoolId::Address(pool_address)));
     }
 
+    #[test]
+    fn test_clone_shares_until_changed() {
+        let mut market = Market::default();
+        let pool_address = Address::random();
+        market.add_pool(MockPool { address: pool_address, token0: Address::random(), token1: Address::random() }).unwrap();
+
+        let view = market.clone();
+        assert!(Arc::ptr_eq(&market.pools, &view.pools));
+        assert!(Arc::ptr_eq(&market.swap_paths, &view.swap_paths));
+
+        market.remove_pool(&PoolId::Address(pool_address));
+        assert!(!Arc::ptr_eq(&market.pools, &view.pools));
+        assert!(!market.is_pool(&PoolId::Address(pool_address)));
+        assert!(view.is_pool(&PoolId::Address(pool_address)));
+        assert!(Arc::ptr_eq(&market.tokens, &view.tokens));
+    }
+
     #[test]
     fn test_add_token() {
         let mut market = Market::<LoomDataTypesEthereum>::default();
@@ -388,6 +871,32 @@ mod tests {
         assert_eq!(market.get_token_token_pools(&token0, &token1).unwrap().len(), 1);
     }
 
+    #[test]
+    fn test_proxy_upgrade() {
+        let mut market = Market::default();
+        let pool_address = Address::random();
+        let pool_id = PoolId::Address(pool_address);
+        let mock_pool = MockPool { address: pool_address, token0: Address::random(), token1: Address::random() };
+        market.add_pool(mock_pool).unwrap();
+
+        let implementation = Address::repeat_byte(1);
+        let new_implementation = Address::repeat_byte(2);
+        assert!(!market.set_proxy_upgraded(pool_address, new_implementation));
+
+        market.set_proxy_implementation(pool_address, implementation);
+        assert!(!market.set_proxy_upgraded(pool_address, implementation));
+        assert!(!market.is_pool_disabled(&pool_id));
+
+        assert!(market.set_proxy_upgraded(pool_address, new_implementation));
+        assert!(market.is_pool_disabled(&pool_id));
+        assert_eq!(market.proxy_upgrade(&pool_address), Some(new_implementation));
+
+        assert!(market.revalidate_proxy(pool_address));
+        assert!(!market.is_pool_disabled(&pool_id));
+        assert_eq!(market.proxy_implementation(&pool_address), Some(new_implementation));
+        assert!(!market.revalidate_proxy(pool_address));
+    }
+
     #[test]
     fn test_get_token_token_pools() {
         let mut market = Market::default();
@@ -563,4 +1072,226 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_build_swap_path_vec_gas_budget() -> Result<()> {
+        let mut market = Market::default();
+
+        let weth_token = Token::new_with_data(TokenAddressEth::WETH, Some("WETH".to_string()), None, Some(18), true, false);
+        market.add_token(weth_token);
+
+        // weth -> token1 -> token2 -> token3 -> weth
+        let tokens = [TokenAddressEth::WETH, Address::random(), Address::random(), Address::random(), TokenAddressEth::WETH];
+        let pools = tokens
+            .windows(2)
+            .map(|pair| PoolWrapper::new(Arc::new(MockPool { address: Address::random(), token0: pair[0], token1: pair[1] })))
+            .collect::<Vec<_>>();
+        for pool in pools.iter() {
+            market.add_pool(pool.clone())?;
+        }
+
+        let mut directions = BTreeMap::new();
+        directions.insert(pools[1].clone(), pools[1].get_swap_directions());
+
+        // three hops limit of the default builder
+        assert!(market.build_swap_path_vec(&directions)?.is_empty());
+
+        // four UniswapV2 swaps exceed the budget
+        market.set_pools_config(PoolsLoadingConfig::new().with_path_gas_budget(PathGasBudget::new(5, 200_000)));
+        assert!(market.build_swap_path_vec(&directions)?.is_empty());
+
+        market.set_pools_config(PoolsLoadingConfig::new().with_path_gas_budget(PathGasBudget::new(5, 300_000)));
+        let swap_paths = market.build_swap_path_vec(&directions)?;
+        assert_eq!(swap_paths.len(), 2);
+        for swap_path in swap_paths.iter() {
+            assert_eq!(swap_path.pool_count(), 4);
+            assert_eq!(swap_path.tokens.first().unwrap().get_address(), TokenAddressEth::WETH);
+            assert_eq!(swap_path.tokens.last().unwrap().get_address(), TokenAddressEth::WETH);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_denied_pairs_and_pools() -> Result<()> {
+        let mut market = Market::default();
+        market.add_token(Token::new_with_data(TokenAddressEth::WETH, Some("WETH".to_string()), None, Some(18), true, false));
+
+        let token = Address::repeat_byte(1);
+        let pool0 =
+            PoolWrapper::new(Arc::new(MockPool { address: Address::repeat_byte(10), token0: TokenAddressEth::WETH, token1: token }));
+        let pool1 =
+            PoolWrapper::new(Arc::new(MockPool { address: Address::repeat_byte(11), token0: TokenAddressEth::WETH, token1: token }));
+        market.add_pool(pool0.clone())?;
+        market.add_pool(pool1.clone())?;
+
+        let mut directions = BTreeMap::new();
+        directions.insert(pool0.clone(), pool0.get_swap_directions());
+        assert_eq!(market.build_swap_path_vec(&directions)?.len(), 2);
+
+        let mut denied_pool_market = market.clone();
+        denied_pool_market.deny_pool(pool1.get_pool_id());
+        denied_pool_market.set_pool_all_disabled(pool1.get_pool_id(), false);
+        assert!(denied_pool_market.is_pool_disabled(&pool1.get_pool_id()));
+        assert!(denied_pool_market.build_swap_path_vec(&directions)?.is_empty());
+
+        market.deny_pair(token, TokenAddressEth::WETH);
+        assert!(market.is_pair_denied(&TokenAddressEth::WETH, &token));
+        assert!(market.build_swap_path_vec(&directions)?.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_denied() -> Result<()> {
+        let mut market = Market::default();
+        market.add_token(Token::new_with_data(TokenAddressEth::WETH, Some("WETH".to_string()), None, Some(18), true, false));
+
+        let token = Address::repeat_byte(1);
+        let pool0 =
+            PoolWrapper::new(Arc::new(MockPool { address: Address::repeat_byte(10), token0: TokenAddressEth::WETH, token1: token }));
+        let pool1 =
+            PoolWrapper::new(Arc::new(MockPool { address: Address::repeat_byte(11), token0: TokenAddressEth::WETH, token1: token }));
+        market.add_pool(pool0.clone())?;
+        market.add_pool(pool1.clone())?;
+
+        let mut directions = BTreeMap::new();
+        directions.insert(pool0.clone(), pool0.get_swap_directions());
+
+        market.set_denied([(token, TokenAddressEth::WETH)], [pool1.get_pool_id()]);
+        assert!(market.is_pair_denied(&TokenAddressEth::WETH, &token));
+        assert!(market.is_pool_disabled(&pool1.get_pool_id()));
+
+        // entries removed from the config are allowed again
+        market.set_denied([], [pool1.get_pool_id()]);
+        assert!(!market.is_pair_denied(&TokenAddressEth::WETH, &token));
+        assert!(market.is_pool_disabled(&pool1.get_pool_id()));
+        assert!(market.build_swap_path_vec(&directions)?.is_empty());
+
+        market.set_denied([], []);
+        assert!(!market.is_pool_disabled(&pool1.get_pool_id()));
+        assert_eq!(market.build_swap_path_vec(&directions)?.len(), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_build_pool_swap_path_vec() -> Result<()> {
+        let mut market = Market::default();
+        market.add_token(Token::new_with_data(TokenAddressEth::WETH, Some("WETH".to_string()), None, Some(18), true, false));
+
+        let (token1, token2) = (Address::repeat_byte(1), Address::repeat_byte(2));
+        let pools = [(TokenAddressEth::WETH, token1), (TokenAddressEth::WETH, token1), (token1, token2), (token2, TokenAddressEth::WETH)]
+            .iter()
+            .enumerate()
+            .map(|(idx, (token0, token1))| {
+                PoolWrapper::new(Arc::new(MockPool { address: Address::repeat_byte(10 + idx as u8), token0: *token0, token1: *token1 }))
+            })
+            .collect::<Vec<_>>();
+        for pool in pools.iter() {
+            market.add_pool(pool.clone())?;
+        }
+
+        let mut directions = BTreeMap::new();
+        directions.insert(pools[0].clone(), pools[0].get_swap_directions());
+        let full_paths = market.build_swap_path_vec(&directions)?;
+
+        // two cycles with the other WETH pool and two three hop cycles, one per direction
+        let pool_paths = market.build_pool_swap_path_vec(&pools[0])?;
+        assert_eq!(pool_paths.len(), 4);
+        assert_eq!(pool_paths.len(), full_paths.len());
+        for swap_path in pool_paths.iter() {
+            assert!(swap_path.contains_pool(&pools[0]));
+            assert_eq!(swap_path.tokens.first().unwrap().get_address(), TokenAddressEth::WETH);
+            assert_eq!(swap_path.tokens.last().unwrap().get_address(), TokenAddressEth::WETH);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_build_pool_swap_path_vec_pool_reuse() -> Result<()> {
+        let mut market = Market::default();
+        market.add_token(Token::new_with_data(TokenAddressEth::WETH, Some("WETH".to_string()), None, Some(18), true, false));
+
+        let pool = PoolWrapper::new(Arc::new(MockPool {
+            address: Address::repeat_byte(10),
+            token0: TokenAddressEth::WETH,
+            token1: Address::repeat_byte(1),
+        }));
+        market.add_pool(pool.clone())?;
+
+        // the only cycle swaps back through the same pool
+        assert!(market.build_pool_swap_path_vec(&pool)?.is_empty());
+
+        market.set_pools_config(PoolsLoadingConfig::new().with_path_build(PathBuildConfig::new().with_unique_pools(false)));
+        let swap_paths = market.build_pool_swap_path_vec(&pool)?;
+        assert_eq!(swap_paths.len(), 1);
+        assert_eq!(swap_paths[0].pools, vec![pool.clone(), pool.clone()]);
+        assert_eq!(swap_paths[0].tokens.first().unwrap().get_address(), TokenAddressEth::WETH);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_pool_groups() {
+        let mut market = Market::default();
+        let token0 = Address::repeat_byte(1);
+        let token1 = Address::repeat_byte(2);
+        let pool0 = PoolWrapper::new(Arc::new(MockPool { address: Address::repeat_byte(10), token0, token1 }));
+        let pool1 = PoolWrapper::new(Arc::new(MockPool { address: Address::repeat_byte(11), token0: token1, token1: token0 }));
+        let pool2 = PoolWrapper::new(Arc::new(MockPool { address: Address::repeat_byte(12), token0, token1: Address::repeat_byte(3) }));
+        market.add_pool(pool0.clone()).unwrap();
+        market.add_pool(pool1.clone()).unwrap();
+        market.add_pool(pool2.clone()).unwrap();
+
+        let pool_group = market.get_pool_group(&pool0.get_pool_id()).unwrap();
+        assert_eq!(pool_group, &vec![pool0.get_pool_id(), pool1.get_pool_id()]);
+        assert_eq!(market.pool_groups().count(), 1);
+
+        let alternatives = market.get_pool_group_alternatives(&pool1.get_pool_id());
+        assert_eq!(alternatives.len(), 1);
+        assert_eq!(alternatives[0].get_pool_id(), pool0.get_pool_id());
+
+        market.pools_disabled.insert(pool0.get_pool_id(), true);
+        assert!(market.get_pool_group_alternatives(&pool1.get_pool_id()).is_empty());
+    }
+
+    #[test]
+    fn test_pool_events() {
+        let mut market = Market::default();
+        let (token0, token1) = (Address::repeat_byte(1), Address::repeat_byte(2));
+        let pool0 = PoolWrapper::new(Arc::new(MockPool { address: Address::repeat_byte(10), token0, token1 }));
+        let pool1 = PoolWrapper::new(Arc::new(MockPool { address: Address::repeat_byte(11), token0: token1, token1: token0 }));
+        let (pool0_id, pool1_id) = (pool0.get_pool_id(), pool1.get_pool_id());
+        market.add_pool(pool0.clone()).unwrap();
+        market.add_pool(pool1.clone()).unwrap();
+        let (token0, token1) = (Arc::new(Token::new(token0)), Arc::new(Token::new(token1)));
+        market.add_paths(vec![SwapPath::new(vec![token0.clone(), token1.clone(), token0], vec![pool0.clone(), pool1.clone()])]);
+
+        market.set_pool_all_disabled(pool0_id, true);
+        // not changed
+        market.set_pool_all_disabled(pool0_id, true);
+        market.set_pool_all_disabled(pool0_id, false);
+        assert!(market.remove_pool(&pool1_id).is_some());
+        assert!(market.remove_pool(&pool1_id).is_none());
+
+        let events: Vec<PoolEvent> = market.pool_events().since(0).map(|record| record.event.clone()).collect();
+        assert_eq!(
+            events,
+            vec![
+                PoolEvent::Added { pool_id: pool0_id, pool_class: PoolClass::UniswapV2 },
+                PoolEvent::Added { pool_id: pool1_id, pool_class: PoolClass::UniswapV2 },
+                PoolEvent::Updated { pool_id: pool0_id, paths_added: 1 },
+                PoolEvent::Updated { pool_id: pool1_id, paths_added: 1 },
+                PoolEvent::Disabled { pool_id: pool0_id, disabled: true },
+                PoolEvent::Disabled { pool_id: pool0_id, disabled: false },
+                PoolEvent::Removed { pool_id: pool1_id },
+            ]
+        );
+        assert_eq!(market.pool_events().last_seq(), 7);
+        assert!(!market.is_pool(&pool1_id));
+        assert!(market.get_token_token_pools(&token1.get_address(), &token0.get_address()).unwrap().is_empty());
+        assert_eq!(market.get_pool_group(&pool0_id).unwrap(), &vec![pool0_id]);
+    }
 }
@@ -0,0 +1,13 @@
+use crate::PoolId;
+use loom_types_blockchain::{LoomDataTypes, LoomDataTypesEthereum};
+use thiserror::Error;
+
+#[derive(Clone, Debug, Error)]
+pub enum MarketError<LDT: LoomDataTypes = LoomDataTypesEthereum> {
+    #[error("Token {token} not found in market")]
+    TokenNotFound { token: LDT::Address },
+    #[error("Pool {pool} not found in market")]
+    PoolNotFound { pool: PoolId<LDT> },
+    #[error("Pool {pool} already exists")]
+    PoolAlreadyExists { pool: PoolId<LDT> },
+}
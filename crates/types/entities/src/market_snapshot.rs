@@ -0,0 +1,302 @@
+use std::collections::BTreeMap;
+
+use alloy_primitives::Address;
+use loom_types_blockchain::LoomDataTypes;
+use serde::{Deserialize, Serialize};
+
+use crate::{Market, PoolClass, PoolId, PoolProtocol, PoolWrapper, SwapPath, Token, TokenSafety};
+
+/// Classification of a token used by path building and risk scoring
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenClassification {
+    pub basic: bool,
+    pub middle: bool,
+    pub rebasing: bool,
+    pub transfer_hook: bool,
+    pub safety: TokenSafety,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PoolSnapshot {
+    pub protocol: PoolProtocol,
+    pub pool_class: PoolClass,
+    pub tokens: Vec<Address>,
+    pub disabled: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PathSnapshot {
+    pub score: Option<f64>,
+    pub disabled: bool,
+}
+
+impl TokenClassification {
+    pub fn new(market: &Market, token: &Token) -> Self {
+        let address = token.get_address();
+        Self {
+            basic: token.is_basic(),
+            middle: token.is_middle(),
+            rebasing: token.is_rebasing(),
+            transfer_hook: market.has_transfer_hook(&address),
+            safety: market.token_safety(&address),
+        }
+    }
+}
+
+impl PoolSnapshot {
+    pub fn new(market: &Market, pool_id: &PoolId, pool: &PoolWrapper) -> Self {
+        Self {
+            protocol: pool.get_protocol(),
+            pool_class: pool.get_class(),
+            tokens: pool.get_tokens(),
+            disabled: market.is_pool_disabled(pool_id),
+        }
+    }
+}
+
+impl PathSnapshot {
+    pub fn new(market: &Market, swap_path: &SwapPath) -> Self {
+        Self { score: swap_path.score, disabled: swap_path.disabled || !market.is_path_allowed(swap_path) }
+    }
+}
+
+/// Serializable copy of the pools, swap paths and token classifications of a market. Snapshots taken before and after
+/// an upgrade or a config edit are compared with [`MarketSnapshot::diff`].
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct MarketSnapshot {
+    pub block_number: u64,
+    /// Pools by pool id
+    pub pools: BTreeMap<String, PoolSnapshot>,
+    pub tokens: BTreeMap<Address, TokenClassification>,
+    /// Swap paths by [`path_key`]
+    pub paths: BTreeMap<String, PathSnapshot>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PoolToggle {
+    pub pool: String,
+    pub disabled: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PathScoreChange {
+    pub path: String,
+    pub before: Option<f64>,
+    pub after: Option<f64>,
+}
+
+impl PathScoreChange {
+    /// Absolute score change, `None` if the path is scored in one snapshot only
+    pub fn delta(&self) -> Option<f64> {
+        Some((self.after? - self.before?).abs())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TokenReclassification {
+    pub address: Address,
+    pub before: TokenClassification,
+    pub after: TokenClassification,
+}
+
+/// Changes between two market snapshots
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct MarketSnapshotDiff {
+    pub from_block: u64,
+    pub to_block: u64,
+    pub pools_added: Vec<String>,
+    pub pools_removed: Vec<String>,
+    /// Pools present in both snapshots and enabled or disabled since
+    pub pools_toggled: Vec<PoolToggle>,
+    pub paths_added: usize,
+    pub paths_removed: usize,
+    /// Paths present in both snapshots and disabled since
+    pub paths_disabled: usize,
+    /// Paths present in both snapshots and enabled since
+    pub paths_enabled: usize,
+    /// Largest changes first, paths scored in one snapshot only last
+    pub score_changes: Vec<PathScoreChange>,
+    pub tokens_reclassified: Vec<TokenReclassification>,
+}
+
+impl MarketSnapshotDiff {
+    pub fn is_empty(&self) -> bool {
+        self.pools_added.is_empty()
+            && self.pools_removed.is_empty()
+            && self.pools_toggled.is_empty()
+            && self.paths_added == 0
+            && self.paths_removed == 0
+            && self.paths_disabled == 0
+            && self.paths_enabled == 0
+            && self.score_changes.is_empty()
+            && self.tokens_reclassified.is_empty()
+    }
+}
+
+/// Key of a swap path, its tokens interleaved with the ids of its pools
+pub fn path_key<LDT: LoomDataTypes>(swap_path: &SwapPath<LDT>) -> String {
+    let mut key = Vec::with_capacity(swap_path.tokens.len() + swap_path.pools.len());
+    for (idx, token) in swap_path.tokens.iter().enumerate() {
+        key.push(token.get_address().to_string());
+        if let Some(pool) = swap_path.pools.get(idx) {
+            key.push(pool.get_pool_id().to_string());
+        }
+    }
+    key.join(">")
+}
+
+impl MarketSnapshot {
+    pub fn new(market: &Market, block_number: u64) -> Self {
+        Self::new_page(market, block_number, 0, usize::MAX)
+    }
+
+    /// Page of the snapshot with up to `limit` pools by pool id, tokens by address and swap paths by index starting at
+    /// `start`. Pages with the same limit are merged into the full snapshot with [`MarketSnapshot::merge`]
+    pub fn new_page(market: &Market, block_number: u64, start: usize, limit: usize) -> Self {
+        let pools: BTreeMap<String, _> = market.pools().iter().map(|(pool_id, pool)| (pool_id.to_string(), (pool_id, pool))).collect();
+        let pools = pools
+            .into_iter()
+            .skip(start)
+            .take(limit)
+            .map(|(key, (pool_id, pool))| (key, PoolSnapshot::new(market, pool_id, pool)))
+            .collect();
+
+        let tokens: BTreeMap<&Address, _> = market.tokens().iter().collect();
+        let tokens = tokens
+            .into_iter()
+            .skip(start)
+            .take(limit)
+            .map(|(address, token)| (*address, TokenClassification::new(market, token)))
+            .collect();
+
+        let paths = market
+            .swap_paths()
+            .paths
+            .iter()
+            .skip(start)
+            .take(limit)
+            .map(|swap_path| (path_key(swap_path), PathSnapshot::new(market, swap_path)))
+            .collect();
+
+        Self { block_number, pools, tokens, paths }
+    }
+
+    /// Add the pools, tokens and swap paths of a page, the snapshot keeps the latest block number of its pages
+    pub fn merge(&mut self, page: MarketSnapshot) {
+        self.block_number = self.block_number.max(page.block_number);
+        self.pools.extend(page.pools);
+        self.tokens.extend(page.tokens);
+        self.paths.extend(page.paths);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pools.is_empty() && self.tokens.is_empty() && self.paths.is_empty()
+    }
+
+    /// Changes from this snapshot to `after`
+    pub fn diff(&self, after: &MarketSnapshot) -> MarketSnapshotDiff {
+        let mut diff = MarketSnapshotDiff { from_block: self.block_number, to_block: after.block_number, ..Default::default() };
+
+        for (pool_id, pool) in after.pools.iter() {
+            match self.pools.get(pool_id) {
+                None => diff.pools_added.push(pool_id.clone()),
+                Some(before) if before.disabled != pool.disabled => {
+                    diff.pools_toggled.push(PoolToggle { pool: pool_id.clone(), disabled: pool.disabled })
+                }
+                Some(_) => {}
+            }
+        }
+        diff.pools_removed = self.pools.keys().filter(|pool_id| !after.pools.contains_key(*pool_id)).cloned().collect();
+
+        for (key, path) in after.paths.iter() {
+            let Some(before) = self.paths.get(key) else {
+                diff.paths_added += 1;
+                continue;
+            };
+            match (before.disabled, path.disabled) {
+                (false, true) => diff.paths_disabled += 1,
+                (true, false) => diff.paths_enabled += 1,
+                _ => {}
+            }
+            if before.score != path.score {
+                diff.score_changes.push(PathScoreChange { path: key.clone(), before: before.score, after: path.score });
+            }
+        }
+        diff.paths_removed = self.paths.keys().filter(|key| !after.paths.contains_key(*key)).count();
+        diff.score_changes.sort_by(|a, b| match (a.delta(), b.delta()) {
+            (Some(a), Some(b)) => b.total_cmp(&a),
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => a.path.cmp(&b.path),
+        });
+
+        for (address, classification) in after.tokens.iter() {
+            if let Some(before) = self.tokens.get(address) {
+                if before != classification {
+                    diff.tokens_reclassified.push(TokenReclassification { address: *address, before: *before, after: *classification });
+                }
+            }
+        }
+
+        diff
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mock_pool::MockPool;
+    use crate::{PoolId, Token};
+
+    #[test]
+    fn test_diff() {
+        let (token0, token1) = (Address::repeat_byte(1), Address::repeat_byte(2));
+        let (pool0, pool1) = (Address::repeat_byte(0x10), Address::repeat_byte(0x11));
+
+        let mut market = Market::default();
+        market.add_token(Token::new(token0));
+        market.add_pool(MockPool::new(token0, token1, pool0)).unwrap();
+        market.add_paths(vec![SwapPath::new(vec![Token::new(token0), Token::new(token1)], vec![MockPool::new(token0, token1, pool0)])]);
+        market.swap_paths_mut().paths[0].score = Some(1.0);
+        let before = MarketSnapshot::new(&market, 1);
+        assert!(before.diff(&before).is_empty());
+
+        market.add_pool(MockPool::new(token0, token1, pool1)).unwrap();
+        market.swap_paths_mut().paths[0].score = Some(3.0);
+        market.set_token_safety(token0, TokenSafety::Unsafe);
+        market.deny_pool(PoolId::Address(pool0));
+        let after = MarketSnapshot::new(&market, 2);
+
+        let diff = before.diff(&after);
+        assert_eq!(diff.pools_added, vec![pool1.to_string()]);
+        assert_eq!(diff.pools_toggled, vec![PoolToggle { pool: pool0.to_string(), disabled: true }]);
+        assert_eq!(diff.paths_disabled, 1);
+        assert_eq!(diff.score_changes[0].delta(), Some(2.0));
+        assert_eq!(diff.tokens_reclassified[0].after.safety, TokenSafety::Unsafe);
+
+        let reverse = after.diff(&before);
+        assert_eq!(reverse.pools_removed, vec![pool1.to_string()]);
+        assert_eq!(reverse.paths_enabled, 1);
+    }
+
+    #[test]
+    fn test_pages() {
+        let tokens: Vec<Address> = (1..=3).map(Address::repeat_byte).collect();
+        let mut market = Market::default();
+        for (idx, token) in tokens.iter().enumerate() {
+            market.add_token(Token::new(*token));
+            let pool = MockPool::new(*token, tokens[(idx + 1) % tokens.len()], Address::repeat_byte(0x10 + idx as u8));
+            market.add_pool(pool.clone()).unwrap();
+            market.add_paths(vec![SwapPath::new(vec![Token::new(*token), Token::new(tokens[(idx + 1) % tokens.len()])], vec![pool])]);
+        }
+
+        let mut merged = MarketSnapshot::new_page(&market, 1, 0, 2);
+        assert_eq!((merged.pools.len(), merged.tokens.len(), merged.paths.len()), (2, 2, 2));
+        merged.merge(MarketSnapshot::new_page(&market, 2, 2, 2));
+        assert!(MarketSnapshot::new_page(&market, 2, 4, 2).is_empty());
+
+        let full = MarketSnapshot::new(&market, 2);
+        assert_eq!(merged, full);
+        assert!(merged.diff(&full).is_empty());
+    }
+}
@@ -1,6 +1,7 @@
+use crate::state_proof::ProvenBlock;
 use alloy_primitives::{Address, BlockHash, BlockNumber, U256};
 use loom_evm_db::DatabaseHelpers;
-use loom_types_blockchain::{GethStateUpdate, GethStateUpdateVec};
+use loom_types_blockchain::{GethStateUpdate, GethStateUpdateVec, TouchedAddresses};
 use revm::{Database, DatabaseCommit, DatabaseRef};
 use std::collections::{HashMap, HashSet};
 
@@ -43,11 +44,22 @@ pub struct MarketState<DB> {
     pub block_hash: BlockHash,
     pub state_db: DB,
     pub config: MarketStateConfig,
+    // tracked address -> block number of the last state update
+    pub last_updated: HashMap<Address, BlockNumber>,
+    /// Header of the block the state is at, proofs of the required state are verified against its state root
+    pub proven_block: Option<ProvenBlock>,
 }
 
 impl<DB: DatabaseRef + Database + DatabaseCommit> MarketState<DB> {
     pub fn new(db: DB) -> MarketState<DB> {
-        MarketState { block_number: Default::default(), block_hash: Default::default(), state_db: db, config: Default::default() }
+        MarketState {
+            block_number: Default::default(),
+            block_hash: Default::default(),
+            state_db: db,
+            config: Default::default(),
+            last_updated: Default::default(),
+            proven_block: None,
+        }
     }
 
     pub fn hash(&self) -> BlockHash {
@@ -61,6 +73,39 @@ impl<DB: DatabaseRef + Database + DatabaseCommit> MarketState<DB> {
         (self.block_number, self.block_hash)
     }
 
+    /// Start tracking updates of the address, e.g. after the pool state was fetched
+    pub fn track_updates(&mut self, address: Address, block_number: BlockNumber) {
+        self.last_updated.insert(address, block_number);
+    }
+
+    /// Mark tracked addresses as updated in the block, untracked addresses are ignored.
+    /// Iterates the smaller of the touched and the tracked sets.
+    pub fn set_updated(&mut self, touched: &TouchedAddresses, block_number: BlockNumber) {
+        if touched.len() < self.last_updated.len() {
+            for address in touched.iter() {
+                if let Some(last_updated) = self.last_updated.get_mut(address) {
+                    *last_updated = block_number;
+                }
+            }
+        } else {
+            for (_, last_updated) in self.last_updated.iter_mut().filter(|(address, _)| touched.contains(address)) {
+                *last_updated = block_number;
+            }
+        }
+    }
+
+    pub fn last_updated_block(&self, address: &Address) -> Option<BlockNumber> {
+        self.last_updated.get(address).copied()
+    }
+
+    /// True if the address was not updated during the last `max_age` blocks or is not tracked
+    pub fn is_stale(&self, address: &Address, max_age: u64) -> bool {
+        match self.last_updated.get(address) {
+            Some(last_updated) => self.block_number.saturating_sub(*last_updated) >= max_age,
+            None => true,
+        }
+    }
+
     pub fn apply_geth_update(&mut self, update: GethStateUpdate) {
         DatabaseHelpers::apply_geth_state_update(&mut self.state_db, update)
     }
@@ -98,3 +143,31 @@ impl<DB: DatabaseRef + Database + DatabaseCommit> MarketState<DB> {
     //     //debug!("Added state : {}", state.len());
     // }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use loom_evm_db::LoomDBType;
+
+    #[test]
+    fn test_stale_tracking() {
+        let (pool, untracked) = (Address::repeat_byte(1), Address::repeat_byte(2));
+        let mut market_state = MarketState::new(LoomDBType::default());
+        market_state.track_updates(pool, 100);
+
+        market_state.block_number = 149;
+        assert!(!market_state.is_stale(&pool, 50));
+        // crosses the staleness window
+        market_state.block_number = 150;
+        assert!(market_state.is_stale(&pool, 50));
+
+        // recovers with a state update touching it
+        market_state.set_updated(vec![pool, untracked], 150);
+        assert_eq!(market_state.last_updated_block(&pool), Some(150));
+        assert!(!market_state.is_stale(&pool, 50));
+
+        // untracked addresses are always stale and not tracked by updates
+        assert!(market_state.is_stale(&untracked, 50));
+        assert_eq!(market_state.last_updated_block(&untracked), None);
+    }
+}
@@ -0,0 +1,47 @@
+use std::ops::Deref;
+
+use loom_types_blockchain::{LoomDataTypes, LoomDataTypesEthereum};
+
+use crate::Market;
+
+/// Immutable copy of the market with its pools, paths and indices, published once per block. Readers of the view do not
+/// contend with pool loaders holding the market write lock
+#[derive(Clone, Default)]
+pub struct MarketView<LDT: LoomDataTypes = LoomDataTypesEthereum> {
+    epoch: u64,
+    block_number: u64,
+    market: Market<LDT>,
+}
+
+impl<LDT: LoomDataTypes> MarketView<LDT> {
+    pub fn new(block_number: u64, market: Market<LDT>) -> Self {
+        Self { epoch: 0, block_number, market }
+    }
+
+    pub fn with_epoch(self, epoch: u64) -> Self {
+        Self { epoch, ..self }
+    }
+
+    /// Number of the view, advanced only when the market state of the block is applied and no pool is partially added.
+    /// Pools of a view have their paths built and their state written to the market state
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    /// Block of the market state the view was taken with, pools of the view have their state in the market state of the block
+    pub fn block_number(&self) -> u64 {
+        self.block_number
+    }
+
+    pub fn market(&self) -> &Market<LDT> {
+        &self.market
+    }
+}
+
+impl<LDT: LoomDataTypes> Deref for MarketView<LDT> {
+    type Target = Market<LDT>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.market
+    }
+}
@@ -2,12 +2,15 @@ use crate::pool_id::PoolId;
 use crate::required_state::RequiredState;
 use crate::{Pool, PoolAbiEncoder, PoolClass, PoolProtocol, PreswapRequirement, SwapDirection};
 use alloy_primitives::{Address, U256};
-use eyre::ErrReport;
 use eyre::Result;
+use eyre::{eyre, ErrReport};
 use revm::primitives::Env;
 use revm::DatabaseRef;
 use std::any::Any;
 
+const MOCK_POOL_GAS: u64 = 100_000;
+
+/// Constant product pool of tests, the reserves of token0 and token1 are read from the storage slots 0 and 1 of the pool
 #[derive(Clone)]
 pub struct MockPool {
     pub(crate) token0: Address,
@@ -19,6 +22,16 @@ impl MockPool {
     pub fn new(token0: Address, token1: Address, address: Address) -> Self {
         Self { token0, token1, address }
     }
+
+    fn reserves(&self, state: &dyn DatabaseRef<Error = ErrReport>, token_address_from: &Address) -> Result<(U256, U256)> {
+        let reserve0 = state.storage_ref(self.address, U256::ZERO)?;
+        let reserve1 = state.storage_ref(self.address, U256::from(1))?;
+        match *token_address_from {
+            token if token == self.token0 => Ok((reserve0, reserve1)),
+            token if token == self.token1 => Ok((reserve1, reserve0)),
+            _ => Err(eyre!("TOKEN_NOT_FOUND")),
+        }
+    }
 }
 
 impl Pool for MockPool {
@@ -62,7 +75,11 @@ impl Pool for MockPool {
         token_address_to: &Address,
         in_amount: U256,
     ) -> Result<(U256, u64), ErrReport> {
-        panic!("Not implemented")
+        let (reserve_in, reserve_out) = self.reserves(state, token_address_from)?;
+        if reserve_in.is_zero() {
+            return Ok((U256::ZERO, MOCK_POOL_GAS));
+        }
+        Ok((in_amount * reserve_out / (reserve_in + in_amount), MOCK_POOL_GAS))
     }
 
     fn calculate_in_amount(
@@ -73,11 +90,15 @@ impl Pool for MockPool {
         token_address_to: &Address,
         out_amount: U256,
     ) -> Result<(U256, u64), ErrReport> {
-        panic!("Not implemented")
+        let (reserve_in, reserve_out) = self.reserves(state, token_address_from)?;
+        if out_amount >= reserve_out {
+            return Err(eyre!("RESERVE_EXCEEDED"));
+        }
+        Ok((reserve_in * out_amount / (reserve_out - out_amount) + U256::from(1), MOCK_POOL_GAS))
     }
 
     fn can_flash_swap(&self) -> bool {
-        panic!("Not implemented")
+        true
     }
 
     fn can_calculate_in_amount(&self) -> bool {
@@ -93,7 +114,9 @@ impl Pool for MockPool {
     }
 
     fn get_state_required(&self) -> Result<RequiredState> {
-        panic!("Not implemented")
+        let mut state_required = RequiredState::new();
+        state_required.add_slot_range(self.address, U256::ZERO, 2);
+        Ok(state_required)
     }
 
     fn is_native(&self) -> bool {
@@ -0,0 +1,129 @@
+use std::collections::HashSet;
+
+use loom_types_blockchain::LoomDataTypes;
+use serde::Deserialize;
+
+use crate::SwapPath;
+
+fn default_true() -> bool {
+    true
+}
+
+/// Constraints on the tokens and pools of built cycles. The anchor is the first token of a cycle, it is the only token
+/// allowed to appear twice as the cycle returns to it
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct PathBuildConfig {
+    /// Reject cycles passing a token other than the anchor more than once
+    #[serde(default = "default_true")]
+    unique_tokens: bool,
+    /// Reject cycles swapping through a pool more than once, e.g. a multi token pool entered with different tokens
+    #[serde(default = "default_true")]
+    unique_pools: bool,
+    /// Maximum number of distinct tokens besides the anchor, unlimited if not set
+    max_middle_tokens: Option<usize>,
+}
+
+impl Default for PathBuildConfig {
+    fn default() -> Self {
+        Self { unique_tokens: true, unique_pools: true, max_middle_tokens: None }
+    }
+}
+
+impl PathBuildConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_unique_tokens(self, unique_tokens: bool) -> Self {
+        Self { unique_tokens, ..self }
+    }
+
+    pub fn with_unique_pools(self, unique_pools: bool) -> Self {
+        Self { unique_pools, ..self }
+    }
+
+    pub fn with_max_middle_tokens(self, max_middle_tokens: usize) -> Self {
+        Self { max_middle_tokens: Some(max_middle_tokens), ..self }
+    }
+
+    pub fn unique_tokens(&self) -> bool {
+        self.unique_tokens
+    }
+
+    pub fn unique_pools(&self) -> bool {
+        self.unique_pools
+    }
+
+    pub fn max_middle_tokens(&self) -> Option<usize> {
+        self.max_middle_tokens
+    }
+
+    /// Check the token and pool sequence of the path
+    pub fn allows<LDT: LoomDataTypes>(&self, swap_path: &SwapPath<LDT>) -> bool {
+        let tokens: Vec<LDT::Address> = swap_path.tokens.iter().map(|token| token.get_address()).collect();
+        let Some((anchor, rest)) = tokens.split_first() else {
+            return true;
+        };
+        let middle = match rest.split_last() {
+            Some((last, middle)) if last == anchor => middle,
+            _ => rest,
+        };
+
+        let distinct_middle: HashSet<&LDT::Address> = middle.iter().collect();
+        if self.unique_tokens && (distinct_middle.len() != middle.len() || distinct_middle.contains(anchor)) {
+            return false;
+        }
+        if self.max_middle_tokens.is_some_and(|max_middle_tokens| distinct_middle.len() > max_middle_tokens) {
+            return false;
+        }
+        if self.unique_pools {
+            let distinct_pools: HashSet<_> = swap_path.pools.iter().map(|pool| pool.get_pool_id()).collect();
+            if distinct_pools.len() != swap_path.pools.len() {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{MockPool, PoolWrapper, Token};
+    use alloy_primitives::Address;
+    use loom_types_blockchain::LoomDataTypesEthereum;
+    use std::sync::Arc;
+
+    fn cycle(tokens: &[u8], pools: &[u8]) -> SwapPath<LoomDataTypesEthereum> {
+        let tokens: Vec<Arc<Token>> = tokens.iter().map(|token| Arc::new(Token::new(Address::repeat_byte(*token)))).collect();
+        let pools: Vec<PoolWrapper> = pools
+            .iter()
+            .zip(tokens.windows(2))
+            .map(|(pool, pair)| {
+                PoolWrapper::new(Arc::new(MockPool {
+                    address: Address::repeat_byte(*pool),
+                    token0: pair[0].get_address(),
+                    token1: pair[1].get_address(),
+                }))
+            })
+            .collect();
+        SwapPath::new(tokens, pools)
+    }
+
+    #[test]
+    fn test_allows() {
+        let config = PathBuildConfig::default();
+        assert!(config.allows(&cycle(&[1, 2, 3, 1], &[10, 11, 12])));
+        // middle token repeated
+        assert!(!config.allows(&cycle(&[1, 2, 3, 2, 1], &[10, 11, 12, 13])));
+        // anchor passed in the middle
+        assert!(!config.allows(&cycle(&[1, 2, 1, 3, 1], &[10, 11, 12, 13])));
+        // pool revisited
+        assert!(!config.allows(&cycle(&[1, 2, 3, 1], &[10, 11, 10])));
+        assert!(config.with_unique_pools(false).allows(&cycle(&[1, 2, 3, 1], &[10, 11, 10])));
+
+        let config = config.with_max_middle_tokens(2);
+        assert!(config.allows(&cycle(&[1, 2, 3, 1], &[10, 11, 12])));
+        assert!(!config.allows(&cycle(&[1, 2, 3, 4, 1], &[10, 11, 12, 13])));
+    }
+}
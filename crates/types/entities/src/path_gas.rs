@@ -0,0 +1,122 @@
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use serde::Deserialize;
+
+use crate::PoolClass;
+
+/// Longest cycle the gas budgeted path builder enumerates
+pub const MAX_PATH_HOPS: usize = 5;
+/// Cycles up to this number of hops are kept regardless of their gas
+pub const UNBUDGETED_PATH_HOPS: usize = 3;
+
+// weight of a new gas sample in the moving average, 1/8
+const GAS_SAMPLE_WEIGHT_SHIFT: u32 = 3;
+
+/// Gas of a single swap through a pool of the class, used until samples are recorded
+pub fn default_pool_class_gas(pool_class: PoolClass) -> u64 {
+    match pool_class {
+        PoolClass::UniswapV2 => 60_000,
+        PoolClass::UniswapV3 | PoolClass::PancakeV3 => 110_000,
+        PoolClass::UniswapV4 => 120_000,
+        PoolClass::Maverick | PoolClass::MaverickV2 => 130_000,
+        PoolClass::BalancerV1 | PoolClass::BalancerV2 => 130_000,
+        PoolClass::LidoStEth | PoolClass::LidoWstEth | PoolClass::RocketPool => 100_000,
+        PoolClass::Curve | PoolClass::PendleV2 | PoolClass::WooFiV2 => 160_000,
+        PoolClass::GmxV2 => 250_000,
+        PoolClass::Unknown | PoolClass::Custom(_) => 150_000,
+    }
+}
+
+/// Moving averages of the gas used by swaps through pools of each class. Clones share the averages, samples are recorded
+/// without a lock of the market
+#[derive(Clone, Debug, Default)]
+pub struct PoolClassGasCosts {
+    costs: Arc<RwLock<HashMap<PoolClass, u64>>>,
+}
+
+impl PoolClassGasCosts {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, pool_class: PoolClass) -> u64 {
+        let costs = self.costs.read().unwrap_or_else(|error| error.into_inner());
+        costs.get(&pool_class).copied().unwrap_or_else(|| default_pool_class_gas(pool_class))
+    }
+
+    pub fn record(&self, pool_class: PoolClass, gas_used: u64) {
+        let mut costs = self.costs.write().unwrap_or_else(|error| error.into_inner());
+        let current = costs.get(&pool_class).copied().unwrap_or_else(|| default_pool_class_gas(pool_class));
+        let updated = current - (current >> GAS_SAMPLE_WEIGHT_SHIFT) + (gas_used >> GAS_SAMPLE_WEIGHT_SHIFT);
+        costs.insert(pool_class, updated);
+    }
+
+    /// Record the gas used by each hop with the class of its pool
+    pub fn record_hops(&self, hop_gas: &[(PoolClass, u64)]) {
+        for (pool_class, gas_used) in hop_gas {
+            self.record(*pool_class, *gas_used);
+        }
+    }
+
+    pub fn path_gas(&self, pool_classes: &[PoolClass]) -> u64 {
+        pool_classes.iter().map(|pool_class| self.get(*pool_class)).sum()
+    }
+}
+
+/// Enumerates cycles longer than three hops, keeping them only when their estimated gas fits the budget
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct PathGasBudget {
+    /// Longest cycle, capped at [`MAX_PATH_HOPS`]
+    max_hops: usize,
+    /// Maximum estimated gas of cycles with more than [`UNBUDGETED_PATH_HOPS`] hops
+    max_gas: u64,
+}
+
+impl PathGasBudget {
+    pub fn new(max_hops: usize, max_gas: u64) -> Self {
+        Self { max_hops, max_gas }
+    }
+
+    pub fn max_hops(&self) -> usize {
+        self.max_hops.min(MAX_PATH_HOPS)
+    }
+
+    pub fn max_gas(&self) -> u64 {
+        self.max_gas
+    }
+
+    /// Whether a cycle with at least `hops` hops and `gas` estimated gas can be kept
+    pub fn is_within(&self, hops: usize, gas: u64) -> bool {
+        hops <= self.max_hops() && (hops <= UNBUDGETED_PATH_HOPS || gas <= self.max_gas)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_record_hops() {
+        let gas_costs = PoolClassGasCosts::new();
+        assert_eq!(gas_costs.get(PoolClass::UniswapV2), 60_000);
+
+        gas_costs.record(PoolClass::UniswapV2, 140_000);
+        assert_eq!(gas_costs.get(PoolClass::UniswapV2), 70_000);
+
+        // clones share the averages, each hop is recorded with its own gas
+        gas_costs.clone().record_hops(&[(PoolClass::UniswapV2, 70_000), (PoolClass::UniswapV3, 190_000)]);
+        assert_eq!(gas_costs.get(PoolClass::UniswapV2), 70_000);
+        assert_eq!(gas_costs.get(PoolClass::UniswapV3), 120_000);
+    }
+
+    #[test]
+    fn test_budget() {
+        let budget = PathGasBudget::new(10, 300_000);
+        assert_eq!(budget.max_hops(), MAX_PATH_HOPS);
+        assert!(budget.is_within(3, 1_000_000));
+        assert!(budget.is_within(4, 300_000));
+        assert!(!budget.is_within(4, 300_001));
+        assert!(!budget.is_within(6, 0));
+    }
+}
@@ -44,6 +44,12 @@ pub fn get_protocol_by_factory(factory_address: Address) -> PoolProtocol {
         PoolProtocol::Maverick
     } else if factory_address == FactoryAddress::INTEGRAL {
         PoolProtocol::Integral
+    } else if factory_address == FactoryAddress::RAMSES {
+        PoolProtocol::Ramses
+    } else if factory_address == FactoryAddress::THENA {
+        PoolProtocol::Thena
+    } else if factory_address == FactoryAddress::EQUALIZER {
+        PoolProtocol::Equalizer
     } else {
         PoolProtocol::Unknown
     }
@@ -92,12 +98,123 @@ pub enum PoolClass {
     #[serde(rename = "balancer2")]
     #[strum(serialize = "balancer2")]
     BalancerV2,
+    #[serde(rename = "gmx2")]
+    #[strum(serialize = "gmx2")]
+    GmxV2,
+    #[serde(rename = "pendle2")]
+    #[strum(serialize = "pendle2")]
+    PendleV2,
+    #[serde(rename = "woofi2")]
+    #[strum(serialize = "woofi2")]
+    WooFiV2,
     #[serde(rename = "custom")]
     #[strum(serialize = "custom")]
     Custom(u64),
 }
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+/// Callback a pool of the class calls on the swapper during the swap
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CallbackStyle {
+    None,
+    /// `uniswapV2Call` after the output is sent, the input is checked after the callback
+    UniswapV2,
+    /// `swapCallback` with the amounts to pay, the input is paid inside the callback
+    UniswapV3,
+}
+
+/// How the swap input has to reach a pool of the class, see [`PreswapRequirement`]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PreswapKind {
+    Unknown,
+    Transfer,
+    Allowance,
+    Callback,
+    Base,
+}
+
+/// Swap features of a pool class. Pools can be more restrictive than their class, e.g. a UniswapV2 pool of a fork
+/// with a different callback.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct PoolClassCapabilities {
+    pub supports_flash_swap: bool,
+    pub callback_style: CallbackStyle,
+    pub preswap_requirement: PreswapKind,
+    /// Swaps with exact out amount can be encoded
+    pub exact_out_support: bool,
+    /// The pool takes or returns native ETH instead of WETH
+    pub native_eth: bool,
+    /// The swap call checks a min out amount, it is encoded from the calculated out amount of the hop
+    pub quoted_min_out: bool,
+}
+
+impl PoolClassCapabilities {
+    /// Capabilities of classes without swap encoding
+    pub const UNSUPPORTED: Self = Self {
+        supports_flash_swap: false,
+        callback_style: CallbackStyle::None,
+        preswap_requirement: PreswapKind::Unknown,
+        exact_out_support: false,
+        native_eth: false,
+        quoted_min_out: false,
+    };
+
+    const fn swap_only(preswap_requirement: PreswapKind, native_eth: bool) -> Self {
+        Self { preswap_requirement, native_eth, ..Self::UNSUPPORTED }
+    }
+
+    const fn with_quoted_min_out(self) -> Self {
+        Self { quoted_min_out: true, ..self }
+    }
+}
+
+impl PoolClass {
+    /// Swap features of the class. New classes have to be added here explicitly.
+    pub const fn capabilities(&self) -> PoolClassCapabilities {
+        match self {
+            PoolClass::UniswapV2 => PoolClassCapabilities {
+                supports_flash_swap: true,
+                callback_style: CallbackStyle::UniswapV2,
+                preswap_requirement: PreswapKind::Transfer,
+                exact_out_support: true,
+                native_eth: false,
+                quoted_min_out: false,
+            },
+            PoolClass::UniswapV3 | PoolClass::PancakeV3 | PoolClass::Maverick => PoolClassCapabilities {
+                supports_flash_swap: true,
+                callback_style: CallbackStyle::UniswapV3,
+                preswap_requirement: PreswapKind::Callback,
+                exact_out_support: true,
+                native_eth: false,
+                quoted_min_out: false,
+            },
+            PoolClass::Curve => PoolClassCapabilities::swap_only(PreswapKind::Allowance, true),
+            PoolClass::LidoStEth => PoolClassCapabilities::swap_only(PreswapKind::Base, true),
+            PoolClass::LidoWstEth => PoolClassCapabilities::swap_only(PreswapKind::Allowance, false),
+            PoolClass::PendleV2 => PoolClassCapabilities::swap_only(PreswapKind::Allowance, false).with_quoted_min_out(),
+            PoolClass::WooFiV2 => PoolClassCapabilities::swap_only(PreswapKind::Base, false).with_quoted_min_out(),
+            PoolClass::Unknown
+            | PoolClass::UniswapV4
+            | PoolClass::MaverickV2
+            | PoolClass::RocketPool
+            | PoolClass::BalancerV1
+            | PoolClass::BalancerV2
+            | PoolClass::GmxV2
+            | PoolClass::Custom(_) => PoolClassCapabilities::UNSUPPORTED,
+        }
+    }
+
+    /// Fee rate in parts per million of the fee reported by the pools of the class, `None` when the unit is not known.
+    /// Uniswap V2 forks report the share of the input kept after the fee in basis points, V3 pools the fee in parts per million
+    pub fn fee_ppm(&self, fee: U256) -> Option<U256> {
+        match self {
+            PoolClass::UniswapV2 => U256::from(10_000).checked_sub(fee).map(|fee| fee * U256::from(100)),
+            PoolClass::UniswapV3 | PoolClass::PancakeV3 => Some(fee),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum PoolProtocol {
     Unknown,
     AaveV2,
@@ -118,6 +235,9 @@ pub enum PoolProtocol {
     UniswapV4,
     PancakeV3,
     Integral,
+    Ramses,
+    Thena,
+    Equalizer,
     Maverick,
     MaverickV2,
     Curve,
@@ -126,6 +246,9 @@ pub enum PoolProtocol {
     RocketEth,
     BalancerV1,
     BalancerV2,
+    GmxV2,
+    PendleV2,
+    WooFiV2,
     Custom(u64),
 }
 
@@ -151,6 +274,9 @@ impl Display for PoolProtocol {
             Self::Shibaswap => "Shibaswap",
             Self::Safeswap => "Safeswap",
             Self::Integral => "Integral",
+            Self::Ramses => "Ramses",
+            Self::Thena => "Thena",
+            Self::Equalizer => "Equalizer",
             Self::Maverick => "Maverick",
             Self::MaverickV2 => "MaverickV2",
             Self::Curve => "Curve",
@@ -159,6 +285,9 @@ impl Display for PoolProtocol {
             Self::RocketEth => "RocketEth",
             Self::BalancerV1 => "BalancerV1",
             Self::BalancerV2 => "BalancerV2",
+            Self::GmxV2 => "GmxV2",
+            Self::PendleV2 => "PendleV2",
+            Self::WooFiV2 => "WooFiV2",
             Self::Custom(x) => "Custom",
         };
         write!(f, "{}", protocol_name)
@@ -231,6 +360,16 @@ impl<LDT: LoomDataTypes> PoolWrapper<LDT> {
     pub fn new(pool: Arc<dyn Pool<LDT>>) -> Self {
         PoolWrapper { pool }
     }
+
+    /// Pool can be flash swapped and its class has flash swap encoding
+    pub fn supports_flash_swap(&self) -> bool {
+        self.pool.can_flash_swap() && self.pool.get_class().capabilities().supports_flash_swap
+    }
+
+    /// Pool can calculate in amounts and its class has exact out swap encoding
+    pub fn supports_exact_out(&self) -> bool {
+        self.pool.can_calculate_in_amount() && self.pool.get_class().capabilities().exact_out_support
+    }
 }
 
 impl<T: 'static + Pool<LoomDataTypesEthereum>> From<T> for PoolWrapper<LoomDataTypesEthereum> {
@@ -252,6 +391,11 @@ pub trait Pool<LDT: LoomDataTypes = LoomDataTypesEthereum>: Sync + Send {
 
     fn get_fee(&self) -> U256;
 
+    /// Fee applied when swapping from `token_from` to `token_to`, for pools with direction dependent fees
+    fn get_fee_by_direction(&self, _token_from: &LDT::Address, _token_to: &LDT::Address) -> U256 {
+        self.get_fee()
+    }
+
     fn get_tokens(&self) -> Vec<LDT::Address>;
 
     fn get_swap_directions(&self) -> Vec<SwapDirection<LDT>>;
@@ -292,6 +436,16 @@ pub trait Pool<LDT: LoomDataTypes = LoomDataTypesEthereum>: Sync + Send {
     fn get_pool_manager_cells(&self) -> Vec<(Address, Vec<U256>)> {
         vec![]
     }
+
+    /// Factory that created the pool, if known
+    fn get_factory(&self) -> Option<Address> {
+        None
+    }
+
+    /// LP token the pool mints and burns in its swap directions, if any
+    fn get_lp_token(&self) -> Option<Address> {
+        None
+    }
 }
 
 pub struct DefaultAbiSwapEncoder {}
@@ -324,6 +478,16 @@ where
 }
 
 impl<LDT: LoomDataTypes> PreswapRequirement<LDT> {
+    pub fn kind(&self) -> PreswapKind {
+        match self {
+            PreswapRequirement::Unknown => PreswapKind::Unknown,
+            PreswapRequirement::Transfer(_) => PreswapKind::Transfer,
+            PreswapRequirement::Allowance => PreswapKind::Allowance,
+            PreswapRequirement::Callback => PreswapKind::Callback,
+            PreswapRequirement::Base => PreswapKind::Base,
+        }
+    }
+
     pub fn address_or(&self, default_address: LDT::Address) -> LDT::Address {
         match self {
             PreswapRequirement::Transfer(address) => *address,
@@ -382,11 +546,28 @@ pub trait PoolAbiEncoder: Send + Sync {
 
 #[cfg(test)]
 mod test {
-    use crate::PoolClass;
+    use crate::{CallbackStyle, PoolClass, PoolClassCapabilities, PreswapKind};
 
     #[test]
     fn test_strum() {
         println!("{}", PoolClass::Unknown);
         println!("{}", PoolClass::UniswapV2);
     }
+
+    #[test]
+    fn test_capabilities() {
+        let uni2 = PoolClass::UniswapV2.capabilities();
+        assert!(uni2.supports_flash_swap && uni2.exact_out_support);
+        assert_eq!(uni2.callback_style, CallbackStyle::UniswapV2);
+        assert_eq!(uni2.preswap_requirement, PreswapKind::Transfer);
+
+        assert_eq!(PoolClass::PancakeV3.capabilities(), PoolClass::UniswapV3.capabilities());
+        assert_eq!(PoolClass::UniswapV3.capabilities().callback_style, CallbackStyle::UniswapV3);
+
+        let curve = PoolClass::Curve.capabilities();
+        assert!(!curve.supports_flash_swap && !curve.exact_out_support && curve.native_eth);
+
+        assert_eq!(PoolClass::GmxV2.capabilities(), PoolClassCapabilities::UNSUPPORTED);
+        assert_eq!(PoolClass::Custom(1).capabilities(), PoolClassCapabilities::UNSUPPORTED);
+    }
 }
@@ -1,11 +1,29 @@
-use crate::PoolClass;
-use std::collections::HashMap;
+use crate::state_proof::StateLoadingMode;
+use crate::{PathBuildConfig, PathGasBudget, Pool, PoolClass};
+use alloy_primitives::Address;
+use loom_types_blockchain::LoomDataTypes;
+use std::collections::{HashMap, HashSet};
 use strum::IntoEnumIterator;
 
-#[derive(Clone)]
+#[derive(Clone, Debug)]
 pub struct PoolsLoadingConfig {
     threads: Option<usize>,
     is_enabled: HashMap<PoolClass, bool>,
+    // if set only pools created by these factories are loaded
+    allowed_factories: Option<HashSet<Address>>,
+    denied_factories: HashSet<Address>,
+    // token pairs and pools excluded from path building
+    denied_pairs: HashSet<(Address, Address)>,
+    denied_pools: HashSet<Address>,
+    // V3 pools loaded with their oracle observations for TWAP checks
+    twap_pools: HashSet<Address>,
+    // base tokens of WooFi pools, the address book tokens of the chain if not set
+    woofi_base_tokens: Option<Vec<Address>>,
+    // if set paths are built up to five hops within the gas budget
+    path_gas_budget: Option<PathGasBudget>,
+    // token and pool repetition rules of built paths
+    path_build: PathBuildConfig,
+    state_loading: StateLoadingMode,
 }
 
 impl PoolsLoadingConfig {
@@ -15,7 +33,19 @@ impl PoolsLoadingConfig {
             is_enabled.insert(pool_class, true);
         }
 
-        Self { threads: None, is_enabled }
+        Self {
+            threads: None,
+            is_enabled,
+            allowed_factories: None,
+            denied_factories: HashSet::new(),
+            denied_pairs: HashSet::new(),
+            denied_pools: HashSet::new(),
+            twap_pools: HashSet::new(),
+            woofi_base_tokens: None,
+            path_gas_budget: None,
+            path_build: PathBuildConfig::default(),
+            state_loading: StateLoadingMode::default(),
+        }
     }
 
     pub fn disable_all(self) -> Self {
@@ -36,7 +66,7 @@ impl PoolsLoadingConfig {
 
     pub fn disable(self, pool_class: PoolClass) -> Self {
         let mut is_enabled = self.is_enabled;
-        is_enabled.insert(pool_class, true);
+        is_enabled.insert(pool_class, false);
 
         Self { is_enabled, ..self }
     }
@@ -45,6 +75,74 @@ impl PoolsLoadingConfig {
         self.is_enabled.get(&pool_class).is_some_and(|s| *s)
     }
 
+    /// Load only pools created by the given factories. Pools without a known factory are not affected.
+    pub fn allow_factories<I: IntoIterator<Item = Address>>(self, factories: I) -> Self {
+        let mut allowed_factories = self.allowed_factories.unwrap_or_default();
+        allowed_factories.extend(factories);
+
+        Self { allowed_factories: Some(allowed_factories), ..self }
+    }
+
+    pub fn deny_factories<I: IntoIterator<Item = Address>>(self, factories: I) -> Self {
+        let mut denied_factories = self.denied_factories;
+        denied_factories.extend(factories);
+
+        Self { denied_factories, ..self }
+    }
+
+    pub fn is_factory_allowed(&self, factory: &Address) -> bool {
+        !self.denied_factories.contains(factory) && self.allowed_factories.as_ref().is_none_or(|allowed| allowed.contains(factory))
+    }
+
+    /// Exclude swaps between the tokens of the pairs from path building, e.g. tokens with transfer hooks griefing searchers
+    pub fn deny_pairs<I: IntoIterator<Item = (Address, Address)>>(self, pairs: I) -> Self {
+        let mut denied_pairs = self.denied_pairs;
+        denied_pairs.extend(pairs);
+
+        Self { denied_pairs, ..self }
+    }
+
+    pub fn denied_pairs(&self) -> &HashSet<(Address, Address)> {
+        &self.denied_pairs
+    }
+
+    /// Exclude the pools from path building
+    pub fn deny_pools<I: IntoIterator<Item = Address>>(self, pools: I) -> Self {
+        let mut denied_pools = self.denied_pools;
+        denied_pools.extend(pools);
+
+        Self { denied_pools, ..self }
+    }
+
+    pub fn denied_pools(&self) -> &HashSet<Address> {
+        &self.denied_pools
+    }
+
+    /// Load the oracle observations of the V3 pools with their state for TWAP checks
+    pub fn with_twap_pools<I: IntoIterator<Item = Address>>(self, pools: I) -> Self {
+        let mut twap_pools = self.twap_pools;
+        twap_pools.extend(pools);
+
+        Self { twap_pools, ..self }
+    }
+
+    pub fn is_twap_pool(&self, pool: &Address) -> bool {
+        self.twap_pools.contains(pool)
+    }
+
+    pub fn with_woofi_base_tokens(self, woofi_base_tokens: Vec<Address>) -> Self {
+        Self { woofi_base_tokens: Some(woofi_base_tokens), ..self }
+    }
+
+    pub fn woofi_base_tokens(&self) -> Option<&Vec<Address>> {
+        self.woofi_base_tokens.as_ref()
+    }
+
+    /// Check pool class and factory of the pool
+    pub fn is_pool_allowed<LDT: LoomDataTypes>(&self, pool: &dyn Pool<LDT>) -> bool {
+        self.is_enabled(pool.get_class()) && pool.get_factory().is_none_or(|factory| self.is_factory_allowed(&factory))
+    }
+
     pub fn with_threads(self, threads: usize) -> Self {
         Self { threads: Some(threads), ..self }
     }
@@ -52,6 +150,30 @@ impl PoolsLoadingConfig {
     pub fn threads(&self) -> Option<usize> {
         self.threads
     }
+
+    pub fn with_path_gas_budget(self, path_gas_budget: PathGasBudget) -> Self {
+        Self { path_gas_budget: Some(path_gas_budget), ..self }
+    }
+
+    pub fn path_gas_budget(&self) -> Option<&PathGasBudget> {
+        self.path_gas_budget.as_ref()
+    }
+
+    pub fn with_path_build(self, path_build: PathBuildConfig) -> Self {
+        Self { path_build, ..self }
+    }
+
+    pub fn path_build(&self) -> &PathBuildConfig {
+        &self.path_build
+    }
+
+    pub fn with_state_loading(self, state_loading: StateLoadingMode) -> Self {
+        Self { state_loading, ..self }
+    }
+
+    pub fn state_loading(&self) -> StateLoadingMode {
+        self.state_loading
+    }
 }
 
 impl Default for PoolsLoadingConfig {
@@ -59,3 +181,29 @@ impl Default for PoolsLoadingConfig {
         PoolsLoadingConfig::new()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_disable() {
+        let config = PoolsLoadingConfig::new().disable(PoolClass::Curve);
+        assert!(!config.is_enabled(PoolClass::Curve));
+        assert!(config.is_enabled(PoolClass::UniswapV2));
+    }
+
+    #[test]
+    fn test_factories() {
+        let allowed = Address::repeat_byte(1);
+        let denied = Address::repeat_byte(2);
+
+        let config = PoolsLoadingConfig::new().deny_factories([denied]);
+        assert!(config.is_factory_allowed(&allowed));
+        assert!(!config.is_factory_allowed(&denied));
+
+        let config = config.allow_factories([allowed]);
+        assert!(config.is_factory_allowed(&allowed));
+        assert!(!config.is_factory_allowed(&Address::repeat_byte(3)));
+    }
+}
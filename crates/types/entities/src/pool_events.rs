@@ -0,0 +1,140 @@
+use std::collections::VecDeque;
+use std::fmt::{Display, Formatter};
+
+use loom_types_blockchain::{LoomDataTypes, LoomDataTypesEthereum};
+
+use crate::{PoolClass, PoolId};
+
+/// Events kept in the log of the market when no capacity is configured
+pub const DEFAULT_POOL_EVENTS_CAPACITY: usize = 4096;
+
+/// Lifecycle change of a pool of the market
+#[derive(Clone, Debug, PartialEq)]
+pub enum PoolEvent<LDT: LoomDataTypes = LoomDataTypesEthereum> {
+    Added {
+        pool_id: PoolId<LDT>,
+        pool_class: PoolClass,
+    },
+    /// Swap paths through the pool were added
+    Updated {
+        pool_id: PoolId<LDT>,
+        paths_added: usize,
+    },
+    /// Pool disabled, or enabled again if `disabled` is false. Pools of classes toggled by the kill switch are not reported
+    Disabled {
+        pool_id: PoolId<LDT>,
+        disabled: bool,
+    },
+    Removed {
+        pool_id: PoolId<LDT>,
+    },
+}
+
+impl<LDT: LoomDataTypes> PoolEvent<LDT> {
+    pub fn pool_id(&self) -> PoolId<LDT> {
+        match self {
+            Self::Added { pool_id, .. } | Self::Updated { pool_id, .. } | Self::Disabled { pool_id, .. } | Self::Removed { pool_id } => {
+                *pool_id
+            }
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Added { .. } => "added",
+            Self::Updated { .. } => "updated",
+            Self::Disabled { disabled: true, .. } => "disabled",
+            Self::Disabled { disabled: false, .. } => "enabled",
+            Self::Removed { .. } => "removed",
+        }
+    }
+}
+
+impl<LDT: LoomDataTypes> Display for PoolEvent<LDT> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.kind(), self.pool_id())
+    }
+}
+
+/// Pool event with its sequence number in the log
+#[derive(Clone, Debug, PartialEq)]
+pub struct PoolEventRecord<LDT: LoomDataTypes = LoomDataTypesEthereum> {
+    pub seq: u64,
+    pub event: PoolEvent<LDT>,
+}
+
+/// Latest pool events of the market numbered from 1, the oldest are dropped above the capacity. Readers keep the last
+/// sequence number they have seen instead of rescanning the market for changes
+#[derive(Clone, Debug)]
+pub struct PoolEventLog<LDT: LoomDataTypes = LoomDataTypesEthereum> {
+    records: VecDeque<PoolEventRecord<LDT>>,
+    capacity: usize,
+    last_seq: u64,
+}
+
+impl<LDT: LoomDataTypes> Default for PoolEventLog<LDT> {
+    fn default() -> Self {
+        Self::new(DEFAULT_POOL_EVENTS_CAPACITY)
+    }
+}
+
+impl<LDT: LoomDataTypes> PoolEventLog<LDT> {
+    pub fn new(capacity: usize) -> Self {
+        Self { records: VecDeque::new(), capacity: capacity.max(1), last_seq: 0 }
+    }
+
+    /// Append the event, returns its sequence number
+    pub fn push(&mut self, event: PoolEvent<LDT>) -> u64 {
+        self.last_seq += 1;
+        self.records.push_back(PoolEventRecord { seq: self.last_seq, event });
+        while self.records.len() > self.capacity {
+            self.records.pop_front();
+        }
+        self.last_seq
+    }
+
+    /// Sequence number of the latest event, zero before the first one
+    pub fn last_seq(&self) -> u64 {
+        self.last_seq
+    }
+
+    /// Sequence number of the oldest event kept, events before it were dropped
+    pub fn first_seq(&self) -> Option<u64> {
+        self.records.front().map(|record| record.seq)
+    }
+
+    /// Events after the sequence number, the oldest first
+    pub fn since(&self, seq: u64) -> impl Iterator<Item = &PoolEventRecord<LDT>> {
+        // sequence numbers of the kept records are contiguous
+        let skip = match self.first_seq() {
+            Some(first_seq) if seq >= first_seq => (seq - first_seq + 1) as usize,
+            _ => 0,
+        };
+        self.records.iter().skip(skip)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloy_primitives::Address;
+
+    #[test]
+    fn test_pool_event_log() {
+        let (pool0, pool1) = (PoolId::Address(Address::repeat_byte(0x10)), PoolId::Address(Address::repeat_byte(0x11)));
+        let mut log: PoolEventLog = PoolEventLog::new(2);
+        assert_eq!(log.last_seq(), 0);
+        assert_eq!(log.since(0).count(), 0);
+
+        assert_eq!(log.push(PoolEvent::Added { pool_id: pool0, pool_class: PoolClass::UniswapV2 }), 1);
+        assert_eq!(log.push(PoolEvent::Disabled { pool_id: pool0, disabled: true }), 2);
+        assert_eq!(log.push(PoolEvent::Removed { pool_id: pool1 }), 3);
+
+        // the first event is dropped
+        assert_eq!(log.first_seq(), Some(2));
+        assert_eq!(log.since(0).map(|record| record.seq).collect::<Vec<_>>(), vec![2, 3]);
+        assert_eq!(log.since(2).map(|record| record.event.clone()).collect::<Vec<_>>(), vec![PoolEvent::Removed { pool_id: pool1 }]);
+        assert_eq!(log.since(3).count(), 0);
+        assert_eq!(log.since(2).next().unwrap().event.to_string(), format!("removed {pool1}"));
+    }
+}
@@ -35,6 +35,10 @@ where
     ) -> Result<PoolWrapper<LDT>>;
     fn is_code(&self, code: &Bytes) -> bool;
     fn protocol_loader(&self) -> Result<Pin<Box<dyn Stream<Item = (PoolId, PoolClass)> + Send>>>;
+    /// Factory addresses of pools handled by the loader, registered in the factory resolver of PoolLoaders
+    fn factories(&self) -> Vec<LDT::Address> {
+        Vec::new()
+    }
 }
 
 pub struct PoolLoaders<P, N = Ethereum, LDT = LoomDataTypesEthereum>
@@ -46,6 +50,7 @@ where
     provider: Option<P>,
     config: Option<PoolsLoadingConfig>,
     pub map: HashMap<PoolClass, Arc<dyn PoolLoader<P, N, LDT>>>,
+    factories: HashMap<LDT::Address, PoolClass>,
 }
 
 impl<P, N, LDT> PoolLoaders<P, N, LDT>
@@ -63,13 +68,49 @@ where
     }
 
     pub fn with_provider<NP: Provider<N>>(self, provider: NP) -> PoolLoaders<NP, N, LDT> {
-        PoolLoaders { provider: Some(provider), map: HashMap::new(), config: self.config }
+        PoolLoaders { provider: Some(provider), map: HashMap::new(), config: self.config, factories: self.factories }
     }
 
     pub fn add_loader<L: PoolLoader<P, N, LDT> + Send + Sync + Clone + 'static>(self, pool_class: PoolClass, loader: L) -> Self {
-        let mut map = self.map;
-        map.insert(pool_class, Arc::new(loader));
-        Self { map, ..self }
+        let mut pool_loaders = self;
+        pool_loaders.register_loader(pool_class, Arc::new(loader));
+        pool_loaders
+    }
+
+    pub fn with_factory(self, factory_address: LDT::Address, pool_class: PoolClass) -> Self {
+        let mut pool_loaders = self;
+        pool_loaders.register_factory(factory_address, pool_class);
+        pool_loaders
+    }
+
+    /// Register a loader for the pool class, replacing the previous one. Factories declared by the loader are registered too.
+    pub fn register_loader(&mut self, pool_class: PoolClass, loader: Arc<dyn PoolLoader<P, N, LDT>>) {
+        for factory_address in loader.factories() {
+            self.factories.insert(factory_address, pool_class);
+        }
+        self.map.insert(pool_class, loader);
+    }
+
+    pub fn register_factory(&mut self, factory_address: LDT::Address, pool_class: PoolClass) {
+        self.factories.insert(factory_address, pool_class);
+    }
+
+    pub fn get_loader(&self, pool_class: &PoolClass) -> Option<Arc<dyn PoolLoader<P, N, LDT>>> {
+        self.map.get(pool_class).cloned()
+    }
+
+    pub fn pool_classes(&self) -> Vec<PoolClass> {
+        self.map.keys().cloned().collect()
+    }
+
+    /// Resolve pool class by factory address of the pool
+    pub fn pool_class_by_factory(&self, factory_address: &LDT::Address) -> Option<PoolClass> {
+        self.factories.get(factory_address).cloned()
+    }
+
+    /// Addresses of the registered factories
+    pub fn factory_addresses(&self) -> Vec<LDT::Address> {
+        self.factories.keys().cloned().collect()
     }
 }
 
@@ -80,7 +121,7 @@ where
     LDT: LoomDataTypes,
 {
     fn default() -> Self {
-        Self { provider: None, map: Default::default(), config: None }
+        Self { provider: None, map: Default::default(), config: None, factories: Default::default() }
     }
 }
 
@@ -0,0 +1,377 @@
+use std::collections::HashMap;
+
+use alloy_primitives::utils::Unit;
+use alloy_primitives::U256;
+use eyre::ErrReport;
+use revm::primitives::Env;
+use revm::DatabaseRef;
+use tracing::trace;
+
+use crate::Market;
+use loom_types_blockchain::{LoomDataTypes, LoomDataTypesEthereum};
+
+const ONE_ETHER: U256 = Unit::ETHER.wei_const();
+
+/// Hops from WETH a token is valued through by default
+pub const DEFAULT_PRICE_GRAPH_DEPTH: usize = 2;
+
+/// USD value with 18 decimals of the smallest amount of a token a swap hop may take or return, 0.01 USD
+pub const DEFAULT_DUST_USD: U256 = U256::from_limbs([10_000_000_000_000_000, 0, 0, 0]);
+
+/// Prices of market tokens in WETH at a block, derived from the deepest pool of every hop from WETH.
+/// A pool is considered deepest when it returns the most for the same quoted value, i.e. has the lowest price impact.
+#[derive(Clone, Debug)]
+pub struct PriceGraph<LDT: LoomDataTypes = LoomDataTypesEthereum> {
+    block_number: u64,
+    // quoted value of every hop in WETH
+    quote_amount: U256,
+    max_depth: usize,
+    // stablecoins averaged into the USD price
+    usd_tokens: Vec<LDT::Address>,
+    // token -> token amount per one ETH, same as the token eth price
+    prices: HashMap<LDT::Address, U256>,
+    // USD with 18 decimals per one ETH
+    usd_price: Option<U256>,
+    // token -> decimals of the valued tokens
+    decimals: HashMap<LDT::Address, u8>,
+}
+
+impl<LDT: LoomDataTypes> Default for PriceGraph<LDT> {
+    fn default() -> Self {
+        Self {
+            block_number: 0,
+            quote_amount: ONE_ETHER,
+            max_depth: DEFAULT_PRICE_GRAPH_DEPTH,
+            usd_tokens: Vec::new(),
+            prices: HashMap::new(),
+            usd_price: None,
+            decimals: HashMap::new(),
+        }
+    }
+}
+
+impl<LDT: LoomDataTypes> PriceGraph<LDT> {
+    pub fn new(usd_tokens: Vec<LDT::Address>) -> Self {
+        Self { usd_tokens, ..Self::default() }
+    }
+
+    pub fn with_quote_amount(self, quote_amount: U256) -> Self {
+        Self { quote_amount, ..self }
+    }
+
+    pub fn with_max_depth(self, max_depth: usize) -> Self {
+        Self { max_depth, ..self }
+    }
+
+    /// Block of the last computation
+    pub fn block_number(&self) -> u64 {
+        self.block_number
+    }
+
+    /// Recompute prices for `block_number` over the state of the previous block.
+    /// Prices are computed once per block, returns false if they are already computed.
+    pub fn update<DB: DatabaseRef<Error = ErrReport>>(&mut self, block_number: u64, market: &Market<LDT>, state: &DB, env: Env) -> bool {
+        if self.block_number >= block_number && !self.prices.is_empty() {
+            return false;
+        }
+
+        let mut prices: HashMap<LDT::Address, U256> = HashMap::new();
+        prices.insert(LDT::WETH, ONE_ETHER);
+        let mut frontier = vec![LDT::WETH];
+
+        for _ in 0..self.max_depth {
+            // token -> best price of the level, the largest output comes from the deepest pool
+            let mut level_prices: HashMap<LDT::Address, U256> = HashMap::new();
+
+            for token_from in frontier.iter() {
+                let Some(price_from) = prices.get(token_from).copied() else { continue };
+                let Some(tokens_to) = market.get_token_tokens(token_from) else { continue };
+                let amount_in = price_from * self.quote_amount / ONE_ETHER;
+                if amount_in.is_zero() {
+                    continue;
+                }
+
+                for token_to in tokens_to.iter() {
+                    if prices.contains_key(token_to) {
+                        continue;
+                    }
+                    let Some(pools) = market.get_token_token_pools(token_from, token_to) else { continue };
+
+                    for pool_id in pools.iter() {
+                        if market.is_pool_disabled(pool_id) {
+                            continue;
+                        }
+                        let Some(pool) = market.get_pool(pool_id) else { continue };
+                        match pool.calculate_out_amount(state, env.clone(), token_from, token_to, amount_in) {
+                            Ok((amount_out, _)) => {
+                                let price = amount_out * ONE_ETHER / self.quote_amount;
+                                if level_prices.get(token_to).is_none_or(|level_price| price > *level_price) {
+                                    level_prices.insert(*token_to, price);
+                                }
+                            }
+                            Err(error) => {
+                                trace!(%error, %pool_id, "Price graph quote failed")
+                            }
+                        }
+                    }
+                }
+            }
+
+            frontier = level_prices.iter().filter(|(_, price)| !price.is_zero()).map(|(token, _)| *token).collect();
+            prices.extend(level_prices.into_iter().filter(|(_, price)| !price.is_zero()));
+            if frontier.is_empty() {
+                break;
+            }
+        }
+
+        self.decimals =
+            prices.keys().filter_map(|address| market.get_token(address).map(|token| (*address, token.get_decimals()))).collect();
+        self.prices = prices;
+        self.usd_price = self.calc_usd_price();
+        self.block_number = block_number;
+        true
+    }
+
+    // average price of the USD tokens normalized to 18 decimals
+    fn calc_usd_price(&self) -> Option<U256> {
+        let usd_prices: Vec<U256> = self
+            .usd_tokens
+            .iter()
+            .filter_map(|address| {
+                let price = self.prices.get(address)?;
+                let decimals = *self.decimals.get(address)?;
+                Some(price * U256::from(10).pow(U256::from(18u8.saturating_sub(decimals))))
+            })
+            .collect();
+        if usd_prices.is_empty() {
+            return None;
+        }
+        Some(usd_prices.iter().sum::<U256>() / U256::from(usd_prices.len()))
+    }
+
+    /// Token amount per one ETH
+    pub fn price(&self, address: &LDT::Address) -> Option<U256> {
+        self.prices.get(address).copied()
+    }
+
+    pub fn prices(&self) -> impl Iterator<Item = (&LDT::Address, &U256)> {
+        self.prices.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.prices.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.prices.is_empty()
+    }
+
+    /// USD with 18 decimals per one ETH
+    pub fn usd_price(&self) -> Option<U256> {
+        self.usd_price
+    }
+
+    pub fn calc_eth_value(&self, address: &LDT::Address, value: U256) -> Option<U256> {
+        self.price(address).filter(|price| !price.is_zero()).map(|price| value * ONE_ETHER / price)
+    }
+
+    pub fn calc_token_value_from_eth(&self, address: &LDT::Address, eth_value: U256) -> Option<U256> {
+        self.price(address).map(|price| eth_value * price / ONE_ETHER)
+    }
+
+    /// Token amount worth the USD value with 18 decimals
+    pub fn calc_token_value_from_usd(&self, address: &LDT::Address, usd_value: U256) -> Option<U256> {
+        let usd_price = self.usd_price.filter(|usd_price| !usd_price.is_zero())?;
+        self.calc_token_value_from_eth(address, usd_value * ONE_ETHER / usd_price)
+    }
+
+    /// Value of the token amount in USD with 18 decimals
+    pub fn calc_usd_value(&self, address: &LDT::Address, value: U256) -> Option<U256> {
+        let eth_value = self.calc_eth_value(address, value)?;
+        self.usd_price.map(|usd_price| eth_value * usd_price / ONE_ETHER)
+    }
+
+    /// Value of the token amount in USD, for logs and notifications
+    pub fn calc_usd_value_f64(&self, address: &LDT::Address, value: U256) -> Option<f64> {
+        self.calc_usd_value(address, value).map(|usd_value| {
+            let (usd, usd_fraction) = usd_value.div_rem(ONE_ETHER);
+            usd.saturating_to::<u64>() as f64 + usd_fraction.saturating_to::<u64>() as f64 / 1e18
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::required_state::RequiredState;
+    use crate::{Pool, PoolAbiEncoder, PoolClass, PoolId, PoolProtocol, PreswapRequirement, SwapDirection, Token};
+    use alloy_primitives::Address;
+    use eyre::{eyre, Result};
+    use loom_evm_db::LoomDBType;
+    use std::any::Any;
+
+    // pays `rate` raw token1 per 10^18 raw token0 and back
+    struct RatePool {
+        address: Address,
+        token0: Address,
+        token1: Address,
+        rate: U256,
+    }
+
+    impl Pool for RatePool {
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+
+        fn get_class(&self) -> PoolClass {
+            PoolClass::UniswapV2
+        }
+
+        fn get_protocol(&self) -> PoolProtocol {
+            PoolProtocol::UniswapV2
+        }
+
+        fn get_address(&self) -> Address {
+            self.address
+        }
+
+        fn get_pool_id(&self) -> PoolId {
+            PoolId::Address(self.address)
+        }
+
+        fn get_fee(&self) -> U256 {
+            U256::ZERO
+        }
+
+        fn get_tokens(&self) -> Vec<Address> {
+            vec![self.token0, self.token1]
+        }
+
+        fn get_swap_directions(&self) -> Vec<SwapDirection> {
+            vec![(self.token0, self.token1).into(), (self.token1, self.token0).into()]
+        }
+
+        fn calculate_out_amount(
+            &self,
+            _state: &dyn DatabaseRef<Error = ErrReport>,
+            _env: Env,
+            token_address_from: &Address,
+            _token_address_to: &Address,
+            in_amount: U256,
+        ) -> Result<(U256, u64)> {
+            if *token_address_from == self.token0 {
+                Ok((in_amount * self.rate / ONE_ETHER, 100_000))
+            } else {
+                Ok((in_amount * ONE_ETHER / self.rate, 100_000))
+            }
+        }
+
+        fn calculate_in_amount(
+            &self,
+            _state: &dyn DatabaseRef<Error = ErrReport>,
+            _env: Env,
+            _token_address_from: &Address,
+            _token_address_to: &Address,
+            _out_amount: U256,
+        ) -> Result<(U256, u64)> {
+            Err(eyre!("NOT_IMPLEMENTED"))
+        }
+
+        fn can_flash_swap(&self) -> bool {
+            false
+        }
+
+        fn can_calculate_in_amount(&self) -> bool {
+            false
+        }
+
+        fn get_abi_encoder(&self) -> Option<&dyn PoolAbiEncoder> {
+            None
+        }
+
+        fn get_read_only_cell_vec(&self) -> Vec<U256> {
+            Vec::new()
+        }
+
+        fn get_state_required(&self) -> Result<RequiredState> {
+            Ok(RequiredState::new())
+        }
+
+        fn is_native(&self) -> bool {
+            false
+        }
+
+        fn preswap_requirement(&self) -> PreswapRequirement {
+            PreswapRequirement::Base
+        }
+    }
+
+    #[test]
+    fn test_update() -> Result<()> {
+        let weth = LoomDataTypesEthereum::WETH;
+        let (usdc, dai, far) = (Address::repeat_byte(1), Address::repeat_byte(2), Address::repeat_byte(3));
+        let mut market = Market::default();
+        market.add_token(Token::new(weth));
+        market.add_token(Token::new_with_data(usdc, Some("USDC".to_string()), None, Some(6), true, false));
+        market.add_token(Token::new(dai));
+        market.add_token(Token::new(far));
+        // the deeper WETH/USDC pool returns 2000 USDC per ETH, DAI and the far token are valued through USDC and DAI
+        market.add_pool(RatePool {
+            address: Address::repeat_byte(0x10),
+            token0: weth,
+            token1: usdc,
+            rate: U256::from(1_990_000_000u64),
+        })?;
+        market.add_pool(RatePool {
+            address: Address::repeat_byte(0x11),
+            token0: weth,
+            token1: usdc,
+            rate: U256::from(2_000_000_000u64),
+        })?;
+        market.add_pool(RatePool {
+            address: Address::repeat_byte(0x12),
+            token0: usdc,
+            token1: dai,
+            rate: U256::from(10).pow(U256::from(30)),
+        })?;
+        market.add_pool(RatePool { address: Address::repeat_byte(0x13), token0: dai, token1: far, rate: ONE_ETHER })?;
+
+        let mut price_graph = PriceGraph::<LoomDataTypesEthereum>::new(vec![usdc, dai]);
+        assert!(price_graph.update(10, &market, &LoomDBType::default(), Env::default()));
+
+        assert_eq!(price_graph.block_number(), 10);
+        assert_eq!(price_graph.price(&weth), Some(ONE_ETHER));
+        assert_eq!(price_graph.price(&usdc), Some(U256::from(2_000_000_000u64)));
+        assert_eq!(price_graph.price(&dai), Some(U256::from(2000) * ONE_ETHER));
+        // three hops from WETH, deeper than the default depth
+        assert_eq!(price_graph.price(&far), None);
+        assert_eq!(price_graph.usd_price(), Some(U256::from(2000) * ONE_ETHER));
+
+        assert!(!price_graph.update(10, &market, &LoomDBType::default(), Env::default()));
+        assert!(price_graph.update(11, &market, &LoomDBType::default(), Env::default()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_values() {
+        let usdc = Address::repeat_byte(1);
+        let dai = Address::repeat_byte(2);
+        let mut price_graph = PriceGraph::<LoomDataTypesEthereum>::new(vec![usdc, dai]);
+        price_graph.prices.insert(LoomDataTypesEthereum::WETH, ONE_ETHER);
+        // 2000 USDC and 2010 DAI per ETH
+        price_graph.prices.insert(usdc, U256::from(2_000_000_000u64));
+        price_graph.prices.insert(dai, U256::from(2010) * ONE_ETHER);
+        price_graph.decimals.insert(usdc, 6);
+        price_graph.decimals.insert(dai, 18);
+        price_graph.usd_price = price_graph.calc_usd_price();
+
+        assert_eq!(price_graph.usd_price(), Some(U256::from(2005) * ONE_ETHER));
+        assert_eq!(price_graph.calc_eth_value(&usdc, U256::from(1_000_000_000u64)), Some(ONE_ETHER / U256::from(2)));
+        assert_eq!(price_graph.calc_token_value_from_eth(&dai, ONE_ETHER), Some(U256::from(2010) * ONE_ETHER));
+        assert_eq!(price_graph.calc_usd_value(&LoomDataTypesEthereum::WETH, ONE_ETHER), Some(U256::from(2005) * ONE_ETHER));
+        assert_eq!(price_graph.calc_usd_value_f64(&usdc, U256::from(2_000_000_000u64)), Some(2005.0));
+        assert_eq!(price_graph.calc_token_value_from_usd(&LoomDataTypesEthereum::WETH, U256::from(2005) * ONE_ETHER), Some(ONE_ETHER));
+        assert_eq!(price_graph.calc_token_value_from_usd(&usdc, DEFAULT_DUST_USD), Some(U256::from(9975)));
+        assert!(price_graph.calc_eth_value(&Address::repeat_byte(3), ONE_ETHER).is_none());
+    }
+}
@@ -0,0 +1,220 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use alloy_primitives::{Address, U256};
+use eyre::{eyre, ErrReport, OptionExt, Result};
+use revm::primitives::Env;
+use revm::DatabaseRef;
+
+use crate::{Market, PoolId, PoolWrapper, SwapAmountType, SwapLine, SwapPath, Token};
+
+/// Best route of a quote with the expected out amount at the block of the quote
+#[derive(Clone, Debug)]
+pub struct Quote {
+    pub swap_line: SwapLine,
+    pub amount_out: U256,
+    pub gas_used: u64,
+    /// Block of the market state the quote was calculated at
+    pub block_number: u64,
+}
+
+/// Synchronous quoting over a market and the market state its pools are at, e.g. the latest market view with the market
+/// state under its read lock. Nothing is copied, quotes run on the caller thread
+pub struct Quoter<'a, DB> {
+    market: &'a Market,
+    state_db: &'a DB,
+    block_number: u64,
+    block_timestamp: u64,
+}
+
+impl<'a, DB: DatabaseRef<Error = ErrReport>> Quoter<'a, DB> {
+    pub fn new(market: &'a Market, state_db: &'a DB, block_number: u64) -> Self {
+        Self { market, state_db, block_number, block_timestamp: 0 }
+    }
+
+    pub fn with_block_timestamp(self, block_timestamp: u64) -> Self {
+        Self { block_timestamp, ..self }
+    }
+
+    /// Routes from the token to the token through a pool of both, and the parts of the loaded paths starting with the
+    /// token and ending at the first following token out. Routes through disabled pools are skipped
+    pub fn routes(&self, token_in: &Address, token_out: &Address) -> Vec<SwapPath> {
+        let market = self.market;
+        let token = |address: &Address| market.get_token(address).unwrap_or_else(|| Arc::new(Token::new(*address)));
+        let is_enabled = |pool: &PoolWrapper| !market.is_pool_disabled(&pool.get_pool_id());
+
+        let mut routes = Vec::new();
+        let mut seen: HashSet<Vec<PoolId>> = HashSet::new();
+        for pool_id in market.get_token_token_pools(token_in, token_out).into_iter().flatten() {
+            let Some(pool) = market.get_pool(pool_id).filter(|pool| is_enabled(*pool)) else {
+                continue;
+            };
+            if seen.insert(vec![*pool_id]) {
+                routes.push(SwapPath::new_swap(token(token_in), token(token_out), pool.clone()));
+            }
+        }
+
+        // paths disabled by other pools may still have enabled parts
+        let swap_paths = market.swap_paths();
+        let mut visited: HashSet<usize> = HashSet::new();
+        for pool_id in market.get_token_pools(token_in).into_iter().flatten() {
+            for path_idx in swap_paths.pool_paths.get(pool_id).into_iter().flatten() {
+                if !visited.insert(*path_idx) {
+                    continue;
+                }
+                let Some(route) = swap_paths.get_path_by_idx(*path_idx).and_then(|swap_path| path_route(swap_path, token_in, token_out))
+                else {
+                    continue;
+                };
+                if route.pools.iter().all(|pool| is_enabled(pool))
+                    && seen.insert(route.pools.iter().map(|pool| pool.get_pool_id()).collect())
+                {
+                    routes.push(route);
+                }
+            }
+        }
+        routes
+    }
+
+    /// Best route for the amount of the token to the token, by the out amount
+    pub fn quote_exact_in(&self, token_in: Address, token_out: Address, amount_in: U256) -> Result<Quote> {
+        if token_in == token_out {
+            return Err(eyre!("SAME_TOKEN"));
+        }
+
+        let mut env = Env::default();
+        env.block.number = U256::from(self.block_number);
+        env.block.timestamp = U256::from(self.block_timestamp);
+
+        let mut best: Option<Quote> = None;
+        for swap_path in self.routes(&token_in, &token_out) {
+            let mut swap_line = SwapLine::from(swap_path);
+            let Ok((amount_out, gas_used, calculation_results)) = swap_line.calculate_with_in_amount(self.state_db, env.clone(), amount_in)
+            else {
+                continue;
+            };
+            if best.as_ref().is_some_and(|best| best.amount_out >= amount_out) {
+                continue;
+            }
+            swap_line.amount_in = SwapAmountType::Set(amount_in);
+            swap_line.amount_out = SwapAmountType::Set(amount_out);
+            swap_line.gas_used = Some(gas_used);
+            swap_line.calculation_results = calculation_results;
+            best = Some(Quote { swap_line, amount_out, gas_used, block_number: self.block_number });
+        }
+        best.ok_or_eyre("NO_ROUTE")
+    }
+}
+
+/// Part of the path from the token to the first following token out, cycles are followed past their last hop
+fn path_route(swap_path: &SwapPath, token_in: &Address, token_out: &Address) -> Option<SwapPath> {
+    let hops = swap_path.pools.len();
+    if hops == 0 || swap_path.tokens.len() != hops + 1 {
+        return None;
+    }
+    let start = swap_path.tokens[..hops].iter().position(|token| token.get_address() == *token_in)?;
+    let is_cycle = swap_path.tokens.first() == swap_path.tokens.last();
+    let at = |i: usize| if is_cycle { (start + i) % hops } else { start + i };
+    let max_len = if is_cycle { hops } else { hops - start };
+    let len = (1..=max_len).find(|i| swap_path.tokens[at(*i)].get_address() == *token_out)?;
+
+    let tokens = (0..=len).map(|i| swap_path.tokens[at(i)].clone()).collect::<Vec<_>>();
+    let pools = (0..len).map(|i| swap_path.pools[at(i)].clone()).collect::<Vec<_>>();
+    Some(SwapPath::new(tokens, pools))
+}
+
+impl Quote {
+    /// Pools of the route in the swap order
+    pub fn pool_ids(&self) -> Vec<PoolId> {
+        self.swap_line.pools().iter().map(|pool| pool.get_pool_id()).collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::MockPool;
+    use loom_evm_db::LoomDBType;
+
+    fn set_reserves(state_db: &mut LoomDBType, pool: Address, reserve0: u64, reserve1: u64) {
+        let ether = U256::from(10).pow(U256::from(18));
+        state_db.insert_account_storage(pool, U256::ZERO, U256::from(reserve0) * ether).unwrap();
+        state_db.insert_account_storage(pool, U256::from(1), U256::from(reserve1) * ether).unwrap();
+    }
+
+    #[test]
+    fn test_quoter_routes() {
+        let (weth, usdc, dai, link) = (Address::repeat_byte(1), Address::repeat_byte(2), Address::repeat_byte(3), Address::repeat_byte(4));
+        let pools = [
+            MockPool::new(weth, usdc, Address::repeat_byte(0x10)),
+            MockPool::new(weth, usdc, Address::repeat_byte(0x11)),
+            MockPool::new(usdc, dai, Address::repeat_byte(0x12)),
+            MockPool::new(weth, dai, Address::repeat_byte(0x13)),
+        ];
+        let mut market = Market::default();
+        for pool in pools.iter() {
+            market.add_pool(pool.clone()).unwrap();
+        }
+        // weth -> usdc -> dai -> weth through both weth usdc pools
+        let tokens = || vec![Token::new(weth), Token::new(usdc), Token::new(dai), Token::new(weth)];
+        market.add_paths(vec![
+            SwapPath::new(tokens(), vec![pools[0].clone(), pools[2].clone(), pools[3].clone()]),
+            SwapPath::new(tokens(), vec![pools[1].clone(), pools[2].clone(), pools[3].clone()]),
+        ]);
+        let state_db = LoomDBType::default();
+        let quoter = Quoter::new(&market, &state_db, 100);
+
+        // one direct pool, two through usdc from the loaded paths
+        let routes = quoter.routes(&weth, &dai);
+        assert_eq!(routes.iter().filter(|route| route.pools.len() == 1).count(), 1);
+        assert_eq!(routes.iter().filter(|route| route.pools.len() == 2).count(), 2);
+        assert!(routes.iter().all(|route| route.tokens.first().unwrap().get_address() == weth));
+        assert!(routes.iter().all(|route| route.tokens.last().unwrap().get_address() == dai));
+        // the cycles are rotated to start with dai
+        let routes = quoter.routes(&dai, &usdc);
+        assert_eq!(routes.iter().filter(|route| route.pools.len() == 2).count(), 2);
+        assert!(quoter.routes(&weth, &link).is_empty());
+
+        market.deny_pool(PoolId::Address(Address::repeat_byte(0x13)));
+        assert_eq!(Quoter::new(&market, &state_db, 100).routes(&weth, &dai).len(), 2);
+    }
+
+    #[test]
+    fn test_quote_exact_in() -> Result<()> {
+        let (weth, usdc, dai, link) = (Address::repeat_byte(1), Address::repeat_byte(2), Address::repeat_byte(3), Address::repeat_byte(4));
+        let (weth_usdc, usdc_dai, weth_dai) = (Address::repeat_byte(0x10), Address::repeat_byte(0x12), Address::repeat_byte(0x13));
+        let pools = [MockPool::new(weth, usdc, weth_usdc), MockPool::new(usdc, dai, usdc_dai), MockPool::new(weth, dai, weth_dai)];
+        let mut market = Market::default();
+        for pool in pools.iter() {
+            market.add_pool(pool.clone()).unwrap();
+        }
+        market.add_paths(vec![SwapPath::new(
+            vec![Token::new(weth), Token::new(usdc), Token::new(dai), Token::new(weth)],
+            vec![pools[0].clone(), pools[1].clone(), pools[2].clone()],
+        )]);
+
+        // the direct pool gives 2000 dai for 1 weth less the price impact, the route through usdc 2500
+        let mut state_db = LoomDBType::default();
+        set_reserves(&mut state_db, weth_usdc, 1_000, 2_500_000);
+        set_reserves(&mut state_db, usdc_dai, 10_000_000, 10_000_000);
+        set_reserves(&mut state_db, weth_dai, 1_000, 2_000_000);
+
+        let amount_in = U256::from(10).pow(U256::from(18));
+        let quoter = Quoter::new(&market, &state_db, 100);
+        let quote = quoter.quote_exact_in(weth, dai, amount_in)?;
+        assert_eq!(quote.pool_ids(), vec![PoolId::Address(weth_usdc), PoolId::Address(usdc_dai)]);
+        assert_eq!(quote.block_number, 100);
+        let ether = U256::from(10).pow(U256::from(18));
+        assert!(quote.amount_out > U256::from(2_490) * ether && quote.amount_out < U256::from(2_500) * ether);
+        assert_eq!(quote.swap_line.calculation_results.len(), 2);
+
+        // with the usdc pool drained the direct pool is the best route
+        set_reserves(&mut state_db, usdc_dai, 10_000_000, 1_000);
+        let quote = Quoter::new(&market, &state_db, 101).quote_exact_in(weth, dai, amount_in)?;
+        assert_eq!(quote.pool_ids(), vec![PoolId::Address(weth_dai)]);
+
+        assert!(quoter.quote_exact_in(weth, weth, amount_in).is_err());
+        assert!(quoter.quote_exact_in(weth, link, amount_in).is_err());
+        Ok(())
+    }
+}
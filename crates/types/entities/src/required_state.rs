@@ -1,18 +1,61 @@
 use std::collections::BTreeMap;
 use std::fmt::Debug;
 
+#[cfg(feature = "provider")]
 use alloy_network::Network;
-use alloy_primitives::{Address, BlockNumber, Bytes, TxKind, U256};
+use alloy_primitives::{address, Address, Bytes, TxKind, U256};
+#[cfg(feature = "provider")]
+use alloy_primitives::{BlockNumber, B256};
+#[cfg(feature = "provider")]
 use alloy_provider::Provider;
-use alloy_rpc_types::{BlockId, BlockNumberOrTag, TransactionInput, TransactionRequest};
+#[cfg(feature = "provider")]
+use alloy_rpc_types::{BlockId, BlockNumberOrTag};
+use alloy_rpc_types::{TransactionInput, TransactionRequest};
 use alloy_rpc_types_trace::geth::AccountState;
+use alloy_sol_types::{sol, SolCall};
+#[cfg(feature = "provider")]
 use eyre::{eyre, Result};
-use tracing::{error, trace};
+use tracing::trace;
+#[cfg(feature = "provider")]
+use tracing::{debug, error};
 
+#[cfg(feature = "provider")]
+use crate::state_proof::{verified_account_state, verify_account_proof, verify_storage_proofs, ProvenBlock, ProvenState, StateLoadingMode};
+#[cfg(feature = "provider")]
 use loom_node_debug_provider::DebugProviderExt;
-use loom_types_blockchain::{debug_trace_call_pre_state, GethStateUpdate, GethStateUpdateVec};
+#[cfg(feature = "provider")]
+use loom_types_blockchain::debug_trace_call_pre_state;
+use loom_types_blockchain::{GethStateUpdate, GethStateUpdateVec};
 use loom_types_blockchain::{LoomDataTypes, LoomDataTypesEthereum};
 
+/// Multicall3 is deployed at the same address on most chains
+pub const MULTICALL3_ADDRESS: Address = address!("cA11bde05977b3631167028862bE2a173976CA11");
+// gas limit of a batched call, stays below the default eth_call gas cap of the nodes
+const MULTICALL_CHUNK_GAS_LIMIT: u64 = 25_000_000;
+// aggregate3 loop and call overhead per batched call
+const MULTICALL_CALL_GAS_OVERHEAD: u64 = 10_000;
+const DEFAULT_CALL_GAS: u64 = 1_000_000;
+// rounds of local execution and proof fetching, every round proves the reads depending on the state proven before
+#[cfg(feature = "provider")]
+const MAX_PROOF_ROUNDS: usize = 8;
+
+sol! {
+    interface IMulticall3 {
+        struct Call3 {
+            address target;
+            bool allowFailure;
+            bytes callData;
+        }
+
+        struct Result {
+            bool success;
+            bytes returnData;
+        }
+
+        function aggregate3(Call3[] calldata calls) external payable returns (Result[] memory returnData);
+    }
+}
+
 #[derive(Clone, Debug, Default)]
 pub struct RequiredState<LDT: LoomDataTypes = LoomDataTypesEthereum> {
     calls: Vec<LDT::TransactionRequest>,
@@ -27,7 +70,7 @@ impl RequiredState {
 
     pub fn add_call<T: Into<Bytes> + Debug, A: Into<Address> + Debug>(&mut self, to: A, call_data: T) -> &mut Self {
         let req: TransactionRequest = TransactionRequest {
-            gas: Some(1_000_000),
+            gas: Some(DEFAULT_CALL_GAS),
             to: Some(TxKind::Call(to.into())),
             input: TransactionInput::new(call_data.into()),
             ..TransactionRequest::default()
@@ -62,11 +105,121 @@ impl RequiredState {
         }
         self
     }
+
+    /// Add calls and slots of another required state, e.g. to fetch the state of a batch of pools at once
+    pub fn merge(&mut self, other: RequiredState) -> &mut Self {
+        self.calls.extend(other.calls);
+        self.slots.extend(other.slots);
+        self.empty_slots.extend(other.empty_slots);
+        self
+    }
+}
+
+/// Split calls into Multicall3 batches with the sum of the call gas limits below the chunk gas limit
+fn multicall_chunks(calls: Vec<TransactionRequest>, chunk_gas_limit: u64) -> Vec<Vec<TransactionRequest>> {
+    let mut chunks: Vec<Vec<TransactionRequest>> = Vec::new();
+    let mut chunk: Vec<TransactionRequest> = Vec::new();
+    let mut chunk_gas: u64 = 0;
+
+    for call in calls {
+        let call_gas = call.gas.unwrap_or(DEFAULT_CALL_GAS) + MULTICALL_CALL_GAS_OVERHEAD;
+        if !chunk.is_empty() && chunk_gas + call_gas > chunk_gas_limit {
+            chunks.push(std::mem::take(&mut chunk));
+            chunk_gas = 0;
+        }
+        chunk_gas += call_gas;
+        chunk.push(call);
+    }
+    if !chunk.is_empty() {
+        chunks.push(chunk);
+    }
+    chunks
+}
+
+fn multicall_request(calls: &[TransactionRequest]) -> TransactionRequest {
+    let gas: u64 = calls.iter().map(|call| call.gas.unwrap_or(DEFAULT_CALL_GAS) + MULTICALL_CALL_GAS_OVERHEAD).sum();
+    let calls: Vec<IMulticall3::Call3> = calls
+        .iter()
+        .map(|call| IMulticall3::Call3 {
+            target: call.to.unwrap_or_default().to().map_or(Address::ZERO, |x| *x),
+            allowFailure: true,
+            callData: call.input.input().cloned().unwrap_or_default(),
+        })
+        .collect();
+
+    TransactionRequest {
+        gas: Some(gas),
+        to: Some(TxKind::Call(MULTICALL3_ADDRESS)),
+        input: TransactionInput::new(IMulticall3::aggregate3Call { calls }.abi_encode().into()),
+        ..TransactionRequest::default()
+    }
 }
 
+fn merge_state_update(ret: &mut GethStateUpdate, update: GethStateUpdate) {
+    for (address, account_state) in update.into_iter() {
+        let entry = ret.entry(address).or_insert(account_state.clone());
+        for (slot, value) in account_state.storage.clone().into_iter() {
+            entry.storage.insert(slot, value);
+            trace!(%address, %slot, %value, "Inserting storage");
+        }
+    }
+}
+
+#[cfg(feature = "provider")]
 pub struct RequiredStateReader {}
 
+#[cfg(feature = "provider")]
 impl RequiredStateReader {
+    /// Trace calls batched into Multicall3 chunks, every chunk costs an `eth_call` to check the call results and
+    /// a `debug_traceCall` collecting the state read by all calls of the chunk.
+    /// Returns `None` if Multicall3 cannot be used and the calls have to be traced one by one.
+    async fn fetch_calls_batched<N: Network, C: DebugProviderExt<N> + Provider<N> + Clone + 'static>(
+        client: C,
+        calls: Vec<TransactionRequest>,
+        block_id: BlockId,
+    ) -> Result<Option<GethStateUpdate>> {
+        let mut ret: GethStateUpdate = GethStateUpdate::new();
+        let targets_multicall = calls.iter().any(|call| call.to == Some(TxKind::Call(MULTICALL3_ADDRESS)));
+
+        for chunk in multicall_chunks(calls, MULTICALL_CHUNK_GAS_LIMIT) {
+            let req = multicall_request(&chunk);
+
+            let output: Bytes = match client.raw_request("eth_call".into(), (req.clone(), block_id)).await {
+                Ok(output) => output,
+                Err(error) => {
+                    debug!(%error, "Multicall3 call failed");
+                    return Ok(None);
+                }
+            };
+            let results = match IMulticall3::aggregate3Call::abi_decode_returns(&output, false) {
+                Ok(results) => results.returnData,
+                Err(error) => {
+                    debug!(%error, "Multicall3 is not available");
+                    return Ok(None);
+                }
+            };
+            if let Some((call, _)) = chunk.iter().zip(results.iter()).find(|(_, result)| !result.success) {
+                let to = call.to.unwrap_or_default().to().map_or(Address::ZERO, |x| *x);
+                error!("Contract call failed {}", to);
+                return Err(eyre!("CONTRACT_CALL_FAILED"));
+            }
+
+            match debug_trace_call_pre_state(client.clone(), req, block_id, None).await {
+                Ok(update) => merge_state_update(&mut ret, update),
+                Err(error) => {
+                    debug!(%error, "Multicall3 trace failed");
+                    return Ok(None);
+                }
+            }
+        }
+
+        // multicall account is only a part of the state if it is called directly
+        if !targets_multicall {
+            ret.remove(&MULTICALL3_ADDRESS);
+        }
+        Ok(Some(ret))
+    }
+
     pub async fn fetch_calls_and_slots<N: Network, C: DebugProviderExt<N> + Provider<N> + Clone + 'static>(
         client: C,
         required_state: RequiredState,
@@ -79,25 +232,28 @@ impl RequiredStateReader {
         };
 
         let mut ret: GethStateUpdate = GethStateUpdate::new();
-        for req in required_state.calls.into_iter() {
-            let to = req.to.unwrap_or_default().to().map_or(Address::ZERO, |x| *x);
-
-            let call_result = debug_trace_call_pre_state(client.clone(), req, block_id, None).await;
-            trace!("trace_call_result: {:?}", call_result);
-            match call_result {
-                Ok(update) => {
-                    for (address, account_state) in update.into_iter() {
-                        let entry = ret.entry(address).or_insert(account_state.clone());
-                        for (slot, value) in account_state.storage.clone().into_iter() {
-                            entry.storage.insert(slot, value);
-                            trace!(%address, %slot, %value, "Inserting storage");
+
+        let batched = match required_state.calls.len() > 1 {
+            true => Self::fetch_calls_batched(client.clone(), required_state.calls.clone(), block_id).await?,
+            false => None,
+        };
+
+        match batched {
+            Some(update) => merge_state_update(&mut ret, update),
+            None => {
+                for req in required_state.calls.into_iter() {
+                    let to = req.to.unwrap_or_default().to().map_or(Address::ZERO, |x| *x);
+
+                    let call_result = debug_trace_call_pre_state(client.clone(), req, block_id, None).await;
+                    trace!("trace_call_result: {:?}", call_result);
+                    match call_result {
+                        Ok(update) => merge_state_update(&mut ret, update),
+                        Err(e) => {
+                            error!("Contract call failed {} {}", to, e);
+                            return Err(eyre!("CONTRACT_CALL_FAILED"));
                         }
                     }
                 }
-                Err(e) => {
-                    error!("Contract call failed {} {}", to, e);
-                    return Err(eyre!("CONTRACT_CALL_FAILED"));
-                }
             }
         }
         for (address, slot) in required_state.slots.into_iter() {
@@ -123,6 +279,73 @@ impl RequiredStateReader {
 
         Ok(ret)
     }
+
+    /// Fetch the required state and verify every account and slot with `eth_getProof` against the state root of the
+    /// proven block. The calls are executed locally over the verified state to find the accounts and slots they read,
+    /// proofs are fetched until every read is proven, the node trace is not trusted for the read set.
+    pub async fn fetch_calls_and_slots_verified<N: Network, C: Provider<N> + Clone + 'static>(
+        client: C,
+        required_state: RequiredState,
+        block: ProvenBlock,
+    ) -> Result<GethStateUpdate> {
+        let block_id = BlockId::Number(BlockNumberOrTag::Number(block.number));
+        let calls: Vec<(Address, Bytes)> = required_state
+            .calls
+            .iter()
+            .filter_map(|req| Some((*req.to.as_ref()?.to()?, req.input.input().cloned().unwrap_or_default())))
+            .collect();
+        let slots: Vec<(Address, U256)> = required_state.slots.iter().chain(required_state.empty_slots.iter()).copied().collect();
+
+        let mut proven_state = ProvenState::default();
+        for _ in 0..MAX_PROOF_ROUNDS {
+            let missing = proven_state.missing(&calls, &slots, &block);
+            if missing.is_empty() {
+                debug!(block_number = block.number, accounts = proven_state.len(), "Required state verified");
+                return Ok(proven_state.into_state());
+            }
+
+            for (address, slots) in missing {
+                let slots: Vec<B256> = slots.into_iter().collect();
+                let proof = client.get_proof(address, slots.clone()).block_id(block_id).await.map_err(|error| {
+                    error!(%error, %address, "Failed to fetch proof");
+                    eyre!("PROOF_FETCH_FAILED")
+                })?;
+
+                let verified = match proven_state.contains_account(&address) {
+                    true => verify_account_proof(block.state_root, &proof)
+                        .and_then(|_| verify_storage_proofs(&proof, &slots))
+                        .map(|storage| AccountState { storage, ..AccountState::default() }),
+                    // code is not covered by the proof, it is checked against the proven code hash
+                    false => {
+                        let code = client.get_code_at(address).block_id(block_id).await?;
+                        verified_account_state(block.state_root, &proof, &slots, Some(code))
+                    }
+                }
+                .inspect_err(|error| error!(%error, %address, block_number = block.number, "State proof verification failed"))?;
+                proven_state.insert(address, verified);
+            }
+        }
+
+        Err(eyre!("PROVEN_STATE_INCOMPLETE"))
+    }
+
+    /// Fetch the required state in the loading mode. Proofs are verified against the proven block, required in
+    /// [`StateLoadingMode::Proof`]
+    pub async fn fetch_with_mode<N: Network, C: DebugProviderExt<N> + Provider<N> + Clone + 'static>(
+        client: C,
+        required_state: RequiredState,
+        block_number: Option<BlockNumber>,
+        mode: StateLoadingMode,
+        proven_block: Option<ProvenBlock>,
+    ) -> Result<GethStateUpdate> {
+        match mode {
+            StateLoadingMode::Trace => Self::fetch_calls_and_slots(client, required_state, block_number).await,
+            StateLoadingMode::Proof => {
+                let block = proven_block.ok_or_else(|| eyre!("PROVEN_BLOCK_NOT_SET"))?;
+                Self::fetch_calls_and_slots_verified(client, required_state, block).await
+            }
+        }
+    }
 }
 
 pub fn accounts_len(state: &BTreeMap<Address, AccountState>) -> (usize, usize) {
@@ -138,3 +361,61 @@ pub fn accounts_vec_len(state: &GethStateUpdateVec) -> usize {
 pub fn storage_vec_len(state: &GethStateUpdateVec) -> usize {
     state.iter().map(|item| accounts_len(item).1).sum()
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_multicall_chunks() {
+        let mut required_state = RequiredState::new();
+        for _ in 0..30 {
+            required_state.add_call(Address::repeat_byte(1), Bytes::from(vec![1u8, 2, 3, 4]));
+        }
+
+        // 1_010_000 gas per call
+        let chunks = multicall_chunks(required_state.calls.clone(), MULTICALL_CHUNK_GAS_LIMIT);
+        assert_eq!(chunks.iter().map(|chunk| chunk.len()).collect::<Vec<_>>(), vec![24, 6]);
+
+        let req = multicall_request(&chunks[1]);
+        assert_eq!(req.gas, Some(6 * 1_010_000));
+        let call = IMulticall3::aggregate3Call::abi_decode(req.input.input().unwrap(), true).unwrap();
+        assert_eq!(call.calls.len(), 6);
+        assert_eq!(call.calls[0].target, Address::repeat_byte(1));
+        assert_eq!(call.calls[0].callData, Bytes::from(vec![1u8, 2, 3, 4]));
+
+        // a call above the limit gets its own chunk
+        assert_eq!(multicall_chunks(required_state.calls[..2].to_vec(), 1_000_000).len(), 2);
+    }
+
+    #[cfg(feature = "provider")]
+    #[tokio::test]
+    async fn test_fetch_slots_replayed() -> Result<()> {
+        use alloy_provider::ProviderBuilder;
+        use alloy_rpc_client::RpcClient;
+        use loom_node_debug_provider::ReplayTransport;
+
+        let path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("fixtures").join("required_state_usdc_weth.jsonl");
+        let client =
+            ProviderBuilder::new().disable_recommended_fillers().on_client(RpcClient::new(ReplayTransport::from_file(path)?, true));
+
+        // uniswap v2 usdc/weth pool, the reserves slot and one of the price accumulators are recorded
+        let pool = address!("b4e16d0168e52d35cacd2c6185b44281ec28c9dc");
+        let mut required_state = RequiredState::new();
+        required_state.add_slot(pool, U256::from(8)).add_slot(pool, U256::from(9)).add_slot(pool, U256::from(10));
+        required_state.add_empty_slot(pool, U256::from(12));
+
+        let state = RequiredStateReader::fetch_calls_and_slots(client, required_state, Some(20935488)).await?;
+        let storage = &state.get(&pool).unwrap().storage;
+        let reserves: U256 = (U256::from(1_729_000_000u64) << 224)
+            | ((U256::from(10_000u64) * U256::from(10u64).pow(U256::from(18))) << 112)
+            | U256::from(30_000_000_000_000u64);
+        assert_eq!(storage.get(&B256::from(U256::from(8))), Some(&B256::from(reserves)));
+        assert_eq!(storage.get(&B256::from(U256::from(9))), Some(&B256::from(U256::from(0x1234567890abcdef1234567890abcdefu128))));
+        // a slot failing to load is skipped, empty slots are not fetched
+        assert!(!storage.contains_key(&B256::from(U256::from(10))));
+        assert_eq!(storage.get(&B256::from(U256::from(12))), Some(&B256::ZERO));
+
+        Ok(())
+    }
+}
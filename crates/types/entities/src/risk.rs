@@ -0,0 +1,187 @@
+use alloy_primitives::Address;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::{Market, SwapPath};
+use loom_types_blockchain::LoomDataTypes;
+
+/// Safety verdict of a token
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TokenSafety {
+    /// Basic tokens and tokens verified by hand
+    Trusted,
+    #[default]
+    Unknown,
+    /// Token with non standard behaviour, e.g. rebasing or transfer fees
+    Suspicious,
+    /// Honeypots and tokens that cannot be sold, paths with them are never executed
+    Unsafe,
+}
+
+/// Weights of the risk factors and the score threshold of a strategy
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct RiskConfig {
+    /// Paths with a higher score are dropped before simulation
+    pub max_score: f64,
+    pub unknown_token: f64,
+    pub suspicious_token: f64,
+    /// Added for every token or pool that is an upgradable proxy
+    pub proxy: f64,
+    /// Added for a pool first seen in the current block, decreases linearly to zero at `pool_mature_blocks`
+    pub young_pool: f64,
+    pub pool_mature_blocks: u64,
+    /// Added for every pool above two
+    pub extra_pool: f64,
+    /// Verdicts of tokens checked by hand, they override the default verdicts of the market
+    pub token_safety: HashMap<Address, TokenSafety>,
+}
+
+impl Default for RiskConfig {
+    fn default() -> Self {
+        Self {
+            max_score: 1.0,
+            unknown_token: 0.1,
+            suspicious_token: 0.5,
+            proxy: 0.3,
+            young_pool: 0.6,
+            pool_mature_blocks: 7200,
+            extra_pool: 0.05,
+            token_safety: HashMap::new(),
+        }
+    }
+}
+
+/// Scores swap paths by token safety, pool age, upgradability and path length
+#[derive(Clone, Debug, Default)]
+pub struct RiskScorer {
+    config: RiskConfig,
+}
+
+impl RiskScorer {
+    pub fn new(config: RiskConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn config(&self) -> &RiskConfig {
+        &self.config
+    }
+
+    /// Set the configured token verdicts in the market
+    pub fn apply_token_safety(&self, market: &mut Market) {
+        for (address, safety) in self.config.token_safety.iter() {
+            market.set_token_safety(*address, *safety);
+        }
+    }
+
+    /// Risk score of the path at `block_number`, infinite for paths with unsafe tokens
+    pub fn score<LDT: LoomDataTypes>(&self, swap_path: &SwapPath<LDT>, market: &Market<LDT>, block_number: u64) -> f64 {
+        let mut score = 0.0;
+
+        // the first token is repeated at the end of an arbitrage path
+        let token_count = if swap_path.tokens.len() > 1 && swap_path.tokens.first() == swap_path.tokens.last() {
+            swap_path.tokens.len() - 1
+        } else {
+            swap_path.tokens.len()
+        };
+
+        for token in swap_path.tokens.iter().take(token_count) {
+            let address = token.get_address();
+            score += match market.token_safety(&address) {
+                TokenSafety::Trusted => 0.0,
+                TokenSafety::Unknown => self.config.unknown_token,
+                TokenSafety::Suspicious => self.config.suspicious_token,
+                TokenSafety::Unsafe => return f64::INFINITY,
+            };
+            if market.is_proxy(&address) {
+                score += self.config.proxy;
+            }
+        }
+
+        for pool in swap_path.pools.iter() {
+            if market.is_proxy(&pool.get_address()) {
+                score += self.config.proxy;
+            }
+            if let Some(first_seen) = market.pool_first_seen(&pool.get_pool_id()) {
+                let age = block_number.saturating_sub(first_seen);
+                if age < self.config.pool_mature_blocks {
+                    score += self.config.young_pool * (1.0 - age as f64 / self.config.pool_mature_blocks as f64);
+                }
+            }
+        }
+
+        score += swap_path.pools.len().saturating_sub(2) as f64 * self.config.extra_pool;
+
+        score
+    }
+
+    pub fn is_acceptable<LDT: LoomDataTypes>(&self, swap_path: &SwapPath<LDT>, market: &Market<LDT>, block_number: u64) -> bool {
+        self.score(swap_path, market, block_number) <= self.config.max_score
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mock_pool::MockPool;
+    use crate::{PoolWrapper, Token};
+    use alloy_primitives::Address;
+    use loom_defi_address_book::TokenAddressEth;
+    use std::sync::Arc;
+
+    fn swap_path(market: &mut Market, token: Address) -> SwapPath {
+        let pool_0 = MockPool::new(TokenAddressEth::WETH, token, Address::repeat_byte(1));
+        let pool_1 = MockPool::new(TokenAddressEth::WETH, token, Address::repeat_byte(2));
+        let mut weth = Token::new(TokenAddressEth::WETH);
+        weth.set_basic();
+        market.add_token(weth);
+        market.add_token(Token::new(token));
+
+        let weth = market.get_token(&TokenAddressEth::WETH).unwrap();
+        let token = market.get_token(&token).unwrap();
+        SwapPath {
+            tokens: vec![weth.clone(), token, weth],
+            pools: vec![PoolWrapper::new(Arc::new(pool_0)), PoolWrapper::new(Arc::new(pool_1))],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_score() {
+        let mut market = Market::default();
+        let token = Address::repeat_byte(0x11);
+        let swap_path = swap_path(&mut market, token);
+        let scorer = RiskScorer::default();
+
+        assert_eq!(scorer.score(&swap_path, &market, 100), 0.1);
+
+        market.set_proxy_implementation(token, Address::repeat_byte(0x22));
+        assert!((scorer.score(&swap_path, &market, 100) - 0.4).abs() < 1e-9);
+
+        market.set_pool_first_seen(swap_path.pools[0].get_pool_id(), 100);
+        assert!((scorer.score(&swap_path, &market, 100) - 1.0).abs() < 1e-9);
+        assert!(scorer.is_acceptable(&swap_path, &market, 100));
+
+        market.set_token_safety(token, TokenSafety::Suspicious);
+        assert!(!scorer.is_acceptable(&swap_path, &market, 100));
+        assert!(scorer.is_acceptable(&swap_path, &market, 100 + 7200));
+
+        market.set_token_safety(token, TokenSafety::Unsafe);
+        assert_eq!(scorer.score(&swap_path, &market, 100 + 7200), f64::INFINITY);
+    }
+
+    #[test]
+    fn test_apply_token_safety() {
+        let mut market = Market::default();
+        let token = Address::repeat_byte(0x11);
+        let swap_path = swap_path(&mut market, token);
+
+        let scorer = RiskScorer::new(RiskConfig { token_safety: HashMap::from([(token, TokenSafety::Unsafe)]), ..RiskConfig::default() });
+        assert!(scorer.is_acceptable(&swap_path, &market, 100));
+
+        scorer.apply_token_safety(&mut market);
+        assert_eq!(market.token_safety(&token), TokenSafety::Unsafe);
+        assert!(!scorer.is_acceptable(&swap_path, &market, 100));
+    }
+}
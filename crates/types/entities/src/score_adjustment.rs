@@ -0,0 +1,191 @@
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use loom_types_blockchain::{LoomDataTypes, LoomDataTypesEthereum};
+use serde::Serialize;
+
+use crate::{PoolId, SwapPath};
+
+/// Adjustments smaller than this after the decay are dropped
+const MIN_ADJUSTMENT: f64 = 1e-6;
+
+/// Score adjustment pushed by an external signal source, e.g. an ML model ranking pools
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ScoreAdjustment {
+    /// Added to the score of the paths at `timestamp`
+    pub value: f64,
+    /// Seconds for the adjustment to decay to half of its value, it never decays if zero
+    pub half_life_secs: u64,
+    pub source: Option<String>,
+    /// Unix timestamp in seconds
+    pub timestamp: u64,
+}
+
+impl ScoreAdjustment {
+    /// Value decayed from the time the adjustment was pushed
+    pub fn decayed(&self, now: u64) -> f64 {
+        if self.half_life_secs == 0 {
+            return self.value;
+        }
+        let elapsed = now.saturating_sub(self.timestamp) as f64;
+        self.value * 0.5f64.powf(elapsed / self.half_life_secs as f64)
+    }
+}
+
+/// Score adjustments of pools and swap paths pushed at runtime. The adjustment of a path is its own adjustment plus the
+/// adjustments of its pools, it is added to the historical score of the path when the searches order paths.
+#[derive(Clone, Debug)]
+pub struct ScoreAdjustments<LDT: LoomDataTypes = LoomDataTypesEthereum> {
+    pools: HashMap<PoolId<LDT>, ScoreAdjustment>,
+    /// Adjustments by [`SwapPath::get_hash`], the key the market indexes its paths with
+    paths: HashMap<u64, ScoreAdjustment>,
+}
+
+impl<LDT: LoomDataTypes> Default for ScoreAdjustments<LDT> {
+    fn default() -> Self {
+        Self { pools: HashMap::new(), paths: HashMap::new() }
+    }
+}
+
+impl<LDT: LoomDataTypes> ScoreAdjustments<LDT> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replace the adjustment of the pool, a zero value removes it
+    pub fn set_pool(&mut self, pool_id: PoolId<LDT>, adjustment: ScoreAdjustment) {
+        if adjustment.value == 0.0 {
+            self.pools.remove(&pool_id);
+        } else {
+            self.pools.insert(pool_id, adjustment);
+        }
+    }
+
+    /// Replace the adjustment of the path by its [`SwapPath::get_hash`], a zero value removes it
+    pub fn set_path(&mut self, path_hash: u64, adjustment: ScoreAdjustment) {
+        if adjustment.value == 0.0 {
+            self.paths.remove(&path_hash);
+        } else {
+            self.paths.insert(path_hash, adjustment);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.pools.clear();
+        self.paths.clear();
+    }
+
+    /// Drop the adjustments decayed to nothing
+    pub fn prune(&mut self, now: u64) {
+        self.pools.retain(|_, adjustment| adjustment.decayed(now).abs() >= MIN_ADJUSTMENT);
+        self.paths.retain(|_, adjustment| adjustment.decayed(now).abs() >= MIN_ADJUSTMENT);
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.pools.is_empty() && self.paths.is_empty()
+    }
+
+    pub fn pools(&self) -> &HashMap<PoolId<LDT>, ScoreAdjustment> {
+        &self.pools
+    }
+
+    pub fn paths(&self) -> &HashMap<u64, ScoreAdjustment> {
+        &self.paths
+    }
+
+    /// Decayed adjustment of the path and of its pools
+    pub fn adjustment(&self, swap_path: &SwapPath<LDT>, now: u64) -> f64 {
+        if self.is_empty() {
+            return 0.0;
+        }
+        let pools_adjustment: f64 =
+            swap_path.pools.iter().filter_map(|pool| self.pools.get(&pool.get_pool_id())).map(|adjustment| adjustment.decayed(now)).sum();
+        let path_adjustment = if self.paths.is_empty() {
+            0.0
+        } else {
+            self.paths.get(&swap_path.get_hash()).map(|adjustment| adjustment.decayed(now)).unwrap_or_default()
+        };
+        pools_adjustment + path_adjustment
+    }
+
+    /// Historical score of the path with the decayed adjustments
+    pub fn adjusted_score(&self, swap_path: &SwapPath<LDT>, now: u64) -> f64 {
+        swap_path.score.unwrap_or_default() + self.adjustment(swap_path, now)
+    }
+}
+
+/// Score adjustments shared by the control API and the searches. Updates replace the adjustments without the market lock,
+/// the searches keep the adjustments they started with
+#[derive(Clone, Debug)]
+pub struct SharedScoreAdjustments<LDT: LoomDataTypes = LoomDataTypesEthereum>(Arc<RwLock<Arc<ScoreAdjustments<LDT>>>>);
+
+impl<LDT: LoomDataTypes> Default for SharedScoreAdjustments<LDT> {
+    fn default() -> Self {
+        Self(Arc::new(RwLock::new(Arc::new(ScoreAdjustments::default()))))
+    }
+}
+
+impl<LDT: LoomDataTypes> SharedScoreAdjustments<LDT> {
+    pub fn load(&self) -> Arc<ScoreAdjustments<LDT>> {
+        self.0.read().map(|adjustments| adjustments.clone()).unwrap_or_default()
+    }
+
+    /// Apply `update` to a copy of the adjustments and replace them with it
+    pub fn update<F: FnOnce(&mut ScoreAdjustments<LDT>)>(&self, update: F) -> Arc<ScoreAdjustments<LDT>> {
+        let mut adjustments = self.load().as_ref().clone();
+        update(&mut adjustments);
+        let adjustments = Arc::new(adjustments);
+        if let Ok(mut guard) = self.0.write() {
+            *guard = adjustments.clone();
+        }
+        adjustments
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{MockPool, Token};
+    use alloy_primitives::Address;
+
+    fn adjustment(value: f64, half_life_secs: u64) -> ScoreAdjustment {
+        ScoreAdjustment { value, half_life_secs, source: Some("model".to_string()), timestamp: 1000 }
+    }
+
+    #[test]
+    fn test_score_adjustments() {
+        let (token0, token1) = (Address::repeat_byte(1), Address::repeat_byte(2));
+        let (pool0, pool1) = (Address::repeat_byte(0x10), Address::repeat_byte(0x11));
+        let swap_path = SwapPath {
+            score: Some(0.5),
+            ..SwapPath::new(
+                vec![Token::new(token0), Token::new(token1), Token::new(token0)],
+                vec![MockPool::new(token0, token1, pool0), MockPool::new(token0, token1, pool1)],
+            )
+        };
+
+        let mut adjustments = ScoreAdjustments::new();
+        assert_eq!(adjustments.adjusted_score(&swap_path, 1000), 0.5);
+
+        adjustments.set_pool(PoolId::Address(pool0), adjustment(1.0, 60));
+        adjustments.set_path(swap_path.get_hash(), adjustment(-0.25, 0));
+        assert_eq!(adjustments.adjusted_score(&swap_path, 1000), 1.25);
+        // pool adjustment decayed by two half lives, path adjustment without decay
+        assert_eq!(adjustments.adjusted_score(&swap_path, 1120), 0.5);
+
+        adjustments.prune(1000 + 60 * 30);
+        assert!(adjustments.pools().is_empty());
+        assert_eq!(adjustments.paths().len(), 1);
+
+        adjustments.set_path(swap_path.get_hash(), adjustment(0.0, 0));
+        assert!(adjustments.is_empty());
+
+        // searches keep the adjustments loaded before an update
+        let shared = SharedScoreAdjustments::default();
+        let loaded = shared.load();
+        shared.update(|adjustments| adjustments.set_pool(PoolId::Address(pool0), adjustment(1.0, 0)));
+        assert!(loaded.is_empty());
+        assert_eq!(shared.clone().load().adjusted_score(&swap_path, 1000), 1.5);
+    }
+}
@@ -1,7 +1,8 @@
 use alloy_consensus::{SignableTransaction, TxEnvelope};
 use alloy_network::{TransactionBuilder, TxSigner as AlloyTxSigner, TxSignerSync};
-use alloy_primitives::{hex, Address, Bytes, B256};
+use alloy_primitives::{hex, Address, Bytes, PrimitiveSignature, B256};
 use alloy_rpc_types::Transaction;
+use alloy_signer::SignerSync;
 use alloy_signer_local::PrivateKeySigner;
 use eyre::{eyre, OptionExt, Result};
 use indexmap::IndexMap;
@@ -16,6 +17,10 @@ use std::sync::Arc;
 pub trait LoomTxSigner<LDT: LoomDataTypes>: Send + Sync + Debug {
     fn sign<'a>(&'a self, tx: LDT::TransactionRequest) -> Pin<Box<dyn std::future::Future<Output = Result<LDT::Transaction>> + Send + 'a>>;
     fn sign_sync(&self, tx: LDT::TransactionRequest) -> Result<LDT::Transaction>;
+    /// Sign a prehashed message, e.g. an EIP-712 signing hash. Signers not holding a key of their own do not sign hashes
+    fn sign_hash_sync(&self, _hash: &B256) -> Result<PrimitiveSignature> {
+        Err(eyre!("SIGN_HASH_NOT_SUPPORTED"))
+    }
     fn address(&self) -> LDT::Address;
 }
 
@@ -100,6 +105,10 @@ impl LoomTxSigner<LoomDataTypesEthereum> for TxSignerEth {
         };
         Ok(tx)
     }
+
+    fn sign_hash_sync(&self, hash: &B256) -> Result<PrimitiveSignature> {
+        Ok(self.wallet.sign_hash_sync(hash)?)
+    }
 }
 
 impl TxSignerEth {
@@ -138,6 +147,11 @@ impl<LDT: LoomDataTypes> TxSigners<LDT> {
         self.signers.is_empty()
     }
 
+    /// Add a signer not backed by a single private key, e.g. a sponsored signer
+    pub fn add_signer(&mut self, signer: Arc<dyn LoomTxSigner<LDT>>) {
+        self.signers.insert(signer.address(), signer);
+    }
+
     pub fn get_random_signer(&self) -> Option<Arc<dyn LoomTxSigner<LDT>>> {
         if self.is_empty() {
             None
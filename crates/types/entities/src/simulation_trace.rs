@@ -0,0 +1,166 @@
+use std::collections::VecDeque;
+
+use alloy_primitives::{Address, U256};
+use alloy_rpc_types_trace::geth::CallFrame;
+use serde::Serialize;
+
+use crate::PoolId;
+
+/// Traces kept when no capacity is configured
+pub const DEFAULT_SIMULATION_TRACES_CAPACITY: usize = 32;
+
+/// Storage slot changed by the simulated transaction
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct StorageWrite {
+    pub address: Address,
+    pub slot: U256,
+    pub original: U256,
+    pub present: U256,
+}
+
+/// Full trace of a simulated swap transaction, captured on demand to diagnose the gas used by an encoded sequence
+#[derive(Clone, Debug, Serialize)]
+pub struct SimulationTrace {
+    pub id: u64,
+    /// Block the transaction was simulated for
+    pub block_number: u64,
+    pub swap: String,
+    /// Pools of the swap, matched against the trace requests
+    pub pools: Vec<PoolId>,
+    pub gas_used: u64,
+    /// Revert or halt reason of a failed simulation
+    pub error: Option<String>,
+    /// Call tree with the gas of every frame
+    pub call_trace: CallFrame,
+    pub storage_writes: Vec<StorageWrite>,
+}
+
+/// Request to trace the next simulations of swaps through the pool, or of any swap
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct TraceRequest {
+    pub pool: Option<PoolId>,
+    pub remaining: usize,
+}
+
+/// Pending trace requests and the latest captured traces, the oldest traces are dropped above the capacity
+#[derive(Clone, Debug)]
+pub struct SimulationTraces {
+    requests: Vec<TraceRequest>,
+    traces: VecDeque<SimulationTrace>,
+    capacity: usize,
+    next_id: u64,
+}
+
+impl Default for SimulationTraces {
+    fn default() -> Self {
+        Self::new(DEFAULT_SIMULATION_TRACES_CAPACITY)
+    }
+}
+
+impl SimulationTraces {
+    pub fn new(capacity: usize) -> Self {
+        Self { requests: Vec::new(), traces: VecDeque::new(), capacity: capacity.max(1), next_id: 1 }
+    }
+
+    /// Trace the next `count` simulations of swaps through the pool, or of any swap if not set
+    pub fn request(&mut self, pool: Option<PoolId>, count: usize) {
+        if count > 0 {
+            self.requests.push(TraceRequest { pool, remaining: count });
+        }
+    }
+
+    pub fn requests(&self) -> &[TraceRequest] {
+        &self.requests
+    }
+
+    fn request_idx(&self, pool_ids: &[PoolId]) -> Option<usize> {
+        self.requests.iter().position(|request| request.pool.as_ref().is_none_or(|pool| pool_ids.contains(pool)))
+    }
+
+    /// Whether a simulation of a swap through the pools is traced, the request is counted when the trace is added
+    pub fn is_requested(&self, pool_ids: &[PoolId]) -> bool {
+        self.request_idx(pool_ids).is_some()
+    }
+
+    /// Count a simulation of a swap through the pools against the first matching request, returns if it is traced
+    pub fn take_request(&mut self, pool_ids: &[PoolId]) -> bool {
+        let Some(idx) = self.request_idx(pool_ids) else {
+            return false;
+        };
+        self.requests[idx].remaining -= 1;
+        if self.requests[idx].remaining == 0 {
+            self.requests.remove(idx);
+        }
+        true
+    }
+
+    /// Store the trace with a new id counted against the first matching request, returns the id. Traces of simulations
+    /// started while the requests were pending are dropped once the requests are fulfilled
+    pub fn add(&mut self, trace: SimulationTrace) -> Option<u64> {
+        if !self.take_request(&trace.pools) {
+            return None;
+        }
+        let id = self.next_id;
+        self.next_id += 1;
+        self.traces.push_back(SimulationTrace { id, ..trace });
+        while self.traces.len() > self.capacity {
+            self.traces.pop_front();
+        }
+        Some(id)
+    }
+
+    pub fn get(&self, id: u64) -> Option<&SimulationTrace> {
+        self.traces.iter().find(|trace| trace.id == id)
+    }
+
+    /// Captured traces, the oldest first
+    pub fn traces(&self) -> impl Iterator<Item = &SimulationTrace> {
+        self.traces.iter()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn trace(block_number: u64, pools: Vec<PoolId>) -> SimulationTrace {
+        SimulationTrace {
+            id: 0,
+            block_number,
+            swap: "swap".to_string(),
+            pools,
+            gas_used: 100_000,
+            error: None,
+            call_trace: CallFrame::default(),
+            storage_writes: vec![],
+        }
+    }
+
+    #[test]
+    fn test_simulation_traces() {
+        let (pool0, pool1) = (PoolId::Address(Address::repeat_byte(0x10)), PoolId::Address(Address::repeat_byte(0x11)));
+        let mut traces = SimulationTraces::new(2);
+
+        assert!(!traces.take_request(&[pool0]));
+        traces.request(Some(pool1), 2);
+        traces.request(None, 1);
+        // requests of other pools are skipped
+        assert!(traces.take_request(&[pool0]));
+        assert_eq!(traces.requests().len(), 1);
+        assert!(traces.take_request(&[pool0, pool1]));
+        assert!(traces.take_request(&[pool1]));
+        assert!(!traces.take_request(&[pool1]));
+
+        // requests are counted by the traces added, simulations without a trace leave them pending
+        traces.request(None, 3);
+        assert!(traces.is_requested(&[pool0]) && traces.is_requested(&[pool0]));
+        assert_eq!(traces.add(trace(100, vec![pool0])), Some(1));
+        assert_eq!(traces.add(trace(101, vec![pool1])), Some(2));
+        assert_eq!(traces.add(trace(102, vec![pool0])), Some(3));
+        assert!(!traces.is_requested(&[pool0]));
+        assert_eq!(traces.add(trace(103, vec![pool0])), None);
+        assert!(traces.get(1).is_none());
+        assert_eq!(traces.get(3).unwrap().block_number, 102);
+        assert_eq!(traces.traces().map(|trace| trace.id).collect::<Vec<_>>(), vec![2, 3]);
+    }
+}
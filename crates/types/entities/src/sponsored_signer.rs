@@ -0,0 +1,281 @@
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use alloy_consensus::{SignableTransaction, TxEnvelope};
+use alloy_eips::eip7702::Authorization;
+use alloy_network::{TransactionBuilder, TxSignerSync};
+use alloy_primitives::{keccak256, Address, Bytes, PrimitiveSignature, TxKind, B256, U256};
+use alloy_rpc_types::{Transaction, TransactionInput, TransactionRequest};
+use alloy_signer::SignerSync;
+use alloy_signer_local::PrivateKeySigner;
+use alloy_sol_types::{sol, SolCall};
+use eyre::{eyre, OptionExt, Result};
+use serde::Deserialize;
+
+use crate::LoomTxSigner;
+use loom_types_blockchain::{LoomDataTypes, LoomDataTypesEthereum};
+
+// EIP-3074 AUTH message prefix
+const AUTH_MAGIC: u8 = 0x04;
+
+sol! {
+    /// EIP-3074 invoker executing a call from the authority with AUTHCALL after verifying its AUTH signature
+    interface IAuthCallInvoker {
+        function execute(address authority, bytes32 commit, uint8 v, bytes32 r, bytes32 s, address to, uint256 value, bytes calldata data) external payable;
+    }
+}
+
+/// How the gas payer executes the swap on behalf of the profit account
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(tag = "mode", rename_all = "lowercase")]
+pub enum SponsorshipMode {
+    /// Type 4 transaction to the profit account, its code is delegated to `delegate` by an authorization signed by the
+    /// profit account. Requires EIP-7702 support of the chain and a multicaller implementation usable as a delegate
+    Eip7702 { delegate: Address },
+    /// Call of an invoker contract executing the swap with AUTHCALL from the profit account. Requires EIP-3074 support of
+    /// the chain
+    Eip3074 { invoker: Address },
+}
+
+/// Signer sponsoring swaps of a profit account with a separate gas payer. The gas payer signs and sends the transactions
+/// and holds only the gas inventory, swaps execute in the context of the profit account which keeps the profits.
+///
+/// The authority nonce is the nonce of the profit account, it is synced from chain through the nonce listener of the
+/// monitored profit account at startup and after each inclusion of a transaction to or from it. Authorizations with a
+/// stale nonce are skipped by EIP-7702 and keep the previous delegation, a stale EIP-3074 nonce reverts the invoker.
+pub struct SponsoredTxSigner {
+    gas_payer: PrivateKeySigner,
+    profit_account: PrivateKeySigner,
+    mode: SponsorshipMode,
+    chain_id: u64,
+    authority_nonce: Arc<AtomicU64>,
+}
+
+impl fmt::Debug for SponsoredTxSigner {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SponsoredTxSigner")
+            .field("gas_payer", &self.gas_payer.address().to_string())
+            .field("profit_account", &self.profit_account.address().to_string())
+            .field("mode", &self.mode)
+            .finish()
+    }
+}
+
+impl SponsoredTxSigner {
+    pub fn new(gas_payer: PrivateKeySigner, profit_account: PrivateKeySigner, mode: SponsorshipMode, chain_id: u64) -> Self {
+        Self { gas_payer, profit_account, mode, chain_id, authority_nonce: Arc::new(AtomicU64::new(0)) }
+    }
+
+    /// Current nonce of the profit account
+    pub fn with_authority_nonce(self, nonce: u64) -> Self {
+        self.authority_nonce.store(nonce, Ordering::Relaxed);
+        self
+    }
+
+    /// Nonce of the profit account the authorizations are signed with, updated from chain by the nonce monitor
+    pub fn authority_nonce(&self) -> Arc<AtomicU64> {
+        self.authority_nonce.clone()
+    }
+
+    pub fn profit_account(&self) -> Address {
+        self.profit_account.address()
+    }
+
+    pub fn mode(&self) -> SponsorshipMode {
+        self.mode
+    }
+
+    /// Signing hash of an EIP-3074 AUTH message
+    pub fn auth_message_hash(chain_id: u64, nonce: u64, invoker: Address, commit: B256) -> B256 {
+        let mut message = Vec::with_capacity(1 + 0x20 * 4);
+        message.push(AUTH_MAGIC);
+        message.extend_from_slice(&U256::from(chain_id).to_be_bytes::<32>());
+        message.extend_from_slice(&U256::from(nonce).to_be_bytes::<32>());
+        message.extend_from_slice(invoker.into_word().as_slice());
+        message.extend_from_slice(commit.as_slice());
+        keccak256(message)
+    }
+
+    /// Rewrite a swap transaction to the multicaller into the sponsored transaction sent by the gas payer
+    pub fn sponsor(&self, tx: TransactionRequest) -> Result<TransactionRequest> {
+        let target = tx.to.and_then(|to| to.to().copied()).ok_or_eyre("SPONSORED_TX_HAS_NO_TARGET")?;
+        let data = tx.input.input().cloned().unwrap_or_default();
+        let value = tx.value.unwrap_or_default();
+        let nonce = self.authority_nonce.load(Ordering::Relaxed);
+
+        match self.mode {
+            SponsorshipMode::Eip7702 { delegate } => {
+                if target != delegate {
+                    return Err(eyre!("SPONSORED_TARGET_IS_NOT_DELEGATE"));
+                }
+                let authorization = Authorization { chain_id: U256::from(self.chain_id), address: delegate, nonce };
+                let signature = self.profit_account.sign_hash_sync(&authorization.signature_hash())?;
+
+                Ok(TransactionRequest {
+                    from: Some(self.gas_payer.address()),
+                    to: Some(TxKind::Call(self.profit_account.address())),
+                    authorization_list: Some(vec![authorization.into_signed(signature)]),
+                    ..tx
+                })
+            }
+            SponsorshipMode::Eip3074 { invoker } => {
+                let commit = auth_commit(target, value, &data);
+                let signature = self.profit_account.sign_hash_sync(&Self::auth_message_hash(self.chain_id, nonce, invoker, commit))?;
+
+                let call_data = IAuthCallInvoker::executeCall {
+                    authority: self.profit_account.address(),
+                    commit,
+                    v: signature.v() as u8,
+                    r: B256::from(signature.r().to_be_bytes::<32>()),
+                    s: B256::from(signature.s().to_be_bytes::<32>()),
+                    to: target,
+                    value,
+                    data,
+                }
+                .abi_encode();
+
+                Ok(TransactionRequest {
+                    from: Some(self.gas_payer.address()),
+                    to: Some(TxKind::Call(invoker)),
+                    input: TransactionInput::new(Bytes::from(call_data)),
+                    ..tx
+                })
+            }
+        }
+    }
+
+    fn sign_sponsored(&self, tx: TransactionRequest) -> Result<Transaction> {
+        let typed_tx = self.sponsor(tx)?.build_unsigned().map_err(|e| eyre!(format!("CANNOT_BUILD_UNSIGNED with error: {}", e)))?;
+
+        let tx_env: TxEnvelope = match self.mode {
+            SponsorshipMode::Eip7702 { .. } => {
+                let mut typed_tx = typed_tx.eip7702().ok_or_eyre("TRANSACTION_IS_NOT_EIP7702")?.clone();
+                let signature = self.gas_payer.sign_transaction_sync(&mut typed_tx)?;
+                typed_tx.into_signed(signature).into()
+            }
+            SponsorshipMode::Eip3074 { .. } => {
+                let mut typed_tx = typed_tx.eip1559().ok_or_eyre("TRANSACTION_IS_NOT_EIP1559")?.clone();
+                let signature = self.gas_payer.sign_transaction_sync(&mut typed_tx)?;
+                typed_tx.into_signed(signature).into()
+            }
+        };
+
+        Ok(Transaction {
+            inner: tx_env,
+            block_hash: None,
+            block_number: None,
+            transaction_index: None,
+            effective_gas_price: None,
+            from: self.gas_payer.address(),
+        })
+    }
+}
+
+/// Commitment of the AUTH signature to the call executed by the invoker with AUTHCALL
+fn auth_commit(to: Address, value: U256, data: &Bytes) -> B256 {
+    let mut packed = Vec::with_capacity(20 + 0x20 + data.len());
+    packed.extend_from_slice(to.as_slice());
+    packed.extend_from_slice(&value.to_be_bytes::<32>());
+    packed.extend_from_slice(data);
+    keccak256(packed)
+}
+
+impl LoomTxSigner<LoomDataTypesEthereum> for SponsoredTxSigner {
+    fn sign<'a>(
+        &'a self,
+        tx: <LoomDataTypesEthereum as LoomDataTypes>::TransactionRequest,
+    ) -> Pin<Box<dyn Future<Output = Result<<LoomDataTypesEthereum as LoomDataTypes>::Transaction>> + Send + 'a>> {
+        Box::pin(async move { self.sign_sponsored(tx) })
+    }
+
+    fn sign_sync(
+        &self,
+        tx: <LoomDataTypesEthereum as LoomDataTypes>::TransactionRequest,
+    ) -> Result<<LoomDataTypesEthereum as LoomDataTypes>::Transaction> {
+        self.sign_sponsored(tx)
+    }
+
+    fn sign_hash_sync(&self, hash: &B256) -> Result<PrimitiveSignature> {
+        Ok(self.gas_payer.sign_hash_sync(hash)?)
+    }
+
+    /// The gas payer sends the transactions, its nonce is used
+    fn address(&self) -> Address {
+        self.gas_payer.address()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::AccountNonceAndBalanceState;
+
+    const MULTICALLER: Address = Address::repeat_byte(0x11);
+
+    fn signer(mode: SponsorshipMode) -> SponsoredTxSigner {
+        SponsoredTxSigner::new(
+            PrivateKeySigner::from_bytes(&B256::repeat_byte(1)).unwrap(),
+            PrivateKeySigner::from_bytes(&B256::repeat_byte(2)).unwrap(),
+            mode,
+            1,
+        )
+        .with_authority_nonce(7)
+    }
+
+    fn swap_tx() -> TransactionRequest {
+        TransactionRequest::default()
+            .with_to(MULTICALLER)
+            .with_input(Bytes::from(vec![0xDE, 0xAD]))
+            .with_nonce(1)
+            .with_gas_limit(300_000)
+            .with_max_fee_per_gas(10)
+            .with_max_priority_fee_per_gas(1)
+            .with_chain_id(1)
+    }
+
+    #[test]
+    fn test_eip7702_sponsorship() {
+        let signer = signer(SponsorshipMode::Eip7702 { delegate: MULTICALLER });
+        let sponsored = signer.sponsor(swap_tx()).unwrap();
+        assert_eq!(sponsored.to, Some(TxKind::Call(signer.profit_account())));
+
+        let authorization = &sponsored.authorization_list.unwrap()[0];
+        assert_eq!(authorization.address, MULTICALLER);
+        assert_eq!(authorization.nonce, 7);
+        // signing does not advance the nonce, a tx that does not land keeps it
+        assert_eq!(signer.sponsor(swap_tx()).unwrap().authorization_list.unwrap()[0].nonce, 7);
+
+        // the nonce follows the monitored profit account
+        let mut accounts = AccountNonceAndBalanceState::new();
+        accounts.add_account(signer.profit_account()).set_nonce_listener(signer.authority_nonce());
+        assert_eq!(signer.sponsor(swap_tx()).unwrap().authorization_list.unwrap()[0].nonce, 0);
+        accounts.get_mut_account(&signer.profit_account()).unwrap().set_nonce(8);
+        assert_eq!(signer.sponsor(swap_tx()).unwrap().authorization_list.unwrap()[0].nonce, 8);
+
+        let tx = signer.sign_sync(swap_tx()).unwrap();
+        assert_eq!(tx.from, signer.address());
+        assert!(matches!(tx.inner, TxEnvelope::Eip7702(_)));
+        assert!(signer.sponsor(swap_tx().with_to(Address::ZERO)).is_err());
+    }
+
+    #[test]
+    fn test_eip3074_sponsorship() {
+        let invoker = Address::repeat_byte(0x22);
+        let signer = signer(SponsorshipMode::Eip3074 { invoker });
+        let sponsored = signer.sponsor(swap_tx()).unwrap();
+        assert_eq!(sponsored.to, Some(TxKind::Call(invoker)));
+
+        let call = IAuthCallInvoker::executeCall::abi_decode(sponsored.input.input().unwrap(), true).unwrap();
+        assert_eq!(call.authority, signer.profit_account());
+        assert_eq!(call.to, MULTICALLER);
+        assert_eq!(call.data, Bytes::from(vec![0xDE, 0xAD]));
+
+        assert_eq!(call.commit, auth_commit(MULTICALLER, U256::ZERO, &call.data));
+        let signature = PrimitiveSignature::new(U256::from_be_bytes(call.r.0), U256::from_be_bytes(call.s.0), call.v != 0);
+        let auth_hash = SponsoredTxSigner::auth_message_hash(1, 7, invoker, call.commit);
+        assert_eq!(signature.recover_address_from_prehash(&auth_hash).unwrap(), signer.profit_account());
+    }
+}
@@ -0,0 +1,274 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Mutex;
+
+use alloy_consensus::BlockHeader;
+use alloy_primitives::{keccak256, Address, BlockNumber, Bytes, TxKind, B256, U256};
+use alloy_rpc_types::EIP1186AccountProofResponse;
+use alloy_rpc_types_trace::geth::AccountState;
+use alloy_trie::proof::verify_proof;
+use alloy_trie::{Nibbles, TrieAccount, EMPTY_ROOT_HASH, KECCAK_EMPTY};
+use eyre::{eyre, ErrReport, Result};
+use loom_types_blockchain::GethStateUpdate;
+use revm::primitives::{AccountInfo, Bytecode, Env};
+use revm::{DatabaseRef, Evm};
+use serde::Deserialize;
+
+const PROVEN_CALL_GAS_LIMIT: u64 = 1_000_000;
+
+/// How the required state of pools is read from the node
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StateLoadingMode {
+    /// Trace the calls of the required state and read the slots, values are trusted
+    #[default]
+    Trace,
+    /// Verify every account and slot with `eth_getProof` against the state root of the block header followed by the
+    /// bot, for nodes that are not fully trusted
+    Proof,
+}
+
+/// Block the state is proven at. The state root comes from the header chain followed by the bot and not from the node
+/// serving the proofs, so the node cannot choose the root its proofs are checked against
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ProvenBlock {
+    pub number: BlockNumber,
+    pub timestamp: u64,
+    pub state_root: B256,
+}
+
+impl ProvenBlock {
+    pub fn from_header<H: BlockHeader>(header: &H) -> Self {
+        Self { number: header.number(), timestamp: header.timestamp(), state_root: header.state_root() }
+    }
+}
+
+/// State assembled from verified proofs only. The calls of the required state are executed locally over it to find
+/// the accounts and slots they read, reads of state not proven yet are recorded and return empty values.
+#[derive(Debug, Default)]
+pub struct ProvenState {
+    accounts: GethStateUpdate,
+    // address -> slots read but not proven, an empty set for an unproven account
+    missing: Mutex<BTreeMap<Address, BTreeSet<B256>>>,
+}
+
+impl ProvenState {
+    pub fn contains_account(&self, address: &Address) -> bool {
+        self.accounts.contains_key(address)
+    }
+
+    pub fn len(&self) -> usize {
+        self.accounts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.accounts.is_empty()
+    }
+
+    /// Add a verified account or verified slots of an account added before
+    pub fn insert(&mut self, address: Address, account_state: AccountState) {
+        match self.accounts.get_mut(&address) {
+            Some(proven) => proven.storage.extend(account_state.storage),
+            None => {
+                self.accounts.insert(address, account_state);
+            }
+        }
+    }
+
+    /// Accounts and slots the calls read that are not proven yet, and the requested slots not proven yet
+    pub fn missing(&self, calls: &[(Address, Bytes)], slots: &[(Address, U256)], block: &ProvenBlock) -> BTreeMap<Address, BTreeSet<B256>> {
+        let mut env = Env::default();
+        env.block.number = U256::from(block.number);
+        env.block.timestamp = U256::from(block.timestamp);
+        env.tx.gas_limit = PROVEN_CALL_GAS_LIMIT;
+
+        for (to, call_data) in calls {
+            env.tx.transact_to = TxKind::Call(*to);
+            env.tx.data = call_data.clone();
+            let mut evm = Evm::builder().with_ref_db(self).with_env(Box::new(env.clone())).build();
+            // calls revert until the state they read is proven
+            let _ = evm.transact();
+        }
+
+        let mut missing = std::mem::take(&mut *self.missing.lock().unwrap());
+        for (address, slot) in slots {
+            let slot = B256::from(*slot);
+            if !self.accounts.get(address).is_some_and(|account| account.storage.contains_key(&slot)) {
+                missing.entry(*address).or_default().insert(slot);
+            }
+        }
+        missing
+    }
+
+    pub fn into_state(self) -> GethStateUpdate {
+        self.accounts
+    }
+
+    fn record_missing(&self, address: Address, slot: Option<B256>) {
+        let mut missing = self.missing.lock().unwrap();
+        let slots = missing.entry(address).or_default();
+        if let Some(slot) = slot {
+            slots.insert(slot);
+        }
+    }
+}
+
+impl DatabaseRef for ProvenState {
+    type Error = ErrReport;
+
+    fn basic_ref(&self, address: Address) -> Result<Option<AccountInfo>> {
+        let Some(account) = self.accounts.get(&address) else {
+            // the default caller and coinbase
+            if !address.is_zero() {
+                self.record_missing(address, None);
+            }
+            return Ok(None);
+        };
+        let code = account.code.clone().filter(|code| !code.is_empty()).map(Bytecode::new_raw);
+        Ok(Some(AccountInfo {
+            balance: account.balance.unwrap_or_default(),
+            nonce: account.nonce.unwrap_or_default(),
+            code_hash: code.as_ref().map_or(KECCAK_EMPTY, |code| code.hash_slow()),
+            code,
+        }))
+    }
+
+    fn code_by_hash_ref(&self, _code_hash: B256) -> Result<Bytecode> {
+        // the code is returned with the account
+        Ok(Bytecode::default())
+    }
+
+    fn storage_ref(&self, address: Address, index: U256) -> Result<U256> {
+        let slot = B256::from(index);
+        match self.accounts.get(&address).and_then(|account| account.storage.get(&slot)) {
+            Some(value) => Ok(U256::from_be_bytes(value.0)),
+            None => {
+                self.record_missing(address, Some(slot));
+                Ok(U256::ZERO)
+            }
+        }
+    }
+
+    fn block_hash_ref(&self, _number: u64) -> Result<B256> {
+        Ok(B256::ZERO)
+    }
+}
+
+// nodes return zero hashes for the code and storage of accounts not in the trie
+fn is_empty_account(proof: &EIP1186AccountProofResponse) -> bool {
+    proof.nonce == 0
+        && proof.balance.is_zero()
+        && (proof.code_hash == KECCAK_EMPTY || proof.code_hash.is_zero())
+        && (proof.storage_hash == EMPTY_ROOT_HASH || proof.storage_hash.is_zero())
+}
+
+/// Verify the account proof against the state root
+pub fn verify_account_proof(state_root: B256, proof: &EIP1186AccountProofResponse) -> Result<()> {
+    let expected = match is_empty_account(proof) {
+        true => None,
+        false => Some(alloy_rlp::encode(TrieAccount {
+            nonce: proof.nonce,
+            balance: proof.balance,
+            storage_root: proof.storage_hash,
+            code_hash: proof.code_hash,
+        })),
+    };
+
+    verify_proof(state_root, Nibbles::unpack(keccak256(proof.address)), expected, &proof.account_proof)
+        .map_err(|error| eyre!("ACCOUNT_PROOF_INVALID: {} {error}", proof.address))
+}
+
+/// Verify the storage proofs of the requested slots against the storage root of the verified account proof.
+/// Returns slot values, proofs are expected in the order of the requested slots.
+pub fn verify_storage_proofs(proof: &EIP1186AccountProofResponse, slots: &[B256]) -> Result<BTreeMap<B256, B256>> {
+    if proof.storage_proof.len() != slots.len() {
+        return Err(eyre!("STORAGE_PROOF_COUNT_MISMATCH"));
+    }
+    let storage_root = if proof.storage_hash.is_zero() { EMPTY_ROOT_HASH } else { proof.storage_hash };
+
+    let mut storage = BTreeMap::new();
+    for (slot, storage_proof) in slots.iter().zip(proof.storage_proof.iter()) {
+        let value: U256 = storage_proof.value;
+        let expected = if value.is_zero() { None } else { Some(alloy_rlp::encode(value)) };
+        verify_proof(storage_root, Nibbles::unpack(keccak256(slot)), expected, &storage_proof.proof)
+            .map_err(|error| eyre!("STORAGE_PROOF_INVALID: {} {slot} {error}", proof.address))?;
+        storage.insert(*slot, B256::from(value));
+    }
+    Ok(storage)
+}
+
+/// Account state built from a verified account proof with verified slot values.
+/// The code is checked against the code hash of the account.
+pub fn verified_account_state(
+    state_root: B256,
+    proof: &EIP1186AccountProofResponse,
+    slots: &[B256],
+    code: Option<Bytes>,
+) -> Result<AccountState> {
+    verify_account_proof(state_root, proof)?;
+    let storage = verify_storage_proofs(proof, slots)?;
+
+    let code = match code.filter(|code| !code.is_empty()) {
+        Some(code) => {
+            if keccak256(&code) != proof.code_hash {
+                return Err(eyre!("CODE_HASH_MISMATCH: {}", proof.address));
+            }
+            Some(code)
+        }
+        None => {
+            if !is_empty_account(proof) && proof.code_hash != KECCAK_EMPTY && !proof.code_hash.is_zero() {
+                return Err(eyre!("CODE_NOT_FOUND: {}", proof.address));
+            }
+            None
+        }
+    };
+
+    Ok(AccountState { balance: Some(proof.balance), code, nonce: Some(proof.nonce), storage })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloy_primitives::Address;
+
+    #[test]
+    fn test_empty_trie_proofs() {
+        let mut proof = EIP1186AccountProofResponse { address: Address::repeat_byte(1), ..Default::default() };
+
+        // absence of the account is proven by an empty proof against the empty root
+        assert!(verify_account_proof(EMPTY_ROOT_HASH, &proof).is_ok());
+        let state = verified_account_state(EMPTY_ROOT_HASH, &proof, &[], None).unwrap();
+        assert_eq!(state.balance, Some(U256::ZERO));
+        assert!(state.code.is_none());
+
+        // balance not included in the trie
+        proof.balance = U256::from(1);
+        assert!(verify_account_proof(EMPTY_ROOT_HASH, &proof).is_err());
+        assert!(verify_storage_proofs(&proof, &[B256::ZERO]).is_err());
+
+        // code with a different hash
+        proof.balance = U256::ZERO;
+        assert!(verified_account_state(EMPTY_ROOT_HASH, &proof, &[], Some(Bytes::from(vec![0x60, 0x00]))).is_err());
+    }
+
+    #[test]
+    fn test_proven_state_missing() {
+        let pool = Address::repeat_byte(1);
+        let block = ProvenBlock { number: 1, timestamp: 1, state_root: EMPTY_ROOT_HASH };
+        let mut proven_state = ProvenState::default();
+
+        // SLOAD(1) SLOAD(0) ADD, the account is read before its slots
+        let code = Bytes::from(vec![0x60, 0x01, 0x54, 0x60, 0x00, 0x54, 0x01, 0x00]);
+        let calls = vec![(pool, Bytes::new())];
+        let missing = proven_state.missing(&calls, &[(pool, U256::from(5))], &block);
+        assert_eq!(missing.get(&pool), Some(&BTreeSet::from([B256::from(U256::from(5))])));
+
+        proven_state.insert(pool, AccountState { code: Some(code), ..AccountState::default() });
+        let missing = proven_state.missing(&calls, &[], &block);
+        assert_eq!(missing.get(&pool), Some(&BTreeSet::from([B256::from(U256::from(1)), B256::ZERO])));
+
+        let storage = BTreeMap::from([(B256::ZERO, B256::ZERO), (B256::from(U256::from(1)), B256::from(U256::from(2)))]);
+        proven_state.insert(pool, AccountState { storage, ..AccountState::default() });
+        assert!(proven_state.missing(&calls, &[], &block).is_empty());
+        assert_eq!(proven_state.len(), 1);
+    }
+}
@@ -2,6 +2,7 @@ use alloy_primitives::Address;
 use serde::de::DeserializeOwned;
 use std::path::PathBuf;
 use thiserror::Error;
+#[cfg(feature = "provider")]
 use tokio::fs;
 
 #[derive(Debug, Error)]
@@ -17,6 +18,7 @@ pub trait StrategyConfig {
     fn eoa(&self) -> Option<Address>;
 }
 
+#[cfg(feature = "provider")]
 pub async fn load_from_file<C: DeserializeOwned>(file_path: PathBuf) -> Result<C, LoadConfigError> {
     let contents = fs::read_to_string(file_path).await?;
     let config: C = toml::from_str(&contents)?;
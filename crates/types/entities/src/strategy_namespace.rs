@@ -0,0 +1,139 @@
+use std::collections::HashMap;
+
+use alloy_primitives::utils::parse_units;
+use alloy_primitives::{Address, U256};
+use eyre::{eyre, Result};
+use serde::Deserialize;
+
+/// Signers and limits of a strategy namespace. Strategy instances running in a namespace sign their swaps with its signers only
+/// and share its limits, a namespace exhausting its limits or the balances of its signers does not affect the others
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct NamespaceConfig {
+    /// Signers owned by the namespace, not used by swaps of other namespaces or without a namespace
+    pub signers: Vec<Address>,
+    /// Input capital in ETH of the swaps targeting the same block
+    pub max_capital_eth: Option<String>,
+    /// Swaps targeting the same block
+    pub max_swaps_per_block: Option<usize>,
+}
+
+impl NamespaceConfig {
+    pub fn max_capital_eth(&self) -> Option<U256> {
+        self.max_capital_eth.as_ref().and_then(|value| parse_units(value, "ether").ok()).map(|value| value.get_absolute())
+    }
+}
+
+/// Swaps of a namespace for its latest target block
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct NamespaceUsage {
+    pub block_number: u64,
+    pub swaps: usize,
+    pub capital_eth: U256,
+    /// Swaps dropped by the limits
+    pub rejected: usize,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct StrategyNamespaces {
+    namespaces: HashMap<String, (NamespaceConfig, NamespaceUsage)>,
+}
+
+impl StrategyNamespaces {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, name: String, config: NamespaceConfig) -> Result<()> {
+        if self.namespaces.contains_key(&name) {
+            return Err(eyre!("NAMESPACE_ALREADY_EXISTS"));
+        }
+        if let Some(signer) = config.signers.iter().find(|signer| self.owner(signer).is_some()) {
+            return Err(eyre!("NAMESPACE_SIGNER_SHARED : {signer}"));
+        }
+        self.namespaces.insert(name, (config, NamespaceUsage::default()));
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.namespaces.is_empty()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&NamespaceConfig> {
+        self.namespaces.get(name).map(|(config, _)| config)
+    }
+
+    pub fn usage(&self, name: &str) -> Option<&NamespaceUsage> {
+        self.namespaces.get(name).map(|(_, usage)| usage)
+    }
+
+    /// Namespace owning the signer
+    pub fn owner(&self, signer: &Address) -> Option<&str> {
+        self.namespaces.iter().find(|(_, (config, _))| config.signers.contains(signer)).map(|(name, _)| name.as_str())
+    }
+
+    /// Signers out of `signers` usable by the swaps of the namespace, or by swaps without a namespace
+    pub fn allowed_signers(&self, namespace: Option<&str>, signers: &[Address]) -> Result<Vec<Address>> {
+        match namespace {
+            Some(name) => {
+                let config = self.get(name).ok_or_else(|| eyre!("NAMESPACE_NOT_FOUND : {name}"))?;
+                Ok(signers.iter().filter(|signer| config.signers.contains(signer)).cloned().collect())
+            }
+            None => Ok(signers.iter().filter(|signer| self.owner(signer).is_none()).cloned().collect()),
+        }
+    }
+
+    /// Count a swap with its input capital against the limits of the namespace for the target block
+    pub fn reserve(&mut self, name: &str, block_number: u64, capital_eth: U256) -> Result<()> {
+        let (config, usage) = self.namespaces.get_mut(name).ok_or_else(|| eyre!("NAMESPACE_NOT_FOUND : {name}"))?;
+        if block_number > usage.block_number {
+            *usage = NamespaceUsage { block_number, ..NamespaceUsage::default() };
+        }
+
+        if config.max_swaps_per_block.is_some_and(|max_swaps| usage.swaps >= max_swaps) {
+            usage.rejected += 1;
+            return Err(eyre!("NAMESPACE_SWAP_LIMIT"));
+        }
+        if config.max_capital_eth().is_some_and(|max_capital| usage.capital_eth + capital_eth > max_capital) {
+            usage.rejected += 1;
+            return Err(eyre!("NAMESPACE_CAPITAL_LIMIT"));
+        }
+        usage.swaps += 1;
+        usage.capital_eth += capital_eth;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloy_primitives::utils::Unit;
+
+    const ONE_ETHER: U256 = Unit::ETHER.wei_const();
+
+    #[test]
+    fn test_namespaces() {
+        let (signer0, signer1, signer2) = (Address::repeat_byte(1), Address::repeat_byte(2), Address::repeat_byte(3));
+        let mut namespaces = StrategyNamespaces::new();
+        let aggressive = NamespaceConfig { signers: vec![signer0], max_capital_eth: Some("10".to_string()), max_swaps_per_block: Some(2) };
+        namespaces.add("aggressive".to_string(), aggressive).unwrap();
+        namespaces.add("conservative".to_string(), NamespaceConfig { signers: vec![signer1], ..NamespaceConfig::default() }).unwrap();
+        assert!(namespaces.add("other".to_string(), NamespaceConfig { signers: vec![signer0], ..NamespaceConfig::default() }).is_err());
+
+        let signers = [signer0, signer1, signer2];
+        assert_eq!(namespaces.allowed_signers(Some("aggressive"), &signers).unwrap(), vec![signer0]);
+        assert_eq!(namespaces.allowed_signers(None, &signers).unwrap(), vec![signer2]);
+        assert!(namespaces.allowed_signers(Some("unknown"), &signers).is_err());
+
+        namespaces.reserve("aggressive", 100, ONE_ETHER * U256::from(6)).unwrap();
+        assert!(namespaces.reserve("aggressive", 100, ONE_ETHER * U256::from(6)).is_err());
+        namespaces.reserve("aggressive", 100, ONE_ETHER).unwrap();
+        assert!(namespaces.reserve("aggressive", 100, U256::ZERO).is_err());
+        assert_eq!(namespaces.usage("aggressive").unwrap().rejected, 2);
+
+        // limits of other namespaces and of the next block are not affected
+        namespaces.reserve("conservative", 100, ONE_ETHER * U256::from(100)).unwrap();
+        namespaces.reserve("aggressive", 101, ONE_ETHER * U256::from(6)).unwrap();
+        assert_eq!(namespaces.usage("aggressive").unwrap().swaps, 1);
+    }
+}
@@ -1,7 +1,7 @@
 use std::fmt::{Display, Formatter};
 use std::sync::Arc;
 
-use crate::{PoolId, PoolWrapper, SwapLine, SwapStep, Token};
+use crate::{FundingMode, PoolId, PoolWrapper, SwapLine, SwapStep, Token};
 use alloy_primitives::U256;
 use loom_types_blockchain::{LoomDataTypes, LoomDataTypesEthereum};
 
@@ -41,7 +41,17 @@ impl<LDT: LoomDataTypes> Swap<LDT> {
             Swap::BackrunSwapSteps((sp0, sp1)) => SwapStep::abs_profit(sp0, sp1),
             Swap::Multiple(swap_vec) => swap_vec.iter().map(|x| x.abs_profit()).sum(),
             Swap::None => U256::ZERO,
-            Swap::ExchangeSwapLine(_) => U256::ZERO,
+            Swap::ExchangeSwapLine(path) => path.exchange_abs_profit(),
+        }
+    }
+
+    /// Funding mode the swap is encoded with, `None` for multiple swaps
+    pub fn funding_mode(&self) -> Option<FundingMode> {
+        match self {
+            Swap::BackrunSwapLine(swap_line) => Some(swap_line.funding_mode(swap_line.amount_in.unwrap_or_default())),
+            Swap::BackrunSwapSteps((sp0, sp1)) => Some(SwapStep::funding_mode(sp0, sp1)),
+            Swap::ExchangeSwapLine(_) => Some(FundingMode::Balance),
+            Swap::Multiple(_) | Swap::None => None,
         }
     }
 
@@ -60,7 +70,9 @@ impl<LDT: LoomDataTypes> Swap<LDT> {
 
     pub fn abs_profit_eth(&self) -> U256 {
         match self {
-            Swap::ExchangeSwapLine(_) => U256::ZERO,
+            Swap::ExchangeSwapLine(path) => {
+                path.get_first_token().and_then(|token| token.calc_eth_value(path.exchange_abs_profit())).unwrap_or_default()
+            }
             Swap::BackrunSwapLine(path) => path.abs_profit_eth(),
             Swap::BackrunSwapSteps((sp0, sp1)) => SwapStep::abs_profit_eth(sp0, sp1),
             Swap::Multiple(swap_vec) => swap_vec.iter().map(|x| x.abs_profit_eth()).sum(),
@@ -68,6 +80,39 @@ impl<LDT: LoomDataTypes> Swap<LDT> {
         }
     }
 
+    /// ETH value of the input amounts
+    pub fn capital_eth(&self) -> U256 {
+        match self {
+            Swap::ExchangeSwapLine(path) | Swap::BackrunSwapLine(path) => {
+                path.get_first_token().and_then(|token| token.calc_eth_value(path.amount_in.unwrap_or_default())).unwrap_or_default()
+            }
+            Swap::BackrunSwapSteps((sp0, _sp1)) => sp0
+                .get_first_token()
+                .zip(sp0.get_in_amount().ok())
+                .and_then(|(token, amount_in)| token.calc_eth_value(amount_in))
+                .unwrap_or_default(),
+            Swap::Multiple(swap_vec) => swap_vec.iter().map(|x| x.capital_eth()).sum(),
+            Swap::None => U256::ZERO,
+        }
+    }
+
+    /// Swap lines of multiple independent backrun swap lines with set input amounts, `None` for other swaps
+    pub fn independent_swap_lines(&self) -> Option<Vec<SwapLine<LDT>>> {
+        let Swap::Multiple(swap_vec) = self else {
+            return None;
+        };
+        if swap_vec.len() < 2 {
+            return None;
+        }
+        swap_vec
+            .iter()
+            .map(|swap| match swap {
+                Swap::BackrunSwapLine(swap_line) if swap_line.amount_in.is_set() => Some(swap_line.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
     pub fn get_first_token(&self) -> Option<&Arc<Token<LDT>>> {
         match self {
             Swap::ExchangeSwapLine(swap_path) => swap_path.get_first_token(),
@@ -119,4 +164,17 @@ impl<LDT: LoomDataTypes> Swap<LDT> {
             Swap::None => Vec::new(),
         }
     }
+
+    /// Pools of the swap that hold a rebasing token
+    pub fn get_rebasing_pools_vec(&self) -> Vec<PoolWrapper<LDT>> {
+        match self {
+            Swap::ExchangeSwapLine(swap_line) => swap_line.rebasing_pools(),
+            Swap::BackrunSwapLine(swap_line) => swap_line.rebasing_pools(),
+            Swap::BackrunSwapSteps((sp0, sp1)) => {
+                sp0.swap_line_vec().iter().chain(sp1.swap_line_vec().iter()).flat_map(|item| item.rebasing_pools()).collect()
+            }
+            Swap::Multiple(swap_vec) => swap_vec.iter().flat_map(|x| x.get_rebasing_pools_vec()).collect(),
+            Swap::None => Vec::new(),
+        }
+    }
 }
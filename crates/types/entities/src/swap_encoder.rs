@@ -1,7 +1,7 @@
 use crate::tips::Tips;
-use crate::Swap;
+use crate::{ChainPreset, ExecutionProfile, SharedTipRecipients, Swap};
 use alloy_primitives::{Address, BlockNumber, Bytes, U256};
-use eyre::Result;
+use eyre::{eyre, Result};
 use std::ops::Deref;
 use std::sync::Arc;
 
@@ -12,6 +12,7 @@ pub trait SwapEncoder {
     /// - next_block_gas_price - base_fee + priority fee for transaction
     /// - sender_address - EOA of of the transaction
     /// - sender_eth_balance - balance of EOA
+    /// - profit_receiver - receiver of the profit net of the gas and tips, kept by the multicaller if not set
     ///
     /// returns (to. value, call_data) for transaction
     #[allow(clippy::too_many_arguments)]
@@ -23,12 +24,44 @@ pub trait SwapEncoder {
         gas_cost: Option<U256>,
         sender_address: Option<Address>,
         sender_eth_balance: Option<U256>,
+        profit_receiver: Option<Address>,
     ) -> Result<(Address, Option<U256>, Bytes, Vec<Tips>)>
     where
         Self: Sized;
 
+    /// Encode the swap without tips for the public mempool. The transaction reverts when the profit of the swap is below
+    /// `min_profit` in units of its first token or the block timestamp is after `deadline`, so a front-run or a state
+    /// change costs the gas of a revert instead of a loss.
+    ///
+    /// returns (to, call_data) for transaction
+    fn encode_revert_on_loss(&self, _swap: &Swap, _min_profit: U256, _deadline: u64) -> Result<(Address, Bytes)> {
+        Err(eyre!("REVERT_ON_LOSS_NOT_SUPPORTED"))
+    }
+
     fn set_address(&mut self, address: Address);
 
+    /// Adapt the encoding to how the transactions reach the block, e.g. skip tips on chains without builders
+    fn set_execution_profile(&mut self, _execution_profile: ExecutionProfile) {}
+
+    /// Profile the swaps are encoded for
+    fn execution_profile(&self) -> ExecutionProfile {
+        ExecutionProfile::default()
+    }
+
+    /// Reset the allowances given to pools to zero after each hop
+    fn set_revoke_approvals(&mut self, _revoke_approvals: bool) {}
+
+    /// Pay the tips to the recipient of the builder the swaps are sent to, read on every encoded swap
+    fn set_tip_recipients(&mut self, _tip_recipients: SharedTipRecipients, _builder: Option<String>) {}
+
+    /// Use the flash loan lender, periphery contracts and pool class encoders available on the chain
+    fn set_chain_preset(&mut self, _chain_preset: ChainPreset) {}
+
+    /// Preset the swaps are encoded with
+    fn chain_preset(&self) -> ChainPreset {
+        ChainPreset::default()
+    }
+
     fn address(&self) -> Address;
 }
 
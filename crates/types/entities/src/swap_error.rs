@@ -4,6 +4,15 @@ use eyre::{eyre, Report};
 use loom_types_blockchain::{LoomDataTypes, LoomDataTypesEthereum};
 use std::hash::{Hash, Hasher};
 
+const TICK_WORD_NOT_LOADED: &str = "TICK_WORD_NOT_LOADED";
+/// No amount of the swap line is profitable within the limits of the calculation, e.g. the capital limit
+pub const NOT_PROFITABLE: &str = "NOT_PROFITABLE";
+
+/// Calculation reached a tick bitmap word that is not loaded into the state db yet
+pub fn tick_word_not_loaded(word: i16) -> Report {
+    eyre!("{TICK_WORD_NOT_LOADED} {word}")
+}
+
 #[derive(Clone, Debug)]
 pub struct EstimationError<LDT: LoomDataTypes = LoomDataTypesEthereum> {
     pub msg: String,
@@ -28,6 +37,18 @@ pub struct SwapError<LDT: LoomDataTypes = LoomDataTypesEthereum> {
     pub amount: U256,
 }
 
+impl<LDT: LoomDataTypes> SwapError<LDT> {
+    /// Tick bitmap word missing in the state db. The pool is not broken, its tick range has to be extended
+    pub fn missing_tick_word(&self) -> Option<i16> {
+        self.msg.strip_prefix(TICK_WORD_NOT_LOADED)?.trim().parse().ok()
+    }
+
+    /// Swap line without a profitable amount, the pools are not broken
+    pub fn is_not_profitable(&self) -> bool {
+        self.msg == NOT_PROFITABLE
+    }
+}
+
 impl<LDT: LoomDataTypes> From<SwapError<LDT>> for Report {
     fn from(value: SwapError<LDT>) -> Self {
         eyre!(value.msg)
@@ -49,3 +70,24 @@ impl<LDT: LoomDataTypes> PartialEq<Self> for SwapError<LDT> {
 }
 
 impl<LDT: LoomDataTypes> Eq for SwapError<LDT> {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_missing_tick_word() {
+        let mut swap_error: SwapError = SwapError {
+            msg: tick_word_not_loaded(-3).to_string(),
+            pool: PoolId::default(),
+            token_from: Default::default(),
+            token_to: Default::default(),
+            is_in_amount: true,
+            amount: U256::ZERO,
+        };
+        assert_eq!(swap_error.missing_tick_word(), Some(-3));
+
+        swap_error.msg = "RETURN_RESULT_IS_ZERO".to_string();
+        assert_eq!(swap_error.missing_tick_word(), None);
+    }
+}
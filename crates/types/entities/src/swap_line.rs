@@ -4,13 +4,13 @@ use std::sync::Arc;
 
 use alloy_primitives::{I256, U256};
 use eyre::{eyre, ErrReport, Report, Result};
-use loom_types_blockchain::{LoomDataTypes, LoomDataTypesEthereum};
+use loom_types_blockchain::{LoomDataTypes, LoomDataTypesEthereum, StackSlot};
 use revm::primitives::Env;
 use revm::DatabaseRef;
 use tracing::debug;
 
 use crate::swap_path::SwapPath;
-use crate::{CalculationResult, PoolId, PoolWrapper, SwapError, SwapStep, Token};
+use crate::{CalculationResult, FundingMode, PoolClass, PoolId, PoolWrapper, SwapError, SwapStep, Token};
 
 #[derive(Debug, Clone, Default)]
 pub enum SwapAmountType<LDT: LoomDataTypes = LoomDataTypesEthereum> {
@@ -48,6 +48,28 @@ impl<LDT: LoomDataTypes> SwapAmountType<LDT> {
     pub fn is_not_set(&self) -> bool {
         !matches!(self, Self::Set(_))
     }
+
+    /// Stack slot the amount is read from, `None` if the amount is encoded in call data.
+    /// Balance is read from the slot pushed by the preceding balanceOf call.
+    #[inline]
+    pub fn stack_slot(&self) -> Option<StackSlot> {
+        match self {
+            Self::Set(_) => None,
+            Self::NotSet | Self::Balance(_) => Some(StackSlot::Last),
+            Self::RelativeStack(offset) => Some(StackSlot::Relative(*offset)),
+            Self::Stack0 => Some(StackSlot::Absolute(0)),
+        }
+    }
+
+    /// Number of stack slots that must be provided by the preceding calls to read the amount.
+    #[inline]
+    pub fn inherited_stack_slots(&self) -> u32 {
+        match self {
+            Self::Set(_) | Self::Balance(_) => 0,
+            Self::NotSet | Self::Stack0 => 1,
+            Self::RelativeStack(offset) => offset + 1,
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -61,8 +83,20 @@ pub struct SwapLine<LDT: LoomDataTypes = LoomDataTypesEthereum> {
     pub calculation_results: Vec<CalculationResult>,
     /// Output token of the swap
     pub swap_to: Option<LDT::Address>,
+    /// Minimal output amount paid to the `swap_to` recipient, enforced on-chain
+    pub min_amount_out: Option<U256>,
+    /// Pay a wrapped native output to the `swap_to` recipient as native ETH, only set for EOA recipients
+    pub native_payout: bool,
     /// Gas used for the swap
     pub gas_used: Option<u64>,
+    /// Funding mode chosen for the swap, the cheapest borrowing mode is used when not set
+    pub funding: Option<FundingMode>,
+}
+
+/// Pool `fee` has a lower fee rate than pool `other`, fees in units not known for the pool class are never lower
+fn has_lower_fee(fee: Option<(PoolClass, U256)>, other: Option<(PoolClass, U256)>) -> bool {
+    let fee_ppm = |fee: Option<(PoolClass, U256)>| fee.and_then(|(pool_class, fee)| pool_class.fee_ppm(fee));
+    matches!((fee_ppm(fee), fee_ppm(other)), (Some(fee), Some(other)) if fee < other)
 }
 
 impl<LDT: LoomDataTypes> Default for SwapLine<LDT> {
@@ -73,7 +107,10 @@ impl<LDT: LoomDataTypes> Default for SwapLine<LDT> {
             amount_out: SwapAmountType::default(),
             calculation_results: Vec::default(),
             swap_to: None,
+            min_amount_out: None,
+            native_payout: false,
             gas_used: None,
+            funding: None,
         }
     }
 }
@@ -195,12 +232,41 @@ impl<LDT: LoomDataTypes> SwapLine<LDT> {
         self.pools().last()
     }
 
+    /// Remove consecutive hops through the same pool swapping back into the token they started from, such round trips only
+    /// pay the pool fee twice. The input and output amounts are kept, the hop calculation results are dropped. Returns the
+    /// number of hops removed
+    pub fn cancel_round_trips(&mut self) -> usize {
+        if self.path.tokens.len() != self.path.pools.len() + 1 {
+            return 0;
+        }
+
+        let mut removed = 0;
+        let mut idx = 0;
+        while idx + 1 < self.path.pools.len() {
+            let round_trip = self.path.pools[idx] == self.path.pools[idx + 1] && self.path.tokens[idx] == self.path.tokens[idx + 2];
+            // the swap line keeps at least one hop
+            if round_trip && self.path.pools.len() > 2 {
+                self.path.pools.drain(idx..idx + 2);
+                self.path.tokens.drain(idx + 1..idx + 3);
+                removed += 2;
+                idx = idx.saturating_sub(1);
+            } else {
+                idx += 1;
+            }
+        }
+        if removed > 0 {
+            self.calculation_results.clear();
+        }
+        removed
+    }
+
     /// Convert the swap line to two swap steps for flash swapping
     pub fn to_swap_steps(&self, multicaller: LDT::Address) -> Option<(SwapStep<LDT>, SwapStep<LDT>)> {
         let mut sp0: Option<SwapLine<LDT>> = None;
         let mut sp1: Option<SwapLine<LDT>> = None;
 
-        for i in 1..self.path.pool_count() {
+        let pool_count = self.path.pool_count();
+        for i in 1..pool_count {
             let (head_path, mut tail_path) = self.split(i).unwrap();
             if head_path.can_flash_swap() || tail_path.can_flash_swap() {
                 if head_path.can_flash_swap() {
@@ -212,6 +278,15 @@ impl<LDT: LoomDataTypes> SwapLine<LDT> {
             }
         }
 
+        // flash swap the last pool instead of the first one when its fee is lower
+        if sp0.as_ref().is_some_and(|head_path| head_path.pool_count() == 1 && head_path.can_flash_swap()) && pool_count > 2 {
+            let (head_path, tail_path) = self.split(pool_count - 1).unwrap();
+            if tail_path.can_flash_swap() && has_lower_fee(self.pool_fee(pool_count - 1), self.pool_fee(0)) {
+                sp0 = Some(head_path);
+                sp1 = Some(tail_path);
+            }
+        }
+
         if sp0.is_none() || sp1.is_none() {
             let (head_path, tail_path) = self.split(1).unwrap();
             sp0 = Some(head_path);
@@ -228,6 +303,13 @@ impl<LDT: LoomDataTypes> SwapLine<LDT> {
         Some((step_0, step_1))
     }
 
+    /// Class and fee of the pool at `pool_index` in the swap direction of the path
+    fn pool_fee(&self, pool_index: usize) -> Option<(PoolClass, U256)> {
+        let pool = self.pools().get(pool_index)?;
+        let fee = pool.get_fee_by_direction(&self.tokens()[pool_index].get_address(), &self.tokens()[pool_index + 1].get_address());
+        Some((pool.get_class(), fee))
+    }
+
     /// Split the swap line into two swap lines at a specific pool index
     pub fn split(&self, pool_index: usize) -> Result<(SwapLine<LDT>, SwapLine<LDT>)> {
         let first = SwapLine::<LDT> {
@@ -236,7 +318,10 @@ impl<LDT: LoomDataTypes> SwapLine<LDT> {
             amount_out: SwapAmountType::NotSet,
             calculation_results: vec![],
             swap_to: None,
+            min_amount_out: None,
+            native_payout: false,
             gas_used: None,
+            funding: None,
         };
         let second = SwapLine::<LDT> {
             path: SwapPath::new(self.tokens()[pool_index..].to_vec(), self.pools()[pool_index..].to_vec()),
@@ -244,19 +329,76 @@ impl<LDT: LoomDataTypes> SwapLine<LDT> {
             amount_out: self.amount_out,
             calculation_results: vec![],
             swap_to: None,
+            min_amount_out: None,
+            native_payout: false,
             gas_used: None,
+            funding: None,
         };
         Ok((first, second))
     }
 
-    /// Check if all pools in the swap line can be flash swapped
+    /// Pools of the swap line that hold a rebasing token
+    pub fn rebasing_pools(&self) -> Vec<PoolWrapper<LDT>> {
+        self.pools().iter().enumerate().filter(|(idx, _)| self.pool_has_rebasing_token(*idx)).map(|(_, pool)| pool.clone()).collect()
+    }
+
+    fn pool_has_rebasing_token(&self, pool_index: usize) -> bool {
+        self.tokens().get(pool_index).is_some_and(|token| token.is_rebasing())
+            || self.tokens().get(pool_index + 1).is_some_and(|token| token.is_rebasing())
+    }
+
+    /// Check if any token of the swap line is rebasing
+    pub fn has_rebasing_token(&self) -> bool {
+        self.tokens().iter().any(|token| token.is_rebasing())
+    }
+
+    /// Check if any token of the swap line calls transfer hooks
+    pub fn has_transfer_hook_token(&self) -> bool {
+        self.tokens().iter().any(|token| token.has_transfer_hook())
+    }
+
+    /// Check if all pools in the swap line can be flash swapped. Reserves of pools with rebasing tokens do not match
+    /// the balances, so their hops are never flash swapped. Hooks of transferred tokens can reenter the flash swap
+    /// callback, so swap lines with such tokens are never flash swapped.
     pub fn can_flash_swap(&self) -> bool {
-        for pool in self.pools().iter() {
-            if !pool.can_flash_swap() {
-                return false;
-            }
+        if self.has_transfer_hook_token() {
+            return false;
         }
-        true
+        self.pools().iter().enumerate().all(|(idx, pool)| !self.pool_has_rebasing_token(idx) && pool.supports_flash_swap())
+    }
+
+    /// Funding alternatives the multicaller can encode for the swap line: a flash swap if the first or the last pool
+    /// can be flash swapped, a Balancer flash loan otherwise
+    pub fn funding_modes(&self) -> Vec<FundingMode> {
+        let pool_count = self.path.pool_count();
+        let can_flash_swap = pool_count > 1
+            && !self.has_transfer_hook_token()
+            && ((self.get_first_pool().is_some_and(|pool| pool.supports_flash_swap()) && !self.pool_has_rebasing_token(0))
+                || (self.get_last_pool().is_some_and(|pool| pool.supports_flash_swap()) && !self.pool_has_rebasing_token(pool_count - 1)));
+        if can_flash_swap {
+            vec![FundingMode::FlashSwap, FundingMode::BalancerFlashLoan]
+        } else {
+            vec![FundingMode::BalancerFlashLoan]
+        }
+    }
+
+    /// Chosen funding mode or the cheapest one for borrowing `amount_in`
+    pub fn funding_mode(&self, amount_in: U256) -> FundingMode {
+        self.funding.unwrap_or_else(|| FundingMode::cheapest(&self.funding_modes(), amount_in).unwrap_or_default())
+    }
+
+    /// Fund the swap from the multicaller `inventory` of the input token when it covers the input amount,
+    /// borrow otherwise. Returns the chosen mode
+    pub fn select_funding(&mut self, inventory: U256) -> FundingMode {
+        let amount_in = self.amount_in.unwrap_or_default();
+        let funding = FundingMode::with_inventory(&self.funding_modes(), amount_in, inventory).unwrap_or_default();
+        self.funding = Some(funding);
+        funding
+    }
+
+    /// Fee of the cheapest funding mode for borrowing `amount_in`
+    pub fn funding_fee(&self, amount_in: U256) -> U256 {
+        self.funding_mode(amount_in).fee(amount_in)
     }
 
     /// Calculate the absolute profit of the swap line
@@ -276,11 +418,33 @@ impl<LDT: LoomDataTypes> SwapLine<LDT> {
         let SwapAmountType::Set(amount_out) = self.amount_out else {
             return U256::ZERO;
         };
-        if amount_out > amount_in {
-            return amount_out - amount_in;
-        }
+        amount_out.saturating_sub(amount_in.saturating_add(self.funding_fee(amount_in)))
+    }
+
+    /// Calculate the expected output surplus of an exchange swap line in first token units.
+    /// The output is valued at the current token ETH prices and compared with the input amount.
+    pub fn exchange_abs_profit(&self) -> U256 {
+        let (Some(token_in), Some(token_out)) = (self.tokens().first(), self.tokens().last()) else {
+            return U256::ZERO;
+        };
+        let (SwapAmountType::Set(amount_in), SwapAmountType::Set(amount_out)) = (self.amount_in, self.amount_out) else {
+            return U256::ZERO;
+        };
+        let Some(amount_out_in_token_in) =
+            token_out.calc_eth_value(amount_out).and_then(|amount_out_eth| token_in.calc_token_value_from_eth(amount_out_eth))
+        else {
+            return U256::ZERO;
+        };
+
+        amount_out_in_token_in.saturating_sub(amount_in)
+    }
 
-        U256::ZERO
+    /// Gas of each calculated hop with the class of its pool, empty if the hops are not calculated
+    pub fn hop_gas(&self) -> Vec<(PoolClass, u64)> {
+        if self.calculation_results.len() != self.pools().len() {
+            return Vec::new();
+        }
+        self.pools().iter().zip(self.calculation_results.iter()).map(|(pool, result)| (pool.get_class(), result.gas_used)).collect()
     }
 
     /// Calculate the absolute profit of the swap line in ETH
@@ -301,7 +465,9 @@ impl<LDT: LoomDataTypes> SwapLine<LDT> {
                 return if token_in == token_out {
                     if let SwapAmountType::Set(amount_in) = self.amount_in {
                         if let SwapAmountType::Set(amount_out) = self.amount_out {
-                            return Ok(I256::from_raw(amount_out) - I256::from_raw(amount_in));
+                            return Ok(I256::from_raw(amount_out)
+                                - I256::from_raw(amount_in)
+                                - I256::from_raw(self.funding_fee(amount_in)));
                         }
                     }
                     Err(eyre!("AMOUNTS_NOT_SET"))
@@ -353,7 +519,19 @@ impl<LDT: LoomDataTypes> SwapLine<LDT> {
                         });
                     }
 
-                    calculation_results.push(CalculationResult::new(current_in_amount, out_amount_result));
+                    // rounds to zero in the next hop or at the transfer, reverting only in the simulation
+                    if out_amount_result < token_to.get_dust_amount() {
+                        return Err(SwapError::<LDT> {
+                            msg: "DUST_OUT_AMOUNT".to_string(),
+                            pool: pool.get_pool_id(),
+                            token_from: token_from.get_address(),
+                            token_to: token_to.get_address(),
+                            is_in_amount: true,
+                            amount: current_in_amount,
+                        });
+                    }
+
+                    calculation_results.push(CalculationResult::new(current_in_amount, out_amount_result).with_gas_used(gas_result));
                     current_in_amount = out_amount_result;
                     final_out_amount = out_amount_result;
                     gas_used += gas_result
@@ -407,7 +585,17 @@ impl<LDT: LoomDataTypes> SwapLine<LDT> {
                             amount: current_out_amount,
                         });
                     }
-                    calculation_results.push(CalculationResult::new(current_out_amount, in_amount_result));
+                    if in_amount_result < token_from.get_dust_amount() {
+                        return Err(SwapError::<LDT> {
+                            msg: "DUST_IN_AMOUNT".to_string(),
+                            pool: pool.get_pool_id(),
+                            token_from: token_from.get_address(),
+                            token_to: token_to.get_address(),
+                            is_in_amount: false,
+                            amount: current_out_amount,
+                        });
+                    }
+                    calculation_results.push(CalculationResult::new(current_out_amount, in_amount_result).with_gas_used(gas_result));
                     current_out_amount = in_amount_result;
                     final_in_amount = in_amount_result;
                     gas_used += gas_result;
@@ -467,7 +655,8 @@ impl<LDT: LoomDataTypes> SwapLine<LDT> {
                     }
                 };
 
-            let current_profit = I256::from_raw(current_out_amount) - I256::from_raw(next_amount);
+            let current_profit =
+                I256::from_raw(current_out_amount) - I256::from_raw(next_amount) - I256::from_raw(self.funding_fee(next_amount));
 
             if best_profit.is_none() {
                 best_profit = Some(current_profit);
@@ -537,6 +726,7 @@ mod tests {
     use alloy_primitives::utils::parse_units;
     use alloy_primitives::Address;
     use loom_defi_address_book::{TokenAddressEth, UniswapV2PoolAddress, UniswapV3PoolAddress};
+    use loom_evm_db::LoomDBType;
     use std::sync::Arc;
 
     fn default_swap_line() -> (MockPool, MockPool, SwapLine<LoomDataTypesEthereum>) {
@@ -556,7 +746,10 @@ mod tests {
             amount_out: SwapAmountType::Set(parse_units("0.03", "ether").unwrap().get_absolute()),
             calculation_results: vec![],
             swap_to: Some(Address::default()),
+            min_amount_out: None,
+            native_payout: false,
             gas_used: Some(10000),
+            funding: None,
         };
 
         (pool1, pool2, swap_line)
@@ -633,4 +826,115 @@ mod tests {
         let pool = swap_line.get_last_pool();
         assert_eq!(pool.unwrap().get_address(), pool2.address);
     }
+
+    #[test]
+    fn test_rebasing_pools() {
+        let (_, _, swap_line) = default_swap_line();
+        assert!(!swap_line.has_rebasing_token());
+        assert!(swap_line.rebasing_pools().is_empty());
+
+        let weth = Arc::new(Token::new_with_data(TokenAddressEth::WETH, Some("WETH".to_string()), None, Some(18), true, false));
+        let usdt = Arc::new(Token::new_with_data(TokenAddressEth::USDT, Some("USDT".to_string()), None, Some(6), true, false));
+        let mut steth = Token::new_with_data(TokenAddressEth::STETH, Some("stETH".to_string()), None, Some(18), false, false);
+        steth.set_rebasing();
+        let steth = Arc::new(steth);
+
+        let weth_usdt = MockPool { token0: TokenAddressEth::WETH, token1: TokenAddressEth::USDT, address: Address::repeat_byte(1) };
+        let usdt_steth = MockPool { token0: TokenAddressEth::USDT, token1: TokenAddressEth::STETH, address: Address::repeat_byte(2) };
+        let steth_weth = MockPool { token0: TokenAddressEth::WETH, token1: TokenAddressEth::STETH, address: Address::repeat_byte(3) };
+
+        let swap_line = SwapLine::<LoomDataTypesEthereum>::from(SwapPath::new(
+            vec![weth.clone(), usdt, steth, weth],
+            vec![weth_usdt, usdt_steth.clone(), steth_weth.clone()],
+        ));
+        assert!(swap_line.has_rebasing_token());
+        assert_eq!(
+            swap_line.rebasing_pools().iter().map(|pool| pool.get_address()).collect::<Vec<_>>(),
+            vec![usdt_steth.address, steth_weth.address]
+        );
+        assert!(!swap_line.can_flash_swap());
+
+        // only the hops of the rebasing token are excluded from flash swaps
+        let (head, tail) = swap_line.split(1).unwrap();
+        assert!(head.can_flash_swap());
+        assert!(!tail.can_flash_swap());
+        let (step_0, _) = swap_line.to_swap_steps(Address::repeat_byte(4)).unwrap();
+        assert_eq!(step_0.swap_line_vec()[0].get_first_pool().unwrap().get_address(), Address::repeat_byte(1));
+    }
+
+    #[test]
+    fn test_transfer_hook_funding() {
+        let weth = Arc::new(Token::new_with_data(TokenAddressEth::WETH, Some("WETH".to_string()), None, Some(18), true, false));
+        let mut hooked = Token::new(Address::repeat_byte(0x77));
+        hooked.set_transfer_hook();
+        let hooked = Arc::new(hooked);
+
+        let pool0 = MockPool { token0: TokenAddressEth::WETH, token1: hooked.get_address(), address: Address::repeat_byte(1) };
+        let pool1 = MockPool { token0: TokenAddressEth::WETH, token1: hooked.get_address(), address: Address::repeat_byte(2) };
+        let swap_line = SwapLine::<LoomDataTypesEthereum>::from(SwapPath::new(vec![weth.clone(), hooked, weth], vec![pool0, pool1]));
+
+        assert!(swap_line.has_transfer_hook_token());
+        assert!(!swap_line.can_flash_swap());
+        assert_eq!(swap_line.funding_modes(), vec![FundingMode::BalancerFlashLoan]);
+    }
+
+    #[test]
+    fn test_dust_hops() {
+        let weth = Arc::new(Token::new_with_data(TokenAddressEth::WETH, Some("WETH".to_string()), None, Some(18), true, false));
+        let usdc = Arc::new(Token::new_with_data(TokenAddressEth::USDC, Some("USDC".to_string()), None, Some(6), false, false));
+        let pool0 = MockPool::new(TokenAddressEth::WETH, TokenAddressEth::USDC, Address::repeat_byte(1));
+        let pool1 = MockPool::new(TokenAddressEth::WETH, TokenAddressEth::USDC, Address::repeat_byte(2));
+        let swap_line = SwapLine::<LoomDataTypesEthereum>::from(SwapPath::new(vec![weth.clone(), usdc.clone(), weth], vec![pool0, pool1]));
+
+        // 2000 USDC for 1 WETH in both pools
+        let mut state_db = LoomDBType::default();
+        for pool in [Address::repeat_byte(1), Address::repeat_byte(2)] {
+            state_db.insert_account_storage(pool, U256::ZERO, U256::from(1_000) * U256::from(10).pow(U256::from(18))).unwrap();
+            state_db.insert_account_storage(pool, U256::from(1), U256::from(2_000_000) * U256::from(10).pow(U256::from(6))).unwrap();
+        }
+        let env = Env::default();
+        let dust_in = U256::from(1_000_000_000_000u64);
+
+        // 0.002 USDC mid-route is above the decimals floor of USDC
+        assert!(swap_line.calculate_with_in_amount(&state_db, env.clone(), dust_in).is_ok());
+        assert!(swap_line.calculate_with_out_amount(&state_db, env.clone(), dust_in).is_ok());
+
+        // and below 0.01 USD
+        usdc.set_dust_amount(Some(U256::from(10_000)));
+        let err = swap_line.calculate_with_in_amount(&state_db, env.clone(), dust_in).unwrap_err();
+        assert_eq!((err.msg.as_str(), err.pool), ("DUST_OUT_AMOUNT", PoolId::Address(Address::repeat_byte(1))));
+        let err = swap_line.calculate_with_out_amount(&state_db, env.clone(), dust_in).unwrap_err();
+        assert_eq!((err.msg.as_str(), err.pool), ("DUST_IN_AMOUNT", PoolId::Address(Address::repeat_byte(2))));
+
+        // 0.02 USDC mid-route is not dust
+        let (out_amount, _, calculation_results) =
+            swap_line.calculate_with_in_amount(&state_db, env.clone(), dust_in * U256::from(10)).unwrap();
+        assert!(calculation_results[0].amount_out >= usdc.get_dust_amount());
+        assert!(out_amount > U256::ZERO);
+        assert!(swap_line.calculate_with_out_amount(&state_db, env, dust_in * U256::from(10)).is_ok());
+    }
+
+    #[test]
+    fn test_flash_swap_tail_fee() {
+        let v2 = |fee: u64| Some((PoolClass::UniswapV2, U256::from(fee)));
+        let v3 = |fee: u64| Some((PoolClass::UniswapV3, U256::from(fee)));
+
+        // v2 fees are the share kept, 9975 is a 0.25% fee and lower than the 0.3% of 9970
+        assert!(has_lower_fee(v2(9975), v2(9970)));
+        assert!(!has_lower_fee(v2(9970), v2(9975)));
+        assert!(!has_lower_fee(v2(9970), v2(9970)));
+
+        assert!(has_lower_fee(v3(500), v3(3000)));
+        assert!(!has_lower_fee(v3(10000), v3(3000)));
+
+        // 0.05% v3 pool against a 0.3% v2 pool, a 0.3% fee is the same in both units
+        assert!(has_lower_fee(v3(500), v2(9970)));
+        assert!(!has_lower_fee(v2(9970), v3(500)));
+        assert!(!has_lower_fee(v2(9970), v3(3000)));
+        assert!(has_lower_fee(v2(9975), v3(3000)));
+
+        // fees of classes without a known unit are never compared
+        assert!(!has_lower_fee(Some((PoolClass::Curve, U256::ZERO)), v3(3000)));
+        assert!(!has_lower_fee(v3(500), None));
+    }
 }
@@ -128,6 +128,24 @@ impl<LDT: LoomDataTypes> SwapPath<LDT> {
         false
     }
 
+    /// The cycle starting and ending with its first token matching `is_start`, `None` if the path is no cycle or no token
+    /// matches
+    pub fn rotated_cycle<F: Fn(&Token<LDT>) -> bool>(self, is_start: F) -> Option<Self> {
+        let hops = self.pools.len();
+        if hops == 0 || self.tokens.len() != hops + 1 || self.tokens.first() != self.tokens.last() {
+            return None;
+        }
+        let start = self.tokens[..hops].iter().position(|token| is_start(token))?;
+        if start == 0 {
+            return Some(self);
+        }
+        Some(SwapPath {
+            tokens: (0..=hops).map(|i| self.tokens[(start + i) % hops].clone()).collect(),
+            pools: (0..hops).map(|i| self.pools[(start + i) % hops].clone()).collect(),
+            ..self
+        })
+    }
+
     #[inline]
     pub fn get_hash(&self) -> u64 {
         let mut h = DefaultHasher::new();
@@ -409,6 +427,23 @@ mod test {
         println!("{paths:?}")
     }
 
+    #[test]
+    fn test_rotated_cycle() {
+        let tokens: Vec<Token> = (1..=3).map(|i| Token::new(Address::repeat_byte(i))).collect();
+        let pools: Vec<PoolWrapper> = (1..=3).map(|i| PoolWrapper::new(Arc::new(EmptyPool::new(Address::repeat_byte(0x10 + i))))).collect();
+        let cycle = SwapPath::new(vec![tokens[0].clone(), tokens[1].clone(), tokens[2].clone(), tokens[0].clone()], pools.clone());
+
+        let rotated = cycle.clone().rotated_cycle(|token| token.get_address() == Address::repeat_byte(3)).unwrap();
+        assert_eq!(rotated.tokens.iter().map(|token| token.get_address()).collect::<Vec<_>>(), [3, 1, 2, 3].map(Address::repeat_byte));
+        assert_eq!(rotated.pools, vec![pools[2].clone(), pools[0].clone(), pools[1].clone()]);
+
+        assert_eq!(cycle.clone().rotated_cycle(|token| token.get_address() == Address::repeat_byte(1)), Some(cycle.clone()));
+        assert_eq!(cycle.rotated_cycle(|token| token.get_address() == Address::repeat_byte(4)), None);
+
+        let open_path = SwapPath::new(vec![tokens[0].clone(), tokens[1].clone()], vec![pools[0].clone()]);
+        assert_eq!(open_path.rotated_cycle(|_| true), None);
+    }
+
     #[tokio::test]
     async fn async_test() {
         let basic_token = Token::new(Address::repeat_byte(0x11));
@@ -2,7 +2,7 @@
 use std::collections::{BTreeMap, HashSet};
 use std::sync::Arc;
 
-use crate::{Market, PoolWrapper, SwapDirection, SwapPath};
+use crate::{Market, PathGasBudget, PoolId, PoolWrapper, SwapDirection, SwapPath, UNBUDGETED_PATH_HOPS};
 use eyre::Result;
 use loom_types_blockchain::LoomDataTypes;
 
@@ -432,6 +432,154 @@ fn build_swap_path_three_hopes_no_basic<LDT: LoomDataTypes>(
     Ok(ret)
 }
 
+// Swap path of the cycle starting with its first basic token
+fn rotated_cycle_path<LDT: LoomDataTypes>(
+    market: &Market<LDT>,
+    tokens: &[LDT::Address],
+    pools: &[PoolId<LDT>],
+) -> Result<Option<SwapPath<LDT>>> {
+    let Some(start) = tokens[..pools.len()].iter().position(|token| market.is_basic_token(token)) else {
+        return Ok(None);
+    };
+    let mut swap_path: Option<SwapPath<LDT>> = None;
+    for i in 0..pools.len() {
+        let idx = (start + i) % pools.len();
+        let Some(pool) = market.get_pool(&pools[idx]) else { return Ok(None) };
+        let token_from = market.get_token_or_default(&tokens[idx]);
+        let token_to = market.get_token_or_default(&tokens[idx + 1]);
+        match swap_path.as_mut() {
+            Some(swap_path) => swap_path.push_swap_hope(token_from, token_to, pool.clone())?,
+            None => swap_path = Some(SwapPath::new_swap(token_from, token_to, pool.clone())),
+        }
+    }
+    Ok(swap_path)
+}
+
+// Pools the depth first search of the cycles through a pool direction extends open cycles with, dense tokens of large markets
+// are not walked exhaustively
+const MAX_CYCLE_SEARCH_STEPS: usize = 100_000;
+
+// Depth first extension of the open cycle `tokens` through `pools` until it returns to its first token
+fn extend_cycle_within_budget<LDT: LoomDataTypes>(
+    market: &Market<LDT>,
+    budget: &PathGasBudget,
+    tokens: &mut Vec<LDT::Address>,
+    pools: &mut Vec<PoolId<LDT>>,
+    gas: u64,
+    steps: &mut usize,
+    ret: &mut Vec<SwapPath<LDT>>,
+) -> Result<()> {
+    let token_last = tokens[tokens.len() - 1];
+    let path_build = market.pools_config().path_build();
+    // the last hop can only close the cycle, it is looked up directly instead of walking all neighbours of dense tokens
+    let token_next_set: HashSet<LDT::Address> = if pools.len() + 1 >= budget.max_hops() {
+        HashSet::from([tokens[0]])
+    } else {
+        let Some(token_tokens) = market.get_token_tokens(&token_last) else {
+            return Ok(());
+        };
+        token_tokens.iter().copied().collect()
+    };
+
+    for token_next in token_next_set {
+        let closes_cycle = token_next == tokens[0];
+        if !closes_cycle && path_build.unique_tokens() && tokens.contains(&token_next) {
+            continue;
+        }
+        // an open cycle needs at least one more hop back to its first token
+        let min_hops = if closes_cycle { pools.len() + 1 } else { pools.len() + 2 };
+        if min_hops > budget.max_hops() {
+            continue;
+        }
+        // the open cycle holds the anchor and its middle tokens, the next token would be one more middle token
+        if !closes_cycle && path_build.max_middle_tokens().is_some_and(|max_middle_tokens| tokens.len() > max_middle_tokens) {
+            continue;
+        }
+        let Some(token_token_pools) = market.get_token_token_pools(&token_last, &token_next) else { continue };
+
+        for pool_id in token_token_pools.iter() {
+            if (path_build.unique_pools() && pools.contains(pool_id)) || market.is_pool_disabled(pool_id) {
+                continue;
+            }
+            let Some(pool) = market.get_pool(pool_id) else { continue };
+            let next_gas = gas + market.pool_gas_costs().get(pool.get_class());
+            if !budget.is_within(min_hops, next_gas) {
+                continue;
+            }
+            if *steps >= MAX_CYCLE_SEARCH_STEPS {
+                return Ok(());
+            }
+            *steps += 1;
+
+            tokens.push(token_next);
+            pools.push(*pool_id);
+            if closes_cycle {
+                if let Some(swap_path) = rotated_cycle_path(market, tokens, pools)? {
+                    ret.push(swap_path);
+                }
+            } else {
+                extend_cycle_within_budget(market, budget, tokens, pools, next_gas, steps, ret)?;
+            }
+            tokens.pop();
+            pools.pop();
+        }
+    }
+    Ok(())
+}
+
+// Cycles through the pool with up to `budget.max_hops()` hops, starting with a basic token
+fn build_swap_path_gas_budget<LDT: LoomDataTypes>(
+    market: &Market<LDT>,
+    pool: &PoolWrapper<LDT>,
+    token_from_address: LDT::Address,
+    token_to_address: LDT::Address,
+    budget: &PathGasBudget,
+) -> Result<Vec<SwapPath<LDT>>> {
+    let mut ret: Vec<SwapPath<LDT>> = Vec::new();
+    let mut tokens = vec![token_from_address, token_to_address];
+    let mut pools = vec![pool.get_pool_id()];
+    let gas = market.pool_gas_costs().get(pool.get_class());
+    extend_cycle_within_budget(market, budget, &mut tokens, &mut pools, gas, &mut 0, &mut ret)?;
+    Ok(ret)
+}
+
+/// Build cycles up to three hops through the pool only. The cycles are explored from the pool over the token graph
+/// indices of the market, so adding a pool to a large market does not rebuild paths of its tokens from scratch
+pub fn build_pool_swap_path_vec<LDT: LoomDataTypes>(market: &Market<LDT>, pool: &PoolWrapper<LDT>) -> Result<Vec<SwapPath<LDT>>> {
+    if market.is_pool_disabled(&pool.get_pool_id()) {
+        return Ok(vec![]);
+    }
+    let budget = market.pools_config().path_gas_budget().copied().unwrap_or(PathGasBudget::new(UNBUDGETED_PATH_HOPS, u64::MAX));
+
+    let mut ret_map = SwapPathSet::new();
+    for direction in pool.get_swap_directions().iter() {
+        ret_map.extend(build_swap_path_gas_budget(market, pool, *direction.from(), *direction.to(), &budget)?);
+    }
+
+    Ok(ret_map.vec().into_iter().filter(|swap_path| market.is_path_allowed(swap_path)).collect())
+}
+
+/// Build cycles through the pools of `directions` up to [`crate::MAX_PATH_HOPS`] hops.
+/// Cycles longer than three hops are kept only if the historical gas of their pool classes fits the budget.
+pub fn build_swap_path_vec_gas_budget<LDT: LoomDataTypes>(
+    market: &Market<LDT>,
+    directions: &BTreeMap<PoolWrapper<LDT>, Vec<SwapDirection<LDT>>>,
+    budget: &PathGasBudget,
+) -> Result<Vec<SwapPath<LDT>>> {
+    let mut ret_map = SwapPathSet::new();
+
+    for (pool, directions) in directions.iter() {
+        if market.is_pool_disabled(&pool.get_pool_id()) {
+            continue;
+        }
+        for direction in directions.iter() {
+            ret_map.extend(build_swap_path_gas_budget(market, pool, *direction.from(), *direction.to(), budget)?);
+        }
+    }
+
+    Ok(ret_map.vec().into_iter().filter(|swap_path| market.is_path_allowed(swap_path)).collect())
+}
+
 pub fn build_swap_path_vec<LDT: LoomDataTypes>(
     market: &Market<LDT>,
     directions: &BTreeMap<PoolWrapper<LDT>, Vec<SwapDirection<LDT>>>,
@@ -439,6 +587,9 @@ pub fn build_swap_path_vec<LDT: LoomDataTypes>(
     let mut ret_map = SwapPathSet::new();
 
     for (pool, directions) in directions.iter() {
+        if market.is_pool_disabled(&pool.get_pool_id()) {
+            continue;
+        }
         for direction in directions.iter() {
             let token_from_address = *direction.from();
             let token_to_address = *direction.to();
@@ -468,5 +619,5 @@ pub fn build_swap_path_vec<LDT: LoomDataTypes>(
         }
     }
 
-    Ok(ret_map.vec())
+    Ok(ret_map.vec().into_iter().filter(|swap_path| market.is_path_allowed(swap_path)).collect())
 }
@@ -5,9 +5,9 @@ use alloy_primitives::{I256, U256};
 use eyre::{eyre, ErrReport, Result};
 use revm::primitives::Env;
 use revm::DatabaseRef;
-use tracing::error;
+use tracing::{debug, error};
 
-use crate::{PoolWrapper, SwapAmountType, SwapLine, Token};
+use crate::{FundingMode, PoolWrapper, SwapAmountType, SwapLine, Token};
 use loom_evm_db::LoomDBType;
 use loom_types_blockchain::LoomDataTypes;
 
@@ -85,7 +85,7 @@ impl<LDT: LoomDataTypes> SwapStep<LDT> {
     pub fn can_flash_swap(&self) -> bool {
         for swap_line in self.swap_line_vec.iter() {
             for pool in swap_line.pools().iter() {
-                if !pool.can_flash_swap() {
+                if !pool.supports_flash_swap() {
                     return false;
                 }
             }
@@ -96,7 +96,7 @@ impl<LDT: LoomDataTypes> SwapStep<LDT> {
     pub fn can_calculate_in_amount(&self) -> bool {
         for swap_line in self.swap_line_vec.iter() {
             for pool in swap_line.pools().iter() {
-                if !pool.can_calculate_in_amount() {
+                if !pool.supports_exact_out() {
                     return false;
                 }
             }
@@ -218,7 +218,7 @@ impl<LDT: LoomDataTypes> SwapStep<LDT> {
             swap_step_1.add(split_0_1);
             swap_step_1.add(split_1_1);
 
-            return Ok((swap_step_0, swap_step_1));
+            return Ok(Self::simplify_swap_steps(swap_step_0, swap_step_1));
         }
 
         if split_index_end > 0 {
@@ -265,12 +265,74 @@ impl<LDT: LoomDataTypes> SwapStep<LDT> {
 
             swap_step_1.add(split_0_1);
 
-            return Ok((swap_step_0, swap_step_1));
+            return Ok(Self::simplify_swap_steps(swap_step_0, swap_step_1));
         }
 
         Err(eyre!("CANNOT_MERGE"))
     }
 
+    /// Cancel hops of the swap steps swapping through the same pool back and forth. Round trips inside a swap line are
+    /// removed, and while every swap line of the first step ends with the hop that every swap line of the second step
+    /// reverses, the hop is removed from both steps. Amounts between the steps are reset to be calculated again
+    pub fn simplify_swap_steps(swap_step_0: SwapStep<LDT>, swap_step_1: SwapStep<LDT>) -> (SwapStep<LDT>, SwapStep<LDT>) {
+        let (mut swap_step_0, mut swap_step_1) = (swap_step_0, swap_step_1);
+
+        let mut removed: usize = swap_step_0
+            .swap_line_vec
+            .iter_mut()
+            .chain(swap_step_1.swap_line_vec.iter_mut())
+            .map(|swap_line| swap_line.cancel_round_trips())
+            .sum();
+
+        while Self::crossing_hop(&swap_step_0, &swap_step_1) {
+            for swap_line in swap_step_0.swap_line_vec.iter_mut() {
+                swap_line.path.pools.pop();
+                swap_line.path.tokens.pop();
+                swap_line.amount_out = SwapAmountType::NotSet;
+                swap_line.calculation_results.clear();
+            }
+            for swap_line in swap_step_1.swap_line_vec.iter_mut() {
+                swap_line.path.pools.remove(0);
+                swap_line.path.tokens.remove(0);
+                if let SwapAmountType::Set(_) = swap_line.amount_in {
+                    swap_line.amount_in = SwapAmountType::NotSet;
+                }
+                swap_line.calculation_results.clear();
+            }
+            removed += swap_step_0.len() + swap_step_1.len();
+        }
+
+        if removed > 0 {
+            debug!(removed, "Swap steps simplified : {} + {}", swap_step_0, swap_step_1);
+        }
+        (swap_step_0, swap_step_1)
+    }
+
+    /// Check if every swap line of the first step ends with the same hop that every swap line of the second step starts
+    /// with in the opposite direction, keeping at least one hop in every swap line
+    fn crossing_hop(swap_step_0: &SwapStep<LDT>, swap_step_1: &SwapStep<LDT>) -> bool {
+        let Some(first_line) = swap_step_0.swap_line_vec.first() else {
+            return false;
+        };
+        let (Some(pool), Some(token_from)) = (first_line.pools().last(), first_line.tokens().iter().rev().nth(1)) else {
+            return false;
+        };
+
+        !swap_step_1.is_empty()
+            && swap_step_0.swap_line_vec.iter().all(|swap_line| {
+                swap_line.pools().len() > 1
+                    && swap_line.tokens().len() == swap_line.pools().len() + 1
+                    && swap_line.pools().last() == Some(pool)
+                    && swap_line.tokens().iter().rev().nth(1) == Some(token_from)
+            })
+            && swap_step_1.swap_line_vec.iter().all(|swap_line| {
+                swap_line.pools().len() > 1
+                    && swap_line.tokens().len() == swap_line.pools().len() + 1
+                    && swap_line.pools().first() == Some(pool)
+                    && swap_line.tokens().get(1) == Some(token_from)
+            })
+    }
+
     pub fn get_first_token_address(&self) -> Option<LDT::Address> {
         let mut ret: Option<LDT::Address> = None;
         for sp in self.swap_line_vec.iter() {
@@ -439,24 +501,32 @@ impl<LDT: LoomDataTypes> SwapStep<LDT> {
 
     }*/
 
+    /// Funding mode used by the multicaller encoder: a flash swap if one of the steps can be flash swapped,
+    /// a Balancer flash loan otherwise
+    pub fn funding_mode(swap_step_0: &SwapStep<LDT>, swap_step_1: &SwapStep<LDT>) -> FundingMode {
+        if swap_step_0.can_flash_swap() || swap_step_1.can_flash_swap() {
+            FundingMode::FlashSwap
+        } else {
+            FundingMode::BalancerFlashLoan
+        }
+    }
+
     pub fn profit(swap_step_0: &SwapStep<LDT>, swap_step_1: &SwapStep<LDT>) -> I256 {
         let in_amount: I256 = I256::try_from(swap_step_0.get_in_amount().unwrap_or(U256::MAX)).unwrap_or(I256::MAX);
         let out_amount: I256 = I256::try_from(swap_step_1.get_out_amount().unwrap_or(U256::ZERO)).unwrap_or(I256::ZERO);
         if in_amount.is_negative() {
             I256::MIN
         } else {
-            out_amount - in_amount
+            let funding_fee = Self::funding_mode(swap_step_0, swap_step_1).fee(in_amount.into_raw());
+            out_amount - in_amount - I256::from_raw(funding_fee)
         }
     }
 
     pub fn abs_profit(swap_step_0: &SwapStep<LDT>, swap_step_1: &SwapStep<LDT>) -> U256 {
         let in_amount: U256 = swap_step_0.get_in_amount().unwrap_or(U256::MAX);
         let out_amount: U256 = swap_step_1.get_out_amount().unwrap_or(U256::ZERO);
-        if in_amount >= out_amount {
-            U256::ZERO
-        } else {
-            out_amount - in_amount
-        }
+        let funding_fee = Self::funding_mode(swap_step_0, swap_step_1).fee(in_amount);
+        out_amount.saturating_sub(in_amount.saturating_add(funding_fee))
     }
 
     pub fn abs_profit_eth(swap_step_0: &SwapStep<LDT>, swap_step_1: &SwapStep<LDT>) -> U256 {
@@ -826,3 +896,51 @@ impl<LDT: LoomDataTypes> SwapStep<LDT> {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mock_pool::MockPool;
+    use crate::SwapPath;
+    use alloy_primitives::Address;
+    use loom_types_blockchain::LoomDataTypesEthereum;
+
+    #[test]
+    fn test_simplify_swap_steps() {
+        let (weth, usdc, dai) = (Address::repeat_byte(1), Address::repeat_byte(2), Address::repeat_byte(3));
+        let (weth_usdc, usdc_dai, weth_usdc_1, weth_dai) =
+            (Address::repeat_byte(0x10), Address::repeat_byte(0x11), Address::repeat_byte(0x12), Address::repeat_byte(0x13));
+        let tokens = |addresses: &[Address]| addresses.iter().map(|address| Token::new(*address)).collect::<Vec<_>>();
+
+        // WETH -> USDC -> DAI on both lines of the first step, DAI -> USDC -> WETH on the second step
+        let line_0_0 = SwapLine::<LoomDataTypesEthereum>::from(SwapPath::new(
+            tokens(&[weth, usdc, dai]),
+            vec![MockPool::new(weth, usdc, weth_usdc), MockPool::new(usdc, dai, usdc_dai)],
+        ));
+        let line_0_1 = SwapLine::from(SwapPath::new(
+            tokens(&[weth, dai, weth, usdc, dai]),
+            vec![
+                MockPool::new(weth, dai, weth_dai),
+                MockPool::new(weth, dai, weth_dai),
+                MockPool::new(weth, usdc, weth_usdc_1),
+                MockPool::new(usdc, dai, usdc_dai),
+            ],
+        ));
+        let line_1 = SwapLine::from(SwapPath::new(
+            tokens(&[dai, usdc, weth]),
+            vec![MockPool::new(usdc, dai, usdc_dai), MockPool::new(weth, usdc, weth_usdc)],
+        ));
+
+        let mut swap_step_0 = SwapStep::new(Address::ZERO);
+        swap_step_0.add(line_0_0).add(line_0_1);
+        let mut swap_step_1 = SwapStep::new(Address::ZERO);
+        swap_step_1.add(line_1);
+
+        let (swap_step_0, swap_step_1) = SwapStep::simplify_swap_steps(swap_step_0, swap_step_1);
+        assert_eq!(swap_step_0.swap_line_vec()[0].tokens().len(), 2);
+        // the round trip through the WETH/DAI pool is removed before the crossing USDC/DAI hop
+        assert_eq!(swap_step_0.swap_line_vec()[1].pools(), &vec![PoolWrapper::from(MockPool::new(weth, usdc, weth_usdc_1))]);
+        assert_eq!(swap_step_1.swap_line_vec()[0].get_first_token().unwrap().get_address(), usdc);
+        assert_eq!(swap_step_1.swap_line_vec()[0].pools().len(), 1);
+    }
+}
@@ -0,0 +1,289 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use alloy_primitives::{Address, PrimitiveSignature, U256};
+use alloy_sol_types::{sol, Eip712Domain, SolStruct};
+use eyre::{eyre, Result};
+use serde::{Deserialize, Serialize};
+
+sol! {
+    /// Tip recipients of a chain signed by its admin, the zero address pays the block coinbase
+    #[derive(Debug, PartialEq, Eq)]
+    struct TipRecipientsUpdate {
+        uint64 nonce;
+        uint64 deadline;
+        address defaultRecipient;
+        string[] builders;
+        address[] recipients;
+    }
+}
+
+/// Receiver of the tips of the encoded swaps
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum TipRecipient {
+    /// Paid to the coinbase of the block by the multicaller, the rest of the profit is paid out to the sender
+    #[default]
+    Coinbase,
+    /// Paid to the payment address of the builder, the rest of the profit stays in the multicaller
+    Address(Address),
+}
+
+impl From<Address> for TipRecipient {
+    fn from(address: Address) -> Self {
+        match address.is_zero() {
+            true => Self::Coinbase,
+            false => Self::Address(address),
+        }
+    }
+}
+
+impl From<TipRecipient> for Address {
+    fn from(recipient: TipRecipient) -> Self {
+        match recipient {
+            TipRecipient::Coinbase => Address::ZERO,
+            TipRecipient::Address(address) => address,
+        }
+    }
+}
+
+impl Display for TipRecipient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Coinbase => write!(f, "coinbase"),
+            Self::Address(address) => write!(f, "{address}"),
+        }
+    }
+}
+
+impl FromStr for TipRecipient {
+    type Err = eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        if s.eq_ignore_ascii_case("coinbase") {
+            return Ok(Self::Coinbase);
+        }
+        Ok(Address::from_str(s).map_err(|_| eyre!("INVALID_TIP_RECIPIENT"))?.into())
+    }
+}
+
+impl TryFrom<String> for TipRecipient {
+    type Error = eyre::Report;
+
+    fn try_from(value: String) -> Result<Self> {
+        value.parse()
+    }
+}
+
+impl From<TipRecipient> for String {
+    fn from(recipient: TipRecipient) -> Self {
+        recipient.to_string()
+    }
+}
+
+/// Tip recipients shared with the swap encoders, read on every encoded swap
+pub type SharedTipRecipients = Arc<RwLock<TipRecipients>>;
+
+/// Updates with a deadline further away are rejected, so a leaked update cannot be applied long after it was signed
+pub const MAX_TIP_RECIPIENTS_UPDATE_VALIDITY_SECS: u64 = 3600;
+
+/// Tip recipients of a chain by builder. Updates are signed by the admin with EIP-712 in the domain of the chain and the
+/// multicaller, replayed updates are rejected by their nonce, kept in the nonce file across restarts, and by their deadline
+#[derive(Clone, Debug, Default)]
+pub struct TipRecipients {
+    chain_id: u64,
+    /// Multicaller the updates are signed for
+    verifying_contract: Option<Address>,
+    /// Signer of the updates, updates are rejected if not set
+    admin: Option<Address>,
+    default: TipRecipient,
+    builders: BTreeMap<String, TipRecipient>,
+    nonce: u64,
+    nonce_file: Option<PathBuf>,
+}
+
+impl TipRecipients {
+    pub fn new(chain_id: u64) -> Self {
+        Self { chain_id, ..Self::default() }
+    }
+
+    pub fn with_verifying_contract(self, verifying_contract: Option<Address>) -> Self {
+        Self { verifying_contract, ..self }
+    }
+
+    pub fn with_admin(self, admin: Option<Address>) -> Self {
+        Self { admin, ..self }
+    }
+
+    /// Keep the nonce of the last update in the file, starting from the nonce saved in it if the file exists
+    pub fn with_nonce_file(self, nonce_file: PathBuf) -> Result<Self> {
+        let nonce = match std::fs::read_to_string(&nonce_file) {
+            Ok(contents) => contents.trim().parse().map_err(|_| eyre!("INVALID_NONCE_FILE"))?,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => 0,
+            Err(error) => return Err(error.into()),
+        };
+        Ok(Self { nonce, nonce_file: Some(nonce_file), ..self })
+    }
+
+    pub fn with_default(self, default: TipRecipient) -> Self {
+        Self { default, ..self }
+    }
+
+    pub fn with_builder(mut self, builder: String, recipient: TipRecipient) -> Self {
+        self.builders.insert(builder, recipient);
+        self
+    }
+
+    pub fn chain_id(&self) -> u64 {
+        self.chain_id
+    }
+
+    pub fn verifying_contract(&self) -> Option<Address> {
+        self.verifying_contract
+    }
+
+    pub fn admin(&self) -> Option<Address> {
+        self.admin
+    }
+
+    pub fn default_recipient(&self) -> TipRecipient {
+        self.default
+    }
+
+    pub fn builders(&self) -> &BTreeMap<String, TipRecipient> {
+        &self.builders
+    }
+
+    /// Nonce of the last applied update
+    pub fn nonce(&self) -> u64 {
+        self.nonce
+    }
+
+    /// Recipient of the tips of swaps sent to the builder, the default one for unknown builders
+    pub fn recipient(&self, builder: Option<&str>) -> TipRecipient {
+        builder.and_then(|builder| self.builders.get(builder)).copied().unwrap_or(self.default)
+    }
+
+    pub fn domain(&self) -> Eip712Domain {
+        Eip712Domain::new(
+            Some(Cow::Borrowed("Loom Tip Recipients")),
+            Some(Cow::Borrowed("1")),
+            Some(U256::from(self.chain_id)),
+            self.verifying_contract,
+            None,
+        )
+    }
+
+    /// Replace the recipients by the update signed by the admin, the nonce must be above the one of the last update and the
+    /// deadline not passed. The nonce is saved to the nonce file before the recipients are replaced
+    pub fn apply_update(&mut self, update: TipRecipientsUpdate, signature: &PrimitiveSignature) -> Result<()> {
+        let admin = self.admin.ok_or_else(|| eyre!("TIP_RECIPIENTS_ADMIN_NOT_SET"))?;
+        if update.builders.len() != update.recipients.len() {
+            return Err(eyre!("BUILDERS_AND_RECIPIENTS_LENGTH_MISMATCH"));
+        }
+        if update.nonce <= self.nonce {
+            return Err(eyre!("NONCE_TOO_LOW"));
+        }
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        if update.deadline < now {
+            return Err(eyre!("UPDATE_EXPIRED"));
+        }
+        if update.deadline > now + MAX_TIP_RECIPIENTS_UPDATE_VALIDITY_SECS {
+            return Err(eyre!("DEADLINE_TOO_FAR"));
+        }
+        let signer = signature.recover_address_from_prehash(&update.eip712_signing_hash(&self.domain()))?;
+        if signer != admin {
+            return Err(eyre!("INVALID_SIGNATURE"));
+        }
+
+        if let Some(nonce_file) = &self.nonce_file {
+            let tmp_file = nonce_file.with_extension("tmp");
+            std::fs::write(&tmp_file, update.nonce.to_string())?;
+            std::fs::rename(&tmp_file, nonce_file)?;
+        }
+        self.default = update.defaultRecipient.into();
+        self.builders = update.builders.into_iter().zip(update.recipients.into_iter().map(TipRecipient::from)).collect();
+        self.nonce = update.nonce;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{sign_typed_data, TxSigners};
+    use alloy_primitives::Bytes;
+
+    #[test]
+    fn test_tip_recipients_update() -> Result<()> {
+        let mut signers = TxSigners::new();
+        let admin = signers.add_testkey();
+        let other = signers.add_privkey(Bytes::copy_from_slice(&[0x11; 32]));
+        let payment = Address::repeat_byte(0x42);
+
+        let multicaller = Address::repeat_byte(0x10);
+        let nonce_file = std::env::temp_dir().join(format!("loom_tip_recipients_nonce_{}", std::process::id()));
+        let _ = std::fs::remove_file(&nonce_file);
+
+        let mut tip_recipients = TipRecipients::new(1)
+            .with_verifying_contract(Some(multicaller))
+            .with_admin(Some(admin.address()))
+            .with_nonce_file(nonce_file.clone())?;
+        assert_eq!(tip_recipients.recipient(Some("titan")), TipRecipient::Coinbase);
+
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let update = TipRecipientsUpdate {
+            nonce: 1,
+            deadline: now + 60,
+            defaultRecipient: Address::ZERO,
+            builders: vec!["titan".to_string()],
+            recipients: vec![payment],
+        };
+        let signature = sign_typed_data(&other, &tip_recipients.domain(), &update)?;
+        assert!(tip_recipients.apply_update(update.clone(), &signature).is_err());
+
+        let signature = sign_typed_data(&admin, &tip_recipients.domain(), &update)?;
+        tip_recipients.apply_update(update.clone(), &signature)?;
+        assert_eq!(tip_recipients.recipient(Some("titan")), TipRecipient::Address(payment));
+        assert_eq!(tip_recipients.recipient(Some("beaver")), TipRecipient::Coinbase);
+        assert_eq!(tip_recipients.recipient(None), TipRecipient::Coinbase);
+        // replayed
+        assert!(tip_recipients.apply_update(update.clone(), &signature).is_err());
+        // replayed after a restart
+        let mut restarted = TipRecipients::new(1)
+            .with_verifying_contract(Some(multicaller))
+            .with_admin(Some(admin.address()))
+            .with_nonce_file(nonce_file.clone())?;
+        assert_eq!(restarted.nonce(), 1);
+        assert!(restarted.apply_update(update.clone(), &signature).is_err());
+        std::fs::remove_file(&nonce_file)?;
+
+        // signed for another chain
+        let update = TipRecipientsUpdate { nonce: 2, ..update };
+        let signature = sign_typed_data(&admin, &TipRecipients::new(10).with_verifying_contract(Some(multicaller)).domain(), &update)?;
+        assert!(tip_recipients.apply_update(update.clone(), &signature).is_err());
+
+        // signed for another multicaller
+        let signature = sign_typed_data(&admin, &TipRecipients::new(1).domain(), &update)?;
+        assert!(tip_recipients.apply_update(update.clone(), &signature).is_err());
+
+        // expired or valid for too long
+        for deadline in [now - 1, now + MAX_TIP_RECIPIENTS_UPDATE_VALIDITY_SECS + 60] {
+            let update = TipRecipientsUpdate { deadline, ..update.clone() };
+            let signature = sign_typed_data(&admin, &tip_recipients.domain(), &update)?;
+            assert!(tip_recipients.apply_update(update, &signature).is_err());
+        }
+        let signature = sign_typed_data(&admin, &tip_recipients.domain(), &update)?;
+        tip_recipients.apply_update(update, &signature)?;
+        assert_eq!(tip_recipients.nonce(), 2);
+
+        assert_eq!("coinbase".parse::<TipRecipient>()?, TipRecipient::Coinbase);
+        assert_eq!(payment.to_string().parse::<TipRecipient>()?, TipRecipient::Address(payment));
+        Ok(())
+    }
+}
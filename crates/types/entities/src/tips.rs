@@ -7,7 +7,7 @@ use alloy_primitives::utils::format_units;
 use alloy_primitives::{Address, U256};
 use eyre::{eyre, OptionExt, Result};
 use lazy_static::lazy_static;
-use loom_evm_utils::NWETH;
+use loom_defi_address_book::NWETH;
 use rand::random;
 use tracing::{error, info};
 
@@ -67,6 +67,11 @@ pub fn tips_and_value_for_swap_type(
     gas_cost: Option<U256>,
     eth_balance: U256,
 ) -> Result<(Vec<Tips>, U256)> {
+    // exchange swaps are executed on behalf of the order owner, the surplus is not shared as tips
+    if let Swap::ExchangeSwapLine(_) = swap {
+        return Ok((vec![], U256::ZERO));
+    }
+
     let total_profit_eth = swap.abs_profit_eth();
     info!("Total profit eth : {}", format_units(total_profit_eth, "ether").unwrap_or_default());
     let tips_pct = randomize_tips_pct(tips_pct.unwrap_or(tips_pct_advanced(&total_profit_eth)));
@@ -120,21 +125,18 @@ pub fn tips_and_value_for_swap_type(
             let mut tips_hashset: HashMap<Address, Tips> = HashMap::new();
 
             let profit_eth = swap.abs_profit_eth();
+            let gas_cost = gas_cost.unwrap_or_default();
 
-            if let Some(gas_cost) = gas_cost {
-                if profit_eth < gas_cost {
-                    error!(
-                        profit_eth = NWETH::to_float(profit_eth),
-                        gas_cost = NWETH::to_float(gas_cost),
-                        %swap,
-                        "Profit doesn't exceed the gas cost"
-                    );
-                    return Err(eyre!("NO_PROFIT_EXCEEDING_GAS"));
-                }
+            if profit_eth < gas_cost {
+                error!(
+                    profit_eth = NWETH::to_float(profit_eth),
+                    gas_cost = NWETH::to_float(gas_cost),
+                    %swap,
+                    "Profit doesn't exceed the gas cost"
+                );
+                return Err(eyre!("NO_PROFIT_EXCEEDING_GAS"));
             }
 
-            let gas_cost_per_record = gas_cost.unwrap_or_default() / U256::from(swap_vec.len());
-
             for swap_record in swap_vec.iter() {
                 let token_in = swap_record.get_first_token().ok_or_eyre("NO_FIRST_TOKEN")?.clone();
 
@@ -146,10 +148,6 @@ pub fn tips_and_value_for_swap_type(
 
                 let profit_eth = token_in.calc_eth_value(profit).ok_or_eyre("CALC_ETH_VALUE_FAILED")?;
 
-                let tips = profit_eth.checked_sub(gas_cost_per_record).ok_or_eyre("SUBTRACTION_OVERFLOWN")? * U256::from(tips_pct)
-                    / U256::from(10000);
-                let min_change = token_in.calc_token_value_from_eth(tips + gas_cost_per_record).unwrap();
-
                 let entry = tips_hashset.entry(token_in.get_address()).or_insert(Tips {
                     token_in,
                     profit: U256::ZERO,
@@ -160,41 +158,38 @@ pub fn tips_and_value_for_swap_type(
 
                 entry.profit += profit;
                 entry.profit_eth += profit_eth;
-                entry.tips += tips;
-                entry.min_change += min_change;
             }
 
-            let mut value = U256::ZERO;
-
-            if tips_hashset.iter().any(|(_, x)| x.token_in.is_weth()) {
-                let total_tips_eth: U256 = tips_hashset.iter().filter(|(_, x)| x.token_in.is_weth()).map(|(_, x)| x.tips).sum();
-                let total_tips_non_eth: U256 = tips_hashset.iter().filter(|(_, x)| !x.token_in.is_weth()).map(|(_, x)| x.tips).sum();
-                let total_profit_eth: U256 = tips_hashset.iter().filter(|(_, x)| x.token_in.is_weth()).map(|(_, x)| x.profit_eth).sum();
-                for (_, token_tips) in tips_hashset.iter_mut() {
-                    if token_tips.token_in.is_weth() {
-                        token_tips.tips = total_tips_eth + total_tips_non_eth;
-                        if total_tips_eth + total_tips_non_eth > total_profit_eth {
-                            value = total_tips_eth + total_tips_non_eth - total_profit_eth;
-                        }
-
-                        if value > eth_balance {
-                            token_tips.tips = token_tips.tips.checked_sub(value).ok_or_eyre("SUBTRACTION_OVERFLOWN")?;
-                            value = eth_balance * U256::from(9000) / U256::from(10000);
-                            token_tips.tips += value;
-                        }
-                    } else {
-                        token_tips.tips = U256::ZERO;
+            // tips are computed once from the total profit, every input token keeps its profit share of the gas cost
+            // and the tips, its balance change is checked once
+            let total_tips = (profit_eth - gas_cost) * U256::from(tips_pct) / U256::from(10000);
+            for token_tips in tips_hashset.values_mut() {
+                let share_eth = (gas_cost + total_tips) * token_tips.profit_eth / profit_eth.max(U256::from(1));
+                token_tips.min_change = token_tips.token_in.calc_token_value_from_eth(share_eth).ok_or_eyre("CALC_TOKEN_VALUE_FAILED")?;
+            }
+
+            // the tips are paid by a single transfer, from the WETH profit when there is one, by the value otherwise
+            let mut tips_vec: Vec<Tips> = tips_hashset.into_values().collect();
+            tips_vec.sort_by_key(|token_tips| (!token_tips.token_in.is_weth(), token_tips.token_in.get_address()));
+
+            let value = match tips_vec.first_mut() {
+                Some(token_tips) if token_tips.token_in.is_weth() => {
+                    let mut value = total_tips.saturating_sub(token_tips.profit_eth);
+                    token_tips.tips = total_tips;
+                    if value > eth_balance {
+                        token_tips.tips = token_tips.tips.checked_sub(value).ok_or_eyre("SUBTRACTION_OVERFLOWN")?;
+                        value = eth_balance * U256::from(9000) / U256::from(10000);
+                        token_tips.tips += value;
                     }
+                    value
                 }
-            } else {
-                let total_tips = tips_hashset.values().map(|x| x.tips).sum::<U256>();
-                value = if total_tips >= eth_balance { eth_balance * U256::from(9000) / U256::from(10000) } else { total_tips };
-
-                for (idx, (_, token_tips)) in tips_hashset.iter_mut().enumerate() {
-                    token_tips.tips = if idx == 0 { value } else { U256::ZERO };
+                Some(token_tips) => {
+                    let value = if total_tips >= eth_balance { eth_balance * U256::from(9000) / U256::from(10000) } else { total_tips };
+                    token_tips.tips = value;
+                    value
                 }
-            }
-            let tips_vec = tips_hashset.values().cloned().collect();
+                None => U256::ZERO,
+            };
 
             Ok((tips_vec, value + U256::from(100)))
         }
@@ -202,3 +197,87 @@ pub fn tips_and_value_for_swap_type(
         _ => Err(eyre!("NOT_IMPLEMENTED")),
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{FundingMode, MockPool, SwapAmountType, SwapLine, SwapPath};
+    use loom_defi_address_book::TokenAddressEth;
+
+    const ONE_ETHER: U256 = U256::from_limbs([1_000_000_000_000_000_000, 0, 0, 0]);
+
+    fn backrun_swap(token: &Arc<Token>, middle: &Arc<Token>, pool: u8, amount_in: U256, amount_out: U256) -> Swap {
+        let pools = vec![
+            MockPool::new(token.get_address(), middle.get_address(), Address::repeat_byte(pool)),
+            MockPool::new(middle.get_address(), token.get_address(), Address::repeat_byte(pool + 1)),
+        ];
+        let mut swap_line = SwapLine::from(SwapPath::new(vec![token.clone(), middle.clone(), token.clone()], pools));
+        swap_line.amount_in = SwapAmountType::Set(amount_in);
+        swap_line.amount_out = SwapAmountType::Set(amount_out);
+        swap_line.funding = Some(FundingMode::Balance);
+        Swap::BackrunSwapLine(swap_line)
+    }
+
+    fn usdc() -> Arc<Token> {
+        let usdc = Token::new_with_data(TokenAddressEth::USDC, Some("USDC".to_string()), None, Some(6), true, false);
+        // 2000 USDC per ETH
+        usdc.set_eth_price(Some(U256::from(2_000_000_000u64)));
+        Arc::new(usdc)
+    }
+
+    #[test]
+    fn test_multiple_tips_from_weth() -> Result<()> {
+        let weth = Arc::new(Token::new(TokenAddressEth::WETH));
+        let usdc = usdc();
+        let middle = Arc::new(Token::new(Address::repeat_byte(0x01)));
+        // 1 ETH profit each
+        let swap = Swap::Multiple(vec![
+            backrun_swap(&weth, &middle, 0x10, ONE_ETHER * U256::from(10), ONE_ETHER * U256::from(11)),
+            backrun_swap(&usdc, &middle, 0x20, U256::from(10_000_000_000u64), U256::from(12_000_000_000u64)),
+        ]);
+        let gas_cost = ONE_ETHER / U256::from(5);
+
+        let (tips_vec, value) = tips_and_value_for_swap_type(&swap, Some(5000), Some(gas_cost), ONE_ETHER)?;
+        assert_eq!(tips_vec.len(), 2);
+        let (weth_tips, usdc_tips) = (&tips_vec[0], &tips_vec[1]);
+        assert!(weth_tips.token_in.is_weth());
+        assert_eq!(usdc_tips.profit, U256::from(2_000_000_000u64));
+        assert_eq!(usdc_tips.profit_eth, ONE_ETHER);
+
+        // the randomized half of the profit above the gas cost is paid once from the WETH profit
+        let max_tips = (ONE_ETHER * U256::from(2) - gas_cost) / U256::from(2);
+        assert!(weth_tips.tips <= max_tips && weth_tips.tips > max_tips * U256::from(99) / U256::from(100));
+        assert_eq!(usdc_tips.tips, U256::ZERO);
+        assert_eq!(value, U256::from(100));
+
+        // both tokens keep half of the gas cost and the tips as their balance change
+        assert_eq!(weth_tips.min_change, (gas_cost + weth_tips.tips) / U256::from(2));
+        assert_eq!(Some(usdc_tips.min_change), usdc.calc_token_value_from_eth(weth_tips.min_change));
+        Ok(())
+    }
+
+    #[test]
+    fn test_multiple_tips_by_value() -> Result<()> {
+        let usdc = usdc();
+        let middle = Arc::new(Token::new(Address::repeat_byte(0x01)));
+        let swap = Swap::Multiple(vec![
+            backrun_swap(&usdc, &middle, 0x10, U256::from(10_000_000_000u64), U256::from(12_000_000_000u64)),
+            backrun_swap(&usdc, &middle, 0x20, U256::from(10_000_000_000u64), U256::from(11_000_000_000u64)),
+        ]);
+
+        // the lines of the same input token are checked once, the tips are paid by the value
+        let (tips_vec, value) = tips_and_value_for_swap_type(&swap, Some(5000), None, ONE_ETHER * U256::from(10))?;
+        assert_eq!(tips_vec.len(), 1);
+        assert_eq!(tips_vec[0].profit, U256::from(3_000_000_000u64));
+        assert_eq!(value, tips_vec[0].tips + U256::from(100));
+
+        // the value is limited by the balance
+        let eth_balance = ONE_ETHER / U256::from(10);
+        let (tips_vec, value) = tips_and_value_for_swap_type(&swap, Some(5000), None, eth_balance)?;
+        assert_eq!(tips_vec[0].tips, eth_balance * U256::from(9) / U256::from(10));
+        assert_eq!(value, tips_vec[0].tips + U256::from(100));
+
+        assert!(tips_and_value_for_swap_type(&swap, Some(5000), Some(ONE_ETHER * U256::from(2)), eth_balance).is_err());
+        Ok(())
+    }
+}
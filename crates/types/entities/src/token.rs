@@ -10,16 +10,24 @@ use alloy_primitives::{I256, U256};
 use loom_types_blockchain::{LoomDataTypes, LoomDataTypesEthereum};
 
 const ONE_ETHER: U256 = Unit::ETHER.wei_const();
+// decimals of the token divided by it give the decimal exponent of the smallest amount a swap hop may take or return
+const DUST_DECIMALS_DIVISOR: u8 = 3;
 
 #[derive(Clone, Debug, Default)]
 pub struct Token<LDT: LoomDataTypes = LoomDataTypesEthereum> {
     address: LDT::Address,
     basic: bool,
     middle: bool,
+    // balances change without transfers (AMPL, stETH), pool reserves have to be synced before a swap
+    rebasing: bool,
+    // transfers call hooks of the sender or receiver (ERC-777, ERC-1363), the received amount may differ from the sent one
+    transfer_hook: bool,
     decimals: u8,
     name: Option<String>,
     symbol: Option<String>,
     eth_price: Arc<RwLock<Option<U256>>>,
+    // token amount worth the USD dust value, set with the prices
+    dust_amount: Arc<RwLock<Option<U256>>>,
 }
 
 pub type TokenWrapper<LDT> = Arc<Token<LDT>>;
@@ -72,7 +80,18 @@ impl<LDT: LoomDataTypes> Token<LDT> {
         basic: bool,
         middle: bool,
     ) -> Token<LDT> {
-        Token { address, symbol, name, decimals: decimals.unwrap_or(18), basic, middle, eth_price: Arc::new(RwLock::new(None)) }
+        Token {
+            address,
+            symbol,
+            name,
+            decimals: decimals.unwrap_or(18),
+            basic,
+            middle,
+            rebasing: false,
+            transfer_hook: false,
+            eth_price: Arc::new(RwLock::new(None)),
+            dust_amount: Arc::new(RwLock::new(None)),
+        }
     }
 
     #[inline]
@@ -114,6 +133,16 @@ impl<LDT: LoomDataTypes> Token<LDT> {
         self.middle
     }
 
+    #[inline]
+    pub fn is_rebasing(&self) -> bool {
+        self.rebasing
+    }
+
+    #[inline]
+    pub fn has_transfer_hook(&self) -> bool {
+        self.transfer_hook
+    }
+
     pub fn set_basic(&mut self) -> &mut Self {
         self.basic = true;
         self
@@ -124,6 +153,16 @@ impl<LDT: LoomDataTypes> Token<LDT> {
         self
     }
 
+    pub fn set_rebasing(&mut self) -> &mut Self {
+        self.rebasing = true;
+        self
+    }
+
+    pub fn set_transfer_hook(&mut self) -> &mut Self {
+        self.transfer_hook = true;
+        self
+    }
+
     pub fn to_float(&self, value: U256) -> f64 {
         if self.decimals == 0 {
             0f64
@@ -188,6 +227,22 @@ impl<LDT: LoomDataTypes> Token<LDT> {
         let x = self.get_eth_price();
         x.map(|x| eth_value.mul(x).div(ONE_ETHER))
     }
+
+    /// Smallest amount a swap hop may take or return, smaller amounts round to zero along a path and revert at the transfer.
+    /// The larger of `10^(decimals / 3)` and the amount worth the USD dust value of the price graph.
+    pub fn get_dust_amount(&self) -> U256 {
+        let decimals_floor = U256::from(10).pow(U256::from(self.decimals / DUST_DECIMALS_DIVISOR));
+        match self.dust_amount.read() {
+            Ok(x) => x.map_or(decimals_floor, |dust_amount| dust_amount.max(decimals_floor)),
+            _ => decimals_floor,
+        }
+    }
+
+    pub fn set_dust_amount(&self, dust_amount: Option<U256>) {
+        if let Ok(mut x) = self.dust_amount.write() {
+            *x = dust_amount;
+        }
+    }
 }
 
 #[cfg(test)]
@@ -212,4 +267,19 @@ mod test {
 
         println!("{}", weth_token.to_float(one_ether));
     }
+
+    #[test]
+    fn test_dust_amount() {
+        let usdc_token = Token::<LoomDataTypesEthereum>::new_with_data(TokenAddressEth::USDC, None, None, Some(6), false, false);
+        let weth_token = Token::<LoomDataTypesEthereum>::new_with_data(TokenAddressEth::WETH, None, None, Some(18), true, false);
+        assert_eq!(usdc_token.get_dust_amount(), U256::from(100));
+        assert_eq!(weth_token.get_dust_amount(), U256::from(1_000_000));
+
+        // 0.01 USD of USDC
+        usdc_token.set_dust_amount(Some(U256::from(10_000)));
+        assert_eq!(usdc_token.get_dust_amount(), U256::from(10_000));
+        // never below the decimals floor
+        usdc_token.set_dust_amount(Some(U256::from(1)));
+        assert_eq!(usdc_token.get_dust_amount(), U256::from(100));
+    }
 }
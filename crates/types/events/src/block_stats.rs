@@ -0,0 +1,44 @@
+use crate::Message;
+use alloy_primitives::{BlockNumber, U256};
+
+/// Opportunity computed for a block that had already been superseded when it was found
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MissedOpportunity {
+    pub swap: String,
+    pub origin: Option<String>,
+    pub profit_eth: U256,
+    /// Latest block number at the time the opportunity was found
+    pub found_at_block: BlockNumber,
+}
+
+/// Searcher coverage of the state of a block, opportunities found on it target the next block
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BlockStats {
+    pub block_number: BlockNumber,
+    pub pools_updated: usize,
+    pub paths_evaluated: usize,
+    pub opportunities: usize,
+    pub best_profit_eth: U256,
+    pub simulation_failures: usize,
+    pub best_missed: Option<MissedOpportunity>,
+}
+
+impl BlockStats {
+    pub fn new(block_number: BlockNumber) -> Self {
+        Self { block_number, ..Default::default() }
+    }
+
+    pub fn add_opportunity(&mut self, profit_eth: U256) {
+        self.opportunities += 1;
+        self.best_profit_eth = self.best_profit_eth.max(profit_eth);
+    }
+
+    /// Keep the missed opportunity if it is more profitable than the best missed one
+    pub fn add_missed(&mut self, missed: MissedOpportunity) {
+        if self.best_missed.as_ref().is_none_or(|best| best.profit_eth < missed.profit_eth) {
+            self.best_missed = Some(missed);
+        }
+    }
+}
+
+pub type MessageBlockStats = Message<BlockStats>;
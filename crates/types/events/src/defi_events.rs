@@ -1,6 +1,6 @@
 use alloy_primitives::BlockNumber;
 use loom_types_blockchain::{LoomDataTypes, LoomDataTypesEthereum};
-use loom_types_entities::PoolId;
+use loom_types_entities::{PoolClass, PoolId};
 
 #[derive(Clone, Debug)]
 pub enum MarketEvents<LDT: LoomDataTypes = LoomDataTypesEthereum> {
@@ -9,6 +9,7 @@ pub enum MarketEvents<LDT: LoomDataTypes = LoomDataTypesEthereum> {
     BlockLogsUpdate { block_number: BlockNumber, block_hash: LDT::BlockHash },
     BlockStateUpdate { block_hash: LDT::BlockHash },
     NewPoolLoaded { pool_id: PoolId<LDT>, swap_path_idx_vec: Vec<usize> },
+    PoolClassDisabled { pool_class: PoolClass },
 }
 
 #[derive(Clone, Debug)]
@@ -27,4 +28,9 @@ pub enum MempoolEvents<LDT: LoomDataTypes = LoomDataTypesEthereum> {
     MempoolLogUpdate {
         tx_hash: LDT::TxHash,
     },
+    /// The transaction replaced an earlier transaction with the same sender and nonce.
+    MempoolTxReplaced {
+        tx_hash: LDT::TxHash,
+        replaced_tx_hash: LDT::TxHash,
+    },
 }
@@ -1,4 +1,5 @@
 use crate::Message;
+use alloy_primitives::BlockNumber;
 use loom_types_blockchain::{LoomDataTypes, LoomDataTypesEthereum};
 use loom_types_entities::{EstimationError, SwapError};
 
@@ -7,6 +8,11 @@ pub enum HealthEvent<LDT: LoomDataTypes = LoomDataTypesEthereum> {
     PoolSwapError(SwapError<LDT>),
     SwapLineEstimationError(EstimationError<LDT>),
     MonitorTx(LDT::TxHash),
+    /// Swap paths calculated by the searcher on a state update targeting the next block
+    PathsEvaluated {
+        next_block_number: BlockNumber,
+        paths: usize,
+    },
 }
 
 pub type MessageHealthEvent<LDT = LoomDataTypesEthereum> = Message<HealthEvent<LDT>>;
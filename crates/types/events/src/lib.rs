@@ -1,18 +1,22 @@
 pub use best_tx_compose::*;
+pub use block_stats::*;
 pub use defi_events::*;
 pub use health_event::*;
 pub use message::Message;
 pub use node::*;
+pub use ops_call::{MessageOpsCall, OpsCall};
 pub use state_update_event::*;
 pub use swap_compose::*;
 pub use tasks::LoomTask;
 pub use tx_compose::*;
 
 mod best_tx_compose;
+mod block_stats;
 mod defi_events;
 mod health_event;
 mod message;
 mod node;
+mod ops_call;
 mod swap_compose;
 
 mod state_update_event;
@@ -27,6 +27,16 @@ pub struct BlockLogs<LDT: LoomDataTypes = LoomDataTypesEthereum> {
     pub logs: Vec<LDT::Log>,
 }
 
+/// Preconfirmed part of the pending block of an OP-stack chain streaming Flashblocks, the sequencer extends the block
+/// every 200ms. `state_update` holds the post states of `txs`
+#[derive(Clone, Debug)]
+pub struct Flashblock<LDT: LoomDataTypes = LoomDataTypesEthereum> {
+    pub block_number: u64,
+    pub index: u64,
+    pub txs: Vec<LDT::Transaction>,
+    pub state_update: GethStateUpdateVec,
+}
+
 #[derive(Clone, Debug, Default)]
 pub struct BlockHeader<LDT: LoomDataTypes = LoomDataTypesEthereum> {
     pub header: LDT::Header,
@@ -40,6 +50,7 @@ pub type MessageBlockHeader<LDT = LoomDataTypesEthereum> = Message<BlockHeader<L
 pub type MessageBlock<LDT = LoomDataTypesEthereum> = Message<BlockUpdate<LDT>>;
 pub type MessageBlockLogs<LDT = LoomDataTypesEthereum> = Message<BlockLogs<LDT>>;
 pub type MessageBlockStateUpdate<LDT = LoomDataTypesEthereum> = Message<BlockStateUpdate<LDT>>;
+pub type MessageFlashblock<LDT = LoomDataTypesEthereum> = Message<Flashblock<LDT>>;
 
 impl BlockHeader<LoomDataTypesEthereum> {
     pub fn new(header: Header) -> Self {
@@ -0,0 +1,22 @@
+use alloy_primitives::{Address, Bytes, U256};
+
+use crate::Message;
+
+/// Low urgency operational call, e.g. a sweep, an approval or a config transaction. Sent from a smart account through an
+/// ERC-4337 bundler, off the nonces of the signers of the swaps
+#[derive(Clone, Debug, Default)]
+pub struct OpsCall {
+    pub to: Address,
+    pub value: U256,
+    pub data: Bytes,
+    /// Purpose of the call for the logs
+    pub label: String,
+}
+
+impl OpsCall {
+    pub fn new(to: Address, value: U256, data: Bytes, label: impl Into<String>) -> Self {
+        Self { to, value, data, label: label.into() }
+    }
+}
+
+pub type MessageOpsCall = Message<OpsCall>;
@@ -74,6 +74,11 @@ impl<DB: Clone + 'static, LDT: LoomDataTypes> SwapComposeData<DB, LDT> {
         }
     }
 
+    /// Swaps merged into one are paid out to a single profit receiver and counted against the limits of a single namespace
+    pub fn same_owner(&self, other: &Self) -> bool {
+        self.tx_compose.profit_receiver == other.tx_compose.profit_receiver && self.tx_compose.namespace == other.tx_compose.namespace
+    }
+
     pub fn cross_pools(&self, others_pools: &[PoolId<LDT>]) -> bool {
         self.swap.get_pool_id_vec().iter().any(|x| others_pools.contains(x))
     }
@@ -4,4 +4,10 @@ use loom_types_entities::{PoolClass, PoolId};
 #[derive(Clone, Debug)]
 pub enum LoomTask<LDT: LoomDataTypes = LoomDataTypesEthereum> {
     FetchAndAddPools(Vec<(PoolId<LDT>, PoolClass)>),
+    /// Re-fetch the required state of a loaded pool
+    RefreshPoolState(PoolId<LDT>),
+    /// Build the swap paths missing for the pools of the token, e.g. after the token was reclassified
+    RebuildPathsForToken(LDT::Address),
+    /// Re-apply the pools loading config to the market and retry pools whose loads failed
+    ReloadConfig,
 }
@@ -48,7 +48,13 @@ pub struct TxComposeData<LDT: LoomDataTypes = LoomDataTypesEthereum> {
     pub next_block_base_fee: u64,
     pub tx_bundle: Option<Vec<TxState<LDT>>>,
     pub rlp_bundle: Option<Vec<RlpState>>,
+    /// Backrun transaction for the public mempool reverting on loss, sent if the bundle misses its target block
+    pub public_fallback: Option<TxState<LDT>>,
     pub origin: Option<String>,
+    /// Strategy namespace limiting the signers and capital of the swap
+    pub namespace: Option<String>,
+    /// Receiver of the swap profit net of the gas and tips, the profit stays in the multicaller if not set
+    pub profit_receiver: Option<LDT::Address>,
     pub swap: Option<Swap>,
     pub tips: Option<U256>,
 }
@@ -70,7 +76,10 @@ impl<LDT: LoomDataTypes> Default for TxComposeData<LDT> {
             next_block_timestamp: Default::default(),
             tx_bundle: None,
             rlp_bundle: None,
+            public_fallback: None,
             origin: None,
+            namespace: None,
+            profit_receiver: None,
             swap: None,
             tips: None,
         }